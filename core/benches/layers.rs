@@ -0,0 +1,129 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Measures the hot paths of the layer stack, the traversal of a stack with hundreds of layers
+//! and querying systems from the top of it.
+//!
+//! Run with `cargo bench --bench layers`, a stack which does not change
+//! between frames must not allocate, which is checked before measuring.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use pluto_engine::application::layer::pluto::PlutoLayerManager;
+use pluto_engine::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerManager, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use pluto_engine::application::system::System;
+use pluto_engine::application::time::Time;
+use pluto_engine::memory::{allocation_count, CountingAllocator};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator(std::alloc::System);
+
+struct Counter(u64);
+
+impl System for Counter {}
+
+/// Owns a system and queries the systems of the manager and of the layers below.
+struct BenchLayer {
+    provides_counter: bool,
+    /// The number of queries made per frame, on top of the regular ones.
+    queries: u32,
+}
+
+impl Layer for BenchLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        if self.provides_counter {
+            dependencies.provide(Counter(0));
+        }
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        black_box(systems.query::<Time>().map(Time::delta));
+        if let Some(counter) = systems.query_mut::<Counter>() {
+            counter.0 += 1;
+        }
+
+        for _ in 0..self.queries {
+            black_box(systems.query::<Time>());
+        }
+
+        next.next(systems);
+    }
+}
+
+/// Creates a stack of `layers` layers, every tenth providing a system.
+///
+/// *The top-most layer makes `queries` additional queries per frame.*
+fn create_stack(layers: u32, queries: u32) -> PlutoLayerManager {
+    let mut manager = PlutoLayerManager::new();
+    for layer in 0..layers {
+        manager.add_layer(Box::new(BenchLayer {
+            provides_counter: layer % 10 == 0,
+            queries: if layer + 1 == layers { queries } else { 0 },
+        }));
+    }
+
+    // Attaches the layers, so only steady frames are measured
+    manager.run();
+    let allocations = allocation_count();
+    manager.run();
+    assert_eq!(
+        allocation_count() - allocations,
+        0,
+        "a frame of an unchanged stack allocated"
+    );
+
+    manager
+}
+
+fn layer_traversal(c: &mut Criterion) {
+    let mut group = c.benchmark_group("layer_traversal");
+    for layers in [10, 100, 500] {
+        let mut manager = create_stack(layers, 0);
+        group.bench_function(BenchmarkId::from_parameter(layers), |b| {
+            b.iter(|| black_box(manager.run()))
+        });
+        manager.shutdown();
+    }
+    group.finish();
+}
+
+/// The queried system is provided by the manager, below every layer.
+fn system_query(c: &mut Criterion) {
+    let mut group = c.benchmark_group("system_query_1000");
+    for layers in [1, 100] {
+        let mut manager = create_stack(layers, 1000);
+        group.bench_function(BenchmarkId::from_parameter(layers), |b| {
+            b.iter(|| black_box(manager.run()))
+        });
+        manager.shutdown();
+    }
+    group.finish();
+}
+
+criterion_group!(benches, layer_traversal, system_query);
+criterion_main!(benches);
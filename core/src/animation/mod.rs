@@ -0,0 +1,287 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Tweens and keyframed timelines interpolating values over time, advanced by the [`Time`].
+
+use crate::application::time::Time;
+use crate::color::{Color, RGBA};
+use crate::math::{Point2, Point3, Quat, Vec2, Vec3, Vec4};
+use std::time::Duration;
+
+pub mod easing;
+pub mod timeline;
+
+pub use easing::Easing;
+
+/// A value that can be interpolated by tweens and timelines.
+pub trait Tweenable: Copy {
+    /// *Returns `self` for a ratio of `0`, `other` for a ratio of `1`
+    /// and a mix of both in between, easings may extrapolate beyond both.*
+    fn tween(self, other: Self, ratio: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn tween(self, other: Self, ratio: f32) -> Self {
+        self + (other - self) * ratio
+    }
+}
+
+impl Tweenable for Vec2 {
+    fn tween(self, other: Self, ratio: f32) -> Self {
+        self + (other - self) * ratio
+    }
+}
+
+impl Tweenable for Vec3 {
+    fn tween(self, other: Self, ratio: f32) -> Self {
+        self + (other - self) * ratio
+    }
+}
+
+impl Tweenable for Vec4 {
+    fn tween(self, other: Self, ratio: f32) -> Self {
+        self + (other - self) * ratio
+    }
+}
+
+impl Tweenable for Point2 {
+    fn tween(self, other: Self, ratio: f32) -> Self {
+        self + (other - self) * ratio
+    }
+}
+
+impl Tweenable for Point3 {
+    fn tween(self, other: Self, ratio: f32) -> Self {
+        self + (other - self) * ratio
+    }
+}
+
+/// Rotates at a constant angular speed along the shortest path.
+impl Tweenable for Quat {
+    fn tween(self, other: Self, ratio: f32) -> Self {
+        self.slerp(other, ratio)
+    }
+}
+
+/// Interpolates with [`Color::lerp`], in sRGB space.
+impl Tweenable for RGBA {
+    fn tween(self, other: Self, ratio: f32) -> Self {
+        self.lerp(other, ratio)
+    }
+}
+
+/// What a tween or timeline does once its duration has passed.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum TweenMode {
+    /// Stops at the end.
+    #[default]
+    Once,
+    /// Restarts from the beginning.
+    Loop,
+    /// Plays backwards to the beginning, then forwards again.
+    PingPong,
+}
+
+impl TweenMode {
+    /// Wraps the elapsed time of a repeating animation into its period.
+    ///
+    /// *Returns the wrapped elapsed time and whether the animation is playing backwards.*
+    pub(crate) fn wrap(self, elapsed: Duration, duration: Duration) -> (Duration, bool) {
+        if duration.is_zero() {
+            return (duration, false);
+        }
+
+        let wrap = |period: Duration| {
+            Duration::from_nanos((elapsed.as_nanos() % period.as_nanos()) as u64)
+        };
+
+        match self {
+            TweenMode::Once => (elapsed.min(duration), false),
+            TweenMode::Loop => (wrap(duration), false),
+            TweenMode::PingPong => {
+                let elapsed = wrap(duration * 2);
+
+                if elapsed > duration {
+                    (duration * 2 - elapsed, true)
+                } else {
+                    (elapsed, false)
+                }
+            }
+        }
+    }
+}
+
+/// Interpolates a value from a start to an end over a duration,
+/// e.g. to fade or slide UI elements in and out.
+#[derive(Clone, Debug)]
+pub struct Tween<T: Tweenable> {
+    from: T,
+    to: T,
+    duration: Duration,
+    easing: Easing,
+    mode: TweenMode,
+    elapsed: Duration,
+    /// Whether a ping-pong tween is playing backwards.
+    reversed: bool,
+    paused: bool,
+}
+
+impl<T: Tweenable> Tween<T> {
+    /// Creates a linear tween, playing once.
+    pub fn new(from: T, to: T, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing: Easing::Linear,
+            mode: TweenMode::Once,
+            elapsed: Duration::ZERO,
+            reversed: false,
+            paused: false,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: TweenMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Advances the tween by the delta of the current iteration.
+    ///
+    /// *Returns the value after advancing.*
+    pub fn update(&mut self, time: &Time) -> T {
+        self.tick(time.delta())
+    }
+
+    /// Advances the tween by the given delta.
+    ///
+    /// *Returns the value after advancing.*
+    pub fn tick(&mut self, delta: Duration) -> T {
+        if !self.paused {
+            // Keeps the forward time of a ping-pong tween, so the wrapping continues in order
+            let forward = if self.reversed {
+                self.duration * 2 - self.elapsed
+            } else {
+                self.elapsed
+            };
+
+            (self.elapsed, self.reversed) = self.mode.wrap(forward + delta, self.duration);
+        }
+
+        self.value()
+    }
+
+    /// *Returns the linear progress from `0.0` to `1.0`, before easing.*
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+
+        self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+    }
+
+    pub fn value(&self) -> T {
+        self.from.tween(self.to, self.easing.apply(self.progress()))
+    }
+
+    /// *Returns `true` if a tween playing once has reached its end, repeating tweens never finish.*
+    pub fn is_finished(&self) -> bool {
+        self.mode == TweenMode::Once && self.elapsed >= self.duration
+    }
+
+    /// Restarts the tween from its start value.
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.reversed = false;
+    }
+
+    /// Starts a new tween from the current value to the given end value, keeping the duration,
+    /// easing and mode, e.g. when the target of a transition changes midway.
+    pub fn retarget(&mut self, to: T) {
+        self.from = self.value();
+        self.to = to;
+        self.reset();
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::animation::{Easing, Tween, TweenMode};
+    use crate::color::{Color, BLACK, WHITE};
+    use std::time::Duration;
+
+    /// A tween is played once, looped and ping-ponged past its duration.
+    /// A tween played once should stop at its end, a looping one should wrap around
+    /// and a ping-pong one should play backwards.
+    #[test]
+    fn test_tween_modes() {
+        let second = Duration::from_secs(1);
+        let tween = Tween::new(0.0, 10.0, second * 2);
+
+        let mut once = tween.clone();
+        assert_eq!(once.tick(second), 5.0);
+        assert_eq!(once.tick(second * 2), 10.0);
+        assert!(once.is_finished());
+
+        let mut looping = tween.clone().with_mode(TweenMode::Loop);
+        assert_eq!(looping.tick(second * 5), 5.0);
+        assert!(!looping.is_finished());
+
+        let mut ping_pong = tween.with_mode(TweenMode::PingPong);
+        assert_eq!(ping_pong.tick(second * 3), 5.0);
+        assert_eq!(ping_pong.tick(second / 2), 2.5);
+        assert_eq!(ping_pong.tick(second * 2), 7.5);
+    }
+
+    /// A color tween with an easing is retargeted halfway.
+    /// The tween should restart from the eased color it had reached.
+    #[test]
+    fn test_tween_retarget() {
+        let second = Duration::from_secs(1);
+        let mut tween = Tween::new(BLACK, WHITE, second * 2).with_easing(Easing::QuadIn);
+
+        let halfway = tween.tick(second);
+        assert_eq!(halfway, BLACK.lerp(WHITE, 0.25));
+
+        tween.retarget(BLACK);
+        assert_eq!(tween.value(), halfway);
+        assert_eq!(tween.tick(second * 2), BLACK);
+    }
+}
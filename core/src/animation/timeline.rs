@@ -0,0 +1,244 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::animation::{Easing, TweenMode, Tweenable};
+use crate::application::time::Time;
+use std::time::Duration;
+
+/// A value a track reaches at a point in time.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Keyframe<T: Tweenable> {
+    pub time: Duration,
+    pub value: T,
+    /// The easing of the interpolation from the previous keyframe to this one.
+    pub easing: Easing,
+}
+
+/// Keyframes of a single animated value, sorted by their time.
+///
+/// The value is held before the first and after the last keyframe.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<T: Tweenable> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Tweenable> Track<T> {
+    /// Creates a track starting at the given value.
+    pub fn new(value: T) -> Self {
+        Self {
+            keyframes: vec![Keyframe {
+                time: Duration::ZERO,
+                value,
+                easing: Easing::Linear,
+            }],
+        }
+    }
+
+    /// Adds a keyframe, replacing a keyframe at the same time.
+    pub fn key(mut self, time: Duration, value: T, easing: Easing) -> Self {
+        let keyframe = Keyframe {
+            time,
+            value,
+            easing,
+        };
+
+        match self
+            .keyframes
+            .binary_search_by_key(&time, |keyframe| keyframe.time)
+        {
+            Ok(index) => self.keyframes[index] = keyframe,
+            Err(index) => self.keyframes.insert(index, keyframe),
+        }
+
+        self
+    }
+
+    pub fn get_keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    /// *Returns the time of the last keyframe.*
+    pub fn duration(&self) -> Duration {
+        self.keyframes
+            .last()
+            .map_or(Duration::ZERO, |keyframe| keyframe.time)
+    }
+
+    /// *Returns the value at the given time, interpolated between the surrounding keyframes.*
+    pub fn sample(&self, time: Duration) -> T {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+
+        if next == 0 {
+            return self.keyframes[0].value;
+        }
+
+        let previous = &self.keyframes[next - 1];
+        let Some(next) = self.keyframes.get(next) else {
+            return previous.value;
+        };
+
+        let ratio =
+            (time - previous.time).as_secs_f32() / (next.time - previous.time).as_secs_f32();
+
+        previous.value.tween(next.value, next.easing.apply(ratio))
+    }
+}
+
+/// A playhead sampling any number of tracks, which can animate values of different types.
+///
+/// Layers keep the timeline next to its tracks, advance it every iteration
+/// and sample each track to apply the animated values.
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    duration: Duration,
+    mode: TweenMode,
+    elapsed: Duration,
+    /// The time of the playhead, moving backwards while a ping-pong timeline is reversed.
+    playhead: Duration,
+    speed: f32,
+    paused: bool,
+}
+
+impl Timeline {
+    /// Creates a timeline playing once at normal speed.
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            mode: TweenMode::Once,
+            elapsed: Duration::ZERO,
+            playhead: Duration::ZERO,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: TweenMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Scales the deltas the timeline is advanced by, `2.0` plays twice as fast.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.0);
+    }
+
+    /// Advances the timeline by the delta of the current iteration.
+    pub fn update(&mut self, time: &Time) {
+        self.tick(time.delta());
+    }
+
+    /// Advances the timeline by the given delta.
+    pub fn tick(&mut self, delta: Duration) {
+        if self.paused {
+            return;
+        }
+
+        self.elapsed += delta.mul_f32(self.speed);
+
+        // Only the wrapped time is kept, a repeating timeline would overflow eventually
+        let (playhead, reversed) = self.mode.wrap(self.elapsed, self.duration);
+        self.elapsed = match self.mode {
+            TweenMode::Once => self.elapsed.min(self.duration),
+            _ if reversed => self.duration * 2 - playhead,
+            _ => playhead,
+        };
+        self.playhead = playhead;
+    }
+
+    /// *Returns the value of the track at the playhead.*
+    pub fn sample<T: Tweenable>(&self, track: &Track<T>) -> T {
+        track.sample(self.playhead)
+    }
+
+    pub fn get_playhead(&self) -> Duration {
+        self.playhead
+    }
+
+    /// Moves the playhead, clamped to the duration.
+    pub fn seek(&mut self, time: Duration) {
+        self.elapsed = time.min(self.duration);
+        self.playhead = self.elapsed;
+    }
+
+    /// *Returns `true` if a timeline playing once has reached its end,
+    /// repeating timelines never finish.*
+    pub fn is_finished(&self) -> bool {
+        self.mode == TweenMode::Once && self.playhead >= self.duration
+    }
+
+    pub fn reset(&mut self) {
+        self.seek(Duration::ZERO);
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::animation::timeline::{Timeline, Track};
+    use crate::animation::{Easing, TweenMode};
+    use crate::math::Vec2;
+    use std::time::Duration;
+
+    /// A looping timeline samples a float track and a vector track with keyframes added out of order.
+    /// Both tracks should be interpolated between their surrounding keyframes with the easing of
+    /// the later one, and hold their last value until the timeline wraps around.
+    #[test]
+    fn test_timeline_tracks() {
+        let millis = Duration::from_millis;
+
+        let opacity = Track::new(0.0).key(millis(1000), 0.0, Easing::Linear).key(
+            millis(500),
+            1.0,
+            Easing::QuadIn,
+        );
+        let offset =
+            Track::new(Vec2::new(0.0, 0.0)).key(millis(2000), Vec2::new(4.0, 2.0), Easing::Linear);
+
+        let mut timeline = Timeline::new(offset.duration()).with_mode(TweenMode::Loop);
+
+        timeline.tick(millis(250));
+        assert_eq!(timeline.sample(&opacity), 0.25);
+        assert_eq!(timeline.sample(&offset), Vec2::new(0.5, 0.25));
+
+        timeline.tick(millis(1250));
+        assert_eq!(timeline.sample(&opacity), 0.0);
+        assert_eq!(timeline.sample(&offset), Vec2::new(3.0, 1.5));
+
+        timeline.tick(millis(1000));
+        assert_eq!(timeline.get_playhead(), millis(500));
+        assert_eq!(timeline.sample(&opacity), 1.0);
+    }
+}
@@ -0,0 +1,135 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::application::system::System;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+trait EventQueueDyn: Any {
+    fn swap(&mut self);
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct EventQueue<T> {
+    /// Events published during the previous iteration, readable during this one.
+    current: Vec<T>,
+    /// Events published during this iteration.
+    pending: Vec<T>,
+}
+
+impl<T: 'static> EventQueueDyn for EventQueue<T> {
+    fn swap(&mut self) {
+        self.current.clear();
+        std::mem::swap(&mut self.current, &mut self.pending);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Typed queues of events broadcast between layers.
+///
+/// Events published during an iteration of the layer manager can be read by all layers
+/// during the next iteration, regardless of their order, and are dropped afterwards.
+#[derive(Default)]
+pub struct EventBus {
+    queues: HashMap<TypeId, Box<dyn EventQueueDyn>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn publish<T: 'static>(&mut self, event: T) {
+        self.queues
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(EventQueue::<T> {
+                    current: Vec::new(),
+                    pending: Vec::new(),
+                })
+            })
+            .as_any_mut()
+            .downcast_mut::<EventQueue<T>>()
+            .unwrap()
+            .pending
+            .push(event);
+    }
+
+    /// *Returns the events of type `T` published during the previous iteration.*
+    pub fn read<T: 'static>(&self) -> &[T] {
+        self.queues
+            .get(&TypeId::of::<T>())
+            .and_then(|queue| queue.as_any().downcast_ref::<EventQueue<T>>())
+            .map_or(&[], |queue| &queue.current)
+    }
+
+    /// Drops the events read during the last iteration and makes the newly published ones readable.
+    ///
+    /// *Called by the layer manager once per iteration.*
+    pub fn update(&mut self) {
+        for queue in self.queues.values_mut() {
+            queue.swap();
+        }
+    }
+}
+
+impl System for EventBus {}
+
+#[cfg(test)]
+mod test {
+    use crate::application::event::EventBus;
+
+    #[derive(Debug, PartialEq)]
+    struct PlayerDied(u32);
+
+    /// Two events are published and the bus is updated twice.
+    /// The events should only be readable after the first update, in publishing order.
+    #[test]
+    fn test_event_lifetime() {
+        let mut events = EventBus::new();
+        events.publish(PlayerDied(1));
+        events.publish(PlayerDied(2));
+        events.publish("unrelated");
+
+        assert!(events.read::<PlayerDied>().is_empty());
+
+        events.update();
+        assert_eq!(events.read::<PlayerDied>(), [PlayerDied(1), PlayerDied(2)]);
+        assert_eq!(events.read::<&str>(), ["unrelated"]);
+
+        events.update();
+        assert!(events.read::<PlayerDied>().is_empty());
+        assert!(events.read::<u64>().is_empty());
+    }
+}
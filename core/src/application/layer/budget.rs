@@ -0,0 +1,175 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::fmt::{Display, Formatter};
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+/// A phase of a single layer traversal.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum TraversalPhase {
+    /// The upwards traversal, see [`Layer::on_enter`](super::Layer::on_enter).
+    Enter,
+    /// The downwards traversal, see [`Layer::on_leave`](super::Layer::on_leave).
+    Leave,
+}
+
+/// A policy applied to a layer that exceeded its budget.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum BudgetPolicy {
+    /// The violation is logged as a warning and no further action is taken.
+    ///
+    /// *This is the default policy.*
+    #[default]
+    Log,
+    /// The layer is only entered every Nth frame from now on.
+    ///
+    /// *Systems provided by a throttled layer remain available to the layers above it.*
+    Throttle(NonZeroU32),
+    /// The layer is synchronously detached at the end of the frame.
+    Detach,
+}
+
+/// Limits on the resources a single layer may consume per traversal phase.
+///
+/// The cost of a phase excludes the cost of the layers above it,
+/// so a layer is never charged for the work done by the layers it calls `next` on.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LayerBudget {
+    /// The maximum time spent in a single traversal phase.
+    pub max_time: Option<Duration>,
+    /// The maximum number of heap allocations in a single traversal phase.
+    ///
+    /// *Allocations are only counted if the
+    /// [`CountingAllocator`](crate::memory::CountingAllocator) is the global allocator.*
+    pub max_allocations: Option<usize>,
+    /// The policy applied when the budget is exceeded.
+    pub policy: BudgetPolicy,
+}
+
+impl LayerBudget {
+    pub fn new(policy: BudgetPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    pub fn max_time(mut self, max_time: Duration) -> Self {
+        self.max_time = Some(max_time);
+        self
+    }
+
+    pub fn max_allocations(mut self, max_allocations: usize) -> Self {
+        self.max_allocations = Some(max_allocations);
+        self
+    }
+
+    /// Checks the measured cost of a phase against this budget.
+    ///
+    /// *Returns `None` if the cost is within the budget.*
+    pub fn check(&self, cost: &LayerCost) -> Option<BudgetExceeded> {
+        if let Some(limit) = self.max_time {
+            if cost.time > limit {
+                return Some(BudgetExceeded::Time {
+                    spent: cost.time,
+                    limit,
+                });
+            }
+        }
+
+        if let Some(limit) = self.max_allocations {
+            if cost.allocations > limit {
+                return Some(BudgetExceeded::Allocations {
+                    spent: cost.allocations,
+                    limit,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+/// The measured cost of a single traversal phase of a layer.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct LayerCost {
+    pub time: Duration,
+    pub allocations: usize,
+}
+
+impl std::ops::Add for LayerCost {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            time: self.time + rhs.time,
+            allocations: self.allocations + rhs.allocations,
+        }
+    }
+}
+
+impl std::ops::Sub for LayerCost {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            time: self.time.saturating_sub(rhs.time),
+            allocations: self.allocations.saturating_sub(rhs.allocations),
+        }
+    }
+}
+
+/// The resource by which a budget was exceeded.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BudgetExceeded {
+    Time { spent: Duration, limit: Duration },
+    Allocations { spent: usize, limit: usize },
+}
+
+/// A report of a layer exceeding its budget, passed to the budget hook of the layer manager.
+#[derive(Copy, Clone, Debug)]
+pub struct BudgetViolation {
+    pub layer_name: &'static str,
+    pub phase: TraversalPhase,
+    pub exceeded: BudgetExceeded,
+    pub policy: BudgetPolicy,
+}
+
+impl Display for BudgetViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.exceeded {
+            BudgetExceeded::Time { spent, limit } => write!(
+                f,
+                "Layer {} exceeded its time budget in {:?}: {:?} > {:?}",
+                self.layer_name, self.phase, spent, limit
+            ),
+            BudgetExceeded::Allocations { spent, limit } => write!(
+                f,
+                "Layer {} exceeded its allocation budget in {:?}: {} > {}",
+                self.layer_name, self.phase, spent, limit
+            ),
+        }
+    }
+}
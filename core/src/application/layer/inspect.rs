@@ -0,0 +1,65 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::application::layer::budget::LayerCost;
+use crate::application::layer::LayerId;
+use std::num::NonZeroU32;
+
+/// The attach state of a layer, see [`LayerSnapshot`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum LayerState {
+    /// The layer is being polled to be attached and is not entered yet.
+    Attaching,
+    /// The layer is part of the layer stack and entered every frame.
+    Attached,
+    /// The layer was removed from the layer stack and is being polled to be detached.
+    Detaching,
+}
+
+/// The measured cost of a layer in the last frame it was entered in.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct LayerFrameCost {
+    /// The index of the frame the cost was measured in, see [`LayerSnapshot::last_frame`].
+    pub frame: u64,
+    /// The cost of [`Layer::on_enter`](super::Layer::on_enter),
+    /// excluding the layers above it.
+    pub enter: LayerCost,
+    /// The cost of [`Layer::on_leave`](super::Layer::on_leave).
+    pub leave: LayerCost,
+}
+
+/// A read-only snapshot of a single layer, for debug UIs and other tooling.
+#[derive(Copy, Clone, Debug)]
+pub struct LayerSnapshot {
+    /// The id of the layer, `None` unless the layer is attached.
+    pub id: Option<LayerId>,
+    /// The type name of the layer.
+    pub name: &'static str,
+    pub state: LayerState,
+    /// Set when the layer is only entered every Nth frame for exceeding its budget.
+    pub throttle: Option<NonZeroU32>,
+    /// The cost of the layer in the last frame it was entered in,
+    /// `None` if it was not entered yet.
+    pub last_frame: Option<LayerFrameCost>,
+}
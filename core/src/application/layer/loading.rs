@@ -0,0 +1,104 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::application::system::System;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// A shared fraction of completed work, reported by an asynchronous initialization
+/// and returned by [`Layer::attach_progress`](super::Layer::attach_progress).
+#[derive(Clone, Debug, Default)]
+pub struct LoadProgress(Arc<AtomicU32>);
+
+impl LoadProgress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the completed fraction, clamped between `0` and `1`.
+    pub fn set(&self, fraction: f32) {
+        self.0
+            .store(fraction.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Sets the completed fraction to `completed` out of `total` steps.
+    pub fn set_steps(&self, completed: usize, total: usize) {
+        if total == 0 {
+            self.set(1.0);
+        } else {
+            self.set(completed as f32 / total as f32);
+        }
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// The progress of a single layer being attached deferred.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoadingLayerStatus {
+    /// The type name of the layer.
+    pub name: &'static str,
+    /// See [`Layer::attach_progress`](super::Layer::attach_progress).
+    pub progress: Option<f32>,
+}
+
+/// A system provided to all layers, exposing the layers being attached deferred
+/// as of the end of the last frame.
+#[derive(Default)]
+pub struct LoadingStatus {
+    pub(super) layers: Vec<LoadingLayerStatus>,
+}
+
+impl LoadingStatus {
+    /// *Returns the layers being attached, in the order they were added.*
+    pub fn latest(&self) -> &[LoadingLayerStatus] {
+        &self.layers
+    }
+
+    pub fn is_loading(&self) -> bool {
+        !self.layers.is_empty()
+    }
+
+    /// *Returns the mean progress of all layers being attached,
+    /// `1` if no layers are being attached.*
+    ///
+    /// Layers not reporting their progress count as not started until they are attached.
+    pub fn progress(&self) -> f32 {
+        if self.layers.is_empty() {
+            return 1.0;
+        }
+
+        let total = self
+            .layers
+            .iter()
+            .map(|layer| layer.progress.unwrap_or(0.0))
+            .sum::<f32>();
+
+        total / self.layers.len() as f32
+    }
+}
+
+impl System for LoadingStatus {}
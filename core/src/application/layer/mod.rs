@@ -22,10 +22,21 @@
  * SOFTWARE.
  */
 
+use crate::application::event::EventBus;
+use crate::application::layer::inspect::LayerSnapshot;
 use crate::application::system::System;
+use crate::application::time::Time;
+use pluto_engine_display::pluto_engine_window::executor::LocalFuture;
 use std::any::{Any, TypeId};
 
+pub mod budget;
+pub mod inspect;
+pub mod loading;
 pub mod pluto;
+pub mod timing;
+
+/// The id of an attached layer, unique for the lifetime of its layer manager.
+pub type LayerId = u64;
 
 /// An object used to declare dependencies between layers.
 pub struct LayerDependencyDeclaration<'a>(&'a mut dyn LayerDependencyManager);
@@ -113,6 +124,14 @@ impl LayerDependencyDeclaration<'_> {
         let layer = supplier();
         self.0.add_layer(layer).as_any_mut().downcast_mut().unwrap()
     }
+
+    /// Provides a system owned by the layer manager on behalf of the declaring layer.
+    ///
+    /// The system is available to the declaring layer and all layers above it
+    /// during traversal. *Providing a system of the same type twice replaces the first one.*
+    pub fn provide<T: System>(&mut self, system: T) {
+        self.0.provide_system(TypeId::of::<T>(), Box::new(system));
+    }
 }
 
 /// A proxy trait for [`Layer`] that allows layers to declare dependencies on other layers.
@@ -125,21 +144,34 @@ pub trait LayerDependencyManager {
 
     /// Adds a layer to the list of layers to be attached.
     fn add_layer(&mut self, layer: Box<dyn Layer>) -> &mut dyn Layer;
+
+    /// Registers a system provided by the layer being attached.
+    fn provide_system(&mut self, id: SystemId, system: Box<dyn System>);
 }
 
 /// A trait for querying the layer manager for available systems provided by other layers.
 ///
 /// *Don't downcast this to the manager unless you want to be added to the naughty list. >:(*
 pub trait LayerSystemProvider {
+    /// Returns a reference to the system with the given id, if it exists.
+    fn query_dyn(&self, id: SystemId) -> Option<&dyn System>;
+
+    /// Returns a mutable reference to the system with the given id, if it exists.
+    fn query_dyn_mut(&mut self, id: SystemId) -> Option<&mut dyn System>;
+}
+
+impl dyn LayerSystemProvider + '_ {
     /// Returns a reference to the system of the given type, if it exists.
-    fn query<T: System>(&self) -> Option<&T>
-    where
-        Self: Sized;
+    pub fn query<T: System>(&self) -> Option<&T> {
+        self.query_dyn(TypeId::of::<T>())
+            .and_then(|system| system.as_any().downcast_ref())
+    }
 
     /// Returns a mutable reference to the system of the given type, if it exists.
-    fn query_mut<T: System>(&mut self) -> Option<&mut T>
-    where
-        Self: Sized;
+    pub fn query_mut<T: System>(&mut self) -> Option<&mut T> {
+        self.query_dyn_mut(TypeId::of::<T>())
+            .and_then(|system| system.as_any_mut().downcast_mut())
+    }
 }
 
 /// A trait for layers to provide the layers above this one with additional systems.
@@ -147,11 +179,125 @@ pub trait LayerSystemProvider {
 /// This method is only available when traversing the stack upwards, any systems provided
 /// are automatically popped when the layer is traversed downwards.
 pub trait LayerSystemManager<'a>: LayerSystemProvider + AsProvider {
-    fn provide_system<T: System>(&mut self, system: &'a mut Box<T>)
-    where
-        Self: Sized;
+    /// Provides a system with the given id to the layers above the current one.
+    fn provide_system_dyn(&mut self, id: SystemId, system: &'a mut dyn System);
+}
+
+impl<'a> dyn LayerSystemManager<'a> + '_ {
+    /// Returns a reference to the system of the given type, if it exists.
+    pub fn query<T: System>(&self) -> Option<&T> {
+        self.as_provider().query()
+    }
+
+    /// Returns a mutable reference to the system of the given type, if it exists.
+    pub fn query_mut<T: System>(&mut self) -> Option<&mut T> {
+        self.as_provider_mut().query_mut()
+    }
+
+    /// Provides a system to the layers above the current one.
+    pub fn provide_system<T: System>(&mut self, system: &'a mut T) {
+        self.provide_system_dyn(TypeId::of::<T>(), system);
+    }
+
+    /// Returns the commands changing the layer stack once the traversal is complete.
+    ///
+    /// ***Panics** if the layer manager does not provide [`LayerCommands`].*
+    pub fn commands(&mut self) -> &mut LayerCommands {
+        self.query_mut()
+            .expect("The layer manager does not provide layer commands")
+    }
+
+    /// Returns the events published between layers.
+    ///
+    /// ***Panics** if the layer manager does not provide an [`EventBus`].*
+    pub fn events(&mut self) -> &mut EventBus {
+        self.query_mut()
+            .expect("The layer manager does not provide an event bus")
+    }
+
+    /// Returns the time of the current iteration.
+    ///
+    /// ***Panics** if the layer manager does not provide [`Time`].*
+    pub fn time(&self) -> &Time {
+        self.query()
+            .expect("The layer manager does not provide time")
+    }
+}
+
+/// A request to change the layer stack, see [`LayerCommands`].
+pub enum LayerCommand {
+    /// Attaches a layer with the given priority.
+    Attach {
+        layer: Box<dyn Layer>,
+        priority: i32,
+        swap_type: LayerSwapType,
+    },
+    /// Detaches all layers of the given type.
+    Detach(TypeId),
 }
 
+/// Requests to attach or detach layers during traversal.
+///
+/// Available to all layers as a system, the commands are applied in order
+/// by the layer manager once the traversal is complete.
+/// *Attached layers are therefore first entered during the next traversal.*
+#[derive(Default)]
+pub struct LayerCommands {
+    commands: Vec<LayerCommand>,
+}
+
+impl LayerCommands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests to attach a layer with the default priority of `0`.
+    pub fn attach(&mut self, layer: Box<dyn Layer>) {
+        self.attach_with_priority(layer, 0);
+    }
+
+    pub fn attach_with_priority(&mut self, layer: Box<dyn Layer>, priority: i32) {
+        self.commands.push(LayerCommand::Attach {
+            layer,
+            priority,
+            swap_type: LayerSwapType::Synchronous,
+        });
+    }
+
+    /// Requests to attach a layer with the default priority of `0`,
+    /// entering it once its asynchronous initialization completes.
+    ///
+    /// *See [`Layer::attach_async`].*
+    pub fn attach_deferred(&mut self, layer: Box<dyn Layer>) {
+        self.commands.push(LayerCommand::Attach {
+            layer,
+            priority: 0,
+            swap_type: LayerSwapType::Deferred,
+        });
+    }
+
+    /// Requests to detach all layers of type `T`.
+    pub fn detach<T: Layer>(&mut self) {
+        self.commands.push(LayerCommand::Detach(TypeId::of::<T>()));
+    }
+
+    /// Requests an arbitrary command, e.g. to detach layers by their type id.
+    pub fn push(&mut self, command: LayerCommand) {
+        self.commands.push(command);
+    }
+
+    /// Removes all commands, in the order they were requested.
+    pub fn drain(&mut self) -> impl Iterator<Item = LayerCommand> + '_ {
+        self.commands.drain(..)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+impl System for LayerCommands {}
+
 /// A utility trait for downcasting of the layer manager proxy to the layer provider proxy.
 pub trait AsProvider {
     /// Downcasts the layer manager proxy to a reference to the layer provider proxy.
@@ -181,6 +327,9 @@ pub trait LayerObj: 'static {
 
     /// Converts a layer mutable reference to an `Any` mutable reference.
     fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Returns the type name of the layer, for diagnostic purposes.
+    fn layer_name(&self) -> &'static str;
 }
 
 pub trait Layer: LayerObj {
@@ -195,6 +344,19 @@ pub trait Layer: LayerObj {
     /// An event that is called **before** the layer is detached from the layer stack.
     fn on_detach(&mut self) {}
 
+    /// Starts the heavy initialization of the layer, such as loading assets,
+    /// called once right after [`Layer::on_attach`].
+    ///
+    /// *Returns `None` if the layer has nothing to initialize asynchronously.*
+    ///
+    /// The future cannot borrow the layer, its results may be shared through an `Rc`.
+    /// Deferred attaches poll the future whenever its waker is woken, synchronous attaches block
+    /// on it instead. **Blocking is not possible on the web, attach such layers deferred.**
+    /// [`Layer::poll_attach`] is only polled once the future completes.
+    fn attach_async(&mut self) -> Option<LocalFuture> {
+        None
+    }
+
     /// Polls the layer until it is ready to be attached to the layer stack.
     ///
     /// *Returns `true` if the layer is ready to be attached.*
@@ -205,6 +367,17 @@ pub trait Layer: LayerObj {
         true
     }
 
+    /// Returns the fraction of the attach work completed so far, between `0` and `1`,
+    /// displayed by loading screens while the layer is attached deferred.
+    ///
+    /// *Returns `None` if the layer does not report its progress.*
+    ///
+    /// Asynchronous initialization may report its progress through a shared
+    /// [`LoadProgress`](loading::LoadProgress).
+    fn attach_progress(&self) -> Option<f32> {
+        None
+    }
+
     /// Polls the layer until it is ready to be detached from the layer stack.
     ///
     /// *Returns `true` if the layer is ready to be detached.*
@@ -217,9 +390,10 @@ pub trait Layer: LayerObj {
 
     /// An event that is called when the layer is traversed **upwards**.
     ///
-    /// The `systems` parameter provides all available systems provided by layers below this one.
+    /// The `systems` parameter provides all available systems provided by this layer
+    /// and the layers below it, see [`LayerDependencyDeclaration::provide`].
     /// New systems may be provided to layers above this one by calling the
-    /// [`LayerSystemManager::provide_system`] method.
+    /// [`LayerSystemManager::provide_system_dyn`] method.
     /// *These systems will be automatically popped when this layer is traversed downwards.*
     ///
     /// The `next` function MUST be called to continue the traversal.
@@ -241,6 +415,10 @@ impl<T: Layer> LayerObj for T {
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
+
+    fn layer_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
 }
 
 /// A layer walker is used to traverse the layer stack, visiting each layer.
@@ -252,6 +430,21 @@ pub trait LayerWalker {
     fn next(&mut self, systems: &mut dyn LayerSystemManager<'_>);
 }
 
+/// A policy applied when a layer does not call [`LayerWalker::next`] in [`Layer::on_enter`],
+/// skipping all layers above it.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum BrokenChainPolicy {
+    /// The broken chain is not reported.
+    Ignore,
+    /// The broken chain is logged as a warning, once per layer.
+    ///
+    /// *This is the default policy.*
+    #[default]
+    Log,
+    /// The traversal panics with the name of the offending layer.
+    Panic,
+}
+
 /// A strategy for swapping layers.
 ///
 /// *This is used to determine how a layer should be attached and detached.*
@@ -305,7 +498,7 @@ impl LayerSwapType {
 }
 
 /// Systems are identified by their type.
-type SystemId = TypeId;
+pub type SystemId = TypeId;
 
 /// A base trait for layer managers.
 ///
@@ -328,4 +521,13 @@ pub trait LayerManager {
     /// Returns `true` if the layer manager has finished running, that is whether no
     /// layers are attached and no layers are polled to be attached.
     fn run(&mut self) -> bool;
+
+    /// Detaches all layers, top to bottom, polling each to completion.
+    ///
+    /// *Layers still being attached are dropped without being detached.*
+    fn shutdown(&mut self);
+
+    /// Returns a snapshot of all layers, attached layers first in traversal order,
+    /// followed by attaching and detaching layers.
+    fn inspect(&self) -> Vec<LayerSnapshot>;
 }
@@ -22,47 +22,53 @@
  * SOFTWARE.
  */
 
-mod traversal_chain;
-
-use crate::application::layer::pluto::traversal_chain::TraversalChain;
+use crate::application::event::EventBus;
+use crate::application::layer::budget::{
+    BudgetPolicy, BudgetViolation, LayerBudget, LayerCost, TraversalPhase,
+};
+use crate::application::layer::inspect::{LayerFrameCost, LayerSnapshot, LayerState};
+use crate::application::layer::loading::{LoadingLayerStatus, LoadingStatus};
+use crate::application::layer::timing::{LayerTiming, LayerTimingWindow, LayerTimings};
 use crate::application::layer::{
-    Layer, LayerDependencyDeclaration, LayerDependencyManager, LayerManager, LayerSwapType,
-    LayerSystemManager, LayerSystemProvider, LayerWalker, SystemId,
+    BrokenChainPolicy, Layer, LayerCommand, LayerCommands, LayerDependencyDeclaration,
+    LayerDependencyManager, LayerId, LayerManager, LayerSwapType, LayerSystemManager,
+    LayerSystemProvider, LayerWalker, SystemId,
 };
 use crate::application::system::System;
-use std::any::{Any, TypeId};
+use crate::application::time::Time;
+use crate::debug::validation;
+use crate::memory;
+use instant::Instant;
+use log::warn;
+use pluto_engine_display::pluto_engine_window::executor::{block_on, LocalExecutor};
+use pluto_io::jobs::JobPool;
+use std::any::TypeId;
 use std::cell::Cell;
 use std::collections::{HashMap, VecDeque};
 use std::fmt::{Debug, Formatter};
-use std::slice::IterMut;
+use std::mem;
+use std::num::NonZeroU32;
+use std::rc::Rc;
 
-type LayerId = u64;
+fn system_entry<T: System>(system: T) -> (SystemId, Box<dyn System>) {
+    (TypeId::of::<T>(), Box::new(system))
+}
 
 struct PlutoLayerDependencyManager<'a> {
     manager: &'a mut PlutoLayerManager,
+    systems: Vec<(SystemId, Box<dyn System>)>,
 }
 
 impl LayerDependencyManager for PlutoLayerDependencyManager<'_> {
     fn find_by_type(&self, layer_type: TypeId) -> Option<&dyn Layer> {
-        Some(
-            self.manager
-                .layers
-                .values()
-                .find(|l| l.layer.type_id() == layer_type)?
-                .layer
-                .as_ref(),
-        )
+        let id = self.manager.find_id_by_type(layer_type)?;
+        Some(self.manager.get_layer_info(id)?.layer.as_ref())
     }
 
     fn find_by_type_mut(&mut self, layer_type: TypeId) -> Option<&mut dyn Layer> {
-        Some(
-            self.manager
-                .layers
-                .values_mut()
-                .find(|l| l.layer.type_id() == layer_type)?
-                .layer
-                .as_mut(),
-        )
+        let id = self.manager.find_id_by_type(layer_type)?;
+        let index = self.manager.get_layer_index(id);
+        Some(self.manager.layers[index].layer.as_mut())
     }
 
     fn add_layer(&mut self, mut layer: Box<dyn Layer>) -> &mut dyn Layer {
@@ -72,61 +78,305 @@ impl LayerDependencyManager for PlutoLayerDependencyManager<'_> {
 
         self.manager.new_layers.back_mut().unwrap().1.as_mut()
     }
+
+    fn provide_system(&mut self, id: SystemId, system: Box<dyn System>) {
+        self.systems.retain(|(system_id, ..)| *system_id != id);
+        self.systems.push((id, system));
+    }
 }
 
-struct PlutoLayerSystemProxy<'a> {
-    systems: HashMap<SystemId, &'a mut dyn System>,
+/// A scope of systems visible to a single layer and all layers above it.
+///
+/// Each traversed layer gets its own proxy which falls back to the proxy of the layer below,
+/// systems provided in this scope are therefore popped once the proxy is dropped.
+///
+/// *The parent is only queried, so the systems of a scope may be borrowed
+/// for a shorter time than the systems of the scopes below it.*
+struct PlutoLayerSystemProxy<'p, 'a> {
+    parent: Option<&'p mut dyn LayerSystemProvider>,
+    /// The systems owned by the layer of this scope, borrowed instead of copied
+    /// so entering a layer does not allocate.
+    owned: &'a mut [(SystemId, Box<dyn System>)],
+    /// Systems provided while the layer is entered, later ones shadow earlier ones.
+    provided: Vec<(SystemId, &'a mut dyn System)>,
 }
 
-impl LayerSystemProvider for PlutoLayerSystemProxy<'_> {
-    fn query<T: System>(&self) -> Option<&T>
-    where
-        Self: Sized,
-    {
-        self.systems
-            .get(&TypeId::of::<T>())
-            .map(|system| system.as_any())
-            .and_then(|system| system.downcast_ref::<T>())
+impl<'p, 'a> PlutoLayerSystemProxy<'p, 'a> {
+    fn root(systems: &'a mut [(SystemId, Box<dyn System>)]) -> Self {
+        Self {
+            parent: None,
+            owned: systems,
+            provided: Vec::new(),
+        }
     }
 
-    fn query_mut<T: System>(&mut self) -> Option<&mut T>
-    where
-        Self: Sized,
-    {
-        self.systems
-            .get_mut(&TypeId::of::<T>())
-            .map(|system| system.as_any_mut())
-            .and_then(|system| system.downcast_mut::<T>())
+    fn scope(
+        parent: &'p mut dyn LayerSystemProvider,
+        systems: &'a mut [(SystemId, Box<dyn System>)],
+    ) -> Self {
+        Self {
+            parent: Some(parent),
+            owned: systems,
+            provided: Vec::new(),
+        }
     }
 }
 
-impl<'a> LayerSystemManager<'a> for PlutoLayerSystemProxy<'a> {
-    fn provide_system<T: System>(&mut self, system: &'a mut Box<T>)
-    where
-        Self: Sized,
-    {
-        self.systems
-            .insert(TypeId::of::<T>(), system.as_system_mut());
+impl LayerSystemProvider for PlutoLayerSystemProxy<'_, '_> {
+    fn query_dyn(&self, id: SystemId) -> Option<&dyn System> {
+        if let Some((.., system)) = self.provided.iter().rev().find(|(key, ..)| *key == id) {
+            return Some(&**system);
+        }
+
+        match self.owned.iter().find(|(key, ..)| *key == id) {
+            Some((.., system)) => Some(system.as_ref()),
+            None => self.parent.as_ref()?.query_dyn(id),
+        }
+    }
+
+    fn query_dyn_mut(&mut self, id: SystemId) -> Option<&mut dyn System> {
+        if let Some((.., system)) = self.provided.iter_mut().rev().find(|(key, ..)| *key == id) {
+            return Some(&mut **system);
+        }
+
+        match self.owned.iter_mut().find(|(key, ..)| *key == id) {
+            Some((.., system)) => Some(system.as_mut()),
+            None => self.parent.as_mut()?.query_dyn_mut(id),
+        }
+    }
+}
+
+impl<'a> LayerSystemManager<'a> for PlutoLayerSystemProxy<'_, 'a> {
+    fn provide_system_dyn(&mut self, id: SystemId, system: &'a mut dyn System) {
+        self.provided.push((id, system));
     }
 }
 
+/// A snapshot of the resources consumed so far, used to measure the cost of a layer.
+#[derive(Copy, Clone)]
+struct CostProbe {
+    start: Instant,
+    allocations: usize,
+}
+
+impl CostProbe {
+    fn start() -> Self {
+        Self {
+            start: Instant::now(),
+            allocations: memory::allocation_count(),
+        }
+    }
+
+    fn stop(&self) -> LayerCost {
+        LayerCost {
+            time: self.start.elapsed(),
+            allocations: memory::allocation_count().wrapping_sub(self.allocations),
+        }
+    }
+}
+
+type BudgetHook = Box<dyn FnMut(&BudgetViolation)>;
+
+/// Budgets of layers, keyed by the layer type.
+#[derive(Default)]
+struct PlutoLayerBudgets {
+    budgets: HashMap<TypeId, LayerBudget>,
+    /// The budget of layers without a budget for their type.
+    default: Option<LayerBudget>,
+    hook: Option<BudgetHook>,
+}
+
+impl PlutoLayerBudgets {
+    fn enforce(&mut self, layer: &mut BudgetedLayer, phase: TraversalPhase, cost: &LayerCost) {
+        let layer_type = <dyn Layer>::as_any(layer.layer).type_id();
+        let Some(budget) = self.budgets.get(&layer_type).or(self.default.as_ref()) else {
+            return;
+        };
+
+        let Some(exceeded) = budget.check(cost) else {
+            return;
+        };
+
+        let violation = BudgetViolation {
+            layer_name: layer.layer.layer_name(),
+            phase,
+            exceeded,
+            policy: budget.policy,
+        };
+
+        warn!("{}, applying {:?}.", violation, violation.policy);
+
+        match budget.policy {
+            BudgetPolicy::Log => {}
+            BudgetPolicy::Throttle(every_nth_frame) => *layer.throttle = Some(every_nth_frame),
+            BudgetPolicy::Detach => *layer.over_budget = true,
+        }
+
+        if let Some(hook) = self.hook.as_mut() {
+            hook(&violation);
+        }
+    }
+}
+
+/// The parts of [`LayerInfo`] affected by budget enforcement.
+struct BudgetedLayer<'l> {
+    layer: &'l dyn Layer,
+    throttle: &'l mut Option<NonZeroU32>,
+    over_budget: &'l mut bool,
+}
+
+/// Visits the layers of a frame, each borrowed for the whole traversal.
 struct PlutoLayerWalker<'a> {
-    layers: IterMut<'a, *mut LayerInfo>,
+    /// The layers not entered yet, in traversal order.
+    layers: std::slice::IterMut<'a, LayerInfo>,
+    budgets: &'a mut PlutoLayerBudgets,
+    frame: u64,
+    /// The cost of the layers above the one currently being entered.
+    nested_cost: LayerCost,
+    /// How many times [`LayerWalker::next`] was called by the layer currently being entered.
+    next_calls: u32,
+    broken_chain_policy: BrokenChainPolicy,
+}
+
+impl PlutoLayerWalker<'_> {
+    fn enter_next(&mut self, system_proxy: &mut dyn LayerSystemManager) {
+        if let Some(layer_info) = self.layers.next() {
+            let LayerInfo {
+                layer,
+                systems,
+                throttle,
+                over_budget,
+                broke_chain,
+                last_frame,
+                timing,
+                ..
+            } = layer_info;
+            let mut layer_systems =
+                PlutoLayerSystemProxy::scope(system_proxy.as_provider_mut(), systems);
+
+            if let Some(every_nth_frame) = *throttle {
+                if !self.frame.is_multiple_of(every_nth_frame.get() as u64) {
+                    self.enter_next(&mut layer_systems);
+                    return;
+                }
+            }
+
+            let call_probe = CostProbe::start();
+            let outer_nested_cost = mem::take(&mut self.nested_cost);
+            let outer_next_calls = mem::take(&mut self.next_calls);
+
+            layer.on_enter(&mut layer_systems, self);
+            drop(layer_systems);
+
+            let next_calls = mem::replace(&mut self.next_calls, outer_next_calls);
+            validation::check(next_calls <= 1, || {
+                format!(
+                    "{} called `next` {} times in `on_enter`, \
+                     the layers above it must only be entered once per frame",
+                    layer.layer_name(),
+                    next_calls
+                )
+            });
+
+            if next_calls == 0 {
+                self.report_broken_chain(layer.as_ref(), broke_chain);
+            }
+            let enter_cost = call_probe.stop() - mem::take(&mut self.nested_cost);
+
+            let leave_probe = CostProbe::start();
+            layer.on_leave(system_proxy.as_provider_mut());
+            let leave_cost = leave_probe.stop();
+
+            self.nested_cost = outer_nested_cost + call_probe.stop();
+
+            *last_frame = Some(LayerFrameCost {
+                frame: self.frame,
+                enter: enter_cost,
+                leave: leave_cost,
+            });
+            timing.record(enter_cost.time + leave_cost.time);
+
+            let mut budgeted = BudgetedLayer {
+                layer: layer.as_ref(),
+                throttle,
+                over_budget,
+            };
+            self.budgets
+                .enforce(&mut budgeted, TraversalPhase::Enter, &enter_cost);
+            self.budgets
+                .enforce(&mut budgeted, TraversalPhase::Leave, &leave_cost);
+        }
+    }
+
+    fn report_broken_chain(&self, layer: &dyn Layer, broke_chain: &mut bool) {
+        let skipped = self.layers.len();
+        let message = || {
+            format!(
+                "{} did not call `next` in `on_enter`, skipping the {} layer(s) above it",
+                layer.layer_name(),
+                skipped
+            )
+        };
+
+        match self.broken_chain_policy {
+            BrokenChainPolicy::Ignore => {}
+            BrokenChainPolicy::Log if !*broke_chain => warn!("{}.", message()),
+            BrokenChainPolicy::Log => {}
+            BrokenChainPolicy::Panic => panic!("{}", message()),
+        }
+
+        *broke_chain = true;
+    }
 }
 
 impl LayerWalker for PlutoLayerWalker<'_> {
     fn next(&mut self, system_proxy: &mut dyn LayerSystemManager) {
-        if let Some(&mut layer_info) = self.layers.next() {
-            let layer_info = unsafe { &mut *layer_info };
-            layer_info.layer.on_enter(system_proxy, self);
-            layer_info.layer.on_leave(system_proxy.as_provider_mut());
-        }
+        self.next_calls += 1;
+        self.enter_next(system_proxy);
     }
 }
 
 struct LayerInfo {
     id: LayerId,
     layer: Box<dyn Layer>,
+    systems: Vec<(SystemId, Box<dyn System>)>,
+    /// Set when the layer is throttled for exceeding its budget.
+    throttle: Option<NonZeroU32>,
+    /// Set when the layer should be detached for exceeding its budget.
+    over_budget: bool,
+    /// Layers with a higher priority are traversed after layers with a lower one.
+    priority: i32,
+    /// Set once the layer did not call `next`, so the broken chain is only logged once.
+    broke_chain: bool,
+    last_frame: Option<LayerFrameCost>,
+    timing: LayerTimingWindow,
+}
+
+impl LayerInfo {
+    fn new(
+        id: LayerId,
+        layer: Box<dyn Layer>,
+        systems: Vec<(SystemId, Box<dyn System>)>,
+        priority: i32,
+    ) -> Self {
+        Self {
+            id,
+            layer,
+            systems,
+            throttle: None,
+            over_budget: false,
+            priority,
+            broke_chain: false,
+            last_frame: None,
+            timing: LayerTimingWindow::default(),
+        }
+    }
+
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        self.layer
+            .should_detach()
+            .or(self.over_budget.then_some(LayerSwapType::Synchronous))
+    }
 }
 
 impl Debug for LayerInfo {
@@ -140,31 +390,341 @@ impl Debug for LayerInfo {
     }
 }
 
+/// A layer whose attach event was triggered, but which is not entered yet.
+struct AttachingLayer {
+    layer: Box<dyn Layer>,
+    systems: Vec<(SystemId, Box<dyn System>)>,
+    priority: i32,
+    /// Set once the asynchronous initialization of the layer completed.
+    initialized: Rc<Cell<bool>>,
+}
+
 pub struct PlutoLayerManager {
-    traversal_chain: TraversalChain,
-    layers: HashMap<LayerId, LayerInfo>,
+    /// The attached layers in traversal order, bottom to top.
+    layers: Vec<LayerInfo>,
     detaching_layers: Vec<(LayerSwapType, Box<dyn Layer>)>,
     new_layers: VecDeque<(LayerSwapType, Box<dyn Layer>)>,
+    attaching_layers: Vec<AttachingLayer>,
+    /// Runs the asynchronous initialization of deferred layers,
+    /// polling each only after its waker was woken.
+    attach_executor: LocalExecutor,
     id_counter: LayerId,
+    budgets: PlutoLayerBudgets,
+    broken_chain_policy: BrokenChainPolicy,
+    frame: u64,
+    /// The systems provided to all layers, [`LayerCommands`], [`EventBus`], [`Time`],
+    /// [`JobPool`], [`LayerTimings`] and [`LoadingStatus`].
+    systems: Vec<(SystemId, Box<dyn System>)>,
+    /// A scratch buffer reused every iteration, so traversing an unchanged stack does not allocate.
+    layers_to_detach: Vec<(LayerId, LayerSwapType)>,
 }
 
 impl PlutoLayerManager {
     pub fn new() -> Self {
         Self {
-            traversal_chain: TraversalChain::new(),
-            layers: HashMap::new(),
+            layers: Vec::new(),
             detaching_layers: Vec::new(),
             new_layers: VecDeque::new(),
+            attaching_layers: Vec::new(),
+            attach_executor: LocalExecutor::new(),
             id_counter: 0,
+            budgets: PlutoLayerBudgets::default(),
+            broken_chain_policy: BrokenChainPolicy::default(),
+            frame: 0,
+            systems: vec![
+                system_entry(LayerCommands::new()),
+                system_entry(EventBus::new()),
+                system_entry(Time::new()),
+                system_entry(JobPool::default()),
+                system_entry(LayerTimings::default()),
+                system_entry(LoadingStatus::default()),
+            ],
+            layers_to_detach: Vec::new(),
         }
     }
 
+    /// *Returns the job pool provided to all layers, e.g. to share it with asset managers.*
+    pub fn get_jobs(&self) -> &JobPool {
+        self.system()
+    }
+
+    /// *Returns the timings of the layers entered in the last frame.*
+    pub fn get_layer_timings(&self) -> &LayerTimings {
+        self.system()
+    }
+
+    /// Sets the budget of all layers of the given type, replacing the previous one.
+    pub fn set_budget<T: Layer>(&mut self, budget: LayerBudget) {
+        self.budgets.budgets.insert(TypeId::of::<T>(), budget);
+    }
+
+    /// Removes the budget of all layers of the given type.
+    ///
+    /// *Layers already throttled by their budget remain throttled.*
+    pub fn remove_budget<T: Layer>(&mut self) {
+        self.budgets.budgets.remove(&TypeId::of::<T>());
+    }
+
+    /// Sets the budget of all layers without a budget for their type,
+    /// e.g. to warn about any layer taking a significant part of the frame.
+    pub fn set_default_budget(&mut self, budget: Option<LayerBudget>) {
+        self.budgets.default = budget;
+    }
+
+    /// Sets a hook called for every budget violation, in addition to the budget policy.
+    pub fn set_budget_hook(&mut self, hook: impl FnMut(&BudgetViolation) + 'static) {
+        self.budgets.hook = Some(Box::new(hook));
+    }
+
+    /// Sets the policy applied when a layer does not call `next` in `on_enter`.
+    pub fn set_broken_chain_policy(&mut self, policy: BrokenChainPolicy) {
+        self.broken_chain_policy = policy;
+    }
+
+    /// Adds a layer above all layers with the same or a lower priority,
+    /// but below all layers with a higher priority.
+    ///
+    /// *Layers added by [`LayerManager::add_layer`] have a priority of `0`.*
+    pub fn add_layer_with_priority(&mut self, layer: Box<dyn Layer>, priority: i32) {
+        let index = self.priority_index(priority);
+        self.insert_layer(layer, index, priority);
+    }
+
+    /// Adds a layer right below the first layer of type `T`, with the same priority.
+    ///
+    /// ***Panics** if no layer of type `T` is attached.*
+    pub fn add_layer_before<T: Layer>(&mut self, layer: Box<dyn Layer>) {
+        let (id, priority) = self.find_layer::<T>();
+        let index = self.get_layer_index(id);
+        self.insert_layer(layer, index, priority);
+    }
+
+    /// Adds a layer right above the first layer of type `T`, with the same priority.
+    ///
+    /// ***Panics** if no layer of type `T` is attached.*
+    pub fn add_layer_after<T: Layer>(&mut self, layer: Box<dyn Layer>) {
+        let (id, priority) = self.find_layer::<T>();
+        let index = self.get_layer_index(id) + 1;
+        self.insert_layer(layer, index, priority);
+    }
+
+    /// Adds a layer with the given priority once its asynchronous initialization completes,
+    /// polling the initialization whenever it is woken and the layer itself once per iteration.
+    ///
+    /// *Dependencies of the layer are attached right away.* See [`Layer::attach_async`].
+    pub fn add_layer_deferred(&mut self, layer: Box<dyn Layer>, priority: i32) {
+        let index = self.priority_index(priority);
+        let (attaching, _) = self.begin_attach(layer, index, priority, LayerSwapType::Deferred);
+        self.attaching_layers.push(attaching);
+    }
+
+    /// Returns the events broadcast between layers,
+    /// allowing to publish events from outside of the layer stack.
+    pub fn get_events_mut(&mut self) -> &mut EventBus {
+        self.system_mut()
+    }
+
+    /// Returns the time provided to layers, allowing to configure it.
+    pub fn get_time_mut(&mut self) -> &mut Time {
+        self.system_mut()
+    }
+
+    /// *Returns one of the systems provided to all layers.*
+    fn system<T: System>(&self) -> &T {
+        self.systems
+            .iter()
+            .find_map(|(.., system)| system.as_any().downcast_ref())
+            .unwrap()
+    }
+
+    fn system_mut<T: System>(&mut self) -> &mut T {
+        self.systems
+            .iter_mut()
+            .find_map(|(.., system)| system.as_any_mut().downcast_mut())
+            .unwrap()
+    }
+
+    /// *Returns the names of the attached layers in traversal order, bottom to top.*
+    pub fn get_layer_order(&self) -> Vec<&'static str> {
+        self.layers
+            .iter()
+            .map(|info| info.layer.layer_name())
+            .collect()
+    }
+
+    fn get_layer_info(&self, id: LayerId) -> Option<&LayerInfo> {
+        self.layers.iter().find(|info| info.id == id)
+    }
+
+    fn get_layer_index(&self, id: LayerId) -> usize {
+        self.layers.iter().position(|info| info.id == id).unwrap()
+    }
+
+    fn find_layer<T: Layer>(&self) -> (LayerId, i32) {
+        let id = self
+            .find_id_by_type(TypeId::of::<T>())
+            .unwrap_or_else(|| panic!("No layer of type {}", std::any::type_name::<T>()));
+
+        (id, self.get_layer_info(id).unwrap().priority)
+    }
+
+    /// *Returns the bottom-most attached layer of the given type.*
+    fn find_id_by_type(&self, layer_type: TypeId) -> Option<LayerId> {
+        self.layers
+            .iter()
+            .find(|info| <dyn Layer>::as_any(&*info.layer).type_id() == layer_type)
+            .map(|info| info.id)
+    }
+
+    /// *Returns the index a layer with the given priority should be inserted at.*
+    fn priority_index(&self, priority: i32) -> usize {
+        self.layers
+            .iter()
+            .position(|info| info.priority > priority)
+            .unwrap_or(self.layers.len())
+    }
+
+    /// Attaches a layer at the index, its dependencies are inserted right below it.
+    ///
+    /// *Returns the index right above the layer.*
+    fn insert_layer(&mut self, layer: Box<dyn Layer>, index: usize, priority: i32) -> usize {
+        let (mut attaching, index) =
+            self.begin_attach(layer, index, priority, LayerSwapType::Synchronous);
+
+        // Manually added layers are always polled to completion (synchronously).
+        LayerSwapType::Synchronous.poll_attach(&mut attaching.layer);
+
+        self.finish_attach(attaching, index);
+        index + 1
+    }
+
+    /// Triggers the attach event of a layer, inserting its dependencies at the index,
+    /// and starts its asynchronous initialization.
+    ///
+    /// *Returns the layer and the index right above its dependencies.*
+    fn begin_attach(
+        &mut self,
+        mut layer: Box<dyn Layer>,
+        mut index: usize,
+        priority: i32,
+        swap_type: LayerSwapType,
+    ) -> (AttachingLayer, usize) {
+        // Trigger the layer's attach event.
+        let mut dependency_manager = PlutoLayerDependencyManager {
+            manager: self,
+            systems: Vec::new(),
+        };
+        layer.on_attach(&mut LayerDependencyDeclaration(&mut dependency_manager));
+        let systems = dependency_manager.systems;
+
+        // Recursively add all dependency layers, breadth first.
+        while let Some((.., layer)) = self.new_layers.pop_front() {
+            index = self.insert_layer(layer, index, priority);
+        }
+
+        let initialized = Rc::new(Cell::new(true));
+        if let Some(future) = layer.attach_async() {
+            match swap_type {
+                LayerSwapType::Synchronous => block_on(future),
+                LayerSwapType::Deferred => {
+                    initialized.set(false);
+                    let completed = initialized.clone();
+                    self.attach_executor.spawn(Box::pin(async move {
+                        future.await;
+                        completed.set(true);
+                    }));
+                }
+            }
+        }
+
+        let attaching = AttachingLayer {
+            layer,
+            systems,
+            priority,
+            initialized,
+        };
+
+        (attaching, index)
+    }
+
+    fn finish_attach(&mut self, attaching: AttachingLayer, index: usize) {
+        let id = self.create_id();
+        let info = LayerInfo::new(id, attaching.layer, attaching.systems, attaching.priority);
+        self.layers.insert(index, info);
+    }
+
+    /// Publishes the timings of the layers entered in the current frame to [`LayerTimings`].
+    fn publish_timings(&mut self) {
+        // The timings are swapped out and back in to keep their capacity
+        let mut timings = mem::take(&mut self.system_mut::<LayerTimings>().timings);
+        timings.clear();
+        timings.extend(self.layers.iter().filter_map(|info| {
+            let last = info.last_frame.filter(|last| last.frame == self.frame)?;
+            let (mean_time, peak_time) = info.timing.aggregate();
+
+            Some(LayerTiming {
+                name: info.layer.layer_name(),
+                last,
+                mean_time,
+                peak_time,
+            })
+        }));
+        self.system_mut::<LayerTimings>().timings = timings;
+    }
+
+    /// Publishes the progress of the layers still being attached to [`LoadingStatus`].
+    fn publish_loading(&mut self) {
+        let mut layers = mem::take(&mut self.system_mut::<LoadingStatus>().layers);
+        layers.clear();
+        layers.extend(
+            self.attaching_layers
+                .iter()
+                .map(|attaching| LoadingLayerStatus {
+                    name: attaching.layer.layer_name(),
+                    progress: attaching.layer.attach_progress(),
+                }),
+        );
+        self.system_mut::<LoadingStatus>().layers = layers;
+    }
+
     fn create_id(&mut self) -> LayerId {
         let id = self.id_counter;
         self.id_counter += 1;
         id
     }
 
+    fn apply_commands(&mut self, commands: &mut LayerCommands) {
+        for command in commands.drain() {
+            match command {
+                LayerCommand::Attach {
+                    layer,
+                    priority,
+                    swap_type: LayerSwapType::Synchronous,
+                } => {
+                    self.add_layer_with_priority(layer, priority);
+                }
+                LayerCommand::Attach {
+                    layer,
+                    priority,
+                    swap_type: LayerSwapType::Deferred,
+                } => {
+                    self.add_layer_deferred(layer, priority);
+                }
+                LayerCommand::Detach(layer_type) => {
+                    while let Some(id) = self.find_id_by_type(layer_type) {
+                        self.begin_detach(id, LayerSwapType::Synchronous);
+                    }
+                }
+            }
+        }
+    }
+
+    fn begin_detach(&mut self, id: LayerId, swap_type: LayerSwapType) {
+        let mut layer_info = self.layers.remove(self.get_layer_index(id));
+        layer_info.layer.on_detach();
+        self.detaching_layers.push((swap_type, layer_info.layer));
+    }
+
     fn detach_poll(&mut self) {
         // Poll detaching layers
         let mut i = 0;
@@ -180,23 +740,19 @@ impl PlutoLayerManager {
     }
 
     fn attach_poll(&mut self) {
+        self.attach_executor.run_woken();
+
         // Poll attaching layers
         let mut i = 0;
-        while i < self.new_layers.len() {
-            let (swap_type, ..) = self.new_layers[i];
-            let (.., layer) = &mut self.new_layers[i];
-
-            if swap_type.poll_attach(layer) {
-                let (.., layer_owned) = self.new_layers.remove(i).unwrap();
-                let id = self.create_id();
-                self.layers.insert(
-                    id,
-                    LayerInfo {
-                        id,
-                        layer: layer_owned,
-                    },
-                );
-                self.traversal_chain.insert_last(id);
+        while i < self.attaching_layers.len() {
+            let AttachingLayer {
+                layer, initialized, ..
+            } = &mut self.attaching_layers[i];
+
+            if initialized.get() && LayerSwapType::Deferred.poll_attach(layer) {
+                let attaching = self.attaching_layers.remove(i);
+                let index = self.priority_index(attaching.priority);
+                self.finish_attach(attaching, index);
             } else {
                 i += 1;
             }
@@ -205,80 +761,121 @@ impl PlutoLayerManager {
 }
 
 impl LayerManager for PlutoLayerManager {
-    fn add_layer(&mut self, mut layer: Box<dyn Layer>) {
-        // Trigger the layer's attach event.
-        layer.on_attach(&mut LayerDependencyDeclaration(
-            &mut PlutoLayerDependencyManager { manager: self },
-        ));
-
-        // Recursively add all dependency layers, breadth first.
-        while let Some((.., layer)) = self.new_layers.pop_front() {
-            self.add_layer(layer);
-        }
-
-        // Manually added layers are always polled to completion (synchronously).
-        LayerSwapType::Synchronous.poll_attach(&mut layer);
-
-        let id = self.create_id();
-        let info = LayerInfo { id, layer };
-        self.layers.insert(id, info);
-        self.traversal_chain.insert_last(id);
+    fn add_layer(&mut self, layer: Box<dyn Layer>) {
+        self.add_layer_with_priority(layer, 0);
     }
 
     fn run(&mut self) -> bool {
-        let mut system_proxy = PlutoLayerSystemProxy {
-            systems: HashMap::new(),
-        };
-
-        let layers_iter = self.traversal_chain.iter();
-        let mut layers = layers_iter
-            .map(|id| self.layers.get_mut(&id).unwrap() as *mut LayerInfo)
-            .collect::<Vec<_>>();
+        self.get_events_mut().update();
+        self.get_time_mut().update();
 
         let mut walker = PlutoLayerWalker {
-            layers: layers.iter_mut(),
+            layers: self.layers.iter_mut(),
+            budgets: &mut self.budgets,
+            frame: self.frame,
+            nested_cost: LayerCost::default(),
+            next_calls: 0,
+            broken_chain_policy: self.broken_chain_policy,
         };
 
-        walker.next(&mut system_proxy);
-
-        // Collect all layers that are detaching
-        let layers_to_detach: Vec<(LayerId, LayerSwapType)> = self
-            .layers
-            .iter()
-            .filter_map(|(id, layer_info)| {
-                if let Some(swap_type) = layer_info.layer.should_detach() {
-                    return Some((*id, swap_type));
-                }
-
-                None
-            })
-            .collect();
+        walker.next(&mut PlutoLayerSystemProxy::root(&mut self.systems));
+        self.publish_timings();
+        self.frame += 1;
+
+        // The commands are swapped out and back in to keep their capacity
+        let mut commands = mem::take(self.system_mut::<LayerCommands>());
+        self.apply_commands(&mut commands);
+        *self.system_mut() = commands;
+
+        // Collect all layers that are detaching, bottom to top
+        let mut layers_to_detach = mem::take(&mut self.layers_to_detach);
+        layers_to_detach.extend(
+            self.layers
+                .iter()
+                .filter_map(|info| Some((info.id, info.should_detach()?))),
+        );
 
         // Remove layers that are detaching
-        for (id, swap_type) in layers_to_detach.into_iter() {
-            let layer_info = self.layers.remove(&id).unwrap();
-            self.traversal_chain.remove(id);
-            self.detaching_layers.push((swap_type, layer_info.layer));
+        for (id, swap_type) in layers_to_detach.drain(..) {
+            self.begin_detach(id, swap_type);
         }
+        self.layers_to_detach = layers_to_detach;
 
         self.detach_poll();
 
         self.attach_poll();
+        self.publish_loading();
+
+        self.layers.is_empty() && self.attaching_layers.is_empty()
+    }
+
+    fn shutdown(&mut self) {
+        // Layers which did not finish attaching were never entered
+        self.new_layers.clear();
+        self.attaching_layers.clear();
+        self.attach_executor = LocalExecutor::new();
+
+        let ids = self.layers.iter().map(|info| info.id).collect::<Vec<_>>();
+        for id in ids.into_iter().rev() {
+            self.begin_detach(id, LayerSwapType::Synchronous);
+        }
+
+        for (swap_type, ..) in self.detaching_layers.iter_mut() {
+            *swap_type = LayerSwapType::Synchronous;
+        }
 
-        self.layers.is_empty()
+        self.detach_poll();
+    }
+
+    fn inspect(&self) -> Vec<LayerSnapshot> {
+        let attached = self.layers.iter().map(|info| LayerSnapshot {
+            id: Some(info.id),
+            name: info.layer.layer_name(),
+            state: LayerState::Attached,
+            throttle: info.throttle,
+            last_frame: info.last_frame,
+        });
+
+        let unattached = |layer: &dyn Layer, state| LayerSnapshot {
+            id: None,
+            name: layer.layer_name(),
+            state,
+            throttle: None,
+            last_frame: None,
+        };
+        let attaching = self
+            .attaching_layers
+            .iter()
+            .map(|attaching| unattached(attaching.layer.as_ref(), LayerState::Attaching));
+        let detaching = self
+            .detaching_layers
+            .iter()
+            .map(|(_, layer)| unattached(layer.as_ref(), LayerState::Detaching));
+
+        attached.chain(attaching).chain(detaching).collect()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::application::layer::pluto::traversal_chain::TraversalChainNode;
+    use crate::application::layer::budget::{BudgetPolicy, LayerBudget, TraversalPhase};
+    use crate::application::layer::inspect::LayerState;
+    use crate::application::layer::loading::{LoadProgress, LoadingStatus};
     use crate::application::layer::pluto::PlutoLayerManager;
     use crate::application::layer::{
-        Layer, LayerDependencyDeclaration, LayerManager, LayerSwapType, LayerSystemManager,
-        LayerWalker,
+        BrokenChainPolicy, Layer, LayerDependencyDeclaration, LayerManager, LayerSwapType,
+        LayerSystemManager, LayerSystemProvider, LayerWalker,
     };
+    use crate::application::system::System;
     use log::debug;
+    use pluto_engine_display::pluto_engine_window::executor::LocalFuture;
     use std::any::{Any, TypeId};
+    use std::cell::{Cell, RefCell};
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::task::{Context, Poll, Waker};
+    use std::time::Duration;
 
     struct DummyLayer2 {
         enter_count: u32,
@@ -339,6 +936,139 @@ mod test {
         }
     }
 
+    struct CounterSystem {
+        count: u32,
+    }
+
+    impl System for CounterSystem {}
+
+    struct CounterLayer;
+
+    impl Layer for CounterLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+
+        fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+            dependencies.provide(CounterSystem { count: 0 });
+        }
+
+        fn on_enter(&mut self, systems: &mut dyn LayerSystemManager, next: &mut dyn LayerWalker) {
+            systems.query_mut::<CounterSystem>().unwrap().count += 1;
+            next.next(systems);
+        }
+    }
+
+    struct CounterUserLayer {
+        seen: Option<u32>,
+        seen_on_leave: bool,
+    }
+
+    impl Layer for CounterUserLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+
+        fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+            dependencies.or_create(|| Box::new(CounterLayer));
+        }
+
+        fn on_enter(&mut self, systems: &mut dyn LayerSystemManager, next: &mut dyn LayerWalker) {
+            self.seen = systems
+                .query::<CounterSystem>()
+                .map(|counter| counter.count);
+            next.next(systems);
+        }
+
+        fn on_leave(&mut self, systems: &mut dyn LayerSystemProvider) {
+            self.seen_on_leave = systems.query::<CounterSystem>().is_some();
+        }
+    }
+
+    /// A layer providing a system is added as a dependency of another layer.
+    /// The system should be visible to both layers when traversing upwards.
+    #[test]
+    fn test_systems() {
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer(Box::new(CounterUserLayer {
+            seen: None,
+            seen_on_leave: false,
+        }));
+
+        layer_manager.run();
+        layer_manager.run();
+
+        let user = layer_manager.get_layer_info(1).unwrap();
+        let user = user.layer.as_any().downcast_ref::<CounterUserLayer>();
+        let user = user.unwrap();
+
+        assert_eq!(user.seen, Some(2));
+        assert!(user.seen_on_leave);
+    }
+
+    struct SlowLayer;
+
+    impl Layer for SlowLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+
+        fn on_enter(&mut self, systems: &mut dyn LayerSystemManager, next: &mut dyn LayerWalker) {
+            std::thread::sleep(Duration::from_millis(2));
+            next.next(systems);
+        }
+    }
+
+    /// A layer exceeding its time budget with the detach policy is added.
+    /// The hook should be called once and the layer detached after the first run.
+    #[test]
+    fn test_budget_detach() {
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer(Box::new(SlowLayer));
+        layer_manager.set_budget::<SlowLayer>(
+            LayerBudget::new(BudgetPolicy::Detach).max_time(Duration::from_millis(1)),
+        );
+
+        let violations = Rc::new(Cell::new(0));
+        let hook_violations = violations.clone();
+        layer_manager.set_budget_hook(move |violation| {
+            assert_eq!(violation.phase, TraversalPhase::Enter);
+            hook_violations.set(hook_violations.get() + 1);
+        });
+
+        layer_manager.run();
+
+        assert_eq!(violations.get(), 1);
+        assert!(layer_manager.layers.is_empty());
+    }
+
+    /// A slow and a fast layer are added with a default time budget.
+    /// Only the slow layer should exceed it and be reported as the slowest one.
+    #[test]
+    fn test_layer_timings() {
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer(Box::new(SlowLayer));
+        layer_manager.add_layer(Box::new(CounterLayer));
+        layer_manager.set_default_budget(Some(
+            LayerBudget::new(BudgetPolicy::Log).max_time(Duration::from_millis(1)),
+        ));
+
+        let violations = Rc::new(RefCell::new(Vec::new()));
+        let hook_violations = violations.clone();
+        layer_manager.set_budget_hook(move |violation| {
+            hook_violations.borrow_mut().push(violation.layer_name);
+        });
+
+        layer_manager.run();
+
+        let timings = layer_manager.get_layer_timings();
+        assert_eq!(timings.latest().len(), 2);
+        let slowest = timings.slowest().unwrap();
+        assert!(slowest.name.ends_with("SlowLayer"));
+        assert!(slowest.mean_time >= Duration::from_millis(2));
+        assert_eq!(violations.borrow().as_slice(), &[slowest.name]);
+    }
+
     /// A single layer with one dependency is added to the layer manager.
     /// Two layers should be present.
     #[test]
@@ -351,37 +1081,33 @@ mod test {
         assert_eq!(manager.layers.len(), 2);
 
         assert_eq!(
-            <dyn Layer>::as_any(&*manager.layers.get(&0).unwrap().layer).type_id(),
+            <dyn Layer>::as_any(&*manager.get_layer_info(0).unwrap().layer).type_id(),
             TypeId::of::<DummyLayer2>()
         );
 
         assert_eq!(
-            <dyn Layer>::as_any(&*manager.layers.get(&1).unwrap().layer).type_id(),
+            <dyn Layer>::as_any(&*manager.get_layer_info(1).unwrap().layer).type_id(),
             TypeId::of::<DummyLayer>()
         );
     }
 
     /// A single layer with one dependency is added to the layer manager.
-    /// A traversal chain with exactly 3 nodes should be present.
+    /// The dependency should be traversed first, and every layer should have its own ID.
     #[test]
-    fn test_traversal_chain() {
+    fn test_traversal_order() {
         let mut layer_manager = PlutoLayerManager::new();
         layer_manager.add_layer(Box::new(DummyLayer { enter_count: 0 }));
 
-        println!("{:?}", layer_manager.traversal_chain.fwd_chain);
-
-        assert_eq!(layer_manager.traversal_chain.fwd_chain.len(), 3);
-        assert_eq!(layer_manager.traversal_chain.bwd_chain.len(), 3);
+        println!("{:?}", layer_manager.layers);
 
-        let mut node = TraversalChainNode::Start;
-
-        for i in 0..layer_manager.traversal_chain.fwd_chain.len() {
-            let next_node = layer_manager.traversal_chain.fwd_chain.get(&node).unwrap();
-            assert_ne!(next_node, &node);
-            node = next_node.clone();
-        }
-
-        assert_eq!(node, TraversalChainNode::End);
+        assert_eq!(
+            layer_manager.get_layer_order(),
+            [
+                std::any::type_name::<DummyLayer2>(),
+                std::any::type_name::<DummyLayer>()
+            ]
+        );
+        assert_ne!(layer_manager.layers[0].id, layer_manager.layers[1].id);
     }
 
     /// A single layer with one dependency is added to the layer manager.
@@ -393,8 +1119,6 @@ mod test {
         let mut layer_manager = PlutoLayerManager::new();
         layer_manager.add_layer(Box::new(DummyLayer { enter_count: 0 }));
 
-        println!("{:?}", layer_manager.traversal_chain.fwd_chain);
-
         loop {
             if layer_manager.run() {
                 break;
@@ -402,7 +1126,7 @@ mod test {
 
             assert!(!layer_manager.layers.is_empty());
 
-            let layer = layer_manager.layers.get(&1);
+            let layer = layer_manager.get_layer_info(1);
 
             assert!(layer.is_some());
 
@@ -419,17 +1143,14 @@ mod test {
     }
 
     /// A single layer with one dependency is added to the layer manager.
-    /// The layer manager is run 3 times.
-    /// The traversal chain should should container a single start -> finish link.
+    /// The layer manager is run until all layers are detached.
+    /// No layers should be left to traverse.
     #[test]
-    fn test_traversal_chain_deconstruct() {
+    fn test_traversal_order_deconstruct() {
         let mut layer_manager = PlutoLayerManager::new();
         layer_manager.add_layer(Box::new(DummyLayer { enter_count: 0 }));
 
-        println!("{:?}", layer_manager.traversal_chain.fwd_chain);
-
-        assert_eq!(layer_manager.traversal_chain.fwd_chain.len(), 3);
-        assert_eq!(layer_manager.traversal_chain.bwd_chain.len(), 3);
+        assert_eq!(layer_manager.layers.len(), 2);
 
         loop {
             if layer_manager.run() {
@@ -437,7 +1158,451 @@ mod test {
             }
         }
 
-        assert_eq!(layer_manager.traversal_chain.fwd_chain.len(), 1);
-        assert_eq!(layer_manager.traversal_chain.bwd_chain.len(), 1);
+        assert!(layer_manager.layers.is_empty());
+        assert!(layer_manager.get_layer_order().is_empty());
+    }
+
+    struct OrderedDetachLayer {
+        name: &'static str,
+        detached: Rc<RefCell<Vec<&'static str>>>,
+    }
+
+    impl Layer for OrderedDetachLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+
+        fn on_detach(&mut self) {
+            self.detached.borrow_mut().push(self.name);
+        }
+    }
+
+    /// Two layers which never detach themselves are added and the manager is shut down.
+    /// Both layers should be detached, the top one first, and the manager should be empty.
+    #[test]
+    fn test_shutdown() {
+        let detached = Rc::new(RefCell::new(Vec::new()));
+        let mut layer_manager = PlutoLayerManager::new();
+
+        for name in ["bottom", "top"] {
+            layer_manager.add_layer(Box::new(OrderedDetachLayer {
+                name,
+                detached: detached.clone(),
+            }));
+        }
+
+        assert!(!layer_manager.run());
+        layer_manager.shutdown();
+
+        assert_eq!(*detached.borrow(), ["top", "bottom"]);
+        assert!(layer_manager.layers.is_empty());
+        assert!(layer_manager.detaching_layers.is_empty());
+    }
+
+    struct WorldLayer;
+    struct UiLayer;
+    struct DebugLayer;
+
+    impl Layer for WorldLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+    }
+
+    impl Layer for UiLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+    }
+
+    impl Layer for DebugLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+    }
+
+    /// A high priority layer is added first, then layers relative to a default priority one.
+    /// The high priority layer should stay on top and the others should be placed
+    /// around the layer they were added relative to.
+    #[test]
+    fn test_layer_order() {
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer_with_priority(Box::new(UiLayer), 10);
+        layer_manager.add_layer(Box::new(WorldLayer));
+        layer_manager.add_layer_after::<WorldLayer>(Box::new(DebugLayer));
+        layer_manager.add_layer_before::<WorldLayer>(Box::new(CounterLayer));
+
+        let order = layer_manager
+            .get_layer_order()
+            .into_iter()
+            .map(|name| name.rsplit("::").next().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            order,
+            ["CounterLayer", "WorldLayer", "DebugLayer", "UiLayer"]
+        );
+    }
+
+    struct PauseLayer {
+        paused: bool,
+    }
+
+    impl Layer for PauseLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+
+        fn on_enter(&mut self, systems: &mut dyn LayerSystemManager, next: &mut dyn LayerWalker) {
+            self.paused = !self.paused;
+
+            if self.paused {
+                systems
+                    .commands()
+                    .attach_with_priority(Box::new(UiLayer), 10);
+            } else {
+                systems.commands().detach::<UiLayer>();
+            }
+
+            next.next(systems);
+        }
+    }
+
+    /// A layer attaches a menu layer during traversal and detaches it during the next one.
+    /// The menu should be attached after the first run and detached after the second.
+    #[test]
+    fn test_layer_commands() {
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer(Box::new(PauseLayer { paused: false }));
+
+        assert!(!layer_manager.run());
+        assert_eq!(layer_manager.get_layer_order().len(), 2);
+        assert!(layer_manager.get_layer_order()[1].ends_with("UiLayer"));
+
+        assert!(!layer_manager.run());
+        assert_eq!(layer_manager.get_layer_order().len(), 1);
+    }
+
+    struct NamedLayer {
+        name: &'static str,
+        detach: bool,
+        detached: Rc<RefCell<Vec<&'static str>>>,
+    }
+
+    impl Layer for NamedLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            self.detach.then_some(LayerSwapType::Synchronous)
+        }
+
+        fn on_detach(&mut self) {
+            self.detached.borrow_mut().push(self.name);
+        }
+    }
+
+    struct NamedLayerUser {
+        seen: Option<&'static str>,
+    }
+
+    impl Layer for NamedLayerUser {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+
+        fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+            self.seen = dependencies
+                .optional::<NamedLayer>()
+                .map(|layer| layer.name);
+        }
+    }
+
+    /// Many layers of the same type are added, followed by a layer depending on that type.
+    /// The dependency should always resolve to the bottom-most layer and all layers
+    /// detaching in the same iteration should be detached bottom to top.
+    #[test]
+    fn test_deterministic_order() {
+        const NAMES: [&str; 8] = ["a", "b", "c", "d", "e", "f", "g", "h"];
+
+        let detached = Rc::new(RefCell::new(Vec::new()));
+        let mut layer_manager = PlutoLayerManager::new();
+
+        for name in NAMES {
+            layer_manager.add_layer(Box::new(NamedLayer {
+                name,
+                detach: true,
+                detached: detached.clone(),
+            }));
+        }
+
+        layer_manager.add_layer(Box::new(NamedLayerUser { seen: None }));
+        let user = layer_manager
+            .find_id_by_type(TypeId::of::<NamedLayerUser>())
+            .unwrap();
+        let seen = layer_manager
+            .get_layer_info(user)
+            .unwrap()
+            .layer
+            .as_any()
+            .downcast_ref::<NamedLayerUser>()
+            .unwrap()
+            .seen;
+        assert_eq!(seen, Some("a"));
+
+        assert!(!layer_manager.run());
+        assert_eq!(*detached.borrow(), NAMES);
+    }
+
+    /// A future which is pending for a given number of polls.
+    struct Loading(u32);
+
+    impl Future for Loading {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+            if self.0 == 0 {
+                return Poll::Ready(());
+            }
+
+            self.0 -= 1;
+            context.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    struct AssetLayer {
+        loaded: Rc<Cell<bool>>,
+    }
+
+    impl Layer for AssetLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+
+        fn attach_async(&mut self) -> Option<LocalFuture> {
+            let loaded = self.loaded.clone();
+            Some(Box::pin(async move {
+                Loading(2).await;
+                loaded.set(true);
+            }))
+        }
+    }
+
+    /// A layer loading its assets over two iterations is added deferred and synchronously.
+    /// The deferred layer should only be attached once loaded, the synchronous one right away.
+    #[test]
+    fn test_attach_async() {
+        let loaded = Rc::new(Cell::new(false));
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer_deferred(
+            Box::new(AssetLayer {
+                loaded: loaded.clone(),
+            }),
+            0,
+        );
+
+        assert!(!layer_manager.run());
+        assert!(!layer_manager.run());
+        assert!(!loaded.get());
+        assert!(layer_manager.get_layer_order().is_empty());
+
+        assert!(!layer_manager.run());
+        assert!(loaded.get());
+        assert_eq!(layer_manager.get_layer_order().len(), 1);
+
+        let loaded = Rc::new(Cell::new(false));
+        layer_manager.add_layer(Box::new(AssetLayer {
+            loaded: loaded.clone(),
+        }));
+        assert!(loaded.get());
+        assert_eq!(layer_manager.get_layer_order().len(), 2);
+    }
+
+    /// A future which completes once signalled, counting how many times it was polled.
+    #[derive(Default)]
+    struct Signal {
+        ready: Cell<bool>,
+        polls: Cell<u32>,
+        waker: RefCell<Option<Waker>>,
+    }
+
+    struct SignalFuture(Rc<Signal>);
+
+    impl Future for SignalFuture {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+            let signal = &self.0;
+            signal.polls.set(signal.polls.get() + 1);
+
+            if signal.ready.get() {
+                return Poll::Ready(());
+            }
+
+            *signal.waker.borrow_mut() = Some(context.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    struct SignalLayer(Rc<Signal>);
+
+    impl Layer for SignalLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+
+        fn attach_async(&mut self) -> Option<LocalFuture> {
+            Some(Box::pin(SignalFuture(self.0.clone())))
+        }
+    }
+
+    /// A layer waiting for a signal is added deferred and the manager is run a few times.
+    /// Its initialization should only be polled again once woken, then the layer attached.
+    #[test]
+    fn test_attach_async_polls_woken() {
+        let signal = Rc::new(Signal::default());
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer_deferred(Box::new(SignalLayer(signal.clone())), 0);
+
+        for _ in 0..3 {
+            assert!(!layer_manager.run());
+        }
+        assert_eq!(signal.polls.get(), 1);
+        assert!(layer_manager.get_layer_order().is_empty());
+
+        signal.ready.set(true);
+        signal.waker.take().unwrap().wake();
+        assert!(!layer_manager.run());
+        assert_eq!(signal.polls.get(), 2);
+        assert_eq!(layer_manager.get_layer_order().len(), 1);
+    }
+
+    #[cfg(all(feature = "pe_validation", debug_assertions))]
+    struct TwiceLayer;
+
+    #[cfg(all(feature = "pe_validation", debug_assertions))]
+    impl Layer for TwiceLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+
+        fn on_enter(&mut self, systems: &mut dyn LayerSystemManager, next: &mut dyn LayerWalker) {
+            next.next(systems);
+            next.next(systems);
+        }
+    }
+
+    /// A layer enters the layers above it twice in the same frame.
+    /// With validation compiled in, the traversal should panic naming the layer.
+    #[test]
+    #[cfg(all(feature = "pe_validation", debug_assertions))]
+    #[should_panic(expected = "TwiceLayer called `next` 2 times")]
+    fn test_validate_next_calls() {
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer(Box::new(TwiceLayer));
+        layer_manager.run();
+    }
+
+    struct BrokenLayer;
+
+    impl Layer for BrokenLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+
+        fn on_enter(&mut self, _: &mut dyn LayerSystemManager, _: &mut dyn LayerWalker) {}
+    }
+
+    /// A layer does not call `next` in `on_enter`, skipping the layer above it.
+    /// With the panic policy, the traversal should panic naming the layer.
+    #[test]
+    #[should_panic(
+        expected = "BrokenLayer did not call `next` in `on_enter`, skipping the 1 layer(s)"
+    )]
+    fn test_broken_chain() {
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.set_broken_chain_policy(BrokenChainPolicy::Panic);
+        layer_manager.add_layer_with_priority(Box::new(BrokenLayer), 0);
+        layer_manager.add_layer_with_priority(Box::new(CounterLayer), 1);
+        layer_manager.run();
+    }
+
+    /// A counter layer is attached and an asset layer is loading deferred over two iterations.
+    /// The snapshot should list the attached layer with its cost first, then the attaching one.
+    #[test]
+    fn test_inspect() {
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer(Box::new(CounterLayer));
+        layer_manager.add_layer_deferred(
+            Box::new(AssetLayer {
+                loaded: Rc::new(Cell::new(false)),
+            }),
+            0,
+        );
+        layer_manager.run();
+
+        let snapshot = layer_manager.inspect();
+        assert_eq!(snapshot.len(), 2);
+
+        assert_eq!(snapshot[0].id, Some(0));
+        assert!(snapshot[0].name.ends_with("CounterLayer"));
+        assert_eq!(snapshot[0].state, LayerState::Attached);
+        assert_eq!(snapshot[0].last_frame.map(|cost| cost.frame), Some(0));
+
+        assert_eq!(snapshot[1].id, None);
+        assert!(snapshot[1].name.ends_with("AssetLayer"));
+        assert_eq!(snapshot[1].state, LayerState::Attaching);
+        assert!(snapshot[1].last_frame.is_none());
+    }
+
+    struct StepLayer {
+        steps: usize,
+        progress: LoadProgress,
+    }
+
+    impl Layer for StepLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+
+        fn poll_attach(&mut self) -> bool {
+            self.steps += 1;
+            self.progress.set_steps(self.steps, 4);
+            self.steps == 4
+        }
+
+        fn attach_progress(&self) -> Option<f32> {
+            Some(self.progress.get())
+        }
+    }
+
+    /// A layer attaching over four polls is added deferred along with one not reporting progress.
+    /// The loading status should report the mean progress until both are attached.
+    #[test]
+    fn test_loading_status() {
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer_deferred(
+            Box::new(StepLayer {
+                steps: 0,
+                progress: LoadProgress::new(),
+            }),
+            0,
+        );
+        layer_manager.add_layer_deferred(
+            Box::new(AssetLayer {
+                loaded: Rc::new(Cell::new(false)),
+            }),
+            0,
+        );
+
+        layer_manager.run();
+        let loading = layer_manager.system::<LoadingStatus>();
+        assert_eq!(loading.latest().len(), 2);
+        assert_eq!(loading.progress(), 0.125);
+
+        for _ in 0..3 {
+            layer_manager.run();
+        }
+        let loading = layer_manager.system::<LoadingStatus>();
+        assert!(!loading.is_loading());
+        assert_eq!(loading.progress(), 1.0);
     }
 }
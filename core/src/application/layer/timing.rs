@@ -0,0 +1,129 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::application::layer::inspect::LayerFrameCost;
+use crate::application::system::System;
+use std::time::Duration;
+
+/// The number of frames a layer is entered in, over which its time is aggregated.
+pub const TIMING_WINDOW: u32 = 60;
+
+/// The cost of a layer entered in the last frame, along with its time aggregated
+/// over the last [`TIMING_WINDOW`] frames it was entered in.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct LayerTiming {
+    /// The type name of the layer.
+    pub name: &'static str,
+    pub last: LayerFrameCost,
+    /// The mean time of both traversal phases.
+    pub mean_time: Duration,
+    /// The longest time of both traversal phases.
+    pub peak_time: Duration,
+}
+
+/// A system provided to all layers, exposing the timings of the layers entered
+/// in the last frame, e.g. to feed them into [`RenderStats`](crate::render::stats::RenderStats).
+#[derive(Default)]
+pub struct LayerTimings {
+    pub(super) timings: Vec<LayerTiming>,
+}
+
+impl LayerTimings {
+    /// *Returns the timings of the layers entered in the last frame, in traversal order.*
+    pub fn latest(&self) -> &[LayerTiming] {
+        &self.timings
+    }
+
+    /// *Returns the timing of the layer with the highest mean time, if any layer was entered.*
+    pub fn slowest(&self) -> Option<&LayerTiming> {
+        self.timings.iter().max_by_key(|timing| timing.mean_time)
+    }
+}
+
+impl System for LayerTimings {}
+
+/// Aggregates the time of a single layer over a window of frames.
+///
+/// *The aggregate of the last completed window is reported,
+/// or of the current one until the first window completes.*
+#[derive(Copy, Clone, Debug, Default)]
+pub(super) struct LayerTimingWindow {
+    frames: u32,
+    total: Duration,
+    peak: Duration,
+    completed: Option<(Duration, Duration)>,
+}
+
+impl LayerTimingWindow {
+    pub(super) fn record(&mut self, time: Duration) {
+        self.frames += 1;
+        self.total += time;
+        self.peak = self.peak.max(time);
+
+        if self.frames == TIMING_WINDOW {
+            self.completed = Some((self.total / TIMING_WINDOW, self.peak));
+            *self = Self {
+                completed: self.completed,
+                ..Default::default()
+            };
+        }
+    }
+
+    /// *Returns the mean and peak time.*
+    pub(super) fn aggregate(&self) -> (Duration, Duration) {
+        match self.completed {
+            Some(completed) => completed,
+            None if self.frames == 0 => Default::default(),
+            None => (self.total / self.frames, self.peak),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::application::layer::timing::{LayerTimingWindow, TIMING_WINDOW};
+    use std::time::Duration;
+
+    /// A layer takes 1 ms for a full window, then 3 ms for a few frames.
+    /// The aggregate should be that of the completed window until the next one completes.
+    #[test]
+    fn test_timing_window() {
+        let mut window = LayerTimingWindow::default();
+        window.record(Duration::from_millis(1));
+        window.record(Duration::from_millis(3));
+        assert_eq!(
+            window.aggregate(),
+            (Duration::from_millis(2), Duration::from_millis(3))
+        );
+
+        for _ in 2..TIMING_WINDOW {
+            window.record(Duration::from_millis(1));
+        }
+        let completed = window.aggregate();
+        assert_eq!(completed.1, Duration::from_millis(3));
+
+        window.record(Duration::from_millis(3));
+        assert_eq!(window.aggregate(), completed);
+    }
+}
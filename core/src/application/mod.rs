@@ -24,8 +24,12 @@
 
 use crate::application::layer::LayerManager;
 
+pub mod event;
 pub mod layer;
+pub mod simulation;
+pub mod state;
 pub mod system;
+pub mod time;
 
 pub trait Application {
     fn run(layer_manager: &mut dyn LayerManager) -> Self;
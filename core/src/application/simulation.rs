@@ -0,0 +1,459 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Running the simulation on a worker thread, decoupled from rendering.
+//!
+//! The simulation advances in fixed ticks and publishes a snapshot of its state after every
+//! tick through a triple buffer. The render thread never waits for the simulation: it renders
+//! the state interpolated between the two latest snapshots.
+//!
+//! On platforms without threads, such as the web, the simulation is stepped on the render
+//! thread instead, with the same interpolation.
+
+use instant::Instant;
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A state which can be blended between two snapshots.
+pub trait Interpolate {
+    /// *Returns the state at `alpha` between `self` (0.0) and `next` (1.0).*
+    fn interpolate(&self, next: &Self, alpha: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, next: &Self, alpha: f32) -> Self {
+        self + (next - self) * alpha
+    }
+}
+
+impl<T: Interpolate + Clone> Interpolate for Vec<T> {
+    /// *Entries without a counterpart in `self` are taken from `next` as they are.*
+    fn interpolate(&self, next: &Self, alpha: f32) -> Self {
+        next.iter()
+            .enumerate()
+            .map(|(i, next)| match self.get(i) {
+                Some(previous) => previous.interpolate(next, alpha),
+                None => next.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Set in [`TripleBufferShared::middle`] while the middle buffer holds a value the reader has not read yet.
+const FRESH: u8 = 0b100;
+const INDEX_MASK: u8 = 0b011;
+
+struct TripleBufferShared<T> {
+    buffers: [UnsafeCell<T>; 3],
+    /// The index of the middle buffer, with the [`FRESH`] bit.
+    middle: AtomicU8,
+}
+
+// SAFETY: The writer, the reader and the middle always own distinct buffers, ownership of
+// the middle buffer is exchanged through an atomic swap, so no buffer is accessed by two threads.
+unsafe impl<T: Send> Sync for TripleBufferShared<T> {}
+
+/// Creates a triple buffer, letting a writer publish values without ever waiting for the reader.
+///
+/// The writer owns the buffer being written and the reader the buffer being read,
+/// the third buffer holds the latest published value. Publishing and reading swap
+/// a buffer with the middle one, so the three buffers are recycled and neither side locks.
+pub fn triple_buffer<T: Clone>(initial: T) -> (TripleBufferWriter<T>, TripleBufferReader<T>) {
+    let shared = Arc::new(TripleBufferShared {
+        buffers: [
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial),
+        ],
+        middle: AtomicU8::new(1),
+    });
+
+    (
+        TripleBufferWriter {
+            shared: shared.clone(),
+            write: 0,
+        },
+        TripleBufferReader { shared, read: 2 },
+    )
+}
+
+pub struct TripleBufferWriter<T> {
+    shared: Arc<TripleBufferShared<T>>,
+    write: u8,
+}
+
+impl<T> TripleBufferWriter<T> {
+    /// *Returns the buffer to write the next value to, holding an older value.*
+    pub fn input(&mut self) -> &mut T {
+        // SAFETY: The write buffer is owned by the writer until it is published.
+        unsafe { &mut *self.shared.buffers[self.write as usize].get() }
+    }
+
+    /// Publishes the input buffer, replacing the previous value if the reader has not read it yet.
+    pub fn publish(&mut self) {
+        let previous = self
+            .shared
+            .middle
+            .swap(self.write | FRESH, Ordering::AcqRel);
+        self.write = previous & INDEX_MASK;
+    }
+
+    /// Writes a value to the input buffer and publishes it.
+    pub fn write(&mut self, value: T) {
+        *self.input() = value;
+        self.publish();
+    }
+}
+
+pub struct TripleBufferReader<T> {
+    shared: Arc<TripleBufferShared<T>>,
+    read: u8,
+}
+
+impl<T> TripleBufferReader<T> {
+    /// *Returns the latest published value, or `None` if nothing was published since the last call.*
+    pub fn read(&mut self) -> Option<&T> {
+        if self.shared.middle.load(Ordering::Relaxed) & FRESH == 0 {
+            return None;
+        }
+
+        let middle = self.shared.middle.swap(self.read, Ordering::AcqRel);
+        self.read = middle & INDEX_MASK;
+
+        // SAFETY: The read buffer is owned by the reader until it is swapped back.
+        Some(unsafe { &*self.shared.buffers[self.read as usize].get() })
+    }
+}
+
+/// The state of the simulation after a tick.
+#[derive(Clone, Debug)]
+pub struct Snapshot<T> {
+    pub tick: u64,
+    pub state: T,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct SimulationConfig {
+    /// The simulated time of a single tick.
+    pub tick_duration: Duration,
+    /// Whether to run the simulation on a worker thread.
+    ///
+    /// *Ignored on the web, where the simulation always runs on the render thread.*
+    pub threaded: bool,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            tick_duration: Duration::from_secs(1) / 60,
+            threaded: true,
+        }
+    }
+}
+
+enum SimulationDriver<T> {
+    Threaded {
+        snapshots: TripleBufferReader<Snapshot<T>>,
+        /// When the current snapshot was received.
+        received: Instant,
+        stop: Arc<AtomicBool>,
+    },
+    Local {
+        tick: Box<dyn FnMut() -> T>,
+        tick_count: u64,
+        last_update: Instant,
+        accumulator: Duration,
+    },
+}
+
+/// A fixed timestep simulation producing snapshots of type `T` for the renderer.
+pub struct Simulation<T> {
+    config: SimulationConfig,
+    driver: SimulationDriver<T>,
+    previous: Snapshot<T>,
+    current: Snapshot<T>,
+}
+
+impl<T: Interpolate + Clone + Send + 'static> Simulation<T> {
+    /// Starts a simulation.
+    ///
+    /// The simulation state is created by `init` on the thread running the simulation,
+    /// so it does not need to be [`Send`], e.g. a layer manager running the update phases.
+    /// `tick` advances the state by a single tick and returns its snapshot.
+    pub fn start<S: 'static>(
+        config: SimulationConfig,
+        initial: T,
+        init: impl FnOnce() -> S + Send + 'static,
+        mut tick: impl FnMut(&mut S) -> T + Send + 'static,
+    ) -> Self {
+        let threaded = config.threaded && !cfg!(target_arch = "wasm32");
+
+        let driver = if threaded {
+            let (mut writer, reader) = triple_buffer(Snapshot {
+                tick: 0,
+                state: initial.clone(),
+            });
+            let stop = Arc::new(AtomicBool::new(false));
+            let worker_stop = stop.clone();
+
+            std::thread::spawn(move || {
+                let mut state = init();
+                let mut next_tick = Instant::now();
+
+                for tick_count in 1.. {
+                    if worker_stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    writer.write(Snapshot {
+                        tick: tick_count,
+                        state: tick(&mut state),
+                    });
+
+                    next_tick += config.tick_duration;
+                    let now = Instant::now();
+                    if next_tick > now {
+                        std::thread::sleep(next_tick - now);
+                    } else {
+                        // Too slow to keep up, do not try to catch up on the missed ticks.
+                        next_tick = now;
+                    }
+                }
+            });
+
+            SimulationDriver::Threaded {
+                snapshots: reader,
+                received: Instant::now(),
+                stop,
+            }
+        } else {
+            let mut state = init();
+
+            SimulationDriver::Local {
+                tick: Box::new(move || tick(&mut state)),
+                tick_count: 0,
+                last_update: Instant::now(),
+                accumulator: Duration::ZERO,
+            }
+        };
+
+        let snapshot = Snapshot {
+            tick: 0,
+            state: initial,
+        };
+
+        Self {
+            config,
+            driver,
+            previous: snapshot.clone(),
+            current: snapshot,
+        }
+    }
+
+    /// Returns `true` if the simulation runs on a worker thread.
+    pub fn is_threaded(&self) -> bool {
+        matches!(self.driver, SimulationDriver::Threaded { .. })
+    }
+
+    /// *Returns the latest snapshot produced by the simulation.*
+    pub fn latest(&self) -> &Snapshot<T> {
+        &self.current
+    }
+
+    fn push_snapshot(&mut self, snapshot: Snapshot<T>) {
+        self.previous = std::mem::replace(&mut self.current, snapshot);
+    }
+
+    /// Receives new snapshots and returns the state to render this frame,
+    /// interpolated between the two latest snapshots.
+    ///
+    /// When running on the render thread, this also advances the simulation by the elapsed time.
+    pub fn render_state(&mut self) -> T {
+        let now = Instant::now();
+        let tick_duration = self.config.tick_duration;
+
+        let (new_snapshots, elapsed) = match &mut self.driver {
+            SimulationDriver::Threaded {
+                snapshots,
+                received,
+                ..
+            } => {
+                // The snapshot is copied into the oldest one, reusing its allocations
+                if let Some(snapshot) = snapshots.read() {
+                    *received = now;
+                    std::mem::swap(&mut self.previous, &mut self.current);
+                    self.current.tick = snapshot.tick;
+                    self.current.state.clone_from(&snapshot.state);
+                }
+
+                (Vec::new(), now - *received)
+            }
+            SimulationDriver::Local {
+                tick,
+                tick_count,
+                last_update,
+                accumulator,
+            } => {
+                *accumulator += now - *last_update;
+                *last_update = now;
+
+                let mut new_snapshots = Vec::new();
+                while *accumulator >= tick_duration {
+                    *accumulator -= tick_duration;
+                    *tick_count += 1;
+                    new_snapshots.push(Snapshot {
+                        tick: *tick_count,
+                        state: tick(),
+                    });
+                }
+
+                (new_snapshots, *accumulator)
+            }
+        };
+
+        // Only the two latest snapshots are needed for the interpolation.
+        let skipped = new_snapshots.len().saturating_sub(2);
+        for snapshot in new_snapshots.into_iter().skip(skipped) {
+            self.push_snapshot(snapshot);
+        }
+
+        let alpha = (elapsed.as_secs_f32() / tick_duration.as_secs_f32()).clamp(0.0, 1.0);
+        self.previous.state.interpolate(&self.current.state, alpha)
+    }
+}
+
+impl<T> Drop for Simulation<T> {
+    fn drop(&mut self) {
+        if let SimulationDriver::Threaded { stop, .. } = &self.driver {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::application::simulation::{
+        triple_buffer, Interpolate, Simulation, SimulationConfig,
+    };
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    /// Several values are published before the reader reads.
+    /// Only the latest value should be read, and only once.
+    #[test]
+    fn test_triple_buffer() {
+        let (mut writer, mut reader) = triple_buffer(0);
+        writer.write(1);
+        writer.write(2);
+
+        assert_eq!(reader.read(), Some(&2));
+        assert_eq!(reader.read(), None);
+    }
+
+    /// Vectors are published and read repeatedly.
+    /// The writer should get back the buffers the reader is done with, keeping their allocations.
+    #[test]
+    fn test_triple_buffer_recycle() {
+        let (mut writer, mut reader) = triple_buffer(Vec::new());
+        let mut allocations = HashSet::new();
+
+        for i in 0..10 {
+            let input = writer.input();
+            input.clear();
+            input.push(i);
+            allocations.insert(input.as_ptr());
+            writer.publish();
+
+            assert_eq!(reader.read(), Some(&vec![i]));
+        }
+
+        assert_eq!(allocations.len(), 3);
+    }
+
+    /// A writer thread publishes increasing values while the reader reads.
+    /// The values read should never go back and the last one should be read eventually.
+    #[test]
+    fn test_triple_buffer_threaded() {
+        let (mut writer, mut reader) = triple_buffer(0u32);
+        let handle = std::thread::spawn(move || {
+            for i in 1..=10000 {
+                writer.write(i);
+            }
+        });
+
+        let mut last = 0;
+        while last < 10000 {
+            if let Some(&value) = reader.read() {
+                assert!(value > last);
+                last = value;
+            }
+        }
+
+        handle.join().unwrap();
+    }
+
+    /// A counter is simulated on a worker thread.
+    /// The rendered state should eventually advance and stay within the simulated range.
+    #[test]
+    fn test_threaded_simulation() {
+        let mut simulation = Simulation::start(
+            SimulationConfig {
+                tick_duration: Duration::from_millis(1),
+                threaded: true,
+            },
+            0.0,
+            || 0.0f32,
+            |counter| {
+                *counter += 1.0;
+                *counter
+            },
+        );
+
+        assert!(simulation.is_threaded());
+
+        let mut rendered = 0.0;
+        for _ in 0..1000 {
+            rendered = simulation.render_state();
+            if rendered > 0.0 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        assert!(rendered > 0.0);
+        assert!(rendered <= simulation.latest().state);
+    }
+
+    /// Vectors of different lengths are interpolated.
+    /// Extra entries of the next state should be taken as they are.
+    #[test]
+    fn test_interpolate_vec() {
+        let previous = vec![0.0, 10.0];
+        let next = vec![1.0, 20.0, 5.0];
+
+        assert_eq!(previous.interpolate(&next, 0.5), vec![0.5, 15.0, 5.0]);
+    }
+}
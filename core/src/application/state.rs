@@ -0,0 +1,324 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! A hierarchical state machine mapping game states, such as a menu or a paused game,
+//! to sets of layers.
+//!
+//! States form a stack, pushing a state attaches its layers above the layers of the states
+//! below it, which remain attached. Popping a state detaches its layers again.
+//! The layers are attached and detached through [`LayerCommands`]
+//! once the traversal of the frame the transition was requested in completes.
+
+use crate::application::layer::{
+    Layer, LayerCommand, LayerCommands, LayerDependencyDeclaration, LayerSwapType,
+    LayerSystemProvider,
+};
+use crate::application::system::System;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+/// A marker trait for types identifying game states, typically a fieldless enum.
+pub trait GameStateId: Copy + Eq + Hash + Debug + Send + 'static {}
+
+impl<T: Copy + Eq + Hash + Debug + Send + 'static> GameStateId for T {}
+
+type LayerFactory = Box<dyn Fn() -> Box<dyn Layer> + Send>;
+
+struct StateLayer {
+    layer_type: TypeId,
+    priority: i32,
+    swap_type: LayerSwapType,
+    create: LayerFactory,
+}
+
+/// The layers attached while a game state is on the state stack.
+///
+/// *Layers are detached by type, so a layer type should only be part of a single state.*
+#[derive(Default)]
+pub struct StateLayers {
+    layers: Vec<StateLayer>,
+}
+
+impl StateLayers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a layer with the default priority of `0`, created every time the state is entered.
+    pub fn layer<T: Layer>(self, create: impl Fn() -> T + Send + 'static) -> Self {
+        self.layer_with_priority(0, create)
+    }
+
+    pub fn layer_with_priority<T: Layer>(
+        self,
+        priority: i32,
+        create: impl Fn() -> T + Send + 'static,
+    ) -> Self {
+        self.push::<T>(priority, LayerSwapType::Synchronous, create)
+    }
+
+    /// Adds a layer entered once its asynchronous initialization completes,
+    /// see [`Layer::attach_async`].
+    pub fn layer_deferred<T: Layer>(self, create: impl Fn() -> T + Send + 'static) -> Self {
+        self.push::<T>(0, LayerSwapType::Deferred, create)
+    }
+
+    fn push<T: Layer>(
+        mut self,
+        priority: i32,
+        swap_type: LayerSwapType,
+        create: impl Fn() -> T + Send + 'static,
+    ) -> Self {
+        self.layers.push(StateLayer {
+            layer_type: TypeId::of::<T>(),
+            priority,
+            swap_type,
+            create: Box::new(move || Box::new(create())),
+        });
+        self
+    }
+}
+
+/// A transition of the state stack, waiting for its layers to be attached or detached.
+#[derive(Copy, Clone, Debug)]
+enum Transition<S> {
+    Enter(S),
+    Exit(S),
+}
+
+struct StateMachineInner<S> {
+    states: HashMap<S, StateLayers>,
+    stack: Vec<S>,
+    pending: Vec<Transition<S>>,
+}
+
+/// A system managing a stack of game states, see the [module documentation](self).
+///
+/// Provided by the [`GameStateLayer`], which applies the transitions.
+pub struct GameStateMachine<S: GameStateId>(Arc<Mutex<StateMachineInner<S>>>);
+
+impl<S: GameStateId> Clone for GameStateMachine<S> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<S: GameStateId> Default for GameStateMachine<S> {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(StateMachineInner {
+            states: HashMap::new(),
+            stack: Vec::new(),
+            pending: Vec::new(),
+        })))
+    }
+}
+
+impl<S: GameStateId> GameStateMachine<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the layers of a state, replacing the previous ones.
+    pub fn with_state(self, state: S, layers: StateLayers) -> Self {
+        self.0.lock().unwrap().states.insert(state, layers);
+        self
+    }
+
+    /// *Returns the state on top of the state stack, if any.*
+    pub fn current(&self) -> Option<S> {
+        self.0.lock().unwrap().stack.last().copied()
+    }
+
+    /// *Returns the state stack, bottom to top.*
+    pub fn stack(&self) -> Vec<S> {
+        self.0.lock().unwrap().stack.clone()
+    }
+
+    /// Pushes a state on top of the state stack, keeping the states below it.
+    ///
+    /// # Panics
+    /// Panics if the state was not registered with [`Self::with_state`].
+    pub fn push(&self, state: S) {
+        let mut inner = self.0.lock().unwrap();
+        assert!(
+            inner.states.contains_key(&state),
+            "The state {:?} was not registered with the state machine",
+            state
+        );
+
+        inner.stack.push(state);
+        inner.pending.push(Transition::Enter(state));
+    }
+
+    /// Pops the state on top of the state stack.
+    ///
+    /// *Returns the popped state, `None` if the stack was empty.*
+    pub fn pop(&self) -> Option<S> {
+        let mut inner = self.0.lock().unwrap();
+        let state = inner.stack.pop()?;
+        inner.pending.push(Transition::Exit(state));
+        Some(state)
+    }
+
+    /// Replaces the state on top of the state stack, or pushes it if the stack is empty.
+    ///
+    /// # Panics
+    /// Panics if the state was not registered with [`Self::with_state`].
+    pub fn replace(&self, state: S) {
+        self.pop();
+        self.push(state);
+    }
+
+    /// Converts the pending transitions to layer commands, in the order they were requested.
+    fn apply(&self, commands: &mut LayerCommands) {
+        let mut inner = self.0.lock().unwrap();
+        let StateMachineInner {
+            states, pending, ..
+        } = &mut *inner;
+
+        for transition in pending.drain(..) {
+            match transition {
+                Transition::Enter(state) => {
+                    for layer in &states[&state].layers {
+                        commands.push(LayerCommand::Attach {
+                            layer: (layer.create)(),
+                            priority: layer.priority,
+                            swap_type: layer.swap_type,
+                        });
+                    }
+                }
+                Transition::Exit(state) => {
+                    for layer in &states[&state].layers {
+                        commands.push(LayerCommand::Detach(layer.layer_type));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<S: GameStateId> System for GameStateMachine<S> {}
+
+/// A layer providing the [`GameStateMachine`] system to all layers above it,
+/// applying the transitions requested during each traversal.
+pub struct GameStateLayer<S: GameStateId>(pub GameStateMachine<S>);
+
+impl<S: GameStateId> Layer for GameStateLayer<S> {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_leave(&mut self, systems: &mut dyn LayerSystemProvider) {
+        let commands = systems
+            .query_mut::<LayerCommands>()
+            .expect("The layer commands are provided by the layer manager");
+        self.0.apply(commands);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::application::layer::pluto::PlutoLayerManager;
+    use crate::application::layer::{Layer, LayerManager, LayerSwapType};
+    use crate::application::state::{GameStateLayer, GameStateMachine, StateLayers};
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+    enum State {
+        Menu,
+        InGame,
+        Paused,
+    }
+
+    struct MenuLayer;
+    struct GameLayer;
+    struct PauseLayer;
+
+    impl Layer for MenuLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+    }
+
+    impl Layer for GameLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+    }
+
+    impl Layer for PauseLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            None
+        }
+    }
+
+    /// *Returns the short names of the layers above the state layer.*
+    fn layer_names(layer_manager: &PlutoLayerManager) -> Vec<&'static str> {
+        layer_manager
+            .get_layer_order()
+            .into_iter()
+            .skip(1)
+            .map(|name| name.rsplit("::").next().unwrap())
+            .collect()
+    }
+
+    /// The menu is replaced by the game, which is then paused and resumed.
+    /// The layers of the paused game should stay attached below the pause layer.
+    #[test]
+    fn test_transitions() {
+        let machine = GameStateMachine::new()
+            .with_state(State::Menu, StateLayers::new().layer(|| MenuLayer))
+            .with_state(State::InGame, StateLayers::new().layer(|| GameLayer))
+            .with_state(
+                State::Paused,
+                StateLayers::new().layer_with_priority(10, || PauseLayer),
+            );
+        machine.push(State::Menu);
+
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer_with_priority(Box::new(GameStateLayer(machine.clone())), -1);
+        layer_manager.run();
+        assert_eq!(layer_names(&layer_manager), ["MenuLayer"]);
+
+        machine.replace(State::InGame);
+        layer_manager.run();
+        assert_eq!(layer_names(&layer_manager), ["GameLayer"]);
+
+        machine.push(State::Paused);
+        layer_manager.run();
+        assert_eq!(machine.stack(), [State::InGame, State::Paused]);
+        assert_eq!(layer_names(&layer_manager), ["GameLayer", "PauseLayer"]);
+
+        assert_eq!(machine.pop(), Some(State::Paused));
+        layer_manager.run();
+        assert_eq!(machine.current(), Some(State::InGame));
+        assert_eq!(layer_names(&layer_manager), ["GameLayer"]);
+    }
+}
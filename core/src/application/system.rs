@@ -22,6 +22,7 @@
  * SOFTWARE.
  */
 
+use pluto_io::jobs::JobPool;
 use std::any::Any;
 
 /// A utility trait for systems to support dynamic typing.
@@ -33,10 +34,7 @@ pub trait SystemDyn: 'static {
     fn as_system_mut(&mut self) -> &mut dyn System;
 }
 
-impl<T: SystemDyn + System> SystemDyn for T
-where
-    T: Sized,
-{
+impl<T: System> SystemDyn for T {
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -52,3 +50,6 @@ where
 
 /// The base trait for all systems.
 pub trait System: SystemDyn {}
+
+/// *Provided to all layers by the layer manager.*
+impl System for JobPool {}
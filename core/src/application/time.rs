@@ -0,0 +1,294 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::application::system::System;
+use instant::Instant;
+use std::time::Duration;
+
+/// The time of the current iteration of the layer manager.
+///
+/// *Provided to all layers by the layer manager, which advances it once per iteration.*
+#[derive(Clone, Debug)]
+pub struct Time {
+    last_update: Option<Instant>,
+    delta: Duration,
+    elapsed: Duration,
+    frame: u64,
+    /// The longest delta of a single iteration, e.g. after the application was suspended.
+    max_delta: Duration,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self {
+            last_update: None,
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            frame: 0,
+            max_delta: Duration::from_millis(250),
+        }
+    }
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advances the time by the time passed since the last update.
+    ///
+    /// *The first update advances by zero.*
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let delta = self
+            .last_update
+            .map_or(Duration::ZERO, |last_update| now - last_update);
+
+        self.last_update = Some(now);
+        self.advance(delta);
+    }
+
+    /// Advances the time by the given delta, clamped to the maximum delta.
+    pub fn advance(&mut self, delta: Duration) {
+        self.delta = delta.min(self.max_delta);
+        self.elapsed += self.delta;
+        self.frame += 1;
+    }
+
+    /// *Returns the time passed between the previous iteration and this one.*
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// *Returns the sum of all deltas.*
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// *Returns the index of the current iteration, starting at `1`.*
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn set_max_delta(&mut self, max_delta: Duration) {
+        self.max_delta = max_delta;
+    }
+}
+
+impl System for Time {}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TimerMode {
+    /// The timer finishes once and stays finished until reset.
+    Once,
+    /// The timer restarts every time it finishes.
+    Repeating,
+}
+
+/// A timer counting down a duration, advanced by layers every iteration.
+#[derive(Clone, Debug)]
+pub struct Timer {
+    duration: Duration,
+    elapsed: Duration,
+    mode: TimerMode,
+    paused: bool,
+}
+
+impl Timer {
+    pub fn new(duration: Duration, mode: TimerMode) -> Self {
+        Self {
+            duration,
+            elapsed: Duration::ZERO,
+            mode,
+            paused: false,
+        }
+    }
+
+    pub fn once(duration: Duration) -> Self {
+        Self::new(duration, TimerMode::Once)
+    }
+
+    pub fn repeating(duration: Duration) -> Self {
+        Self::new(duration, TimerMode::Repeating)
+    }
+
+    /// Advances the timer by the delta of the current iteration.
+    ///
+    /// *Returns how many times the timer finished during this iteration.*
+    pub fn update(&mut self, time: &Time) -> u32 {
+        self.tick(time.delta())
+    }
+
+    /// Advances the timer by the given delta.
+    ///
+    /// *Returns how many times the timer finished, a repeating timer may finish
+    /// multiple times if the delta is longer than its duration.*
+    pub fn tick(&mut self, delta: Duration) -> u32 {
+        if self.paused || self.is_finished() {
+            return 0;
+        }
+
+        self.elapsed += delta;
+
+        if self.elapsed < self.duration {
+            return 0;
+        }
+
+        match self.mode {
+            TimerMode::Once => {
+                self.elapsed = self.duration;
+                1
+            }
+            // A zero duration would finish infinitely often
+            TimerMode::Repeating if self.duration.is_zero() => {
+                self.elapsed = Duration::ZERO;
+                1
+            }
+            TimerMode::Repeating => {
+                let times = self.elapsed.as_nanos() / self.duration.as_nanos();
+                self.elapsed -= self.duration * times as u32;
+                times as u32
+            }
+        }
+    }
+
+    /// *Returns `true` if a one-shot timer has finished, repeating timers never stay finished.*
+    pub fn is_finished(&self) -> bool {
+        self.mode == TimerMode::Once && self.elapsed >= self.duration
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.elapsed)
+    }
+
+    /// *Returns the elapsed part of the duration, from `0.0` to `1.0`.*
+    pub fn fraction(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+
+        self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+/// Measures the time passed while it is not paused.
+#[derive(Clone, Debug, Default)]
+pub struct Stopwatch {
+    elapsed: Duration,
+    paused: bool,
+}
+
+impl Stopwatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advances the stopwatch by the delta of the current iteration.
+    pub fn update(&mut self, time: &Time) {
+        self.tick(time.delta());
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        if !self.paused {
+            self.elapsed += delta;
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::application::time::{Time, Timer};
+    use std::time::Duration;
+
+    /// The time is advanced by a regular and a very long delta.
+    /// The long delta should be clamped and the frames counted.
+    #[test]
+    fn test_time_advance() {
+        let mut time = Time::new();
+        time.advance(Duration::from_millis(16));
+        time.advance(Duration::from_secs(10));
+
+        assert_eq!(time.delta(), Duration::from_millis(250));
+        assert_eq!(time.elapsed(), Duration::from_millis(266));
+        assert_eq!(time.frame(), 2);
+    }
+
+    /// A one-shot and a repeating timer are ticked past their duration.
+    /// The one-shot timer should finish once, the repeating one for every duration passed.
+    #[test]
+    fn test_timers() {
+        let mut once = Timer::once(Duration::from_millis(100));
+        assert_eq!(once.tick(Duration::from_millis(60)), 0);
+        assert_eq!(once.tick(Duration::from_millis(60)), 1);
+        assert!(once.is_finished());
+        assert_eq!(once.tick(Duration::from_millis(200)), 0);
+
+        let mut repeating = Timer::repeating(Duration::from_millis(100));
+        assert_eq!(repeating.tick(Duration::from_millis(250)), 2);
+        assert_eq!(repeating.remaining(), Duration::from_millis(50));
+
+        repeating.pause();
+        assert_eq!(repeating.tick(Duration::from_millis(100)), 0);
+    }
+}
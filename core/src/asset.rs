@@ -0,0 +1,91 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::application::layer::{Layer, LayerDependencyDeclaration, LayerSwapType};
+use crate::application::system::System;
+use pluto_io::asset::AssetFailureLog;
+use pluto_io::pack::{PackError, PackReader};
+use std::io;
+use std::io::Cursor;
+use std::sync::RwLock;
+
+/// The default directory assets are read from on platforms with a file system,
+/// relative to the working directory.
+pub const ASSET_DIRECTORY: &str = "assets";
+
+/// The directory assets are read from, `None` for the [`ASSET_DIRECTORY`].
+static ASSET_ROOT: RwLock<Option<String>> = RwLock::new(None);
+
+/// Sets the directory assets are read from, see [`EngineConfig::asset_root`].
+///
+/// [`EngineConfig::asset_root`]: crate::runtime::config::EngineConfig::asset_root
+pub fn set_asset_root(root: impl Into<String>) {
+    *ASSET_ROOT.write().unwrap() = Some(root.into());
+}
+
+/// *Returns the directory assets are read from.*
+pub fn get_asset_root() -> String {
+    ASSET_ROOT
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| ASSET_DIRECTORY.to_owned())
+}
+
+/// Reads an asset by its path relative to the asset root, see [`get_asset_root`],
+/// on Android from the assets packaged in the APK instead.
+pub fn read_asset(path: &str) -> io::Result<Vec<u8>> {
+    #[cfg(target_os = "android")]
+    return crate::runtime::android::read_apk_asset(path);
+
+    #[cfg(not(target_os = "android"))]
+    std::fs::read(std::path::Path::new(&get_asset_root()).join(path))
+}
+
+/// Reads a UTF-8 text asset, see [`read_asset`].
+pub fn read_asset_to_string(path: &str) -> io::Result<String> {
+    String::from_utf8(read_asset(path)?)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Reads an asset pack into memory, see [`read_asset`].
+pub fn open_asset_pack(path: &str) -> Result<PackReader<Cursor<Vec<u8>>>, PackError> {
+    PackReader::from_bytes(read_asset(path)?)
+}
+
+impl System for AssetFailureLog {}
+
+/// A layer providing the session-wide [`AssetFailureLog`] to all layers above it,
+/// so asset managers can record failed imports and debug tools can list them.
+pub struct AssetFailureLogLayer(pub AssetFailureLog);
+
+impl Layer for AssetFailureLogLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+}
@@ -0,0 +1,105 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! The audio system, available with the `pe_audio` feature.
+
+use crate::application::layer::{Layer, LayerDependencyDeclaration, LayerSwapType};
+use crate::application::system::System;
+use pluto_engine_audio::mixer::{Mixer, MixerStats, PlaybackParams, SharedMixer, VoiceId};
+use pluto_engine_audio::sound::Sound;
+use pluto_engine_audio::stream::SoundStream;
+
+/// A system for playing sounds and streaming music.
+///
+/// Provided by the [`AudioLayer`], the sounds are mixed by a shared [`Mixer`]
+/// an audio device pulls its output from, see [`pluto_engine_audio::device::AudioHost`].
+#[derive(Clone)]
+pub struct Audio {
+    mixer: SharedMixer,
+}
+
+impl Audio {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            mixer: Mixer::new(sample_rate, channels).shared(),
+        }
+    }
+
+    /// *Returns the mixer to open an audio device with.*
+    pub fn get_mixer(&self) -> SharedMixer {
+        self.mixer.clone()
+    }
+
+    pub fn play(&self, sound: &Sound, params: PlaybackParams) -> VoiceId {
+        self.mixer.lock().unwrap().play_sound(sound, params)
+    }
+
+    /// Plays a stream, such as music decoded while playing.
+    pub fn play_stream(&self, stream: Box<dyn SoundStream>, params: PlaybackParams) -> VoiceId {
+        self.mixer.lock().unwrap().play(stream, params)
+    }
+
+    /// *Returns `false` if the voice has already finished.*
+    pub fn set_params(&self, voice: VoiceId, params: PlaybackParams) -> bool {
+        self.mixer.lock().unwrap().set_params(voice, params)
+    }
+
+    /// *Returns `false` if the voice has already finished.*
+    pub fn stop(&self, voice: VoiceId) -> bool {
+        self.mixer.lock().unwrap().stop(voice)
+    }
+
+    pub fn is_playing(&self, voice: VoiceId) -> bool {
+        self.mixer.lock().unwrap().is_playing(voice)
+    }
+
+    pub fn set_master_volume(&self, volume: f32) {
+        self.mixer.lock().unwrap().set_master_volume(volume);
+    }
+
+    /// Limits the number of voices mixed at once, see [`Mixer::set_max_voices`].
+    pub fn set_max_voices(&self, max_voices: usize) {
+        self.mixer.lock().unwrap().set_max_voices(max_voices);
+    }
+
+    /// *Returns the voice counts of the last mixed buffer, for example for a debug overlay.*
+    pub fn stats(&self) -> MixerStats {
+        self.mixer.lock().unwrap().stats()
+    }
+}
+
+impl System for Audio {}
+
+/// A layer providing the [`Audio`] system to all layers above it.
+pub struct AudioLayer(pub Audio);
+
+impl Layer for AudioLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+}
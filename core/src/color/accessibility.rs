@@ -0,0 +1,243 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Tools for validating the accessibility of colors.
+
+use crate::color::{linear_to_srgb, srgb_to_linear, RGBA};
+use pluto_engine_display::pluto_engine_render::post_process::PostProcessPass;
+
+/// A type of dichromatic color vision deficiency.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ColorBlindness {
+    /// Missing long-wavelength (red) cones.
+    Protanopia,
+    /// Missing medium-wavelength (green) cones.
+    Deuteranopia,
+    /// Missing short-wavelength (blue) cones.
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    pub const ALL: [ColorBlindness; 3] = [Self::Protanopia, Self::Deuteranopia, Self::Tritanopia];
+
+    /// *Returns the row-major matrix simulating this deficiency in linear RGB,
+    /// as published by Machado et al. (2009) for full severity.*
+    pub fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Self::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Self::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Self::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    /// *Returns how an sRGB color appears with this deficiency.*
+    pub fn simulate(self, color: RGBA) -> RGBA {
+        let linear = [color.r, color.g, color.b].map(srgb_to_linear);
+        let [r, g, b] = self.matrix().map(|row| {
+            let value = row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2];
+            linear_to_srgb(value.clamp(0.0, 1.0))
+        });
+
+        RGBA {
+            r,
+            g,
+            b,
+            a: color.a,
+        }
+    }
+
+    /// *Returns a pass simulating this deficiency on the whole frame.*
+    ///
+    /// The matrix applies to linear colors, so the pass should run before gamma correction.
+    pub fn post_process_pass(self) -> PostProcessPass {
+        PostProcessPass::color_transform(self.matrix())
+    }
+}
+
+/// *Returns the relative luminance of an sRGB color as defined by WCAG 2.*
+pub fn relative_luminance(color: RGBA) -> f32 {
+    0.2126 * srgb_to_linear(color.r)
+        + 0.7152 * srgb_to_linear(color.g)
+        + 0.0722 * srgb_to_linear(color.b)
+}
+
+/// *Returns the WCAG 2 contrast ratio of two colors, from 1 to 21.*
+pub fn contrast_ratio(a: RGBA, b: RGBA) -> f32 {
+    let a = relative_luminance(a);
+    let b = relative_luminance(b);
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// The WCAG 2 conformance level of text contrast.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum ContrastLevel {
+    Fail,
+    AA,
+    AAA,
+}
+
+impl ContrastLevel {
+    /// *Returns the level text of the given colors reaches, large text has lower requirements.*
+    pub fn of_text(foreground: RGBA, background: RGBA, large_text: bool) -> Self {
+        let ratio = contrast_ratio(foreground, background);
+        let (aa, aaa) = if large_text { (3.0, 4.5) } else { (4.5, 7.0) };
+
+        if ratio >= aaa {
+            Self::AAA
+        } else if ratio >= aa {
+            Self::AA
+        } else {
+            Self::Fail
+        }
+    }
+}
+
+/// Collects the colors of UI elements and reports those with insufficient contrast.
+#[derive(Clone, Debug)]
+pub struct ContrastChecker {
+    required: ContrastLevel,
+    failures: Vec<ContrastFailure>,
+}
+
+/// A UI element not reaching the required contrast.
+#[derive(Clone, Debug)]
+pub struct ContrastFailure {
+    pub label: String,
+    pub ratio: f32,
+    pub level: ContrastLevel,
+}
+
+impl ContrastChecker {
+    pub fn new(required: ContrastLevel) -> Self {
+        Self {
+            required,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Checks an element, including how it appears with each type of color blindness.
+    ///
+    /// *Returns `false` if the element does not reach the required level.*
+    pub fn check(
+        &mut self,
+        label: &str,
+        foreground: RGBA,
+        background: RGBA,
+        large_text: bool,
+    ) -> bool {
+        let simulated = ColorBlindness::ALL.map(|deficiency| {
+            (
+                deficiency.simulate(foreground),
+                deficiency.simulate(background),
+            )
+        });
+
+        let (foreground, background) = std::iter::once((foreground, background))
+            .chain(simulated)
+            .min_by(|(af, ab), (bf, bb)| {
+                contrast_ratio(*af, *ab).total_cmp(&contrast_ratio(*bf, *bb))
+            })
+            .unwrap();
+
+        let level = ContrastLevel::of_text(foreground, background, large_text);
+        if level >= self.required {
+            return true;
+        }
+
+        self.failures.push(ContrastFailure {
+            label: label.to_owned(),
+            ratio: contrast_ratio(foreground, background),
+            level,
+        });
+
+        false
+    }
+
+    pub fn get_failures(&self) -> &[ContrastFailure] {
+        &self.failures
+    }
+
+    pub fn clear(&mut self) {
+        self.failures.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::accessibility::{
+        contrast_ratio, ColorBlindness, ContrastChecker, ContrastLevel,
+    };
+    use crate::color::{BLACK, RED, RGBA, WHITE};
+
+    /// Black on white should have the maximum contrast, gray should look the same
+    /// with any color blindness.
+    #[test]
+    fn test_contrast_and_simulation() {
+        assert!((contrast_ratio(BLACK, WHITE) - 21.0).abs() < 1e-3);
+
+        let gray = RGBA {
+            r: 0.5,
+            g: 0.5,
+            b: 0.5,
+            a: 1.0,
+        };
+
+        for deficiency in ColorBlindness::ALL {
+            let simulated = deficiency.simulate(gray);
+            assert!((simulated.r - 0.5).abs() < 0.01);
+            assert!((simulated.g - 0.5).abs() < 0.01);
+            assert!((simulated.b - 0.5).abs() < 0.01);
+        }
+    }
+
+    /// Red text on a green background is checked, which is hard to read with protanopia.
+    /// Black on white should pass.
+    #[test]
+    fn test_contrast_checker() {
+        let green = RGBA {
+            r: 0.0,
+            g: 0.6,
+            b: 0.0,
+            a: 1.0,
+        };
+
+        let mut checker = ContrastChecker::new(ContrastLevel::AA);
+        assert!(checker.check("title", BLACK, WHITE, false));
+        assert!(!checker.check("warning", RED, green, true));
+        assert_eq!(checker.get_failures().len(), 1);
+        assert_eq!(checker.get_failures()[0].label, "warning");
+    }
+}
@@ -22,7 +22,10 @@
  * SOFTWARE.
  */
 
-use cgmath::Vector4;
+use crate::math::Vec4;
+use pluto_engine_display::pluto_engine_render::material::MaterialParam;
+
+pub mod accessibility;
 
 pub mod platform {
     cfg_if::cfg_if! {
@@ -32,11 +35,27 @@ pub mod platform {
     }
 }
 
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub(crate) fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct RGBAu8(pub u8, pub u8, pub u8, pub u8);
 
-pub trait Color: From<RGBAu8> {
+pub trait Color: From<RGBAu8> + Copy {
     fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
         Self::from(RGBAu8(r, g, b, a))
     }
@@ -45,11 +64,38 @@ pub trait Color: From<RGBAu8> {
         Self::from_rgba(r, g, b, 255)
     }
 
+    /// *Returns `self` for a ratio of `0`, `other` for a ratio of `1`
+    /// and a mix of both in between.*
     fn lerp(self, other: Self, ratio: f32) -> Self;
+
+    /// *Returns `steps` colors evenly spaced from `self` to `other`, both included.*
+    fn gradient(self, other: Self, steps: usize) -> Vec<Self> {
+        match steps {
+            0 => Vec::new(),
+            1 => vec![self],
+            _ => (0..steps)
+                .map(|i| self.lerp(other, i as f32 / (steps - 1) as f32))
+                .collect(),
+        }
+    }
+}
+
+/// *Returns a table of colors from `0xRRGGBB` hex codes, usable in constants.*
+pub const fn palette<const N: usize>(hex: [u32; N]) -> [RGBA; N] {
+    let mut colors = [BLACK; N];
+    let mut i = 0;
+
+    while i < N {
+        colors[i] = RGBA::from_hex(hex[i]);
+        i += 1;
+    }
+
+    colors
 }
 
+/// An sRGB color with straight alpha.
 #[repr(C)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct RGBA {
     pub r: f32,
     pub g: f32,
@@ -57,18 +103,25 @@ pub struct RGBA {
     pub a: f32,
 }
 
-impl From<Vector4<f32>> for RGBA {
-    fn from(vec: Vector4<f32>) -> Self {
+impl From<Vec4> for RGBA {
+    fn from(vec: Vec4) -> Self {
         unsafe { std::mem::transmute(vec) }
     }
 }
 
-impl From<RGBA> for Vector4<f32> {
+impl From<RGBA> for Vec4 {
     fn from(rgba: RGBA) -> Self {
         unsafe { std::mem::transmute(rgba) }
     }
 }
 
+/// Colors are passed to materials as `vec4<f32>` parameters, without conversion to linear space.
+impl From<RGBA> for MaterialParam {
+    fn from(rgba: RGBA) -> Self {
+        MaterialParam::Vec4([rgba.r, rgba.g, rgba.b, rgba.a])
+    }
+}
+
 impl From<RGBAu8> for RGBA {
     fn from(rgba: RGBAu8) -> Self {
         Self {
@@ -80,19 +133,48 @@ impl From<RGBAu8> for RGBA {
     }
 }
 
+impl RGBA {
+    /// *Returns an opaque color from a `0xRRGGBB` hex code.*
+    pub const fn from_hex(hex: u32) -> Self {
+        Self {
+            r: ((hex >> 16) & 0xFF) as f32 / 255.0,
+            g: ((hex >> 8) & 0xFF) as f32 / 255.0,
+            b: (hex & 0xFF) as f32 / 255.0,
+            a: 1.0,
+        }
+    }
+
+    /// Interpolates the colors in linear space,
+    /// avoiding the dark midpoints of [`Color::lerp`] between saturated colors.
+    pub fn lerp_linear(self, other: Self, ratio: f32) -> Self {
+        let mix = |a: f32, b: f32| {
+            linear_to_srgb(srgb_to_linear(a) + (srgb_to_linear(b) - srgb_to_linear(a)) * ratio)
+        };
+
+        RGBA {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: self.a + (other.a - self.a) * ratio,
+        }
+    }
+}
+
 impl Color for RGBA {
+    /// Interpolates the sRGB components directly, see [`RGBA::lerp_linear`].
     fn lerp(self, other: Self, ratio: f32) -> Self {
         RGBA {
-            r: self.r * ratio + other.r * (1.0 - ratio),
-            g: self.g * ratio + other.g * (1.0 - ratio),
-            b: self.b * ratio + other.b * (1.0 - ratio),
-            a: self.a * ratio + other.a * (1.0 - ratio),
+            r: self.r + (other.r - self.r) * ratio,
+            g: self.g + (other.g - self.g) * ratio,
+            b: self.b + (other.b - self.b) * ratio,
+            a: self.a + (other.a - self.a) * ratio,
         }
     }
 }
 
-#[derive(Copy, Clone)]
-struct HSBA {
+/// A color defined by its hue in degrees, saturation, brightness and alpha.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HSBA {
     pub h: f32,
     pub s: f32,
     pub b: f32,
@@ -168,8 +250,15 @@ impl From<RGBA> for HSBA {
         let brightness = rgba.r.max(rgba.g).max(rgba.b);
         let min = rgba.r.min(rgba.g).min(rgba.b);
         let chroma = brightness - min;
-        let saturation = chroma / brightness;
-        let hue = if brightness == rgba.r {
+        let saturation = if brightness > 0.0 {
+            chroma / brightness
+        } else {
+            0.0
+        };
+        // Grays have no hue
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if brightness == rgba.r {
             if rgba.g < rgba.b {
                 (rgba.g - rgba.b) / chroma + 6.0
             } else {
@@ -197,12 +286,24 @@ impl From<RGBAu8> for HSBA {
 }
 
 impl Color for HSBA {
+    /// Interpolates the hue along the shorter arc of the hue wheel,
+    /// so going from 350° to 10° passes through 0°.
+    ///
+    /// *The hue of a gray color is ignored.*
     fn lerp(self, other: Self, ratio: f32) -> Self {
+        let from = if self.s == 0.0 { other.h } else { self.h };
+        let to = if other.s == 0.0 { from } else { other.h };
+
+        let mut delta = (to - from).rem_euclid(360.0);
+        if delta > 180.0 {
+            delta -= 360.0;
+        }
+
         HSBA {
-            h: self.h * ratio + other.h * (1.0 - ratio),
-            s: self.s * ratio + other.s * (1.0 - ratio),
-            b: self.b * ratio + other.b * (1.0 - ratio),
-            a: self.a * ratio + other.a * (1.0 - ratio),
+            h: (from + delta * ratio).rem_euclid(360.0),
+            s: self.s + (other.s - self.s) * ratio,
+            b: self.b + (other.b - self.b) * ratio,
+            a: self.a + (other.a - self.a) * ratio,
         }
     }
 }
@@ -230,7 +331,7 @@ pub const RED: RGBA = RGBA {
 
 pub const GREEN: RGBA = RGBA {
     r: 0.0,
-    g: 0.0,
+    g: 1.0,
     b: 0.0,
     a: 1.0,
 };
@@ -248,3 +349,81 @@ pub const YELLOW: RGBA = RGBA {
     b: 0.0,
     a: 1.0,
 };
+
+#[cfg(test)]
+mod test {
+    use crate::color::{palette, Color, BLUE, GREEN, HSBA, RED, RGBA, WHITE, YELLOW};
+
+    fn assert_close(a: RGBA, b: RGBA) {
+        let close = |x: f32, y: f32| (x - y).abs() < 1e-4;
+        assert!(
+            close(a.r, b.r) && close(a.g, b.g) && close(a.b, b.b) && close(a.a, b.a),
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    /// Primary, secondary, gray and arbitrary colors are converted to HSBA and back.
+    /// The colors should be unchanged and grays should have no hue.
+    #[test]
+    fn test_hsba_round_trip() {
+        let gray = RGBA::from_hex(0x808080);
+        let colors = [
+            RED,
+            GREEN,
+            BLUE,
+            YELLOW,
+            WHITE,
+            gray,
+            RGBA::from_hex(0x3E7CB1),
+        ];
+
+        for color in colors {
+            assert_close(RGBA::from(HSBA::from(color)), color);
+        }
+
+        assert_eq!(HSBA::from(gray).h, 0.0);
+        assert_eq!(HSBA::from(GREEN).h, 120.0);
+    }
+
+    /// Hues on both sides of red and a gray are interpolated.
+    /// The hue should go the short way around and a gray should keep the other hue.
+    #[test]
+    fn test_hsba_lerp() {
+        let hsba = |h| HSBA {
+            h,
+            s: 1.0,
+            b: 1.0,
+            a: 1.0,
+        };
+
+        assert!((hsba(350.0).lerp(hsba(10.0), 0.5).h - 0.0).abs() < 1e-4);
+        assert!((hsba(10.0).lerp(hsba(350.0), 0.25).h - 5.0).abs() < 1e-4);
+
+        let gray = HSBA::from(RGBA::from_hex(0x808080));
+        assert_eq!(gray.lerp(hsba(200.0), 0.5).h, 200.0);
+    }
+
+    /// Red and green are interpolated in sRGB and linear space.
+    /// Both should start and end at the colors, the linear midpoint should be brighter.
+    #[test]
+    fn test_rgba_lerp() {
+        assert_close(RED.lerp(GREEN, 0.0), RED);
+        assert_close(RED.lerp(GREEN, 1.0), GREEN);
+        assert_close(RED.lerp_linear(GREEN, 1.0), GREEN);
+        assert!(RED.lerp_linear(GREEN, 0.5).r > RED.lerp(GREEN, 0.5).r);
+
+        assert_eq!(RED.gradient(BLUE, 3).len(), 3);
+        assert_close(RED.gradient(BLUE, 3)[2], BLUE);
+    }
+
+    /// A constant palette is generated from hex codes.
+    /// The colors should be opaque and match the named colors.
+    #[test]
+    fn test_palette() {
+        const PALETTE: [RGBA; 3] = palette([0xFF0000, 0x00FF00, 0x0000FF]);
+
+        assert_eq!(PALETTE, [RED, GREEN, BLUE]);
+    }
+}
@@ -0,0 +1,142 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use crate::input::keyboard::Keyboard;
+use log::info;
+use pluto_engine_display::pluto_engine_window::keyboard::Key;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// A system requesting captures of whole frames by a frame debugger such as RenderDoc.
+///
+/// The captures are triggered by the display, which provides the system through
+/// its `get_frame_capture` accessor.
+#[derive(Clone, Default)]
+pub struct FrameCapture {
+    requested: Arc<AtomicU32>,
+}
+
+impl FrameCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Captures the next rendered frame, including the work of the engine after the state renders.
+    ///
+    /// *Has no effect unless the application runs under a frame debugger.*
+    pub fn capture_next_frame(&self) {
+        self.capture_frames(1);
+    }
+
+    /// Captures each of the next `count` frames separately.
+    pub fn capture_frames(&self, count: u32) {
+        self.requested.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if a capture was requested and not yet started by the display.
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::Relaxed) > 0
+    }
+
+    /// Takes one requested capture, to be started by the display for the frame being rendered.
+    pub fn take_request(&self) -> bool {
+        self.requested
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                count.checked_sub(1)
+            })
+            .is_ok()
+    }
+}
+
+impl System for FrameCapture {}
+
+/// A layer providing the [`FrameCapture`] system to all layers above it.
+///
+/// If a key is set, pressing it captures the next frame,
+/// which requires the [`Keyboard`] system to be provided by a layer below this one.
+pub struct FrameCaptureLayer {
+    pub frame_capture: FrameCapture,
+    pub capture_key: Option<Key>,
+}
+
+impl FrameCaptureLayer {
+    pub fn new(frame_capture: FrameCapture) -> Self {
+        Self {
+            frame_capture,
+            capture_key: None,
+        }
+    }
+
+    pub fn capture_key(mut self, key: Key) -> Self {
+        self.capture_key = Some(key);
+        self
+    }
+}
+
+impl Layer for FrameCaptureLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.frame_capture.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        let pressed = self.capture_key.is_some_and(|key| {
+            systems
+                .query::<Keyboard>()
+                .is_some_and(|keyboard| keyboard.was_pressed(key))
+        });
+
+        if pressed {
+            info!("Capturing the next frame.");
+            self.frame_capture.capture_next_frame();
+        }
+
+        next.next(systems);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::debug::capture::FrameCapture;
+
+    /// Two frames are requested from a cloned system, then three frames are rendered.
+    /// Only the first two frames should be captured.
+    #[test]
+    fn test_frame_capture_requests() {
+        let frame_capture = FrameCapture::new();
+        frame_capture.clone().capture_frames(2);
+
+        assert!(frame_capture.is_requested());
+        assert!(frame_capture.take_request());
+        assert!(frame_capture.take_request());
+        assert!(!frame_capture.take_request());
+        assert!(!frame_capture.is_requested());
+    }
+}
@@ -0,0 +1,387 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use crate::color::RGBA;
+use crate::math::{orthographic, Aabb, InnerSpace, Mat4, Point2, Point3, Rect, SquareMatrix, Vec3};
+use pluto_engine_display::pluto_engine_window::window::LogicalSize;
+use std::f32::consts::TAU;
+use std::sync::{Arc, Mutex};
+
+/// The number of segments of the circles making up spheres.
+const CIRCLE_SEGMENTS: usize = 24;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DebugDrawVertex {
+    pub position: Point3,
+    pub color: RGBA,
+}
+
+/// A text label positioned in logical units from the top left corner of the screen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DebugLabel {
+    pub position: Point2,
+    pub text: String,
+    pub color: RGBA,
+}
+
+/// The primitives submitted during a frame, to be drawn by the display.
+pub struct DebugDrawFrame {
+    /// World space lines, every two vertices form a line.
+    pub lines: Vec<DebugDrawVertex>,
+    pub view_projection: Mat4,
+    /// Screen space lines in logical units, every two vertices form a line.
+    pub screen_lines: Vec<DebugDrawVertex>,
+    /// Transforms logical units into clip space, `+Y` pointing down.
+    pub screen_projection: Mat4,
+    pub labels: Vec<DebugLabel>,
+}
+
+struct DebugDrawState {
+    enabled: bool,
+    lines: Vec<DebugDrawVertex>,
+    screen_lines: Vec<DebugDrawVertex>,
+    labels: Vec<(Point3, DebugLabel)>,
+    screen_labels: Vec<DebugLabel>,
+    view_projection: Mat4,
+    screen_size: LogicalSize<f64>,
+    frame: Option<DebugDrawFrame>,
+}
+
+impl Default for DebugDrawState {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            lines: Vec::new(),
+            screen_lines: Vec::new(),
+            labels: Vec::new(),
+            screen_labels: Vec::new(),
+            view_projection: Mat4::identity(),
+            screen_size: LogicalSize::default(),
+            frame: None,
+        }
+    }
+}
+
+/// A system batching debug primitives submitted by any layer during a frame.
+///
+/// Provided to layers by the [`DebugDrawLayer`], which ends the frame once they have been entered,
+/// the display then draws the lines in a dedicated pass on top of the scene.
+/// Labels are painted by the debug UI if it is enabled and below the [`DebugDrawLayer`].
+#[derive(Clone, Default)]
+pub struct DebugDraw {
+    state: Arc<Mutex<DebugDrawState>>,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Disabling debug drawing drops all submitted primitives, enabled by default.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.state.lock().unwrap().enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().unwrap().enabled
+    }
+
+    /// Sets the camera the world space primitives are seen through, usually every frame.
+    pub fn set_view_projection(&self, view_projection: Mat4) {
+        self.state.lock().unwrap().view_projection = view_projection;
+    }
+
+    /// Sets the logical size of the screen, kept up to date by the display.
+    pub fn set_screen_size(&self, size: LogicalSize<f64>) {
+        self.state.lock().unwrap().screen_size = size;
+    }
+
+    fn push_lines(
+        &self,
+        screen: bool,
+        lines: impl IntoIterator<Item = (Point3, Point3)>,
+        color: RGBA,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        if !state.enabled {
+            return;
+        }
+
+        let vertices = if screen {
+            &mut state.screen_lines
+        } else {
+            &mut state.lines
+        };
+
+        for (a, b) in lines {
+            vertices.push(DebugDrawVertex { position: a, color });
+            vertices.push(DebugDrawVertex { position: b, color });
+        }
+    }
+
+    pub fn line(&self, a: Point3, b: Point3, color: RGBA) {
+        self.push_lines(false, [(a, b)], color);
+    }
+
+    /// Draws the edges of a box.
+    pub fn wire_box(&self, aabb: &Aabb, color: RGBA) {
+        let corners = aabb.corners();
+        let edges = [
+            (0, 1),
+            (2, 3),
+            (4, 5),
+            (6, 7),
+            (0, 2),
+            (1, 3),
+            (4, 6),
+            (5, 7),
+            (0, 4),
+            (1, 5),
+            (2, 6),
+            (3, 7),
+        ];
+
+        self.push_lines(false, edges.map(|(a, b)| (corners[a], corners[b])), color);
+    }
+
+    /// Draws a circle around an axis.
+    pub fn circle(&self, center: Point3, axis: Vec3, radius: f32, color: RGBA) {
+        let axis = axis.normalize();
+        let helper = if axis.x.abs() < 0.9 {
+            Vec3::unit_x()
+        } else {
+            Vec3::unit_y()
+        };
+        let u = axis.cross(helper).normalize() * radius;
+        let v = axis.cross(u);
+
+        let point = |i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            center + u * angle.cos() + v * angle.sin()
+        };
+
+        self.push_lines(
+            false,
+            (0..CIRCLE_SEGMENTS).map(|i| (point(i), point(i + 1))),
+            color,
+        );
+    }
+
+    /// Draws a sphere as three circles around the coordinate axes.
+    pub fn sphere(&self, center: Point3, radius: f32, color: RGBA) {
+        for axis in [Vec3::unit_x(), Vec3::unit_y(), Vec3::unit_z()] {
+            self.circle(center, axis, radius, color);
+        }
+    }
+
+    /// Draws a line in logical units from the top left corner of the screen.
+    pub fn line_2d(&self, a: Point2, b: Point2, color: RGBA) {
+        self.push_lines(
+            true,
+            [(Point3::new(a.x, a.y, 0.0), Point3::new(b.x, b.y, 0.0))],
+            color,
+        );
+    }
+
+    /// Draws the outline of a rectangle in logical units from the top left corner of the screen.
+    pub fn rect_2d(&self, rect: &Rect, color: RGBA) {
+        let corners = [
+            Point3::new(rect.min.x, rect.min.y, 0.0),
+            Point3::new(rect.max.x, rect.min.y, 0.0),
+            Point3::new(rect.max.x, rect.max.y, 0.0),
+            Point3::new(rect.min.x, rect.max.y, 0.0),
+        ];
+
+        self.push_lines(
+            true,
+            (0..4).map(|i| (corners[i], corners[(i + 1) % 4])),
+            color,
+        );
+    }
+
+    /// Draws a text label at a point in the world, hidden if the point is behind the camera.
+    pub fn label(&self, position: Point3, text: impl Into<String>, color: RGBA) {
+        let mut state = self.state.lock().unwrap();
+        if state.enabled {
+            let label = DebugLabel {
+                position: Point2::new(0.0, 0.0),
+                text: text.into(),
+                color,
+            };
+
+            state.labels.push((position, label));
+        }
+    }
+
+    /// Draws a text label in logical units from the top left corner of the screen.
+    pub fn label_2d(&self, position: Point2, text: impl Into<String>, color: RGBA) {
+        let mut state = self.state.lock().unwrap();
+        if state.enabled {
+            state.screen_labels.push(DebugLabel {
+                position,
+                text: text.into(),
+                color,
+            });
+        }
+    }
+
+    /// Collects the primitives submitted during this frame for the display,
+    /// replacing the previous frame if it was not drawn.
+    pub fn end_frame(&self) {
+        let mut state = self.state.lock().unwrap();
+        let view_projection = state.view_projection;
+        let size = state.screen_size;
+
+        let mut labels = std::mem::take(&mut state.screen_labels);
+        labels.extend(state.labels.drain(..).filter_map(|(position, mut label)| {
+            let clip = view_projection * position.to_homogeneous();
+            if clip.w <= 0.0 {
+                return None;
+            }
+
+            label.position = Point2::new(
+                (clip.x / clip.w + 1.0) / 2.0 * size.width as f32,
+                (1.0 - clip.y / clip.w) / 2.0 * size.height as f32,
+            );
+            Some(label)
+        }));
+
+        state.frame = Some(DebugDrawFrame {
+            lines: std::mem::take(&mut state.lines),
+            view_projection,
+            screen_lines: std::mem::take(&mut state.screen_lines),
+            screen_projection: orthographic(
+                0.0,
+                size.width as f32,
+                size.height as f32,
+                0.0,
+                -1.0,
+                1.0,
+            ),
+            labels,
+        });
+    }
+
+    /// *Returns the primitives of the last frame if they were not drawn yet.*
+    pub fn take_frame(&self) -> Option<DebugDrawFrame> {
+        self.state.lock().unwrap().frame.take()
+    }
+}
+
+impl System for DebugDraw {}
+
+/// A layer providing the [`DebugDraw`] system to all layers above it,
+/// ending the frame once they have been entered.
+pub struct DebugDrawLayer(pub DebugDraw);
+
+impl Layer for DebugDrawLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        next.next(systems);
+
+        self.0.end_frame();
+
+        #[cfg(feature = "pe_debug_ui")]
+        if let Some(debug_ui) = systems.query::<crate::debug::ui::DebugUi>() {
+            let state = self.0.state.lock().unwrap();
+            if let Some(frame) = &state.frame {
+                paint_labels(debug_ui, &frame.labels);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pe_debug_ui")]
+fn paint_labels(debug_ui: &crate::debug::ui::DebugUi, labels: &[DebugLabel]) {
+    let painter = debug_ui.get_context().layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("pluto_debug_draw"),
+    ));
+
+    for label in labels {
+        let to_u8 = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let color = label.color;
+
+        painter.text(
+            egui::pos2(label.position.x, label.position.y),
+            egui::Align2::LEFT_TOP,
+            &label.text,
+            egui::FontId::monospace(12.0),
+            egui::Color32::from_rgba_unmultiplied(
+                to_u8(color.r),
+                to_u8(color.g),
+                to_u8(color.b),
+                to_u8(color.a),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::WHITE;
+    use crate::debug::draw::DebugDraw;
+    use crate::math::{Aabb, Mat4, Point2, Point3, Rect, SquareMatrix};
+    use pluto_engine_display::pluto_engine_window::window::LogicalSize;
+
+    /// A box, a rectangle and a label at the origin are drawn, then a second frame ends.
+    /// The first frame should contain all primitives with the label in the middle of the screen,
+    /// the second one should be empty.
+    #[test]
+    fn test_debug_draw_frame() {
+        let debug_draw = DebugDraw::new();
+        debug_draw.set_view_projection(Mat4::identity());
+        debug_draw.set_screen_size(LogicalSize {
+            width: 800.0,
+            height: 600.0,
+        });
+
+        debug_draw.wire_box(
+            &Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0)),
+            WHITE,
+        );
+        debug_draw.rect_2d(&Rect::new(10.0, 10.0, 100.0, 50.0), WHITE);
+        debug_draw.label(Point3::new(0.0, 0.0, 0.0), "Origin", WHITE);
+        debug_draw.end_frame();
+
+        let frame = debug_draw.take_frame().unwrap();
+        assert_eq!(frame.lines.len(), 24);
+        assert_eq!(frame.screen_lines.len(), 8);
+        assert_eq!(frame.labels[0].position, Point2::new(400.0, 300.0));
+
+        debug_draw.end_frame();
+        let frame = debug_draw.take_frame().unwrap();
+        assert!(frame.lines.is_empty() && frame.screen_lines.is_empty() && frame.labels.is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use crate::input::keyboard::Keyboard;
+use log::{info, warn};
+use pluto_engine_display::pluto_engine_render::debug::{
+    DeviceRenderDebug, RenderDebugMode, RenderDebugSwitch,
+};
+use pluto_engine_display::pluto_engine_window::keyboard::Key;
+
+/// A system switching the debug render mode of the device at runtime.
+///
+/// The mode is applied by the pipelines themselves, so it affects every draw of the next frame.
+#[derive(Clone)]
+pub struct RenderDebug {
+    switch: RenderDebugSwitch,
+    supported: Vec<RenderDebugMode>,
+}
+
+impl RenderDebug {
+    pub fn new<'a, D: DeviceRenderDebug<'a>>(device: &D) -> Self {
+        Self::from_switch(
+            device.get_render_debug_switch(),
+            RenderDebugMode::ALL
+                .into_iter()
+                .filter(|mode| device.supports_render_debug_mode(*mode))
+                .collect(),
+        )
+    }
+
+    /// Controls a switch directly, only allowing the given modes besides the normal one.
+    pub fn from_switch(switch: RenderDebugSwitch, supported: Vec<RenderDebugMode>) -> Self {
+        Self { switch, supported }
+    }
+
+    pub fn get_mode(&self) -> RenderDebugMode {
+        self.switch.get()
+    }
+
+    /// *Returns `false` if the mode is not supported by the device, keeping the current mode.*
+    pub fn set_mode(&self, mode: RenderDebugMode) -> bool {
+        if !self.is_supported(mode) {
+            warn!("The {:?} render debug mode is not supported.", mode);
+            return false;
+        }
+
+        self.switch.set(mode);
+        true
+    }
+
+    pub fn is_supported(&self, mode: RenderDebugMode) -> bool {
+        mode == RenderDebugMode::Normal || self.supported.contains(&mode)
+    }
+
+    /// Switches to the next supported mode.
+    ///
+    /// *Returns the new mode.*
+    pub fn cycle(&self) -> RenderDebugMode {
+        let mut mode = self.get_mode().next();
+        while !self.is_supported(mode) {
+            mode = mode.next();
+        }
+
+        self.switch.set(mode);
+        mode
+    }
+}
+
+impl System for RenderDebug {}
+
+/// A layer providing the [`RenderDebug`] system to all layers above it.
+///
+/// If a key is set, pressing it cycles through the supported modes,
+/// which requires the [`Keyboard`] system to be provided by a layer below this one.
+pub struct RenderDebugLayer {
+    pub render_debug: RenderDebug,
+    pub cycle_key: Option<Key>,
+}
+
+impl RenderDebugLayer {
+    pub fn new(render_debug: RenderDebug) -> Self {
+        Self {
+            render_debug,
+            cycle_key: None,
+        }
+    }
+
+    pub fn cycle_key(mut self, key: Key) -> Self {
+        self.cycle_key = Some(key);
+        self
+    }
+}
+
+impl Layer for RenderDebugLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.render_debug.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        let pressed = self.cycle_key.is_some_and(|key| {
+            systems
+                .query::<Keyboard>()
+                .is_some_and(|keyboard| keyboard.was_pressed(key))
+        });
+
+        if pressed {
+            info!("Render debug mode: {:?}", self.render_debug.cycle());
+        }
+
+        next.next(systems);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::debug::render::RenderDebug;
+    use pluto_engine_display::pluto_engine_render::debug::{RenderDebugMode, RenderDebugSwitch};
+
+    /// The modes of a device without wireframe support are cycled and set.
+    /// The wireframe mode should be skipped and refused, leaving the mode unchanged.
+    #[test]
+    fn test_render_debug_cycle() {
+        let switch = RenderDebugSwitch::default();
+        let render_debug =
+            RenderDebug::from_switch(switch.clone(), vec![RenderDebugMode::Overdraw]);
+
+        assert_eq!(render_debug.cycle(), RenderDebugMode::Overdraw);
+        assert_eq!(switch.get(), RenderDebugMode::Overdraw);
+
+        assert!(!render_debug.set_mode(RenderDebugMode::Wireframe));
+        assert_eq!(render_debug.get_mode(), RenderDebugMode::Overdraw);
+
+        assert_eq!(render_debug.cycle(), RenderDebugMode::Normal);
+    }
+}
@@ -0,0 +1,305 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! An overlay showing the frame rate, a graph of frame times and draw statistics.
+
+use crate::application::layer::{Layer, LayerSwapType, LayerSystemManager, LayerWalker};
+use crate::color::{GREEN, RGBA, WHITE, YELLOW};
+use crate::debug::draw::DebugDraw;
+use crate::input::keyboard::Keyboard;
+use crate::math::{Point2, Rect};
+use crate::render::stats::{FrameStats, RenderStats};
+use pluto_engine_display::pluto_engine_window::keyboard::Key;
+use std::collections::VecDeque;
+
+/// The frame time of 60 frames per second in milliseconds, marked in the graph.
+const TARGET_FRAME_TIME: f32 = 1000.0 / 60.0;
+
+/// The spacing between lines of text in logical units.
+const LINE_HEIGHT: f32 = 14.0;
+
+/// Configuration of the [`StatsOverlayLayer`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StatsOverlay {
+    /// Toggles the overlay.
+    pub key: Key,
+    /// Whether the overlay is shown before the key is first pressed.
+    pub visible: bool,
+    /// The top left corner in logical units.
+    pub position: Point2,
+    /// The number of frames shown in the graph.
+    pub history: usize,
+}
+
+impl Default for StatsOverlay {
+    /// A hidden overlay in the top left corner, toggled with F3.
+    fn default() -> Self {
+        Self {
+            key: Key::F3,
+            visible: false,
+            position: Point2::new(8.0, 8.0),
+            history: 120,
+        }
+    }
+}
+
+impl StatsOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key(mut self, key: Key) -> Self {
+        self.key = key;
+        self
+    }
+
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    pub fn position(mut self, position: Point2) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn history(mut self, history: usize) -> Self {
+        self.history = history.max(2);
+        self
+    }
+}
+
+/// *Returns the lines of text shown for a frame.*
+pub fn stats_text(stats: &FrameStats) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "{:.0} FPS ({:.2} ms)",
+            stats.frames_per_second(),
+            stats.frame_time.as_secs_f64() * 1000.0
+        ),
+        format!(
+            "{} draw calls, {} triangles",
+            stats.draw_calls, stats.triangles
+        ),
+    ];
+
+    if !stats.gpu_passes.is_empty() {
+        lines.push(format!(
+            "GPU {:.2} ms",
+            stats.gpu_time().as_secs_f64() * 1000.0
+        ));
+    }
+
+    if let Some(slowest) = stats.layers.iter().max_by_key(|layer| layer.mean_time) {
+        let name = slowest.name.rsplit("::").next().unwrap_or(slowest.name);
+        lines.push(format!(
+            "Slowest layer {} ({:.2} ms)",
+            name,
+            slowest.mean_time.as_secs_f64() * 1000.0
+        ));
+    }
+
+    if stats.culling.tested > 0 {
+        lines.push(format!(
+            "{} visible, {} culled",
+            stats.culling.visible, stats.culling.culled
+        ));
+    }
+
+    lines
+}
+
+/// *Returns the frame time at the top of the graph, the longest frame
+/// but at least twice the target frame time.*
+fn graph_scale(frame_times: &[f32]) -> f32 {
+    frame_times
+        .iter()
+        .copied()
+        .fold(TARGET_FRAME_TIME * 2.0, f32::max)
+}
+
+/// *Returns the line segments of a graph of frame times in milliseconds, oldest first,
+/// filling the rectangle from the left edge.*
+pub fn frame_time_graph(
+    frame_times: &[f32],
+    capacity: usize,
+    rect: &Rect,
+) -> Vec<(Point2, Point2)> {
+    let scale = graph_scale(frame_times);
+    let step = rect.width() / capacity.saturating_sub(1).max(1) as f32;
+
+    let point = |(i, &frame_time): (usize, &f32)| {
+        Point2::new(
+            rect.min.x + i as f32 * step,
+            rect.max.y - frame_time.min(scale) / scale * rect.height(),
+        )
+    };
+
+    frame_times
+        .iter()
+        .enumerate()
+        .map(point)
+        .zip(frame_times.iter().enumerate().skip(1).map(point))
+        .collect()
+}
+
+/// A layer drawing the statistics of the last frame over the layers below it,
+/// shown and hidden with a key.
+///
+/// Requires the [`RenderStats`] and [`DebugDraw`] systems to be provided by layers below this one,
+/// and the [`Keyboard`] system for toggling. Text is painted by the debug UI.
+pub struct StatsOverlayLayer {
+    pub overlay: StatsOverlay,
+    frame_times: VecDeque<f32>,
+}
+
+impl StatsOverlayLayer {
+    pub fn new(overlay: StatsOverlay) -> Self {
+        Self {
+            overlay,
+            frame_times: VecDeque::with_capacity(overlay.history),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.overlay.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.overlay.visible = visible;
+    }
+
+    fn draw(&self, debug_draw: &DebugDraw, stats: &FrameStats) {
+        let position = self.overlay.position;
+        let text = stats_text(stats);
+
+        for (line, i) in text.iter().zip(0..) {
+            debug_draw.label_2d(
+                Point2::new(position.x, position.y + i as f32 * LINE_HEIGHT),
+                line.as_str(),
+                WHITE,
+            );
+        }
+
+        let graph = Rect::new(
+            position.x,
+            position.y + text.len() as f32 * LINE_HEIGHT + 4.0,
+            self.overlay.history as f32,
+            48.0,
+        );
+        debug_draw.rect_2d(&graph, RGBA { a: 0.5, ..WHITE });
+
+        let frame_times = self.frame_times.iter().copied().collect::<Vec<_>>();
+        let segments = frame_time_graph(&frame_times, self.overlay.history, &graph);
+
+        let target = graph.max.y - TARGET_FRAME_TIME / graph_scale(&frame_times) * graph.height();
+        debug_draw.line_2d(
+            Point2::new(graph.min.x, target),
+            Point2::new(graph.max.x, target),
+            RGBA { a: 0.5, ..YELLOW },
+        );
+
+        for ((a, b), frame_time) in segments.into_iter().zip(frame_times.iter().skip(1)) {
+            let color = if *frame_time > TARGET_FRAME_TIME {
+                YELLOW
+            } else {
+                GREEN
+            };
+
+            debug_draw.line_2d(a, b, color);
+        }
+    }
+}
+
+impl Layer for StatsOverlayLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        let pressed = systems
+            .query::<Keyboard>()
+            .is_some_and(|keyboard| keyboard.was_pressed(self.overlay.key));
+
+        if pressed {
+            self.overlay.visible = !self.overlay.visible;
+        }
+
+        if let Some(stats) = systems.query::<RenderStats>().map(RenderStats::latest) {
+            // Frame times are collected while hidden, so the graph is full when shown
+            if self.frame_times.len() >= self.overlay.history {
+                self.frame_times.pop_front();
+            }
+            self.frame_times
+                .push_back(stats.frame_time.as_secs_f32() * 1000.0);
+
+            if self.overlay.visible {
+                if let Some(debug_draw) = systems.query::<DebugDraw>() {
+                    self.draw(debug_draw, &stats);
+                }
+            }
+        }
+
+        next.next(systems);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::debug::stats_overlay::{frame_time_graph, stats_text};
+    use crate::math::{Point2, Rect};
+    use crate::render::stats::FrameStats;
+    use std::time::Duration;
+
+    /// The text of a 20 ms frame with two draw calls is created.
+    /// It should show the frame rate and draw calls, but no GPU time or culling.
+    #[test]
+    fn test_stats_text() {
+        let stats = FrameStats {
+            frame_time: Duration::from_millis(20),
+            draw_calls: 2,
+            triangles: 12,
+            ..FrameStats::default()
+        };
+
+        assert_eq!(
+            stats_text(&stats),
+            ["50 FPS (20.00 ms)", "2 draw calls, 12 triangles"]
+        );
+    }
+
+    /// A graph of three frames, one of them longer than the scale, is created.
+    /// The segments should start at the left edge with the long frame reaching the top.
+    #[test]
+    fn test_frame_time_graph() {
+        let rect = Rect::new(0.0, 0.0, 10.0, 100.0);
+        let segments = frame_time_graph(&[0.0, 100.0, 50.0], 11, &rect);
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(
+            segments[0],
+            (Point2::new(0.0, 100.0), Point2::new(1.0, 0.0))
+        );
+        assert_eq!(segments[1].1, Point2::new(2.0, 50.0));
+    }
+}
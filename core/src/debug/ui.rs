@@ -0,0 +1,337 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use crate::application::time::Time;
+use egui::{ClippedPrimitive, Modifiers, Pos2, RawInput, Rect, TexturesDelta, Vec2};
+use pluto_engine_display::pluto_engine_window::keyboard::Key;
+use pluto_engine_display::pluto_engine_window::mouse::{MouseButton, MouseScrollDelta};
+use pluto_engine_display::pluto_engine_window::window::{
+    PhysicalSize, TextInputEvent, WindowEvent,
+};
+use std::sync::{Arc, Mutex};
+
+/// The points scrolled per line of a mouse wheel.
+const SCROLL_LINE_POINTS: f32 = 50.0;
+
+/// The meshes and texture changes of a debug UI frame, to be drawn by the display.
+pub struct DebugUiOutput {
+    pub primitives: Vec<ClippedPrimitive>,
+    pub textures_delta: TexturesDelta,
+    pub pixels_per_point: f32,
+}
+
+struct DebugUiState {
+    raw_input: RawInput,
+    pointer: Option<Pos2>,
+    modifiers: Modifiers,
+    screen_size: PhysicalSize<u32>,
+    scale_factor: f32,
+    output: Option<DebugUiOutput>,
+}
+
+/// A system owning an egui context, layers draw debug panels with it while being entered.
+///
+/// Fed with window events by the display, which also draws the output of each frame.
+/// Provided to layers by the [`DebugUiLayer`], which begins and ends the frames.
+#[derive(Clone)]
+pub struct DebugUi {
+    context: egui::Context,
+    state: Arc<Mutex<DebugUiState>>,
+}
+
+impl Default for DebugUi {
+    fn default() -> Self {
+        Self {
+            context: egui::Context::default(),
+            state: Arc::new(Mutex::new(DebugUiState {
+                raw_input: RawInput::default(),
+                pointer: None,
+                modifiers: Modifiers::default(),
+                screen_size: PhysicalSize::default(),
+                scale_factor: 1.0,
+                output: None,
+            })),
+        }
+    }
+}
+
+impl DebugUi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The context to build the UI with, only valid while the layers above
+    /// the [`DebugUiLayer`] are entered.
+    pub fn get_context(&self) -> &egui::Context {
+        &self.context
+    }
+
+    /// *Returns `true` if the pointer is over the UI, the application should then ignore mouse input.*
+    pub fn wants_pointer_input(&self) -> bool {
+        self.context.wants_pointer_input()
+    }
+
+    /// *Returns `true` if a text field is focused, the application should then ignore keyboard input.*
+    pub fn wants_keyboard_input(&self) -> bool {
+        self.context.wants_keyboard_input()
+    }
+
+    /// Sets the size of the surface the UI is drawn on and the DPI scale factor.
+    pub fn set_screen(&self, size: PhysicalSize<u32>, scale_factor: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.screen_size = size;
+        state.scale_factor = scale_factor as f32;
+    }
+
+    /// Queues the input events for the next frame.
+    pub fn on_event(&self, event: &WindowEvent) {
+        let mut state = self.state.lock().unwrap();
+        let scale_factor = state.scale_factor;
+
+        let event = match event {
+            WindowEvent::Resized(size) => {
+                state.screen_size = *size;
+                return;
+            }
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                new_size,
+            } => {
+                state.screen_size = *new_size;
+                state.scale_factor = *scale_factor as f32;
+                return;
+            }
+            WindowEvent::CursorMoved { x, y } => {
+                let pos = Pos2::new(*x as f32 / scale_factor, *y as f32 / scale_factor);
+                state.pointer = Some(pos);
+                egui::Event::PointerMoved(pos)
+            }
+            WindowEvent::CursorLeft => {
+                state.pointer = None;
+                egui::Event::PointerGone
+            }
+            WindowEvent::MouseInput { button, pressed } => {
+                let (Some(pos), Some(button)) = (state.pointer, map_button(*button)) else {
+                    return;
+                };
+
+                egui::Event::PointerButton {
+                    pos,
+                    button,
+                    pressed: *pressed,
+                    modifiers: state.modifiers,
+                }
+            }
+            WindowEvent::MouseWheel(delta) => egui::Event::Scroll(match *delta {
+                MouseScrollDelta::Lines { x, y } => Vec2::new(x, y) * SCROLL_LINE_POINTS,
+                MouseScrollDelta::Pixels { x, y } => Vec2::new(x as f32, y as f32) / scale_factor,
+            }),
+            WindowEvent::KeyboardInput { key, pressed } => {
+                match key {
+                    Key::LeftShift | Key::RightShift => state.modifiers.shift = *pressed,
+                    Key::LeftControl | Key::RightControl => {
+                        state.modifiers.ctrl = *pressed;
+                        state.modifiers.command = *pressed;
+                    }
+                    Key::LeftAlt | Key::RightAlt => state.modifiers.alt = *pressed,
+                    _ => {}
+                }
+
+                let Some(key) = map_key(*key) else {
+                    return;
+                };
+
+                egui::Event::Key {
+                    key,
+                    pressed: *pressed,
+                    modifiers: state.modifiers,
+                }
+            }
+            WindowEvent::TextInput(TextInputEvent::Commit(text)) => egui::Event::Text(text.clone()),
+            WindowEvent::TextInput(TextInputEvent::Preedit(preedit)) => {
+                egui::Event::CompositionUpdate(preedit.text.clone())
+            }
+            WindowEvent::TextInput(TextInputEvent::ImeEnabled) => egui::Event::CompositionStart,
+            _ => return,
+        };
+
+        state.raw_input.events.push(event);
+    }
+
+    /// Begins a frame with the queued input, called by the [`DebugUiLayer`].
+    pub fn begin_frame(&self, time: Option<&Time>) {
+        let mut state = self.state.lock().unwrap();
+        let size = state.screen_size;
+        let scale_factor = state.scale_factor;
+
+        let mut raw_input = std::mem::take(&mut state.raw_input);
+        raw_input.screen_rect = Some(Rect::from_min_size(
+            Pos2::ZERO,
+            Vec2::new(size.width as f32, size.height as f32) / scale_factor,
+        ));
+        raw_input.pixels_per_point = Some(scale_factor);
+        raw_input.modifiers = state.modifiers;
+
+        if let Some(time) = time {
+            raw_input.time = Some(time.elapsed().as_secs_f64());
+            raw_input.predicted_dt = time.delta_seconds();
+        }
+
+        drop(state);
+        self.context.begin_frame(raw_input);
+    }
+
+    /// Ends the frame and tessellates the UI, called by the [`DebugUiLayer`].
+    pub fn end_frame(&self) {
+        let output = self.context.end_frame();
+        let primitives = self.context.tessellate(output.shapes);
+
+        let mut state = self.state.lock().unwrap();
+        let mut textures_delta = output.textures_delta;
+
+        // Texture changes of frames which were not drawn still have to be applied
+        if let Some(previous) = state.output.take() {
+            let mut previous_delta = previous.textures_delta;
+            previous_delta.append(textures_delta);
+            textures_delta = previous_delta;
+        }
+
+        state.output = Some(DebugUiOutput {
+            primitives,
+            textures_delta,
+            pixels_per_point: state.scale_factor,
+        });
+    }
+
+    /// *Returns the output of the last frame if it was not drawn yet.*
+    pub fn take_output(&self) -> Option<DebugUiOutput> {
+        self.state.lock().unwrap().output.take()
+    }
+}
+
+impl System for DebugUi {}
+
+fn map_button(button: MouseButton) -> Option<egui::PointerButton> {
+    match button {
+        MouseButton::Left => Some(egui::PointerButton::Primary),
+        MouseButton::Right => Some(egui::PointerButton::Secondary),
+        MouseButton::Middle => Some(egui::PointerButton::Middle),
+        MouseButton::Other(_) => None,
+    }
+}
+
+macro_rules! map_keys {
+    ($key:expr; $($same:ident),*; $($pluto:ident => $egui:ident),*) => {
+        match $key {
+            $(Key::$same => Some(egui::Key::$same),)*
+            $(Key::$pluto => Some(egui::Key::$egui),)*
+            _ => None,
+        }
+    };
+}
+
+fn map_key(key: Key) -> Option<egui::Key> {
+    map_keys!(key;
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+        F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+        Escape, Enter, Space, Tab, Backspace, Insert, Delete, Home, End, PageUp, PageDown;
+        Digit0 => Num0, Digit1 => Num1, Digit2 => Num2, Digit3 => Num3, Digit4 => Num4,
+        Digit5 => Num5, Digit6 => Num6, Digit7 => Num7, Digit8 => Num8, Digit9 => Num9,
+        Left => ArrowLeft, Right => ArrowRight, Up => ArrowUp, Down => ArrowDown
+    )
+}
+
+/// A layer providing the [`DebugUi`] system to all layers above it,
+/// beginning a UI frame before entering them and ending it afterwards.
+pub struct DebugUiLayer(pub DebugUi);
+
+impl Layer for DebugUiLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        self.0.begin_frame(systems.query::<Time>());
+
+        next.next(systems);
+
+        self.0.end_frame();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::debug::ui::DebugUi;
+    use pluto_engine_display::pluto_engine_window::mouse::MouseButton;
+    use pluto_engine_display::pluto_engine_window::window::{PhysicalSize, WindowEvent};
+
+    /// A button is drawn for two frames and clicked in between.
+    /// The click should be reported and the second frame should not upload the font atlas again.
+    #[test]
+    fn test_debug_ui_frame() {
+        let debug_ui = DebugUi::new();
+        debug_ui.set_screen(
+            PhysicalSize {
+                width: 800,
+                height: 600,
+            },
+            2.0,
+        );
+
+        let frame = || {
+            debug_ui.begin_frame(None);
+            let clicked = egui::Area::new("test")
+                .fixed_pos((0.0, 0.0))
+                .show(debug_ui.get_context(), |ui| ui.button("Button").clicked())
+                .inner;
+            debug_ui.end_frame();
+            (clicked, debug_ui.take_output().unwrap())
+        };
+
+        let (clicked, output) = frame();
+        assert!(!clicked);
+        assert!(!output.primitives.is_empty());
+        assert!(!output.textures_delta.set.is_empty());
+        assert_eq!(output.pixels_per_point, 2.0);
+
+        debug_ui.on_event(&WindowEvent::CursorMoved { x: 20.0, y: 20.0 });
+        for pressed in [true, false] {
+            debug_ui.on_event(&WindowEvent::MouseInput {
+                button: MouseButton::Left,
+                pressed,
+            });
+        }
+
+        let (clicked, output) = frame();
+        assert!(clicked);
+        assert!(output.textures_delta.set.is_empty());
+    }
+}
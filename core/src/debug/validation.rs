@@ -0,0 +1,57 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Engine-level validation of API misuse.
+//!
+//! Compiled in with the `pe_validation` feature in debug builds and compiled out of release builds.
+//! When compiled in, validation is enabled by default and can be toggled at runtime with
+//! [`set_enabled`], which also toggles the additional validation of the wgpu backend.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether validation is part of this build.
+pub const COMPILED_IN: bool = cfg!(all(feature = "pe_validation", debug_assertions));
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Toggles validation at runtime, it is enabled by default when compiled in.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+
+    #[cfg(feature = "pe_render_wgpu")]
+    pluto_engine_core_platform_wgpu::validation::set_enabled(enabled);
+}
+
+/// Returns whether validation is compiled in and enabled.
+pub fn is_enabled() -> bool {
+    COMPILED_IN && ENABLED.load(Ordering::Relaxed)
+}
+
+/// Panics with the message if validation is enabled and the condition does not hold.
+#[track_caller]
+pub(crate) fn check(condition: bool, message: impl FnOnce() -> String) {
+    if is_enabled() && !condition {
+        panic!("Validation failed: {}", message());
+    }
+}
@@ -0,0 +1,249 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::application::layer::{Layer, LayerDependencyDeclaration, LayerSwapType};
+use crate::application::system::System;
+use rfd::{AsyncFileDialog, FileHandle};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+/// A named group of file extensions shown in a file dialog, e.g. `("Images", ["png", "jpg"])`.
+#[derive(Clone, Debug)]
+pub struct FileFilter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+/// Options shared by all file dialogs.
+///
+/// *Some options may be ignored on platforms where they are not supported,
+/// such as the starting directory in the browser.*
+#[derive(Clone, Debug, Default)]
+pub struct FileDialogOptions {
+    pub title: Option<String>,
+    pub directory: Option<PathBuf>,
+    pub file_name: Option<String>,
+    pub filters: Vec<FileFilter>,
+}
+
+impl FileDialogOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    pub fn file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    pub fn filter(mut self, name: impl Into<String>, extensions: &[&str]) -> Self {
+        self.filters.push(FileFilter {
+            name: name.into(),
+            extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+        });
+        self
+    }
+
+    fn to_dialog(&self) -> AsyncFileDialog {
+        let mut dialog = AsyncFileDialog::new();
+
+        if let Some(title) = &self.title {
+            dialog = dialog.set_title(title);
+        }
+
+        if let Some(directory) = &self.directory {
+            dialog = dialog.set_directory(directory);
+        }
+
+        if let Some(file_name) = &self.file_name {
+            dialog = dialog.set_file_name(file_name);
+        }
+
+        for filter in &self.filters {
+            dialog = dialog.add_filter(&filter.name, &filter.extensions);
+        }
+
+        dialog
+    }
+}
+
+/// A file selected by the user.
+///
+/// On native platforms this is backed by a path on the file system,
+/// in the browser it is backed by a `File` object or a download prompt.
+pub struct PickedFile(FileHandle);
+
+impl PickedFile {
+    /// Returns the name of the file, without the directory.
+    pub fn name(&self) -> String {
+        self.0.file_name()
+    }
+
+    /// Returns the full path to the file.
+    ///
+    /// *Only available on native platforms.*
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn path(&self) -> &std::path::Path {
+        self.0.path()
+    }
+
+    /// Reads the whole contents of the file.
+    pub async fn read(&self) -> Vec<u8> {
+        self.0.read().await
+    }
+
+    /// Writes the data to the file, replacing its contents.
+    ///
+    /// *In the browser, the user is prompted where to save the file.*
+    pub async fn write(&self, data: &[u8]) -> std::io::Result<()> {
+        self.0.write(data).await
+    }
+}
+
+/// A file dialog that has been opened but may not have been closed by the user yet.
+///
+/// The dialog can either be awaited like any other future,
+/// or polled once per frame using [`PendingDialog::poll_result`].
+pub struct PendingDialog<T> {
+    future: Option<Pin<Box<dyn Future<Output = T>>>>,
+}
+
+impl<T> PendingDialog<T> {
+    fn new(future: impl Future<Output = T> + 'static) -> Self {
+        Self {
+            future: Some(Box::pin(future)),
+        }
+    }
+
+    /// Checks whether the dialog was closed, without blocking.
+    ///
+    /// *Returns `Some` exactly once, when the dialog is closed.*
+    pub fn poll_result(&mut self) -> Option<T> {
+        let mut context = Context::from_waker(Waker::noop());
+        let result = match self.future.as_mut()?.as_mut().poll(&mut context) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return None,
+        };
+
+        self.future = None;
+        Some(result)
+    }
+
+    /// Returns `true` if the result of this dialog was already taken.
+    pub fn is_finished(&self) -> bool {
+        self.future.is_none()
+    }
+}
+
+impl<T> Future for PendingDialog<T> {
+    type Output = T;
+
+    /// ***Panics** if the result was already taken using [`PendingDialog::poll_result`].*
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self
+            .future
+            .as_mut()
+            .expect("the dialog result was already taken");
+
+        let result = match future.as_mut().poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        self.future = None;
+        Poll::Ready(result)
+    }
+}
+
+/// A system for opening native open/save file dialogs.
+///
+/// Provided by the [`FileDialogLayer`].
+#[derive(Default)]
+pub struct FileDialogs;
+
+impl FileDialogs {
+    /// Prompts the user to pick a single file.
+    ///
+    /// *Resolves to `None` if the dialog was cancelled.*
+    pub fn open_file(&self, options: &FileDialogOptions) -> PendingDialog<Option<PickedFile>> {
+        let dialog = options.to_dialog().pick_file();
+        PendingDialog::new(async move { dialog.await.map(PickedFile) })
+    }
+
+    /// Prompts the user to pick any number of files.
+    ///
+    /// *Resolves to an empty `Vec` if the dialog was cancelled.*
+    pub fn open_files(&self, options: &FileDialogOptions) -> PendingDialog<Vec<PickedFile>> {
+        let dialog = options.to_dialog().pick_files();
+        PendingDialog::new(async move {
+            dialog
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(PickedFile)
+                .collect()
+        })
+    }
+
+    /// Prompts the user to pick a location to save a file to.
+    ///
+    /// *Resolves to `None` if the dialog was cancelled.*
+    ///
+    /// In the browser, this resolves immediately and the user is prompted
+    /// once [`PickedFile::write`] is called.
+    pub fn save_file(&self, options: &FileDialogOptions) -> PendingDialog<Option<PickedFile>> {
+        let dialog = options.to_dialog().save_file();
+        PendingDialog::new(async move { dialog.await.map(PickedFile) })
+    }
+}
+
+impl System for FileDialogs {}
+
+/// A layer providing the [`FileDialogs`] system to all layers above it.
+///
+/// Layers wishing to use file dialogs should declare it as a dependency:
+/// `dependencies.or_create(|| Box::new(FileDialogLayer))`.
+pub struct FileDialogLayer;
+
+impl Layer for FileDialogLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(FileDialogs);
+    }
+}
@@ -0,0 +1,130 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use pluto_engine_display::pluto_engine_window::window::WindowEvent;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct FileDropState {
+    hovered: Vec<PathBuf>,
+    dropped: Vec<PathBuf>,
+}
+
+/// A system tracking files dragged over and dropped on the window.
+///
+/// Fed with window events by the display, provided to layers by the [`FileDropLayer`].
+#[derive(Clone, Default)]
+pub struct FileDrop {
+    state: Arc<Mutex<FileDropState>>,
+}
+
+impl FileDrop {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the dragged and dropped files, ignoring other events.
+    pub fn on_event(&self, event: &WindowEvent) {
+        let mut state = self.state.lock().unwrap();
+
+        match event {
+            WindowEvent::FileHovered(path) => state.hovered.push(path.clone()),
+            WindowEvent::FileHoverCancelled => state.hovered.clear(),
+            WindowEvent::FileDropped(path) => {
+                state.hovered.retain(|hovered| hovered != path);
+                state.dropped.push(path.clone());
+            }
+            _ => {}
+        }
+    }
+
+    /// *Returns the files currently dragged over the window.*
+    pub fn hovered(&self) -> Vec<PathBuf> {
+        self.state.lock().unwrap().hovered.clone()
+    }
+
+    /// *Returns the files dropped on the window during this frame.*
+    pub fn dropped(&self) -> Vec<PathBuf> {
+        self.state.lock().unwrap().dropped.clone()
+    }
+
+    /// Forgets the files dropped during this frame.
+    pub fn end_frame(&self) {
+        self.state.lock().unwrap().dropped.clear();
+    }
+}
+
+impl System for FileDrop {}
+
+/// A layer providing the [`FileDrop`] system to all layers above it,
+/// ending the frame once they have been entered.
+pub struct FileDropLayer(pub FileDrop);
+
+impl Layer for FileDropLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        next.next(systems);
+
+        self.0.end_frame();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::desktop::file_drop::FileDrop;
+    use pluto_engine_display::pluto_engine_window::window::WindowEvent;
+    use std::path::PathBuf;
+
+    /// Two files are dragged over the window and one of them is dropped.
+    /// The dropped file should only be reported until the frame ends, the other one stays hovered.
+    #[test]
+    fn test_file_drop_frame() {
+        let file_drop = FileDrop::new();
+        let (a, b) = (PathBuf::from("a.png"), PathBuf::from("b.png"));
+
+        file_drop.on_event(&WindowEvent::FileHovered(a.clone()));
+        file_drop.on_event(&WindowEvent::FileHovered(b.clone()));
+        file_drop.on_event(&WindowEvent::FileDropped(a.clone()));
+
+        assert_eq!(file_drop.dropped(), [a]);
+        assert_eq!(file_drop.hovered(), [b]);
+
+        file_drop.end_frame();
+        file_drop.on_event(&WindowEvent::FileHoverCancelled);
+        assert!(file_drop.dropped().is_empty());
+        assert!(file_drop.hovered().is_empty());
+    }
+}
@@ -0,0 +1,536 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Identifies a connected gamepad, reused by the backend once the gamepad is disconnected.
+pub type GamepadId = usize;
+
+/// A button of a gamepad, named after its position on a standard controller layout.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// An axis of a gamepad, sticks range from `-1.0` to `1.0`, triggers from `0.0` to `1.0`.
+///
+/// *The Y axes of sticks point up.*
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GamepadEvent {
+    Connected {
+        id: GamepadId,
+        name: String,
+    },
+    Disconnected {
+        id: GamepadId,
+    },
+    Button {
+        id: GamepadId,
+        button: GamepadButton,
+        pressed: bool,
+    },
+    Axis {
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// A request to rumble a gamepad, the magnitudes range from `0.0` to `1.0`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rumble {
+    /// The magnitude of the low frequency motor.
+    pub strong: f32,
+    /// The magnitude of the high frequency motor.
+    pub weak: f32,
+    pub duration: Duration,
+}
+
+#[derive(Default)]
+struct GamepadState {
+    name: String,
+    down: HashSet<GamepadButton>,
+    pressed: HashSet<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+struct GamepadsState {
+    gamepads: HashMap<GamepadId, GamepadState>,
+    connected: Vec<GamepadId>,
+    disconnected: Vec<GamepadId>,
+    rumble: Vec<(GamepadId, Rumble)>,
+    deadzone: f32,
+}
+
+impl Default for GamepadsState {
+    fn default() -> Self {
+        Self {
+            gamepads: HashMap::new(),
+            connected: Vec::new(),
+            disconnected: Vec::new(),
+            rumble: Vec::new(),
+            deadzone: 0.1,
+        }
+    }
+}
+
+/// A system tracking the state of all connected gamepads.
+///
+/// Fed with events by the gamepad backend, provided to layers by the [`GamepadLayer`].
+#[derive(Clone, Default)]
+pub struct Gamepads {
+    state: Arc<Mutex<GamepadsState>>,
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_event(&self, event: &GamepadEvent) {
+        let mut state = self.state.lock().unwrap();
+
+        match event {
+            GamepadEvent::Connected { id, name } => {
+                state.gamepads.insert(
+                    *id,
+                    GamepadState {
+                        name: name.clone(),
+                        ..GamepadState::default()
+                    },
+                );
+                state.connected.push(*id);
+            }
+            GamepadEvent::Disconnected { id } => {
+                if state.gamepads.remove(id).is_some() {
+                    state.disconnected.push(*id);
+                }
+            }
+            GamepadEvent::Button {
+                id,
+                button,
+                pressed,
+            } => {
+                if let Some(gamepad) = state.gamepads.get_mut(id) {
+                    if !pressed {
+                        gamepad.down.remove(button);
+                    } else if gamepad.down.insert(*button) {
+                        gamepad.pressed.insert(*button);
+                    }
+                }
+            }
+            GamepadEvent::Axis { id, axis, value } => {
+                if let Some(gamepad) = state.gamepads.get_mut(id) {
+                    gamepad.axes.insert(*axis, *value);
+                }
+            }
+        }
+    }
+
+    /// *Returns the connected gamepads, in the order of their IDs.*
+    pub fn get_connected(&self) -> Vec<GamepadId> {
+        let mut ids = self
+            .state
+            .lock()
+            .unwrap()
+            .gamepads
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn is_connected(&self, id: GamepadId) -> bool {
+        self.state.lock().unwrap().gamepads.contains_key(&id)
+    }
+
+    /// *Returns the gamepads connected during this frame.*
+    pub fn was_connected(&self) -> Vec<GamepadId> {
+        self.state.lock().unwrap().connected.clone()
+    }
+
+    /// *Returns the gamepads disconnected during this frame.*
+    pub fn was_disconnected(&self) -> Vec<GamepadId> {
+        self.state.lock().unwrap().disconnected.clone()
+    }
+
+    pub fn get_name(&self, id: GamepadId) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state.gamepads.get(&id).map(|gamepad| gamepad.name.clone())
+    }
+
+    pub fn is_down(&self, id: GamepadId, button: GamepadButton) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .gamepads
+            .get(&id)
+            .is_some_and(|gamepad| gamepad.down.contains(&button))
+    }
+
+    /// *Returns `true` if the button was pressed during this frame.*
+    pub fn was_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .gamepads
+            .get(&id)
+            .is_some_and(|gamepad| gamepad.pressed.contains(&button))
+    }
+
+    /// *Returns the value of the axis, `0.0` within the deadzone or if the gamepad is not connected.*
+    pub fn get_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        let state = self.state.lock().unwrap();
+        let value = state
+            .gamepads
+            .get(&id)
+            .and_then(|gamepad| gamepad.axes.get(&axis))
+            .copied()
+            .unwrap_or_default();
+
+        if value.abs() < state.deadzone {
+            0.0
+        } else {
+            value
+        }
+    }
+
+    /// Sets the absolute axis value below which axes read as `0.0`, `0.1` by default.
+    pub fn set_deadzone(&self, deadzone: f32) {
+        self.state.lock().unwrap().deadzone = deadzone;
+    }
+
+    /// Requests the gamepad to rumble, ignored if the gamepad or the platform
+    /// does not support force feedback.
+    pub fn rumble(&self, id: GamepadId, rumble: Rumble) {
+        self.state.lock().unwrap().rumble.push((id, rumble));
+    }
+
+    /// *Returns the rumble requests made since the last call, to be played by the backend.*
+    pub fn take_rumble_requests(&self) -> Vec<(GamepadId, Rumble)> {
+        std::mem::take(&mut self.state.lock().unwrap().rumble)
+    }
+
+    /// Forgets the buttons pressed and the gamepads (dis)connected during this frame.
+    pub fn end_frame(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.connected.clear();
+        state.disconnected.clear();
+
+        for gamepad in state.gamepads.values_mut() {
+            gamepad.pressed.clear();
+        }
+    }
+}
+
+impl System for Gamepads {}
+
+/// A layer providing the [`Gamepads`] system to all layers above it.
+///
+/// With the `pe_gamepad` feature, the layer polls gamepads using gilrs before entering
+/// the layers above it, backed by the Gamepad API on the web.
+/// Otherwise the system has to be fed with events by the application.
+pub struct GamepadLayer {
+    gamepads: Gamepads,
+    #[cfg(feature = "pe_gamepad")]
+    backend: Option<gilrs_backend::GilrsBackend>,
+}
+
+impl GamepadLayer {
+    pub fn new(gamepads: Gamepads) -> Self {
+        Self {
+            gamepads,
+            #[cfg(feature = "pe_gamepad")]
+            backend: None,
+        }
+    }
+}
+
+impl Layer for GamepadLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.gamepads.clone());
+
+        #[cfg(feature = "pe_gamepad")]
+        {
+            self.backend = gilrs_backend::GilrsBackend::new(&self.gamepads);
+        }
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        #[cfg(feature = "pe_gamepad")]
+        if let Some(backend) = &mut self.backend {
+            backend.poll(&self.gamepads);
+        }
+
+        next.next(systems);
+
+        self.gamepads.end_frame();
+    }
+}
+
+#[cfg(feature = "pe_gamepad")]
+mod gilrs_backend {
+    use crate::input::gamepad::{GamepadAxis, GamepadButton, GamepadEvent, Gamepads, Rumble};
+    use gilrs::ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks};
+    use gilrs::{Axis, Button, EventType, Gilrs};
+    use instant::Instant;
+    use log::warn;
+
+    pub(super) struct GilrsBackend {
+        gilrs: Gilrs,
+        /// Effects stop playing once dropped, kept until they are over.
+        effects: Vec<(Effect, Instant)>,
+    }
+
+    impl GilrsBackend {
+        /// Reports the gamepads connected before the backend was created.
+        pub(super) fn new(gamepads: &Gamepads) -> Option<Self> {
+            let gilrs = match Gilrs::new() {
+                Ok(gilrs) => gilrs,
+                Err(e) => {
+                    warn!("Gamepads are not available: {}", e);
+                    return None;
+                }
+            };
+
+            for (id, gamepad) in gilrs.gamepads() {
+                gamepads.on_event(&GamepadEvent::Connected {
+                    id: id.into(),
+                    name: gamepad.name().to_owned(),
+                });
+            }
+
+            Some(Self {
+                gilrs,
+                effects: Vec::new(),
+            })
+        }
+
+        pub(super) fn poll(&mut self, gamepads: &Gamepads) {
+            while let Some(event) = self.gilrs.next_event() {
+                let id = event.id.into();
+
+                let event = match event.event {
+                    EventType::Connected => GamepadEvent::Connected {
+                        id,
+                        name: self.gilrs.gamepad(event.id).name().to_owned(),
+                    },
+                    EventType::Disconnected => GamepadEvent::Disconnected { id },
+                    EventType::ButtonPressed(button, _) | EventType::ButtonReleased(button, _) => {
+                        let Some(button) = map_button(button) else {
+                            continue;
+                        };
+
+                        GamepadEvent::Button {
+                            id,
+                            button,
+                            pressed: matches!(event.event, EventType::ButtonPressed(..)),
+                        }
+                    }
+                    EventType::ButtonChanged(Button::LeftTrigger2, value, _) => {
+                        GamepadEvent::Axis {
+                            id,
+                            axis: GamepadAxis::LeftTrigger,
+                            value,
+                        }
+                    }
+                    EventType::ButtonChanged(Button::RightTrigger2, value, _) => {
+                        GamepadEvent::Axis {
+                            id,
+                            axis: GamepadAxis::RightTrigger,
+                            value,
+                        }
+                    }
+                    EventType::AxisChanged(axis, value, _) => {
+                        let Some(axis) = map_axis(axis) else {
+                            continue;
+                        };
+
+                        GamepadEvent::Axis { id, axis, value }
+                    }
+                    _ => continue,
+                };
+
+                gamepads.on_event(&event);
+            }
+
+            for (id, rumble) in gamepads.take_rumble_requests() {
+                self.play_rumble(id, rumble);
+            }
+
+            let now = Instant::now();
+            self.effects.retain(|(_, end)| *end > now);
+        }
+
+        fn play_rumble(&mut self, id: usize, rumble: Rumble) {
+            let Some(gamepad_id) = self
+                .gilrs
+                .gamepads()
+                .map(|(gamepad_id, _)| gamepad_id)
+                .find(|gamepad_id| usize::from(*gamepad_id) == id)
+            else {
+                return;
+            };
+
+            if !self.gilrs.gamepad(gamepad_id).is_ff_supported() {
+                return;
+            }
+
+            let ms = rumble.duration.as_millis().min(u32::MAX as u128) as u32;
+            let base_effect = |kind| BaseEffect {
+                kind,
+                scheduling: Replay {
+                    play_for: Ticks::from_ms(ms),
+                    ..Replay::default()
+                },
+                ..BaseEffect::default()
+            };
+            let magnitude = |value: f32| (value.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+
+            let effect = EffectBuilder::new()
+                .add_effect(base_effect(BaseEffectType::Strong {
+                    magnitude: magnitude(rumble.strong),
+                }))
+                .add_effect(base_effect(BaseEffectType::Weak {
+                    magnitude: magnitude(rumble.weak),
+                }))
+                .gamepads(&[gamepad_id])
+                .repeat(Repeat::For(Ticks::from_ms(ms)))
+                .finish(&mut self.gilrs);
+
+            match effect.and_then(|effect| effect.play().map(|_| effect)) {
+                Ok(effect) => self
+                    .effects
+                    .push((effect, Instant::now() + rumble.duration)),
+                Err(e) => warn!("Failed to rumble gamepad {}: {}", id, e),
+            }
+        }
+    }
+
+    fn map_button(button: Button) -> Option<GamepadButton> {
+        Some(match button {
+            Button::South => GamepadButton::South,
+            Button::East => GamepadButton::East,
+            Button::North => GamepadButton::North,
+            Button::West => GamepadButton::West,
+            Button::LeftTrigger => GamepadButton::LeftBumper,
+            Button::RightTrigger => GamepadButton::RightBumper,
+            Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+            Button::RightTrigger2 => GamepadButton::RightTrigger,
+            Button::Select => GamepadButton::Select,
+            Button::Start => GamepadButton::Start,
+            Button::Mode => GamepadButton::Mode,
+            Button::LeftThumb => GamepadButton::LeftThumb,
+            Button::RightThumb => GamepadButton::RightThumb,
+            Button::DPadUp => GamepadButton::DPadUp,
+            Button::DPadDown => GamepadButton::DPadDown,
+            Button::DPadLeft => GamepadButton::DPadLeft,
+            Button::DPadRight => GamepadButton::DPadRight,
+            _ => return None,
+        })
+    }
+
+    fn map_axis(axis: Axis) -> Option<GamepadAxis> {
+        Some(match axis {
+            Axis::LeftStickX => GamepadAxis::LeftStickX,
+            Axis::LeftStickY => GamepadAxis::LeftStickY,
+            Axis::RightStickX => GamepadAxis::RightStickX,
+            Axis::RightStickY => GamepadAxis::RightStickY,
+            Axis::LeftZ => GamepadAxis::LeftTrigger,
+            Axis::RightZ => GamepadAxis::RightTrigger,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::input::gamepad::{GamepadAxis, GamepadButton, GamepadEvent, Gamepads};
+
+    /// A gamepad is connected, a button is pressed and a stick is moved slightly, then it is disconnected.
+    /// The stick should read as centered and nothing should be reported once disconnected.
+    #[test]
+    fn test_gamepad_frame() {
+        let gamepads = Gamepads::new();
+
+        gamepads.on_event(&GamepadEvent::Connected {
+            id: 0,
+            name: "Pad".to_owned(),
+        });
+        gamepads.on_event(&GamepadEvent::Button {
+            id: 0,
+            button: GamepadButton::South,
+            pressed: true,
+        });
+        gamepads.on_event(&GamepadEvent::Axis {
+            id: 0,
+            axis: GamepadAxis::LeftStickX,
+            value: 0.05,
+        });
+        assert_eq!(gamepads.was_connected(), [0]);
+        assert!(gamepads.was_pressed(0, GamepadButton::South));
+        assert_eq!(gamepads.get_axis(0, GamepadAxis::LeftStickX), 0.0);
+
+        gamepads.end_frame();
+        gamepads.on_event(&GamepadEvent::Disconnected { id: 0 });
+        assert!(gamepads.was_connected().is_empty());
+        assert_eq!(gamepads.was_disconnected(), [0]);
+        assert!(!gamepads.is_down(0, GamepadButton::South));
+        assert!(gamepads.get_connected().is_empty());
+    }
+}
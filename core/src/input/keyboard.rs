@@ -0,0 +1,133 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use pluto_engine_display::pluto_engine_window::keyboard::Key;
+use pluto_engine_display::pluto_engine_window::window::WindowEvent;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct KeyboardState {
+    down: HashSet<Key>,
+    pressed: HashSet<Key>,
+}
+
+/// A system tracking the state of the keyboard.
+///
+/// Fed with window events by the display, provided to layers by the [`KeyboardLayer`].
+#[derive(Clone, Default)]
+pub struct Keyboard {
+    state: Arc<Mutex<KeyboardState>>,
+}
+
+impl Keyboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the keyboard state, ignoring events other than keyboard input.
+    pub fn on_event(&self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput { key, pressed } = *event {
+            let mut state = self.state.lock().unwrap();
+
+            if pressed {
+                if state.down.insert(key) {
+                    state.pressed.insert(key);
+                }
+            } else {
+                state.down.remove(&key);
+            }
+        }
+    }
+
+    pub fn is_down(&self, key: Key) -> bool {
+        self.state.lock().unwrap().down.contains(&key)
+    }
+
+    /// *Returns `true` if the key was pressed during this frame, ignoring key repeats.*
+    pub fn was_pressed(&self, key: Key) -> bool {
+        self.state.lock().unwrap().pressed.contains(&key)
+    }
+
+    /// Forgets the keys pressed during this frame.
+    pub fn end_frame(&self) {
+        self.state.lock().unwrap().pressed.clear();
+    }
+}
+
+impl System for Keyboard {}
+
+/// A layer providing the [`Keyboard`] system to all layers above it,
+/// ending the keyboard frame once they have been entered.
+pub struct KeyboardLayer(pub Keyboard);
+
+impl Layer for KeyboardLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        next.next(systems);
+
+        self.0.end_frame();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::input::keyboard::Keyboard;
+    use pluto_engine_display::pluto_engine_window::keyboard::Key;
+    use pluto_engine_display::pluto_engine_window::window::WindowEvent;
+
+    /// A key is pressed, repeated and released within a frame.
+    /// It should count as pressed once, until the frame ends.
+    #[test]
+    fn test_keyboard_frame() {
+        let keyboard = Keyboard::new();
+        let event = |pressed| WindowEvent::KeyboardInput {
+            key: Key::Space,
+            pressed,
+        };
+
+        keyboard.on_event(&event(true));
+        keyboard.on_event(&event(true));
+        assert!(keyboard.is_down(Key::Space));
+        assert!(keyboard.was_pressed(Key::Space));
+
+        keyboard.on_event(&event(false));
+        assert!(!keyboard.is_down(Key::Space));
+        assert!(keyboard.was_pressed(Key::Space));
+
+        keyboard.end_frame();
+        assert!(!keyboard.was_pressed(Key::Space));
+    }
+}
@@ -0,0 +1,173 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use pluto_engine_display::pluto_engine_window::window::{
+    Preedit, TextInputEvent, Window, WindowEvent,
+};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct TextInputState {
+    committed: String,
+    preedit: Option<Preedit>,
+    ime_enabled: bool,
+    ime_allowed: Option<bool>,
+    ime_position: Option<(f64, f64)>,
+}
+
+/// A system collecting the text entered in the window, including text composed by input methods.
+///
+/// Fed with window events by the display, provided to layers by the [`TextInputLayer`].
+#[derive(Clone, Default)]
+pub struct TextInput {
+    state: Arc<Mutex<TextInputState>>,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the entered text, ignoring events other than text input.
+    pub fn on_event(&self, event: &WindowEvent) {
+        if let WindowEvent::TextInput(event) = event {
+            let mut state = self.state.lock().unwrap();
+
+            match event {
+                TextInputEvent::Commit(text) => {
+                    state.committed.push_str(text);
+                    state.preedit = None;
+                }
+                TextInputEvent::Preedit(preedit) if preedit.text.is_empty() => state.preedit = None,
+                TextInputEvent::Preedit(preedit) => state.preedit = Some(preedit.clone()),
+                TextInputEvent::ImeEnabled => state.ime_enabled = true,
+                TextInputEvent::ImeDisabled => {
+                    state.ime_enabled = false;
+                    state.preedit = None;
+                }
+            }
+        }
+    }
+
+    /// *Returns the text committed during this frame.*
+    pub fn get_committed(&self) -> String {
+        self.state.lock().unwrap().committed.clone()
+    }
+
+    /// *Returns the text being composed by the input method, to be displayed at the cursor
+    /// of the focused text field.*
+    pub fn get_preedit(&self) -> Option<Preedit> {
+        self.state.lock().unwrap().preedit.clone()
+    }
+
+    pub fn is_ime_enabled(&self) -> bool {
+        self.state.lock().unwrap().ime_enabled
+    }
+
+    /// Allows input methods to compose text, applied to the window before the next frame.
+    ///
+    /// Text fields should allow input methods while focused.
+    pub fn set_ime_allowed(&self, allowed: bool) {
+        self.state.lock().unwrap().ime_allowed = Some(allowed);
+    }
+
+    /// Moves the candidate window of the input method near the text cursor,
+    /// in logical units, applied to the window before the next frame.
+    pub fn set_ime_position(&self, x: f64, y: f64) {
+        self.state.lock().unwrap().ime_position = Some((x, y));
+    }
+
+    /// Applies the input method requests made since the last call to the window.
+    pub fn apply_requests<W: Window>(&self, window: &W) {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(allowed) = state.ime_allowed.take() {
+            window.set_ime_allowed(allowed);
+        }
+
+        if let Some((x, y)) = state.ime_position.take() {
+            window.set_ime_position(x, y);
+        }
+    }
+
+    /// Forgets the text committed during this frame.
+    pub fn end_frame(&self) {
+        self.state.lock().unwrap().committed.clear();
+    }
+}
+
+impl System for TextInput {}
+
+/// A layer providing the [`TextInput`] system to all layers above it,
+/// ending the frame once they have been entered.
+pub struct TextInputLayer(pub TextInput);
+
+impl Layer for TextInputLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        next.next(systems);
+
+        self.0.end_frame();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::input::text_input::TextInput;
+    use pluto_engine_display::pluto_engine_window::window::{Preedit, TextInputEvent, WindowEvent};
+
+    /// A character is typed, then an input method composes and commits a word within a frame.
+    /// Both should be committed in order and the composition should end with the commit.
+    #[test]
+    fn test_text_input_frame() {
+        let text_input = TextInput::new();
+        let event = |event| WindowEvent::TextInput(event);
+
+        text_input.on_event(&event(TextInputEvent::Commit("a".to_owned())));
+        text_input.on_event(&event(TextInputEvent::ImeEnabled));
+        text_input.on_event(&event(TextInputEvent::Preedit(Preedit {
+            text: "にほ".to_owned(),
+            cursor: Some((6, 6)),
+        })));
+        assert_eq!(text_input.get_preedit().unwrap().text, "にほ");
+
+        text_input.on_event(&event(TextInputEvent::Commit("日本".to_owned())));
+        assert_eq!(text_input.get_committed(), "a日本");
+        assert!(text_input.get_preedit().is_none());
+        assert!(text_input.is_ime_enabled());
+
+        text_input.end_frame();
+        assert!(text_input.get_committed().is_empty());
+    }
+}
@@ -27,8 +27,25 @@ extern crate core;
 pub use cgmath;
 pub use log;
 pub use pluto_engine_display;
+pub use pluto_engine_display::pluto_engine_render;
+pub use pluto_engine_display::pluto_engine_window;
 pub use pluto_io;
 
+#[cfg(feature = "pe_audio")]
+pub use pluto_engine_audio;
+
+pub mod animation;
 pub mod application;
+pub mod asset;
+#[cfg(feature = "pe_audio")]
+pub mod audio;
 pub mod color;
+pub mod debug;
+pub mod desktop;
+pub mod input;
+pub mod math;
+pub mod memory;
+pub mod prelude;
+pub mod render;
 pub mod runtime;
+pub mod settings;
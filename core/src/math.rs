@@ -0,0 +1,546 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Math types used throughout the engine, built on top of [`cgmath`].
+//!
+//! The engine uses a right-handed coordinate system with `+Y` up, `+X` right
+//! and `-Z` forward. Projections map depth to the `0..1` range of wgpu.
+
+use cgmath::{perspective as gl_perspective, EuclideanSpace, Transform as _};
+
+pub use cgmath::{
+    Deg, ElementWise, InnerSpace, Matrix, MetricSpace, One, Rad, Rotation, Rotation3, SquareMatrix,
+    VectorSpace, Zero,
+};
+
+pub type Vec2 = cgmath::Vector2<f32>;
+pub type Vec3 = cgmath::Vector3<f32>;
+pub type Vec4 = cgmath::Vector4<f32>;
+pub type Point2 = cgmath::Point2<f32>;
+pub type Point3 = cgmath::Point3<f32>;
+pub type Mat3 = cgmath::Matrix3<f32>;
+pub type Mat4 = cgmath::Matrix4<f32>;
+pub type Quat = cgmath::Quaternion<f32>;
+
+pub const UP: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+pub const RIGHT: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+pub const FORWARD: Vec3 = Vec3::new(0.0, 0.0, -1.0);
+
+/// Converts the OpenGL clip space depth range of `-1..1` produced by cgmath to `0..1`.
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: Mat4 = Mat4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+/// *Returns a perspective projection with a depth range of `0..1`.*
+pub fn perspective(fov_y: impl Into<Rad<f32>>, aspect: f32, near: f32, far: f32) -> Mat4 {
+    OPENGL_TO_WGPU_MATRIX * gl_perspective(fov_y.into(), aspect, near, far)
+}
+
+/// *Returns an orthographic projection with a depth range of `0..1`.*
+pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+    OPENGL_TO_WGPU_MATRIX * cgmath::ortho(left, right, bottom, top, near, far)
+}
+
+/// A translation, rotation and non-uniform scale, applied in reverse order.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        translation: Vec3::new(0.0, 0.0, 0.0),
+        rotation: Quat::new(1.0, 0.0, 0.0, 0.0),
+        scale: Vec3::new(1.0, 1.0, 1.0),
+    };
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_translation(self.translation)
+            * Mat4::from(self.rotation)
+            * Mat4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+
+    /// *Returns the direction the transform faces, [`FORWARD`] rotated by the rotation.*
+    pub fn forward(&self) -> Vec3 {
+        self.rotation.rotate_vector(FORWARD)
+    }
+
+    pub fn transform_point(&self, point: Point3) -> Point3 {
+        self.matrix().transform_point(point)
+    }
+}
+
+/// An axis-aligned rectangle, `min` is inclusive and `max` exclusive.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rect {
+    pub min: Point2,
+    pub max: Point2,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            min: Point2::new(x, y),
+            max: Point2::new(x + width, y + height),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max.y - self.min.y
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn center(&self) -> Point2 {
+        self.min.midpoint(self.max)
+    }
+
+    /// *Returns `true` if the rectangle has no area.*
+    pub fn is_empty(&self) -> bool {
+        self.width() <= 0.0 || self.height() <= 0.0
+    }
+
+    pub fn contains(&self, point: Point2) -> bool {
+        (self.min.x..self.max.x).contains(&point.x) && (self.min.y..self.max.y).contains(&point.y)
+    }
+
+    /// *Returns the overlap of both rectangles, `None` if they do not overlap.*
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let rect = Rect {
+            min: Point2::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y)),
+            max: Point2::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y)),
+        };
+
+        (!rect.is_empty()).then_some(rect)
+    }
+
+    /// *Returns the smallest rectangle containing both rectangles.*
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            min: Point2::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Point2::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+}
+
+/// An axis-aligned bounding box.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    pub fn new(min: Point3, max: Point3) -> Self {
+        Self { min, max }
+    }
+
+    /// *Returns the smallest box containing all points, `None` if there are no points.*
+    pub fn from_points(points: impl IntoIterator<Item = Point3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(Self::new(first, first), |aabb, point| {
+            aabb.union(&Self::new(point, point))
+        }))
+    }
+
+    pub fn center(&self) -> Point3 {
+        self.min.midpoint(self.max)
+    }
+
+    /// *Returns half of the size of the box.*
+    pub fn extents(&self) -> Vec3 {
+        (self.max - self.min) / 2.0
+    }
+
+    pub fn corners(&self) -> [Point3; 8] {
+        let (min, max) = (self.min, self.max);
+
+        [
+            Point3::new(min.x, min.y, min.z),
+            Point3::new(max.x, min.y, min.z),
+            Point3::new(min.x, max.y, min.z),
+            Point3::new(max.x, max.y, min.z),
+            Point3::new(min.x, min.y, max.z),
+            Point3::new(max.x, min.y, max.z),
+            Point3::new(min.x, max.y, max.z),
+            Point3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    pub fn contains(&self, point: Point3) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Point3::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            max: Point3::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        }
+    }
+
+    /// *Returns the box containing this box after it was transformed by the matrix.*
+    pub fn transformed(&self, matrix: &Mat4) -> Aabb {
+        Self::from_points(self.corners().map(|corner| matrix.transform_point(corner))).unwrap()
+    }
+}
+
+/// A bounding sphere.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sphere {
+    pub center: Point3,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Point3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// *Returns the smallest sphere containing the box.*
+    pub fn from_aabb(aabb: &Aabb) -> Self {
+        Self::new(aabb.center(), aabb.extents().magnitude())
+    }
+
+    /// *Returns the sphere containing this sphere after it was transformed by the matrix,
+    /// scaled by the largest scale of the matrix.*
+    pub fn transformed(&self, matrix: &Mat4) -> Sphere {
+        let scale = [matrix.x, matrix.y, matrix.z]
+            .iter()
+            .map(|axis| axis.truncate().magnitude())
+            .fold(0.0, f32::max);
+
+        Sphere::new(matrix.transform_point(self.center), self.radius * scale)
+    }
+}
+
+/// The bounds of an object, tested against a [`Frustum`] when culling.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BoundingVolume {
+    Aabb(Aabb),
+    Sphere(Sphere),
+}
+
+impl BoundingVolume {
+    pub fn transformed(&self, matrix: &Mat4) -> BoundingVolume {
+        match self {
+            BoundingVolume::Aabb(aabb) => BoundingVolume::Aabb(aabb.transformed(matrix)),
+            BoundingVolume::Sphere(sphere) => BoundingVolume::Sphere(sphere.transformed(matrix)),
+        }
+    }
+}
+
+impl From<Aabb> for BoundingVolume {
+    fn from(aabb: Aabb) -> Self {
+        BoundingVolume::Aabb(aabb)
+    }
+}
+
+impl From<Sphere> for BoundingVolume {
+    fn from(sphere: Sphere) -> Self {
+        BoundingVolume::Sphere(sphere)
+    }
+}
+
+/// A half-line starting at the origin.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Point3,
+    /// The normalized direction of the ray.
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// Creates a ray, normalizing the direction.
+    pub fn new(origin: Point3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// *Returns the point at the given distance along the ray.*
+    pub fn at(&self, distance: f32) -> Point3 {
+        self.origin + self.direction * distance
+    }
+
+    /// *Returns the distance along the ray at which it enters the box, `0.0` if it starts inside,
+    /// `None` if it misses.*
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = f32::INFINITY;
+
+        for axis in 0..3 {
+            let inverse = 1.0 / self.direction[axis];
+            let a = (aabb.min[axis] - self.origin[axis]) * inverse;
+            let b = (aabb.max[axis] - self.origin[axis]) * inverse;
+
+            // Parallel rays produce infinite distances, excluding rays outside of the slab
+            near = near.max(a.min(b));
+            far = far.min(a.max(b));
+
+            if near > far {
+                return None;
+            }
+        }
+
+        Some(near)
+    }
+}
+
+/// A plane of points `p` where `normal.dot(p) + distance` is zero,
+/// points in front of it have positive distances.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Creates a plane from the coefficients of its equation, normalizing them.
+    fn from_coefficients(coefficients: Vec4) -> Self {
+        let length = coefficients.truncate().magnitude();
+
+        Self {
+            normal: coefficients.truncate() / length,
+            distance: coefficients.w / length,
+        }
+    }
+
+    /// *Returns the signed distance of the point from the plane.*
+    pub fn distance_to(&self, point: Point3) -> f32 {
+        self.normal.dot(point.to_vec()) + self.distance
+    }
+}
+
+/// The volume visible to a camera, bounded by six planes facing inwards.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frustum {
+    /// The left, right, bottom, top, near and far planes.
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the frustum of a view-projection matrix with a depth range of `0..1`,
+    /// as returned by [`perspective`] and [`orthographic`].
+    pub fn from_view_projection(view_projection: &Mat4) -> Self {
+        let row = |i| view_projection.row(i);
+
+        Self {
+            planes: [
+                row(3) + row(0),
+                row(3) - row(0),
+                row(3) + row(1),
+                row(3) - row(1),
+                row(2),
+                row(3) - row(2),
+            ]
+            .map(Plane::from_coefficients),
+        }
+    }
+
+    pub fn contains_point(&self, point: Point3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance_to(point) >= 0.0)
+    }
+
+    /// *Returns `false` if the sphere is entirely outside the frustum.*
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance_to(sphere.center) >= -sphere.radius)
+    }
+
+    /// *Returns `false` if the box is entirely outside the frustum.*
+    ///
+    /// Boxes near the corners of the frustum may intersect even though they are outside.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner furthest in front of the plane
+            let corner = Point3::new(
+                if plane.normal.x >= 0.0 {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if plane.normal.y >= 0.0 {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if plane.normal.z >= 0.0 {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+
+            plane.distance_to(corner) >= 0.0
+        })
+    }
+
+    pub fn intersects(&self, volume: &BoundingVolume) -> bool {
+        match volume {
+            BoundingVolume::Aabb(aabb) => self.intersects_aabb(aabb),
+            BoundingVolume::Sphere(sphere) => self.intersects_sphere(sphere),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{
+        perspective, Aabb, Deg, Frustum, Mat4, Point2, Point3, Quat, Ray, Rect, Rotation3, Sphere,
+        Transform, Vec3,
+    };
+
+    /// Two overlapping and two disjoint rectangles are intersected.
+    /// The overlap should be returned only for the overlapping ones.
+    #[test]
+    fn test_rect_intersection() {
+        let a = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let b = Rect::new(5.0, 5.0, 10.0, 10.0);
+
+        assert_eq!(a.intersection(&b), Some(Rect::new(5.0, 5.0, 5.0, 5.0)));
+        assert_eq!(a.intersection(&Rect::new(10.0, 0.0, 1.0, 1.0)), None);
+        assert!(a.contains(Point2::new(0.0, 9.5)));
+        assert!(!a.contains(Point2::new(10.0, 5.0)));
+    }
+
+    /// A unit box is rotated by 90° and moved.
+    /// The bounding box should follow the transform.
+    #[test]
+    fn test_aabb_transformed() {
+        let aabb = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 1.0, 1.0));
+        let transform = Transform {
+            translation: Vec3::new(0.0, 5.0, 0.0),
+            rotation: Quat::from_angle_y(Deg(90.0)),
+            ..Transform::IDENTITY
+        };
+
+        let transformed = aabb.transformed(&transform.matrix());
+        let close =
+            |a: Point3, b: Point3| (a - b).x.abs() + (a - b).y.abs() + (a - b).z.abs() < 1e-5;
+
+        assert!(close(transformed.min, Point3::new(0.0, 5.0, -2.0)));
+        assert!(close(transformed.max, Point3::new(1.0, 6.0, 0.0)));
+        assert!((transform.forward() - Vec3::new(-1.0, 0.0, 0.0)).x.abs() < 1e-5);
+    }
+
+    /// A frustum is extracted from a camera at the origin looking along -z.
+    /// Volumes in front of the camera should intersect it, ones behind or beside it should not.
+    #[test]
+    fn test_frustum_culling() {
+        let view_projection = perspective(Deg(90.0), 1.0, 0.1, 100.0)
+            * Mat4::look_at_rh(
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(0.0, 0.0, -1.0),
+                Vec3::unit_y(),
+            );
+        let frustum = Frustum::from_view_projection(&view_projection);
+
+        assert!(frustum.contains_point(Point3::new(0.0, 0.0, -10.0)));
+        assert!(!frustum.contains_point(Point3::new(0.0, 0.0, 10.0)));
+        assert!(!frustum.contains_point(Point3::new(0.0, 0.0, -200.0)));
+
+        let unit =
+            |x, y, z| Aabb::new(Point3::new(x, y, z), Point3::new(x + 1.0, y + 1.0, z + 1.0));
+        assert!(frustum.intersects_aabb(&unit(-0.5, -0.5, -5.0)));
+        assert!(frustum.intersects_aabb(&unit(4.5, 0.0, -5.0)));
+        assert!(!frustum.intersects_aabb(&unit(6.0, 0.0, -5.0)));
+        assert!(!frustum.intersects_aabb(&unit(0.0, 0.0, 2.0)));
+
+        assert!(frustum.intersects_sphere(&Sphere::new(Point3::new(5.5, 0.0, -5.0), 1.0)));
+        assert!(!frustum.intersects_sphere(&Sphere::new(Point3::new(8.0, 0.0, -5.0), 1.0)));
+    }
+
+    /// Rays pointing at, away from and past a box are intersected with it.
+    /// Only the ray pointing at the box should hit it, at the distance of its near face.
+    #[test]
+    fn test_ray_intersect_aabb() {
+        let aabb = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let origin = Point3::new(0.0, 0.0, 5.0);
+
+        let hit = Ray::new(origin, Vec3::new(0.0, 0.0, -2.0)).intersect_aabb(&aabb);
+        assert_eq!(hit, Some(4.0));
+        assert_eq!(
+            Ray::new(origin, Vec3::new(0.0, 0.0, 1.0)).intersect_aabb(&aabb),
+            None
+        );
+        assert_eq!(
+            Ray::new(Point3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0)).intersect_aabb(&aabb),
+            None
+        );
+        assert_eq!(
+            Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)).intersect_aabb(&aabb),
+            Some(0.0)
+        );
+    }
+}
@@ -0,0 +1,72 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! The traits and types most applications need, re-exported with short paths.
+//!
+//! ```ignore
+//! use pluto_engine::prelude::*;
+//! ```
+//!
+//! *Platform specific types, such as the wgpu and winit backends, are not included.*
+
+pub use crate::application::event::EventBus;
+pub use crate::application::layer::pluto::PlutoLayerManager;
+pub use crate::application::layer::{
+    Layer, LayerCommands, LayerDependencyDeclaration, LayerManager, LayerSwapType,
+    LayerSystemManager, LayerSystemProvider, LayerWalker,
+};
+pub use crate::application::state::{GameStateLayer, GameStateMachine, StateLayers};
+pub use crate::application::system::System;
+pub use crate::application::time::{Time, Timer};
+pub use crate::application::Application;
+pub use crate::color::{Color, HSBA, RGBA};
+pub use crate::math::{
+    Deg, ElementWise, InnerSpace, Mat3, Mat4, Matrix, MetricSpace, One, Point2, Point3, Quat, Rad,
+    Rotation, Rotation3, SquareMatrix, Transform, Vec2, Vec3, Vec4, VectorSpace, Zero,
+};
+pub use crate::render::camera::{Camera, ScreenSpace};
+pub use crate::runtime::config::EngineConfig;
+pub use crate::runtime::pluto_runtime::PlutoRuntime;
+pub use crate::runtime::{ApplicationBootstrapper, Runtime};
+
+pub use pluto_engine_display::error::EngineError;
+pub use pluto_engine_display::{
+    ApplicationDisplay, ApplicationState, PlutoDevice, PlutoPipeline, PlutoQueue, PlutoSurface,
+    PlutoSurfaceTexture, WindowDisplay,
+};
+
+pub use pluto_engine_display::pluto_engine_render::device::{
+    CommandBuffer, CommandBufferBuilder, Device, DeviceUniforms, PhysicalDevice, Queue,
+};
+pub use pluto_engine_display::pluto_engine_render::frame::ClearColor;
+pub use pluto_engine_display::pluto_engine_render::instance::ContextInstance;
+pub use pluto_engine_display::pluto_engine_render::mesh::{AttributeFormat, Vertex};
+pub use pluto_engine_display::pluto_engine_render::pipeline::{
+    BlendMode, Pipeline, PipelineCreateInfo, PrimitiveState,
+};
+pub use pluto_engine_display::pluto_engine_render::shader::ShaderCode;
+pub use pluto_engine_display::pluto_engine_render::surface::{Surface, SurfaceTexture};
+pub use pluto_engine_display::pluto_engine_render::texture::{Texture, TextureFormat, TextureView};
+pub use pluto_engine_display::pluto_engine_render::uniform::{UniformBindGroup, UniformBuffer};
+
+pub use pluto_engine_display::pluto_engine_window::window::{LogicalSize, PhysicalSize, Window};
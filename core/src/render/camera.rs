@@ -0,0 +1,352 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::math::{
+    orthographic, perspective, Aabb, Deg, InnerSpace, Mat4, Point2, Point3, Ray, SquareMatrix,
+    Vec3, Vec4, UP,
+};
+use cgmath::Transform as _;
+use pluto_engine_display::pluto_engine_render::shadow::{ShadowMapConfig, ShadowUniform};
+use pluto_engine_display::pluto_engine_render::uniform::{ShaderStages, UniformLayout};
+use pluto_engine_display::pluto_engine_window::window::{LogicalSize, PhysicalSize};
+use pluto_engine_display::WindowDisplay;
+
+pub use crate::math::OPENGL_TO_WGPU_MATRIX;
+
+/// A perspective camera looking from `eye` towards `target`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    pub eye: Point3,
+    pub target: Point3,
+    pub up: Vec3,
+    /// The vertical field of view.
+    pub fov_y: Deg<f32>,
+    /// The width of the viewport divided by its height.
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Camera {
+    /// Creates a camera at `(0, 0, 2)` looking at the origin with a 45° vertical field of view.
+    pub fn new(aspect: f32) -> Self {
+        Self {
+            eye: Point3::new(0.0, 0.0, 2.0),
+            target: Point3::new(0.0, 0.0, 0.0),
+            up: UP,
+            fov_y: Deg(45.0),
+            aspect,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+
+    pub fn view(&self) -> Mat4 {
+        Mat4::look_at_rh(self.eye, self.target, self.up)
+    }
+
+    pub fn projection(&self) -> Mat4 {
+        perspective(self.fov_y, self.aspect, self.near, self.far)
+    }
+
+    pub fn view_projection(&self) -> Mat4 {
+        self.projection() * self.view()
+    }
+
+    /// *Returns the position of a point in logical units from the top left corner of the screen,
+    /// `None` if it is behind the camera.*
+    pub fn world_to_screen(&self, point: Point3, screen: &ScreenSpace) -> Option<Point2> {
+        let clip = self.view_projection() * point.to_homogeneous();
+        if clip.w <= 0.0 {
+            return None;
+        }
+
+        Some(screen.ndc_to_logical(clip.x / clip.w, clip.y / clip.w))
+    }
+
+    /// *Returns the point under a position in logical units from the top left corner
+    /// of the screen, at a depth between the near plane at `0.0` and the far plane at `1.0`.*
+    pub fn screen_to_world(&self, position: Point2, depth: f32, screen: &ScreenSpace) -> Point3 {
+        let (x, y) = screen.logical_to_ndc(position);
+        let inverse = self
+            .view_projection()
+            .invert()
+            .expect("The camera has a degenerate projection");
+
+        Point3::from_homogeneous(inverse * Vec4::new(x, y, depth, 1.0))
+    }
+
+    /// *Returns the ray from the near plane through a position in logical units
+    /// from the top left corner of the screen, e.g. for picking.*
+    pub fn screen_ray(&self, position: Point2, screen: &ScreenSpace) -> Ray {
+        let near = self.screen_to_world(position, 0.0, screen);
+        let far = self.screen_to_world(position, 1.0, screen);
+
+        Ray::new(near, far - near)
+    }
+}
+
+/// The size and DPI scale of the surface a camera renders to,
+/// converting between logical units, physical pixels and normalized device coordinates.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScreenSpace {
+    pub size: LogicalSize<f64>,
+    /// The ratio between physical pixels and logical units.
+    pub scale_factor: f64,
+}
+
+impl ScreenSpace {
+    pub fn new(size: LogicalSize<f64>, scale_factor: f64) -> Self {
+        Self { size, scale_factor }
+    }
+
+    /// *Returns the screen space of the surface of the display.*
+    pub fn from_display(display: &impl WindowDisplay) -> Self {
+        Self::new(display.logical_size(), display.scale_factor())
+    }
+
+    pub fn physical_size(&self) -> PhysicalSize<u32> {
+        self.size.to_physical(self.scale_factor)
+    }
+
+    /// *Returns the width divided by the height, as used by [`Camera::aspect`].*
+    pub fn aspect(&self) -> f32 {
+        (self.size.width / self.size.height) as f32
+    }
+
+    /// Converts a position in physical pixels, such as the cursor position, to logical units.
+    pub fn physical_to_logical(&self, physical: Point2) -> Point2 {
+        physical / self.scale_factor as f32
+    }
+
+    pub fn logical_to_physical(&self, logical: Point2) -> Point2 {
+        logical * self.scale_factor as f32
+    }
+
+    fn logical_to_ndc(&self, position: Point2) -> (f32, f32) {
+        (
+            position.x / self.size.width as f32 * 2.0 - 1.0,
+            1.0 - position.y / self.size.height as f32 * 2.0,
+        )
+    }
+
+    fn ndc_to_logical(&self, x: f32, y: f32) -> Point2 {
+        Point2::new(
+            (x + 1.0) / 2.0 * self.size.width as f32,
+            (1.0 - y) / 2.0 * self.size.height as f32,
+        )
+    }
+}
+
+/// An orthographic camera looking along the direction of a directional light,
+/// fitted around the bounds of the shadow casters and receivers.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LightCamera {
+    /// The direction the light travels in.
+    pub direction: Vec3,
+    /// The world space bounds covered by the shadow map.
+    pub bounds: Aabb,
+}
+
+impl LightCamera {
+    pub fn new(direction: Vec3, bounds: Aabb) -> Self {
+        Self { direction, bounds }
+    }
+
+    pub fn view(&self) -> Mat4 {
+        let direction = self.direction.normalize();
+
+        // Lights pointing straight up or down need another up vector
+        let up = if direction.cross(UP).magnitude2() < 1e-6 {
+            Vec3::unit_z()
+        } else {
+            UP
+        };
+
+        Mat4::look_to_rh(self.bounds.center(), direction, up)
+    }
+
+    /// *Returns the tightest projection containing the bounds as seen from the light.*
+    pub fn projection(&self) -> Mat4 {
+        let view = self.view();
+        let bounds = Aabb::from_points(
+            self.bounds
+                .corners()
+                .iter()
+                .map(|&corner| view.transform_point(corner)),
+        )
+        .expect("Bounds always have corners");
+
+        // The camera looks along -z, so the nearest corner has the largest z
+        orthographic(
+            bounds.min.x,
+            bounds.max.x,
+            bounds.min.y,
+            bounds.max.y,
+            -bounds.max.z,
+            -bounds.min.z,
+        )
+    }
+
+    pub fn view_projection(&self) -> Mat4 {
+        self.projection() * self.view()
+    }
+
+    /// *Returns the uniform to write to a shadow map drawn from this camera.*
+    pub fn shadow_uniform(&self, config: &ShadowMapConfig) -> ShadowUniform {
+        ShadowUniform::new(self.view_projection().into(), config)
+    }
+}
+
+/// The model-view-projection matrix in the layout expected by shaders,
+/// a column-major `mat4x4<f32>`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MvpUniform {
+    pub mvp: [[f32; 4]; 4],
+}
+
+impl MvpUniform {
+    pub const SIZE: u64 = std::mem::size_of::<Self>() as u64;
+
+    pub fn new(camera: &Camera, model: Mat4) -> Self {
+        Self {
+            mvp: (camera.view_projection() * model).into(),
+        }
+    }
+
+    /// *Returns the layout of this uniform at the given binding, visible to the vertex stage.*
+    pub fn layout(binding: u32) -> UniformLayout {
+        UniformLayout {
+            binding,
+            size: Self::SIZE,
+            visibility: ShaderStages::Vertex,
+        }
+    }
+
+    /// *Returns the uniform data to be written to a uniform buffer.*
+    pub fn as_bytes(&self) -> Vec<u8> {
+        self.mvp
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect()
+    }
+}
+
+impl Default for MvpUniform {
+    fn default() -> Self {
+        Self {
+            mvp: Mat4::identity().into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cgmath::Vector4;
+
+    /// The camera target should be projected to the center of the viewport, within the depth range.
+    #[test]
+    fn test_target_projects_to_center() {
+        let camera = Camera::new(16.0 / 9.0);
+        let clip = camera.view_projection() * Vector4::new(0.0, 0.0, 0.0, 1.0);
+        let ndc = clip.truncate() / clip.w;
+
+        assert!(ndc.x.abs() < 1e-6 && ndc.y.abs() < 1e-6);
+        assert!(ndc.z > 0.0 && ndc.z < 1.0);
+    }
+
+    /// A light camera is fitted around bounds, once with a light pointing straight down.
+    /// Every corner of the bounds should be projected within the viewport and depth range.
+    #[test]
+    fn test_light_camera_contains_bounds() {
+        let bounds = Aabb::new(Point3::new(-2.0, 0.0, -1.0), Point3::new(3.0, 1.0, 4.0));
+
+        for direction in [Vec3::new(1.0, -2.0, 0.5), Vec3::new(0.0, -1.0, 0.0)] {
+            let view_projection = LightCamera::new(direction, bounds).view_projection();
+
+            for corner in bounds.corners() {
+                let clip = view_projection * corner.to_homogeneous();
+                let ndc = clip.truncate() / clip.w;
+
+                assert!(ndc.x.abs() <= 1.0 + 1e-4 && ndc.y.abs() <= 1.0 + 1e-4);
+                assert!(ndc.z >= -1e-4 && ndc.z <= 1.0 + 1e-4);
+            }
+        }
+    }
+
+    /// The camera target and a point off to the side are converted to the screen and back.
+    /// The target should be at the center, and both points should be recovered.
+    #[test]
+    fn test_screen_round_trip() {
+        let screen = ScreenSpace::new(
+            LogicalSize {
+                width: 800.0,
+                height: 600.0,
+            },
+            2.0,
+        );
+        let camera = Camera::new(screen.aspect());
+
+        let center = camera.world_to_screen(camera.target, &screen).unwrap();
+        assert!((center - Point2::new(400.0, 300.0)).magnitude() < 1e-3);
+        assert_eq!(
+            screen.physical_to_logical(Point2::new(800.0, 600.0)),
+            center
+        );
+        assert_eq!(
+            screen.physical_size(),
+            PhysicalSize {
+                width: 1600,
+                height: 1200
+            }
+        );
+
+        let point = Point3::new(0.5, -0.25, -1.0);
+        let position = camera.world_to_screen(point, &screen).unwrap();
+        assert!(position.x > 400.0 && position.y > 300.0);
+
+        let ray = camera.screen_ray(position, &screen);
+        let distance = (point - ray.origin).magnitude();
+        assert!((ray.at(distance) - point).magnitude() < 1e-3);
+        assert_eq!(
+            camera.world_to_screen(Point3::new(0.0, 0.0, 5.0), &screen),
+            None
+        );
+    }
+
+    /// The uniform data should be the matrix columns in order.
+    #[test]
+    fn test_mvp_bytes() {
+        let uniform = MvpUniform::default();
+        let bytes = uniform.as_bytes();
+
+        assert_eq!(bytes.len() as u64, MvpUniform::SIZE);
+        assert_eq!(bytes[0..4], 1.0f32.to_ne_bytes());
+        assert_eq!(bytes[4..8], 0.0f32.to_ne_bytes());
+        assert_eq!(bytes[20..24], 1.0f32.to_ne_bytes());
+    }
+}
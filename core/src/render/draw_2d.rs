@@ -0,0 +1,219 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! An immediate 2D API for tools and overlays, drawing colored shapes without pipelines.
+
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use crate::color::RGBA;
+use crate::math::{orthographic, InnerSpace, Mat4, Point2, Rect, Vec2};
+use pluto_engine_display::pluto_engine_window::window::LogicalSize;
+use std::f32::consts::TAU;
+use std::sync::{Arc, Mutex};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Draw2DVertex {
+    pub position: Point2,
+    pub color: RGBA,
+}
+
+/// The triangles submitted during a frame, to be drawn by the display.
+pub struct Draw2DFrame {
+    /// Every three vertices form a triangle, drawn in submission order.
+    pub vertices: Vec<Draw2DVertex>,
+    /// Transforms logical units into clip space, `+Y` pointing down.
+    pub projection: Mat4,
+}
+
+#[derive(Default)]
+struct Draw2DState {
+    vertices: Vec<Draw2DVertex>,
+    screen_size: LogicalSize<f64>,
+    frame: Option<Draw2DFrame>,
+}
+
+/// A system batching 2D shapes submitted by any layer during a frame.
+///
+/// Positions are in logical units from the top left corner of the screen, later shapes are drawn
+/// over earlier ones. Provided to layers by the [`Draw2DLayer`], the display then draws the shapes
+/// after post-processing and below debug drawing.
+#[derive(Clone, Default)]
+pub struct Draw2D {
+    state: Arc<Mutex<Draw2DState>>,
+}
+
+impl Draw2D {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the logical size of the screen, kept up to date by the display.
+    pub fn set_screen_size(&self, size: LogicalSize<f64>) {
+        self.state.lock().unwrap().screen_size = size;
+    }
+
+    pub fn get_screen_size(&self) -> LogicalSize<f64> {
+        self.state.lock().unwrap().screen_size
+    }
+
+    pub fn fill_triangle(&self, a: Point2, b: Point2, c: Point2, color: RGBA) {
+        self.state
+            .lock()
+            .unwrap()
+            .vertices
+            .extend([a, b, c].map(|position| Draw2DVertex { position, color }));
+    }
+
+    /// Fills a convex quad given by its corners in order.
+    fn fill_quad(&self, corners: [Point2; 4], color: RGBA) {
+        self.fill_triangle(corners[0], corners[1], corners[2], color);
+        self.fill_triangle(corners[0], corners[2], corners[3], color);
+    }
+
+    pub fn fill_rect(&self, rect: &Rect, color: RGBA) {
+        self.fill_quad(
+            [
+                rect.min,
+                Point2::new(rect.max.x, rect.min.y),
+                rect.max,
+                Point2::new(rect.min.x, rect.max.y),
+            ],
+            color,
+        );
+    }
+
+    /// Draws the outline of a rectangle, the stroke lies inside the rectangle.
+    pub fn draw_rect(&self, rect: &Rect, width: f32, color: RGBA) {
+        let width = width.min(rect.width() / 2.0).min(rect.height() / 2.0);
+        let inner_height = rect.height() - 2.0 * width;
+
+        // The sides do not overlap, so translucent outlines have uniform corners
+        for side in [
+            Rect::new(rect.min.x, rect.min.y, rect.width(), width),
+            Rect::new(rect.min.x, rect.max.y - width, rect.width(), width),
+            Rect::new(rect.min.x, rect.min.y + width, width, inner_height),
+            Rect::new(rect.max.x - width, rect.min.y + width, width, inner_height),
+        ] {
+            self.fill_rect(&side, color);
+        }
+    }
+
+    /// Draws a line of the given width centered on the segment between the points.
+    pub fn draw_line(&self, a: Point2, b: Point2, width: f32, color: RGBA) {
+        let direction = b - a;
+        if direction.magnitude2() == 0.0 {
+            return;
+        }
+
+        let offset = direction.normalize() * (width / 2.0);
+        let normal = Vec2::new(-offset.y, offset.x);
+
+        self.fill_quad([a + normal, b + normal, b - normal, a - normal], color);
+    }
+
+    /// Fills a circle approximated by a polygon with the given number of segments.
+    pub fn fill_circle(&self, center: Point2, radius: f32, segments: u32, color: RGBA) {
+        let segments = segments.max(3);
+        let point = |i: u32| {
+            let angle = i as f32 / segments as f32 * TAU;
+            center + Vec2::new(angle.cos(), angle.sin()) * radius
+        };
+
+        for i in 0..segments {
+            self.fill_triangle(center, point(i), point(i + 1), color);
+        }
+    }
+
+    /// Collects the shapes submitted during this frame for the display,
+    /// replacing the previous frame if it was not drawn.
+    pub fn end_frame(&self) {
+        let mut state = self.state.lock().unwrap();
+        let size = state.screen_size;
+
+        state.frame = Some(Draw2DFrame {
+            vertices: std::mem::take(&mut state.vertices),
+            projection: orthographic(0.0, size.width as f32, size.height as f32, 0.0, -1.0, 1.0),
+        });
+    }
+
+    /// *Returns the shapes of the last frame if they were not drawn yet.*
+    pub fn take_frame(&self) -> Option<Draw2DFrame> {
+        self.state.lock().unwrap().frame.take()
+    }
+}
+
+impl System for Draw2D {}
+
+/// A layer providing the [`Draw2D`] system to all layers above it,
+/// ending the frame once they have been entered.
+pub struct Draw2DLayer(pub Draw2D);
+
+impl Layer for Draw2DLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        next.next(systems);
+
+        self.0.end_frame();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::{RED, WHITE};
+    use crate::math::{Point2, Rect};
+    use crate::render::draw_2d::Draw2D;
+
+    /// A filled rectangle, an outline and a line are drawn, then a second frame ends.
+    /// The first frame should contain the triangles in submission order,
+    /// the second one should be empty.
+    #[test]
+    fn test_draw_2d_frame() {
+        let draw_2d = Draw2D::new();
+
+        draw_2d.fill_rect(&Rect::new(10.0, 10.0, 100.0, 50.0), WHITE);
+        draw_2d.draw_rect(&Rect::new(0.0, 0.0, 20.0, 20.0), 2.0, RED);
+        draw_2d.draw_line(Point2::new(0.0, 0.0), Point2::new(10.0, 0.0), 4.0, RED);
+        draw_2d.end_frame();
+
+        let frame = draw_2d.take_frame().unwrap();
+        assert_eq!(frame.vertices.len(), 6 + 4 * 6 + 6);
+        assert_eq!(frame.vertices[0].color, WHITE);
+        assert_eq!(frame.vertices[2].position, Point2::new(110.0, 60.0));
+
+        let line = &frame.vertices[30..];
+        assert_eq!(line[0].position, Point2::new(0.0, 2.0));
+        assert_eq!(line[2].position, Point2::new(10.0, -2.0));
+
+        draw_2d.end_frame();
+        assert!(draw_2d.take_frame().unwrap().vertices.is_empty());
+    }
+}
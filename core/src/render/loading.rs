@@ -0,0 +1,191 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! A loading screen covering the screen with a progress bar
+//! while layers are attached deferred, see [`LoadingStatus`].
+
+use crate::application::layer::loading::LoadingStatus;
+use crate::application::layer::{Layer, LayerSwapType, LayerSystemManager, LayerWalker};
+use crate::color::{BLACK, RGBA, WHITE};
+use crate::math::{Rect, Vec2};
+use crate::render::draw_2d::Draw2D;
+use pluto_engine_display::pluto_engine_window::window::LogicalSize;
+
+/// The width of the outline of the progress bar in logical units.
+const OUTLINE_WIDTH: f32 = 2.0;
+
+/// Configuration of the [`LoadingLayer`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoadingScreen {
+    /// The color covering the screen while loading.
+    pub background: RGBA,
+    pub bar_color: RGBA,
+    /// The size of the progress bar in logical units, centered on the screen.
+    pub bar_size: Vec2,
+    /// Whether the layer detaches itself once all layers being loaded are attached.
+    pub detach_when_loaded: bool,
+}
+
+impl Default for LoadingScreen {
+    /// A white progress bar on a black screen, kept attached for later loads.
+    fn default() -> Self {
+        Self {
+            background: BLACK,
+            bar_color: WHITE,
+            bar_size: Vec2::new(320.0, 16.0),
+            detach_when_loaded: false,
+        }
+    }
+}
+
+impl LoadingScreen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn background(mut self, background: RGBA) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn bar_color(mut self, bar_color: RGBA) -> Self {
+        self.bar_color = bar_color;
+        self
+    }
+
+    pub fn bar_size(mut self, bar_size: Vec2) -> Self {
+        self.bar_size = bar_size;
+        self
+    }
+
+    pub fn detach_when_loaded(mut self, detach_when_loaded: bool) -> Self {
+        self.detach_when_loaded = detach_when_loaded;
+        self
+    }
+}
+
+/// *Returns the outline of the progress bar centered on the screen,
+/// and the part filled according to the progress inside of it.*
+pub fn progress_bar(screen_size: LogicalSize<f64>, bar_size: Vec2, progress: f32) -> (Rect, Rect) {
+    let outline = Rect::new(
+        (screen_size.width as f32 - bar_size.x) / 2.0,
+        (screen_size.height as f32 - bar_size.y) / 2.0,
+        bar_size.x,
+        bar_size.y,
+    );
+
+    let inset = OUTLINE_WIDTH * 2.0;
+    let fill = Rect::new(
+        outline.min.x + inset,
+        outline.min.y + inset,
+        (outline.width() - inset * 2.0).max(0.0) * progress.clamp(0.0, 1.0),
+        (outline.height() - inset * 2.0).max(0.0),
+    );
+
+    (outline, fill)
+}
+
+/// A layer drawing the [`LoadingScreen`] over the layers above it while layers are being loaded.
+///
+/// *Requires the [`Draw2D`] system, the layer should be added with a high priority
+/// so the layers being loaded are attached below it.*
+pub struct LoadingLayer {
+    screen: LoadingScreen,
+    was_loading: bool,
+    loaded: bool,
+}
+
+impl LoadingLayer {
+    pub fn new(screen: LoadingScreen) -> Self {
+        Self {
+            screen,
+            was_loading: false,
+            loaded: false,
+        }
+    }
+
+    fn draw(&self, draw_2d: &Draw2D, progress: f32) {
+        let screen_size = draw_2d.get_screen_size();
+        draw_2d.fill_rect(
+            &Rect::new(
+                0.0,
+                0.0,
+                screen_size.width as f32,
+                screen_size.height as f32,
+            ),
+            self.screen.background,
+        );
+
+        let (outline, fill) = progress_bar(screen_size, self.screen.bar_size, progress);
+        draw_2d.draw_rect(&outline, OUTLINE_WIDTH, self.screen.bar_color);
+        draw_2d.fill_rect(&fill, self.screen.bar_color);
+    }
+}
+
+impl Layer for LoadingLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        (self.screen.detach_when_loaded && self.loaded).then_some(LayerSwapType::Synchronous)
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        let (loading, progress) = systems
+            .query::<LoadingStatus>()
+            .map_or((false, 1.0), |status| {
+                (status.is_loading(), status.progress())
+            });
+
+        self.loaded |= self.was_loading && !loading;
+        self.was_loading = loading;
+
+        next.next(systems);
+
+        // Drawn after the layers above, so the screen covers them
+        if loading {
+            if let Some(draw_2d) = systems.query::<Draw2D>() {
+                self.draw(draw_2d, progress);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{Rect, Vec2};
+    use crate::render::loading::progress_bar;
+    use pluto_engine_display::pluto_engine_window::window::LogicalSize;
+
+    /// A bar of 100 by 20 units at a quarter of the progress is placed on a 200 by 100 screen.
+    /// The bar should be centered, filling a quarter of its inside.
+    #[test]
+    fn test_progress_bar() {
+        let screen_size = LogicalSize {
+            width: 200.0,
+            height: 100.0,
+        };
+        let (outline, fill) = progress_bar(screen_size, Vec2::new(100.0, 20.0), 0.25);
+
+        assert_eq!(outline, Rect::new(50.0, 40.0, 100.0, 20.0));
+        assert_eq!(fill, Rect::new(54.0, 44.0, 23.0, 12.0));
+    }
+}
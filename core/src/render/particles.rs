@@ -0,0 +1,462 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! CPU-simulated particles, drawn by the platform as instanced camera-facing quads.
+
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use crate::application::time::Time;
+use crate::color::{Color, RGBA, WHITE};
+use crate::math::{MetricSpace, Point3, Vec3};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+/// Describes how an emitter spawns particles and how they change over their lifetime.
+///
+/// Values given as a range are picked uniformly per particle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParticleEmitterDesc {
+    /// The number of particles spawned per second.
+    pub rate: f32,
+    /// The lifetime of a particle in seconds.
+    pub lifetime: Range<f32>,
+    /// The initial velocity of a particle, each component is picked from `velocity_min`
+    /// to `velocity_max`.
+    pub velocity_min: Vec3,
+    pub velocity_max: Vec3,
+    /// The acceleration applied to every particle.
+    pub gravity: Vec3,
+    /// The size of a particle at the start and at the end of its lifetime.
+    pub size: Range<f32>,
+    /// The color of a particle at the start of its lifetime,
+    /// interpolated towards `color_end` with [`Color::lerp`].
+    pub color_start: RGBA,
+    pub color_end: RGBA,
+    /// The maximum number of particles alive at once, no particles are spawned above it.
+    pub max_particles: usize,
+}
+
+impl Default for ParticleEmitterDesc {
+    /// Spawns ten white particles per second, fading out over a second.
+    fn default() -> Self {
+        Self {
+            rate: 10.0,
+            lifetime: 1.0..1.0,
+            velocity_min: Vec3::new(0.0, 0.0, 0.0),
+            velocity_max: Vec3::new(0.0, 0.0, 0.0),
+            gravity: Vec3::new(0.0, 0.0, 0.0),
+            size: 0.1..0.1,
+            color_start: WHITE,
+            color_end: RGBA { a: 0.0, ..WHITE },
+            max_particles: 1000,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Particle {
+    position: Point3,
+    velocity: Vec3,
+    age: f32,
+    lifetime: f32,
+}
+
+/// A particle ready to be drawn, with its properties at its current age.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParticleDraw {
+    pub position: Point3,
+    pub size: f32,
+    pub color: RGBA,
+}
+
+/// A fast pseudo-random generator, the particles do not need more.
+#[derive(Copy, Clone, Debug)]
+struct XorShift(u32);
+
+impl XorShift {
+    fn new(seed: u32) -> Self {
+        // The state must never be zero
+        Self(seed.wrapping_mul(0x9E37_79B9) | 1)
+    }
+
+    /// *Returns a value from `0` to `1`.*
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+/// Spawns and simulates the particles of a single [`ParticleEmitterDesc`].
+#[derive(Clone, Debug)]
+pub struct ParticleEmitter {
+    desc: ParticleEmitterDesc,
+    position: Point3,
+    emitting: bool,
+    particles: Vec<Particle>,
+    /// The fraction of a particle left over from previous updates.
+    spawn_accumulator: f32,
+    rng: XorShift,
+}
+
+impl ParticleEmitter {
+    pub fn new(desc: ParticleEmitterDesc, position: Point3, seed: u32) -> Self {
+        Self {
+            desc,
+            position,
+            emitting: true,
+            particles: Vec::new(),
+            spawn_accumulator: 0.0,
+            rng: XorShift::new(seed),
+        }
+    }
+
+    pub fn get_desc(&self) -> &ParticleEmitterDesc {
+        &self.desc
+    }
+
+    /// Particles already spawned keep the old description until they die.
+    pub fn set_desc(&mut self, desc: ParticleEmitterDesc) {
+        self.desc = desc;
+    }
+
+    pub fn get_position(&self) -> Point3 {
+        self.position
+    }
+
+    /// Moves the emitter, particles already spawned are not moved.
+    pub fn set_position(&mut self, position: Point3) {
+        self.position = position;
+    }
+
+    /// Stops or resumes spawning particles at the rate of the emitter,
+    /// particles already spawned live out their lifetime.
+    pub fn set_emitting(&mut self, emitting: bool) {
+        self.emitting = emitting;
+        self.spawn_accumulator = 0.0;
+    }
+
+    pub fn is_emitting(&self) -> bool {
+        self.emitting
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Spawns particles at once, regardless of the rate, up to the maximum number of particles.
+    pub fn burst(&mut self, count: usize) {
+        let count = count.min(self.desc.max_particles.saturating_sub(self.particles.len()));
+
+        for _ in 0..count {
+            let desc = &self.desc;
+            let rng = &mut self.rng;
+
+            let particle = Particle {
+                position: self.position,
+                velocity: Vec3::new(
+                    rng.range(desc.velocity_min.x, desc.velocity_max.x),
+                    rng.range(desc.velocity_min.y, desc.velocity_max.y),
+                    rng.range(desc.velocity_min.z, desc.velocity_max.z),
+                ),
+                age: 0.0,
+                lifetime: rng.range(desc.lifetime.start, desc.lifetime.end),
+            };
+
+            self.particles.push(particle);
+        }
+    }
+
+    /// Advances the particles by `delta` seconds, removing dead particles and spawning new ones.
+    pub fn update(&mut self, delta: f32) {
+        let gravity = self.desc.gravity;
+
+        self.particles.retain_mut(|particle| {
+            particle.age += delta;
+            particle.velocity += gravity * delta;
+            particle.position += particle.velocity * delta;
+
+            particle.age < particle.lifetime
+        });
+
+        if self.emitting {
+            self.spawn_accumulator += self.desc.rate.max(0.0) * delta;
+            let count = self.spawn_accumulator.floor();
+            self.spawn_accumulator -= count;
+
+            self.burst(count as usize);
+        }
+    }
+
+    /// *Returns the particle with its size and color interpolated by its age.*
+    fn draw(&self, particle: &Particle) -> ParticleDraw {
+        let ratio = if particle.lifetime > 0.0 {
+            (particle.age / particle.lifetime).min(1.0)
+        } else {
+            1.0
+        };
+
+        let size = &self.desc.size;
+
+        ParticleDraw {
+            position: particle.position,
+            size: size.start + (size.end - size.start) * ratio,
+            color: self.desc.color_start.lerp(self.desc.color_end, ratio),
+        }
+    }
+
+    /// Adds the particles of this emitter to `particles`, in the order they were spawned.
+    pub fn collect_particles(&self, particles: &mut Vec<ParticleDraw>) {
+        particles.extend(self.particles.iter().map(|particle| self.draw(particle)));
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ParticleEmitterHandle(u64);
+
+#[derive(Default)]
+struct ParticleSystemState {
+    emitters: Vec<(ParticleEmitterHandle, ParticleEmitter)>,
+    next_handle: u64,
+}
+
+impl ParticleSystemState {
+    fn emitter(&mut self, handle: ParticleEmitterHandle) -> Option<&mut ParticleEmitter> {
+        self.emitters
+            .iter_mut()
+            .find(|(emitter_handle, _)| *emitter_handle == handle)
+            .map(|(_, emitter)| emitter)
+    }
+}
+
+/// A system owning all particle emitters, simulated once per frame by the [`ParticleLayer`].
+///
+/// Layers add emitters and collect the particles to draw them,
+/// with the `WgpuParticleRenderer` on the `wgpu` platform.
+#[derive(Clone, Default)]
+pub struct ParticleSystem {
+    state: Arc<Mutex<ParticleSystemState>>,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_emitter(
+        &self,
+        desc: ParticleEmitterDesc,
+        position: Point3,
+    ) -> ParticleEmitterHandle {
+        let mut state = self.state.lock().unwrap();
+        let handle = ParticleEmitterHandle(state.next_handle);
+        state.next_handle += 1;
+
+        let emitter = ParticleEmitter::new(desc, position, handle.0 as u32);
+        state.emitters.push((handle, emitter));
+
+        handle
+    }
+
+    /// Removes the emitter along with its particles.
+    ///
+    /// *Returns the emitter if it was present.*
+    pub fn remove_emitter(&self, handle: ParticleEmitterHandle) -> Option<ParticleEmitter> {
+        let mut state = self.state.lock().unwrap();
+        let index = state
+            .emitters
+            .iter()
+            .position(|(emitter_handle, _)| *emitter_handle == handle)?;
+
+        Some(state.emitters.remove(index).1)
+    }
+
+    /// Runs a closure with the emitter, e.g. to move it or spawn a burst of particles.
+    ///
+    /// *Returns `None` if the emitter was removed.*
+    pub fn with_emitter<R>(
+        &self,
+        handle: ParticleEmitterHandle,
+        f: impl FnOnce(&mut ParticleEmitter) -> R,
+    ) -> Option<R> {
+        self.state.lock().unwrap().emitter(handle).map(f)
+    }
+
+    /// Advances all emitters by `delta` seconds.
+    pub fn update(&self, delta: f32) {
+        for (_, emitter) in &mut self.state.lock().unwrap().emitters {
+            emitter.update(delta);
+        }
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .emitters
+            .iter()
+            .map(|(_, emitter)| emitter.particle_count())
+            .sum()
+    }
+
+    /// *Returns the particles of all emitters sorted back to front as seen from `eye`,
+    /// as required for alpha blending.*
+    pub fn collect_particles(&self, eye: Point3) -> Vec<ParticleDraw> {
+        let mut particles = Vec::new();
+
+        for (_, emitter) in &self.state.lock().unwrap().emitters {
+            emitter.collect_particles(&mut particles);
+        }
+
+        particles.sort_by(|a, b| {
+            b.position
+                .distance2(eye)
+                .total_cmp(&a.position.distance2(eye))
+        });
+
+        particles
+    }
+}
+
+impl System for ParticleSystem {}
+
+#[cfg(feature = "pe_render_wgpu")]
+mod platform {
+    use crate::math::InnerSpace;
+    use crate::render::camera::Camera;
+    use crate::render::particles::ParticleDraw;
+    use pluto_engine_core_platform_wgpu::particles::{ParticleInstance, ParticleView};
+
+    impl From<ParticleDraw> for ParticleInstance {
+        fn from(particle: ParticleDraw) -> Self {
+            let color = particle.color;
+
+            Self {
+                position: particle.position.into(),
+                size: particle.size,
+                color: [color.r, color.g, color.b, color.a],
+            }
+        }
+    }
+
+    impl From<&Camera> for ParticleView {
+        fn from(camera: &Camera) -> Self {
+            let forward = (camera.target - camera.eye).normalize();
+            let right = forward.cross(camera.up).normalize();
+            let up = right.cross(forward);
+            Self {
+                view_projection: camera.view_projection().into(),
+                camera_right: right.into(),
+                camera_up: up.into(),
+            }
+        }
+    }
+}
+
+/// A layer providing the [`ParticleSystem`] to all layers above it,
+/// simulating it by the delta of the frame before entering them.
+pub struct ParticleLayer(pub ParticleSystem);
+
+impl Layer for ParticleLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        if let Some(time) = systems.query::<Time>() {
+            self.0.update(time.delta_seconds());
+        }
+
+        next.next(systems);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::{Color, RED, YELLOW};
+    use crate::math::{Point3, Vec3};
+    use crate::render::particles::{ParticleEmitterDesc, ParticleSystem};
+
+    /// Particles are spawned at a rate of ten per second with a one second lifetime,
+    /// then the emitter is updated past the lifetime of the first particles.
+    /// The particles should be spawned at the rate and removed once their lifetime ends.
+    #[test]
+    fn test_particle_spawn_rate() {
+        let system = ParticleSystem::new();
+        system.add_emitter(ParticleEmitterDesc::default(), Point3::new(0.0, 0.0, 0.0));
+
+        system.update(0.55);
+        assert_eq!(system.particle_count(), 5);
+
+        system.update(0.5);
+        assert_eq!(system.particle_count(), 10);
+
+        system.update(0.5);
+        assert_eq!(system.particle_count(), 10);
+    }
+
+    /// A burst is spawned with a fixed velocity and gravity and updated to half its lifetime.
+    /// The particles should have moved and their color and size should be halfway
+    /// between the start and end values.
+    #[test]
+    fn test_particle_over_life() {
+        let system = ParticleSystem::new();
+        let emitter = system.add_emitter(
+            ParticleEmitterDesc {
+                rate: 0.0,
+                lifetime: 2.0..2.0,
+                velocity_min: Vec3::new(1.0, 0.0, 0.0),
+                velocity_max: Vec3::new(1.0, 0.0, 0.0),
+                gravity: Vec3::new(0.0, -2.0, 0.0),
+                size: 1.0..3.0,
+                color_start: RED,
+                color_end: YELLOW,
+                max_particles: 3,
+            },
+            Point3::new(0.0, 0.0, 0.0),
+        );
+
+        system.with_emitter(emitter, |emitter| emitter.burst(5));
+        system.update(1.0);
+
+        let particles = system.collect_particles(Point3::new(0.0, 0.0, 10.0));
+        assert_eq!(particles.len(), 3);
+        assert_eq!(particles[0].position, Point3::new(1.0, -2.0, 0.0));
+        assert_eq!(particles[0].size, 2.0);
+        assert_eq!(particles[0].color, RED.lerp(YELLOW, 0.5));
+
+        assert!(system.remove_emitter(emitter).is_some());
+        assert_eq!(system.particle_count(), 0);
+    }
+}
@@ -0,0 +1,155 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Selecting objects under the cursor, see [`pluto_engine_display::pluto_engine_render::picking`].
+
+use crate::application::layer::{Layer, LayerDependencyDeclaration, LayerSwapType};
+use crate::application::system::System;
+use crate::math::Point2;
+use pluto_engine_display::pluto_engine_render::device::Queue;
+use pluto_engine_display::pluto_engine_render::picking::DevicePicking;
+use pluto_engine_display::pluto_engine_render::target::RenderTarget;
+use pluto_engine_display::pluto_engine_render::texture::ReadbackError;
+use std::sync::{Arc, Mutex};
+
+/// The id of the object at the picked position, `None` if there is no object.
+pub type PickResult = Result<Option<u32>, ReadbackError>;
+
+type PickSlot = Arc<Mutex<Option<PickResult>>>;
+
+/// A system for reading back the ids of objects drawn at positions on the screen.
+///
+/// Provided by the [`PickingLayer`], the ids are read back
+/// by the renderer using a [`PickingReader`] sharing this system.
+#[derive(Clone, Default)]
+pub struct Picking {
+    requests: Arc<Mutex<Vec<(Point2, PickSlot)>>>,
+}
+
+impl Picking {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests the id at a position in physical pixels from the top left corner of the screen,
+    /// such as the cursor position, in the next rendered frame.
+    ///
+    /// Positions outside of the screen pick no object.
+    pub fn pick(&self, position: Point2) -> PendingPick {
+        let slot = PickSlot::default();
+        self.requests.lock().unwrap().push((position, slot.clone()));
+        PendingPick(slot)
+    }
+
+    /// Returns `true` if a pick was requested and not yet started by the renderer.
+    pub fn is_requested(&self) -> bool {
+        !self.requests.lock().unwrap().is_empty()
+    }
+
+    fn take_requests(&self) -> Vec<(Point2, PickSlot)> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+impl System for Picking {}
+
+/// A pick that has been requested but may not have been read back from the GPU yet.
+pub struct PendingPick(PickSlot);
+
+impl PendingPick {
+    /// Checks whether the id is ready, without blocking.
+    ///
+    /// *Returns `Some` exactly once, when the id is available.*
+    pub fn poll_result(&mut self) -> Option<PickResult> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+/// The renderer side of the [`Picking`] system, reading back ids from a pick target.
+///
+/// `R` is the pick readback type of the device, see [`DevicePicking`].
+pub struct PickingReader<R> {
+    picking: Picking,
+    in_flight: Vec<(R, PickSlot)>,
+}
+
+impl<R> PickingReader<R> {
+    pub fn new(picking: Picking) -> Self {
+        Self {
+            picking,
+            in_flight: Vec::new(),
+        }
+    }
+
+    /// Starts the requested picks from the target and completes the finished ones.
+    ///
+    /// Should be called once per frame, after the ids of the frame are drawn into the target.
+    pub fn on_frame_rendered<'a, D, Q>(
+        &mut self,
+        device: &D,
+        queue: &Q,
+        target: &D::RenderTargetType,
+    ) where
+        D: DevicePicking<'a, Q, PickReadbackType = R>,
+        Q: Queue<'a>,
+    {
+        let size = target.get_size();
+
+        for (position, slot) in self.picking.take_requests() {
+            let inside = position.x >= 0.0
+                && position.y >= 0.0
+                && (position.x as u32) < size.width
+                && (position.y as u32) < size.height;
+
+            if inside {
+                let readback =
+                    device.read_pick_id(queue, target, position.x as u32, position.y as u32);
+                self.in_flight.push((readback, slot));
+            } else {
+                *slot.lock().unwrap() = Some(Ok(None));
+            }
+        }
+
+        self.in_flight.retain_mut(|(readback, slot)| {
+            let Some(result) = device.poll_pick_id(readback) else {
+                return true;
+            };
+
+            *slot.lock().unwrap() = Some(result);
+            false
+        });
+    }
+}
+
+/// A layer providing the [`Picking`] system to all layers above it.
+pub struct PickingLayer(pub Picking);
+
+impl Layer for PickingLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+}
@@ -0,0 +1,386 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! A hierarchy of transformed nodes with bounds, culled against the camera every frame.
+
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use crate::math::{BoundingVolume, Frustum, Mat4, SquareMatrix, Transform};
+use crate::render::stats::{CullingStats, RenderStats};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct NodeId(u64);
+
+/// A node of the [`SceneGraph`], transformed relative to its parent.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SceneNode {
+    pub transform: Transform,
+    /// The bounds in the space of the node, nodes without bounds are never culled.
+    pub bounds: Option<BoundingVolume>,
+}
+
+impl SceneNode {
+    pub fn new(transform: Transform) -> Self {
+        Self {
+            transform,
+            bounds: None,
+        }
+    }
+
+    pub fn with_bounds(mut self, bounds: impl Into<BoundingVolume>) -> Self {
+        self.bounds = Some(bounds.into());
+        self
+    }
+}
+
+/// A node which passed culling, along with its world matrix.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VisibleNode {
+    pub id: NodeId,
+    pub world: Mat4,
+}
+
+/// The nodes visible to the camera, parents before their children.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VisibleSet {
+    pub nodes: Vec<VisibleNode>,
+    pub stats: CullingStats,
+}
+
+impl VisibleSet {
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes.iter().any(|node| node.id == id)
+    }
+}
+
+struct NodeEntry {
+    node: SceneNode,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+#[derive(Default)]
+struct SceneState {
+    next_id: u64,
+    nodes: BTreeMap<NodeId, NodeEntry>,
+    view_projection: Option<Mat4>,
+    visible: VisibleSet,
+}
+
+impl SceneState {
+    /// Visits the node and its descendants, parents before their children.
+    fn visit(&self, id: NodeId, parent: Mat4, visitor: &mut impl FnMut(NodeId, &SceneNode, Mat4)) {
+        let entry = &self.nodes[&id];
+        let world = parent * entry.node.transform.matrix();
+
+        visitor(id, &entry.node, world);
+
+        for &child in &entry.children {
+            self.visit(child, world, visitor);
+        }
+    }
+
+    fn visit_all(&self, mut visitor: impl FnMut(NodeId, &SceneNode, Mat4)) {
+        let roots = self
+            .nodes
+            .iter()
+            .filter(|(_, entry)| entry.parent.is_none())
+            .map(|(&id, _)| id);
+
+        for root in roots {
+            self.visit(root, Mat4::identity(), &mut visitor);
+        }
+    }
+}
+
+/// A system holding the scene graph and the nodes visible in the current frame.
+///
+/// Provided by the [`SceneLayer`], which culls the nodes against the camera
+/// before the layers above it are entered.
+#[derive(Clone, Default)]
+pub struct SceneGraph(Arc<Mutex<SceneState>>);
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node as a child of the parent, or as a root if there is none.
+    ///
+    /// ***Panics** if the parent is not in the graph.*
+    pub fn add_node(&self, parent: Option<NodeId>, node: SceneNode) -> NodeId {
+        let mut state = self.0.lock().unwrap();
+        let id = NodeId(state.next_id);
+        state.next_id += 1;
+
+        if let Some(parent) = parent {
+            state
+                .nodes
+                .get_mut(&parent)
+                .expect("The parent is not in the scene graph")
+                .children
+                .push(id);
+        }
+
+        state.nodes.insert(
+            id,
+            NodeEntry {
+                node,
+                parent,
+                children: Vec::new(),
+            },
+        );
+
+        id
+    }
+
+    /// Removes the node and all of its descendants.
+    ///
+    /// *Returns `false` if the node is not in the graph.*
+    pub fn remove_node(&self, id: NodeId) -> bool {
+        let mut state = self.0.lock().unwrap();
+
+        let Some(entry) = state.nodes.remove(&id) else {
+            return false;
+        };
+
+        if let Some(parent) = entry.parent.and_then(|parent| state.nodes.get_mut(&parent)) {
+            parent.children.retain(|&child| child != id);
+        }
+
+        let mut removed = entry.children;
+        while let Some(child) = removed.pop() {
+            if let Some(entry) = state.nodes.remove(&child) {
+                removed.extend(entry.children);
+            }
+        }
+
+        true
+    }
+
+    pub fn get_node(&self, id: NodeId) -> Option<SceneNode> {
+        self.0
+            .lock()
+            .unwrap()
+            .nodes
+            .get(&id)
+            .map(|entry| entry.node)
+    }
+
+    pub fn get_parent(&self, id: NodeId) -> Option<NodeId> {
+        self.0.lock().unwrap().nodes.get(&id)?.parent
+    }
+
+    /// *Returns `false` if the node is not in the graph.*
+    pub fn set_transform(&self, id: NodeId, transform: Transform) -> bool {
+        self.update_node(id, |node| node.transform = transform)
+    }
+
+    /// *Returns `false` if the node is not in the graph.*
+    pub fn set_bounds(&self, id: NodeId, bounds: Option<BoundingVolume>) -> bool {
+        self.update_node(id, |node| node.bounds = bounds)
+    }
+
+    fn update_node(&self, id: NodeId, update: impl FnOnce(&mut SceneNode)) -> bool {
+        match self.0.lock().unwrap().nodes.get_mut(&id) {
+            Some(entry) => {
+                update(&mut entry.node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// *Returns the transform of the node combined with those of its ancestors.*
+    pub fn world_matrix(&self, id: NodeId) -> Option<Mat4> {
+        let state = self.0.lock().unwrap();
+        let mut entry = state.nodes.get(&id)?;
+        let mut matrix = entry.node.transform.matrix();
+
+        while let Some(parent) = entry.parent {
+            entry = &state.nodes[&parent];
+            matrix = entry.node.transform.matrix() * matrix;
+        }
+
+        Some(matrix)
+    }
+
+    /// Sets the view-projection matrix the nodes are culled against,
+    /// e.g. [`Camera::view_projection`](crate::render::camera::Camera::view_projection).
+    ///
+    /// *Nothing is culled until a camera is set.*
+    pub fn set_view_projection(&self, view_projection: Mat4) {
+        self.0.lock().unwrap().view_projection = Some(view_projection);
+    }
+
+    /// Tests the bounds of all nodes against the frustum.
+    pub fn cull(&self, frustum: &Frustum) -> VisibleSet {
+        let state = self.0.lock().unwrap();
+        let mut visible = VisibleSet::default();
+
+        state.visit_all(|id, node, world| {
+            if let Some(bounds) = &node.bounds {
+                visible.stats.tested += 1;
+
+                if !frustum.intersects(&bounds.transformed(&world)) {
+                    visible.stats.culled += 1;
+                    return;
+                }
+            }
+
+            visible.nodes.push(VisibleNode { id, world });
+        });
+
+        visible.stats.visible = visible.nodes.len() as u32;
+        visible
+    }
+
+    /// Culls the nodes against the camera, replacing the visible set.
+    ///
+    /// *Called by the [`SceneLayer`] every frame.*
+    pub fn update_visible(&self) -> CullingStats {
+        let view_projection = self.0.lock().unwrap().view_projection;
+
+        let visible = match view_projection {
+            Some(view_projection) => self.cull(&Frustum::from_view_projection(&view_projection)),
+            None => {
+                let mut visible = VisibleSet::default();
+                self.0
+                    .lock()
+                    .unwrap()
+                    .visit_all(|id, _, world| visible.nodes.push(VisibleNode { id, world }));
+                visible.stats.visible = visible.nodes.len() as u32;
+                visible
+            }
+        };
+
+        let stats = visible.stats;
+        self.0.lock().unwrap().visible = visible;
+        stats
+    }
+
+    /// *Returns the nodes visible in the current frame.*
+    pub fn visible(&self) -> VisibleSet {
+        self.0.lock().unwrap().visible.clone()
+    }
+}
+
+impl System for SceneGraph {}
+
+/// A layer providing the [`SceneGraph`] system to all layers above it.
+///
+/// The visible set is updated before the layers above are entered, and the culling
+/// statistics are recorded to [`RenderStats`] if a layer below provides them.
+pub struct SceneLayer(pub SceneGraph);
+
+impl Layer for SceneLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        let stats = self.0.update_visible();
+
+        if let Some(render_stats) = systems.query::<RenderStats>() {
+            render_stats.record_culling(stats);
+        }
+
+        next.next(systems);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{Aabb, Deg, Mat4, Point3, Sphere, Transform, Vec3};
+    use crate::render::camera::Camera;
+    use crate::render::scene::{SceneGraph, SceneNode};
+    use crate::render::stats::CullingStats;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5))
+    }
+
+    /// A parent with a visible child and a child moved behind the camera is culled.
+    /// Only the child behind the camera should be culled, with the parent transform applied.
+    #[test]
+    fn test_cull_hierarchy() {
+        let scene = SceneGraph::new();
+        let parent = scene.add_node(
+            None,
+            SceneNode::new(Transform::from_translation(Vec3::new(0.0, 0.0, -5.0))),
+        );
+        let visible = scene.add_node(Some(parent), SceneNode::default().with_bounds(unit_box()));
+        let hidden = scene.add_node(
+            Some(parent),
+            SceneNode::new(Transform::from_translation(Vec3::new(0.0, 0.0, 20.0)))
+                .with_bounds(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0)),
+        );
+
+        let camera = Camera {
+            fov_y: Deg(60.0),
+            ..Camera::new(1.0)
+        };
+        scene.set_view_projection(camera.view_projection());
+
+        let stats = scene.update_visible();
+        assert_eq!(
+            stats,
+            CullingStats {
+                tested: 2,
+                culled: 1,
+                visible: 2,
+            }
+        );
+
+        let set = scene.visible();
+        assert!(set.contains(parent) && set.contains(visible) && !set.contains(hidden));
+        assert_eq!(set.nodes[1].world, scene.world_matrix(visible).unwrap());
+        assert_eq!(
+            scene.world_matrix(hidden).unwrap(),
+            Mat4::from_translation(Vec3::new(0.0, 0.0, 15.0))
+        );
+    }
+
+    /// A node with a child is removed.
+    /// The child should be removed along with it.
+    #[test]
+    fn test_remove_subtree() {
+        let scene = SceneGraph::new();
+        let root = scene.add_node(None, SceneNode::default());
+        let parent = scene.add_node(Some(root), SceneNode::default());
+        let child = scene.add_node(Some(parent), SceneNode::default());
+
+        assert!(scene.remove_node(parent));
+        assert!(!scene.remove_node(child));
+        assert_eq!(scene.get_parent(child), None);
+        assert_eq!(scene.update_visible().visible, 1);
+    }
+}
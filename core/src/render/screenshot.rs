@@ -0,0 +1,135 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::application::layer::{Layer, LayerDependencyDeclaration, LayerSwapType};
+use crate::application::system::System;
+use pluto_engine_display::pluto_engine_render::device::{DeviceTextureReader, Queue};
+use pluto_engine_display::pluto_engine_render::texture::{ReadbackError, TexturePixels};
+use std::sync::{Arc, Mutex};
+
+type ScreenshotResult = Result<TexturePixels, ReadbackError>;
+
+type ScreenshotSlot = Arc<Mutex<Option<ScreenshotResult>>>;
+
+/// A system for capturing the frames presented to the display.
+///
+/// Provided by the [`ScreenshotLayer`], the captures are performed
+/// by the renderer using a [`ScreenshotCapturer`] sharing this system.
+#[derive(Clone, Default)]
+pub struct Screenshot {
+    requests: Arc<Mutex<Vec<ScreenshotSlot>>>,
+}
+
+impl Screenshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests a capture of the next rendered frame.
+    pub fn capture(&self) -> PendingScreenshot {
+        let slot = ScreenshotSlot::default();
+        self.requests.lock().unwrap().push(slot.clone());
+        PendingScreenshot(slot)
+    }
+
+    /// Returns `true` if a capture was requested and not yet started by the renderer.
+    pub fn is_requested(&self) -> bool {
+        !self.requests.lock().unwrap().is_empty()
+    }
+
+    fn take_requests(&self) -> Vec<ScreenshotSlot> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+impl System for Screenshot {}
+
+/// A screenshot that has been requested but may not have been read back from the GPU yet.
+pub struct PendingScreenshot(ScreenshotSlot);
+
+impl PendingScreenshot {
+    /// Checks whether the screenshot is ready, without blocking.
+    ///
+    /// *Returns `Some` exactly once, when the pixels are available.*
+    pub fn poll_result(&mut self) -> Option<ScreenshotResult> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+/// The renderer side of the [`Screenshot`] system, copying rendered frames into CPU memory.
+///
+/// `R` is the readback type of the device, see [`DeviceTextureReader`].
+pub struct ScreenshotCapturer<R> {
+    screenshot: Screenshot,
+    in_flight: Vec<(R, Vec<ScreenshotSlot>)>,
+}
+
+impl<R> ScreenshotCapturer<R> {
+    pub fn new(screenshot: Screenshot) -> Self {
+        Self {
+            screenshot,
+            in_flight: Vec::new(),
+        }
+    }
+
+    /// Starts the requested captures of the rendered texture and completes the finished ones.
+    ///
+    /// Should be called once per frame, after the frame is rendered and before it is presented.
+    pub fn on_frame_rendered<'a, D, Q, T>(&mut self, device: &D, queue: &Q, texture: &T)
+    where
+        D: DeviceTextureReader<'a, Q, T, ReadbackType = R>,
+        Q: Queue<'a>,
+    {
+        let requests = self.screenshot.take_requests();
+        if !requests.is_empty() {
+            self.in_flight
+                .push((device.read_pixels(queue, texture), requests));
+        }
+
+        self.in_flight.retain_mut(|(readback, slots)| {
+            let Some(result) = device.poll_readback(readback) else {
+                return true;
+            };
+
+            for slot in slots.iter() {
+                *slot.lock().unwrap() = Some(result.clone());
+            }
+
+            false
+        });
+    }
+}
+
+/// A layer providing the [`Screenshot`] system to all layers above it.
+pub struct ScreenshotLayer(pub Screenshot);
+
+impl Layer for ScreenshotLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+}
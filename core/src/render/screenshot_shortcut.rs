@@ -0,0 +1,273 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! A keyboard shortcut saving screenshots to disk.
+
+use crate::application::layer::{Layer, LayerSwapType, LayerSystemManager, LayerWalker};
+use crate::input::keyboard::Keyboard;
+use crate::render::screenshot::{PendingScreenshot, Screenshot};
+use log::{error, info, warn};
+use pluto_engine_display::pluto_engine_render::texture::TexturePixels;
+use pluto_engine_display::pluto_engine_window::keyboard::Key;
+use pluto_io::jobs::JobPool;
+use std::path::{Path, PathBuf};
+
+/// Information stored in saved screenshots, useful when they are attached to bug reports.
+#[derive(Clone, Debug, Default)]
+pub struct ScreenshotMetadata {
+    pub build_version: String,
+    pub scene_name: Option<String>,
+}
+
+/// Encodes a screenshot as a PNG, with the metadata stored in text chunks.
+pub fn encode_png(
+    pixels: &TexturePixels,
+    metadata: &ScreenshotMetadata,
+) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png = Vec::new();
+
+    {
+        let mut encoder = png::Encoder::new(&mut png, pixels.size.width, pixels.size.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        encoder.add_itxt_chunk("Software".to_owned(), "PlutoEngine".to_owned())?;
+        encoder.add_itxt_chunk("Version".to_owned(), metadata.build_version.clone())?;
+
+        if let Some(scene_name) = &metadata.scene_name {
+            encoder.add_itxt_chunk("Scene".to_owned(), scene_name.clone())?;
+        }
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&pixels.data)?;
+    }
+
+    Ok(png)
+}
+
+/// *Returns the platform pictures directory, falling back to the working directory.*
+pub fn default_screenshot_dir() -> PathBuf {
+    let xdg_pictures = std::env::var_os("XDG_PICTURES_DIR").filter(|_| cfg!(unix));
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+
+    xdg_pictures
+        .map(PathBuf::from)
+        .or_else(|| home.map(|home| Path::new(&home).join("Pictures")))
+        .unwrap_or_default()
+        .join("Screenshots")
+}
+
+type ClipboardHandler = Box<dyn FnMut(&TexturePixels)>;
+
+/// Configuration of the [`ScreenshotShortcutLayer`].
+pub struct ScreenshotShortcut {
+    pub key: Key,
+    pub directory: PathBuf,
+    pub metadata: ScreenshotMetadata,
+    clipboard: Option<ClipboardHandler>,
+}
+
+impl ScreenshotShortcut {
+    /// Saves screenshots to [`default_screenshot_dir`] when Print Screen is pressed.
+    pub fn new(build_version: impl Into<String>) -> Self {
+        Self {
+            key: Key::PrintScreen,
+            directory: default_screenshot_dir(),
+            metadata: ScreenshotMetadata {
+                build_version: build_version.into(),
+                scene_name: None,
+            },
+            clipboard: None,
+        }
+    }
+
+    pub fn key(mut self, key: Key) -> Self {
+        self.key = key;
+        self
+    }
+
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = directory.into();
+        self
+    }
+
+    pub fn scene_name(mut self, scene_name: impl Into<String>) -> Self {
+        self.metadata.scene_name = Some(scene_name.into());
+        self
+    }
+
+    /// Also passes every screenshot to a handler copying it to the clipboard.
+    ///
+    /// *The engine has no clipboard access of its own, the handler is provided by the application.*
+    pub fn clipboard(mut self, handler: impl FnMut(&TexturePixels) + 'static) -> Self {
+        self.clipboard = Some(Box::new(handler));
+        self
+    }
+}
+
+/// A layer saving screenshots whenever the configured key is pressed.
+///
+/// Requires the [`Keyboard`] and [`Screenshot`] systems to be provided by layers below this one.
+pub struct ScreenshotShortcutLayer {
+    pub shortcut: ScreenshotShortcut,
+    pending: Vec<PendingScreenshot>,
+    saved: u32,
+}
+
+impl ScreenshotShortcutLayer {
+    pub fn new(shortcut: ScreenshotShortcut) -> Self {
+        Self {
+            shortcut,
+            pending: Vec::new(),
+            saved: 0,
+        }
+    }
+
+    fn save(
+        pixels: &TexturePixels,
+        metadata: &ScreenshotMetadata,
+        path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let png = encode_png(pixels, metadata)?;
+
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        std::fs::write(path, png)?;
+
+        Ok(())
+    }
+
+    /// Encodes and writes the screenshot on the job pool if available,
+    /// so large screenshots do not stall the frame.
+    fn on_captured(&mut self, pixels: TexturePixels, jobs: Option<&JobPool>) {
+        if let Some(clipboard) = &mut self.shortcut.clipboard {
+            clipboard(&pixels);
+        }
+
+        if cfg!(target_arch = "wasm32") {
+            warn!("Saving screenshots is not supported on the web.");
+            return;
+        }
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.saved += 1;
+
+        let path = self
+            .shortcut
+            .directory
+            .join(format!("screenshot-{}-{}.png", timestamp, self.saved));
+        let metadata = self.shortcut.metadata.clone();
+
+        let save = move || match Self::save(&pixels, &metadata, &path) {
+            Ok(()) => info!("Screenshot saved to {}.", path.display()),
+            Err(err) => error!("Failed to save a screenshot: {}", err),
+        };
+
+        match jobs {
+            Some(jobs) => jobs.spawn(save),
+            None => save(),
+        }
+    }
+}
+
+impl Layer for ScreenshotShortcutLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        let pressed = systems
+            .query::<Keyboard>()
+            .is_some_and(|keyboard| keyboard.was_pressed(self.shortcut.key));
+
+        if pressed {
+            match systems.query::<Screenshot>() {
+                Some(screenshot) => self.pending.push(screenshot.capture()),
+                None => warn!("Screenshot requested, but no Screenshot system is available."),
+            }
+        }
+
+        let mut finished = Vec::new();
+        self.pending
+            .retain_mut(|pending| match pending.poll_result() {
+                Some(result) => {
+                    finished.push(result);
+                    false
+                }
+                None => true,
+            });
+
+        for result in finished {
+            match result {
+                Ok(pixels) => self.on_captured(pixels, systems.query::<JobPool>()),
+                Err(err) => error!("Failed to capture a screenshot: {}", err),
+            }
+        }
+
+        next.next(systems);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::render::screenshot_shortcut::{encode_png, ScreenshotMetadata};
+    use pluto_engine_display::pluto_engine_render::texture::TexturePixels;
+    use pluto_engine_display::pluto_engine_window::window::PhysicalSize;
+
+    /// A 1x1 screenshot is encoded with a scene name.
+    /// Decoding it should yield the same pixel and the metadata.
+    #[test]
+    fn test_encode_png() {
+        let pixels = TexturePixels {
+            size: PhysicalSize {
+                width: 1,
+                height: 1,
+            },
+            data: vec![10, 20, 30, 255],
+        };
+        let metadata = ScreenshotMetadata {
+            build_version: "1.2.3".to_owned(),
+            scene_name: Some("Ice Caves".to_owned()),
+        };
+
+        let png = encode_png(&pixels, &metadata).unwrap();
+
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        assert_eq!(data, pixels.data);
+
+        let text = reader
+            .info()
+            .utf8_text
+            .iter()
+            .map(|chunk| (chunk.keyword.clone(), chunk.get_text().unwrap()))
+            .collect::<Vec<_>>();
+        assert!(text.contains(&("Version".to_owned(), "1.2.3".to_owned())));
+        assert!(text.contains(&("Scene".to_owned(), "Ice Caves".to_owned())));
+    }
+}
@@ -0,0 +1,623 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Batched drawing of textured 2D quads.
+
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use crate::color::{RGBA, WHITE};
+use crate::math::{orthographic, Mat4, Point2, Rect, Vec2};
+use pluto_engine_display::pluto_engine_render::atlas::AtlasRegion;
+use pluto_engine_display::pluto_engine_render::texture::TexturePixels;
+use pluto_engine_display::pluto_engine_window::window::{LogicalSize, PhysicalSize};
+use std::cmp::Ordering;
+use std::sync::{Arc, Mutex};
+
+/// A texture created by the [`SpriteBatcher`], uploaded by the display before it is drawn.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SpriteTexture {
+    pub id: u64,
+    pub size: PhysicalSize<u32>,
+}
+
+/// How the image of a sprite fills its rectangle.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpriteMode {
+    /// The image is scaled to the rectangle.
+    Stretch,
+    /// The borders of the image, given in texture pixels, keep their size and only the
+    /// center and edges are stretched, e.g. for UI panels.
+    ///
+    /// If the rectangle is smaller than two opposite borders, they are scaled down together.
+    NineSlice {
+        left: f32,
+        top: f32,
+        right: f32,
+        bottom: f32,
+    },
+    /// The image is repeated at the given size in logical units from the top left corner,
+    /// tiles at the right and bottom edges are cut off.
+    Tiled { tile_size: Vec2 },
+}
+
+/// A textured rectangle, in logical units from the top left corner of the screen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprite {
+    pub texture: SpriteTexture,
+    pub rect: Rect,
+    /// The texture coordinates of the image, the whole texture by default.
+    pub uv: Rect,
+    /// Multiplied with the colors of the texture.
+    pub color: RGBA,
+    pub mode: SpriteMode,
+    /// Sprites of higher layers are drawn over those of lower ones.
+    pub layer: i32,
+    /// Orders sprites within a layer, higher values are drawn on top.
+    pub z: f32,
+}
+
+impl Sprite {
+    pub fn new(texture: SpriteTexture, rect: Rect) -> Self {
+        Self {
+            texture,
+            rect,
+            uv: Rect::new(0.0, 0.0, 1.0, 1.0),
+            color: WHITE,
+            mode: SpriteMode::Stretch,
+            layer: 0,
+            z: 0.0,
+        }
+    }
+
+    /// Uses an image packed into the texture, see [`AtlasRegion`].
+    pub fn with_region(mut self, region: &AtlasRegion) -> Self {
+        self.uv = Rect {
+            min: region.uv_min.into(),
+            max: region.uv_max.into(),
+        };
+        self
+    }
+
+    pub fn with_color(mut self, color: RGBA) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: SpriteMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: i32, z: f32) -> Self {
+        self.layer = layer;
+        self.z = z;
+        self
+    }
+
+    pub fn get_sort_key(&self) -> SpriteSortKey {
+        SpriteSortKey {
+            layer: self.layer,
+            z: self.z,
+            texture: self.texture.id,
+        }
+    }
+}
+
+/// What sprites are ordered by when they are drawn.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpriteSortKey {
+    pub layer: i32,
+    pub z: f32,
+    pub texture: u64,
+}
+
+/// The order in which the [`SpriteBatcher`] draws the sprites of a frame.
+///
+/// Sprites which compare equal are always drawn in submission order.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum SpriteSortPolicy {
+    /// Sprites are drawn in submission order, ignoring their layers and z,
+    /// for UIs which already submit their widgets back to front.
+    Painter,
+    /// Sprites are drawn by layer, then by z within the layer.
+    Layered,
+    /// Sprites are drawn by layer and z, sprites with equal ones are grouped by texture
+    /// to be drawn in fewer batches.
+    ///
+    /// *Overlapping sprites with the same layer and z may not be drawn in submission order.*
+    #[default]
+    Batched,
+}
+
+impl SpriteSortPolicy {
+    pub fn compare(&self, a: &SpriteSortKey, b: &SpriteSortKey) -> Ordering {
+        let layered = || a.layer.cmp(&b.layer).then(a.z.total_cmp(&b.z));
+
+        match self {
+            SpriteSortPolicy::Painter => Ordering::Equal,
+            SpriteSortPolicy::Layered => layered(),
+            SpriteSortPolicy::Batched => layered().then(a.texture.cmp(&b.texture)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpriteVertex {
+    pub position: Point2,
+    pub uv: Point2,
+    pub color: RGBA,
+}
+
+/// Consecutive vertices drawn with the same texture.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpriteBatch {
+    pub texture: SpriteTexture,
+    /// The range of vertices, every three of them form a triangle.
+    pub vertices: std::ops::Range<usize>,
+}
+
+/// The sprites submitted during a frame, to be drawn by the display.
+pub struct SpriteFrame {
+    pub vertices: Vec<SpriteVertex>,
+    pub batches: Vec<SpriteBatch>,
+    /// Textures to upload before drawing, including those of frames which were not drawn.
+    pub uploads: Vec<(SpriteTexture, TexturePixels)>,
+    /// Textures which are not used anymore.
+    pub freed: Vec<SpriteTexture>,
+    /// Transforms logical units into clip space, `+Y` pointing down.
+    pub projection: Mat4,
+}
+
+struct SpriteQuad {
+    key: SpriteSortKey,
+    texture: SpriteTexture,
+    vertices: [SpriteVertex; 6],
+}
+
+#[derive(Default)]
+struct SpriteState {
+    next_texture: u64,
+    uploads: Vec<(SpriteTexture, TexturePixels)>,
+    freed: Vec<SpriteTexture>,
+    quads: Vec<SpriteQuad>,
+    sort_policy: SpriteSortPolicy,
+    screen_size: LogicalSize<f64>,
+    frame: Option<SpriteFrame>,
+}
+
+impl SpriteState {
+    fn push_quad(&mut self, sprite: &Sprite, rect: Rect, uv: Rect) {
+        if rect.is_empty() {
+            return;
+        }
+
+        let color = sprite.color;
+        let corner = |x: bool, y: bool| SpriteVertex {
+            position: Point2::new(
+                if x { rect.max.x } else { rect.min.x },
+                if y { rect.max.y } else { rect.min.y },
+            ),
+            uv: Point2::new(
+                if x { uv.max.x } else { uv.min.x },
+                if y { uv.max.y } else { uv.min.y },
+            ),
+            color,
+        };
+        let [a, b, c, d] = [
+            corner(false, false),
+            corner(true, false),
+            corner(true, true),
+            corner(false, true),
+        ];
+
+        self.quads.push(SpriteQuad {
+            key: sprite.get_sort_key(),
+            texture: sprite.texture,
+            vertices: [a, b, c, a, c, d],
+        });
+    }
+
+    /// *Returns the vertices of the submitted quads in drawing order,
+    /// and the batches of consecutive vertices sharing a texture.*
+    fn take_batches(&mut self) -> (Vec<SpriteVertex>, Vec<SpriteBatch>) {
+        let mut quads = std::mem::take(&mut self.quads);
+        let policy = self.sort_policy;
+        // The sort is stable, keeping the submission order of equal sprites
+        quads.sort_by(|a, b| policy.compare(&a.key, &b.key));
+
+        let mut vertices = Vec::with_capacity(quads.len() * 6);
+        let mut batches: Vec<SpriteBatch> = Vec::new();
+
+        for quad in quads {
+            let start = vertices.len();
+            vertices.extend(quad.vertices);
+
+            match batches.last_mut() {
+                Some(batch) if batch.texture == quad.texture => batch.vertices.end = vertices.len(),
+                _ => batches.push(SpriteBatch {
+                    texture: quad.texture,
+                    vertices: start..vertices.len(),
+                }),
+            }
+        }
+
+        (vertices, batches)
+    }
+}
+
+/// Splits a span into a start border, a center and an end border,
+/// scaling the borders down if they do not fit.
+///
+/// *Returns the offsets of the four edges from the start of the span.*
+fn slice_span(length: f32, start: f32, end: f32) -> [f32; 4] {
+    let borders = start + end;
+    let scale = if borders > length && borders > 0.0 {
+        length / borders
+    } else {
+        1.0
+    };
+
+    [0.0, start * scale, length - end * scale, length]
+}
+
+/// A system batching sprites submitted by any layer during a frame.
+///
+/// Sprites are drawn by layer and z, see [`SpriteSortPolicy`], consecutive sprites sharing
+/// a texture are drawn together. Provided to layers by the [`SpriteLayer`], the display draws the sprites after
+/// post-processing and below the shapes of [`crate::render::draw_2d::Draw2D`].
+#[derive(Clone, Default)]
+pub struct SpriteBatcher {
+    state: Arc<Mutex<SpriteState>>,
+}
+
+impl SpriteBatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the logical size of the screen, kept up to date by the display.
+    pub fn set_screen_size(&self, size: LogicalSize<f64>) {
+        self.state.lock().unwrap().screen_size = size;
+    }
+
+    pub fn get_screen_size(&self) -> LogicalSize<f64> {
+        self.state.lock().unwrap().screen_size
+    }
+
+    /// Creates a texture from 8-bit sRGB pixels with straight alpha,
+    /// such as a [`pluto_engine_display::pluto_engine_render::atlas::TextureAtlas`].
+    pub fn create_texture(&self, pixels: TexturePixels) -> SpriteTexture {
+        let mut state = self.state.lock().unwrap();
+
+        let texture = SpriteTexture {
+            id: state.next_texture,
+            size: pixels.size,
+        };
+        state.next_texture += 1;
+        state.uploads.push((texture, pixels));
+
+        texture
+    }
+
+    /// Sets the order in which the sprites of the following frames are drawn,
+    /// [`SpriteSortPolicy::Batched`] by default.
+    pub fn set_sort_policy(&self, policy: SpriteSortPolicy) {
+        self.state.lock().unwrap().sort_policy = policy;
+    }
+
+    pub fn get_sort_policy(&self) -> SpriteSortPolicy {
+        self.state.lock().unwrap().sort_policy
+    }
+
+    /// Frees a texture once the current frame is drawn.
+    pub fn free_texture(&self, texture: SpriteTexture) {
+        self.state.lock().unwrap().freed.push(texture);
+    }
+
+    pub fn draw(&self, sprite: &Sprite) {
+        let mut state = self.state.lock().unwrap();
+        let Sprite {
+            texture,
+            rect,
+            uv,
+            mode,
+            ..
+        } = *sprite;
+
+        match mode {
+            SpriteMode::Stretch => state.push_quad(sprite, rect, uv),
+            SpriteMode::NineSlice {
+                left,
+                top,
+                right,
+                bottom,
+            } => {
+                let xs = slice_span(rect.width(), left, right);
+                let ys = slice_span(rect.height(), top, bottom);
+
+                let texture_width = texture.size.width as f32;
+                let texture_height = texture.size.height as f32;
+                let us = [
+                    uv.min.x,
+                    uv.min.x + left / texture_width,
+                    uv.max.x - right / texture_width,
+                    uv.max.x,
+                ];
+                let vs = [
+                    uv.min.y,
+                    uv.min.y + top / texture_height,
+                    uv.max.y - bottom / texture_height,
+                    uv.max.y,
+                ];
+
+                for row in 0..3 {
+                    for column in 0..3 {
+                        let slice = Rect {
+                            min: Point2::new(rect.min.x + xs[column], rect.min.y + ys[row]),
+                            max: Point2::new(rect.min.x + xs[column + 1], rect.min.y + ys[row + 1]),
+                        };
+                        let slice_uv = Rect {
+                            min: Point2::new(us[column], vs[row]),
+                            max: Point2::new(us[column + 1], vs[row + 1]),
+                        };
+
+                        state.push_quad(sprite, slice, slice_uv);
+                    }
+                }
+            }
+            SpriteMode::Tiled { tile_size } => {
+                if tile_size.x <= 0.0 || tile_size.y <= 0.0 {
+                    return;
+                }
+
+                let mut y = rect.min.y;
+                while y < rect.max.y {
+                    let height = tile_size.y.min(rect.max.y - y);
+                    let v = uv.min.y + uv.height() * height / tile_size.y;
+
+                    let mut x = rect.min.x;
+                    while x < rect.max.x {
+                        let width = tile_size.x.min(rect.max.x - x);
+                        let u = uv.min.x + uv.width() * width / tile_size.x;
+
+                        state.push_quad(
+                            sprite,
+                            Rect::new(x, y, width, height),
+                            Rect {
+                                min: uv.min,
+                                max: Point2::new(u, v),
+                            },
+                        );
+
+                        x += tile_size.x;
+                    }
+
+                    y += tile_size.y;
+                }
+            }
+        }
+    }
+
+    /// Collects the sprites submitted during this frame for the display,
+    /// replacing the previous frame if it was not drawn.
+    pub fn end_frame(&self) {
+        let mut state = self.state.lock().unwrap();
+        let size = state.screen_size;
+
+        let mut uploads = std::mem::take(&mut state.uploads);
+        let mut freed = std::mem::take(&mut state.freed);
+
+        // Texture changes of frames which were not drawn still have to be applied
+        if let Some(previous) = state.frame.take() {
+            uploads.splice(0..0, previous.uploads);
+            freed.splice(0..0, previous.freed);
+        }
+
+        let (vertices, batches) = state.take_batches();
+
+        state.frame = Some(SpriteFrame {
+            vertices,
+            batches,
+            uploads,
+            freed,
+            projection: orthographic(0.0, size.width as f32, size.height as f32, 0.0, -1.0, 1.0),
+        });
+    }
+
+    /// *Returns the sprites of the last frame if they were not drawn yet.*
+    pub fn take_frame(&self) -> Option<SpriteFrame> {
+        self.state.lock().unwrap().frame.take()
+    }
+}
+
+impl System for SpriteBatcher {}
+
+/// A layer providing the [`SpriteBatcher`] system to all layers above it,
+/// ending the frame once they have been entered.
+pub struct SpriteLayer(pub SpriteBatcher);
+
+impl Layer for SpriteLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        next.next(systems);
+
+        self.0.end_frame();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{Point2, Rect, Vec2};
+    use crate::render::sprite::{Sprite, SpriteBatcher, SpriteMode, SpriteSortPolicy};
+    use pluto_engine_display::pluto_engine_render::texture::TexturePixels;
+    use pluto_engine_display::pluto_engine_window::window::PhysicalSize;
+
+    fn texture_pixels(width: u32, height: u32) -> TexturePixels {
+        TexturePixels {
+            size: PhysicalSize { width, height },
+            data: vec![255; (width * height * 4) as usize],
+        }
+    }
+
+    /// Two sprites of one texture and one of another are drawn, then a second frame ends.
+    /// Sprites sharing a texture should be batched, and the upload kept until a frame is taken.
+    #[test]
+    fn test_sprite_batches() {
+        let sprites = SpriteBatcher::new();
+        let first = sprites.create_texture(texture_pixels(4, 4));
+        let second = sprites.create_texture(texture_pixels(2, 2));
+
+        sprites.draw(&Sprite::new(first, Rect::new(0.0, 0.0, 10.0, 10.0)));
+        sprites.draw(&Sprite::new(first, Rect::new(10.0, 0.0, 10.0, 10.0)));
+        sprites.draw(&Sprite::new(second, Rect::new(0.0, 10.0, 10.0, 10.0)));
+        sprites.end_frame();
+        sprites.end_frame();
+
+        let frame = sprites.take_frame().unwrap();
+        assert!(frame.vertices.is_empty());
+        assert_eq!(frame.uploads.len(), 2);
+        assert_eq!(frame.uploads[1].0, second);
+
+        sprites.draw(&Sprite::new(first, Rect::new(0.0, 0.0, 10.0, 10.0)));
+        sprites.draw(&Sprite::new(first, Rect::new(10.0, 0.0, 10.0, 10.0)));
+        sprites.draw(&Sprite::new(second, Rect::new(0.0, 10.0, 10.0, 10.0)));
+        sprites.end_frame();
+
+        let frame = sprites.take_frame().unwrap();
+        assert!(frame.uploads.is_empty());
+        assert_eq!(frame.batches.len(), 2);
+        assert_eq!(frame.batches[0].vertices, 0..12);
+        assert_eq!(frame.batches[1].texture, second);
+        assert_eq!(frame.vertices[2].position, Point2::new(10.0, 10.0));
+        assert_eq!(frame.vertices[2].uv, Point2::new(1.0, 1.0));
+    }
+
+    /// A nine-slice sprite is drawn larger than its borders and smaller than them.
+    /// The borders should keep their size with matching texture coordinates,
+    /// then be scaled down without leaving an empty center.
+    #[test]
+    fn test_nine_slice() {
+        let sprites = SpriteBatcher::new();
+        let texture = sprites.create_texture(texture_pixels(16, 8));
+        let mode = SpriteMode::NineSlice {
+            left: 4.0,
+            top: 2.0,
+            right: 4.0,
+            bottom: 2.0,
+        };
+
+        sprites.draw(&Sprite::new(texture, Rect::new(0.0, 0.0, 100.0, 50.0)).with_mode(mode));
+        sprites.end_frame();
+
+        let frame = sprites.take_frame().unwrap();
+        assert_eq!(frame.vertices.len(), 9 * 6);
+
+        let center = &frame.vertices[4 * 6..5 * 6];
+        assert_eq!(center[0].position, Point2::new(4.0, 2.0));
+        assert_eq!(center[0].uv, Point2::new(0.25, 0.25));
+        assert_eq!(center[2].position, Point2::new(96.0, 48.0));
+        assert_eq!(center[2].uv, Point2::new(0.75, 0.75));
+
+        sprites.draw(&Sprite::new(texture, Rect::new(0.0, 0.0, 4.0, 50.0)).with_mode(mode));
+        sprites.end_frame();
+
+        // The center column collapses, leaving the left and right columns of two units
+        let frame = sprites.take_frame().unwrap();
+        assert_eq!(frame.vertices.len(), 6 * 6);
+        assert_eq!(frame.vertices[2].position, Point2::new(2.0, 2.0));
+        assert_eq!(frame.vertices[2].uv, Point2::new(0.25, 0.25));
+    }
+
+    /// A tiled sprite using part of a texture is drawn over a rectangle which is not
+    /// a multiple of the tile size.
+    /// The edge tiles should be cut off with their texture coordinates cut off alike.
+    #[test]
+    fn test_tiled() {
+        let sprites = SpriteBatcher::new();
+        let texture = sprites.create_texture(texture_pixels(8, 8));
+
+        let mut sprite =
+            Sprite::new(texture, Rect::new(10.0, 10.0, 25.0, 10.0)).with_mode(SpriteMode::Tiled {
+                tile_size: Vec2::new(10.0, 10.0),
+            });
+        sprite.uv = Rect::new(0.5, 0.0, 0.5, 0.5);
+        sprites.draw(&sprite);
+        sprites.end_frame();
+
+        let frame = sprites.take_frame().unwrap();
+        assert_eq!(frame.vertices.len(), 3 * 6);
+        assert_eq!(frame.batches.len(), 1);
+
+        let last = &frame.vertices[2 * 6..];
+        assert_eq!(last[0].position, Point2::new(30.0, 10.0));
+        assert_eq!(last[0].uv, Point2::new(0.5, 0.0));
+        assert_eq!(last[2].position, Point2::new(35.0, 20.0));
+        assert_eq!(last[2].uv, Point2::new(0.75, 0.5));
+    }
+
+    /// Sprites of two textures are drawn on different layers and z, out of order,
+    /// with each sort policy.
+    /// They should be sorted by layer, z and texture, by layer and z only,
+    /// or not at all for the painter's order.
+    #[test]
+    fn test_sort_policy() {
+        let sprites = SpriteBatcher::new();
+        let first = sprites.create_texture(texture_pixels(1, 1));
+        let second = sprites.create_texture(texture_pixels(1, 1));
+
+        let draw = |x: f32, texture, layer, z| {
+            sprites.draw(&Sprite::new(texture, Rect::new(x, 0.0, 1.0, 1.0)).with_layer(layer, z));
+        };
+        let order = |policy| {
+            sprites.set_sort_policy(policy);
+            draw(0.0, second, 1, 0.0);
+            draw(1.0, second, 0, 0.0);
+            draw(2.0, first, 0, 0.0);
+            draw(3.0, first, 0, -1.0);
+            draw(4.0, second, 0, 0.0);
+            sprites.end_frame();
+
+            let frame = sprites.take_frame().unwrap();
+            let order = frame
+                .vertices
+                .chunks(6)
+                .map(|quad| quad[0].position.x as u32)
+                .collect::<Vec<_>>();
+            (order, frame.batches.len())
+        };
+
+        assert_eq!(sprites.get_sort_policy(), SpriteSortPolicy::Batched);
+        assert_eq!(order(SpriteSortPolicy::Batched), (vec![3, 2, 1, 4, 0], 2));
+        assert_eq!(order(SpriteSortPolicy::Layered), (vec![3, 1, 2, 4, 0], 4));
+        assert_eq!(order(SpriteSortPolicy::Painter), (vec![0, 1, 2, 3, 4], 3));
+    }
+}
@@ -0,0 +1,209 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::application::layer::timing::{LayerTiming, LayerTimings};
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use instant::Instant;
+use pluto_engine_display::pluto_engine_render::timer::PassTiming;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Statistics of a single rendered frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    /// The CPU time between the start of this frame and the start of the previous one.
+    pub frame_time: Duration,
+    pub draw_calls: u32,
+    pub triangles: u64,
+    /// GPU time of each measured pass.
+    ///
+    /// *These are the latest available timings, typically a few frames behind.
+    /// Empty if the device does not support timestamp queries.*
+    pub gpu_passes: Vec<PassTiming>,
+    /// The latest culling pass, see [`crate::render::scene::SceneLayer`].
+    pub culling: CullingStats,
+    /// The timings of the layers entered in the previous frame, see [`LayerTimings`].
+    pub layers: Vec<LayerTiming>,
+}
+
+/// Statistics of a frustum culling pass.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CullingStats {
+    /// The number of nodes with bounds, which were tested against the frustum.
+    pub tested: u32,
+    /// The number of tested nodes outside of the frustum.
+    pub culled: u32,
+    /// The number of visible nodes, including those without bounds.
+    pub visible: u32,
+}
+
+impl FrameStats {
+    /// *Returns the number of frames per second if all frames took as long as this one.*
+    pub fn frames_per_second(&self) -> f64 {
+        if self.frame_time.is_zero() {
+            0.0
+        } else {
+            1.0 / self.frame_time.as_secs_f64()
+        }
+    }
+
+    /// *Returns the total GPU time of all measured passes.*
+    pub fn gpu_time(&self) -> Duration {
+        self.gpu_passes.iter().map(|pass| pass.duration).sum()
+    }
+}
+
+/// A system exposing the statistics of the last rendered frame.
+///
+/// Provided by the [`RenderStatsLayer`], the statistics are collected
+/// by the renderer using a [`RenderStatsRecorder`] sharing this system.
+#[derive(Clone, Default)]
+pub struct RenderStats {
+    latest: Arc<Mutex<FrameStats>>,
+    culling: Arc<Mutex<CullingStats>>,
+    layers: Arc<Mutex<Vec<LayerTiming>>>,
+}
+
+impl RenderStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// *Returns the statistics of the last completed frame.*
+    pub fn latest(&self) -> FrameStats {
+        self.latest.lock().unwrap().clone()
+    }
+
+    /// Records a culling pass, published with the statistics of the current frame.
+    pub fn record_culling(&self, culling: CullingStats) {
+        *self.culling.lock().unwrap() = culling;
+    }
+
+    /// Records the layer timings, published with the statistics of the current frame.
+    pub fn record_layer_timings(&self, timings: &[LayerTiming]) {
+        let mut layers = self.layers.lock().unwrap();
+        layers.clear();
+        layers.extend_from_slice(timings);
+    }
+}
+
+impl System for RenderStats {}
+
+/// The renderer side of the [`RenderStats`] system.
+pub struct RenderStatsRecorder {
+    stats: RenderStats,
+    frame_start: Option<Instant>,
+    current: FrameStats,
+    gpu_passes: Vec<PassTiming>,
+}
+
+impl RenderStatsRecorder {
+    pub fn new(stats: RenderStats) -> Self {
+        Self {
+            stats,
+            frame_start: None,
+            current: FrameStats::default(),
+            gpu_passes: Vec::new(),
+        }
+    }
+
+    /// Publishes the statistics of the previous frame and starts measuring a new one.
+    pub fn begin_frame(&mut self) {
+        let now = Instant::now();
+
+        if let Some(frame_start) = self.frame_start.replace(now) {
+            let mut frame = std::mem::take(&mut self.current);
+            frame.frame_time = now - frame_start;
+            frame.gpu_passes = self.gpu_passes.clone();
+            frame.culling = *self.stats.culling.lock().unwrap();
+            frame.layers = self.stats.layers.lock().unwrap().clone();
+            *self.stats.latest.lock().unwrap() = frame;
+        }
+    }
+
+    /// Records a draw call of the given number of triangles.
+    pub fn record_draw(&mut self, triangles: u64) {
+        self.current.draw_calls += 1;
+        self.current.triangles += triangles;
+    }
+
+    /// Records GPU pass timings, as returned by `DeviceGpuTimer::poll_gpu_timer`.
+    pub fn record_gpu_timings(&mut self, gpu_passes: Vec<PassTiming>) {
+        self.gpu_passes = gpu_passes;
+    }
+}
+
+/// A layer providing the [`RenderStats`] system to all layers above it,
+/// recording the [`LayerTimings`] every frame.
+pub struct RenderStatsLayer(pub RenderStats);
+
+impl Layer for RenderStatsLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager, next: &mut dyn LayerWalker) {
+        if let Some(timings) = systems.query::<LayerTimings>() {
+            self.0.record_layer_timings(timings.latest());
+        }
+
+        next.next(systems);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::render::stats::{CullingStats, RenderStats, RenderStatsRecorder};
+
+    /// Two draw calls are recorded in a frame.
+    /// The statistics should only be published when the next frame begins.
+    #[test]
+    fn test_record_frame() {
+        let stats = RenderStats::new();
+        let mut recorder = RenderStatsRecorder::new(stats.clone());
+
+        recorder.begin_frame();
+        recorder.record_draw(2);
+        recorder.record_draw(10);
+        stats.record_culling(CullingStats {
+            tested: 3,
+            culled: 1,
+            visible: 2,
+        });
+        assert_eq!(stats.latest().draw_calls, 0);
+
+        recorder.begin_frame();
+        let frame = stats.latest();
+        assert_eq!(frame.draw_calls, 2);
+        assert_eq!(frame.triangles, 12);
+        assert_eq!(frame.culling.culled, 1);
+    }
+}
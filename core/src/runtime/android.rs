@@ -0,0 +1,62 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Running applications as Android activities, see [`crate::android_main`].
+//!
+//! The native window only exists while the activity is resumed, so windows are created on the
+//! first resume and displays recreate their surface whenever the activity is resumed again.
+
+pub use ndk_glue;
+
+use std::ffi::CString;
+use std::io;
+use std::io::Read;
+
+/// Reads an asset packaged in the APK, by its path relative to the `assets` directory of the APK.
+pub fn read_apk_asset(path: &str) -> io::Result<Vec<u8>> {
+    let name =
+        CString::new(path).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut asset = ndk_glue::native_activity()
+        .asset_manager()
+        .open(&name)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{}: not packaged in the APK", path),
+            )
+        })?;
+
+    let mut bytes = Vec::with_capacity(asset.get_length());
+    asset.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Logs to logcat with the given minimum level, the standard output of activities is discarded.
+pub fn init_logger(level: log::Level) {
+    android_logger::init_once(
+        android_logger::Config::default()
+            .with_min_level(level)
+            .with_tag("pluto"),
+    );
+}
@@ -0,0 +1,307 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Introspection of what was linked into the build, and lazy loading of optional modules.
+//!
+//! Web games pay for every linked subsystem in download size. A [`BundleReport`] lists the
+//! engine features and subsystems of the build, enable the `pe_size_report` feature to log it
+//! at startup and compare it with the output of a size profiler such as `twiggy`.
+//!
+//! Optional functionality built as a separate WASM module, such as scripts, can be fetched on
+//! demand through [`LazyModules`] instead of being part of the initial download.
+
+use log::info;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+
+/// A Cargo feature of the engine.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EngineFeature {
+    pub name: &'static str,
+    pub enabled: bool,
+}
+
+macro_rules! engine_features {
+    ($($feature:literal),* $(,)?) => {
+        &[$(EngineFeature {
+            name: $feature,
+            enabled: cfg!(feature = $feature),
+        }),*]
+    };
+}
+
+/// All optional features of the engine, and whether they are enabled in this build.
+pub const ENGINE_FEATURES: &[EngineFeature] = engine_features![
+    "pe_render_wgpu",
+    "pe_window_winit",
+    "pe_file_dialog",
+    "pe_audio",
+    "pe_size_report",
+];
+
+/// A subsystem linked into the build.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SubsystemInfo {
+    pub name: &'static str,
+    /// The feature the subsystem is gated behind, `None` if it is always linked.
+    pub feature: Option<&'static str>,
+}
+
+/// The registration table of subsystems linked into this build.
+pub const LINKED_SUBSYSTEMS: &[SubsystemInfo] = &[
+    SubsystemInfo {
+        name: "layers",
+        feature: None,
+    },
+    SubsystemInfo {
+        name: "input",
+        feature: None,
+    },
+    SubsystemInfo {
+        name: "color",
+        feature: None,
+    },
+    SubsystemInfo {
+        name: "render",
+        feature: None,
+    },
+    SubsystemInfo {
+        name: "assets",
+        feature: None,
+    },
+    #[cfg(feature = "pe_render_wgpu")]
+    SubsystemInfo {
+        name: "wgpu backend",
+        feature: Some("pe_render_wgpu"),
+    },
+    #[cfg(feature = "pe_window_winit")]
+    SubsystemInfo {
+        name: "winit backend",
+        feature: Some("pe_window_winit"),
+    },
+    #[cfg(feature = "pe_file_dialog")]
+    SubsystemInfo {
+        name: "file dialogs",
+        feature: Some("pe_file_dialog"),
+    },
+    #[cfg(feature = "pe_audio")]
+    SubsystemInfo {
+        name: "audio",
+        feature: Some("pe_audio"),
+    },
+];
+
+/// The features and subsystems of this build.
+#[derive(Clone, Debug)]
+pub struct BundleReport {
+    pub target: &'static str,
+    pub features: &'static [EngineFeature],
+    pub subsystems: &'static [SubsystemInfo],
+}
+
+impl BundleReport {
+    pub fn collect() -> Self {
+        Self {
+            target: if cfg!(target_arch = "wasm32") {
+                "wasm32"
+            } else {
+                "native"
+            },
+            features: ENGINE_FEATURES,
+            subsystems: LINKED_SUBSYSTEMS,
+        }
+    }
+
+    /// *Returns the names of the enabled features.*
+    pub fn enabled_features(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.features
+            .iter()
+            .filter(|feature| feature.enabled)
+            .map(|feature| feature.name)
+    }
+
+    pub fn log(&self) {
+        for line in self.to_string().lines() {
+            info!("{}", line);
+        }
+    }
+}
+
+impl Display for BundleReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Bundle report ({} build)", self.target)?;
+
+        for feature in self.features {
+            let state = if feature.enabled { "+" } else { "-" };
+            writeln!(f, "  {} {}", state, feature.name)?;
+        }
+
+        writeln!(f, "Linked subsystems:")?;
+        for subsystem in self.subsystems {
+            match subsystem.feature {
+                Some(feature) => writeln!(f, "  {} ({})", subsystem.name, feature)?,
+                None => writeln!(f, "  {}", subsystem.name)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Fetches the bytes of a module, for example over HTTP on the web.
+///
+/// The callback may be called on any thread once the module has been fetched.
+pub trait ModuleSource: Send + Sync {
+    fn fetch(&self, name: &str, done: Box<dyn FnOnce(Result<Vec<u8>, String>) + Send>);
+}
+
+/// Reads modules from `<directory>/<name>.wasm`.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct DirectoryModuleSource(pub std::path::PathBuf);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ModuleSource for DirectoryModuleSource {
+    fn fetch(&self, name: &str, done: Box<dyn FnOnce(Result<Vec<u8>, String>) + Send>) {
+        let path = self.0.join(name).with_extension("wasm");
+        done(std::fs::read(&path).map_err(|err| format!("{}: {}", path.display(), err)));
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ModuleState {
+    NotLoaded,
+    Loading,
+    Loaded(Arc<[u8]>),
+    Failed(String),
+}
+
+/// Optional modules loaded on first use.
+///
+/// Each module is fetched at most once, later requests share the result.
+#[derive(Clone)]
+pub struct LazyModules {
+    source: Arc<dyn ModuleSource>,
+    modules: Arc<Mutex<HashMap<String, ModuleState>>>,
+}
+
+impl LazyModules {
+    pub fn new(source: impl ModuleSource + 'static) -> Self {
+        Self {
+            source: Arc::new(source),
+            modules: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Starts fetching the module unless it was already requested.
+    ///
+    /// *Returns the current state of the module.*
+    pub fn request(&self, name: &str) -> ModuleState {
+        {
+            let mut modules = self.modules.lock().unwrap();
+            match modules.get(name) {
+                Some(state) => return state.clone(),
+                None => modules.insert(name.to_owned(), ModuleState::Loading),
+            };
+        }
+
+        let modules = self.modules.clone();
+        let module_name = name.to_owned();
+        self.source.fetch(
+            name,
+            Box::new(move |result| {
+                let state = match result {
+                    Ok(bytes) => ModuleState::Loaded(bytes.into()),
+                    Err(err) => ModuleState::Failed(err),
+                };
+
+                modules.lock().unwrap().insert(module_name, state);
+            }),
+        );
+
+        self.state(name)
+    }
+
+    pub fn state(&self, name: &str) -> ModuleState {
+        self.modules
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .unwrap_or(ModuleState::NotLoaded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::runtime::bundle::{BundleReport, LazyModules, ModuleSource, ModuleState};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct CountingSource(Arc<AtomicUsize>);
+
+    impl ModuleSource for CountingSource {
+        fn fetch(&self, name: &str, done: Box<dyn FnOnce(Result<Vec<u8>, String>) + Send>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            done(match name {
+                "scripting" => Ok(b"\0asm".to_vec()),
+                _ => Err("not found".to_owned()),
+            });
+        }
+    }
+
+    /// A module is requested twice and a missing one once.
+    /// Each module should only be fetched once and end in the matching state.
+    #[test]
+    fn test_lazy_modules() {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let modules = LazyModules::new(CountingSource(fetches.clone()));
+
+        assert!(matches!(modules.state("scripting"), ModuleState::NotLoaded));
+        assert!(
+            matches!(modules.request("scripting"), ModuleState::Loaded(bytes) if &*bytes == b"\0asm")
+        );
+        assert!(matches!(
+            modules.request("scripting"),
+            ModuleState::Loaded(_)
+        ));
+        assert!(matches!(modules.request("physics"), ModuleState::Failed(_)));
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    /// The report should list every feature, enabled ones with a plus sign.
+    #[test]
+    fn test_bundle_report() {
+        let report = BundleReport::collect();
+        let text = report.to_string();
+
+        for feature in report.features {
+            assert!(text.contains(feature.name));
+        }
+        assert_eq!(
+            report.enabled_features().any(|name| name == "pe_audio"),
+            cfg!(feature = "pe_audio")
+        );
+    }
+}
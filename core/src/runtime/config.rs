@@ -0,0 +1,218 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Engine options chosen when the application starts, see [`EngineConfig`].
+
+use crate::asset::ASSET_DIRECTORY;
+use crate::settings::{SettingValue, SettingsTable};
+use log::LevelFilter;
+
+/// Options of the engine chosen when the application starts,
+/// passed to the [`ApplicationBootstrapper`](super::ApplicationBootstrapper).
+///
+/// Read from a [`SettingsTable`] with the keys `render.vsync`, `render.msaa_samples`,
+/// `debug.validation`, `assets.root` and `log.level`, which may also be given as query
+/// parameters on the web, e.g. `?render.vsync=false&log.level="debug"`,
+/// or as `--key=value` arguments on native platforms, see [`EngineConfig::from_environment`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineConfig {
+    /// Whether presenting waits for the vertical blank of the display.
+    pub vsync: bool,
+    /// The number of samples per pixel applications should create their render targets with.
+    ///
+    /// *Either `1` or `4`, the sample counts supported by all devices.*
+    pub msaa_samples: u32,
+    /// Whether debug validation is enabled, see [`crate::debug::validation`].
+    ///
+    /// *Has no effect unless validation is compiled in.*
+    pub validation: bool,
+    /// The directory assets are read from, see [`crate::asset::read_asset`].
+    pub asset_root: String,
+    /// The maximum level of logged messages.
+    ///
+    /// *The logger of the application should be initialized with this level,
+    /// the engine can only lower the level of an already initialized logger.*
+    pub log_level: LevelFilter,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            vsync: true,
+            msaa_samples: 1,
+            validation: true,
+            asset_root: ASSET_DIRECTORY.to_owned(),
+            log_level: LevelFilter::Warn,
+        }
+    }
+}
+
+impl EngineConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.vsync = vsync;
+        self
+    }
+
+    /// Sets the sample count, rounded down to a supported one.
+    pub fn msaa_samples(mut self, msaa_samples: u32) -> Self {
+        self.msaa_samples = if msaa_samples >= 4 { 4 } else { 1 };
+        self
+    }
+
+    pub fn validation(mut self, validation: bool) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    pub fn asset_root(mut self, asset_root: impl Into<String>) -> Self {
+        self.asset_root = asset_root.into();
+        self
+    }
+
+    pub fn log_level(mut self, log_level: LevelFilter) -> Self {
+        self.log_level = log_level;
+        self
+    }
+
+    /// Reads the options from a table, missing or mistyped entries keep their defaults.
+    pub fn from_table(table: &SettingsTable) -> Self {
+        let default = Self::default();
+
+        Self {
+            vsync: table.get_bool("render.vsync").unwrap_or(default.vsync),
+            validation: table
+                .get_bool("debug.validation")
+                .unwrap_or(default.validation),
+            asset_root: table
+                .get_str("assets.root")
+                .map_or(default.asset_root, str::to_owned),
+            log_level: table
+                .get_str("log.level")
+                .and_then(|level| level.parse().ok())
+                .unwrap_or(default.log_level),
+            ..default
+        }
+        .msaa_samples(table.get_u32("render.msaa_samples").unwrap_or(1))
+    }
+
+    /// Reads the options from a URL query string, such as `?render.vsync=false`.
+    ///
+    /// *Values are not percent-decoded.*
+    pub fn from_query(query: &str) -> Self {
+        let query = query.strip_prefix('?').unwrap_or(query);
+        Self::from_table(&parse_pairs(query.split('&')))
+    }
+
+    /// Reads the options from command line arguments, such as `--render.vsync=false`,
+    /// arguments of other forms are skipped.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let args = args.into_iter().collect::<Vec<_>>();
+        Self::from_table(&parse_pairs(
+            args.iter().filter_map(|arg| arg.strip_prefix("--")),
+        ))
+    }
+
+    /// Reads the options from the query string of the page on the web,
+    /// and from the command line arguments on other platforms.
+    pub fn from_environment() -> Self {
+        cfg_if::cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                let query = web_sys::window()
+                    .and_then(|window| window.location().search().ok())
+                    .unwrap_or_default();
+                Self::from_query(&query)
+            } else {
+                Self::from_args(std::env::args().skip(1))
+            }
+        }
+    }
+
+    /// Applies the process-wide options, called by the bootstrapper before the application starts.
+    ///
+    /// *The surface and render target options are applied when they are created.*
+    pub fn apply(&self) {
+        log::set_max_level(self.log_level);
+        crate::debug::validation::set_enabled(self.validation);
+        crate::asset::set_asset_root(self.asset_root.clone());
+    }
+}
+
+/// Parses `key=value` pairs, values are parsed like those of a [`SettingsTable`]
+/// but strings may be unquoted. Pairs without a value are skipped.
+fn parse_pairs<'a>(pairs: impl Iterator<Item = &'a str>) -> SettingsTable {
+    let mut table = SettingsTable::new();
+
+    for (key, value) in pairs.filter_map(|pair| pair.split_once('=')) {
+        let value = match value {
+            "true" => SettingValue::Bool(true),
+            "false" => SettingValue::Bool(false),
+            value => match value.parse() {
+                Ok(number) => SettingValue::Number(number),
+                Err(_) => SettingValue::String(value.trim_matches('"').to_owned()),
+            },
+        };
+
+        table.set(key, value);
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use crate::runtime::config::EngineConfig;
+    use log::LevelFilter;
+
+    /// A query string and command line arguments set every option, some of them invalid.
+    /// Valid options should be read, invalid ones and unrelated arguments should be ignored.
+    #[test]
+    fn test_config_sources() {
+        let config = EngineConfig::from_query(
+            "?render.vsync=false&render.msaa_samples=8&debug.validation=false\
+             &assets.root=\"data/assets\"&log.level=debug",
+        );
+        assert_eq!(
+            config,
+            EngineConfig::new()
+                .vsync(false)
+                .msaa_samples(4)
+                .validation(false)
+                .asset_root("data/assets")
+                .log_level(LevelFilter::Debug)
+        );
+
+        let args = [
+            "--render.vsync=1",
+            "--log.level=loud",
+            "--assets.root=res",
+            "file.txt",
+        ];
+        let config = EngineConfig::from_args(args.map(str::to_owned));
+        assert_eq!(config, EngineConfig::new().asset_root("res"));
+    }
+}
@@ -22,12 +22,23 @@
  * SOFTWARE.
  */
 
-use log::info;
-use pluto_engine_display::pluto_engine_window::event_loop::{EventLoop, EventLoopWindowFactory};
+use crate::runtime::config::EngineConfig;
+use log::{error, info};
+use pluto_engine_display::error::EngineError;
+use pluto_engine_display::pluto_engine_window::event_loop::{
+    DisplayEvent, DisplayEventChannelConfig, EventLoop, EventLoopWindowFactory,
+};
+use pluto_engine_display::pluto_engine_window::executor::LocalFuture;
 use pluto_engine_display::pluto_engine_window::window::Window;
 use pluto_engine_display::{ApplicationDisplay, ApplicationState};
 use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
 
+#[cfg(target_os = "android")]
+pub mod android;
+pub mod bundle;
+pub mod config;
 pub mod pluto_runtime;
 
 pub mod platform {
@@ -38,37 +49,161 @@ pub mod platform {
     }
 }
 
-pub struct ApplicationBootstrapper<E>(Box<dyn FnOnce(E::WindowType) + Send + 'static>)
+/// Defines the entry point of the Android activity, calling the given `fn()` on its own thread
+/// once the activity is created. Expands to nothing on other platforms.
+///
+/// ```ignore
+/// fn run() {
+///     pollster::block_on(main())
+/// }
+///
+/// pluto_engine::android_main!(run);
+/// ```
+#[macro_export]
+macro_rules! android_main {
+    ($main:path) => {
+        #[cfg(target_os = "android")]
+        #[no_mangle]
+        unsafe extern "C" fn ANativeActivity_onCreate(
+            activity: *mut ::std::os::raw::c_void,
+            saved_state: *mut ::std::os::raw::c_void,
+            saved_state_size: usize,
+        ) {
+            $crate::runtime::android::ndk_glue::init(
+                activity as _,
+                saved_state as _,
+                saved_state_size,
+                $main,
+            );
+        }
+    };
+}
+
+/// The future driving an application, failing if the application could not be initialized.
+pub type ApplicationFuture = Pin<Box<dyn Future<Output = Result<(), EngineError>>>>;
+
+/// Creates the future driving an application for its window and the engine configuration.
+pub type ApplicationMain<W> =
+    Box<dyn FnOnce(W, EngineConfig) -> ApplicationFuture + Send + 'static>;
+
+/// Reports an error which stopped the application,
+/// also showing it in a message dialog if the `pe_file_dialog` feature is enabled.
+async fn report_error(err: EngineError) {
+    error!("The application failed to initialize: {}", err);
+
+    #[cfg(feature = "pe_file_dialog")]
+    rfd::AsyncMessageDialog::new()
+        .set_level(rfd::MessageLevel::Error)
+        .set_title("Initialization failed")
+        .set_description(err.to_string())
+        .set_buttons(rfd::MessageButtons::Ok)
+        .show()
+        .await;
+}
+
+pub struct ApplicationBootstrapper<E>
 where
-    E: EventLoop;
+    E: EventLoop,
+{
+    main: ApplicationMain<E::WindowType>,
+    worker_thread: bool,
+    event_channel: DisplayEventChannelConfig,
+    config: EngineConfig,
+}
 
 impl<E> ApplicationBootstrapper<E>
 where
     E: EventLoop,
 {
-    pub fn default_loop<'a, AD: ApplicationDisplay<'a>>(state: &mut impl ApplicationState<'a, AD>) {
+    /// Handles the events of the display until it requests to close,
+    /// yielding to the runtime while there are no events.
+    ///
+    /// Every frame the state is updated before it renders, see [`ApplicationState::update`].
+    /// On the web the loop runs as a task of the event loop, frames are then driven by
+    /// `requestAnimationFrame` and the events received in between are handled before each frame.
+    ///
+    /// The state is then notified by [`ApplicationState::on_exit`] and the display shut down.
+    pub async fn default_loop<'a, AD: ApplicationDisplay<'a>>(
+        state: &mut impl ApplicationState<'a, AD>,
+    ) {
         loop {
             let display = state.display();
             if display.close_requested() {
                 break;
             }
 
-            let event = display.get_window().receive_event();
+            let event = display.get_window().next_event().await;
+            if let DisplayEvent::Disconnected = event {
+                break;
+            }
+
             ApplicationDisplay::on_event(display, event)(state);
         }
 
+        state.on_exit();
+        state.display().shutdown();
+
         info!(
             "Window ID {:?} close requested.",
             state.display().get_window().get_id()
         );
     }
 
-    pub fn new(main_loop: Box<dyn FnOnce(E::WindowType) + Send + 'static>) -> Self {
-        Self(main_loop)
+    /// The application runs on a worker thread on native platforms
+    /// and on the event loop thread on the web.
+    pub fn new(main: ApplicationMain<E::WindowType>) -> Self {
+        Self {
+            main,
+            worker_thread: cfg!(not(target_arch = "wasm32")),
+            event_channel: DisplayEventChannelConfig::default(),
+            config: EngineConfig::default(),
+        }
+    }
+
+    /// Sets the engine configuration, applied before the application starts.
+    pub fn config(mut self, config: EngineConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn get_config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    /// Sets the capacity and overflow policy of the event channel of the window.
+    ///
+    /// *Blocking on overflow deadlocks applications running on the event loop thread.*
+    pub fn event_channel(mut self, event_channel: DisplayEventChannelConfig) -> Self {
+        self.event_channel = event_channel;
+        self
     }
 
-    pub fn bootstrap(self, window: E::WindowType) {
-        self.0(window)
+    pub fn get_event_channel(&self) -> DisplayEventChannelConfig {
+        self.event_channel
+    }
+
+    /// Runs the application on the event loop thread even on native platforms,
+    /// frames are then paced by the event loop.
+    pub fn on_event_loop_thread(mut self) -> Self {
+        self.worker_thread = false;
+        self
+    }
+
+    pub fn runs_on_worker_thread(&self) -> bool {
+        self.worker_thread
+    }
+
+    /// Applies the engine configuration and creates the future running the application,
+    /// reporting its errors.
+    pub fn bootstrap(self, window: E::WindowType) -> LocalFuture {
+        self.config.apply();
+        let application = (self.main)(window, self.config);
+
+        Box::pin(async move {
+            if let Err(err) = application.await {
+                report_error(err).await;
+            }
+        })
     }
 }
 
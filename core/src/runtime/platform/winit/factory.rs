@@ -0,0 +1,85 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::runtime::config::EngineConfig;
+use crate::runtime::platform::winit::wgpu::WinitWgpuDisplay;
+use crate::runtime::ApplicationBootstrapper;
+use pluto_engine_core_platform_wgpu::device::{WgpuDevice, WgpuQueue};
+use pluto_engine_core_platform_wgpu::instance::WgpuInstance;
+use pluto_engine_core_platform_winit::event_loop::WinitEventLoop;
+use pluto_engine_display::error::EngineError;
+use pluto_engine_display::pluto_engine_render::device::PhysicalDevice;
+use pluto_engine_display::pluto_engine_render::instance::ContextInstance;
+use pluto_engine_display::pluto_engine_render::surface::Surface;
+use pluto_engine_display::{ApplicationDisplay, ApplicationState};
+use std::sync::Arc;
+
+/// The display and graphics device created for a window, owned by the state of the application.
+pub struct WindowContext {
+    pub display: WinitWgpuDisplay,
+    pub device: Arc<WgpuDevice<'static>>,
+    pub queue: Arc<WgpuQueue<'static>>,
+    /// The options the surface was created with, e.g. to create render targets
+    /// with [`EngineConfig::msaa_samples`].
+    pub config: EngineConfig,
+}
+
+/// An application type, creating its state for each window once the device and display exist.
+///
+/// *Errors returned while creating the state are reported by the runtime.*
+pub trait ApplicationFactory: Send + 'static {
+    type State: ApplicationState<'static, WinitWgpuDisplay>;
+
+    /// Creates the state of the application, e.g. configuring the display
+    /// and calling [`ApplicationState::new`].
+    fn create(self, context: WindowContext) -> Result<Self::State, EngineError>;
+}
+
+impl ApplicationBootstrapper<WinitEventLoop> {
+    /// Bootstraps an application type, creating the graphics device and display of its window
+    /// and running the state with [`ApplicationBootstrapper::default_loop`].
+    pub fn from_factory<F: ApplicationFactory>(factory: F) -> Self {
+        Self::new(Box::new(|window, config| {
+            Box::pin(async move {
+                let (physical_device, mut surface) =
+                    WgpuInstance::new(&window).create_device_and_surface()?;
+                let (device, queue) = physical_device.create_device_and_queue()?;
+                surface.set_vsync(config.vsync);
+                surface.configure(&device);
+
+                let (device, queue) = (Arc::new(device), Arc::new(queue));
+                let display =
+                    WinitWgpuDisplay::new(surface, window, Arc::clone(&device), Arc::clone(&queue));
+                let mut state = factory.create(WindowContext {
+                    display,
+                    device,
+                    queue,
+                    config,
+                })?;
+
+                Self::default_loop(&mut state).await;
+                Ok(())
+            })
+        }))
+    }
+}
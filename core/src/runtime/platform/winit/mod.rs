@@ -24,6 +24,7 @@
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "pe_render_wgpu")] {
+        pub mod factory;
         pub mod wgpu;
     }
 }
@@ -22,27 +22,421 @@
  * SOFTWARE.
  */
 
-use log::{error, warn};
-use pluto_engine_core_platform_wgpu::device::WgpuDevice;
+use crate::application::time::Time;
+use crate::debug::capture::FrameCapture;
+use crate::debug::draw::{DebugDraw, DebugDrawVertex};
+#[cfg(feature = "pe_debug_ui")]
+use crate::debug::ui::DebugUi;
+use crate::desktop::file_drop::FileDrop;
+use crate::input::keyboard::Keyboard;
+use crate::input::text_input::TextInput;
+use crate::render::draw_2d::Draw2D;
+use crate::render::sprite::SpriteBatcher;
+use log::{error, info, warn};
+use pluto_engine_core_platform_wgpu::debug_lines::{
+    DebugLineBatch, DebugLineVertex, WgpuDebugLineRenderer,
+};
+#[cfg(feature = "pe_debug_ui")]
+use pluto_engine_core_platform_wgpu::debug_ui::WgpuDebugUiRenderer;
+use pluto_engine_core_platform_wgpu::device::{WgpuDevice, WgpuQueue};
+use pluto_engine_core_platform_wgpu::draw_2d::{Draw2DVertex, WgpuDraw2DRenderer};
+use pluto_engine_core_platform_wgpu::frame::{record_frame, WgpuFrameComposer};
 use pluto_engine_core_platform_wgpu::instance::WgpuInstance;
+use pluto_engine_core_platform_wgpu::post_process::WgpuPostProcessChain;
+use pluto_engine_core_platform_wgpu::sprite::{SpriteDraw, SpriteVertex, WgpuSpriteRenderer};
+use pluto_engine_core_platform_wgpu::surface::WgpuSurface;
+use pluto_engine_core_platform_wgpu::target::WgpuRenderTarget;
 use pluto_engine_core_platform_winit::window::WinitWindow;
-use pluto_engine_display::pluto_engine_render::surface::{Surface, SurfaceError, SurfaceTexture};
+use pluto_engine_display::pluto_engine_render::debug::DeviceFrameCapture;
+use pluto_engine_display::pluto_engine_render::deletion::DeviceDeferredDeletion;
+use pluto_engine_display::pluto_engine_render::device::{
+    CommandBuffer, CommandBufferBuilder, Device, Queue,
+};
+use pluto_engine_display::pluto_engine_render::surface::{
+    Surface, SurfaceError, SurfaceRecovery, SurfaceTexture,
+};
+use pluto_engine_display::pluto_engine_render::target::{RenderTarget, SurfaceDependentResource};
+use pluto_engine_display::pluto_engine_render::texture::Texture;
 use pluto_engine_display::pluto_engine_window::event_loop::DisplayEvent;
-use pluto_engine_display::pluto_engine_window::window::{PhysicalSize, Window, WindowEvent};
+use pluto_engine_display::pluto_engine_window::window::{LogicalSize, Window, WindowEvent};
 use pluto_engine_display::{
-    ApplicationDisplay, ApplicationState, PlutoDevice, PlutoSurface, PlutoSurfaceSize,
-    WindowDisplay,
+    ApplicationDisplay, ApplicationState, PlutoDevice, PlutoQueue, PlutoSurface, PlutoSurfaceSize,
+    PlutoSurfaceTexture, WindowDisplay,
 };
+use std::sync::Arc;
 
-pub struct WinitWgpuDisplay<'p> {
-    surface: &'p mut PlutoSurface<'p, WinitWgpuDisplay<'p>>,
-    window: &'p WinitWindow,
-    device: &'p WgpuDevice<'p>,
-    surface_size: PhysicalSize<<PlutoSurface<'p, WinitWgpuDisplay<'p>> as Surface<'p>>::SizeType>,
+/// A display owning its window and the surface created for it,
+/// so that it can be stored by the state of the application without borrowing either.
+///
+/// *The device and queue are shared with the state, see [`ApplicationDisplay::get_device`].*
+pub struct WinitWgpuDisplay {
+    // Declared before the window to be dropped before it
+    surface: WgpuSurface<'static>,
+    window: WinitWindow,
+    device: Arc<WgpuDevice<'static>>,
+    queue: Arc<WgpuQueue<'static>>,
+    surface_size: PlutoSurfaceSize<'static, WinitWgpuDisplay>,
+    scale_factor: f64,
     close_requested: bool,
+    surface_recovery: SurfaceRecovery,
+    keyboard: Keyboard,
+    text_input: TextInput,
+    file_drop: FileDrop,
+    frame_capture: FrameCapture,
+    /// Measures the delta passed to [`ApplicationState::update`].
+    frame_time: Time,
+    post_process: Option<WgpuPostProcessChain<'static>>,
+    frame_composer: Option<WgpuFrameComposer<'static>>,
+    sprites: Option<(SpriteBatcher, WgpuSpriteRenderer)>,
+    draw_2d: Option<(Draw2D, WgpuDraw2DRenderer)>,
+    debug_draw: Option<(DebugDraw, WgpuDebugLineRenderer)>,
+    #[cfg(feature = "pe_debug_ui")]
+    debug_ui: Option<(DebugUi, WgpuDebugUiRenderer)>,
+    suspended: bool,
 }
 
-impl<'p> WindowDisplay for WinitWgpuDisplay<'p> {
+impl WinitWgpuDisplay {
+    /// Returns the keyboard fed by the events of this display,
+    /// to be provided to layers using a [`crate::input::keyboard::KeyboardLayer`].
+    pub fn get_keyboard(&self) -> &Keyboard {
+        &self.keyboard
+    }
+
+    /// Returns the text input fed by the events of this display,
+    /// to be provided to layers using a [`crate::input::text_input::TextInputLayer`].
+    ///
+    /// *Input method requests made through the system are applied before each frame.*
+    pub fn get_text_input(&self) -> &TextInput {
+        &self.text_input
+    }
+
+    /// Returns the dropped files fed by the events of this display,
+    /// to be provided to layers using a [`crate::desktop::file_drop::FileDropLayer`].
+    pub fn get_file_drop(&self) -> &FileDrop {
+        &self.file_drop
+    }
+
+    /// Returns the frame captures started by this display,
+    /// to be provided to layers using a [`crate::debug::capture::FrameCaptureLayer`].
+    pub fn get_frame_capture(&self) -> &FrameCapture {
+        &self.frame_capture
+    }
+
+    /// *Returns `true` if a requested capture was started for the frame being rendered.*
+    fn begin_frame_capture(&self) -> bool {
+        let capturing = self.frame_capture.take_request();
+        if capturing {
+            self.device.start_frame_capture();
+        }
+
+        capturing
+    }
+
+    /// Reconfigures the surface after it was lost or outdated, backing off while it keeps failing.
+    fn recover_surface(&mut self) {
+        if self.surface_recovery.on_failure() {
+            self.refresh_surface();
+        }
+
+        // Recovery continues on the next repaint, even if the application is idle
+        self.window.request_repaint();
+    }
+
+    /// Runs the chain between rendering and presenting each frame,
+    /// the scene should then be rendered into [`WinitWgpuDisplay::get_scene_target`].
+    pub fn set_post_process_chain(&mut self, mut chain: WgpuPostProcessChain<'static>) {
+        chain.on_surface_resized(&self.device, self.surface_size);
+        self.post_process = Some(chain);
+    }
+
+    pub fn clear_post_process_chain(&mut self) -> Option<WgpuPostProcessChain<'static>> {
+        self.post_process.take()
+    }
+
+    pub fn get_post_process_chain(&self) -> Option<&WgpuPostProcessChain<'static>> {
+        self.post_process.as_ref()
+    }
+
+    /// *Returns the target of the scene if a post-processing chain is set.*
+    pub fn get_scene_target(&self) -> Option<&WgpuRenderTarget<'static>> {
+        self.get_post_process_chain()
+            .map(WgpuPostProcessChain::get_scene_target)
+    }
+
+    /// Records the passes of the composer every frame, before the state renders.
+    ///
+    /// *Passes draw into the scene target if a post-processing chain is set.*
+    pub fn set_frame_composer(&mut self, composer: WgpuFrameComposer<'static>) {
+        self.frame_composer = Some(composer);
+    }
+
+    pub fn clear_frame_composer(&mut self) -> Option<WgpuFrameComposer<'static>> {
+        self.frame_composer.take()
+    }
+
+    /// Returns the composer, allowing to add and remove passes between frames.
+    pub fn get_frame_composer_mut(&mut self) -> Option<&mut WgpuFrameComposer<'static>> {
+        self.frame_composer.as_mut()
+    }
+
+    fn run_frame_composer(&mut self, texture: &PlutoSurfaceTexture<'static, Self>) {
+        if let Some(composer) = &mut self.frame_composer {
+            let target = match &self.post_process {
+                Some(chain) => chain.get_scene_target().get_color_texture().create_view(),
+                None => texture.get_texture_view(),
+            };
+
+            let mut command_buffer = self.device.begin_command_buffer();
+            record_frame(composer, &mut command_buffer, &target);
+
+            self.queue.get_backing_queue().submit(std::iter::once(
+                command_buffer.build().get_backing_command_buffer(),
+            ));
+        }
+    }
+
+    /// Draws the sprites submitted to the batcher on top of each frame,
+    /// after post-processing and below 2D shapes.
+    pub fn set_sprites(&mut self, sprites: SpriteBatcher) {
+        sprites.set_screen_size(self.logical_size());
+        let renderer = WgpuSpriteRenderer::new(&self.device, self.surface.get_texture_format());
+        self.sprites = Some((sprites, renderer));
+    }
+
+    pub fn clear_sprites(&mut self) -> Option<SpriteBatcher> {
+        self.sprites.take().map(|(sprites, _)| sprites)
+    }
+
+    fn run_sprites(&mut self, texture: &PlutoSurfaceTexture<'static, Self>) {
+        let logical_size = self.logical_size();
+
+        if let Some((sprites, renderer)) = &mut self.sprites {
+            sprites.set_screen_size(logical_size);
+
+            let Some(frame) = sprites.take_frame() else {
+                return;
+            };
+
+            for (texture, pixels) in &frame.uploads {
+                renderer.create_texture(&self.device, &self.queue, texture.id, pixels);
+            }
+
+            let vertices = frame
+                .vertices
+                .iter()
+                .map(|vertex| SpriteVertex {
+                    position: vertex.position.into(),
+                    uv: vertex.uv.into(),
+                    color: [
+                        vertex.color.r,
+                        vertex.color.g,
+                        vertex.color.b,
+                        vertex.color.a,
+                    ],
+                })
+                .collect::<Vec<_>>();
+
+            let draws = frame
+                .batches
+                .iter()
+                .map(|batch| SpriteDraw {
+                    texture: batch.texture.id,
+                    vertices: batch.vertices.start as u32..batch.vertices.end as u32,
+                })
+                .collect::<Vec<_>>();
+
+            let mut command_buffer = self.device.begin_command_buffer();
+            renderer.record(
+                &self.device,
+                &mut command_buffer,
+                &texture.get_texture_view(),
+                frame.projection.into(),
+                &vertices,
+                &draws,
+            );
+
+            self.queue.get_backing_queue().submit(std::iter::once(
+                command_buffer.build().get_backing_command_buffer(),
+            ));
+
+            // The textures are only dropped by the renderer, the submitted pass keeps them alive
+            for texture in &frame.freed {
+                renderer.free_texture(texture.id);
+            }
+        }
+    }
+
+    /// Draws the shapes submitted to the 2D system on top of each frame,
+    /// after post-processing and below debug drawing.
+    pub fn set_draw_2d(&mut self, draw_2d: Draw2D) {
+        draw_2d.set_screen_size(self.logical_size());
+        let renderer = WgpuDraw2DRenderer::new(&self.device, self.surface.get_texture_format());
+        self.draw_2d = Some((draw_2d, renderer));
+    }
+
+    pub fn clear_draw_2d(&mut self) -> Option<Draw2D> {
+        self.draw_2d.take().map(|(draw_2d, _)| draw_2d)
+    }
+
+    fn run_draw_2d(&mut self, texture: &PlutoSurfaceTexture<'static, Self>) {
+        let logical_size = self.logical_size();
+
+        if let Some((draw_2d, renderer)) = &self.draw_2d {
+            draw_2d.set_screen_size(logical_size);
+
+            let Some(frame) = draw_2d.take_frame() else {
+                return;
+            };
+
+            let vertices = frame
+                .vertices
+                .iter()
+                .map(|vertex| Draw2DVertex {
+                    position: vertex.position.into(),
+                    color: [
+                        vertex.color.r,
+                        vertex.color.g,
+                        vertex.color.b,
+                        vertex.color.a,
+                    ],
+                })
+                .collect::<Vec<_>>();
+
+            let mut command_buffer = self.device.begin_command_buffer();
+            renderer.record(
+                &self.device,
+                &mut command_buffer,
+                &texture.get_texture_view(),
+                frame.projection.into(),
+                &vertices,
+            );
+
+            self.queue.get_backing_queue().submit(std::iter::once(
+                command_buffer.build().get_backing_command_buffer(),
+            ));
+        }
+    }
+
+    /// Draws the lines submitted to the debug draw system on top of each frame,
+    /// after post-processing and below the debug UI.
+    pub fn set_debug_draw(&mut self, debug_draw: DebugDraw) {
+        debug_draw.set_screen_size(self.logical_size());
+        let renderer = WgpuDebugLineRenderer::new(&self.device, self.surface.get_texture_format());
+        self.debug_draw = Some((debug_draw, renderer));
+    }
+
+    pub fn clear_debug_draw(&mut self) -> Option<DebugDraw> {
+        self.debug_draw.take().map(|(debug_draw, _)| debug_draw)
+    }
+
+    fn run_debug_draw(&mut self, texture: &PlutoSurfaceTexture<'static, Self>) {
+        let logical_size = self.logical_size();
+
+        if let Some((debug_draw, renderer)) = &self.debug_draw {
+            debug_draw.set_screen_size(logical_size);
+
+            let Some(frame) = debug_draw.take_frame() else {
+                return;
+            };
+
+            let vertices = |vertices: &[DebugDrawVertex]| {
+                vertices
+                    .iter()
+                    .map(|vertex| DebugLineVertex {
+                        position: vertex.position.into(),
+                        color: [
+                            vertex.color.r,
+                            vertex.color.g,
+                            vertex.color.b,
+                            vertex.color.a,
+                        ],
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let (lines, screen_lines) = (vertices(&frame.lines), vertices(&frame.screen_lines));
+
+            let mut command_buffer = self.device.begin_command_buffer();
+            renderer.record(
+                &self.device,
+                &mut command_buffer,
+                &texture.get_texture_view(),
+                &[
+                    DebugLineBatch {
+                        view_projection: frame.view_projection.into(),
+                        vertices: &lines,
+                    },
+                    DebugLineBatch {
+                        view_projection: frame.screen_projection.into(),
+                        vertices: &screen_lines,
+                    },
+                ],
+            );
+
+            self.queue.get_backing_queue().submit(std::iter::once(
+                command_buffer.build().get_backing_command_buffer(),
+            ));
+        }
+    }
+
+    /// Feeds the debug UI with the events of this display and draws it on top of each frame,
+    /// after post-processing.
+    #[cfg(feature = "pe_debug_ui")]
+    pub fn set_debug_ui(&mut self, debug_ui: DebugUi) {
+        debug_ui.set_screen(self.surface_size, self.scale_factor);
+        let renderer = WgpuDebugUiRenderer::new(&self.device, self.surface.get_texture_format());
+        self.debug_ui = Some((debug_ui, renderer));
+    }
+
+    #[cfg(feature = "pe_debug_ui")]
+    pub fn clear_debug_ui(&mut self) -> Option<DebugUi> {
+        self.debug_ui.take().map(|(debug_ui, _)| debug_ui)
+    }
+
+    #[cfg(feature = "pe_debug_ui")]
+    fn run_debug_ui(&mut self, texture: &PlutoSurfaceTexture<'static, Self>) {
+        if let Some((debug_ui, renderer)) = &mut self.debug_ui {
+            let Some(output) = debug_ui.take_output() else {
+                return;
+            };
+
+            renderer.update_textures(&self.device, &self.queue, &output.textures_delta);
+
+            let mut command_buffer = self.device.begin_command_buffer();
+            renderer.record(
+                &self.device,
+                &self.queue,
+                &mut command_buffer,
+                &texture.get_texture_view(),
+                &output.primitives,
+                self.surface_size,
+                output.pixels_per_point,
+            );
+
+            self.queue.get_backing_queue().submit(std::iter::once(
+                command_buffer.build().get_backing_command_buffer(),
+            ));
+        }
+    }
+
+    fn run_post_process(&self, texture: &PlutoSurfaceTexture<'static, Self>) {
+        if let Some(chain) = &self.post_process {
+            let mut command_buffer = self.device.begin_command_buffer();
+            chain.record(&mut command_buffer, &texture.get_texture_view());
+
+            self.queue.get_backing_queue().submit(std::iter::once(
+                command_buffer.build().get_backing_command_buffer(),
+            ));
+        }
+    }
+
+    /// Destroys the resources dropped with [`DeviceDeferredDeletion::destroy_deferred`]
+    /// once the GPU has finished the frames which may use them.
+    fn end_frame(&self) {
+        self.device.end_frame(&self.queue);
+    }
+}
+
+impl WindowDisplay for WinitWgpuDisplay {
     type WindowType = WinitWindow;
 
     fn close_requested(&self) -> bool {
@@ -50,58 +444,169 @@ impl<'p> WindowDisplay for WinitWgpuDisplay<'p> {
     }
 
     fn on_event(&mut self, window_event: &WindowEvent) {
+        #[cfg(feature = "pe_debug_ui")]
+        if let Some((debug_ui, _)) = &self.debug_ui {
+            debug_ui.on_event(window_event);
+        }
+
         match window_event {
             WindowEvent::CloseRequested => self.close_requested = true,
             WindowEvent::Resized(size) => self.resize_surface(*size),
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                new_size,
+            } => {
+                self.scale_factor = *scale_factor;
+                self.resize_surface(*new_size);
+            }
+            WindowEvent::KeyboardInput { .. } => self.keyboard.on_event(window_event),
+            WindowEvent::TextInput(_) => self.text_input.on_event(window_event),
+            WindowEvent::FileDropped(_)
+            | WindowEvent::FileHovered(_)
+            | WindowEvent::FileHoverCancelled => self.file_drop.on_event(window_event),
             _ => {}
         };
     }
 
     fn get_window(&self) -> &Self::WindowType {
-        self.window
+        &self.window
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    fn logical_size(&self) -> LogicalSize<f64> {
+        self.surface_size.to_logical(self.scale_factor)
+    }
+
+    fn is_suspended(&self) -> bool {
+        self.suspended
     }
 }
 
-impl<'p> ApplicationDisplay<'p> for WinitWgpuDisplay<'p> {
-    type ContextType = WgpuInstance<'p, Self::WindowType>;
+impl ApplicationDisplay<'static> for WinitWgpuDisplay {
+    type ContextType = WgpuInstance<'static, Self::WindowType>;
 
     fn new(
-        surface: &'p mut PlutoSurface<'p, Self>,
-        window: &'p Self::WindowType,
-        device: &'p PlutoDevice<'p, Self>,
+        surface: PlutoSurface<'static, Self>,
+        window: Self::WindowType,
+        device: Arc<PlutoDevice<'static, Self>>,
+        queue: Arc<PlutoQueue<'static, Self>>,
     ) -> Self {
         Self {
             surface,
+            surface_size: window.get_size(),
+            scale_factor: window.get_scale_factor(),
             window,
             device,
-            surface_size: Default::default(),
+            queue,
             close_requested: false,
+            surface_recovery: SurfaceRecovery::new(),
+            keyboard: Keyboard::new(),
+            text_input: TextInput::new(),
+            file_drop: FileDrop::new(),
+            frame_capture: FrameCapture::new(),
+            frame_time: Time::new(),
+            post_process: None,
+            frame_composer: None,
+            sprites: None,
+            draw_2d: None,
+            debug_draw: None,
+            #[cfg(feature = "pe_debug_ui")]
+            debug_ui: None,
+            suspended: false,
         }
     }
 
-    fn on_event<AS: ApplicationState<'p, Self>>(
+    fn on_event<AS: ApplicationState<'static, Self>>(
         &mut self,
         display_event: DisplayEvent,
     ) -> Box<dyn FnOnce(&mut AS)>
     where
-        Self: Sized + ApplicationDisplay<'p>,
+        Self: Sized + ApplicationDisplay<'static>,
     {
         match &display_event {
-            DisplayEvent::NextFrame => self.window.request_repaint(),
+            DisplayEvent::NextFrame | DisplayEvent::Repaint if self.suspended => {}
+            DisplayEvent::NextFrame => {
+                self.text_input.apply_requests(&self.window);
+                self.window.request_repaint();
+            }
+            DisplayEvent::Suspended if !self.suspended => {
+                self.suspended = true;
+
+                // Android destroys the native window while suspended, the surface must not outlive it
+                #[cfg(target_os = "android")]
+                self.surface.release();
+
+                return Box::new(|s| s.on_suspend());
+            }
+            // The platform may have replaced the native window, so the surface is reconfigured
+            DisplayEvent::Resumed if self.suspended => {
+                self.suspended = false;
+
+                #[cfg(target_os = "android")]
+                {
+                    self.surface.recreate(&self.window, &self.device);
+                    self.resize_surface(self.window.get_size());
+                }
+
+                self.refresh_surface();
+                return Box::new(|s| s.on_resume());
+            }
+            DisplayEvent::Suspended | DisplayEvent::Resumed => {}
             DisplayEvent::Repaint => {
                 return Box::new(|s| {
+                    let frame_time = &mut s.display().frame_time;
+                    frame_time.update();
+                    let delta = frame_time.delta();
+                    s.update(delta);
+
                     let surface = s.display().get_surface();
                     match surface.acquire_next_texture() {
                         Ok(texture) => {
+                            let display = s.display();
+                            if display.surface_recovery.get_failures() > 0 {
+                                info!(
+                                    "Surface for window ID {:?} recovered.",
+                                    display.window.get_id()
+                                );
+                            }
+                            display.surface_recovery.on_success();
+
+                            let capturing = s.display().begin_frame_capture();
+                            s.display().run_frame_composer(&texture);
                             s.render(&texture);
+                            s.display().run_post_process(&texture);
+                            s.display().run_sprites(&texture);
+                            s.display().run_draw_2d(&texture);
+                            s.display().run_debug_draw(&texture);
+                            #[cfg(feature = "pe_debug_ui")]
+                            s.display().run_debug_ui(&texture);
                             texture.present();
+                            s.display().end_frame();
+                            if capturing {
+                                s.display().device.stop_frame_capture();
+                            }
+                        }
+                        Err(SurfaceError::OutOfMemory) => {
+                            let display = s.display();
+                            error!(
+                                "Out of memory while acquiring the surface for window ID {:?}, closing.",
+                                display.window.get_id()
+                            );
+                            display.close_requested = true;
                         }
-                        Err(SurfaceError::OutOfMemory) => {}
                         Err(SurfaceError::DeviceLost) => {
                             let display = s.display();
-                            warn!("Device for window ID {:?} lost!", display.window.get_id());
-                            display.refresh_surface();
+                            if display.surface_recovery.get_failures() == 0 {
+                                warn!("Surface for window ID {:?} lost!", display.window.get_id());
+                            }
+                            display.recover_surface();
                         }
+                        Err(SurfaceError::Outdated) => s.display().recover_surface(),
+                        // The frame is retried on the next repaint
+                        Err(SurfaceError::Timeout) => s.display().window.request_repaint(),
                         Err(SurfaceError::Other(ref e)) => {
                             let display = s.display();
                             error!(
@@ -111,27 +616,64 @@ impl<'p> ApplicationDisplay<'p> for WinitWgpuDisplay<'p> {
                             );
                         }
                     }
-                })
+                });
+            }
+            DisplayEvent::WindowEvent(ref window_event) => {
+                WindowDisplay::on_event(self, window_event);
+
+                let resized = matches!(
+                    window_event,
+                    WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. }
+                );
+                let window_event = window_event.clone();
+
+                return Box::new(move |s| {
+                    if resized {
+                        let size = s.display().surface_size;
+                        s.on_surface_resized(size);
+                    }
+
+                    ApplicationState::on_event(s, &window_event);
+                });
+            }
+            DisplayEvent::User(event) => {
+                let event = event.clone();
+                return Box::new(move |s| s.on_user_event(&event));
             }
-            DisplayEvent::WindowEvent(ref window_event) => match window_event {
-                _ => WindowDisplay::on_event(self, window_event),
-            },
             DisplayEvent::Disconnected => {}
         };
 
         Box::new(|_| {})
     }
 
+    fn shutdown(&mut self) {
+        self.post_process = None;
+        self.frame_composer = None;
+        self.device.wait_idle();
+    }
+
     fn refresh_surface(&mut self) {
-        self.surface.resize(self.device, self.surface_size);
+        self.surface.resize(&self.device, self.surface_size);
     }
 
-    fn resize_surface(&mut self, size: PlutoSurfaceSize<'p, Self>) {
+    fn resize_surface(&mut self, size: PlutoSurfaceSize<'static, Self>) {
         self.surface_size = size;
-        self.surface.resize(self.device, size);
+        self.surface.resize(&self.device, size);
+
+        if let Some(chain) = &mut self.post_process {
+            chain.on_surface_resized(&self.device, size);
+        }
+    }
+
+    fn get_surface(&self) -> &PlutoSurface<'static, Self> {
+        &self.surface
+    }
+
+    fn get_device(&self) -> &Arc<PlutoDevice<'static, Self>> {
+        &self.device
     }
 
-    fn get_surface(&self) -> &PlutoSurface<'p, Self> {
-        self.surface
+    fn get_queue(&self) -> &Arc<PlutoQueue<'static, Self>> {
+        &self.queue
     }
 }
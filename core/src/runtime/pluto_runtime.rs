@@ -25,6 +25,7 @@
 use crate::runtime::{ApplicationBootstrapper, Runtime};
 
 use pluto_engine_display::pluto_engine_window::event_loop::{EventLoop, EventLoopWindowFactory};
+use pluto_engine_display::pluto_engine_window::executor::block_on;
 use std::convert::Infallible;
 use std::thread;
 
@@ -32,14 +33,22 @@ pub struct PlutoRuntime;
 
 impl<E: EventLoop> Runtime<E> for PlutoRuntime {
     fn run(bootstrapper: ApplicationBootstrapper<E>) -> Infallible {
+        #[cfg(feature = "pe_size_report")]
+        crate::runtime::bundle::BundleReport::collect().log();
+
         E::run(move |evt_loop| {
             let runtime = Self;
             runtime.create_application(evt_loop, bootstrapper);
         })
     }
 
+    /// Runs the application on a dedicated thread, short-lived work of the application and the
+    /// engine runs on the [`JobPool`](pluto_io::jobs::JobPool) of the layer manager instead.
     fn spawn_application_worker<F: FnOnce() + Send + 'static>(&self, worker: F) {
-        thread::spawn(worker);
+        thread::Builder::new()
+            .name("application".to_owned())
+            .spawn(worker)
+            .expect("failed to spawn the application thread");
     }
 
     fn create_application<ELW: EventLoopWindowFactory<E> + ?Sized>(
@@ -47,9 +56,14 @@ impl<E: EventLoop> Runtime<E> for PlutoRuntime {
         event_loop: &mut ELW,
         bootstrapper: ApplicationBootstrapper<E>,
     ) {
-        let window = event_loop.create_window();
-        <PlutoRuntime as Runtime<E>>::spawn_application_worker(self, move || {
-            bootstrapper.bootstrap(window);
-        });
+        let window = event_loop.create_window_with_channel(bootstrapper.get_event_channel());
+
+        if bootstrapper.runs_on_worker_thread() {
+            <PlutoRuntime as Runtime<E>>::spawn_application_worker(self, move || {
+                block_on(bootstrapper.bootstrap(window));
+            });
+        } else {
+            event_loop.spawn_local(bootstrapper.bootstrap(window));
+        }
     }
 }
@@ -0,0 +1,497 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Engine and application settings, persisted between runs and versioned with [`Migrations`].
+
+use crate::application::event::EventBus;
+use crate::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use crate::application::system::System;
+use pluto_engine_display::pluto_engine_window::keyboard::Key;
+use pluto_engine_display::pluto_engine_window::window::LogicalSize;
+use pluto_io::save::{Migrations, Persisted, SaveError};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+pub mod storage;
+
+use storage::SettingsStorage;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettingValue {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl From<bool> for SettingValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f64> for SettingValue {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<f32> for SettingValue {
+    fn from(value: f32) -> Self {
+        Self::Number(value as f64)
+    }
+}
+
+impl From<u32> for SettingValue {
+    fn from(value: u32) -> Self {
+        Self::Number(value as f64)
+    }
+}
+
+impl From<i32> for SettingValue {
+    fn from(value: i32) -> Self {
+        Self::Number(value as f64)
+    }
+}
+
+impl From<String> for SettingValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for SettingValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_owned())
+    }
+}
+
+/// A flat, human-editable document of settings, one `key = value` per line.
+///
+/// Keys are usually grouped with dots, e.g. `window.width = 1280`. Values are `true`, `false`,
+/// numbers or double-quoted strings, lines starting with `#` are comments. Schemas implement
+/// [`Persisted`] by converting to and from a table.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SettingsTable {
+    values: BTreeMap<String, SettingValue>,
+}
+
+impl SettingsTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut table = Self::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |reason: &str| format!("line {}: {}", index + 1, reason);
+
+            let (key, value) = line.split_once('=').ok_or_else(|| error("expected `=`"))?;
+            let key = key.trim();
+            if key.is_empty()
+                || !key
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "._-".contains(c))
+            {
+                return Err(error("invalid key"));
+            }
+
+            let value = match value.trim() {
+                "true" => SettingValue::Bool(true),
+                "false" => SettingValue::Bool(false),
+                quoted if quoted.starts_with('"') => {
+                    SettingValue::String(unquote(quoted).ok_or_else(|| error("invalid string"))?)
+                }
+                number => SettingValue::Number(number.parse().map_err(|_| error("invalid value"))?),
+            };
+
+            table.values.insert(key.to_owned(), value);
+        }
+
+        Ok(table)
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<SettingValue>) {
+        self.values.insert(key.into(), value.into());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&SettingValue> {
+        self.values.get(key)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)? {
+            SettingValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        match self.get(key)? {
+            SettingValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_f32(&self, key: &str) -> Option<f32> {
+        self.get_f64(key).map(|value| value as f32)
+    }
+
+    /// *Returns `None` for negative or fractional numbers.*
+    pub fn get_u32(&self, key: &str) -> Option<u32> {
+        self.get_f64(key)
+            .filter(|value| value.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(value))
+            .map(|value| value as u32)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            SettingValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// *Returns the entries whose keys start with `prefix` followed by a dot,
+    /// with the prefix removed from the keys.*
+    pub fn section<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a SettingValue)> + 'a {
+        self.values.iter().filter_map(move |(key, value)| {
+            key.strip_prefix(prefix)?
+                .strip_prefix('.')
+                .map(|name| (name, value))
+        })
+    }
+
+    /// *Returns the document, with the entries sorted by their keys.*
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for (key, value) in &self.values {
+            let _ = match value {
+                SettingValue::Bool(value) => writeln!(text, "{} = {}", key, value),
+                SettingValue::Number(value) => writeln!(text, "{} = {}", key, value),
+                SettingValue::String(value) => writeln!(text, "{} = {:?}", key, value),
+            };
+        }
+
+        text
+    }
+}
+
+/// Reverses the escaping of [`str::escape_debug`] for the escapes produced by `{:?}`.
+fn unquote(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        value.push(match c {
+            '"' => return None,
+            '\\' => match chars.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                'u' => {
+                    let code = chars.by_ref().skip(1).take_while(|&c| c != '}');
+                    char::from_u32(u32::from_str_radix(&code.collect::<String>(), 16).ok()?)?
+                }
+                escaped => escaped,
+            },
+            c => c,
+        });
+    }
+
+    Some(value)
+}
+
+/// Published on the [`EventBus`] by the [`SettingsLayer`] when the settings were changed,
+/// containing the new settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SettingsChanged<T>(pub T);
+
+struct SettingsState<T: Persisted> {
+    value: T,
+    storage: Box<dyn SettingsStorage>,
+    migrations: Migrations<T>,
+    changed: bool,
+}
+
+/// A system holding settings of the schema `T`, loaded from and saved to a [`SettingsStorage`].
+///
+/// Provided to layers by the [`SettingsLayer`], which saves changed settings
+/// and notifies all layers about them.
+pub struct Settings<T: Persisted> {
+    state: Arc<Mutex<SettingsState<T>>>,
+}
+
+impl<T: Persisted> Clone for Settings<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<T: Persisted + Default + Clone + PartialEq + Send + 'static> Settings<T> {
+    /// Loads the settings from the storage, migrating older versions.
+    ///
+    /// *Falls back to the default settings if nothing was stored yet
+    /// or the stored settings could not be loaded.*
+    pub fn load(storage: Box<dyn SettingsStorage>, migrations: Migrations<T>) -> Self {
+        let value = match storage.read() {
+            Ok(Some(bytes)) => migrations.load(&bytes).unwrap_or_else(|err| {
+                log::warn!("Could not load the settings, using the defaults: {}", err);
+                T::default()
+            }),
+            Ok(None) => T::default(),
+            Err(err) => {
+                log::warn!("Could not read the settings, using the defaults: {}", err);
+                T::default()
+            }
+        };
+
+        Self {
+            state: Arc::new(Mutex::new(SettingsState {
+                value,
+                storage,
+                migrations,
+                changed: false,
+            })),
+        }
+    }
+
+    pub fn get(&self) -> T {
+        self.state.lock().unwrap().value.clone()
+    }
+
+    /// Modifies the settings, marking them as changed if the value differs afterwards.
+    pub fn update(&self, f: impl FnOnce(&mut T)) {
+        let mut state = self.state.lock().unwrap();
+        let mut value = state.value.clone();
+        f(&mut value);
+
+        if value != state.value {
+            state.value = value;
+            state.changed = true;
+        }
+    }
+
+    pub fn reset_to_default(&self) {
+        self.update(|value| *value = T::default());
+    }
+
+    /// Writes the settings to the storage, regardless of whether they were changed.
+    pub fn save(&self) -> Result<(), SaveError> {
+        let mut state = self.state.lock().unwrap();
+        let bytes = state.migrations.save(&state.value);
+
+        Ok(state.storage.write(&bytes)?)
+    }
+
+    /// *Returns the settings if they were changed since the last call.*
+    pub fn take_changed(&self) -> Option<T> {
+        let mut state = self.state.lock().unwrap();
+
+        std::mem::take(&mut state.changed).then(|| state.value.clone())
+    }
+}
+
+impl<T: Persisted + 'static> System for Settings<T> {}
+
+/// A layer providing the [`Settings`] to all layers above it.
+///
+/// Changes made during an iteration are saved afterwards and published as a [`SettingsChanged`]
+/// event, so layers like the display can react to them during the next iteration.
+pub struct SettingsLayer<T: Persisted>(pub Settings<T>);
+
+impl<T: Persisted + Default + Clone + PartialEq + Send + 'static> Layer for SettingsLayer<T> {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        None
+    }
+
+    fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+        dependencies.provide(self.0.clone());
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        next.next(systems);
+
+        let Some(value) = self.0.take_changed() else {
+            return;
+        };
+
+        if let Err(err) = self.0.save() {
+            log::error!("Could not save the settings: {}", err);
+        }
+
+        if let Some(events) = systems.query_mut::<EventBus>() {
+            events.publish(SettingsChanged(value));
+        }
+    }
+}
+
+/// The settings of the engine itself, stored as a [`SettingsTable`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineSettings {
+    pub window_size: LogicalSize<u32>,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// The volume of all audio, from `0.0` to `1.0`.
+    pub master_volume: f32,
+    /// The keys bound to named actions of the application.
+    pub key_bindings: BTreeMap<String, Key>,
+}
+
+impl Default for EngineSettings {
+    fn default() -> Self {
+        Self {
+            window_size: LogicalSize {
+                width: 1280,
+                height: 720,
+            },
+            fullscreen: false,
+            vsync: true,
+            master_volume: 1.0,
+            key_bindings: BTreeMap::new(),
+        }
+    }
+}
+
+impl Persisted for EngineSettings {
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn encode(&self) -> Vec<u8> {
+        let mut table = SettingsTable::new();
+        table.set("window.width", self.window_size.width);
+        table.set("window.height", self.window_size.height);
+        table.set("window.fullscreen", self.fullscreen);
+        table.set("render.vsync", self.vsync);
+        table.set("audio.master_volume", self.master_volume);
+
+        for (action, key) in &self.key_bindings {
+            table.set(format!("bindings.{}", action), key.to_string());
+        }
+
+        table.to_text().into_bytes()
+    }
+
+    /// Missing or mistyped entries keep their defaults, so hand-edited files stay loadable.
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let text = std::str::from_utf8(bytes).map_err(|err| err.to_string())?;
+        let table = SettingsTable::parse(text)?;
+        let default = Self::default();
+
+        let key_bindings = table
+            .section("bindings")
+            .filter_map(|(action, value)| match value {
+                SettingValue::String(key) => Some((action.to_owned(), key.parse().ok()?)),
+                _ => None,
+            })
+            .collect();
+
+        Ok(Self {
+            window_size: LogicalSize {
+                width: table
+                    .get_u32("window.width")
+                    .unwrap_or(default.window_size.width),
+                height: table
+                    .get_u32("window.height")
+                    .unwrap_or(default.window_size.height),
+            },
+            fullscreen: table
+                .get_bool("window.fullscreen")
+                .unwrap_or(default.fullscreen),
+            vsync: table.get_bool("render.vsync").unwrap_or(default.vsync),
+            master_volume: table
+                .get_f32("audio.master_volume")
+                .map_or(default.master_volume, |volume| volume.clamp(0.0, 1.0)),
+            key_bindings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::settings::storage::{MemoryStorage, SettingsStorage};
+    use crate::settings::{EngineSettings, Settings, SettingsTable};
+    use pluto_engine_display::pluto_engine_window::keyboard::Key;
+    use pluto_io::save::Migrations;
+
+    /// A table with every kind of value is written and parsed back, along with a hand-edited one.
+    /// The values should survive the round trip, malformed lines should be rejected.
+    #[test]
+    fn test_settings_table() {
+        let mut table = SettingsTable::new();
+        table.set("a.flag", true);
+        table.set("a.number", 0.25);
+        table.set("b.name", "Quote \" and\nnewline ✓");
+
+        assert_eq!(SettingsTable::parse(&table.to_text()), Ok(table.clone()));
+
+        let edited = SettingsTable::parse("# comment\n\n  a.number=  3 \nb.name = \"x\"").unwrap();
+        assert_eq!(edited.get_u32("a.number"), Some(3));
+        assert_eq!(edited.get_str("b.name"), Some("x"));
+        assert_eq!(edited.get_bool("a.number"), None);
+
+        assert!(SettingsTable::parse("novalue").is_err());
+        assert!(SettingsTable::parse("a = \"unterminated").is_err());
+        assert!(SettingsTable::parse("bad key = 1").is_err());
+    }
+
+    /// Engine settings are changed, saved and loaded again from the same storage.
+    /// The change should be reported once and the loaded settings should match.
+    #[test]
+    fn test_settings_persist() {
+        let storage = MemoryStorage::new();
+        let settings =
+            Settings::<EngineSettings>::load(Box::new(storage.clone()), Migrations::new());
+        assert_eq!(settings.get(), EngineSettings::default());
+
+        settings.update(|settings| settings.vsync = true);
+        assert!(settings.take_changed().is_none());
+
+        settings.update(|settings| {
+            settings.master_volume = 0.5;
+            settings.key_bindings.insert("jump".to_owned(), Key::Space);
+        });
+        let changed = settings.take_changed().unwrap();
+        assert!(settings.take_changed().is_none());
+
+        settings.save().unwrap();
+        assert!(storage.read().unwrap().is_some());
+
+        let loaded = Settings::<EngineSettings>::load(Box::new(storage), Migrations::new());
+        assert_eq!(loaded.get(), changed);
+    }
+}
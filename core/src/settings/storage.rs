@@ -0,0 +1,174 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Where settings are persisted, a file in the configuration directory on native platforms
+//! and the local storage of the browser on the web.
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// A place a single settings document is read from and written to.
+pub trait SettingsStorage: Send {
+    /// *Returns `None` if nothing has been stored yet.*
+    fn read(&self) -> io::Result<Option<Vec<u8>>>;
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()>;
+}
+
+/// Stores settings in a file, creating its directory when first written.
+pub struct FileStorage {
+    pub path: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl SettingsStorage for FileStorage {
+    fn read(&self) -> io::Result<Option<Vec<u8>>> {
+        match std::fs::read(&self.path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// *The data is written to a temporary file first,
+    /// so a crash while saving does not destroy the previous settings.*
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let temp_path = self.path.with_extension("tmp");
+        std::fs::write(&temp_path, bytes)?;
+        std::fs::rename(temp_path, &self.path)
+    }
+}
+
+/// Keeps settings in memory only, shared between clones, e.g. for tests and tools.
+#[derive(Clone, Default)]
+pub struct MemoryStorage(Arc<Mutex<Option<Vec<u8>>>>);
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SettingsStorage for MemoryStorage {
+    fn read(&self) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.0.lock().unwrap().clone())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        *self.0.lock().unwrap() = Some(bytes.to_vec());
+        Ok(())
+    }
+}
+
+/// Stores settings in the local storage of the browser under a key.
+///
+/// *Local storage only holds strings, so the data is stored hex-encoded.*
+#[cfg(target_arch = "wasm32")]
+pub struct WebStorage {
+    pub key: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl WebStorage {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self { key: key.into() }
+    }
+
+    fn local_storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "local storage unavailable"))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl SettingsStorage for WebStorage {
+    fn read(&self) -> io::Result<Option<Vec<u8>>> {
+        let Ok(Some(hex)) = Self::local_storage()?.get_item(&self.key) else {
+            return Ok(None);
+        };
+
+        (0..hex.len() / 2)
+            .map(|i| u8::from_str_radix(hex.get(i * 2..i * 2 + 2).unwrap_or_default(), 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let hex = bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+
+        Self::local_storage()?
+            .set_item(&self.key, &hex)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "local storage is full"))
+    }
+}
+
+/// *Returns the platform configuration directory of the application, falling back
+/// to the working directory.*
+///
+/// Follows `XDG_CONFIG_HOME` on Linux and uses `%APPDATA%` on Windows
+/// and `~/Library/Application Support` on macOS.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_dir(app_name: &str) -> PathBuf {
+    let var = |name| std::env::var_os(name).map(PathBuf::from);
+
+    let base = if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+    };
+
+    base.unwrap_or_default().join(app_name)
+}
+
+/// *Returns the default storage of a settings document of the application,
+/// `name` identifies the document, e.g. `settings` for the engine settings.*
+pub fn default_storage(app_name: &str, name: &str) -> Box<dyn SettingsStorage> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        Box::new(WebStorage::new(format!("{}/{}", app_name, name)))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Box::new(FileStorage::new(
+            config_dir(app_name).join(name).with_extension("cfg"),
+        ))
+    }
+}
@@ -0,0 +1,61 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::mixer::SharedMixer;
+use std::fmt::Debug;
+
+/// An audio output device.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioDeviceInfo {
+    pub name: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub is_default: bool,
+}
+
+/// An audio backend, implemented by platform crates.
+pub trait AudioHost {
+    type DeviceType: AudioDevice;
+    type ErrorType: Debug;
+
+    fn enumerate_devices(&self) -> Vec<AudioDeviceInfo>;
+
+    /// Opens an output device, or the default one if `None`.
+    ///
+    /// The device keeps pulling samples from the mixer until dropped.
+    fn open_device(
+        &self,
+        device: Option<&AudioDeviceInfo>,
+        mixer: SharedMixer,
+    ) -> Result<Self::DeviceType, Self::ErrorType>;
+}
+
+/// An open audio output device.
+pub trait AudioDevice {
+    fn get_info(&self) -> &AudioDeviceInfo;
+
+    fn pause(&self);
+
+    fn resume(&self);
+}
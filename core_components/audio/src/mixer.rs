@@ -0,0 +1,484 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::sound::Sound;
+use crate::stream::{SoundCursor, SoundStream};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// A mixer shared between the engine and an audio device.
+pub type SharedMixer = Arc<Mutex<Mixer>>;
+
+/// The number of source frames decoded at once.
+const CHUNK_FRAMES: usize = 256;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PlaybackParams {
+    pub volume: f32,
+    /// The playback speed, also shifting the pitch. `1.0` plays at the original pitch.
+    pub pitch: f32,
+    /// The stereo balance, from `-1.0` (left) to `1.0` (right).
+    pub pan: f32,
+    pub looping: bool,
+    /// When more voices are playing than the mixer allows,
+    /// the voices with the lowest priority are virtualized first.
+    pub priority: u8,
+}
+
+impl Default for PlaybackParams {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pitch: 1.0,
+            pan: 0.0,
+            looping: false,
+            priority: 0,
+        }
+    }
+}
+
+impl PlaybackParams {
+    /// Attenuates the volume by the distance of the source from the listener.
+    ///
+    /// The volume is unchanged up to the reference distance, then falls off with the inverse
+    /// of the distance, reaching silence at the maximum distance. Silent voices are virtualized.
+    pub fn with_distance(
+        mut self,
+        distance: f32,
+        reference_distance: f32,
+        max_distance: f32,
+    ) -> Self {
+        self.volume *= if distance >= max_distance {
+            0.0
+        } else {
+            (reference_distance / distance.max(reference_distance)).min(1.0)
+        };
+
+        self
+    }
+}
+
+/// Voice counts of the last [`Mixer::mix`] call.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct MixerStats {
+    pub playing: usize,
+    /// Voices which were mixed into the output.
+    pub audible: usize,
+    /// Voices which only advanced their position, because they were too quiet
+    /// or exceeded the maximum voice count.
+    pub virtualized: usize,
+}
+
+/// Identifies a sound played by a [`Mixer`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct VoiceId(u64);
+
+struct Voice {
+    stream: Box<dyn SoundStream>,
+    params: PlaybackParams,
+    /// Decoded stereo frames, starting at the frame before the current position.
+    frames: Vec<[f32; 2]>,
+    /// The fractional position in `frames`.
+    position: f64,
+    chunk: Vec<f32>,
+    ended: bool,
+}
+
+impl Voice {
+    fn new(stream: Box<dyn SoundStream>, params: PlaybackParams) -> Self {
+        let channels = stream.channels().max(1) as usize;
+
+        Self {
+            stream,
+            params,
+            frames: Vec::new(),
+            position: 0.0,
+            chunk: vec![0.0; CHUNK_FRAMES * channels],
+            ended: false,
+        }
+    }
+
+    /// Decodes frames until at least `len` are available or the stream ends.
+    fn fill(&mut self, len: usize) {
+        let channels = self.stream.channels().max(1) as usize;
+        let mut rewound = false;
+
+        while self.frames.len() < len && !self.ended {
+            let read = self.stream.read(&mut self.chunk);
+
+            if read == 0 {
+                // A looping stream which is empty right after rewinding would loop forever
+                if self.params.looping && !rewound && self.stream.rewind() {
+                    rewound = true;
+                } else {
+                    self.ended = true;
+                }
+
+                continue;
+            }
+
+            rewound = false;
+
+            let frames = self.chunk[..read].chunks_exact(channels);
+            self.frames.extend(frames.map(|frame| match *frame {
+                [mono] => [mono, mono],
+                [left, right, ..] => [left, right],
+                [] => unreachable!(),
+            }));
+        }
+    }
+
+    /// Advances the position by the given number of output frames without mixing.
+    ///
+    /// *Returns `false` once the voice has finished.*
+    fn skip(&mut self, output_frames: usize, rate_ratio: f64) -> bool {
+        let channels = self.stream.channels().max(1) as usize;
+        self.position += output_frames as f64 * self.params.pitch.max(0.0) as f64 * rate_ratio;
+
+        let buffered = self.frames.len();
+        if (self.position as usize) < buffered {
+            return true;
+        }
+
+        // Skip the rest in the stream, the decoded frames start over at the new position
+        let mut remaining = self.position as usize - buffered;
+        self.frames.clear();
+        self.position = self.position.fract();
+
+        let mut rewound = false;
+        while remaining > 0 && !self.ended {
+            let skipped = self.stream.skip(remaining * channels) / channels;
+
+            if skipped == 0 {
+                if self.params.looping && !rewound && self.stream.rewind() {
+                    rewound = true;
+                } else {
+                    self.ended = true;
+                }
+
+                continue;
+            }
+
+            rewound = false;
+            remaining -= skipped;
+        }
+
+        !self.ended
+    }
+
+    /// Produces the next stereo frame at the given source to output rate ratio.
+    ///
+    /// *Returns `None` once the voice has finished.*
+    fn next_frame(&mut self, rate_ratio: f64) -> Option<[f32; 2]> {
+        let index = self.position as usize;
+        self.fill(index + 2);
+
+        let current = *self.frames.get(index)?;
+        let next = self.frames.get(index + 1).copied().unwrap_or(current);
+        let t = self.position.fract() as f32;
+
+        self.position += self.params.pitch.max(0.0) as f64 * rate_ratio;
+
+        // Drop consumed frames, keeping the one before the current position
+        let consumed = (self.position as usize).saturating_sub(1);
+        if consumed >= CHUNK_FRAMES {
+            self.frames.drain(..consumed);
+            self.position -= consumed as f64;
+        }
+
+        let PlaybackParams { volume, pan, .. } = self.params;
+        let gain = [volume * (1.0 - pan).min(1.0), volume * (1.0 + pan).min(1.0)];
+
+        Some([
+            (current[0] + (next[0] - current[0]) * t) * gain[0],
+            (current[1] + (next[1] - current[1]) * t) * gain[1],
+        ])
+    }
+}
+
+/// A software mixer combining playing sounds into a single output.
+///
+/// Sounds of any sample rate and channel count are resampled to the output,
+/// audio devices pull mixed samples using [`Mixer::mix`].
+pub struct Mixer {
+    sample_rate: u32,
+    channels: u16,
+    master_volume: f32,
+    max_voices: usize,
+    audibility_threshold: f32,
+    voices: HashMap<VoiceId, Voice>,
+    next_id: u64,
+    stats: MixerStats,
+}
+
+impl Mixer {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            master_volume: 1.0,
+            max_voices: 32,
+            audibility_threshold: 0.001,
+            voices: HashMap::new(),
+            next_id: 0,
+            stats: MixerStats::default(),
+        }
+    }
+
+    pub fn shared(self) -> SharedMixer {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn get_channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Changes the output format, for example when the audio device changes.
+    pub fn set_output_format(&mut self, sample_rate: u32, channels: u16) {
+        self.sample_rate = sample_rate;
+        self.channels = channels;
+    }
+
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume;
+    }
+
+    /// Sets the maximum number of voices mixed at once, 32 by default.
+    ///
+    /// Voices over the limit are virtualized: they keep their playback position
+    /// without being decoded or mixed, and become audible again once there is room.
+    pub fn set_max_voices(&mut self, max_voices: usize) {
+        self.max_voices = max_voices;
+    }
+
+    /// Sets the volume under which voices are virtualized, `0.001` by default.
+    pub fn set_audibility_threshold(&mut self, threshold: f32) {
+        self.audibility_threshold = threshold;
+    }
+
+    pub fn stats(&self) -> MixerStats {
+        self.stats
+    }
+
+    /// Starts playing a stream.
+    pub fn play(&mut self, stream: Box<dyn SoundStream>, params: PlaybackParams) -> VoiceId {
+        let id = VoiceId(self.next_id);
+        self.next_id += 1;
+        self.voices.insert(id, Voice::new(stream, params));
+        id
+    }
+
+    /// Starts playing a decoded sound.
+    pub fn play_sound(&mut self, sound: &Sound, params: PlaybackParams) -> VoiceId {
+        self.play(Box::new(SoundCursor::new(sound.clone())), params)
+    }
+
+    /// *Returns `false` if the voice has already finished.*
+    pub fn set_params(&mut self, id: VoiceId, params: PlaybackParams) -> bool {
+        self.voices
+            .get_mut(&id)
+            .map(|voice| voice.params = params)
+            .is_some()
+    }
+
+    /// *Returns `false` if the voice has already finished.*
+    pub fn stop(&mut self, id: VoiceId) -> bool {
+        self.voices.remove(&id).is_some()
+    }
+
+    pub fn is_playing(&self, id: VoiceId) -> bool {
+        self.voices.contains_key(&id)
+    }
+
+    pub fn voice_count(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Fills the buffer with interleaved samples of all playing voices,
+    /// removing voices which have finished.
+    pub fn mix(&mut self, output: &mut [f32]) {
+        output.fill(0.0);
+
+        let channels = self.channels.max(1) as usize;
+        let output_rate = self.sample_rate.max(1) as f64;
+        let output_frames = output.len() / channels;
+
+        // The loudest voices of the highest priority are mixed, the rest is virtualized
+        let mut ranked = self
+            .voices
+            .iter()
+            .filter(|(_, voice)| voice.params.volume > self.audibility_threshold)
+            .map(|(id, voice)| (*id, voice.params.priority, voice.params.volume))
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.total_cmp(&a.2)).then(a.0.cmp(&b.0)));
+        let audible = ranked
+            .into_iter()
+            .take(self.max_voices)
+            .map(|(id, ..)| id)
+            .collect::<HashSet<_>>();
+
+        self.stats = MixerStats {
+            playing: self.voices.len(),
+            audible: audible.len(),
+            virtualized: self.voices.len() - audible.len(),
+        };
+
+        self.voices.retain(|id, voice| {
+            let rate_ratio = voice.stream.sample_rate() as f64 / output_rate;
+
+            if !audible.contains(id) {
+                return voice.skip(output_frames, rate_ratio);
+            }
+
+            for frame in output.chunks_exact_mut(channels) {
+                let Some([left, right]) = voice.next_frame(rate_ratio) else {
+                    return false;
+                };
+
+                match frame {
+                    [mono] => *mono += (left + right) * 0.5,
+                    [out_left, out_right, ..] => {
+                        *out_left += left;
+                        *out_right += right;
+                    }
+                    [] => unreachable!(),
+                }
+            }
+
+            true
+        });
+
+        output
+            .iter_mut()
+            .for_each(|sample| *sample *= self.master_volume);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mixer::{Mixer, MixerStats, PlaybackParams};
+    use crate::sound::Sound;
+
+    /// A mono sound is played panned to the left at half volume.
+    /// Only the left channel should be audible, and the voice should end with the sound.
+    #[test]
+    fn test_mix_pan() {
+        let sound = Sound::from_samples(44100, 1, vec![1.0; 4]);
+        let mut mixer = Mixer::new(44100, 2);
+
+        let params = PlaybackParams {
+            volume: 0.5,
+            pan: -1.0,
+            ..Default::default()
+        };
+        let voice = mixer.play_sound(&sound, params);
+
+        let mut output = [0.0; 12];
+        mixer.mix(&mut output);
+
+        assert_eq!(output[..8], [0.5, 0.0, 0.5, 0.0, 0.5, 0.0, 0.5, 0.0]);
+        assert_eq!(output[8..], [0.0; 4]);
+        assert!(!mixer.is_playing(voice));
+    }
+
+    /// A ramp at half the output sample rate is played at double pitch.
+    /// It should be played at the output rate, in the original duration.
+    #[test]
+    fn test_mix_resample() {
+        let sound = Sound::from_samples(22050, 1, vec![0.0, 0.25, 0.5, 0.75]);
+        let mut mixer = Mixer::new(44100, 1);
+
+        let params = PlaybackParams {
+            pitch: 2.0,
+            ..Default::default()
+        };
+        let voice = mixer.play_sound(&sound, params);
+
+        let mut output = [0.0; 4];
+        mixer.mix(&mut output);
+        assert_eq!(output, [0.0, 0.25, 0.5, 0.75]);
+
+        mixer.mix(&mut output);
+        assert!(!mixer.is_playing(voice));
+    }
+
+    /// Two voices are played with a limit of one voice, then the important one is stopped.
+    /// Only the important voice should be mixed at first, the other one should keep
+    /// its position and continue where it would have been once it becomes audible.
+    #[test]
+    fn test_voice_virtualization() {
+        let ramp = Sound::from_samples(44100, 1, (0..8).map(|i| i as f32).collect::<Vec<_>>());
+        let important = Sound::from_samples(44100, 1, vec![0.5; 8]);
+        let mut mixer = Mixer::new(44100, 1);
+        mixer.set_max_voices(1);
+
+        let background = mixer.play_sound(&ramp, PlaybackParams::default());
+        let params = PlaybackParams {
+            priority: 1,
+            ..Default::default()
+        };
+        let foreground = mixer.play_sound(&important, params);
+
+        let mut output = [0.0; 4];
+        mixer.mix(&mut output);
+        assert_eq!(output, [0.5; 4]);
+        assert_eq!(
+            mixer.stats(),
+            MixerStats {
+                playing: 2,
+                audible: 1,
+                virtualized: 1
+            }
+        );
+
+        mixer.stop(foreground);
+        mixer.mix(&mut output);
+        assert_eq!(output, [4.0, 5.0, 6.0, 7.0]);
+        assert!(mixer.is_playing(background));
+    }
+
+    /// A sound is played beyond its maximum distance.
+    /// It should be virtualized and end in its original duration.
+    #[test]
+    fn test_distance_virtualization() {
+        let sound = Sound::from_samples(44100, 1, vec![1.0; 4]);
+        let mut mixer = Mixer::new(44100, 1);
+
+        let params = PlaybackParams::default().with_distance(50.0, 1.0, 40.0);
+        let voice = mixer.play_sound(&sound, params);
+
+        let mut output = [0.0; 4];
+        mixer.mix(&mut output);
+        assert_eq!(output, [0.0; 4]);
+        assert_eq!(mixer.stats().virtualized, 1);
+        assert!(mixer.is_playing(voice));
+
+        mixer.mix(&mut output);
+        assert!(!mixer.is_playing(voice));
+    }
+}
@@ -0,0 +1,199 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::stream::{OggStream, SoundStream, WavStream};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum SoundError {
+    Io(std::io::Error),
+    /// The data is not in a supported format.
+    UnsupportedFormat,
+    Malformed(&'static str),
+}
+
+impl Display for SoundError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SoundError::Io(err) => write!(f, "{}", err),
+            SoundError::UnsupportedFormat => write!(f, "unsupported sound format"),
+            SoundError::Malformed(reason) => write!(f, "malformed sound: {}", reason),
+        }
+    }
+}
+
+impl Error for SoundError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SoundError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SoundError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SoundFormat {
+    Wav,
+    /// Ogg Vorbis.
+    Ogg,
+}
+
+impl SoundFormat {
+    /// *Returns the format of encoded sound data by its magic bytes.*
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => Some(Self::Wav),
+            [b'O', b'g', b'g', b'S', ..] => Some(Self::Ogg),
+            _ => None,
+        }
+    }
+}
+
+/// A fully decoded sound, stored as interleaved samples.
+///
+/// Cheap to clone, the samples are shared.
+#[derive(Clone, Debug)]
+pub struct Sound {
+    sample_rate: u32,
+    channels: u16,
+    samples: Arc<[f32]>,
+}
+
+impl Sound {
+    pub fn from_samples(sample_rate: u32, channels: u16, samples: impl Into<Arc<[f32]>>) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            samples: samples.into(),
+        }
+    }
+
+    /// Decodes a sound in any supported format.
+    pub fn decode(bytes: &[u8]) -> Result<Self, SoundError> {
+        match SoundFormat::detect(bytes) {
+            Some(SoundFormat::Wav) => Self::from_wav(bytes),
+            Some(SoundFormat::Ogg) => Self::from_ogg(bytes),
+            None => Err(SoundError::UnsupportedFormat),
+        }
+    }
+
+    pub fn from_wav(bytes: &[u8]) -> Result<Self, SoundError> {
+        Ok(Self::from_stream(WavStream::new(Cursor::new(bytes))?))
+    }
+
+    pub fn from_ogg(bytes: &[u8]) -> Result<Self, SoundError> {
+        Ok(Self::from_stream(OggStream::new(Cursor::new(bytes))?))
+    }
+
+    /// Decodes a stream to its end.
+    pub fn from_stream(mut stream: impl SoundStream) -> Self {
+        let mut samples = Vec::new();
+        let mut buffer = [0.0; 1024];
+
+        loop {
+            match stream.read(&mut buffer) {
+                0 => break,
+                read => samples.extend_from_slice(&buffer[..read]),
+            }
+        }
+
+        Self::from_samples(stream.sample_rate(), stream.channels(), samples)
+    }
+
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn get_channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn get_samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// *Returns the number of samples per channel.*
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames() as f64 / self.sample_rate.max(1) as f64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sound::{Sound, SoundError};
+
+    /// Encodes 16-bit PCM samples as a WAV file.
+    fn encode_wav(sample_rate: u32, channels: u16, samples: &[i16]) -> Vec<u8> {
+        let data_len = samples.len() as u32 * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&channels.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(&(channels * 2).to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        samples
+            .iter()
+            .for_each(|sample| wav.extend_from_slice(&sample.to_le_bytes()));
+        wav
+    }
+
+    /// A stereo 16-bit WAV file is decoded.
+    /// The samples should be normalized, truncated Ogg data should be rejected as malformed.
+    #[test]
+    fn test_decode_wav() {
+        let wav = encode_wav(22050, 2, &[0, i16::MIN, 16384, -16384]);
+        let sound = Sound::decode(&wav).unwrap();
+
+        assert_eq!(sound.get_sample_rate(), 22050);
+        assert_eq!(sound.get_channels(), 2);
+        assert_eq!(sound.frames(), 2);
+        assert_eq!(sound.get_samples(), &[0.0, -1.0, 0.5, -0.5]);
+
+        assert!(matches!(
+            Sound::decode(b"OggS\0\x02"),
+            Err(SoundError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,379 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::sound::{Sound, SoundError};
+use lewton::inside_ogg::OggStreamReader;
+use lewton::samples::InterleavedSamples;
+use std::io::{Read, Seek, SeekFrom};
+
+/// A source of interleaved samples, decoded on demand.
+///
+/// Long sounds such as music should be streamed instead of decoded into a [`Sound`] at once.
+pub trait SoundStream: Send {
+    fn sample_rate(&self) -> u32;
+
+    fn channels(&self) -> u16;
+
+    /// Reads interleaved samples into the buffer.
+    ///
+    /// *Returns the number of samples read, zero once the stream has ended.*
+    fn read(&mut self, buffer: &mut [f32]) -> usize;
+
+    /// Restarts the stream from the beginning.
+    ///
+    /// *Returns `false` if the stream cannot be restarted.*
+    fn rewind(&mut self) -> bool;
+
+    /// Skips samples without producing them, used to keep the position of inaudible voices.
+    ///
+    /// The default implementation decodes and discards the samples,
+    /// streams which can seek should override it.
+    ///
+    /// *Returns the number of samples skipped, zero once the stream has ended.*
+    fn skip(&mut self, samples: usize) -> usize {
+        let mut buffer = [0.0; 1024];
+        let mut skipped = 0;
+
+        while skipped < samples {
+            let len = (samples - skipped).min(buffer.len());
+            let read = self.read(&mut buffer[..len]);
+            if read == 0 {
+                break;
+            }
+
+            skipped += read;
+        }
+
+        skipped
+    }
+}
+
+/// A stream over a decoded [`Sound`].
+pub struct SoundCursor {
+    sound: Sound,
+    position: usize,
+}
+
+impl SoundCursor {
+    pub fn new(sound: Sound) -> Self {
+        Self { sound, position: 0 }
+    }
+}
+
+impl SoundStream for SoundCursor {
+    fn sample_rate(&self) -> u32 {
+        self.sound.get_sample_rate()
+    }
+
+    fn channels(&self) -> u16 {
+        self.sound.get_channels()
+    }
+
+    fn read(&mut self, buffer: &mut [f32]) -> usize {
+        let remaining = &self.sound.get_samples()[self.position..];
+        let read = remaining.len().min(buffer.len());
+        buffer[..read].copy_from_slice(&remaining[..read]);
+        self.position += read;
+        read
+    }
+
+    fn rewind(&mut self) -> bool {
+        self.position = 0;
+        true
+    }
+
+    fn skip(&mut self, samples: usize) -> usize {
+        let skipped = samples.min(self.sound.get_samples().len() - self.position);
+        self.position += skipped;
+        skipped
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum SampleFormat {
+    Unsigned8,
+    Signed16,
+    Signed24,
+    Signed32,
+    Float32,
+}
+
+impl SampleFormat {
+    fn from_wav(format_tag: u16, bits: u16) -> Option<Self> {
+        match (format_tag, bits) {
+            (1, 8) => Some(Self::Unsigned8),
+            (1, 16) => Some(Self::Signed16),
+            (1, 24) => Some(Self::Signed24),
+            (1, 32) => Some(Self::Signed32),
+            (3, 32) => Some(Self::Float32),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::Unsigned8 => 1,
+            Self::Signed16 => 2,
+            Self::Signed24 => 3,
+            Self::Signed32 | Self::Float32 => 4,
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> f32 {
+        match self {
+            Self::Unsigned8 => (bytes[0] as f32 - 128.0) / 128.0,
+            Self::Signed16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+            Self::Signed24 => {
+                i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) as f32 / 2147483648.0
+            }
+            Self::Signed32 => {
+                i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 / 2147483648.0
+            }
+            Self::Float32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+}
+
+/// The format tag of WAVE_FORMAT_EXTENSIBLE, whose actual format is stored in its subformat.
+const WAV_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// A stream decoding PCM or floating point WAV data from a reader.
+pub struct WavStream<R: Read + Seek + Send> {
+    reader: R,
+    sample_rate: u32,
+    channels: u16,
+    format: SampleFormat,
+    data_start: u64,
+    data_len: u64,
+    position: u64,
+    bytes: Vec<u8>,
+}
+
+impl<R: Read + Seek + Send> WavStream<R> {
+    /// Reads the header of a WAV file, leaving the samples to be streamed.
+    pub fn new(mut reader: R) -> Result<Self, SoundError> {
+        let mut header = [0; 12];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+            return Err(SoundError::UnsupportedFormat);
+        }
+
+        let mut format = None;
+
+        loop {
+            let mut chunk = [0; 8];
+            reader
+                .read_exact(&mut chunk)
+                .map_err(|_| SoundError::Malformed("missing data chunk"))?;
+
+            let id = &chunk[0..4];
+            let len = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+
+            match id {
+                b"fmt " => {
+                    let mut fmt = vec![0; len as usize];
+                    reader.read_exact(&mut fmt)?;
+                    if fmt.len() < 16 {
+                        return Err(SoundError::Malformed("format chunk too short"));
+                    }
+
+                    let read_u16 = |at: usize| u16::from_le_bytes([fmt[at], fmt[at + 1]]);
+                    let mut format_tag = read_u16(0);
+                    if format_tag == WAV_FORMAT_EXTENSIBLE && fmt.len() >= 26 {
+                        format_tag = read_u16(24);
+                    }
+
+                    let channels = read_u16(2);
+                    let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+                    let sample_format = SampleFormat::from_wav(format_tag, read_u16(14))
+                        .ok_or(SoundError::UnsupportedFormat)?;
+
+                    if channels == 0 || sample_rate == 0 {
+                        return Err(SoundError::Malformed("no channels or zero sample rate"));
+                    }
+
+                    format = Some((sample_rate, channels, sample_format));
+                    reader.seek(SeekFrom::Current((len % 2) as i64))?;
+                }
+                b"data" => {
+                    let (sample_rate, channels, format) =
+                        format.ok_or(SoundError::Malformed("data before format chunk"))?;
+                    let data_start = reader.stream_position()?;
+
+                    return Ok(Self {
+                        reader,
+                        sample_rate,
+                        channels,
+                        format,
+                        data_start,
+                        data_len: len,
+                        position: 0,
+                        bytes: Vec::new(),
+                    });
+                }
+                _ => {
+                    reader.seek(SeekFrom::Current((len + len % 2) as i64))?;
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek + Send> SoundStream for WavStream<R> {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn read(&mut self, buffer: &mut [f32]) -> usize {
+        let size = self.format.size();
+        let remaining = ((self.data_len - self.position) / size as u64) as usize;
+        let samples = remaining.min(buffer.len());
+
+        self.bytes.resize(samples * size, 0);
+        if self.reader.read_exact(&mut self.bytes).is_err() {
+            self.position = self.data_len;
+            return 0;
+        }
+
+        self.position += self.bytes.len() as u64;
+        for (sample, bytes) in buffer.iter_mut().zip(self.bytes.chunks_exact(size)) {
+            *sample = self.format.decode(bytes);
+        }
+
+        samples
+    }
+
+    fn rewind(&mut self) -> bool {
+        self.position = 0;
+        self.reader.seek(SeekFrom::Start(self.data_start)).is_ok()
+    }
+
+    fn skip(&mut self, samples: usize) -> usize {
+        let size = self.format.size() as u64;
+        let remaining = (self.data_len - self.position) / size;
+        let skipped = remaining.min(samples as u64);
+
+        if self
+            .reader
+            .seek(SeekFrom::Current((skipped * size) as i64))
+            .is_err()
+        {
+            self.position = self.data_len;
+            return 0;
+        }
+
+        self.position += skipped * size;
+        skipped as usize
+    }
+}
+
+/// A stream decoding Ogg Vorbis data from a reader.
+pub struct OggStream<R: Read + Seek + Send> {
+    // Only taken while rewinding, which reopens the stream
+    reader: Option<OggStreamReader<R>>,
+    sample_rate: u32,
+    channels: u16,
+    packet: Vec<f32>,
+    position: usize,
+}
+
+impl<R: Read + Seek + Send> OggStream<R> {
+    /// Reads the Vorbis headers, leaving the samples to be streamed.
+    pub fn new(reader: R) -> Result<Self, SoundError> {
+        let reader = OggStreamReader::new(reader)
+            .map_err(|_| SoundError::Malformed("invalid Vorbis headers"))?;
+        let sample_rate = reader.ident_hdr.audio_sample_rate;
+        let channels = reader.ident_hdr.audio_channels as u16;
+
+        Ok(Self {
+            reader: Some(reader),
+            sample_rate,
+            channels,
+            packet: Vec::new(),
+            position: 0,
+        })
+    }
+}
+
+impl<R: Read + Seek + Send> SoundStream for OggStream<R> {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn read(&mut self, buffer: &mut [f32]) -> usize {
+        let mut read = 0;
+
+        while read < buffer.len() {
+            if self.position == self.packet.len() {
+                let Some(reader) = self.reader.as_mut() else {
+                    break;
+                };
+
+                // Decoding errors end the stream like the end of the data does
+                match reader.read_dec_packet_generic::<InterleavedSamples<f32>>() {
+                    Ok(Some(packet)) => {
+                        self.packet = packet.samples;
+                        self.position = 0;
+                        continue;
+                    }
+                    Ok(None) | Err(_) => break,
+                }
+            }
+
+            let remaining = &self.packet[self.position..];
+            let len = remaining.len().min(buffer.len() - read);
+            buffer[read..read + len].copy_from_slice(&remaining[..len]);
+            self.position += len;
+            read += len;
+        }
+
+        read
+    }
+
+    fn rewind(&mut self) -> bool {
+        self.packet.clear();
+        self.position = 0;
+
+        // Seeking to the first granule would land on the header pages, reopen the stream instead
+        let Some(reader) = self.reader.take() else {
+            return false;
+        };
+
+        let mut inner = reader.into_inner().into_inner();
+        if inner.seek(SeekFrom::Start(0)).is_err() {
+            return false;
+        }
+
+        self.reader = OggStreamReader::new(inner).ok();
+        self.reader.is_some()
+    }
+}
@@ -0,0 +1,70 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use pluto_engine_render::device::DeviceError;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// An error preventing the engine or an application from initializing.
+#[derive(Debug)]
+pub enum EngineError {
+    Device(DeviceError),
+    /// A file required by the application could not be read.
+    Io(io::Error),
+    /// An error specific to the application.
+    Application(Box<dyn Error>),
+}
+
+impl Display for EngineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::Device(cause) => write!(f, "{}", cause),
+            EngineError::Io(cause) => write!(f, "failed to read a file: {}", cause),
+            EngineError::Application(cause) => write!(f, "{}", cause),
+        }
+    }
+}
+
+impl Error for EngineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EngineError::Device(cause) => Some(cause),
+            EngineError::Io(cause) => Some(cause),
+            EngineError::Application(cause) => Some(cause.as_ref()),
+        }
+    }
+}
+
+impl From<DeviceError> for EngineError {
+    fn from(err: DeviceError) -> Self {
+        Self::Device(err)
+    }
+}
+
+impl From<io::Error> for EngineError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
@@ -22,16 +22,22 @@
  * SOFTWARE.
  */
 
+use crate::error::EngineError;
 use pluto_engine_render::device::{Device, PhysicalDevice};
 use pluto_engine_render::instance::ContextInstance;
 use pluto_engine_render::surface::{Surface, SurfaceError};
-use pluto_engine_window::event_loop::DisplayEvent;
+use pluto_engine_window::event_loop::{DisplayEvent, UserEvent};
 use pluto_engine_window::window;
-use pluto_engine_window::window::{PhysicalSize, WindowEvent};
+use pluto_engine_window::window::{LogicalSize, PhysicalSize, WindowEvent};
+
+use std::sync::Arc;
+use std::time::Duration;
 
 pub use pluto_engine_render;
 pub use pluto_engine_window;
 
+pub mod error;
+
 pub type PlutoInstance<'a, AD> = <AD as ApplicationDisplay<'a>>::ContextType;
 
 pub type PlutoPhysicalDevice<'a, AD> =
@@ -57,6 +63,8 @@ pub type PlutoPipelineLayout<'a, AD> = <PlutoDevice<'a, AD> as Device<'a>>::Pipe
 
 pub type PlutoPipeline<'a, AD> = <PlutoDevice<'a, AD> as Device<'a>>::PipelineType;
 
+pub type PlutoComputePipeline<'a, AD> = <PlutoDevice<'a, AD> as Device<'a>>::ComputePipelineType;
+
 pub trait WindowDisplay {
     type WindowType: window::Window;
 
@@ -65,15 +73,31 @@ pub trait WindowDisplay {
     fn on_event(&mut self, window_event: &WindowEvent);
 
     fn get_window(&self) -> &Self::WindowType;
+
+    /// Returns the ratio between physical pixels of the surface and logical units.
+    ///
+    /// UI layers should multiply logical sizes by this factor to render at the correct DPI.
+    fn scale_factor(&self) -> f64;
+
+    /// Returns the size of the surface in logical units.
+    fn logical_size(&self) -> LogicalSize<f64>;
+
+    /// *Returns `true` between [`DisplayEvent::Suspended`] and [`DisplayEvent::Resumed`],
+    /// while the display does not render.*
+    fn is_suspended(&self) -> bool;
 }
 
 pub trait ApplicationDisplay<'a>: WindowDisplay {
     type ContextType: ContextInstance<'a, WindowType = Self::WindowType>;
 
+    /// Creates the display, taking ownership of the window and the surface created for it.
+    ///
+    /// *The device and queue are shared with the state of the application.*
     fn new(
-        surface: &'a mut PlutoSurface<'a, Self>,
-        window: &'a Self::WindowType,
-        device: &'a PlutoDevice<'a, Self>,
+        surface: PlutoSurface<'a, Self>,
+        window: Self::WindowType,
+        device: Arc<PlutoDevice<'a, Self>>,
+        queue: Arc<PlutoQueue<'a, Self>>,
     ) -> Self;
 
     fn on_event<AS: ApplicationState<'a, Self>>(
@@ -83,16 +107,61 @@ pub trait ApplicationDisplay<'a>: WindowDisplay {
     where
         Self: Sized + ApplicationDisplay<'a>;
 
+    /// Waits for the device to complete the submitted work before the display is dropped.
+    fn shutdown(&mut self);
+
     fn refresh_surface(&mut self);
     fn resize_surface(&mut self, size: PlutoSurfaceSize<'a, Self>);
 
     fn get_surface(&self) -> &PlutoSurface<'a, Self>;
+
+    fn get_device(&self) -> &Arc<PlutoDevice<'a, Self>>;
+
+    fn get_queue(&self) -> &Arc<PlutoQueue<'a, Self>>;
 }
 
 pub trait ApplicationState<'a, AD: ApplicationDisplay<'a>> {
-    fn new(display: AD, device: &'a PlutoDevice<'a, AD>, queue: &'a PlutoQueue<'a, AD>) -> Self;
+    /// Creates the state of the application, loading the resources it needs to render.
+    ///
+    /// *Errors are reported by the runtime instead of running the application.*
+    fn new(
+        display: AD,
+        device: Arc<PlutoDevice<'a, AD>>,
+        queue: Arc<PlutoQueue<'a, AD>>,
+    ) -> Result<Self, EngineError>
+    where
+        Self: Sized;
+
+    /// Called once per frame before rendering, with the time passed since the previous update.
+    ///
+    /// Application logic, such as running a layer manager, belongs here rather than in
+    /// [`ApplicationState::render`].
+    fn update(&mut self, _delta: Duration) {}
 
     fn render(&mut self, surface_texture: &PlutoSurfaceTexture<'a, AD>);
 
+    /// Called with every window event, after the display has handled it.
+    fn on_event(&mut self, _event: &WindowEvent) {}
+
+    /// Called after the surface of the display was resized,
+    /// resources depending on its size should be recreated here.
+    fn on_surface_resized(&mut self, _size: PlutoSurfaceSize<'a, AD>) {}
+
+    /// Called once after the display requested to close, before the display is shut down.
+    ///
+    /// Layer managers owned by the state should be shut down here,
+    /// see `LayerManager::shutdown`.
+    fn on_exit(&mut self) {}
+
+    /// Called when the display is suspended, see [`DisplayEvent::Suspended`].
+    fn on_suspend(&mut self) {}
+
+    /// Called when the display is resumed, after its surface was reconfigured.
+    fn on_resume(&mut self) {}
+
+    /// Called with the events sent to the window through a
+    /// [`pluto_engine_window::event_loop::DisplayEventProxy`].
+    fn on_user_event(&mut self, _event: &UserEvent) {}
+
     fn display(&mut self) -> &mut AD;
 }
@@ -0,0 +1,472 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Packing of many small images into a single texture atlas.
+//!
+//! The packer does not depend on a graphics device, so atlases can be built at runtime,
+//! e.g. for glyphs as they are rasterized, or ahead of time from a build script.
+
+use crate::texture::TexturePixels;
+use pluto_engine_window::window::PhysicalSize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+
+/// A rectangle in pixels, with the origin in the top left corner.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PackedRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Packs rectangles into rows ("shelves") of a fixed size area.
+///
+/// Rectangles can be inserted one at a time, which makes the packer suitable for atlases
+/// which grow while the application runs. Inserting them sorted by decreasing height
+/// wastes the least space.
+#[derive(Clone, Debug)]
+pub struct ShelfPacker {
+    size: PhysicalSize<u32>,
+    padding: u32,
+    /// The top edge and the height of the current shelf.
+    shelf_y: u32,
+    shelf_height: u32,
+    cursor_x: u32,
+}
+
+impl ShelfPacker {
+    /// Creates a packer for an area of the given size.
+    ///
+    /// Each rectangle will be surrounded by `padding` pixels which no other rectangle overlaps.
+    pub fn new(size: PhysicalSize<u32>, padding: u32) -> Self {
+        Self {
+            size,
+            padding,
+            shelf_y: 0,
+            shelf_height: 0,
+            cursor_x: 0,
+        }
+    }
+
+    pub fn get_size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+
+    /// *Returns the position of a new rectangle of the given size,
+    /// excluding the padding, or `None` if it does not fit anymore.*
+    pub fn insert(&mut self, width: u32, height: u32) -> Option<PackedRect> {
+        let padded_width = width + self.padding * 2;
+        let padded_height = height + self.padding * 2;
+
+        if padded_width > self.size.width {
+            return None;
+        }
+
+        if self.cursor_x + padded_width > self.size.width {
+            if self.shelf_y + self.shelf_height + padded_height > self.size.height {
+                return None;
+            }
+
+            self.shelf_y += self.shelf_height;
+            self.shelf_height = 0;
+            self.cursor_x = 0;
+        } else if self.shelf_y + padded_height > self.size.height {
+            return None;
+        }
+
+        let rect = PackedRect {
+            x: self.cursor_x + self.padding,
+            y: self.shelf_y + self.padding,
+            width,
+            height,
+        };
+
+        self.cursor_x += padded_width;
+        self.shelf_height = self.shelf_height.max(padded_height);
+
+        Some(rect)
+    }
+}
+
+/// The location of one image in an atlas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AtlasRegion {
+    pub rect: PackedRect,
+    /// The texture coordinates of the top left corner of the image.
+    pub uv_min: [f32; 2],
+    /// The texture coordinates of the bottom right corner of the image.
+    pub uv_max: [f32; 2],
+}
+
+impl AtlasRegion {
+    fn new(rect: PackedRect, size: PhysicalSize<u32>) -> Self {
+        let width = size.width as f32;
+        let height = size.height as f32;
+
+        Self {
+            rect,
+            uv_min: [rect.x as f32 / width, rect.y as f32 / height],
+            uv_max: [
+                (rect.x + rect.width) as f32 / width,
+                (rect.y + rect.height) as f32 / height,
+            ],
+        }
+    }
+
+    /// *Returns the texture coordinates of a point given relative to the image,
+    /// where `[0, 0]` is its top left and `[1, 1]` its bottom right corner.*
+    pub fn remap_uv(&self, uv: [f32; 2]) -> [f32; 2] {
+        [
+            self.uv_min[0] + (self.uv_max[0] - self.uv_min[0]) * uv[0],
+            self.uv_min[1] + (self.uv_max[1] - self.uv_min[1]) * uv[1],
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AtlasError {
+    /// The images do not fit into an atlas of the maximum size.
+    DoesNotFit { max_size: PhysicalSize<u32> },
+    /// The pixel data of an image does not match its size.
+    InvalidImage,
+}
+
+impl Display for AtlasError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AtlasError::DoesNotFit { max_size } => write!(
+                f,
+                "the images do not fit into a {}x{} atlas",
+                max_size.width, max_size.height
+            ),
+            AtlasError::InvalidImage => write!(f, "the image data does not match its size"),
+        }
+    }
+}
+
+impl Error for AtlasError {}
+
+/// Collects images to be packed into a [`TextureAtlas`].
+#[derive(Clone, Debug)]
+pub struct AtlasBuilder<K> {
+    max_size: PhysicalSize<u32>,
+    padding: u32,
+    images: Vec<(K, TexturePixels)>,
+}
+
+impl<K: Clone + Eq + Hash> AtlasBuilder<K> {
+    pub fn new(max_size: PhysicalSize<u32>) -> Self {
+        Self {
+            max_size,
+            padding: 1,
+            images: Vec::new(),
+        }
+    }
+
+    /// Sets the number of pixels around each image, which are filled by repeating its edges.
+    ///
+    /// This keeps neighbouring images from bleeding into each other when the atlas is
+    /// sampled with linear filtering. Mipmapped atlases need more padding for each level.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Adds an image to the atlas, replacing any previous image with the same key.
+    pub fn add(&mut self, key: K, pixels: TexturePixels) -> &mut Self {
+        self.images.retain(|(existing, _)| existing != &key);
+        self.images.push((key, pixels));
+        self
+    }
+
+    /// Packs all images into the smallest power of two sized atlas they fit into.
+    pub fn build(mut self) -> Result<TextureAtlas<K>, AtlasError> {
+        let image_size =
+            |pixels: &TexturePixels| pixels.size.width as usize * pixels.size.height as usize * 4;
+
+        if self
+            .images
+            .iter()
+            .any(|(_, pixels)| pixels.data.len() != image_size(pixels))
+        {
+            return Err(AtlasError::InvalidImage);
+        }
+
+        self.images.sort_by(|(_, a), (_, b)| {
+            (b.size.height, b.size.width).cmp(&(a.size.height, a.size.width))
+        });
+
+        let widest = self
+            .images
+            .iter()
+            .map(|(_, pixels)| pixels.size.width + self.padding * 2)
+            .max()
+            .unwrap_or(1);
+        let mut size = PhysicalSize {
+            width: widest.next_power_of_two(),
+            height: 1,
+        };
+
+        loop {
+            if size.width > self.max_size.width || size.height > self.max_size.height {
+                return Err(AtlasError::DoesNotFit {
+                    max_size: self.max_size,
+                });
+            }
+
+            if let Some(rects) = self.pack(size) {
+                return Ok(self.blit(size, rects));
+            }
+
+            // Grow the shorter side, preferring wide atlases as they fill shelves better.
+            if (size.height < size.width && size.height * 2 <= self.max_size.height)
+                || size.width * 2 > self.max_size.width
+            {
+                size.height *= 2;
+            } else {
+                size.width *= 2;
+            }
+        }
+    }
+
+    fn pack(&self, size: PhysicalSize<u32>) -> Option<Vec<PackedRect>> {
+        let mut packer = ShelfPacker::new(size, self.padding);
+
+        self.images
+            .iter()
+            .map(|(_, pixels)| packer.insert(pixels.size.width, pixels.size.height))
+            .collect()
+    }
+
+    fn blit(self, size: PhysicalSize<u32>, rects: Vec<PackedRect>) -> TextureAtlas<K> {
+        let mut atlas = TextureAtlas {
+            pixels: TexturePixels {
+                size,
+                data: vec![0; size.width as usize * size.height as usize * 4],
+            },
+            regions: HashMap::with_capacity(self.images.len()),
+        };
+
+        for ((key, pixels), rect) in self.images.into_iter().zip(rects) {
+            atlas.insert(key, rect, &pixels, self.padding);
+        }
+
+        atlas
+    }
+}
+
+/// A texture containing many images, together with the lookup table of their locations.
+///
+/// The lookup table is used by renderers to remap texture coordinates of sprites or glyphs.
+#[derive(Clone, Debug)]
+pub struct TextureAtlas<K> {
+    pub pixels: TexturePixels,
+    regions: HashMap<K, AtlasRegion>,
+}
+
+impl<K: Eq + Hash> TextureAtlas<K> {
+    /// Creates an empty atlas to which images are added with [`TextureAtlas::insert`],
+    /// after being placed by a [`ShelfPacker`] of the same size.
+    pub fn empty(size: PhysicalSize<u32>) -> Self {
+        Self {
+            pixels: TexturePixels {
+                size,
+                data: vec![0; size.width as usize * size.height as usize * 4],
+            },
+            regions: HashMap::new(),
+        }
+    }
+
+    /// Copies an image into the atlas at a rectangle and fills its padding with the edges
+    /// of the image.
+    ///
+    /// Empty images only get a region, they have no edges to fill the padding with.
+    ///
+    /// ***Panics*** if the rectangle and its padding do not lie within the atlas,
+    /// or the size of the image differs from the size of the rectangle.
+    pub fn insert(&mut self, key: K, rect: PackedRect, pixels: &TexturePixels, padding: u32) {
+        assert_eq!(
+            (rect.width, rect.height),
+            (pixels.size.width, pixels.size.height)
+        );
+        assert!(rect.x >= padding && rect.y >= padding);
+        assert!(
+            rect.x + rect.width + padding <= self.pixels.size.width
+                && rect.y + rect.height + padding <= self.pixels.size.height
+        );
+
+        if rect.width > 0 && rect.height > 0 {
+            let atlas_width = self.pixels.size.width as usize;
+
+            for y in 0..rect.height + padding * 2 {
+                let source_y = y.saturating_sub(padding).min(rect.height - 1);
+                let target_y = (rect.y - padding + y) as usize;
+
+                for x in 0..rect.width + padding * 2 {
+                    let source_x = x.saturating_sub(padding).min(rect.width - 1);
+                    let target_x = (rect.x - padding + x) as usize;
+
+                    let offset = (target_y * atlas_width + target_x) * 4;
+                    self.pixels.data[offset..offset + 4]
+                        .copy_from_slice(&pixels.get_pixel(source_x, source_y));
+                }
+            }
+        }
+
+        self.regions
+            .insert(key, AtlasRegion::new(rect, self.pixels.size));
+    }
+
+    pub fn get_region(&self, key: &K) -> Option<&AtlasRegion> {
+        self.regions.get(key)
+    }
+
+    pub fn regions(&self) -> impl Iterator<Item = (&K, &AtlasRegion)> {
+        self.regions.iter()
+    }
+
+    pub fn get_size(&self) -> PhysicalSize<u32> {
+        self.pixels.size
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::atlas::{AtlasBuilder, AtlasError, PackedRect, ShelfPacker};
+    use crate::texture::TexturePixels;
+    use pluto_engine_window::window::PhysicalSize;
+
+    fn solid(width: u32, height: u32, color: [u8; 4]) -> TexturePixels {
+        TexturePixels {
+            size: PhysicalSize { width, height },
+            data: color.repeat((width * height) as usize),
+        }
+    }
+
+    /// Rectangles are inserted into a small packer with padding.
+    /// They should be placed in rows and rejected once the area is full.
+    #[test]
+    fn test_shelf_packer() {
+        let mut packer = ShelfPacker::new(
+            PhysicalSize {
+                width: 10,
+                height: 8,
+            },
+            1,
+        );
+
+        let rect = |x, y, width, height| PackedRect {
+            x,
+            y,
+            width,
+            height,
+        };
+
+        assert_eq!(packer.insert(3, 3), Some(rect(1, 1, 3, 3)));
+        assert_eq!(packer.insert(2, 2), Some(rect(6, 1, 2, 2)));
+        assert_eq!(packer.insert(3, 1), Some(rect(1, 6, 3, 1)));
+        assert_eq!(packer.insert(2, 2), None);
+        assert_eq!(packer.insert(1, 1), Some(rect(6, 6, 1, 1)));
+        assert_eq!(packer.insert(9, 1), None);
+    }
+
+    /// Three images are packed with one pixel of padding.
+    /// The atlas should be a power of two, the images and their padding copied,
+    /// and the UV rectangles should match the pixel rectangles.
+    #[test]
+    fn test_build_atlas() {
+        let mut builder = AtlasBuilder::new(PhysicalSize {
+            width: 64,
+            height: 64,
+        });
+        builder
+            .add("red", solid(4, 4, [255, 0, 0, 255]))
+            .add("green", solid(2, 6, [0, 255, 0, 255]))
+            .add("blue", solid(3, 1, [0, 0, 255, 255]));
+        let atlas = builder.build().unwrap();
+
+        let size = atlas.get_size();
+        assert!(size.width.is_power_of_two() && size.height.is_power_of_two());
+
+        let green = atlas.get_region(&"green").unwrap();
+        assert_eq!((green.rect.x, green.rect.y), (1, 1));
+        assert_eq!(atlas.pixels.get_pixel(0, 0), [0, 255, 0, 255]);
+
+        for (_, region) in atlas.regions() {
+            let rect = region.rect;
+            assert_eq!(
+                region.uv_max[0] * size.width as f32,
+                (rect.x + rect.width) as f32
+            );
+            assert_eq!(region.remap_uv([0.0, 0.0]), region.uv_min);
+        }
+
+        let red = atlas.get_region(&"red").unwrap().rect;
+        assert_eq!(
+            atlas.pixels.get_pixel(red.x + 3, red.y + 4),
+            [255, 0, 0, 255]
+        );
+        assert!(atlas.get_region(&"missing").is_none());
+    }
+
+    /// An empty image is packed with padding next to a regular one.
+    /// Building should not fail, and the empty image should get an empty region.
+    #[test]
+    fn test_build_atlas_empty_image() {
+        let mut builder = AtlasBuilder::new(PhysicalSize {
+            width: 64,
+            height: 64,
+        });
+        builder
+            .add("empty", solid(0, 0, [0; 4]))
+            .add("red", solid(2, 2, [255, 0, 0, 255]));
+        let atlas = builder.build().unwrap();
+
+        let empty = atlas.get_region(&"empty").unwrap();
+        assert_eq!((empty.rect.width, empty.rect.height), (0, 0));
+        assert_eq!(empty.uv_min, empty.uv_max);
+
+        let red = atlas.get_region(&"red").unwrap().rect;
+        assert_eq!(atlas.pixels.get_pixel(red.x, red.y), [255, 0, 0, 255]);
+    }
+
+    /// An image larger than the maximum atlas size is packed.
+    /// Building should fail instead of growing the atlas.
+    #[test]
+    fn test_atlas_does_not_fit() {
+        let max_size = PhysicalSize {
+            width: 8,
+            height: 8,
+        };
+        let mut builder = AtlasBuilder::new(max_size);
+        builder.add(0, solid(8, 8, [0; 4]));
+
+        assert_eq!(
+            builder.build().unwrap_err(),
+            AtlasError::DoesNotFit { max_size }
+        );
+    }
+}
@@ -0,0 +1,278 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Caching of compiled shaders and pipelines.
+//!
+//! Shaders are keyed by a hash of their source and entry points, pipelines by the key
+//! of their shader and the hash of their state. Labels are not part of the keys,
+//! so a cached pipeline keeps the label it was first created with.
+
+use crate::device::Device;
+use crate::pipeline::{PipelineCreateInfo, PipelineLayout};
+use crate::shader::{Shader, ShaderCode};
+use crate::shadow::ShadowPipelineCreateInfo;
+use crate::texture::TextureFormat;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// *Returns the cache key of a shader, ignoring its label.*
+pub fn shader_key(code: &ShaderCode<'_>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    match *code {
+        ShaderCode::Wgsl {
+            code,
+            vertex_entry,
+            fragment_entry,
+            label: _,
+        } => {
+            "wgsl".hash(&mut hasher);
+            code.hash(&mut hasher);
+            vertex_entry.hash(&mut hasher);
+            fragment_entry.hash(&mut hasher);
+        }
+    }
+
+    hasher.finish()
+}
+
+/// *Returns the cache key of a pipeline created from the shader with the given key,
+/// ignoring its label.*
+pub fn pipeline_key<'a, L: PipelineLayout<'a>, S: Shader<'a>, T: TextureFormat>(
+    shader_key: u64,
+    info: &PipelineCreateInfo<'a, L, S, T>,
+) -> u64
+where
+    T::BackingType: Hash,
+{
+    let mut hasher = DefaultHasher::new();
+
+    shader_key.hash(&mut hasher);
+    info.buffer_layout.hash(&mut hasher);
+    info.texture_format.get_backing_format().hash(&mut hasher);
+    info.blend.hash(&mut hasher);
+    for target in info.additional_color_targets {
+        target.format.get_backing_format().hash(&mut hasher);
+        target.blend.hash(&mut hasher);
+    }
+    info.uniforms.hash(&mut hasher);
+    info.primitive.hash(&mut hasher);
+    info.push_constants.hash(&mut hasher);
+    info.depth_format.hash(&mut hasher);
+    info.material.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/// *Returns the cache key of a shadow pipeline created from the shader with the given key,
+/// ignoring its label.*
+pub fn shadow_pipeline_key<'a, L: PipelineLayout<'a>, S: Shader<'a>, T: TextureFormat>(
+    shader_key: u64,
+    info: &ShadowPipelineCreateInfo<'a, L, S, T>,
+) -> u64
+where
+    T::BackingType: Hash,
+{
+    let mut hasher = DefaultHasher::new();
+
+    pipeline_key(shader_key, &info.pipeline).hash(&mut hasher);
+    info.pass.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/// The number of lookups served by a [`ResourceCache`] since it was created.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// The number of objects currently cached.
+    pub entries: usize,
+}
+
+struct CacheEntry<V> {
+    /// The key of the shader the object was created from.
+    shader_key: u64,
+    value: V,
+}
+
+struct CacheState<V> {
+    entries: HashMap<u64, CacheEntry<V>>,
+    hits: u64,
+    misses: u64,
+}
+
+/// Cached objects of one kind, such as shaders or pipelines, shared by cloning.
+pub struct ResourceCache<V: Clone> {
+    state: Mutex<CacheState<V>>,
+}
+
+impl<V: Clone> Default for ResourceCache<V> {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+}
+
+impl<V: Clone> ResourceCache<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// *Returns the object cached under the key, creating and caching it first if missing.*
+    ///
+    /// The cache is not locked while the object is created, so objects can create
+    /// other cached objects, such as a pipeline its shader.
+    pub fn get_or_create(&self, key: u64, shader_key: u64, create: impl FnOnce() -> V) -> V {
+        {
+            let mut state = self.state.lock().unwrap();
+
+            if let Some(value) = state.entries.get(&key).map(|entry| entry.value.clone()) {
+                state.hits += 1;
+                return value;
+            }
+
+            state.misses += 1;
+        }
+
+        let value = create();
+
+        self.state.lock().unwrap().entries.insert(
+            key,
+            CacheEntry {
+                shader_key,
+                value: value.clone(),
+            },
+        );
+
+        value
+    }
+
+    /// Removes all objects created from the shader with the given key.
+    ///
+    /// *Returns the number of removed objects.*
+    pub fn invalidate_shader(&self, shader_key: u64) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let len = state.entries.len();
+        state
+            .entries
+            .retain(|_, entry| entry.shader_key != shader_key);
+
+        len - state.entries.len()
+    }
+
+    pub fn clear(&self) {
+        self.state.lock().unwrap().entries.clear();
+    }
+
+    pub fn get_stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap();
+
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            entries: state.entries.len(),
+        }
+    }
+}
+
+/// A device reusing shaders and pipelines created with the same code and state,
+/// instead of compiling them again.
+///
+/// Objects stay cached as long as the device is alive, unless invalidated.
+pub trait DevicePipelineCache<'a>: Device<'a> {
+    /// Removes a shader and all pipelines created from it from the cache,
+    /// so they are compiled again the next time they are created.
+    ///
+    /// *Should be called when reloading a shader, objects already created remain usable.*
+    fn invalidate_shader(&self, code: &ShaderCode<'_>);
+
+    /// Removes all shaders and pipelines from the cache.
+    fn clear_pipeline_cache(&self);
+
+    /// *Returns the statistics of the shader cache and the pipeline cache.*
+    fn get_pipeline_cache_stats(&self) -> (CacheStats, CacheStats);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cache::{shader_key, ResourceCache};
+    use crate::shader::ShaderCode;
+
+    fn wgsl<'a>(code: &'a str, label: Option<&'a str>) -> ShaderCode<'a> {
+        ShaderCode::Wgsl {
+            code,
+            vertex_entry: "vs_main",
+            fragment_entry: "fs_main",
+            label,
+        }
+    }
+
+    /// The same shader is keyed with different labels and then with changed code.
+    /// Only the code should change the key.
+    #[test]
+    fn test_shader_key() {
+        let key = shader_key(&wgsl("code", Some("A")));
+
+        assert_eq!(key, shader_key(&wgsl("code", Some("B"))));
+        assert_ne!(key, shader_key(&wgsl("changed code", Some("A"))));
+    }
+
+    /// Objects of two shaders are cached, then the objects of one of the shaders are invalidated.
+    /// Repeated lookups should not create objects again until they are invalidated.
+    #[test]
+    fn test_resource_cache() {
+        let cache = ResourceCache::new();
+        let mut created = 0;
+
+        for _ in 0..2 {
+            cache.get_or_create(1, 10, || {
+                created += 1;
+                "a"
+            });
+            cache.get_or_create(2, 10, || {
+                created += 1;
+                "b"
+            });
+            cache.get_or_create(3, 20, || {
+                created += 1;
+                "c"
+            });
+        }
+
+        assert_eq!(created, 3);
+        assert_eq!(cache.invalidate_shader(10), 2);
+        assert_eq!(cache.get_or_create(1, 10, || "a2"), "a2");
+        assert_eq!(cache.get_or_create(3, 20, || "c2"), "c");
+
+        let stats = cache.get_stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (4, 4, 2));
+    }
+}
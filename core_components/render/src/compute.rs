@@ -0,0 +1,76 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::shader::Shader;
+use crate::uniform::UniformLayout;
+
+/// A storage buffer used by a compute pipeline, bound at group 0 and the given binding.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StorageBufferLayout {
+    pub binding: u32,
+    /// Whether the shader only reads the buffer, declared as `var<storage, read>`.
+    pub read_only: bool,
+}
+
+pub struct ComputePipelineCreateInfo<'a, S: Shader<'a>> {
+    pub shader: &'a S,
+    pub entry_point: &'a str,
+    pub storage_buffers: &'a [StorageBufferLayout],
+    /// Uniform buffers used by the shader, bound at group 0 next to the storage buffers.
+    pub uniforms: &'a [UniformLayout],
+    /// Names the pipeline in validation errors and frame debuggers,
+    /// defaults to the label of the shader.
+    pub label: Option<&'a str>,
+}
+
+pub trait ComputePipeline<'a> {
+    type BackingType;
+
+    fn get_backing_compute_pipeline(&self) -> &Self::BackingType;
+}
+
+/// A buffer compute shaders can read and write.
+///
+/// *Storage buffers can also be bound as vertex buffers, for example to draw simulated particles.*
+pub trait StorageBuffer<'a> {
+    type BackingType;
+
+    fn get_backing_buffer(&self) -> &Self::BackingType;
+
+    fn get_size(&self) -> u64;
+}
+
+/// A set of buffers bound to the bindings of a compute pipeline.
+pub trait ComputeBindGroup<'a> {
+    type BackingType;
+
+    fn get_backing_bind_group(&self) -> &Self::BackingType;
+}
+
+/// A single dispatch of a compute pass.
+pub struct ComputeDispatch<'b, B> {
+    pub bind_group: &'b B,
+    /// The number of workgroups in each dimension.
+    pub workgroups: [u32; 3],
+}
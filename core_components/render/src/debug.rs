@@ -0,0 +1,117 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::device::Device;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+/// How pipelines draw, used to visualize the geometry of a scene.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum RenderDebugMode {
+    #[default]
+    Normal,
+    /// Triangles are drawn as lines.
+    ///
+    /// *Requires the non-web polygon mode feature of the device.*
+    Wireframe,
+    /// Every fragment adds a constant color without depth testing, brighter areas are drawn
+    /// more times per frame.
+    Overdraw,
+}
+
+impl RenderDebugMode {
+    pub const ALL: [RenderDebugMode; 3] = [
+        RenderDebugMode::Normal,
+        RenderDebugMode::Wireframe,
+        RenderDebugMode::Overdraw,
+    ];
+
+    /// *Returns the mode following this one, wrapping around to [`RenderDebugMode::Normal`].*
+    pub fn next(self) -> Self {
+        match self {
+            RenderDebugMode::Normal => RenderDebugMode::Wireframe,
+            RenderDebugMode::Wireframe => RenderDebugMode::Overdraw,
+            RenderDebugMode::Overdraw => RenderDebugMode::Normal,
+        }
+    }
+}
+
+/// The debug mode of a device, shared with the pipelines it creates so it can be
+/// switched at runtime without recreating them.
+#[derive(Clone, Debug, Default)]
+pub struct RenderDebugSwitch(Arc<AtomicU8>);
+
+impl RenderDebugSwitch {
+    pub fn get(&self) -> RenderDebugMode {
+        RenderDebugMode::ALL[self.0.load(Ordering::Relaxed) as usize]
+    }
+
+    pub fn set(&self, mode: RenderDebugMode) {
+        let index = RenderDebugMode::ALL
+            .iter()
+            .position(|m| *m == mode)
+            .unwrap();
+        self.0.store(index as u8, Ordering::Relaxed);
+    }
+}
+
+/// A device whose pipelines can be drawn in debug modes.
+pub trait DeviceRenderDebug<'a>: Device<'a> {
+    fn get_render_debug_switch(&self) -> RenderDebugSwitch;
+
+    /// *Returns `true` if pipelines created by this device have a variant for the mode,
+    /// pipelines without one are drawn normally.*
+    fn supports_render_debug_mode(&self, mode: RenderDebugMode) -> bool;
+}
+
+/// A device able to trigger captures in an attached frame debugger such as RenderDoc.
+pub trait DeviceFrameCapture<'a>: Device<'a> {
+    /// Starts capturing all GPU work submitted until [`DeviceFrameCapture::stop_frame_capture`].
+    ///
+    /// *Has no effect unless the application was launched by or injected into a frame debugger,
+    /// or on the web.*
+    fn start_frame_capture(&self);
+
+    fn stop_frame_capture(&self);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::debug::{RenderDebugMode, RenderDebugSwitch};
+
+    /// The mode of a cloned switch is cycled through all modes.
+    /// Both switches should report the same mode, returning to the normal mode.
+    #[test]
+    fn test_render_debug_switch() {
+        let switch = RenderDebugSwitch::default();
+        let shared = switch.clone();
+
+        for mode in [RenderDebugMode::Wireframe, RenderDebugMode::Overdraw] {
+            switch.set(switch.get().next());
+            assert_eq!(shared.get(), mode);
+        }
+
+        switch.set(switch.get().next());
+        assert_eq!(shared.get(), RenderDebugMode::Normal);
+    }
+}
@@ -0,0 +1,114 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::device::{Device, Queue};
+use std::collections::VecDeque;
+
+/// Holds resources dropped during a frame until the GPU has finished that frame,
+/// so the engine does not have to keep them alive manually.
+pub struct DeletionQueue<T> {
+    pending: VecDeque<(u64, T)>,
+}
+
+impl<T> Default for DeletionQueue<T> {
+    fn default() -> Self {
+        Self {
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<T> DeletionQueue<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a resource dropped during `frame`, frames are expected to be increasing.
+    pub fn push(&mut self, frame: u64, resource: T) {
+        self.pending.push_back((frame, resource));
+    }
+
+    /// Removes the resources dropped during frames up to and including `completed_frame`.
+    ///
+    /// *Returns the resources in the order they were queued, to be destroyed by the caller.*
+    pub fn collect(&mut self, completed_frame: u64) -> Vec<T> {
+        let count = self
+            .pending
+            .iter()
+            .take_while(|(frame, _)| *frame <= completed_frame)
+            .count();
+
+        self.pending
+            .drain(..count)
+            .map(|(_, resource)| resource)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Defers the destruction of engine objects until the GPU has finished every frame
+/// which may still be using them.
+pub trait DeviceDeferredDeletion<'a, Q: Queue<'a>>: Device<'a> {
+    /// Any engine object which can be destroyed later, such as a texture or a buffer.
+    type ResourceType;
+
+    /// Destroys the resource once all work submitted up to the end of the current frame
+    /// is complete.
+    fn destroy_deferred(&self, resource: impl Into<Self::ResourceType>);
+
+    /// Ends the current frame and destroys the resources of all completed frames,
+    /// should be called once per frame after the last submission.
+    fn end_frame(&self, queue: &Q);
+
+    /// *Returns the number of resources waiting for their frame to complete.*
+    fn get_pending_deletions(&self) -> usize;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::deletion::DeletionQueue;
+
+    /// Resources are dropped during frames 0, 0, 1 and 3, then frames 0 and 2 complete.
+    /// Only resources of completed frames should be collected, in order.
+    #[test]
+    fn test_deletion_queue() {
+        let mut queue = DeletionQueue::new();
+        for (frame, resource) in [(0, 'a'), (0, 'b'), (1, 'c'), (3, 'd')] {
+            queue.push(frame, resource);
+        }
+
+        assert_eq!(queue.collect(0), ['a', 'b']);
+        assert_eq!(queue.collect(2), ['c']);
+        assert_eq!(queue.len(), 1);
+        assert!(queue.collect(2).is_empty());
+        assert_eq!(queue.collect(3), ['d']);
+        assert!(queue.is_empty());
+    }
+}
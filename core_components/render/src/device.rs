@@ -22,10 +22,39 @@
  * SOFTWARE.
  */
 
+use crate::compute::{
+    ComputeBindGroup, ComputeDispatch, ComputePipeline, ComputePipelineCreateInfo, StorageBuffer,
+};
+use crate::image::{ImageError, ImageOptions, TextureImage};
 use crate::mesh::Mesh;
 use crate::pipeline::{Pipeline, PipelineCreateInfo, PipelineLayout};
+use crate::push_constant::PushConstants;
 use crate::shader::{Shader, ShaderCode};
-use crate::texture::{Texture, TextureFormat};
+use crate::texture::{ReadbackError, Texture, TextureFormat, TexturePixels};
+use crate::uniform::{UniformBindGroup, UniformBuffer};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DeviceError {
+    /// No adapter compatible with the surface is available.
+    NoAdapter,
+    /// The adapter failed to create a device, e.g. because of unsupported limits.
+    RequestDevice(String),
+}
+
+impl Display for DeviceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeviceError::NoAdapter => write!(f, "no compatible graphics adapter found"),
+            DeviceError::RequestDevice(message) => {
+                write!(f, "failed to create the graphics device: {}", message)
+            }
+        }
+    }
+}
+
+impl Error for DeviceError {}
 
 pub trait Queue<'a> {
     type BackingType;
@@ -43,7 +72,43 @@ pub trait PhysicalDevice<'a> {
 
     fn get_backing_physical_device(&self) -> &Self::BackingType;
 
-    fn create_device_and_queue(&self) -> (Self::DeviceType, Self::QueueType);
+    fn create_device_and_queue(&self) -> Result<(Self::DeviceType, Self::QueueType), DeviceError>;
+
+    /// Creates the device with every queue the backend exposes separately, see [`DeviceQueues`].
+    fn create_device_and_queues(
+        &self,
+    ) -> Result<(Self::DeviceType, DeviceQueues<Self::QueueType>), DeviceError> {
+        let (device, queue) = self.create_device_and_queue()?;
+
+        Ok((
+            device,
+            DeviceQueues {
+                graphics: queue,
+                transfer: None,
+                compute: None,
+            },
+        ))
+    }
+}
+
+/// The queues of a device, dedicated transfer and compute queues are only present
+/// if the backend exposes them.
+pub struct DeviceQueues<Q> {
+    pub graphics: Q,
+    pub transfer: Option<Q>,
+    pub compute: Option<Q>,
+}
+
+impl<Q> DeviceQueues<Q> {
+    /// *Returns the queue uploads are submitted to, falling back to the graphics queue.*
+    pub fn transfer(&self) -> &Q {
+        self.transfer.as_ref().unwrap_or(&self.graphics)
+    }
+
+    /// *Returns the queue compute passes are submitted to, falling back to the graphics queue.*
+    pub fn compute(&self) -> &Q {
+        self.compute.as_ref().unwrap_or(&self.graphics)
+    }
 }
 
 pub trait Device<'a> {
@@ -56,6 +121,7 @@ pub trait Device<'a> {
     type CommandBufferType: CommandBuffer<'a>;
     type ImageFormatType: TextureFormat;
     type TextureType: Texture<'a>;
+    type ComputePipelineType: ComputePipeline<'a>;
 
     fn get_backing_device(&self) -> &Self::BackingType;
 
@@ -74,6 +140,16 @@ pub trait Device<'a> {
     ) -> Self::PipelineType;
 
     fn create_shader(&self, code: &ShaderCode<'_>) -> Self::ShaderType;
+
+    fn create_compute_pipeline(
+        &self,
+        info: &ComputePipelineCreateInfo<'a, Self::ShaderType>,
+    ) -> Self::ComputePipelineType;
+
+    /// Blocks until all submitted work is complete.
+    ///
+    /// *Returns immediately on platforms which cannot block on the device, such as the web.*
+    fn wait_idle(&self);
 }
 
 pub trait CommandBufferBuilder<'a, C: CommandBuffer<'a>> {
@@ -93,3 +169,108 @@ pub trait CommandBuffer<'a> {
 pub trait DeviceMeshFactory<'a, M: Mesh>: Device<'a> {
     fn create_mesh(&self) -> M;
 }
+
+/// Creates uniform buffers and binds them to pipelines.
+pub trait DeviceUniforms<'a, Q: Queue<'a>>: Device<'a> {
+    type UniformBufferType: UniformBuffer<'a>;
+    type UniformBindGroupType: UniformBindGroup<'a>;
+
+    fn create_uniform_buffer(&self, size: u64) -> Self::UniformBufferType;
+
+    /// Schedules a write of the data to the start of the buffer, performed before the next submission.
+    fn write_uniform_buffer(&self, queue: &Q, buffer: &Self::UniformBufferType, data: &[u8]);
+
+    /// Binds buffers to the uniforms of a pipeline, in the order of its
+    /// [`PipelineCreateInfo::uniforms`].
+    fn create_uniform_bind_group(
+        &self,
+        pipeline: &Self::PipelineType,
+        buffers: &[&Self::UniformBufferType],
+    ) -> Self::UniformBindGroupType;
+}
+
+/// Creates the resources of compute pipelines and records compute passes.
+pub trait DeviceCompute<'a, Q: Queue<'a>>: DeviceUniforms<'a, Q> {
+    type StorageBufferType: StorageBuffer<'a>;
+    type ComputeBindGroupType: ComputeBindGroup<'a>;
+
+    fn create_storage_buffer(&self, size: u64) -> Self::StorageBufferType;
+
+    /// Schedules a write of the data to the start of the buffer, performed before the next submission.
+    fn write_storage_buffer(&self, queue: &Q, buffer: &Self::StorageBufferType, data: &[u8]);
+
+    /// Binds buffers to a compute pipeline, in the order of its
+    /// [`ComputePipelineCreateInfo::storage_buffers`] and [`ComputePipelineCreateInfo::uniforms`].
+    fn create_compute_bind_group(
+        &self,
+        pipeline: &Self::ComputePipelineType,
+        storage_buffers: &[&Self::StorageBufferType],
+        uniforms: &[&Self::UniformBufferType],
+    ) -> Self::ComputeBindGroupType;
+
+    /// Records a compute pass running the dispatches in order.
+    fn record_compute_pass(
+        &self,
+        command_buffer: &mut Self::CommandBufferBuilderType,
+        pipeline: &Self::ComputePipelineType,
+        dispatches: &[ComputeDispatch<'_, Self::ComputeBindGroupType>],
+    );
+}
+
+/// Creates the storage of per-draw data for pipelines created with push constants.
+pub trait DevicePushConstants<'a, Q: Queue<'a>>: Device<'a> {
+    type PushConstantsType: PushConstants<'a>;
+
+    /// Creates the push constants of a pipeline, allowing at most `max_draws`
+    /// draws between flushes when emulated.
+    fn create_push_constants(
+        &self,
+        pipeline: &Self::PipelineType,
+        max_draws: u32,
+    ) -> Self::PushConstantsType;
+
+    /// Uploads the data of emulated push constants set since the last flush.
+    ///
+    /// *Must be called after recording the draws and before submitting them.*
+    fn flush_push_constants(&self, queue: &Q, push_constants: &Self::PushConstantsType);
+}
+
+/// Creates sampled textures from images.
+pub trait DeviceTextureFactory<'a, Q: Queue<'a>>: Device<'a> {
+    /// Creates a texture with all mip levels of the image.
+    fn create_texture_from_pixels(&self, queue: &Q, image: &TextureImage) -> Self::TextureType;
+
+    /// Decodes an image and creates a texture from it, see [`TextureImage::decode`].
+    fn create_texture_from_image(
+        &self,
+        queue: &Q,
+        bytes: &[u8],
+        options: ImageOptions,
+    ) -> Result<Self::TextureType, ImageError> {
+        let image = TextureImage::decode(bytes, options)?;
+        Ok(self.create_texture_from_pixels(queue, &image))
+    }
+}
+
+/// Copies textures of type `T` into CPU memory.
+pub trait DeviceTextureReader<'a, Q: Queue<'a>, T>: Device<'a> {
+    type ReadbackType;
+
+    /// Submits a copy of the texture into a staging buffer.
+    ///
+    /// *The copy is complete once [`DeviceTextureReader::poll_readback`] returns `Some`.*
+    fn read_pixels(&self, queue: &Q, texture: &T) -> Self::ReadbackType;
+
+    /// Checks whether the readback is complete, without blocking.
+    ///
+    /// *Returns `Some` exactly once, when the pixels are available.*
+    fn poll_readback(
+        &self,
+        readback: &mut Self::ReadbackType,
+    ) -> Option<Result<TexturePixels, ReadbackError>>;
+
+    /// Blocks until the readback is complete.
+    ///
+    /// ***Panics*** on platforms which cannot block on the device, such as the web.
+    fn wait_readback(&self, readback: Self::ReadbackType) -> Result<TexturePixels, ReadbackError>;
+}
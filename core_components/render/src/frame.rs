@@ -0,0 +1,273 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Passes recorded by the display every frame, ordered by their dependencies.
+
+use crate::render_pass::{ScissorRect, Viewport};
+
+/// A linear color a render target is cleared to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClearColor {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+impl ClearColor {
+    pub const BLACK: ClearColor = ClearColor::new(0.0, 0.0, 0.0, 1.0);
+    pub const TRANSPARENT: ClearColor = ClearColor::new(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(r: f64, g: f64, b: f64, a: f64) -> Self {
+        Self { r, g, b, a }
+    }
+}
+
+/// What happens to the contents of the target when a pass begins.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum LoadOp {
+    /// Keeps what previous passes have drawn.
+    #[default]
+    Load,
+    Clear(ClearColor),
+}
+
+/// A named pass of a frame, recorded by its recorder.
+pub struct FramePass<R> {
+    name: &'static str,
+    load: LoadOp,
+    dependencies: Vec<&'static str>,
+    viewport: Option<Viewport>,
+    scissor_rect: Option<ScissorRect>,
+    pub recorder: R,
+}
+
+impl<R> FramePass<R> {
+    pub fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn get_load(&self) -> LoadOp {
+        self.load
+    }
+
+    /// *Returns the names of the passes recorded before this one.*
+    pub fn get_dependencies(&self) -> &[&'static str] {
+        &self.dependencies
+    }
+
+    /// Sets the viewport the render passes of this pass begin with,
+    /// `None` for the whole target.
+    pub fn set_viewport(&mut self, viewport: Option<Viewport>) {
+        self.viewport = viewport;
+    }
+
+    pub fn get_viewport(&self) -> Option<Viewport> {
+        self.viewport
+    }
+
+    /// Sets the scissor rectangle the render passes of this pass begin with,
+    /// `None` for the whole target.
+    pub fn set_scissor_rect(&mut self, scissor_rect: Option<ScissorRect>) {
+        self.scissor_rect = scissor_rect;
+    }
+
+    pub fn get_scissor_rect(&self) -> Option<ScissorRect> {
+        self.scissor_rect
+    }
+}
+
+/// The structure of a frame, a list of passes recorded in order of their dependencies.
+///
+/// The target is first cleared to the clear color, if any, then every pass is recorded.
+/// Passes without dependencies between each other are recorded in the order they were added.
+pub struct FrameComposer<R> {
+    clear_color: Option<ClearColor>,
+    passes: Vec<FramePass<R>>,
+}
+
+impl<R> Default for FrameComposer<R> {
+    fn default() -> Self {
+        Self {
+            clear_color: Some(ClearColor::BLACK),
+            passes: Vec::new(),
+        }
+    }
+}
+
+impl<R> FrameComposer<R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the color the target is cleared to at the beginning of every frame,
+    /// `None` to leave clearing to the passes.
+    pub fn set_clear_color(&mut self, clear_color: Option<ClearColor>) {
+        self.clear_color = clear_color;
+    }
+
+    pub fn get_clear_color(&self) -> Option<ClearColor> {
+        self.clear_color
+    }
+
+    /// Adds a pass recorded after all passes with the given names,
+    /// replacing the pass of the same name if there is one.
+    ///
+    /// *Dependencies on passes which were not added are ignored.*
+    ///
+    /// ***Panics** if the pass would depend on itself, directly or through other passes.*
+    pub fn add_pass(
+        &mut self,
+        name: &'static str,
+        load: LoadOp,
+        dependencies: &[&'static str],
+        recorder: R,
+    ) {
+        self.remove_pass(name);
+
+        if let Some(dependency) = dependencies
+            .iter()
+            .find(|dependency| self.depends_on(dependency, name))
+        {
+            panic!(
+                "The frame pass {} cannot depend on {}, which depends on it",
+                name, dependency
+            );
+        }
+
+        self.passes.push(FramePass {
+            name,
+            load,
+            dependencies: dependencies.to_vec(),
+            viewport: None,
+            scissor_rect: None,
+            recorder,
+        });
+    }
+
+    /// Removes the pass with the given name, returning its recorder.
+    pub fn remove_pass(&mut self, name: &str) -> Option<R> {
+        let index = self.passes.iter().position(|pass| pass.name == name)?;
+        Some(self.passes.remove(index).recorder)
+    }
+
+    /// *Returns the pass with the given name, e.g. to change its viewport.*
+    pub fn get_pass_mut(&mut self, name: &str) -> Option<&mut FramePass<R>> {
+        self.passes.iter_mut().find(|pass| pass.name == name)
+    }
+
+    pub fn has_pass(&self, name: &str) -> bool {
+        self.passes.iter().any(|pass| pass.name == name)
+    }
+
+    /// *Returns `true` if the pass `name` is recorded after `dependency`,
+    /// or is `dependency` itself.*
+    fn depends_on(&self, name: &str, dependency: &str) -> bool {
+        name == dependency
+            || self
+                .passes
+                .iter()
+                .find(|pass| pass.name == name)
+                .is_some_and(|pass| {
+                    pass.dependencies
+                        .iter()
+                        .any(|next| self.depends_on(next, dependency))
+                })
+    }
+
+    /// *Returns the indices of the passes in the order they are recorded.*
+    fn order(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.passes.len());
+        let mut recorded = vec![false; self.passes.len()];
+
+        while order.len() < self.passes.len() {
+            // The graph is acyclic, so there is always a pass whose dependencies were recorded
+            let next = (0..self.passes.len())
+                .find(|&i| {
+                    !recorded[i]
+                        && self.passes[i].dependencies.iter().all(|dependency| {
+                            self.passes
+                                .iter()
+                                .zip(&recorded)
+                                .all(|(pass, recorded)| pass.name != *dependency || *recorded)
+                        })
+                })
+                .unwrap();
+
+            recorded[next] = true;
+            order.push(next);
+        }
+
+        order
+    }
+
+    /// *Returns the names of the passes in the order they are recorded.*
+    pub fn get_pass_order(&self) -> Vec<&'static str> {
+        self.order()
+            .into_iter()
+            .map(|i| self.passes[i].name)
+            .collect()
+    }
+
+    /// Calls the function for every pass, in the order they are recorded.
+    pub fn for_each_pass(&mut self, mut f: impl FnMut(&mut FramePass<R>)) {
+        let order = self.order();
+        let mut passes = self.passes.iter_mut().map(Some).collect::<Vec<_>>();
+
+        for i in order {
+            f(passes[i].take().unwrap());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::frame::{FrameComposer, LoadOp};
+
+    /// Passes are added with dependencies on passes added before and after them.
+    /// Each pass should be recorded after its dependencies, otherwise in the order it was added.
+    #[test]
+    fn test_pass_order() {
+        let mut composer = FrameComposer::new();
+        composer.add_pass("ui", LoadOp::Load, &["world", "post"], ());
+        composer.add_pass("world", LoadOp::Load, &["sky"], ());
+        composer.add_pass("debug", LoadOp::Load, &[], ());
+        composer.add_pass("sky", LoadOp::Load, &[], ());
+
+        assert_eq!(composer.get_pass_order(), ["debug", "sky", "world", "ui"]);
+
+        composer.add_pass("debug", LoadOp::Load, &["ui"], ());
+        assert_eq!(composer.get_pass_order(), ["sky", "world", "ui", "debug"]);
+    }
+
+    /// A pass is added depending on a pass which depends on it.
+    /// The composer should panic instead of creating a cycle.
+    #[test]
+    #[should_panic]
+    fn test_pass_cycle() {
+        let mut composer = FrameComposer::new();
+        composer.add_pass("world", LoadOp::Load, &["ui"], ());
+        composer.add_pass("ui", LoadOp::Load, &["world"], ());
+    }
+}
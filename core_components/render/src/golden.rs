@@ -0,0 +1,204 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::texture::TexturePixels;
+use pluto_engine_window::window::PhysicalSize;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// How much a rendered image may differ from its golden image,
+/// accounting for rounding and rasterization differences between drivers.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GoldenTolerance {
+    /// The largest difference of any channel for pixels considered equal.
+    pub channel: u8,
+    /// The fraction of pixels allowed to differ by more than [`GoldenTolerance::channel`].
+    pub mismatched_fraction: f32,
+}
+
+impl Default for GoldenTolerance {
+    fn default() -> Self {
+        Self {
+            channel: 2,
+            mismatched_fraction: 0.001,
+        }
+    }
+}
+
+/// The difference between a rendered image and its golden image.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ImageDifference {
+    /// The number of pixels differing by more than the channel tolerance.
+    pub mismatched: usize,
+    /// The largest difference of any channel.
+    pub max_difference: u8,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GoldenError {
+    SizeMismatch {
+        actual: PhysicalSize<u32>,
+        expected: PhysicalSize<u32>,
+    },
+    /// More pixels differ than allowed by the tolerance.
+    Mismatch(ImageDifference),
+}
+
+impl Display for GoldenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GoldenError::SizeMismatch { actual, expected } => write!(
+                f,
+                "the image is {}x{}, but the golden image is {}x{}",
+                actual.width, actual.height, expected.width, expected.height
+            ),
+            GoldenError::Mismatch(difference) => write!(
+                f,
+                "{} pixels differ from the golden image, by up to {}",
+                difference.mismatched, difference.max_difference
+            ),
+        }
+    }
+}
+
+impl Error for GoldenError {}
+
+/// Compares the RGBA pixels of a rendered image with its golden image.
+///
+/// *Returns the difference if it is within the tolerance.*
+pub fn compare_pixels(
+    actual: &TexturePixels,
+    expected: &TexturePixels,
+    tolerance: &GoldenTolerance,
+) -> Result<ImageDifference, GoldenError> {
+    if actual.size != expected.size {
+        return Err(GoldenError::SizeMismatch {
+            actual: actual.size,
+            expected: expected.size,
+        });
+    }
+
+    let mut difference = ImageDifference::default();
+    for (actual, expected) in actual.data.chunks(4).zip(expected.data.chunks(4)) {
+        let pixel_difference = actual
+            .iter()
+            .zip(expected)
+            .map(|(actual, expected)| actual.abs_diff(*expected))
+            .max()
+            .unwrap_or(0);
+
+        difference.max_difference = difference.max_difference.max(pixel_difference);
+        if pixel_difference > tolerance.channel {
+            difference.mismatched += 1;
+        }
+    }
+
+    let pixels = actual.size.width as usize * actual.size.height as usize;
+    if difference.mismatched as f32 > pixels as f32 * tolerance.mismatched_fraction {
+        return Err(GoldenError::Mismatch(difference));
+    }
+
+    Ok(difference)
+}
+
+/// Highlights pixels differing by more than the channel tolerance in red
+/// on a dimmed copy of the golden image, for inspecting failed comparisons.
+///
+/// *Both images must have the same size.*
+pub fn diff_pixels(
+    actual: &TexturePixels,
+    expected: &TexturePixels,
+    tolerance: &GoldenTolerance,
+) -> TexturePixels {
+    let data = actual
+        .data
+        .chunks(4)
+        .zip(expected.data.chunks(4))
+        .flat_map(|(actual, expected)| {
+            let mismatched = actual
+                .iter()
+                .zip(expected)
+                .any(|(actual, expected)| actual.abs_diff(*expected) > tolerance.channel);
+
+            if mismatched {
+                [255, 0, 0, 255]
+            } else {
+                [expected[0] / 4, expected[1] / 4, expected[2] / 4, 255]
+            }
+        })
+        .collect();
+
+    TexturePixels {
+        size: expected.size,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::golden::{compare_pixels, GoldenError, GoldenTolerance, ImageDifference};
+    use crate::texture::TexturePixels;
+    use pluto_engine_window::window::PhysicalSize;
+
+    /// A 2x2 image is compared with copies differing slightly in every pixel and strongly in one.
+    /// Slight differences should be tolerated, one strongly differing pixel should only be
+    /// tolerated if a quarter of the pixels may differ.
+    #[test]
+    fn test_compare_pixels() {
+        let image = |data: [u8; 16]| TexturePixels {
+            size: PhysicalSize {
+                width: 2,
+                height: 2,
+            },
+            data: data.to_vec(),
+        };
+
+        let expected = image([10; 16]);
+        let slight = image([11; 16]);
+        let mut strong = [10; 16];
+        strong[5] = 50;
+        let strong = image(strong);
+
+        let tolerance = GoldenTolerance::default();
+        assert_eq!(
+            compare_pixels(&slight, &expected, &tolerance),
+            Ok(ImageDifference {
+                mismatched: 0,
+                max_difference: 1
+            })
+        );
+        assert_eq!(
+            compare_pixels(&strong, &expected, &tolerance),
+            Err(GoldenError::Mismatch(ImageDifference {
+                mismatched: 1,
+                max_difference: 40
+            }))
+        );
+
+        let lenient = GoldenTolerance {
+            mismatched_fraction: 0.25,
+            ..tolerance
+        };
+        assert!(compare_pixels(&strong, &expected, &lenient).is_ok());
+    }
+}
@@ -0,0 +1,367 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Decoding of images into textures.
+
+use crate::texture::TexturePixels;
+use pluto_engine_window::window::PhysicalSize;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Cursor;
+use zune_jpeg::zune_core::colorspace::ColorSpace;
+use zune_jpeg::zune_core::options::DecoderOptions;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    /// KTX2, recognized but not decoded yet.
+    Ktx2,
+}
+
+impl ImageFormat {
+    /// *Returns the format of encoded image data by its magic bytes.*
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0x89, b'P', b'N', b'G', ..] => Some(Self::Png),
+            [0xFF, 0xD8, 0xFF, ..] => Some(Self::Jpeg),
+            [0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, ..] => Some(Self::Ktx2),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ImageError {
+    /// The image is in an unknown format or one which cannot be decoded.
+    UnsupportedFormat(Option<ImageFormat>),
+    Decode(String),
+}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::UnsupportedFormat(Some(format)) => {
+                write!(f, "unsupported image format {:?}", format)
+            }
+            ImageError::UnsupportedFormat(None) => write!(f, "unknown image format"),
+            ImageError::Decode(message) => write!(f, "failed to decode the image: {}", message),
+        }
+    }
+}
+
+impl Error for ImageError {}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ImageOptions {
+    /// Whether the image stores sRGB encoded colors, as is usual for color textures.
+    ///
+    /// Data such as normal maps should not be treated as sRGB.
+    pub srgb: bool,
+    pub generate_mipmaps: bool,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            srgb: true,
+            generate_mipmaps: true,
+        }
+    }
+}
+
+/// An image decoded into 8-bit RGBA pixels, ready to be uploaded to a texture.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TextureImage {
+    /// The mip levels of the image, starting with the full size one.
+    pub levels: Vec<TexturePixels>,
+    pub srgb: bool,
+}
+
+impl TextureImage {
+    /// Decodes an image in any supported format.
+    pub fn decode(bytes: &[u8], options: ImageOptions) -> Result<Self, ImageError> {
+        let pixels = match ImageFormat::detect(bytes) {
+            Some(ImageFormat::Png) => decode_png(bytes)?,
+            Some(ImageFormat::Jpeg) => decode_jpeg(bytes)?,
+            format => return Err(ImageError::UnsupportedFormat(format)),
+        };
+
+        Ok(Self::from_pixels(pixels, options))
+    }
+
+    pub fn from_pixels(pixels: TexturePixels, options: ImageOptions) -> Self {
+        let mut levels = vec![pixels];
+
+        if options.generate_mipmaps {
+            while let Some(level) = next_mip_level(levels.last().unwrap(), options.srgb) {
+                levels.push(level);
+            }
+        }
+
+        Self {
+            levels,
+            srgb: options.srgb,
+        }
+    }
+
+    pub fn get_size(&self) -> PhysicalSize<u32> {
+        self.levels[0].size
+    }
+}
+
+fn decode_png(bytes: &[u8]) -> Result<TexturePixels, ImageError> {
+    let to_error = |err: png::DecodingError| ImageError::Decode(err.to_string());
+
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(to_error)?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(to_error)?;
+    let buffer = &buffer[..info.buffer_size()];
+
+    let data = match info.color_type {
+        png::ColorType::Rgba => buffer.to_vec(),
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|la| [la[0], la[0], la[0], la[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&l| [l, l, l, u8::MAX]).collect(),
+        png::ColorType::Indexed => {
+            return Err(ImageError::Decode("unexpanded indexed colors".to_owned()));
+        }
+    };
+
+    Ok(TexturePixels {
+        size: PhysicalSize {
+            width: info.width,
+            height: info.height,
+        },
+        data,
+    })
+}
+
+fn decode_jpeg(bytes: &[u8]) -> Result<TexturePixels, ImageError> {
+    let to_error = |err: zune_jpeg::errors::DecodeErrors| ImageError::Decode(err.to_string());
+
+    let options = DecoderOptions::default().jpeg_set_out_colorspace(ColorSpace::RGBA);
+    let mut decoder = zune_jpeg::JpegDecoder::new_with_options(bytes, options);
+    let data = decoder.decode().map_err(to_error)?;
+    let info = decoder
+        .info()
+        .ok_or_else(|| ImageError::Decode("missing image info".to_owned()))?;
+
+    Ok(TexturePixels {
+        size: PhysicalSize {
+            width: info.width as u32,
+            height: info.height as u32,
+        },
+        data,
+    })
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let value = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+
+    (value * 255.0).round() as u8
+}
+
+/// Downsamples a level to half its size with a box filter,
+/// averaging sRGB colors in linear space.
+///
+/// *Returns `None` if the level is already 1x1.*
+fn next_mip_level(level: &TexturePixels, srgb: bool) -> Option<TexturePixels> {
+    let PhysicalSize { width, height } = level.size;
+    if width <= 1 && height <= 1 {
+        return None;
+    }
+
+    let size = PhysicalSize {
+        width: (width / 2).max(1),
+        height: (height / 2).max(1),
+    };
+
+    let to_linear: [f32; 256] = std::array::from_fn(|value| srgb_to_linear(value as u8));
+    let mut data = Vec::with_capacity(size.width as usize * size.height as usize * 4);
+
+    for y in 0..size.height {
+        for x in 0..size.width {
+            let mut sum = [0.0; 4];
+
+            for (sx, sy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let source_x = (x * 2 + sx).min(width - 1);
+                let source_y = (y * 2 + sy).min(height - 1);
+                let pixel = level.get_pixel(source_x, source_y);
+
+                for channel in 0..4 {
+                    sum[channel] += if srgb && channel < 3 {
+                        to_linear[pixel[channel] as usize]
+                    } else {
+                        pixel[channel] as f32 / 255.0
+                    };
+                }
+            }
+
+            for (channel, sum) in sum.iter().enumerate() {
+                let average = sum / 4.0;
+                data.push(if srgb && channel < 3 {
+                    linear_to_srgb(average)
+                } else {
+                    (average * 255.0).round() as u8
+                });
+            }
+        }
+    }
+
+    Some(TexturePixels { size, data })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::image::{ImageError, ImageFormat, ImageOptions, TextureImage};
+    use pluto_engine_window::window::PhysicalSize;
+
+    /// A grayscale PNG with an alpha channel is encoded and decoded without sRGB.
+    /// The pixels should be expanded to RGBA and all mip levels down to 1x1 generated.
+    #[test]
+    fn test_decode_png() {
+        let mut png = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png, 4, 2);
+            encoder.set_color(png::ColorType::GrayscaleAlpha);
+            let mut writer = encoder.write_header().unwrap();
+            let pixels = [
+                0, 255, 100, 255, 0, 255, 100, 255, 0, 255, 100, 255, 0, 255, 100, 255,
+            ];
+            writer.write_image_data(&pixels).unwrap();
+        }
+
+        let options = ImageOptions {
+            srgb: false,
+            generate_mipmaps: true,
+        };
+        let image = TextureImage::decode(&png, options).unwrap();
+
+        assert_eq!(
+            image.get_size(),
+            PhysicalSize {
+                width: 4,
+                height: 2
+            }
+        );
+        assert_eq!(image.levels[0].get_pixel(1, 0), [100, 100, 100, 255]);
+        assert_eq!(image.levels.len(), 3);
+        assert_eq!(image.levels[1].get_pixel(0, 0), [50, 50, 50, 255]);
+        assert_eq!(
+            image.levels[2].size,
+            PhysicalSize {
+                width: 1,
+                height: 1
+            }
+        );
+
+        assert_eq!(
+            TextureImage::decode(&[0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB], options),
+            Err(ImageError::UnsupportedFormat(Some(ImageFormat::Ktx2)))
+        );
+    }
+
+    /// A solid color JPEG is encoded and decoded.
+    /// The pixels should be expanded to opaque RGBA, close to the original color despite the lossy encoding.
+    #[test]
+    fn test_decode_jpeg() {
+        let mut jpeg = Vec::new();
+        let pixels = [200, 100, 50].repeat(8 * 8);
+        jpeg_encoder::Encoder::new(&mut jpeg, 100)
+            .encode(&pixels, 8, 8, jpeg_encoder::ColorType::Rgb)
+            .unwrap();
+
+        assert_eq!(ImageFormat::detect(&jpeg), Some(ImageFormat::Jpeg));
+
+        let image = TextureImage::decode(&jpeg, ImageOptions::default()).unwrap();
+
+        assert_eq!(
+            image.get_size(),
+            PhysicalSize {
+                width: 8,
+                height: 8
+            }
+        );
+        assert_eq!(image.levels.len(), 4);
+
+        let pixel = image.levels[0].get_pixel(3, 5);
+        for (channel, expected) in [200, 100, 50, 255].into_iter().enumerate() {
+            assert!(pixel[channel].abs_diff(expected) <= 2, "{:?}", pixel);
+        }
+
+        assert!(matches!(
+            TextureImage::decode(&[0xFF, 0xD8, 0xFF, 0xE0], ImageOptions::default()),
+            Err(ImageError::Decode(_))
+        ));
+    }
+
+    /// Black and white are averaged with and without sRGB.
+    /// The sRGB average should be brighter, as it is computed in linear space.
+    #[test]
+    fn test_mipmap_srgb() {
+        let pixels = crate::texture::TexturePixels {
+            size: PhysicalSize {
+                width: 2,
+                height: 1,
+            },
+            data: vec![0, 0, 0, 255, 255, 255, 255, 255],
+        };
+
+        let linear = TextureImage::from_pixels(
+            pixels.clone(),
+            ImageOptions {
+                srgb: false,
+                generate_mipmaps: true,
+            },
+        );
+        let srgb = TextureImage::from_pixels(pixels, ImageOptions::default());
+
+        assert_eq!(linear.levels[1].get_pixel(0, 0), [128, 128, 128, 255]);
+        assert_eq!(srgb.levels[1].get_pixel(0, 0), [188, 188, 188, 255]);
+    }
+}
@@ -22,10 +22,114 @@
  * SOFTWARE.
  */
 
-use crate::device::PhysicalDevice;
+use crate::device::{DeviceError, PhysicalDevice};
 use crate::surface::Surface;
 use pluto_engine_window::window::Window;
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AdapterType {
+    DiscreteGpu,
+    IntegratedGpu,
+    VirtualGpu,
+    /// A software rasterizer.
+    Cpu,
+    Other,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GraphicsBackend {
+    Vulkan,
+    Metal,
+    Dx12,
+    Dx11,
+    Gl,
+    BrowserWebGpu,
+    Other,
+}
+
+/// The limits of an adapter relevant when choosing between adapters.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct AdapterLimits {
+    pub max_texture_dimension_2d: u32,
+    pub max_bind_groups: u32,
+    pub max_push_constant_size: u32,
+}
+
+/// Describes an adapter (a physical device) available to the instance.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdapterInfo {
+    pub name: String,
+    pub adapter_type: AdapterType,
+    pub backend: GraphicsBackend,
+    pub limits: AdapterLimits,
+}
+
+/// Which adapter to prefer when multiple adapters are available.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum AdapterPreference {
+    /// Lets the backend choose, usually the low power adapter.
+    #[default]
+    Default,
+    /// Prefers discrete over integrated GPUs.
+    HighPerformance,
+    /// Prefers integrated over discrete GPUs.
+    LowPower,
+    /// Prefers the first adapter whose name contains the given text, ignoring case.
+    Name(String),
+}
+
+/// Decides which adapter devices are created from.
+///
+/// *If no adapter matches the preference, the default adapter of the backend is used.*
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AdapterSelection {
+    pub preference: AdapterPreference,
+    /// Whether to fall back to a software adapter if no hardware adapter is available.
+    pub software_fallback: bool,
+}
+
+impl AdapterSelection {
+    pub fn new(preference: AdapterPreference) -> Self {
+        Self {
+            preference,
+            software_fallback: false,
+        }
+    }
+
+    pub fn software_fallback(mut self, software_fallback: bool) -> Self {
+        self.software_fallback = software_fallback;
+        self
+    }
+
+    /// *Returns the index of the preferred adapter,
+    /// `None` if the choice should be left to the backend.*
+    pub fn choose(&self, adapters: &[AdapterInfo]) -> Option<usize> {
+        let rank_by = |order: [AdapterType; 2]| {
+            order.iter().find_map(|adapter_type| {
+                adapters
+                    .iter()
+                    .position(|adapter| adapter.adapter_type == *adapter_type)
+            })
+        };
+
+        match &self.preference {
+            AdapterPreference::Default => None,
+            AdapterPreference::HighPerformance => {
+                rank_by([AdapterType::DiscreteGpu, AdapterType::IntegratedGpu])
+            }
+            AdapterPreference::LowPower => {
+                rank_by([AdapterType::IntegratedGpu, AdapterType::DiscreteGpu])
+            }
+            AdapterPreference::Name(name) => {
+                let name = name.to_lowercase();
+                adapters
+                    .iter()
+                    .position(|adapter| adapter.name.to_lowercase().contains(&name))
+            }
+        }
+    }
+}
+
 pub trait ContextInstance<'a> {
     type BackingType;
 
@@ -38,7 +142,60 @@ pub trait ContextInstance<'a> {
 
     fn new(window: &'a Self::WindowType) -> Self;
 
-    fn create_device_and_surface(&self) -> (Self::PhysicalDeviceType, Self::SurfaceType);
+    /// Picks the default adapter compatible with the window and creates a surface for it.
+    fn create_device_and_surface(
+        &self,
+    ) -> Result<(Self::PhysicalDeviceType, Self::SurfaceType), DeviceError> {
+        self.create_device_and_surface_with(&AdapterSelection::default())
+    }
+
+    /// Picks an adapter compatible with the window and creates a surface for it.
+    fn create_device_and_surface_with(
+        &self,
+        selection: &AdapterSelection,
+    ) -> Result<(Self::PhysicalDeviceType, Self::SurfaceType), DeviceError>;
+
+    /// Returns all adapters the instance can create devices from,
+    /// including ones that cannot present to the window.
+    ///
+    /// *Always empty on the web, where only the preferred adapter can be requested.*
+    fn enumerate_adapters(&self) -> Vec<AdapterInfo>;
 
     fn get_backing_instance(&self) -> &Self::BackingType;
 }
+
+#[cfg(test)]
+mod test {
+    use crate::instance::{
+        AdapterInfo, AdapterLimits, AdapterPreference, AdapterSelection, AdapterType,
+        GraphicsBackend,
+    };
+
+    /// An adapter is chosen from an integrated, a discrete and a software adapter.
+    /// Each preference should pick its adapter, or leave the choice to the backend.
+    #[test]
+    fn test_choose_adapter() {
+        let adapter = |name: &str, adapter_type| AdapterInfo {
+            name: name.to_string(),
+            adapter_type,
+            backend: GraphicsBackend::Vulkan,
+            limits: AdapterLimits::default(),
+        };
+        let adapters = [
+            adapter("Intel(R) UHD Graphics", AdapterType::IntegratedGpu),
+            adapter("NVIDIA GeForce RTX 3060", AdapterType::DiscreteGpu),
+            adapter("llvmpipe", AdapterType::Cpu),
+        ];
+        let choose = |preference| AdapterSelection::new(preference).choose(&adapters);
+
+        assert_eq!(choose(AdapterPreference::Default), None);
+        assert_eq!(choose(AdapterPreference::HighPerformance), Some(1));
+        assert_eq!(choose(AdapterPreference::LowPower), Some(0));
+        assert_eq!(choose(AdapterPreference::Name("LLVM".into())), Some(2));
+        assert_eq!(choose(AdapterPreference::Name("AMD".into())), None);
+        assert_eq!(
+            AdapterSelection::new(AdapterPreference::HighPerformance).choose(&adapters[2..]),
+            None
+        );
+    }
+}
@@ -24,11 +24,29 @@
 
 pub use pluto_engine_window;
 
+pub mod atlas;
+pub mod cache;
+pub mod compute;
+pub mod debug;
+pub mod deletion;
 pub mod device;
+pub mod frame;
+pub mod golden;
+pub mod image;
 pub mod instance;
+pub mod material;
 pub mod mesh;
+pub mod obj;
+pub mod picking;
 pub mod pipeline;
+pub mod post_process;
+pub mod push_constant;
 pub mod render_pass;
 pub mod shader;
+pub mod shadow;
 pub mod surface;
+pub mod target;
 pub mod texture;
+pub mod timer;
+pub mod uniform;
+pub mod upload;
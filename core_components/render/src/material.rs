@@ -0,0 +1,427 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Materials, a pipeline with its parameters and textures.
+//!
+//! The parameters of a material are stored in a uniform buffer laid out like a WGSL struct,
+//! set by name instead of writing bytes to buffers. Materials are bound at
+//! [`MATERIAL_GROUP`]: the parameter struct at binding 0, followed by a texture and a sampler
+//! for each texture of the layout, so the shader of a material with a `tint` parameter
+//! and an `albedo` texture declares:
+//!
+//! ```wgsl
+//! struct Material { tint: vec4<f32>; };
+//! [[group(2), binding(0)]] var<uniform> material: Material;
+//! [[group(2), binding(1)]] var albedo: texture_2d<f32>;
+//! [[group(2), binding(2)]] var albedo_sampler: sampler;
+//! ```
+
+use crate::device::{DeviceTextureFactory, DeviceUniforms, Queue};
+use crate::pipeline::PipelineCreateInfo;
+use crate::texture::Texture;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// The bind group index materials are bound at, after uniforms and emulated push constants.
+pub const MATERIAL_GROUP: u32 = 2;
+
+/// The WGSL type of a material parameter.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MaterialParamType {
+    Float,
+    Vec2,
+    Vec3,
+    Vec4,
+    Mat4,
+    Int,
+    UInt,
+}
+
+impl MaterialParamType {
+    pub const fn size(&self) -> u64 {
+        match self {
+            MaterialParamType::Float | MaterialParamType::Int | MaterialParamType::UInt => 4,
+            MaterialParamType::Vec2 => 8,
+            MaterialParamType::Vec3 => 12,
+            MaterialParamType::Vec4 => 16,
+            MaterialParamType::Mat4 => 64,
+        }
+    }
+
+    /// *Returns the alignment of the type in the uniform address space.*
+    pub const fn alignment(&self) -> u64 {
+        match self {
+            MaterialParamType::Float | MaterialParamType::Int | MaterialParamType::UInt => 4,
+            MaterialParamType::Vec2 => 8,
+            MaterialParamType::Vec3 | MaterialParamType::Vec4 | MaterialParamType::Mat4 => 16,
+        }
+    }
+}
+
+/// The value of a material parameter, matrices are stored column by column.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MaterialParam {
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Mat4([[f32; 4]; 4]),
+    Int(i32),
+    UInt(u32),
+}
+
+impl MaterialParam {
+    pub fn get_type(&self) -> MaterialParamType {
+        match self {
+            MaterialParam::Float(_) => MaterialParamType::Float,
+            MaterialParam::Vec2(_) => MaterialParamType::Vec2,
+            MaterialParam::Vec3(_) => MaterialParamType::Vec3,
+            MaterialParam::Vec4(_) => MaterialParamType::Vec4,
+            MaterialParam::Mat4(_) => MaterialParamType::Mat4,
+            MaterialParam::Int(_) => MaterialParamType::Int,
+            MaterialParam::UInt(_) => MaterialParamType::UInt,
+        }
+    }
+
+    /// Writes the value to the beginning of the slice in native byte order.
+    fn write(&self, bytes: &mut [u8]) {
+        let words: Vec<[u8; 4]> = match self {
+            MaterialParam::Float(value) => vec![value.to_ne_bytes()],
+            MaterialParam::Vec2(value) => value.iter().map(|v| v.to_ne_bytes()).collect(),
+            MaterialParam::Vec3(value) => value.iter().map(|v| v.to_ne_bytes()).collect(),
+            MaterialParam::Vec4(value) => value.iter().map(|v| v.to_ne_bytes()).collect(),
+            MaterialParam::Mat4(value) => value.iter().flatten().map(|v| v.to_ne_bytes()).collect(),
+            MaterialParam::Int(value) => vec![value.to_ne_bytes()],
+            MaterialParam::UInt(value) => vec![value.to_ne_bytes()],
+        };
+
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+            chunk.copy_from_slice(&word);
+        }
+    }
+}
+
+impl From<f32> for MaterialParam {
+    fn from(value: f32) -> Self {
+        MaterialParam::Float(value)
+    }
+}
+
+impl From<[f32; 2]> for MaterialParam {
+    fn from(value: [f32; 2]) -> Self {
+        MaterialParam::Vec2(value)
+    }
+}
+
+impl From<[f32; 3]> for MaterialParam {
+    fn from(value: [f32; 3]) -> Self {
+        MaterialParam::Vec3(value)
+    }
+}
+
+impl From<[f32; 4]> for MaterialParam {
+    fn from(value: [f32; 4]) -> Self {
+        MaterialParam::Vec4(value)
+    }
+}
+
+impl From<[[f32; 4]; 4]> for MaterialParam {
+    fn from(value: [[f32; 4]; 4]) -> Self {
+        MaterialParam::Mat4(value)
+    }
+}
+
+impl From<i32> for MaterialParam {
+    fn from(value: i32) -> Self {
+        MaterialParam::Int(value)
+    }
+}
+
+impl From<u32> for MaterialParam {
+    fn from(value: u32) -> Self {
+        MaterialParam::UInt(value)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MaterialError {
+    UnknownParam(String),
+    UnknownTexture(String),
+    TypeMismatch {
+        name: String,
+        expected: MaterialParamType,
+        found: MaterialParamType,
+    },
+}
+
+impl Display for MaterialError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MaterialError::UnknownParam(name) => {
+                write!(f, "the material has no parameter named \"{}\"", name)
+            }
+            MaterialError::UnknownTexture(name) => {
+                write!(f, "the material has no texture named \"{}\"", name)
+            }
+            MaterialError::TypeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "the material parameter \"{}\" is a {:?}, not a {:?}",
+                name, expected, found
+            ),
+        }
+    }
+}
+
+impl Error for MaterialError {}
+
+/// A parameter of a [`MaterialLayout`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MaterialParamEntry {
+    pub name: Cow<'static, str>,
+    pub ty: MaterialParamType,
+    /// The offset of the parameter in the parameter struct in bytes.
+    pub offset: u64,
+}
+
+/// The parameters and textures of a material, declared in the order of the shader.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct MaterialLayout {
+    params: Vec<MaterialParamEntry>,
+    textures: Vec<Cow<'static, str>>,
+    size: u64,
+}
+
+impl MaterialLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a member to the parameter struct, aligned like in WGSL.
+    pub fn param(mut self, name: impl Into<Cow<'static, str>>, ty: MaterialParamType) -> Self {
+        let end = self
+            .params
+            .last()
+            .map_or(0, |param| param.offset + param.ty.size());
+        let offset = end.div_ceil(ty.alignment()) * ty.alignment();
+
+        self.params.push(MaterialParamEntry {
+            name: name.into(),
+            ty,
+            offset,
+        });
+
+        // Uniform structs are padded to a multiple of 16 bytes
+        self.size = (offset + ty.size()).div_ceil(16) * 16;
+        self
+    }
+
+    /// Adds a texture and its sampler, bound after the previously added textures.
+    pub fn texture(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.textures.push(name.into());
+        self
+    }
+
+    pub fn get_params(&self) -> &[MaterialParamEntry] {
+        &self.params
+    }
+
+    pub fn get_textures(&self) -> &[Cow<'static, str>] {
+        &self.textures
+    }
+
+    /// *Returns the size of the parameter struct in bytes, `0` if the material has no parameters.*
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn find_param(&self, name: &str) -> Option<&MaterialParamEntry> {
+        self.params.iter().find(|param| param.name == name)
+    }
+
+    /// *Returns the index of the texture, which is bound at binding `1 + 2 * index`
+    /// and its sampler at the next binding.*
+    pub fn find_texture(&self, name: &str) -> Option<usize> {
+        self.textures.iter().position(|texture| texture == name)
+    }
+}
+
+/// The CPU copy of the parameter struct of a material.
+#[derive(Clone, Debug)]
+pub struct ParameterBlock {
+    layout: MaterialLayout,
+    data: Vec<u8>,
+    dirty: bool,
+}
+
+impl ParameterBlock {
+    /// Creates a block with all parameters zeroed.
+    pub fn new(layout: MaterialLayout) -> Self {
+        Self {
+            data: vec![0; layout.get_size() as usize],
+            layout,
+            dirty: true,
+        }
+    }
+
+    pub fn get_layout(&self) -> &MaterialLayout {
+        &self.layout
+    }
+
+    pub fn set(
+        &mut self,
+        name: &str,
+        value: impl Into<MaterialParam>,
+    ) -> Result<(), MaterialError> {
+        let value = value.into();
+        let param = self
+            .layout
+            .find_param(name)
+            .ok_or_else(|| MaterialError::UnknownParam(name.to_owned()))?;
+
+        if param.ty != value.get_type() {
+            return Err(MaterialError::TypeMismatch {
+                name: name.to_owned(),
+                expected: param.ty,
+                found: value.get_type(),
+            });
+        }
+
+        value.write(&mut self.data[param.offset as usize..]);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// *Returns `true` if parameters were set since the last call, clearing the flag.*
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::replace(&mut self.dirty, false)
+    }
+}
+
+/// A pipeline with its parameters and textures.
+pub trait Material<'a> {
+    type PipelineType;
+    type TextureViewType;
+
+    fn get_pipeline(&self) -> &Self::PipelineType;
+
+    fn get_layout(&self) -> &MaterialLayout;
+
+    /// Sets a parameter, uploaded on the next flush.
+    fn set(&mut self, name: &str, value: impl Into<MaterialParam>) -> Result<(), MaterialError>;
+
+    /// Replaces a texture, bound from the next flush.
+    fn set_texture(&mut self, name: &str, view: Self::TextureViewType)
+        -> Result<(), MaterialError>;
+}
+
+/// Creates materials, sharing pipelines between materials with the same shader and state.
+pub trait DeviceMaterials<'a, Q: Queue<'a>>:
+    DeviceUniforms<'a, Q> + DeviceTextureFactory<'a, Q>
+{
+    type MaterialType: Material<
+        'a,
+        PipelineType = Self::PipelineType,
+        TextureViewType = <Self::TextureType as Texture<'a>>::ViewType,
+    >;
+
+    /// Creates a material with zeroed parameters and white textures.
+    ///
+    /// *Panics if the pipeline is created without a material layout.*
+    fn create_material(
+        &self,
+        queue: &Q,
+        info: &PipelineCreateInfo<
+            'a,
+            Self::PipelineLayoutType,
+            Self::ShaderType,
+            Self::ImageFormatType,
+        >,
+    ) -> Self::MaterialType;
+
+    /// Uploads the parameters and rebinds the textures changed since the last flush.
+    ///
+    /// *Must be called before recording draws with the material.*
+    fn flush_material(&self, queue: &Q, material: &mut Self::MaterialType);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::material::{MaterialError, MaterialLayout, MaterialParamType, ParameterBlock};
+
+    /// A layout mixing scalars, vectors and matrices is created.
+    /// Members should be aligned like in WGSL and the struct padded to 16 bytes.
+    #[test]
+    fn test_material_layout() {
+        let layout = MaterialLayout::new()
+            .param("roughness", MaterialParamType::Float)
+            .param("tint", MaterialParamType::Vec3)
+            .param("scale", MaterialParamType::Float)
+            .param("offset", MaterialParamType::Vec2)
+            .param("transform", MaterialParamType::Mat4);
+
+        let offsets = layout
+            .get_params()
+            .iter()
+            .map(|param| param.offset)
+            .collect::<Vec<_>>();
+
+        assert_eq!(offsets, [0, 16, 28, 32, 48]);
+        assert_eq!(layout.get_size(), 112);
+    }
+
+    /// Parameters are set by name, including a missing one and one of a different type.
+    /// Only matching parameters should be written.
+    #[test]
+    fn test_parameter_block() {
+        let mut block = ParameterBlock::new(
+            MaterialLayout::new()
+                .param("scale", MaterialParamType::Float)
+                .param("tint", MaterialParamType::Vec4),
+        );
+        assert!(block.take_dirty());
+
+        block.set("tint", [1.0, 0.5, 0.25, 1.0]).unwrap();
+        assert!(matches!(
+            block.set("tint", 1.0),
+            Err(MaterialError::TypeMismatch { .. })
+        ));
+        assert_eq!(
+            block.set("color", 1.0),
+            Err(MaterialError::UnknownParam("color".to_owned()))
+        );
+
+        assert!(block.take_dirty());
+        assert_eq!(&block.get_bytes()[20..24], &0.5f32.to_ne_bytes());
+        assert_eq!(block.get_bytes().len(), 32);
+    }
+}
@@ -0,0 +1,164 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+pub mod shapes;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MeshLayout {
+    /// Each attribute is stored tightly packed in its own vertex buffer.
+    Planar,
+    /// All attributes of a vertex are stored next to each other in a single vertex buffer.
+    Interleaved,
+}
+
+/// How often the data of a vertex buffer advances.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum StepMode {
+    #[default]
+    Vertex,
+    /// Advances once per instance, for per-instance data of instanced draws such as transforms.
+    Instance,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum AttributeFormat {
+    Float32,
+    Float32x2,
+    Float32x3,
+    Float32x4,
+}
+
+impl AttributeFormat {
+    pub const fn size(&self) -> usize {
+        match self {
+            AttributeFormat::Float32 => std::mem::size_of::<f32>(),
+            AttributeFormat::Float32x2 => std::mem::size_of::<f32>() * 2,
+            AttributeFormat::Float32x3 => std::mem::size_of::<f32>() * 3,
+            AttributeFormat::Float32x4 => std::mem::size_of::<f32>() * 4,
+        }
+    }
+}
+
+pub trait Vertex: Sized {
+    const ATTRIBS: &'static [AttributeFormat];
+
+    fn layout<'a>() -> VertexLayout<'a> {
+        VertexLayout {
+            stride: std::mem::size_of::<Self>(),
+            layout: MeshLayout::Interleaved,
+            attributes: Self::ATTRIBS,
+            step_mode: StepMode::Vertex,
+        }
+    }
+
+    /// *Returns the layout of a buffer of this type holding per-instance data.*
+    fn instance_layout<'a>() -> VertexLayout<'a> {
+        VertexLayout {
+            step_mode: StepMode::Instance,
+            ..Self::layout()
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct VertexLayout<'a> {
+    /// The size of a vertex in bytes, unused for planar layouts.
+    pub stride: usize,
+    pub layout: MeshLayout,
+    /// The attributes, bound to consecutive shader locations.
+    pub attributes: &'a [AttributeFormat],
+    pub step_mode: StepMode,
+}
+
+impl<'a> VertexLayout<'a> {
+    /// Creates a layout with one vertex buffer per attribute.
+    pub fn planar(attributes: &'a [AttributeFormat]) -> Self {
+        Self {
+            stride: attributes.iter().map(AttributeFormat::size).sum(),
+            layout: MeshLayout::Planar,
+            attributes,
+            step_mode: StepMode::Vertex,
+        }
+    }
+
+    /// *Returns the number of vertex buffers to bind for this layout.*
+    pub fn buffer_count(&self) -> usize {
+        match self.layout {
+            MeshLayout::Planar => self.attributes.len(),
+            MeshLayout::Interleaved => 1,
+        }
+    }
+}
+
+/// An interleaved vertex with the attributes most meshes need,
+/// produced by the [`shapes`] and the OBJ importer.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MeshVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coords: [f32; 2],
+}
+
+impl Vertex for MeshVertex {
+    const ATTRIBS: &'static [AttributeFormat] = &[
+        AttributeFormat::Float32x3,
+        AttributeFormat::Float32x3,
+        AttributeFormat::Float32x2,
+    ];
+}
+
+/// Indexed vertices, triangle lists unless stated otherwise.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshData {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    /// *Returns the vertices as bytes, ready to be copied into a vertex buffer.*
+    pub fn vertex_bytes(&self) -> &[u8] {
+        // The vertex is made of floats only, so it has no padding
+        unsafe {
+            std::slice::from_raw_parts(
+                self.vertices.as_ptr() as *const u8,
+                std::mem::size_of_val(self.vertices.as_slice()),
+            )
+        }
+    }
+
+    /// *Returns the indices as bytes, ready to be copied into a `Uint32` index buffer.*
+    pub fn index_bytes(&self) -> &[u8] {
+        unsafe {
+            std::slice::from_raw_parts(
+                self.indices.as_ptr() as *const u8,
+                std::mem::size_of_val(self.indices.as_slice()),
+            )
+        }
+    }
+}
+
+pub trait VertexBuffer {}
+
+pub trait Mesh {}
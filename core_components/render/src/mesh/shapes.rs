@@ -0,0 +1,281 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Procedurally generated primitives, centered at the origin and one unit across.
+//!
+//! Triangles are wound counter-clockwise when seen from the outside, matching the default
+//! [`PrimitiveState`](crate::pipeline::PrimitiveState), and texture coordinates have their
+//! origin at the top-left corner of each face.
+
+use crate::mesh::{MeshData, MeshVertex};
+use std::f32::consts::PI;
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: [f32; 3], factor: f32) -> [f32; 3] {
+    a.map(|value| value * factor)
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    scale(
+        a,
+        1.0 / a.iter().map(|value| value * value).sum::<f32>().sqrt(),
+    )
+}
+
+/// Appends a tessellated parallelogram spanned by two axes from the origin corner,
+/// facing the direction of `u_axis × v_axis`.
+fn push_face(
+    data: &mut MeshData,
+    origin: [f32; 3],
+    u_axis: [f32; 3],
+    v_axis: [f32; 3],
+    segments: [u32; 2],
+) {
+    let [u_segments, v_segments] = segments.map(|segments| segments.max(1));
+    let normal = normalize(cross(u_axis, v_axis));
+    let first = data.vertices.len() as u32;
+
+    for j in 0..=v_segments {
+        for i in 0..=u_segments {
+            let u = i as f32 / u_segments as f32;
+            let v = j as f32 / v_segments as f32;
+
+            data.vertices.push(MeshVertex {
+                position: add(origin, add(scale(u_axis, u), scale(v_axis, v))),
+                normal,
+                tex_coords: [u, 1.0 - v],
+            });
+        }
+    }
+
+    let row = u_segments + 1;
+
+    for j in 0..v_segments {
+        for i in 0..u_segments {
+            let corner = first + j * row + i;
+
+            data.indices.extend([
+                corner,
+                corner + 1,
+                corner + row + 1,
+                corner,
+                corner + row + 1,
+                corner + row,
+            ]);
+        }
+    }
+}
+
+/// A quad in the XY plane, facing +Z.
+pub fn quad() -> MeshData {
+    let mut data = MeshData::default();
+    push_face(
+        &mut data,
+        [-0.5, -0.5, 0.0],
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [1, 1],
+    );
+    data
+}
+
+/// A plane in the XZ plane facing +Y, split into the given number of segments along X and Z.
+pub fn plane(size: [f32; 2], segments: [u32; 2]) -> MeshData {
+    let [width, depth] = size;
+
+    let mut data = MeshData::default();
+    push_face(
+        &mut data,
+        [-width / 2.0, 0.0, depth / 2.0],
+        [width, 0.0, 0.0],
+        [0.0, 0.0, -depth],
+        segments,
+    );
+    data
+}
+
+/// A cube with each face split into `segments` × `segments` quads.
+///
+/// *Faces do not share vertices, so each face has its own normal and texture coordinates.*
+pub fn cube(segments: u32) -> MeshData {
+    // The origin corner, U axis and V axis of each face
+    const FACES: [[[f32; 3]; 3]; 6] = [
+        [[0.5, -0.5, 0.5], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]],
+        [[-0.5, -0.5, -0.5], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]],
+        [[-0.5, 0.5, 0.5], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+        [[-0.5, -0.5, -0.5], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+        [[-0.5, -0.5, 0.5], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        [[0.5, -0.5, -0.5], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+    ];
+
+    let mut data = MeshData::default();
+
+    for [origin, u_axis, v_axis] in FACES {
+        push_face(&mut data, origin, u_axis, v_axis, [segments, segments]);
+    }
+
+    data
+}
+
+/// A sphere with the given number of segments around the Y axis and rings from pole to pole.
+///
+/// *At least 3 segments and 2 rings are generated.*
+pub fn uv_sphere(segments: u32, rings: u32) -> MeshData {
+    let segments = segments.max(3);
+    let rings = rings.max(2);
+
+    let mut data = MeshData::default();
+
+    for ring in 0..=rings {
+        let theta = PI * ring as f32 / rings as f32;
+
+        for segment in 0..=segments {
+            let phi = 2.0 * PI * segment as f32 / segments as f32;
+            let normal = [
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                -theta.sin() * phi.sin(),
+            ];
+
+            data.vertices.push(MeshVertex {
+                position: scale(normal, 0.5),
+                normal,
+                tex_coords: [segment as f32 / segments as f32, ring as f32 / rings as f32],
+            });
+        }
+    }
+
+    let row = segments + 1;
+
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let upper = ring * row + segment;
+            let lower = upper + row;
+
+            // The triangles touching the poles would be degenerate
+            if ring != 0 {
+                data.indices.extend([upper, lower, upper + 1]);
+            }
+
+            if ring != rings - 1 {
+                data.indices.extend([upper + 1, lower, lower + 1]);
+            }
+        }
+    }
+
+    data
+}
+
+/// Lines in the XZ plane outlining the given number of square cells along X and Z,
+/// for example to visualize the ground in editors.
+///
+/// *The indices form a line list instead of a triangle list.*
+pub fn grid(cells: [u32; 2], cell_size: f32) -> MeshData {
+    let [columns, rows] = cells.map(|cells| cells.max(1));
+    let origin = [
+        -(columns as f32) * cell_size / 2.0,
+        0.0,
+        -(rows as f32) * cell_size / 2.0,
+    ];
+
+    let mut data = MeshData::default();
+
+    for z in 0..=rows {
+        for x in 0..=columns {
+            data.vertices.push(MeshVertex {
+                position: add(origin, [x as f32 * cell_size, 0.0, z as f32 * cell_size]),
+                normal: [0.0, 1.0, 0.0],
+                tex_coords: [x as f32 / columns as f32, z as f32 / rows as f32],
+            });
+        }
+    }
+
+    let row = columns + 1;
+
+    for z in 0..=rows {
+        data.indices.extend([z * row, z * row + columns]);
+    }
+
+    for x in 0..=columns {
+        data.indices.extend([x, rows * row + x]);
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mesh::shapes::{cross, cube, grid, plane, quad, uv_sphere};
+    use crate::mesh::MeshData;
+
+    /// *Asserts that all triangles face the direction of their vertex normals.*
+    fn assert_outward(data: &MeshData) {
+        for triangle in data.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| data.vertices[triangle[i] as usize]);
+            let edge = |from: [f32; 3], to: [f32; 3]| [0, 1, 2].map(|i| to[i] - from[i]);
+            let face = cross(edge(a.position, b.position), edge(a.position, c.position));
+
+            let facing = (0..3).map(|i| face[i] * a.normal[i]).sum::<f32>();
+            assert!(facing > 0.0, "Triangle {:?} faces inwards", triangle);
+        }
+    }
+
+    /// Quads, planes, cubes and spheres are generated.
+    /// All triangles should be wound counter-clockwise seen from the outside.
+    #[test]
+    fn test_shape_winding() {
+        for data in [quad(), plane([2.0, 4.0], [3, 2]), cube(2), uv_sphere(16, 8)] {
+            assert_outward(&data);
+        }
+    }
+
+    /// Shapes are generated with different tessellation.
+    /// The vertex and index counts should follow the tessellation.
+    #[test]
+    fn test_shape_tessellation() {
+        let plane = plane([1.0, 1.0], [3, 2]);
+        assert_eq!((plane.vertices.len(), plane.indices.len()), (12, 36));
+
+        let cube = cube(1);
+        assert_eq!((cube.vertices.len(), cube.indices.len()), (24, 36));
+
+        let sphere = uv_sphere(8, 4);
+        assert_eq!(sphere.vertices.len(), 9 * 5);
+        assert_eq!(sphere.indices.len(), 8 * (4 * 2 - 2) * 3);
+
+        let grid = grid([4, 2], 0.5);
+        assert_eq!(grid.indices.len(), (3 + 5) * 2);
+        assert_eq!(grid.vertices.last().unwrap().position, [1.0, 0.0, 0.5]);
+    }
+}
@@ -0,0 +1,430 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Importing of Wavefront OBJ models and their MTL materials.
+//!
+//! Only polygonal geometry is imported, faces are triangulated as fans. Faces without normals
+//! get flat normals and texture coordinates are flipped vertically to match the top-left
+//! origin of textures.
+
+use crate::mesh::{MeshData, MeshVertex};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::SplitWhitespace;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ObjError {
+    /// A statement could not be parsed, lines are numbered from 1.
+    Syntax { line: usize, reason: String },
+    /// A face refers to a position, normal or texture coordinate which was not declared.
+    InvalidIndex { line: usize, index: i64 },
+}
+
+impl Display for ObjError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjError::Syntax { line, reason } => write!(f, "line {}: {}", line, reason),
+            ObjError::InvalidIndex { line, index } => {
+                write!(f, "line {}: the index {} is out of bounds", line, index)
+            }
+        }
+    }
+}
+
+impl Error for ObjError {}
+
+/// A part of a model drawn with a single material.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjMesh {
+    /// The name of the object or group the mesh belongs to.
+    pub name: String,
+    pub mesh: MeshData,
+    /// The name of the material, see [`ObjModel::find_material`].
+    pub material: Option<String>,
+}
+
+/// A material of an MTL library.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    pub shininess: f32,
+    /// The opacity of the material, `1.0` for opaque materials.
+    pub alpha: f32,
+    /// The path of the diffuse texture, relative to the library.
+    pub diffuse_texture: Option<String>,
+    pub normal_texture: Option<String>,
+}
+
+impl ObjMaterial {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ambient: [0.0; 3],
+            diffuse: [1.0; 3],
+            specular: [0.0; 3],
+            shininess: 0.0,
+            alpha: 1.0,
+            diffuse_texture: None,
+            normal_texture: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjModel {
+    /// The meshes in the order of the file, empty ones are left out.
+    pub meshes: Vec<ObjMesh>,
+    /// The paths of the MTL libraries referenced by the model, see [`ObjModel::add_materials`].
+    pub material_libraries: Vec<String>,
+    pub materials: Vec<ObjMaterial>,
+}
+
+/// The position, texture coordinate and normal indices of a face corner.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+struct MeshBuilder {
+    mesh: ObjMesh,
+    corners: HashMap<Corner, u32>,
+}
+
+impl MeshBuilder {
+    fn new(name: String, material: Option<String>) -> Self {
+        Self {
+            mesh: ObjMesh {
+                name,
+                material,
+                ..ObjMesh::default()
+            },
+            corners: HashMap::new(),
+        }
+    }
+}
+
+impl ObjModel {
+    /// Parses the source of an OBJ file, MTL libraries are added separately.
+    pub fn parse(source: &str) -> Result<Self, ObjError> {
+        let mut model = ObjModel::default();
+
+        let mut positions = Vec::new();
+        let mut tex_coords = Vec::new();
+        let mut normals = Vec::new();
+
+        let mut builder = MeshBuilder::new(String::new(), None);
+
+        for (number, line) in source.lines().enumerate() {
+            let line_number = number + 1;
+            let syntax = |reason: &str| ObjError::Syntax {
+                line: line_number,
+                reason: reason.to_owned(),
+            };
+
+            let mut tokens = line.split_whitespace();
+
+            match tokens.next() {
+                Some("v") => positions.push(parse_floats::<3>(&mut tokens, line_number)?),
+                Some("vt") => {
+                    let [u, v] = parse_floats::<2>(&mut tokens, line_number)?;
+                    tex_coords.push([u, 1.0 - v]);
+                }
+                Some("vn") => normals.push(parse_floats::<3>(&mut tokens, line_number)?),
+                Some("f") => {
+                    let corners = tokens
+                        .map(|corner| {
+                            parse_corner(
+                                corner,
+                                line_number,
+                                [positions.len(), tex_coords.len(), normals.len()],
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    if corners.len() < 3 {
+                        return Err(syntax("faces need at least three vertices"));
+                    }
+
+                    for i in 1..corners.len() - 1 {
+                        let triangle = [corners[0], corners[i], corners[i + 1]];
+                        let flat_normal = face_normal(triangle.map(|(p, _, _)| positions[p]));
+
+                        for corner in triangle {
+                            let next_index = builder.mesh.mesh.vertices.len() as u32;
+                            let index = *builder.corners.entry(corner).or_insert(next_index);
+
+                            if index == next_index {
+                                let (position, tex_coord, normal) = corner;
+                                builder.mesh.mesh.vertices.push(MeshVertex {
+                                    position: positions[position],
+                                    normal: normal.map_or(flat_normal, |n| normals[n]),
+                                    tex_coords: tex_coord.map_or([0.0; 2], |t| tex_coords[t]),
+                                });
+                            }
+
+                            builder.mesh.mesh.indices.push(index);
+                        }
+                    }
+                }
+                Some("o" | "g") => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    let material = builder.mesh.material.clone();
+                    model.push_mesh(std::mem::replace(
+                        &mut builder,
+                        MeshBuilder::new(name, material),
+                    ));
+                }
+                Some("usemtl") => {
+                    let material = tokens.next().ok_or_else(|| syntax("missing material"))?;
+                    let name = builder.mesh.name.clone();
+                    model.push_mesh(std::mem::replace(
+                        &mut builder,
+                        MeshBuilder::new(name, Some(material.to_owned())),
+                    ));
+                }
+                Some("mtllib") => model.material_libraries.extend(tokens.map(str::to_owned)),
+                // Comments, smoothing groups and free-form geometry are ignored
+                _ => {}
+            }
+        }
+
+        model.push_mesh(builder);
+
+        Ok(model)
+    }
+
+    fn push_mesh(&mut self, builder: MeshBuilder) {
+        if !builder.mesh.mesh.indices.is_empty() {
+            self.meshes.push(builder.mesh);
+        }
+    }
+
+    /// Parses the source of an MTL library and adds its materials to the model.
+    pub fn add_materials(&mut self, source: &str) -> Result<(), ObjError> {
+        let mut current: Option<ObjMaterial> = None;
+
+        for (number, line) in source.lines().enumerate() {
+            let line_number = number + 1;
+            let mut tokens = line.split_whitespace();
+
+            let statement = match tokens.next() {
+                Some(statement) if !statement.starts_with('#') => statement,
+                _ => continue,
+            };
+
+            if statement == "newmtl" {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                self.materials
+                    .extend(current.replace(ObjMaterial::new(&name)));
+                continue;
+            }
+
+            let material = current.as_mut().ok_or_else(|| ObjError::Syntax {
+                line: line_number,
+                reason: format!("{} outside of a material", statement),
+            })?;
+
+            match statement {
+                "Ka" => material.ambient = parse_floats::<3>(&mut tokens, line_number)?,
+                "Kd" => material.diffuse = parse_floats::<3>(&mut tokens, line_number)?,
+                "Ks" => material.specular = parse_floats::<3>(&mut tokens, line_number)?,
+                "Ns" => [material.shininess] = parse_floats::<1>(&mut tokens, line_number)?,
+                "d" => [material.alpha] = parse_floats::<1>(&mut tokens, line_number)?,
+                "Tr" => {
+                    let [transparency] = parse_floats::<1>(&mut tokens, line_number)?;
+                    material.alpha = 1.0 - transparency;
+                }
+                // Options of texture maps are not supported, the path is the last token
+                "map_Kd" => material.diffuse_texture = tokens.last().map(str::to_owned),
+                "map_Bump" | "map_bump" | "bump" | "norm" => {
+                    material.normal_texture = tokens.last().map(str::to_owned)
+                }
+                _ => {}
+            }
+        }
+
+        self.materials.extend(current);
+
+        Ok(())
+    }
+
+    pub fn find_material(&self, name: &str) -> Option<&ObjMaterial> {
+        self.materials.iter().find(|material| material.name == name)
+    }
+}
+
+fn parse_floats<const N: usize>(
+    tokens: &mut SplitWhitespace<'_>,
+    line: usize,
+) -> Result<[f32; N], ObjError> {
+    let mut values = [0.0; N];
+
+    for value in &mut values {
+        *value = tokens
+            .next()
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| ObjError::Syntax {
+                line,
+                reason: format!("expected {} numbers", N),
+            })?;
+    }
+
+    Ok(values)
+}
+
+/// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner into zero-based indices,
+/// resolving negative indices relative to the declared counts.
+fn parse_corner(corner: &str, line: usize, counts: [usize; 3]) -> Result<Corner, ObjError> {
+    let mut indices = [None; 3];
+
+    for (i, part) in corner.split('/').enumerate().take(3) {
+        if part.is_empty() {
+            continue;
+        }
+
+        let index = part.parse::<i64>().map_err(|_| ObjError::Syntax {
+            line,
+            reason: format!("invalid face vertex {}", corner),
+        })?;
+
+        let resolved = match index {
+            index if index > 0 => index - 1,
+            index => counts[i] as i64 + index,
+        };
+
+        if !(0..counts[i] as i64).contains(&resolved) {
+            return Err(ObjError::InvalidIndex { line, index });
+        }
+
+        indices[i] = Some(resolved as usize);
+    }
+
+    let position = indices[0].ok_or_else(|| ObjError::Syntax {
+        line,
+        reason: format!("missing position in face vertex {}", corner),
+    })?;
+
+    Ok((position, indices[1], indices[2]))
+}
+
+/// *Returns the normal of a counter-clockwise triangle.*
+fn face_normal([a, b, c]: [[f32; 3]; 3]) -> [f32; 3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let normal = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+
+    let length = normal.iter().map(|n| n * n).sum::<f32>().sqrt();
+
+    if length > 0.0 {
+        normal.map(|n| n / length)
+    } else {
+        [0.0, 0.0, 1.0]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::obj::{ObjError, ObjModel};
+
+    const QUADS: &str = "
+        mtllib quads.mtl
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 1
+        o Floor
+        usemtl Red
+        f 1/1 2/1 3/2 4/2
+        usemtl Blue
+        f -4 -2 -1
+    ";
+
+    /// A model with two materials is parsed, one quad using texture coordinates
+    /// and one triangle using negative indices.
+    /// The quad should be triangulated with shared vertices and get a flat normal.
+    #[test]
+    fn test_parse_obj() {
+        let model = ObjModel::parse(QUADS).unwrap();
+
+        assert_eq!(model.material_libraries, ["quads.mtl"]);
+        assert_eq!(model.meshes.len(), 2);
+
+        let quad = &model.meshes[0];
+        assert_eq!(quad.name, "Floor");
+        assert_eq!(quad.material.as_deref(), Some("Red"));
+        assert_eq!(quad.mesh.vertices.len(), 4);
+        assert_eq!(quad.mesh.indices, [0, 1, 2, 0, 2, 3]);
+        assert_eq!(quad.mesh.vertices[2].normal, [0.0, 0.0, 1.0]);
+        assert_eq!(quad.mesh.vertices[2].tex_coords, [1.0, 0.0]);
+        assert_eq!(quad.mesh.vertex_bytes().len(), 4 * 32);
+
+        let triangle = &model.meshes[1];
+        assert_eq!(triangle.material.as_deref(), Some("Blue"));
+        assert_eq!(triangle.mesh.vertices[1].position, [1.0, 1.0, 0.0]);
+    }
+
+    /// A face referring to a missing vertex and one with too few vertices are parsed.
+    /// Both should fail with the line of the face.
+    #[test]
+    fn test_parse_obj_errors() {
+        assert_eq!(
+            ObjModel::parse("v 0 0 0\nf 1 2 3"),
+            Err(ObjError::InvalidIndex { line: 2, index: 2 })
+        );
+        assert!(matches!(
+            ObjModel::parse("v 0 0 0\n\nf 1 1"),
+            Err(ObjError::Syntax { line: 3, .. })
+        ));
+    }
+
+    /// A library with two materials is added to a model.
+    /// The materials should be found by name with their colors and textures.
+    #[test]
+    fn test_add_materials() {
+        let mut model = ObjModel::parse(QUADS).unwrap();
+
+        model
+            .add_materials(
+                "# Materials\n\
+                 newmtl Red\n\
+                 Kd 1 0 0\n\
+                 map_Kd -s 2 2 2 textures/red.png\n\
+                 newmtl Blue\n\
+                 Kd 0 0 1\n\
+                 Tr 0.25",
+            )
+            .unwrap();
+
+        let red = model.find_material("Red").unwrap();
+        assert_eq!(red.diffuse, [1.0, 0.0, 0.0]);
+        assert_eq!(red.diffuse_texture.as_deref(), Some("textures/red.png"));
+        assert_eq!(model.find_material("Blue").unwrap().alpha, 0.75);
+    }
+}
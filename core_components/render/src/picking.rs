@@ -0,0 +1,79 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Object picking, drawing the ids of objects into an offscreen integer target
+//! and reading back the id under a position.
+//!
+//! Pick targets have a single 32-bit unsigned integer color texture, cleared to [`NO_PICK_ID`].
+//! Pipelines drawing into them are created with the format of the target and
+//! [`BlendMode::Replace`](crate::pipeline::BlendMode::Replace), their shaders write the id:
+//!
+//! ```wgsl
+//! [[stage(fragment)]]
+//! fn fs_pick() -> [[location(0)]] u32 {
+//!     return object.pick_id;
+//! }
+//! ```
+
+use crate::device::Queue;
+use crate::pluto_engine_window::window::PhysicalSize;
+use crate::target::{DepthFormat, DeviceRenderTargets};
+use crate::texture::ReadbackError;
+
+/// The id of pixels no object was drawn to.
+pub const NO_PICK_ID: u32 = 0;
+
+pub trait DevicePicking<'a, Q: Queue<'a>>: DeviceRenderTargets<'a> {
+    type PickReadbackType;
+
+    /// *Returns the format of the color texture of pick targets.*
+    fn get_pick_format(&self) -> Self::ImageFormatType;
+
+    /// Creates a pick target, with a depth texture so only the nearest objects are picked.
+    fn create_pick_target(
+        &self,
+        depth_format: Option<DepthFormat>,
+        size: PhysicalSize<u32>,
+    ) -> Self::RenderTargetType {
+        self.create_render_target(self.get_pick_format(), depth_format, size)
+    }
+
+    /// Submits a copy of the id at a position in physical pixels from the top left corner.
+    ///
+    /// ***Panics** if the position is outside of the target.*
+    fn read_pick_id(
+        &self,
+        queue: &Q,
+        target: &Self::RenderTargetType,
+        x: u32,
+        y: u32,
+    ) -> Self::PickReadbackType;
+
+    /// Checks whether the id was read back, without blocking.
+    ///
+    /// *Returns `Some` exactly once, with `None` if no object was drawn at the position.*
+    fn poll_pick_id(
+        &self,
+        readback: &mut Self::PickReadbackType,
+    ) -> Option<Result<Option<u32>, ReadbackError>>;
+}
@@ -22,9 +22,13 @@
  * SOFTWARE.
  */
 
+use crate::material::MaterialLayout;
 use crate::mesh::VertexLayout;
+use crate::push_constant::PushConstantLayout;
 use crate::shader::Shader;
+use crate::target::DepthFormat;
 use crate::texture::TextureFormat;
+use crate::uniform::UniformLayout;
 
 pub trait PipelineLayout<'a> {
     type BackingType;
@@ -32,11 +36,133 @@ pub trait PipelineLayout<'a> {
     fn get_backing_pipeline_layout(&self) -> &Self::BackingType;
 }
 
+/// How vertices are assembled into primitives.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum PrimitiveTopology {
+    PointList,
+    LineList,
+    LineStrip,
+    #[default]
+    TriangleList,
+    TriangleStrip,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum FrontFace {
+    /// Triangles with counter-clockwise vertices are front facing.
+    #[default]
+    Ccw,
+    Cw,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum CullMode {
+    None,
+    Front,
+    #[default]
+    Back,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum PolygonMode {
+    #[default]
+    Fill,
+    /// Only draws the edges of triangles.
+    ///
+    /// *Requires the non-web polygon mode feature of the device.*
+    Line,
+    /// Only draws the vertices of triangles.
+    ///
+    /// *Requires the non-web polygon mode feature of the device.*
+    Point,
+}
+
+/// The primitive assembly and rasterizer state of a pipeline.
+///
+/// The default draws filled triangle lists, culling clockwise triangles.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct PrimitiveState {
+    pub topology: PrimitiveTopology,
+    pub front_face: FrontFace,
+    pub cull_mode: CullMode,
+    pub polygon_mode: PolygonMode,
+}
+
+/// How the colors written by the fragment shader are combined with the target.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum BlendMode {
+    /// Overwrites the target, e.g. for the data of a G-buffer.
+    Replace,
+    /// Blends colors with straight alpha over the target.
+    #[default]
+    Alpha,
+    /// Blends colors with premultiplied alpha over the target.
+    PremultipliedAlpha,
+    /// Adds colors to the target, e.g. to accumulate lights.
+    Additive,
+}
+
+/// A color attachment written by the fragment shader.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ColorTarget<T: TextureFormat> {
+    pub format: T,
+    pub blend: BlendMode,
+}
+
 pub struct PipelineCreateInfo<'a, L: PipelineLayout<'a>, S: Shader<'a>, T: TextureFormat> {
     pub pipeline_layout: &'a L,
     pub shader: &'a S,
     pub buffer_layout: &'a [VertexLayout<'a>],
+    /// The format of the color attachment written at location 0.
     pub texture_format: T,
+    pub blend: BlendMode,
+    /// Further color attachments written at locations 1 and up, e.g. for deferred shading.
+    ///
+    /// *Render passes using the pipeline need an attachment for each of them,
+    /// see [`DeviceRenderTargets::create_multi_render_target`](crate::target::DeviceRenderTargets::create_multi_render_target).*
+    pub additional_color_targets: &'a [ColorTarget<T>],
+    /// Uniform buffers used by the shader, added to the pipeline layout as group 0.
+    pub uniforms: &'a [UniformLayout],
+    pub primitive: PrimitiveState,
+    pub push_constants: Option<PushConstantLayout>,
+    /// The format of the depth texture of the targets, enables depth testing if present.
+    pub depth_format: Option<DepthFormat>,
+    /// The parameters and textures of materials using the pipeline, bound at
+    /// [`MATERIAL_GROUP`](crate::material::MATERIAL_GROUP).
+    pub material: Option<&'a MaterialLayout>,
+    /// Names the pipeline in validation errors and frame debuggers,
+    /// defaults to the label of the shader.
+    pub label: Option<&'a str>,
+}
+
+impl<'a, L: PipelineLayout<'a>, S: Shader<'a>, T: TextureFormat> PipelineCreateInfo<'a, L, S, T> {
+    /// Describes a pipeline drawing with alpha blending into a single color attachment,
+    /// without vertex buffers, uniforms, depth testing or materials.
+    ///
+    /// The optional state can be set with the struct update syntax:
+    ///
+    /// ```ignore
+    /// let info = PipelineCreateInfo {
+    ///     uniforms: &[MvpUniform::layout(0)],
+    ///     ..PipelineCreateInfo::new(&pipeline_layout, &shader, texture_format)
+    /// };
+    /// ```
+    pub fn new(pipeline_layout: &'a L, shader: &'a S, texture_format: T) -> Self {
+        Self {
+            pipeline_layout,
+            shader,
+            buffer_layout: &[],
+            texture_format,
+            blend: BlendMode::default(),
+            additional_color_targets: &[],
+            uniforms: &[],
+            primitive: PrimitiveState::default(),
+            push_constants: None,
+            depth_format: None,
+            material: None,
+            label: None,
+        }
+    }
 }
 
 pub trait Pipeline<'a> {
@@ -0,0 +1,107 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::borrow::Cow;
+
+/// The WGSL code shared by all passes, prepended to the code of each pass.
+///
+/// It provides the fullscreen vertex shader `vs_main` and declares the output of the previous
+/// pass as `source_texture` and `source_sampler` at bindings 0 and 1 of group 0. Passes define
+/// `fs_main`, taking a `PostProcessVertex`, and may declare a uniform at binding 2.
+pub const POST_PROCESS_PRELUDE: &str = include_str!("shaders/post_process_prelude.wgsl");
+
+/// A fullscreen pass of a post-processing chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostProcessPass {
+    pub name: Cow<'static, str>,
+    /// The WGSL code of the pass, see [`POST_PROCESS_PRELUDE`].
+    pub code: Cow<'static, str>,
+    /// The initial contents of the uniform at binding 2, `None` if the pass has no uniform.
+    pub params: Option<Vec<u8>>,
+}
+
+impl PostProcessPass {
+    pub fn new(name: impl Into<Cow<'static, str>>, code: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            code: code.into(),
+            params: None,
+        }
+    }
+
+    pub fn with_params(mut self, params: Vec<u8>) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    /// Copies the input unchanged, used when a chain has no passes.
+    pub fn passthrough() -> Self {
+        Self::new("Passthrough", include_str!("shaders/passthrough.wgsl"))
+    }
+
+    /// Raises colors to the power of `1 / gamma`.
+    pub fn gamma_correction(gamma: f32) -> Self {
+        Self::new(
+            "Gamma Correction",
+            include_str!("shaders/gamma_correction.wgsl"),
+        )
+        .with_params(Self::gamma_params(gamma))
+    }
+
+    /// *Returns the parameters of a gamma correction pass.*
+    pub fn gamma_params(gamma: f32) -> Vec<u8> {
+        [gamma, 0.0, 0.0, 0.0]
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect()
+    }
+
+    /// Fast approximate anti-aliasing, smoothing edges of the previous pass.
+    pub fn fxaa() -> Self {
+        Self::new("FXAA", include_str!("shaders/fxaa.wgsl"))
+    }
+
+    /// Multiplies colors by a row-major 3x3 matrix, for example to simulate color blindness.
+    pub fn color_transform(matrix: [[f32; 3]; 3]) -> Self {
+        Self::new(
+            "Color Transform",
+            include_str!("shaders/color_transform.wgsl"),
+        )
+        .with_params(Self::color_transform_params(matrix))
+    }
+
+    /// *Returns the parameters of a color transform pass,
+    /// the columns of the matrix each padded to four components.*
+    pub fn color_transform_params(matrix: [[f32; 3]; 3]) -> Vec<u8> {
+        (0..3)
+            .flat_map(|column| [matrix[0][column], matrix[1][column], matrix[2][column], 0.0])
+            .flat_map(|value| value.to_ne_bytes())
+            .collect()
+    }
+
+    /// *Returns the complete WGSL source of the pass, including the prelude.*
+    pub fn get_source(&self) -> String {
+        format!("{}\n{}", POST_PROCESS_PRELUDE, self.code)
+    }
+}
@@ -0,0 +1,45 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::uniform::ShaderStages;
+
+/// Small per-draw data, such as a model matrix, set for each draw without creating bind groups.
+///
+/// Shaders declare the data as a single `var<push_constant>`. On devices without push constant
+/// support, such as WebGL2, the data is supplied through a uniform buffer with dynamic offsets
+/// instead and the declaration is rewritten to a uniform at group 1, binding 0,
+/// so pipelines using emulated push constants cannot use that binding themselves.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PushConstantLayout {
+    /// The size of the data in bytes, a multiple of 4.
+    pub size: u32,
+    pub visibility: ShaderStages,
+}
+
+/// The per-draw data storage of a pipeline.
+pub trait PushConstants<'a> {
+    /// *Returns `true` if the data is supplied through a uniform buffer
+    /// because the device does not support push constants.*
+    fn is_emulated(&self) -> bool;
+}
@@ -22,4 +22,147 @@
  * SOFTWARE.
  */
 
-pub trait RenderPass {}
+//! Drawing state of render passes, shared by all backends.
+
+use pluto_engine_window::window::PhysicalSize;
+
+/// The area of the target vertices in normalized device coordinates are mapped to,
+/// in pixels from the top left corner.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub min_depth: f32,
+    pub max_depth: f32,
+}
+
+impl Viewport {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }
+    }
+
+    /// Creates a viewport covering a whole target of the given size, the default of a pass.
+    pub fn full(size: PhysicalSize<u32>) -> Self {
+        Self::new(0.0, 0.0, size.width as f32, size.height as f32)
+    }
+
+    /// Splits a target of the given size into `count` columns of equal width,
+    /// e.g. for split-screen rendering.
+    ///
+    /// *Returns the viewport of the column at `index`, counted from the left.*
+    pub fn column(size: PhysicalSize<u32>, index: u32, count: u32) -> Self {
+        let width = size.width as f32 / count.max(1) as f32;
+        Self::new(width * index as f32, 0.0, width, size.height as f32)
+    }
+
+    /// *Returns the part of the viewport inside a target of the given size,
+    /// `None` if nothing would be drawn.*
+    pub fn clamp(&self, size: PhysicalSize<u32>) -> Option<Self> {
+        let x = self.x.clamp(0.0, size.width as f32);
+        let y = self.y.clamp(0.0, size.height as f32);
+        let width = (self.x + self.width).min(size.width as f32) - x;
+        let height = (self.y + self.height).min(size.height as f32) - y;
+
+        (width > 0.0 && height > 0.0).then_some(Self {
+            x,
+            y,
+            width,
+            height,
+            ..*self
+        })
+    }
+}
+
+/// The rectangle of the target outside of which nothing is drawn,
+/// in pixels from the top left corner.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ScissorRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ScissorRect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Creates a rectangle covering a whole target of the given size, the default of a pass.
+    pub fn full(size: PhysicalSize<u32>) -> Self {
+        Self::new(0, 0, size.width, size.height)
+    }
+
+    /// *Returns the part of the rectangle inside a target of the given size,
+    /// which is empty if they do not overlap.*
+    pub fn clamp(&self, size: PhysicalSize<u32>) -> Self {
+        let x = self.x.min(size.width);
+        let y = self.y.min(size.height);
+
+        Self {
+            x,
+            y,
+            width: self.width.min(size.width - x),
+            height: self.height.min(size.height - y),
+        }
+    }
+}
+
+pub trait RenderPass {
+    /// Sets the viewport of the following draws.
+    fn set_viewport(&mut self, viewport: &Viewport);
+
+    /// Restricts the following draws to a rectangle of the target.
+    fn set_scissor_rect(&mut self, scissor_rect: &ScissorRect);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::render_pass::{ScissorRect, Viewport};
+    use pluto_engine_window::window::PhysicalSize;
+
+    const SIZE: PhysicalSize<u32> = PhysicalSize {
+        width: 100,
+        height: 50,
+    };
+
+    /// A target is split into columns and viewports and scissor rectangles are clamped to it.
+    /// The columns should cover the target, partially outside areas should be cut off
+    /// and areas fully outside should be empty.
+    #[test]
+    fn test_clamp_to_target() {
+        assert_eq!(
+            Viewport::column(SIZE, 1, 2),
+            Viewport::new(50.0, 0.0, 50.0, 50.0)
+        );
+        assert_eq!(
+            Viewport::new(-10.0, 40.0, 50.0, 50.0).clamp(SIZE),
+            Some(Viewport::new(0.0, 40.0, 40.0, 10.0))
+        );
+        assert_eq!(Viewport::new(100.0, 0.0, 10.0, 10.0).clamp(SIZE), None);
+
+        assert_eq!(
+            ScissorRect::new(90, 10, 20, 20).clamp(SIZE),
+            ScissorRect::new(90, 10, 10, 20)
+        );
+        assert_eq!(
+            ScissorRect::new(120, 60, 20, 20).clamp(SIZE),
+            ScissorRect::new(100, 50, 0, 0)
+        );
+        assert_eq!(ScissorRect::full(SIZE).clamp(SIZE), ScissorRect::full(SIZE));
+    }
+}
@@ -27,6 +27,8 @@ pub enum ShaderCode<'a> {
         code: &'a str,
         vertex_entry: &'a str,
         fragment_entry: &'a str,
+        /// Names the shader in validation errors and frame debuggers.
+        label: Option<&'a str>,
     },
 }
 
@@ -0,0 +1,12 @@
+struct ColorTransformParams {
+    transform: mat3x3<f32>;
+};
+
+[[group(0), binding(2)]]
+var<uniform> params: ColorTransformParams;
+
+[[stage(fragment)]]
+fn fs_main(vertex_in: PostProcessVertex) -> [[location(0)]] vec4<f32> {
+    let color = textureSample(source_texture, source_sampler, vertex_in.uv);
+    return vec4<f32>(clamp(params.transform * color.rgb, vec3<f32>(0.0, 0.0, 0.0), vec3<f32>(1.0, 1.0, 1.0)), color.a);
+}
@@ -0,0 +1,49 @@
+// A compact FXAA, blending along the direction of the local luma gradient.
+
+let FXAA_REDUCE_MIN: f32 = 0.0078125;
+let FXAA_REDUCE_MUL: f32 = 0.125;
+let FXAA_SPAN_MAX: f32 = 8.0;
+
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.299, 0.587, 0.114));
+}
+
+fn sample_rgb(uv: vec2<f32>) -> vec3<f32> {
+    return textureSample(source_texture, source_sampler, uv).rgb;
+}
+
+[[stage(fragment)]]
+fn fs_main(vertex_in: PostProcessVertex) -> [[location(0)]] vec4<f32> {
+    let uv = vertex_in.uv;
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+    let color = textureSample(source_texture, source_sampler, uv);
+
+    let luma_nw = luma(sample_rgb(uv + vec2<f32>(-1.0, -1.0) * texel));
+    let luma_ne = luma(sample_rgb(uv + vec2<f32>(1.0, -1.0) * texel));
+    let luma_sw = luma(sample_rgb(uv + vec2<f32>(-1.0, 1.0) * texel));
+    let luma_se = luma(sample_rgb(uv + vec2<f32>(1.0, 1.0) * texel));
+    let luma_m = luma(color.rgb);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var dir = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+
+    let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL, FXAA_REDUCE_MIN);
+    let rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    let span = vec2<f32>(FXAA_SPAN_MAX, FXAA_SPAN_MAX);
+    dir = clamp(dir * rcp_dir_min, -span, span) * texel;
+
+    let rgb_a = 0.5 * (sample_rgb(uv + dir * (1.0 / 3.0 - 0.5)) + sample_rgb(uv + dir * (2.0 / 3.0 - 0.5)));
+    let rgb_b = rgb_a * 0.5 + 0.25 * (sample_rgb(uv - dir * 0.5) + sample_rgb(uv + dir * 0.5));
+    let luma_b = luma(rgb_b);
+
+    if (luma_b < luma_min || luma_b > luma_max) {
+        return vec4<f32>(rgb_a, color.a);
+    }
+
+    return vec4<f32>(rgb_b, color.a);
+}
@@ -0,0 +1,14 @@
+struct GammaParams {
+    // Only x is used, padded to the minimum uniform size
+    gamma: vec4<f32>;
+};
+
+[[group(0), binding(2)]]
+var<uniform> params: GammaParams;
+
+[[stage(fragment)]]
+fn fs_main(vertex_in: PostProcessVertex) -> [[location(0)]] vec4<f32> {
+    let color = textureSample(source_texture, source_sampler, vertex_in.uv);
+    let exponent = 1.0 / params.gamma.x;
+    return vec4<f32>(pow(color.rgb, vec3<f32>(exponent, exponent, exponent)), color.a);
+}
@@ -0,0 +1,4 @@
+[[stage(fragment)]]
+fn fs_main(vertex_in: PostProcessVertex) -> [[location(0)]] vec4<f32> {
+    return textureSample(source_texture, source_sampler, vertex_in.uv);
+}
@@ -0,0 +1,22 @@
+// Shared by all post-processing passes: a fullscreen triangle and the output of the previous pass.
+
+struct PostProcessVertex {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+};
+
+[[group(0), binding(0)]]
+var source_texture: texture_2d<f32>;
+
+[[group(0), binding(1)]]
+var source_sampler: sampler;
+
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> PostProcessVertex {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var vertex_out: PostProcessVertex;
+    vertex_out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    vertex_out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return vertex_out;
+}
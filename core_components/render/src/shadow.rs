@@ -0,0 +1,214 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Shadow mapping, drawing the depth of the scene as seen from a light into a shadow map
+//! and comparing against it when shading.
+//!
+//! Pipelines taking part in shadow mapping are created with
+//! [`DeviceShadowMaps::create_shadow_pipeline`], wrapping a regular [`PipelineCreateInfo`].
+//! Shadow casters are drawn with pipelines created for [`ShadowPass::Caster`], which only
+//! have a vertex stage and write depth. Pipelines created for [`ShadowPass::Receiver`] get the
+//! shadow map at [`SHADOW_GROUP`]: the [`ShadowUniform`] at binding 0, the depth texture at
+//! binding 1 and a comparison sampler at binding 2, so their shaders declare:
+//!
+//! ```wgsl
+//! struct Shadow { light_view_projection: mat4x4<f32>; texel_size: f32; };
+//! [[group(3), binding(0)]] var<uniform> shadow: Shadow;
+//! [[group(3), binding(1)]] var shadow_map: texture_depth_2d;
+//! [[group(3), binding(2)]] var shadow_sampler: sampler_comparison;
+//! ```
+//!
+//! The sampler compares with `<=`, so `textureSampleCompare` returns `1.0` for lit fragments.
+
+use crate::device::{Device, Queue};
+use crate::pipeline::{PipelineCreateInfo, PipelineLayout};
+use crate::shader::Shader;
+use crate::target::DepthFormat;
+use crate::texture::{Texture, TextureFormat};
+use std::hash::{Hash, Hasher};
+
+/// The bind group index shadow maps are bound at, after materials.
+pub const SHADOW_GROUP: u32 = 3;
+
+/// Offsets the depth written by shadow casters away from the light,
+/// so surfaces do not shadow themselves.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DepthBias {
+    /// A constant offset in units of the smallest depth difference.
+    pub constant: i32,
+    /// An offset scaled by the slope of the surface relative to the light.
+    pub slope_scale: f32,
+    /// The largest offset, `0.0` for no limit.
+    pub clamp: f32,
+}
+
+impl Default for DepthBias {
+    fn default() -> Self {
+        Self {
+            constant: 2,
+            slope_scale: 2.0,
+            clamp: 0.0,
+        }
+    }
+}
+
+/// The part a pipeline takes in shadow mapping.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ShadowPass {
+    /// Draws into shadow maps, writing only the biased depth of the vertex stage.
+    ///
+    /// *The pipeline needs a depth format, its texture format and fragment stage are unused.*
+    Caster(DepthBias),
+    /// Samples a shadow map bound at [`SHADOW_GROUP`].
+    Receiver,
+}
+
+impl Hash for ShadowPass {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            ShadowPass::Caster(bias) => {
+                0u8.hash(state);
+                bias.constant.hash(state);
+                bias.slope_scale.to_bits().hash(state);
+                bias.clamp.to_bits().hash(state);
+            }
+            ShadowPass::Receiver => 1u8.hash(state),
+        }
+    }
+}
+
+/// A pipeline taking the given part in shadow mapping.
+pub struct ShadowPipelineCreateInfo<'a, L: PipelineLayout<'a>, S: Shader<'a>, T: TextureFormat> {
+    pub pipeline: PipelineCreateInfo<'a, L, S, T>,
+    pub pass: ShadowPass,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ShadowMapConfig {
+    /// The width and height of the shadow map in texels.
+    pub size: u32,
+    pub depth_format: DepthFormat,
+}
+
+impl Default for ShadowMapConfig {
+    fn default() -> Self {
+        Self {
+            size: 2048,
+            depth_format: DepthFormat::Depth32Float,
+        }
+    }
+}
+
+/// The uniform bound with a shadow map, in the layout expected by shaders.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShadowUniform {
+    /// Transforms world positions into the clip space of the light, column-major.
+    pub light_view_projection: [[f32; 4]; 4],
+    /// The size of a texel in texture coordinates, e.g. for filtering.
+    pub texel_size: f32,
+}
+
+impl ShadowUniform {
+    /// The size of the uniform struct, padded to a multiple of 16 bytes.
+    pub const SIZE: u64 = 80;
+
+    pub fn new(light_view_projection: [[f32; 4]; 4], config: &ShadowMapConfig) -> Self {
+        Self {
+            light_view_projection,
+            texel_size: 1.0 / config.size as f32,
+        }
+    }
+
+    /// *Returns the uniform data to be written to the buffer of a shadow map.*
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = self
+            .light_view_projection
+            .iter()
+            .flatten()
+            .chain(std::iter::once(&self.texel_size))
+            .flat_map(|value| value.to_ne_bytes())
+            .collect::<Vec<_>>();
+        bytes.resize(Self::SIZE as usize, 0);
+        bytes
+    }
+}
+
+/// A depth texture shadow casters are drawn into, with the uniform and sampler
+/// receivers sample it with.
+pub trait ShadowMap<'a> {
+    type TextureType: Texture<'a>;
+
+    fn get_depth_texture(&self) -> &Self::TextureType;
+
+    fn get_config(&self) -> ShadowMapConfig;
+}
+
+pub trait DeviceShadowMaps<'a, Q: Queue<'a>>: Device<'a> {
+    type ShadowMapType: ShadowMap<'a, TextureType = Self::TextureType>;
+
+    fn create_shadow_map(&self, config: ShadowMapConfig) -> Self::ShadowMapType;
+
+    /// Creates a shadow caster or receiver, cached like [`Device::create_pipeline`].
+    fn create_shadow_pipeline(
+        &self,
+        info: &ShadowPipelineCreateInfo<
+            'a,
+            Self::PipelineLayoutType,
+            Self::ShaderType,
+            Self::ImageFormatType,
+        >,
+    ) -> Self::PipelineType;
+
+    /// Sets the view-projection matrix of the light the shadow map is drawn from.
+    fn write_shadow_uniform(
+        &self,
+        queue: &Q,
+        shadow_map: &Self::ShadowMapType,
+        uniform: &ShadowUniform,
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shadow::{ShadowMapConfig, ShadowUniform};
+
+    /// A shadow uniform is created for a 1024 texel shadow map.
+    /// The matrix should be followed by the texel size, padded to the uniform size.
+    #[test]
+    fn test_shadow_uniform_bytes() {
+        let mut matrix = [[0.0; 4]; 4];
+        matrix[3][0] = 2.0;
+
+        let config = ShadowMapConfig {
+            size: 1024,
+            ..ShadowMapConfig::default()
+        };
+        let bytes = ShadowUniform::new(matrix, &config).as_bytes();
+
+        assert_eq!(bytes.len() as u64, ShadowUniform::SIZE);
+        assert_eq!(bytes[48..52], 2.0f32.to_ne_bytes());
+        assert_eq!(bytes[64..68], (1.0f32 / 1024.0).to_ne_bytes());
+        assert!(bytes[68..].iter().all(|&byte| byte == 0));
+    }
+}
@@ -30,7 +30,14 @@ use std::fmt::{Debug, Display, Formatter};
 
 #[derive(Debug, Clone)]
 pub enum SurfaceError<T: Clone + Error> {
+    /// The surface was lost and has to be reconfigured.
     DeviceLost,
+    /// The surface no longer matches the window, for example after a resize,
+    /// and has to be reconfigured.
+    Outdated,
+    /// No texture became available in time, the frame can be retried.
+    Timeout,
+    /// No memory is left to allocate the texture, rendering cannot continue.
     OutOfMemory,
     Other(T),
 }
@@ -79,7 +86,7 @@ pub trait SurfaceFormat {
 pub trait Surface<'a> {
     type BackingType;
 
-    type SizeType: Sized;
+    type SizeType: Copy;
     type DeviceType: Device<'a>;
     type FormatType: SurfaceFormat;
     type TextureFormatType: TextureFormat;
@@ -98,3 +105,71 @@ pub trait Surface<'a> {
 
     fn acquire_next_texture(&self) -> Result<Self::TextureType, SurfaceError<Self::ErrorType>>;
 }
+
+/// The most frames skipped between attempts to reconfigure a failing surface.
+const MAX_RECOVERY_BACKOFF: u32 = 64;
+
+/// Tracks consecutive failures to acquire surface textures,
+/// backing off exponentially between reconfiguration attempts.
+///
+/// Keeps a surface that cannot be reconfigured, for example during a driver reset,
+/// from being reconfigured every frame.
+#[derive(Clone, Debug, Default)]
+pub struct SurfaceRecovery {
+    failures: u32,
+    skipped: u32,
+}
+
+impl SurfaceRecovery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a failure that requires the surface to be reconfigured.
+    ///
+    /// *Returns `true` if the surface should be reconfigured now,
+    /// `false` if this attempt is skipped to back off.*
+    pub fn on_failure(&mut self) -> bool {
+        let backoff = (1u32 << self.failures.min(6)).min(MAX_RECOVERY_BACKOFF) - 1;
+
+        if self.skipped < backoff {
+            self.skipped += 1;
+            return false;
+        }
+
+        self.failures += 1;
+        self.skipped = 0;
+        true
+    }
+
+    /// Resets the backoff after a texture was acquired.
+    pub fn on_success(&mut self) {
+        self.failures = 0;
+        self.skipped = 0;
+    }
+
+    /// *Returns the number of reconfigurations since the last acquired texture.*
+    pub fn get_failures(&self) -> u32 {
+        self.failures
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::surface::SurfaceRecovery;
+
+    /// A surface fails to be acquired for many frames, then recovers.
+    /// Reconfigurations should become exponentially rarer and reset after the recovery.
+    #[test]
+    fn test_surface_recovery_backoff() {
+        let mut recovery = SurfaceRecovery::new();
+
+        let attempts = (0..16).filter(|_| recovery.on_failure()).count();
+        // Reconfigured on frames 0, 2, 6 and 14
+        assert_eq!(attempts, 4);
+
+        recovery.on_success();
+        assert_eq!(recovery.get_failures(), 0);
+        assert!(recovery.on_failure());
+    }
+}
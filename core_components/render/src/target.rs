@@ -0,0 +1,178 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::device::Device;
+use crate::texture::{Texture, TextureFormat};
+use pluto_engine_window::window::PhysicalSize;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DepthFormat {
+    Depth32Float,
+    Depth24Plus,
+}
+
+/// Color textures with an optional depth texture render passes can draw into
+/// instead of the surface texture.
+///
+/// Most targets have a single color texture, multiple ones are written at once by pipelines
+/// with additional color targets.
+///
+/// *All textures can be sampled by later passes, color textures can also be read back.*
+pub trait RenderTarget<'a> {
+    type TextureType: Texture<'a>;
+    type FormatType: TextureFormat;
+
+    /// Returns the first color texture, written at location 0.
+    fn get_color_texture(&self) -> &Self::TextureType {
+        &self.get_color_textures()[0]
+    }
+
+    /// Returns all color textures, in the order of their locations.
+    fn get_color_textures(&self) -> &[Self::TextureType];
+
+    fn get_depth_texture(&self) -> Option<&Self::TextureType>;
+
+    /// Returns the format of the first color texture.
+    fn get_format(&self) -> Self::FormatType {
+        self.get_formats().remove(0)
+    }
+
+    /// Returns the formats of all color textures, in the order of their locations.
+    fn get_formats(&self) -> Vec<Self::FormatType>;
+
+    fn get_depth_format(&self) -> Option<DepthFormat>;
+
+    fn get_size(&self) -> PhysicalSize<u32>;
+}
+
+/// Creates offscreen render targets.
+pub trait DeviceRenderTargets<'a>: Device<'a> {
+    type RenderTargetType: RenderTarget<
+        'a,
+        TextureType = Self::TextureType,
+        FormatType = Self::ImageFormatType,
+    >;
+
+    fn create_render_target(
+        &self,
+        format: Self::ImageFormatType,
+        depth_format: Option<DepthFormat>,
+        size: PhysicalSize<u32>,
+    ) -> Self::RenderTargetType {
+        self.create_multi_render_target(&[format], depth_format, size)
+    }
+
+    /// Creates a target with a color texture of each format, e.g. a G-buffer.
+    ///
+    /// ***Panics*** if no format is given.
+    fn create_multi_render_target(
+        &self,
+        formats: &[Self::ImageFormatType],
+        depth_format: Option<DepthFormat>,
+        size: PhysicalSize<u32>,
+    ) -> Self::RenderTargetType;
+}
+
+/// *Returns the size of a target scaled relative to the surface, at least one pixel.*
+pub fn scaled_size(surface_size: PhysicalSize<u32>, scale: f32) -> PhysicalSize<u32> {
+    PhysicalSize {
+        width: ((surface_size.width as f32 * scale).round() as u32).max(1),
+        height: ((surface_size.height as f32 * scale).round() as u32).max(1),
+    }
+}
+
+/// A resource whose size follows the surface, such as a depth buffer or an offscreen target.
+///
+/// Applications forward the sizes received by `ApplicationState::on_surface_resized`
+/// to keep the resource in sync with the surface.
+pub trait SurfaceDependentResource<'a, D: Device<'a>> {
+    /// Recreates the resource if it does not match the new surface size.
+    ///
+    /// *Returns `true` if the resource was recreated, in which case bind groups using
+    /// it have to be recreated as well.*
+    fn on_surface_resized(&mut self, device: &D, surface_size: PhysicalSize<u32>) -> bool;
+}
+
+/// A render target sized relative to the surface, recreated when the surface is resized.
+pub struct SurfaceSizedTarget<'a, D: DeviceRenderTargets<'a>> {
+    target: D::RenderTargetType,
+    /// The size of the target relative to the surface, `1.0` for the full resolution.
+    scale: f32,
+}
+
+impl<'a, D: DeviceRenderTargets<'a>> SurfaceSizedTarget<'a, D> {
+    pub fn new(
+        device: &D,
+        format: D::ImageFormatType,
+        depth_format: Option<DepthFormat>,
+        surface_size: PhysicalSize<u32>,
+        scale: f32,
+    ) -> Self {
+        Self {
+            target: device.create_render_target(
+                format,
+                depth_format,
+                scaled_size(surface_size, scale),
+            ),
+            scale,
+        }
+    }
+
+    pub fn get_target(&self) -> &D::RenderTargetType {
+        &self.target
+    }
+}
+
+impl<'a, D: DeviceRenderTargets<'a>> SurfaceDependentResource<'a, D> for SurfaceSizedTarget<'a, D> {
+    fn on_surface_resized(&mut self, device: &D, surface_size: PhysicalSize<u32>) -> bool {
+        let size = scaled_size(surface_size, self.scale);
+        if size == self.target.get_size() {
+            return false;
+        }
+
+        self.target = device.create_multi_render_target(
+            &self.target.get_formats(),
+            self.target.get_depth_format(),
+            size,
+        );
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::target::scaled_size;
+    use pluto_engine_window::window::PhysicalSize;
+
+    /// A half resolution target is sized for a regular and a minimized surface.
+    /// The size should be rounded and never reach zero.
+    #[test]
+    fn test_scaled_size() {
+        let size = |width, height| PhysicalSize { width, height };
+
+        assert_eq!(scaled_size(size(1921, 1080), 0.5), size(961, 540));
+        assert_eq!(scaled_size(size(0, 0), 0.5), size(1, 1));
+    }
+}
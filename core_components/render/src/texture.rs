@@ -22,6 +22,10 @@
  * SOFTWARE.
  */
 
+use pluto_engine_window::window::PhysicalSize;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
 pub trait TextureFormat {
     type BackingType: Copy + Clone;
 
@@ -42,3 +46,42 @@ pub trait TextureView<'a> {
 
     fn get_backing_texture_view(&self) -> &Self::BackingType;
 }
+
+/// Pixels read back from a texture into CPU memory.
+///
+/// Rows are tightly packed 8-bit RGBA, from the top row to the bottom one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TexturePixels {
+    pub size: PhysicalSize<u32>,
+    pub data: Vec<u8>,
+}
+
+impl TexturePixels {
+    /// *Returns the RGBA value of the pixel at the given coordinates.*
+    ///
+    /// ***Panics*** if the coordinates are out of bounds.
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        assert!(x < self.size.width && y < self.size.height);
+
+        let offset = (y as usize * self.size.width as usize + x as usize) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.data[offset..offset + 4]);
+        pixel
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ReadbackError {
+    /// The texture format cannot be converted to 8-bit RGBA.
+    UnsupportedFormat,
+    /// The staging buffer could not be mapped, e.g. because the device was lost.
+    MapFailed,
+}
+
+impl Display for ReadbackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for ReadbackError {}
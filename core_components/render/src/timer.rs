@@ -0,0 +1,70 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::device::{Device, Queue};
+use std::time::Duration;
+
+/// The GPU time spent in a single pass of a frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassTiming {
+    pub name: String,
+    pub duration: Duration,
+}
+
+/// Measures the GPU time of passes using timestamp queries.
+///
+/// *Timings are only available a few frames after they were recorded.*
+pub trait GpuTimer<'a> {
+    type CommandBufferBuilderType;
+
+    /// Writes a timestamp marking the start of a pass.
+    ///
+    /// *Passes beyond the capacity of the timer are ignored.*
+    fn begin_pass(&mut self, command_buffer: &mut Self::CommandBufferBuilderType, name: &str);
+
+    /// Writes a timestamp marking the end of the pass started last.
+    fn end_pass(&mut self, command_buffer: &mut Self::CommandBufferBuilderType);
+
+    /// Schedules the timestamps of this frame to be read back.
+    ///
+    /// Should be recorded into the last command buffer submitted in the frame.
+    fn end_frame(&mut self, command_buffer: &mut Self::CommandBufferBuilderType);
+}
+
+pub trait DeviceGpuTimer<'a>: Device<'a> {
+    type QueueType: Queue<'a>;
+    type GpuTimerType: GpuTimer<'a, CommandBufferBuilderType = Self::CommandBufferBuilderType>;
+
+    /// Creates a timer measuring at most `max_passes` passes per frame.
+    ///
+    /// *Returns `None` if the device does not support timestamp queries.*
+    fn create_gpu_timer(
+        &self,
+        queue: &Self::QueueType,
+        max_passes: u32,
+    ) -> Option<Self::GpuTimerType>;
+
+    /// Checks whether the timings of an earlier frame are available, without blocking.
+    fn poll_gpu_timer(&self, timer: &mut Self::GpuTimerType) -> Option<Vec<PassTiming>>;
+}
@@ -22,47 +22,34 @@
  * SOFTWARE.
  */
 
-pub enum MeshLayout {
-    Planar,
-    Interleaved,
+/// The shader stages a binding is visible to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ShaderStages {
+    Vertex,
+    Fragment,
+    VertexFragment,
 }
 
-pub enum AttributeFormat {
-    Float32,
-    Float32x2,
-    Float32x3,
-    Float32x4,
+/// A uniform buffer used by a pipeline, bound at group 0 and the given binding.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct UniformLayout {
+    pub binding: u32,
+    /// The size of the uniform data in bytes.
+    pub size: u64,
+    pub visibility: ShaderStages,
 }
 
-impl AttributeFormat {
-    pub const fn size(&self) -> usize {
-        match self {
-            AttributeFormat::Float32 => std::mem::size_of::<f32>(),
-            AttributeFormat::Float32x2 => std::mem::size_of::<f32>() * 2,
-            AttributeFormat::Float32x3 => std::mem::size_of::<f32>() * 3,
-            AttributeFormat::Float32x4 => std::mem::size_of::<f32>() * 4,
-        }
-    }
-}
-
-pub trait Vertex: Sized {
-    const ATTRIBS: &'static [AttributeFormat];
+pub trait UniformBuffer<'a> {
+    type BackingType;
 
-    fn layout<'a>() -> VertexLayout<'a> {
-        VertexLayout {
-            stride: std::mem::size_of::<Self>(),
-            layout: MeshLayout::Interleaved,
-            attributes: Self::ATTRIBS,
-        }
-    }
-}
+    fn get_backing_buffer(&self) -> &Self::BackingType;
 
-pub struct VertexLayout<'a> {
-    pub stride: usize,
-    pub layout: MeshLayout,
-    pub attributes: &'a [AttributeFormat],
+    fn get_size(&self) -> u64;
 }
 
-pub trait VertexBuffer {}
+/// A set of uniform buffers bound to the uniforms of a pipeline.
+pub trait UniformBindGroup<'a> {
+    type BackingType;
 
-pub trait Mesh {}
+    fn get_backing_bind_group(&self) -> &Self::BackingType;
+}
@@ -0,0 +1,276 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::device::{Device, DeviceCompute, DeviceQueues, DeviceTextureFactory, Queue};
+use crate::image::TextureImage;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+type UploadResult<T> = Arc<Mutex<Option<T>>>;
+
+/// A resource whose data has been queued for upload but may not have been submitted yet.
+pub struct PendingUpload<T>(UploadResult<T>);
+
+impl<T> PendingUpload<T> {
+    /// *Creates a pending upload and the slot completing it.*
+    pub fn new() -> (Self, UploadSlot<T>) {
+        let result = UploadResult::default();
+        (Self(result.clone()), UploadSlot(result))
+    }
+
+    /// Checks whether the upload was submitted, without blocking.
+    ///
+    /// *Returns `Some` exactly once, the resource can then be used by later submissions.*
+    pub fn poll_result(&mut self) -> Option<T> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+/// The uploader side of a [`PendingUpload`].
+pub struct UploadSlot<T>(UploadResult<T>);
+
+impl<T> UploadSlot<T> {
+    pub fn complete(self, resource: T) {
+        *self.0.lock().unwrap() = Some(resource);
+    }
+}
+
+/// Spreads queued uploads over frames, so large uploads do not stall a single frame.
+pub struct UploadScheduler<U> {
+    queue: VecDeque<(u64, U)>,
+    /// The most bytes submitted per frame.
+    budget: u64,
+}
+
+impl<U> UploadScheduler<U> {
+    pub fn new(budget: u64) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            budget,
+        }
+    }
+
+    pub fn push(&mut self, size: u64, upload: U) {
+        self.queue.push_back((size, upload));
+    }
+
+    /// *Returns the uploads to submit this frame, in the order they were queued.*
+    ///
+    /// At least one upload is returned while any are queued, even if it exceeds the budget.
+    pub fn take_batch(&mut self) -> Vec<U> {
+        let mut batch = Vec::new();
+        let mut size = 0;
+
+        while let Some((upload_size, _)) = self.queue.front() {
+            if !batch.is_empty() && size + upload_size > self.budget {
+                break;
+            }
+
+            let (upload_size, upload) = self.queue.pop_front().unwrap();
+            size += upload_size;
+            batch.push(upload);
+        }
+
+        batch
+    }
+
+    /// *Returns the size of all queued uploads in bytes.*
+    pub fn get_queued_size(&self) -> u64 {
+        self.queue.iter().map(|(size, _)| size).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// Uploads textures and buffers outside of rendering, copying them through staging buffers
+/// submitted to the transfer queue over the next frames.
+pub trait DeviceUploads<'a, Q: Queue<'a>>:
+    DeviceTextureFactory<'a, Q> + DeviceCompute<'a, Q>
+{
+    type UploaderType;
+
+    /// Creates an uploader submitting at most `budget` bytes per frame.
+    fn create_uploader(&self, budget: u64) -> Self::UploaderType;
+
+    /// Copies the image into staging buffers,
+    /// the texture is handed out once the copies are submitted.
+    fn queue_texture_upload(
+        &self,
+        uploader: &mut Self::UploaderType,
+        image: &TextureImage,
+    ) -> PendingUpload<Self::TextureType>;
+
+    /// *The buffer may be padded to the copy alignment of the device.*
+    fn queue_storage_upload(
+        &self,
+        uploader: &mut Self::UploaderType,
+        data: &[u8],
+    ) -> PendingUpload<Self::StorageBufferType>;
+
+    /// Submits the uploads of this frame, should be called once per frame before rendering.
+    fn submit_uploads(&self, queues: &DeviceQueues<Q>, uploader: &mut Self::UploaderType);
+}
+
+/// A range of one of the chunks of a [`FrameArena`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FrameRange {
+    pub chunk: usize,
+    pub offset: u64,
+}
+
+/// Sub-allocates ranges of chunks which are recycled every frame.
+///
+/// Only tracks the capacities of the chunks, the buffers are owned by the allocator using it.
+pub struct FrameArena {
+    chunk_size: u64,
+    alignment: u64,
+    chunks: Vec<u64>,
+    chunk: usize,
+    offset: u64,
+}
+
+impl FrameArena {
+    /// Creates an arena of chunks holding `chunk_size` bytes, with ranges aligned to `alignment`.
+    pub fn new(chunk_size: u64, alignment: u64) -> Self {
+        Self {
+            chunk_size,
+            alignment,
+            chunks: Vec::new(),
+            chunk: 0,
+            offset: 0,
+        }
+    }
+
+    /// Allocates a range for `size` bytes.
+    ///
+    /// *Returns the range and the capacity of a new chunk the range belongs to,
+    /// which has to be created by the caller.*
+    pub fn allocate(&mut self, size: u64) -> (FrameRange, Option<u64>) {
+        let offset = self.offset.div_ceil(self.alignment) * self.alignment;
+
+        if self
+            .chunks
+            .get(self.chunk)
+            .is_some_and(|capacity| offset + size <= *capacity)
+        {
+            self.offset = offset + size;
+            return (
+                FrameRange {
+                    chunk: self.chunk,
+                    offset,
+                },
+                None,
+            );
+        }
+
+        // The rest of the current chunk is left unused until the next frame
+        let start = if self.offset == 0 {
+            self.chunk
+        } else {
+            self.chunk + 1
+        };
+        let reused = (start..self.chunks.len()).find(|chunk| size <= self.chunks[*chunk]);
+
+        let (chunk, new_chunk) = match reused {
+            Some(chunk) => (chunk, None),
+            None => {
+                let capacity = size.max(self.chunk_size);
+                self.chunks.push(capacity);
+                (self.chunks.len() - 1, Some(capacity))
+            }
+        };
+
+        self.chunk = chunk;
+        self.offset = size;
+        (FrameRange { chunk, offset: 0 }, new_chunk)
+    }
+
+    /// Recycles all ranges allocated during the previous frame.
+    pub fn reset(&mut self) {
+        self.chunk = 0;
+        self.offset = 0;
+    }
+
+    /// *Returns the capacity of all chunks in bytes.*
+    pub fn get_capacity(&self) -> u64 {
+        self.chunks.iter().sum()
+    }
+}
+
+/// Allocates buffer ranges for data uploaded every frame, such as dynamic vertices,
+/// recycling them in the next frame instead of creating new buffers.
+pub trait FrameAllocator<'a, D: Device<'a>, Q: Queue<'a>> {
+    type SliceType;
+
+    /// Copies the data into a range usable by the draws of the current frame.
+    fn upload(&mut self, device: &D, queue: &Q, data: &[u8]) -> Self::SliceType;
+
+    /// Recycles the ranges of the previous frame, should be called before the first upload
+    /// of every frame.
+    ///
+    /// *Safe once the previous frame is submitted, writes are ordered after earlier submissions.*
+    fn begin_frame(&mut self);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::upload::{FrameArena, FrameRange, UploadScheduler};
+
+    /// Uploads of 3, 3, 10 and 1 bytes are scheduled with a budget of 8 bytes per frame.
+    /// The oversized upload should get a frame of its own, keeping the order.
+    #[test]
+    fn test_upload_scheduler_budget() {
+        let mut scheduler = UploadScheduler::new(8);
+        for (index, size) in [3, 3, 10, 1].into_iter().enumerate() {
+            scheduler.push(size, index);
+        }
+        assert_eq!(scheduler.get_queued_size(), 17);
+
+        assert_eq!(scheduler.take_batch(), [0, 1]);
+        assert_eq!(scheduler.take_batch(), [2]);
+        assert_eq!(scheduler.take_batch(), [3]);
+        assert!(scheduler.is_empty());
+        assert!(scheduler.take_batch().is_empty());
+    }
+
+    /// Ranges are allocated in two frames of an arena with 16 byte chunks and 4 byte alignment.
+    /// Chunks should be filled in order, added for large ranges and reused after the reset.
+    #[test]
+    fn test_frame_arena() {
+        let mut arena = FrameArena::new(16, 4);
+        let range = |chunk, offset| FrameRange { chunk, offset };
+
+        assert_eq!(arena.allocate(6), (range(0, 0), Some(16)));
+        assert_eq!(arena.allocate(8), (range(0, 8), None));
+        assert_eq!(arena.allocate(4), (range(1, 0), Some(16)));
+        assert_eq!(arena.allocate(20), (range(2, 0), Some(20)));
+        assert_eq!(arena.get_capacity(), 52);
+
+        arena.reset();
+        assert_eq!(arena.allocate(12), (range(0, 0), None));
+        assert_eq!(arena.allocate(18), (range(2, 0), None));
+        assert_eq!(arena.get_capacity(), 52);
+    }
+}
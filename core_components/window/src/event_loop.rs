@@ -22,19 +22,146 @@
  * SOFTWARE.
  */
 
+use crate::executor::LocalFuture;
 use crate::window::{Window, WindowEvent};
+use std::any::Any;
+use std::collections::VecDeque;
 use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{RecvError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub enum DisplayEvent {
     Disconnected,
     Repaint,
     NextFrame,
     WindowEvent(WindowEvent),
+    /// The window is no longer visible, or the application was sent to the background.
+    ///
+    /// *The surface may be destroyed by the platform until the window is resumed,
+    /// displays should not render in the meantime.*
+    Suspended,
+    /// The window is visible again after being suspended.
+    Resumed,
+    /// Sent by the application through a [`DisplayEventProxy`].
+    User(UserEvent),
 }
 
-#[derive(Copy, Clone, Debug)]
-pub enum DisplayCommand {}
+/// Data sent by the application to one of its windows, for example from a network thread.
+#[derive(Clone)]
+pub struct UserEvent(Arc<dyn Any + Send + Sync>);
+
+impl UserEvent {
+    pub fn new<T: Any + Send + Sync>(data: T) -> Self {
+        Self(Arc::new(data))
+    }
+
+    /// *Returns the data if it is of type `T`.*
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+
+    pub fn is<T: Any>(&self) -> bool {
+        self.0.is::<T>()
+    }
+}
+
+impl Debug for UserEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("UserEvent(..)")
+    }
+}
+
+impl DisplayEvent {
+    /// *Returns `true` if only the newest of two events of the same kind is relevant,
+    /// so an older one still waiting in a channel can be replaced.*
+    pub fn coalesces_with(&self, other: &DisplayEvent) -> bool {
+        match (self, other) {
+            (DisplayEvent::Repaint, DisplayEvent::Repaint)
+            | (DisplayEvent::NextFrame, DisplayEvent::NextFrame) => true,
+            (DisplayEvent::WindowEvent(event), DisplayEvent::WindowEvent(other)) => matches!(
+                (event, other),
+                (WindowEvent::Resized(_), WindowEvent::Resized(_))
+                    | (
+                        WindowEvent::ScaleFactorChanged { .. },
+                        WindowEvent::ScaleFactorChanged { .. }
+                    )
+                    | (
+                        WindowEvent::CursorMoved { .. },
+                        WindowEvent::CursorMoved { .. }
+                    )
+            ),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum DisplayCommand {
+    /// Suspends all windows, sent when the browser tab is hidden.
+    Suspend,
+    /// Resumes all windows, sent when the browser tab is visible again.
+    Resume,
+    /// Wakes the event loop without sending an event, so frames are produced again.
+    Wake,
+    /// Delivers an event to the window with the raw ID, see [`DisplayEventProxy`].
+    User { window: u64, event: UserEvent },
+    /// Delivers a window event observed outside of the event loop to the window with the raw ID,
+    /// such as a resize of the canvas on the web.
+    WindowEvent { window: u64, event: WindowEvent },
+}
+
+/// Sends commands to the event loop, from any thread.
+pub type CommandProxy = Arc<dyn Fn(DisplayCommand) + Send + Sync>;
+
+/// A handle other threads use to wake the event loop and deliver events to a window,
+/// see [`Window::create_event_proxy`].
+#[derive(Clone)]
+pub struct DisplayEventProxy {
+    window: u64,
+    command_proxy: CommandProxy,
+}
+
+impl DisplayEventProxy {
+    pub fn new(window: u64, command_proxy: CommandProxy) -> Self {
+        Self {
+            window,
+            command_proxy,
+        }
+    }
+
+    /// Sends data to the window, received as [`DisplayEvent::User`].
+    ///
+    /// *Events sent after the window closed are dropped.*
+    pub fn send<T: Any + Send + Sync>(&self, data: T) {
+        self.send_event(UserEvent::new(data));
+    }
+
+    pub fn send_event(&self, event: UserEvent) {
+        (self.command_proxy)(DisplayCommand::User {
+            window: self.window,
+            event,
+        });
+    }
+
+    /// Wakes the event loop, for example after it stopped polling for frames.
+    pub fn wake(&self) {
+        (self.command_proxy)(DisplayCommand::Wake);
+    }
+}
+
+impl Debug for DisplayEventProxy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DisplayEventProxy")
+            .field("window", &self.window)
+            .finish_non_exhaustive()
+    }
+}
 
 pub trait EventLoop: 'static {
     type WindowType: Window + Send;
@@ -56,7 +183,289 @@ pub trait EventLoop: 'static {
 pub trait EventLoopWindowFactory<E: EventLoop> {
     type LoopType: 'static;
 
-    fn create_window(&mut self) -> E::WindowType;
+    /// Creates a window with the default event channel, see [`DisplayEventChannelConfig`].
+    fn create_window(&mut self) -> E::WindowType {
+        self.create_window_with_channel(DisplayEventChannelConfig::default())
+    }
+
+    fn create_window_with_channel(&mut self, channel: DisplayEventChannelConfig) -> E::WindowType;
+
+    /// Runs a future on the event loop thread, polled after events wake it.
+    fn spawn_local(&mut self, future: LocalFuture);
 
     fn get_backing_loop(&self) -> &Self::LoopType;
 }
+
+/// What a sender does when the channel of a window is full.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ChannelOverflow {
+    /// Blocks the sending thread until the receiver catches up.
+    Block,
+    /// Replaces a waiting event of the same kind, see [`DisplayEvent::coalesces_with`],
+    /// or queues the event beyond the capacity. Never blocks the sending thread.
+    #[default]
+    Coalesce,
+}
+
+/// The capacity and overflow policy of the event channel of a window.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DisplayEventChannelConfig {
+    pub capacity: usize,
+    pub overflow: ChannelOverflow,
+}
+
+impl Default for DisplayEventChannelConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 16,
+            overflow: ChannelOverflow::default(),
+        }
+    }
+}
+
+/// The receiver of the channel was dropped, the window no longer handles events.
+#[derive(Clone, Debug)]
+pub struct DisplayDisconnected(pub DisplayEvent);
+
+impl Display for DisplayDisconnected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the display was disconnected before receiving {:?}",
+            self.0
+        )
+    }
+}
+
+impl Error for DisplayDisconnected {}
+
+struct ChannelState {
+    queue: VecDeque<DisplayEvent>,
+    sender_connected: bool,
+    receiver_connected: bool,
+    /// The task awaiting the next event, see [`DisplayEventReceiver::next_event`].
+    waker: Option<Waker>,
+}
+
+struct Channel {
+    state: Mutex<ChannelState>,
+    /// Notified when an event is received or the receiver is dropped.
+    not_full: Condvar,
+    /// Notified when an event is sent or the sender is dropped.
+    not_empty: Condvar,
+    config: DisplayEventChannelConfig,
+}
+
+/// *Creates a channel of events for a window, blocking senders while it holds `bound` events.*
+///
+/// Unlike a plain channel, the receiver can be awaited, see [`DisplayEventReceiver::next_event`].
+pub fn display_event_channel(bound: usize) -> (DisplayEventSender, DisplayEventReceiver) {
+    display_event_channel_with(DisplayEventChannelConfig {
+        capacity: bound,
+        overflow: ChannelOverflow::Block,
+    })
+}
+
+/// *Creates a channel of events for a window with the given capacity and overflow policy.*
+pub fn display_event_channel_with(
+    config: DisplayEventChannelConfig,
+) -> (DisplayEventSender, DisplayEventReceiver) {
+    let channel = Arc::new(Channel {
+        state: Mutex::new(ChannelState {
+            queue: VecDeque::with_capacity(config.capacity),
+            sender_connected: true,
+            receiver_connected: true,
+            waker: None,
+        }),
+        not_full: Condvar::new(),
+        not_empty: Condvar::new(),
+        config,
+    });
+
+    (
+        DisplayEventSender(channel.clone()),
+        DisplayEventReceiver(channel),
+    )
+}
+
+pub struct DisplayEventSender(Arc<Channel>);
+
+impl DisplayEventSender {
+    /// Sends an event and wakes the task awaiting it.
+    ///
+    /// *Blocks while the channel is full, unless its overflow policy coalesces events.*
+    pub fn send(&self, event: DisplayEvent) -> Result<(), DisplayDisconnected> {
+        let channel = &self.0;
+        let mut state = channel.state.lock().unwrap();
+
+        loop {
+            if !state.receiver_connected {
+                return Err(DisplayDisconnected(event));
+            }
+
+            if state.queue.len() < channel.config.capacity.max(1) {
+                break;
+            }
+
+            match channel.config.overflow {
+                ChannelOverflow::Block => state = channel.not_full.wait(state).unwrap(),
+                ChannelOverflow::Coalesce => {
+                    if let Some(index) = state.queue.iter().position(|e| e.coalesces_with(&event)) {
+                        state.queue.remove(index);
+                    }
+
+                    break;
+                }
+            }
+        }
+
+        state.queue.push_back(event);
+        channel.not_empty.notify_one();
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for DisplayEventSender {
+    fn drop(&mut self) {
+        // Disconnected before waking, otherwise the woken task could still find the channel empty
+        let mut state = self.0.state.lock().unwrap();
+        state.sender_connected = false;
+        self.0.not_empty.notify_all();
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+pub struct DisplayEventReceiver(Arc<Channel>);
+
+impl DisplayEventReceiver {
+    /// Blocks until an event is received.
+    pub fn recv(&self) -> Result<DisplayEvent, RecvError> {
+        let mut state = self.0.state.lock().unwrap();
+
+        loop {
+            match self.receive(&mut state) {
+                Ok(event) => return Ok(event),
+                Err(TryRecvError::Disconnected) => return Err(RecvError),
+                Err(TryRecvError::Empty) => state = self.0.not_empty.wait(state).unwrap(),
+            }
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<DisplayEvent, TryRecvError> {
+        self.receive(&mut self.0.state.lock().unwrap())
+    }
+
+    fn receive(&self, state: &mut ChannelState) -> Result<DisplayEvent, TryRecvError> {
+        match state.queue.pop_front() {
+            Some(event) => {
+                self.0.not_full.notify_one();
+                Ok(event)
+            }
+            None if state.sender_connected => Err(TryRecvError::Empty),
+            None => Err(TryRecvError::Disconnected),
+        }
+    }
+
+    /// *Returns a future resolving to the next event,
+    /// or [`DisplayEvent::Disconnected`] once the sender is dropped.*
+    pub fn next_event(&self) -> NextDisplayEvent<'_> {
+        NextDisplayEvent(self)
+    }
+}
+
+impl Drop for DisplayEventReceiver {
+    fn drop(&mut self) {
+        self.0.state.lock().unwrap().receiver_connected = false;
+        self.0.not_full.notify_all();
+    }
+}
+
+pub struct NextDisplayEvent<'a>(&'a DisplayEventReceiver);
+
+impl Future for NextDisplayEvent<'_> {
+    type Output = DisplayEvent;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.0 .0.state.lock().unwrap();
+
+        match self.0.receive(&mut state) {
+            Ok(event) => Poll::Ready(event),
+            Err(TryRecvError::Disconnected) => Poll::Ready(DisplayEvent::Disconnected),
+            // Registered while the channel is locked, so the next event sent wakes the task
+            Err(TryRecvError::Empty) => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::event_loop::{
+        display_event_channel_with, ChannelOverflow, DisplayCommand, DisplayEvent,
+        DisplayEventChannelConfig, DisplayEventProxy,
+    };
+    use crate::window::{PhysicalSize, WindowEvent};
+    use std::sync::{Arc, Mutex};
+
+    /// Three resizes and a repaint are sent to a full coalescing channel of two events.
+    /// Only the newest resize should be kept, without blocking the sender.
+    #[test]
+    fn test_coalescing_channel() {
+        let (sender, receiver) = display_event_channel_with(DisplayEventChannelConfig {
+            capacity: 2,
+            overflow: ChannelOverflow::Coalesce,
+        });
+        let resized = |width| {
+            DisplayEvent::WindowEvent(WindowEvent::Resized(PhysicalSize { width, height: 1 }))
+        };
+
+        sender.send(DisplayEvent::NextFrame).unwrap();
+        for width in 1..=3 {
+            sender.send(resized(width)).unwrap();
+        }
+        sender.send(DisplayEvent::Repaint).unwrap();
+
+        assert!(matches!(receiver.try_recv(), Ok(DisplayEvent::NextFrame)));
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(DisplayEvent::WindowEvent(WindowEvent::Resized(
+                PhysicalSize { width: 3, .. }
+            )))
+        ));
+        assert!(matches!(receiver.try_recv(), Ok(DisplayEvent::Repaint)));
+
+        drop(receiver);
+        assert!(sender.send(DisplayEvent::Repaint).is_err());
+    }
+
+    /// A proxy is cloned to another thread, which sends a string to its window.
+    /// The command should target the window and carry the string.
+    #[test]
+    fn test_event_proxy_send() {
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let sent = commands.clone();
+        let proxy = DisplayEventProxy::new(7, Arc::new(move |cmd| sent.lock().unwrap().push(cmd)));
+
+        let thread_proxy = proxy.clone();
+        std::thread::spawn(move || thread_proxy.send("ping".to_owned()))
+            .join()
+            .unwrap();
+
+        let commands = commands.lock().unwrap();
+        let [DisplayCommand::User { window: 7, event }] = commands.as_slice() else {
+            panic!("Unexpected commands: {:?}", commands);
+        };
+        assert_eq!(event.downcast_ref::<String>().unwrap(), "ping");
+        assert!(!event.is::<u32>());
+    }
+}
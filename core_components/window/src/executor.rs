@@ -0,0 +1,250 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::event_loop::{CommandProxy, DisplayCommand};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::Thread;
+
+/// A future driving an application, which does not have to be [`Send`].
+pub type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Runs a future to completion on the current thread, parking it while the future is pending.
+///
+/// ***Must not*** be called on the web, where the main thread cannot block.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut context = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+
+        std::thread::park();
+    }
+}
+
+struct TaskWaker {
+    woken: AtomicBool,
+    /// Wakes the event loop, so the task is polled even while the loop waits for events.
+    command_proxy: Option<CommandProxy>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        // The event loop is only woken once until the task is polled
+        if !self.woken.swap(true, Ordering::AcqRel) {
+            if let Some(command_proxy) = &self.command_proxy {
+                command_proxy(DisplayCommand::Wake);
+            }
+        }
+    }
+}
+
+struct LocalTask {
+    future: LocalFuture,
+    woken: Arc<TaskWaker>,
+}
+
+/// Runs futures on the event loop thread, polling them when woken.
+///
+/// The event loop calls [`LocalExecutor::run_woken`] after dispatching events,
+/// the futures are never polled in between. Waking a task sends [`DisplayCommand::Wake`]
+/// through the command proxy, so a waiting event loop dispatches it and polls the task.
+#[derive(Default)]
+pub struct LocalExecutor {
+    tasks: Vec<LocalTask>,
+    command_proxy: Option<CommandProxy>,
+}
+
+impl LocalExecutor {
+    /// Creates an executor which does not wake any event loop,
+    /// its tasks are only polled when [`LocalExecutor::run_woken`] is called.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an executor which wakes the event loop through the proxy whenever a task is woken.
+    pub fn with_command_proxy(command_proxy: CommandProxy) -> Self {
+        Self {
+            tasks: Vec::new(),
+            command_proxy: Some(command_proxy),
+        }
+    }
+
+    /// Adds a task, polled on the next [`LocalExecutor::run_woken`].
+    pub fn spawn(&mut self, future: LocalFuture) {
+        self.tasks.push(LocalTask {
+            future,
+            woken: Arc::new(TaskWaker {
+                woken: AtomicBool::new(true),
+                command_proxy: self.command_proxy.clone(),
+            }),
+        });
+    }
+
+    /// Polls the tasks woken since the last call, dropping the completed ones.
+    pub fn run_woken(&mut self) {
+        self.tasks.retain_mut(|task| {
+            if !task.woken.woken.swap(false, Ordering::AcqRel) {
+                return true;
+            }
+
+            let waker = Waker::from(task.woken.clone());
+            let mut context = Context::from_waker(&waker);
+            task.future.as_mut().poll(&mut context).is_pending()
+        });
+    }
+
+    /// *Returns the number of tasks which have not completed yet.*
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::event_loop::{display_event_channel, CommandProxy, DisplayCommand, DisplayEvent};
+    use crate::executor::{block_on, LocalExecutor};
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+
+    /// A task waits for two events sent to its channel.
+    /// It should only be polled again once an event wakes it and complete after the second one.
+    #[test]
+    fn test_local_executor_polls_woken() {
+        let (sender, receiver) = display_event_channel(4);
+        let received = Rc::new(Cell::new(0));
+
+        let mut executor = LocalExecutor::new();
+        let task_received = received.clone();
+        executor.spawn(Box::pin(async move {
+            for _ in 0..2 {
+                receiver.next_event().await;
+                task_received.set(task_received.get() + 1);
+            }
+        }));
+
+        executor.run_woken();
+        executor.run_woken();
+        assert_eq!(received.get(), 0);
+
+        sender.send(DisplayEvent::NextFrame).unwrap();
+        executor.run_woken();
+        assert_eq!(received.get(), 1);
+        assert_eq!(executor.len(), 1);
+
+        sender.send(DisplayEvent::Repaint).unwrap();
+        executor.run_woken();
+        assert_eq!(received.get(), 2);
+        assert!(executor.is_empty());
+    }
+
+    /// A task waits for an event sent from another thread to an executor with a command proxy.
+    /// The sender should wake the event loop exactly once, before the task is polled again.
+    #[test]
+    fn test_local_executor_wakes_event_loop() {
+        let (sender, receiver) = display_event_channel(4);
+        let (command_sender, commands) = std::sync::mpsc::channel();
+        let command_sender = Mutex::new(command_sender);
+        let proxy: CommandProxy = Arc::new(move |command| {
+            command_sender.lock().unwrap().send(command).unwrap();
+        });
+
+        let mut executor = LocalExecutor::with_command_proxy(proxy);
+        executor.spawn(Box::pin(async move {
+            receiver.next_event().await;
+        }));
+        executor.run_woken();
+        assert!(commands.try_recv().is_err());
+
+        std::thread::spawn(move || {
+            sender.send(DisplayEvent::NextFrame).unwrap();
+            sender.send(DisplayEvent::Repaint).unwrap();
+        })
+        .join()
+        .unwrap();
+
+        assert!(matches!(commands.try_recv(), Ok(DisplayCommand::Wake)));
+        assert!(commands.try_recv().is_err());
+
+        executor.run_woken();
+        assert!(executor.is_empty());
+    }
+
+    /// A worker blocks on events sent from another thread.
+    /// It should receive them in order and the disconnection once the sender is dropped.
+    #[test]
+    fn test_block_on_events() {
+        let (sender, receiver) = display_event_channel(1);
+
+        let worker = std::thread::spawn(move || {
+            block_on(async move {
+                let mut events = Vec::new();
+                loop {
+                    match receiver.next_event().await {
+                        DisplayEvent::Disconnected => return events,
+                        event => events.push(event),
+                    }
+                }
+            })
+        });
+
+        sender.send(DisplayEvent::NextFrame).unwrap();
+        sender.send(DisplayEvent::Repaint).unwrap();
+        drop(sender);
+
+        let events = worker.join().unwrap();
+        assert!(matches!(
+            events.as_slice(),
+            [DisplayEvent::NextFrame, DisplayEvent::Repaint]
+        ));
+    }
+}
@@ -23,4 +23,8 @@
  */
 
 pub mod event_loop;
+pub mod executor;
+pub mod keyboard;
+pub mod mouse;
+pub mod touch;
 pub mod window;
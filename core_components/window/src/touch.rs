@@ -0,0 +1,44 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    /// The touch was interrupted by the system, for example by a gesture of the platform.
+    Cancelled,
+}
+
+/// A finger on a touch screen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Touch {
+    /// Identifies the finger from the start until the end of the touch,
+    /// the identifier may be reused by later touches.
+    pub id: u64,
+    pub phase: TouchPhase,
+    /// The position in physical pixels from the top left corner of the window.
+    pub x: f64,
+    pub y: f64,
+}
@@ -22,10 +22,16 @@
  * SOFTWARE.
  */
 
-use crate::event_loop::{DisplayCommand, DisplayEvent, EventLoop, EventLoopWindowFactory};
+use crate::event_loop::{
+    CommandProxy, DisplayEvent, DisplayEventProxy, DisplayEventReceiver, EventLoop,
+    EventLoopWindowFactory, NextDisplayEvent,
+};
+use crate::keyboard::Key;
+use crate::mouse::{MouseButton, MouseScrollDelta};
+use crate::touch::Touch;
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::sync::mpsc::Receiver;
+use std::path::PathBuf;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Hash)]
 pub struct PhysicalSize<S> {
@@ -33,10 +39,93 @@ pub struct PhysicalSize<S> {
     pub height: S,
 }
 
-#[derive(Copy, Clone, Debug)]
+/// A size in logical (DPI-independent) units.
+///
+/// Multiply by the scale factor to get the size in physical pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct LogicalSize<S> {
+    pub width: S,
+    pub height: S,
+}
+
+impl PhysicalSize<u32> {
+    /// Converts this size to logical units using the given scale factor.
+    pub fn to_logical(self, scale_factor: f64) -> LogicalSize<f64> {
+        LogicalSize {
+            width: self.width as f64 / scale_factor,
+            height: self.height as f64 / scale_factor,
+        }
+    }
+}
+
+impl LogicalSize<f64> {
+    /// Converts this size to physical pixels using the given scale factor, rounding to the nearest pixel.
+    pub fn to_physical(self, scale_factor: f64) -> PhysicalSize<u32> {
+        PhysicalSize {
+            width: (self.width * scale_factor).round() as u32,
+            height: (self.height * scale_factor).round() as u32,
+        }
+    }
+}
+
+/// Text being composed by an input method, not yet committed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Preedit {
+    pub text: String,
+    /// The byte range of the text selected by the input method, `None` hides the cursor.
+    pub cursor: Option<(usize, usize)>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TextInputEvent {
+    /// Text was typed or committed by the input method.
+    ///
+    /// *Control characters such as backspace are only reported as keyboard input.*
+    Commit(String),
+    /// The composed text has changed, an empty preedit ends the composition.
+    Preedit(Preedit),
+    /// An input method has been enabled, following text is sent as preedits and commits.
+    ImeEnabled,
+    ImeDisabled,
+}
+
+#[derive(Clone, Debug)]
 pub enum WindowEvent {
     CloseRequested,
     Resized(PhysicalSize<u32>),
+    /// The DPI scale factor of the window has changed, for example
+    /// because it was moved to a monitor with a different pixel density.
+    ScaleFactorChanged {
+        scale_factor: f64,
+        new_size: PhysicalSize<u32>,
+    },
+    /// A key was pressed or released while the window was focused.
+    KeyboardInput {
+        key: Key,
+        pressed: bool,
+    },
+    /// The cursor moved within the window, in physical pixels from the top left corner.
+    CursorMoved {
+        x: f64,
+        y: f64,
+    },
+    CursorLeft,
+    /// A mouse button was pressed or released while the cursor was over the window.
+    MouseInput {
+        button: MouseButton,
+        pressed: bool,
+    },
+    MouseWheel(MouseScrollDelta),
+    /// A finger touched, moved on or left the touch screen.
+    Touch(Touch),
+    /// A file was dropped on the window, one event per file.
+    FileDropped(PathBuf),
+    /// A file is dragged over the window, one event per file.
+    FileHovered(PathBuf),
+    /// The hovered files left the window without being dropped.
+    FileHoverCancelled,
+    /// Text was entered while the window was focused.
+    TextInput(TextInputEvent),
     Unknown,
 }
 
@@ -55,17 +144,38 @@ pub trait Window {
         ELW: EventLoopWindowFactory<EL, LoopType = Self::LoopType>,
     >(
         event_loop: &ELW,
-        event_receiver: Receiver<DisplayEvent>,
-        command_proxy: Box<dyn Fn(DisplayCommand) + Send>,
+        event_receiver: DisplayEventReceiver,
+        command_proxy: CommandProxy,
     ) -> Self;
 
+    /// Blocks until the next event of this window.
+    ///
+    /// ***Must not*** be called on the event loop thread, use [`Window::next_event`] instead.
     fn receive_event(&self) -> DisplayEvent;
 
+    /// *Returns a future resolving to the next event of this window.*
+    fn next_event(&self) -> NextDisplayEvent<'_>;
+
     fn request_repaint(&self);
 
+    /// Creates a handle other threads can use to send events to this window.
+    fn create_event_proxy(&self) -> DisplayEventProxy;
+
     fn get_id(&self) -> Self::IdType;
 
     fn get_size(&self) -> PhysicalSize<Self::SizeType>;
 
+    /// Returns the ratio between physical pixels and logical units of this window.
+    fn get_scale_factor(&self) -> f64;
+
+    /// Allows input methods to compose text in this window, disabled by default.
+    ///
+    /// *Should only be enabled while a text field is focused, input methods may capture key presses.*
+    fn set_ime_allowed(&self, allowed: bool);
+
+    /// Moves the candidate window of the input method near the given position in logical units,
+    /// usually the text cursor.
+    fn set_ime_position(&self, x: f64, y: f64);
+
     fn get_backing_window(&self) -> &Self::BackingType;
 }
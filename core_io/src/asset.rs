@@ -0,0 +1,473 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Typed assets loaded from a pack.
+//!
+//! An [`AssetManager`] loads entries through a [`PackLoader`] and turns them into assets using
+//! an [`AssetImporter`]. An asset that fails to import does not bring the game down: its handle
+//! ends up in the [`AssetState::Failed`] state, the importer's placeholder is used in its place
+//! and the failure is recorded in the session-wide [`AssetFailureLog`].
+//!
+//! Importing, e.g. decoding images or processing meshes, runs on the thread updating the manager
+//! unless the manager is given a [`JobPool`] with [`AssetManager::with_jobs`].
+
+use crate::jobs::{JobHandle, JobPool};
+use crate::pack::{PackError, PackLoader, PendingAsset};
+use log::error;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug)]
+pub enum AssetImportError {
+    /// The asset does not exist.
+    Missing(String),
+    Io {
+        path: String,
+        error: io::Error,
+    },
+    /// The data of the asset is malformed or corrupted.
+    Decode {
+        path: String,
+        reason: String,
+    },
+    /// The asset was created by a newer version of its format than the importer supports.
+    VersionMismatch {
+        path: String,
+        found: u32,
+        supported: u32,
+    },
+}
+
+impl AssetImportError {
+    /// Converts an error of reading the asset from a pack.
+    pub fn from_pack(path: &str, error: PackError) -> Self {
+        match error {
+            PackError::Io(error) => AssetImportError::Io {
+                path: path.to_owned(),
+                error,
+            },
+            PackError::NotFound(_) => AssetImportError::Missing(path.to_owned()),
+            PackError::Malformed(reason) => AssetImportError::Decode {
+                path: path.to_owned(),
+                reason: reason.to_owned(),
+            },
+            PackError::Corrupted(_) => AssetImportError::Decode {
+                path: path.to_owned(),
+                reason: "checksum mismatch".to_owned(),
+            },
+        }
+    }
+
+    /// *Returns the path of the asset that failed to import.*
+    pub fn get_path(&self) -> &str {
+        match self {
+            AssetImportError::Missing(path)
+            | AssetImportError::Io { path, .. }
+            | AssetImportError::Decode { path, .. }
+            | AssetImportError::VersionMismatch { path, .. } => path,
+        }
+    }
+}
+
+impl Display for AssetImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetImportError::Missing(path) => write!(f, "{} is missing", path),
+            AssetImportError::Io { path, error } => write!(f, "{}: {}", path, error),
+            AssetImportError::Decode { path, reason } => {
+                write!(f, "{} could not be decoded: {}", path, reason)
+            }
+            AssetImportError::VersionMismatch {
+                path,
+                found,
+                supported,
+            } => write!(
+                f,
+                "{} has version {}, the newest supported version is {}",
+                path, found, supported
+            ),
+        }
+    }
+}
+
+impl Error for AssetImportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AssetImportError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Turns the raw data of an entry into an asset.
+pub trait AssetImporter {
+    type Asset;
+
+    fn import(&self, path: &str, bytes: &[u8]) -> Result<Self::Asset, AssetImportError>;
+
+    /// Creates the asset used in place of assets that failed to import,
+    /// for example a checkerboard texture.
+    fn placeholder(&self) -> Self::Asset;
+}
+
+/// A failed import recorded in an [`AssetFailureLog`].
+#[derive(Clone, Debug)]
+pub struct AssetFailure {
+    /// The name of the asset type, as returned by [`std::any::type_name`].
+    pub asset_type: &'static str,
+    pub error: Arc<AssetImportError>,
+}
+
+/// All failed imports of this session, shared between asset managers.
+#[derive(Clone, Debug, Default)]
+pub struct AssetFailureLog(Arc<Mutex<Vec<AssetFailure>>>);
+
+impl AssetFailureLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, failure: AssetFailure) {
+        error!("Failed to import an asset: {}", failure.error);
+        self.0.lock().unwrap().push(failure);
+    }
+
+    pub fn failures(&self) -> Vec<AssetFailure> {
+        self.0.lock().unwrap().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// *Returns one line per failure, suitable for an error summary panel.*
+    pub fn summary(&self) -> Vec<String> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|failure| format!("[{}] {}", failure.asset_type, failure.error))
+            .collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct AssetHandle(usize);
+
+pub enum AssetState<T> {
+    Loading,
+    Loaded(Arc<T>),
+    Failed(Arc<AssetImportError>),
+}
+
+enum PendingImport<T> {
+    Reading(PendingAsset),
+    Importing(JobHandle<Result<T, AssetImportError>>),
+}
+
+struct AssetSlot<T> {
+    path: String,
+    state: AssetState<T>,
+    pending: Option<PendingImport<T>>,
+}
+
+/// Spawns the import of an entry on a job pool.
+type ImportSpawner<T> = Box<dyn Fn(String, Vec<u8>) -> JobHandle<Result<T, AssetImportError>>>;
+
+/// Loads assets of a single type from a pack.
+///
+/// Loading is asynchronous, call [`AssetManager::update`] regularly to import loaded entries.
+pub struct AssetManager<I: AssetImporter> {
+    loader: Arc<PackLoader>,
+    importer: Arc<I>,
+    import_spawner: Option<ImportSpawner<I::Asset>>,
+    failures: AssetFailureLog,
+    placeholder: Option<Arc<I::Asset>>,
+    slots: Vec<AssetSlot<I::Asset>>,
+    handles: HashMap<String, AssetHandle>,
+}
+
+impl<I: AssetImporter> AssetManager<I> {
+    pub fn new(loader: Arc<PackLoader>, importer: I, failures: AssetFailureLog) -> Self {
+        Self {
+            loader,
+            importer: Arc::new(importer),
+            import_spawner: None,
+            failures,
+            placeholder: None,
+            slots: Vec::new(),
+            handles: HashMap::new(),
+        }
+    }
+
+    /// Starts loading the asset, or returns the existing handle if it was already requested.
+    pub fn load(&mut self, path: &str) -> AssetHandle {
+        if let Some(handle) = self.handles.get(path) {
+            return *handle;
+        }
+
+        let handle = AssetHandle(self.slots.len());
+        self.slots.push(AssetSlot {
+            path: path.to_owned(),
+            state: AssetState::Loading,
+            pending: Some(PendingImport::Reading(self.loader.load(path))),
+        });
+        self.handles.insert(path.to_owned(), handle);
+        handle
+    }
+
+    /// Imports all entries that finished loading since the last update,
+    /// or starts importing them if the manager has a job pool.
+    pub fn update(&mut self) {
+        for i in 0..self.slots.len() {
+            let slot = &mut self.slots[i];
+
+            match slot.pending.as_mut() {
+                Some(PendingImport::Reading(pending)) => {
+                    let Some(result) = pending.poll_result() else {
+                        continue;
+                    };
+
+                    match (&self.import_spawner, result) {
+                        (Some(spawn_import), Ok(bytes)) => {
+                            let job = spawn_import(slot.path.clone(), bytes);
+                            slot.pending = Some(PendingImport::Importing(job));
+                        }
+                        (_, result) => {
+                            slot.pending = None;
+                            let imported = self.import(&self.slots[i].path, result);
+                            self.finish_import(AssetHandle(i), imported);
+                        }
+                    }
+                }
+                Some(PendingImport::Importing(job)) => {
+                    if let Some(imported) = job.poll_result() {
+                        slot.pending = None;
+                        self.finish_import(AssetHandle(i), imported);
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Blocks until the asset has been loaded and imported.
+    pub fn wait(&mut self, handle: AssetHandle) -> &AssetState<I::Asset> {
+        let imported = match self.slots[handle.0].pending.take() {
+            Some(PendingImport::Reading(pending)) => {
+                Some(self.import(&self.slots[handle.0].path, pending.wait()))
+            }
+            Some(PendingImport::Importing(job)) => Some(job.wait()),
+            None => None,
+        };
+
+        if let Some(imported) = imported {
+            self.finish_import(handle, imported);
+        }
+
+        self.state(handle)
+    }
+
+    fn import(
+        &self,
+        path: &str,
+        result: Result<Vec<u8>, PackError>,
+    ) -> Result<I::Asset, AssetImportError> {
+        result
+            .map_err(|error| AssetImportError::from_pack(path, error))
+            .and_then(|bytes| self.importer.import(path, &bytes))
+    }
+
+    fn finish_import(&mut self, handle: AssetHandle, imported: Result<I::Asset, AssetImportError>) {
+        let slot = &mut self.slots[handle.0];
+
+        slot.state = match imported {
+            Ok(asset) => AssetState::Loaded(Arc::new(asset)),
+            Err(error) => {
+                let error = Arc::new(error);
+                self.failures.record(AssetFailure {
+                    asset_type: std::any::type_name::<I::Asset>(),
+                    error: error.clone(),
+                });
+                AssetState::Failed(error)
+            }
+        };
+    }
+
+    pub fn state(&self, handle: AssetHandle) -> &AssetState<I::Asset> {
+        &self.slots[handle.0].state
+    }
+
+    /// *Returns the asset, the placeholder if it failed to import,
+    /// or `None` if it is still loading.*
+    pub fn get(&mut self, handle: AssetHandle) -> Option<Arc<I::Asset>> {
+        match &self.slots[handle.0].state {
+            AssetState::Loading => None,
+            AssetState::Loaded(asset) => Some(asset.clone()),
+            AssetState::Failed(_) => Some(self.get_placeholder()),
+        }
+    }
+
+    pub fn get_placeholder(&mut self) -> Arc<I::Asset> {
+        let importer = &self.importer;
+        self.placeholder
+            .get_or_insert_with(|| Arc::new(importer.placeholder()))
+            .clone()
+    }
+
+    pub fn get_failures(&self) -> &AssetFailureLog {
+        &self.failures
+    }
+}
+
+impl<I: AssetImporter + Send + Sync + 'static> AssetManager<I>
+where
+    I::Asset: Send + 'static,
+{
+    /// Imports loaded entries on the job pool instead of the thread updating the manager.
+    pub fn with_jobs(mut self, jobs: JobPool) -> Self {
+        let importer = self.importer.clone();
+
+        self.import_spawner = Some(Box::new(move |path, bytes| {
+            let importer = importer.clone();
+            jobs.spawn_with_result(move || importer.import(&path, &bytes))
+        }));
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::asset::{
+        AssetFailureLog, AssetImportError, AssetImporter, AssetManager, AssetState,
+    };
+    use crate::jobs::JobPool;
+    use crate::pack::{PackCompression, PackLoader, PackReader, PackWriter};
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    /// Imports versioned text, the first byte being the version.
+    struct TextImporter;
+
+    impl AssetImporter for TextImporter {
+        type Asset = String;
+
+        fn import(&self, path: &str, bytes: &[u8]) -> Result<Self::Asset, AssetImportError> {
+            match bytes.split_first() {
+                Some((1, text)) => {
+                    String::from_utf8(text.to_vec()).map_err(|err| AssetImportError::Decode {
+                        path: path.to_owned(),
+                        reason: err.to_string(),
+                    })
+                }
+                Some((version, _)) => Err(AssetImportError::VersionMismatch {
+                    path: path.to_owned(),
+                    found: *version as u32,
+                    supported: 1,
+                }),
+                None => Err(AssetImportError::Decode {
+                    path: path.to_owned(),
+                    reason: "empty".to_owned(),
+                }),
+            }
+        }
+
+        fn placeholder(&self) -> Self::Asset {
+            "placeholder".to_owned()
+        }
+    }
+
+    fn create_manager(failures: AssetFailureLog) -> AssetManager<TextImporter> {
+        let mut writer = PackWriter::new(Cursor::new(Vec::new())).unwrap();
+        writer
+            .add("hello.txt", b"\x01hello", PackCompression::None)
+            .unwrap();
+        writer
+            .add("future.txt", b"\x02hello", PackCompression::None)
+            .unwrap();
+        let pack = writer.finish().unwrap().into_inner();
+
+        let loader = PackLoader::new(PackReader::from_bytes(pack).unwrap());
+        AssetManager::new(Arc::new(loader), TextImporter, failures)
+    }
+
+    /// A valid asset is loaded.
+    /// It should be imported, and loading it again should return the same handle.
+    #[test]
+    fn test_asset_loaded() {
+        let mut manager = create_manager(AssetFailureLog::new());
+        let handle = manager.load("hello.txt");
+
+        assert!(matches!(manager.wait(handle), AssetState::Loaded(_)));
+        assert_eq!(manager.get(handle).unwrap().as_str(), "hello");
+        assert_eq!(manager.load("hello.txt"), handle);
+    }
+
+    /// Assets are imported on a job pool, one of them waited on and the other one updated.
+    /// Both should be imported just like without the pool.
+    #[test]
+    fn test_asset_imported_by_jobs() {
+        let mut manager = create_manager(AssetFailureLog::new()).with_jobs(JobPool::new(1));
+        let hello = manager.load("hello.txt");
+        let future = manager.load("future.txt");
+
+        assert!(matches!(manager.wait(hello), AssetState::Loaded(_)));
+
+        while matches!(manager.state(future), AssetState::Loading) {
+            manager.update();
+        }
+        assert!(matches!(manager.state(future), AssetState::Failed(_)));
+    }
+
+    /// A missing asset and an asset of a newer version are loaded.
+    /// Both should fail with the matching error, be replaced by the placeholder
+    /// and be recorded in the failure log.
+    #[test]
+    fn test_asset_failures() {
+        let failures = AssetFailureLog::new();
+        let mut manager = create_manager(failures.clone());
+        let missing = manager.load("missing.txt");
+        let future = manager.load("future.txt");
+
+        assert!(matches!(
+            manager.wait(missing),
+            AssetState::Failed(error) if matches!(**error, AssetImportError::Missing(_))
+        ));
+        assert!(matches!(
+            manager.wait(future),
+            AssetState::Failed(error) if matches!(**error, AssetImportError::VersionMismatch { found: 2, .. })
+        ));
+        assert_eq!(manager.get(missing).unwrap().as_str(), "placeholder");
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures.failures()[0].error.get_path(), "missing.txt");
+    }
+}
@@ -0,0 +1,343 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! A content-addressed cache for cooked assets.
+//!
+//! Cooked assets are stored under a [`CacheKey`] derived from everything that affects the cook
+//! output: the source data, the cook settings and the cooker version. Since the key does not
+//! depend on where the asset came from, the same cache directory can be shared by several
+//! projects, and a [`CacheBackend`] backed by a remote server can be shared by a whole team.
+
+use crate::package::to_hex;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The address of a cooked asset, the SHA-256 hash of all inputs of the cook.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CacheKey(pub [u8; 32]);
+
+impl CacheKey {
+    /// Creates a key for the output of the cooker with the given name and version.
+    ///
+    /// *The version should be bumped whenever the output of the cooker changes.*
+    pub fn builder(cooker: &str, version: u32) -> CacheKeyBuilder {
+        let mut builder = CacheKeyBuilder(Sha256::new());
+        builder.write_field(b"cooker", cooker.as_bytes());
+        builder.write_field(b"version", &version.to_le_bytes());
+        builder
+    }
+
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.0)
+    }
+}
+
+impl Display for CacheKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+/// Hashes the inputs of a cook into a [`CacheKey`].
+///
+/// Every input is length-prefixed, so different sequences of inputs never produce the same key.
+pub struct CacheKeyBuilder(Sha256);
+
+impl CacheKeyBuilder {
+    fn write_field(&mut self, tag: &[u8], value: &[u8]) {
+        for part in [tag, value] {
+            self.0.update((part.len() as u64).to_le_bytes());
+            self.0.update(part);
+        }
+    }
+
+    /// Adds the contents of a source file.
+    pub fn source(mut self, data: &[u8]) -> Self {
+        self.write_field(b"source", data);
+        self
+    }
+
+    /// Adds a cook setting.
+    pub fn setting(mut self, name: &str, value: &str) -> Self {
+        self.write_field(name.as_bytes(), value.as_bytes());
+        self
+    }
+
+    pub fn build(self) -> CacheKey {
+        CacheKey(self.0.finalize().into())
+    }
+}
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// The stored data does not match its checksum.
+    Corrupted(CacheKey),
+    /// An error of a custom backend, such as a remote cache server being unreachable.
+    Backend(Box<dyn Error + Send + Sync>),
+}
+
+impl Display for CacheError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            CacheError::Corrupted(key) => write!(f, "cache entry {} is corrupted", key),
+            CacheError::Backend(error) => write!(f, "cache backend error: {}", error),
+        }
+    }
+}
+
+impl Error for CacheError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CacheError::Io { error, .. } => Some(error),
+            CacheError::Backend(error) => Some(error.as_ref()),
+            CacheError::Corrupted(_) => None,
+        }
+    }
+}
+
+/// A storage for cooked assets.
+pub trait CacheBackend {
+    /// *Returns `None` if there is no entry for the key.*
+    fn get(&self, key: &CacheKey) -> Result<Option<Vec<u8>>, CacheError>;
+
+    fn put(&self, key: &CacheKey, data: &[u8]) -> Result<(), CacheError>;
+}
+
+/// A cache stored in a local directory, which may be shared by multiple projects.
+///
+/// Entries are stored in files named after their key, prefixed with a SHA-256 checksum
+/// of the data to detect corruption.
+pub struct LocalCache {
+    root: PathBuf,
+}
+
+impl LocalCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        let hex = key.to_hex();
+        self.root.join(&hex[..2]).join(&hex[2..])
+    }
+}
+
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> CacheError + '_ {
+    move |error| CacheError::Io {
+        path: path.to_path_buf(),
+        error,
+    }
+}
+
+impl CacheBackend for LocalCache {
+    fn get(&self, key: &CacheKey) -> Result<Option<Vec<u8>>, CacheError> {
+        let path = self.entry_path(key);
+        let mut entry = match fs::read(&path) {
+            Ok(entry) => entry,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(io_error(&path)(error)),
+        };
+
+        if entry.len() < 32 || Sha256::digest(&entry[32..]).as_slice() != &entry[..32] {
+            return Err(CacheError::Corrupted(*key));
+        }
+
+        entry.drain(..32);
+        Ok(Some(entry))
+    }
+
+    fn put(&self, key: &CacheKey, data: &[u8]) -> Result<(), CacheError> {
+        let path = self.entry_path(key);
+        let dir = path.parent().unwrap();
+        fs::create_dir_all(dir).map_err(io_error(dir))?;
+
+        let mut entry = Vec::with_capacity(32 + data.len());
+        entry.extend_from_slice(&Sha256::digest(data));
+        entry.extend_from_slice(data);
+
+        // Written to a temporary file first, so other processes sharing
+        // the cache never observe a partially written entry.
+        let temp_path = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&temp_path, &entry).map_err(io_error(&temp_path))?;
+        fs::rename(&temp_path, &path).map_err(io_error(&path))
+    }
+}
+
+/// A cache looking up cooked assets in a chain of backends, from the fastest to the slowest,
+/// e.g. a [`LocalCache`] followed by a remote cache server.
+///
+/// Entries found in a slower backend are copied into all faster ones.
+pub struct AssetCache {
+    backends: Vec<CacheTier>,
+}
+
+struct CacheTier {
+    backend: Box<dyn CacheBackend>,
+    writable: bool,
+}
+
+impl AssetCache {
+    pub fn new(local: LocalCache) -> Self {
+        Self {
+            backends: vec![CacheTier {
+                backend: Box::new(local),
+                writable: true,
+            }],
+        }
+    }
+
+    /// Appends a slower backend to the lookup chain.
+    ///
+    /// Newly cooked assets are only uploaded to the backend if `writable` is set,
+    /// which allows e.g. only build machines to populate a shared remote cache.
+    pub fn with_backend(mut self, backend: impl CacheBackend + 'static, writable: bool) -> Self {
+        self.backends.push(CacheTier {
+            backend: Box::new(backend),
+            writable,
+        });
+        self
+    }
+
+    /// Looks up an entry, copying it into the faster backends if found in a slower one.
+    pub fn get(&self, key: &CacheKey) -> Result<Option<Vec<u8>>, CacheError> {
+        for (i, tier) in self.backends.iter().enumerate() {
+            if let Some(data) = tier.backend.get(key)? {
+                for faster in &self.backends[..i] {
+                    faster.backend.put(key, &data)?;
+                }
+
+                return Ok(Some(data));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Stores an entry in all writable backends.
+    pub fn put(&self, key: &CacheKey, data: &[u8]) -> Result<(), CacheError> {
+        self.backends
+            .iter()
+            .filter(|tier| tier.writable)
+            .try_for_each(|tier| tier.backend.put(key, data))
+    }
+
+    /// Returns the cached asset, or cooks and caches it if it is not present.
+    ///
+    /// *Corrupted entries are cooked again and replaced.*
+    pub fn get_or_cook<E: From<CacheError>>(
+        &self,
+        key: &CacheKey,
+        cook: impl FnOnce() -> Result<Vec<u8>, E>,
+    ) -> Result<Vec<u8>, E> {
+        match self.get(key) {
+            Ok(Some(data)) => return Ok(data),
+            Ok(None) | Err(CacheError::Corrupted(_)) => {}
+            Err(error) => return Err(error.into()),
+        }
+
+        let data = cook()?;
+        self.put(key, &data)?;
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cache::{AssetCache, CacheBackend, CacheError, CacheKey, LocalCache};
+    use std::cell::Cell;
+    use std::fs;
+
+    fn temp_cache(name: &str) -> LocalCache {
+        let root = std::env::temp_dir().join(format!("pluto_io_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        LocalCache::new(root)
+    }
+
+    /// Keys are built from the same source with different settings.
+    /// Only identical inputs should produce identical keys.
+    #[test]
+    fn test_cache_key() {
+        let key = |quality| {
+            CacheKey::builder("texture", 1)
+                .source(b"pixels")
+                .setting("quality", quality)
+                .build()
+        };
+
+        assert_eq!(key("high"), key("high"));
+        assert_ne!(key("high"), key("low"));
+        assert_ne!(
+            CacheKey::builder("texture", 1).source(b"ab").build(),
+            CacheKey::builder("texture", 1)
+                .source(b"a")
+                .source(b"b")
+                .build()
+        );
+    }
+
+    /// An asset is requested twice from a cache shared by two "projects".
+    /// It should be cooked only once, and recooked after the entry gets corrupted.
+    #[test]
+    fn test_get_or_cook() {
+        let local = temp_cache("cache");
+        let root = local.get_root().to_path_buf();
+        let first = AssetCache::new(local);
+        let second = AssetCache::new(LocalCache::new(&root));
+
+        let key = CacheKey::builder("mesh", 1).source(b"vertices").build();
+        let cooked = Cell::new(0);
+        let cook = || {
+            cooked.set(cooked.get() + 1);
+            Ok::<_, CacheError>(b"cooked".to_vec())
+        };
+
+        assert_eq!(first.get_or_cook(&key, cook).unwrap(), b"cooked");
+        assert_eq!(second.get_or_cook(&key, cook).unwrap(), b"cooked");
+        assert_eq!(cooked.get(), 1);
+
+        let hex = key.to_hex();
+        fs::write(root.join(&hex[..2]).join(&hex[2..]), b"garbage").unwrap();
+        assert!(matches!(
+            LocalCache::new(&root).get(&key),
+            Err(CacheError::Corrupted(_))
+        ));
+        assert_eq!(second.get_or_cook(&key, cook).unwrap(), b"cooked");
+        assert_eq!(cooked.get(), 2);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,433 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! A pool of worker threads running short jobs of the engine and the game.
+//!
+//! Jobs are either fire-and-forget or return a [`JobHandle`], which can be polled, waited on
+//! or awaited. Loops over many independent items are split between the workers with
+//! [`JobPool::parallel_for`].
+//!
+//! On the web, where threads are not available, jobs run immediately on the calling thread.
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::ops::Range;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct Queue {
+    jobs: VecDeque<Job>,
+    /// Set once the last handle to the pool is dropped, the workers exit once the queue is empty.
+    shutdown: bool,
+    /// Whether the worker threads have been started.
+    started: bool,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    available: Condvar,
+    threads: usize,
+}
+
+impl Shared {
+    fn run_worker(&self) {
+        loop {
+            let job = {
+                let mut queue = self.queue.lock().unwrap();
+                loop {
+                    if let Some(job) = queue.jobs.pop_front() {
+                        break job;
+                    }
+
+                    if queue.shutdown {
+                        return;
+                    }
+
+                    queue = self.available.wait(queue).unwrap();
+                }
+            };
+
+            // Panics are forwarded to the job handle, the worker keeps running
+            let _ = catch_unwind(AssertUnwindSafe(job));
+        }
+    }
+}
+
+/// Stops the workers when the last clone of the pool is dropped.
+struct PoolOwner(Arc<Shared>);
+
+impl Drop for PoolOwner {
+    fn drop(&mut self) {
+        self.0.queue.lock().unwrap().shutdown = true;
+        self.0.available.notify_all();
+    }
+}
+
+/// A thread pool running jobs in the order they were spawned.
+///
+/// Clones share the same workers, which are started when the first job is spawned
+/// and stopped once every clone has been dropped and all jobs have finished.
+#[derive(Clone)]
+pub struct JobPool {
+    owner: Arc<PoolOwner>,
+}
+
+impl Default for JobPool {
+    /// Creates a pool with one worker per core, minus one for the thread spawning the jobs.
+    ///
+    /// *Creates a pool without workers on the web.*
+    fn default() -> Self {
+        let threads = if cfg!(target_arch = "wasm32") {
+            0
+        } else {
+            thread::available_parallelism().map_or(1, |cores| cores.get().saturating_sub(1).max(1))
+        };
+
+        Self::new(threads)
+    }
+}
+
+impl JobPool {
+    /// Creates a pool with the given number of workers,
+    /// a pool without workers runs every job on the calling thread when it is spawned.
+    pub fn new(threads: usize) -> Self {
+        Self {
+            owner: Arc::new(PoolOwner(Arc::new(Shared {
+                queue: Mutex::new(Queue::default()),
+                available: Condvar::new(),
+                threads,
+            }))),
+        }
+    }
+
+    pub fn get_thread_count(&self) -> usize {
+        self.owner.0.threads
+    }
+
+    /// Runs a job on a worker without waiting for it.
+    pub fn spawn(&self, job: impl FnOnce() + Send + 'static) {
+        let shared = &self.owner.0;
+
+        if shared.threads == 0 {
+            job();
+            return;
+        }
+
+        let mut queue = shared.queue.lock().unwrap();
+
+        if !queue.started {
+            queue.started = true;
+
+            for i in 0..shared.threads {
+                let worker = shared.clone();
+                thread::Builder::new()
+                    .name(format!("job-worker-{}", i))
+                    .spawn(move || worker.run_worker())
+                    .expect("failed to spawn a job worker thread");
+            }
+        }
+
+        queue.jobs.push_back(Box::new(job));
+        drop(queue);
+
+        shared.available.notify_one();
+    }
+
+    /// Runs a job on a worker, the result can be received through the returned handle.
+    pub fn spawn_with_result<T: Send + 'static>(
+        &self,
+        job: impl FnOnce() -> T + Send + 'static,
+    ) -> JobHandle<T> {
+        let slot = Arc::new(JobSlot {
+            state: Mutex::new(JobState {
+                result: None,
+                waker: None,
+            }),
+            finished: Condvar::new(),
+        });
+
+        let job_slot = slot.clone();
+        self.spawn(move || {
+            let result = catch_unwind(AssertUnwindSafe(job));
+
+            let mut state = job_slot.state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+
+            drop(state);
+            job_slot.finished.notify_all();
+        });
+
+        JobHandle { slot: Some(slot) }
+    }
+
+    /// Calls `body` for every index of the range, split into chunks run by the workers
+    /// and the calling thread, and returns once all of them have finished.
+    ///
+    /// The calling thread takes part, so this can be called from within a job as well.
+    pub fn parallel_for(&self, range: Range<usize>, body: impl Fn(usize) + Sync) {
+        let len = range.len();
+        if len == 0 {
+            return;
+        }
+
+        // A few chunks per thread balance uneven work without too much synchronization
+        let chunk_count = len.min((self.get_thread_count() + 1) * 4);
+        let chunk_size = len.div_ceil(chunk_count);
+        let chunk_count = len.div_ceil(chunk_size);
+
+        let run_chunk = |chunk: usize| {
+            let start = range.start + chunk * chunk_size;
+            let end = (start + chunk_size).min(range.end);
+            (start..end).for_each(&body);
+        };
+        let run_chunk: &(dyn Fn(usize) + Sync) = &run_chunk;
+
+        // SAFETY: The body is only called after claiming a chunk, and this function does not
+        // return before every claimed chunk has finished, after which no chunk can be claimed.
+        let run_chunk: &'static (dyn Fn(usize) + Sync) = unsafe { std::mem::transmute(run_chunk) };
+
+        let work = Arc::new(ParallelWork {
+            run_chunk,
+            chunk_count,
+            next: AtomicUsize::new(0),
+            done: Mutex::new(0),
+            all_done: Condvar::new(),
+            panic: Mutex::new(None),
+        });
+
+        for _ in 0..self.get_thread_count().min(chunk_count - 1) {
+            let work = work.clone();
+            self.spawn(move || work.run());
+        }
+
+        work.run();
+
+        let mut done = work.done.lock().unwrap();
+        while *done < chunk_count {
+            done = work.all_done.wait(done).unwrap();
+        }
+        drop(done);
+
+        let panic = work.panic.lock().unwrap().take();
+        if let Some(panic) = panic {
+            resume_unwind(panic);
+        }
+    }
+
+    /// Calls `body` for every item of the slice in parallel, see [`JobPool::parallel_for`].
+    pub fn parallel_for_each_mut<T: Send>(&self, items: &mut [T], body: impl Fn(&mut T) + Sync) {
+        let len = items.len();
+        let items = SharedSlice(items.as_mut_ptr());
+
+        // SAFETY: Every index is visited exactly once, so no item is borrowed twice.
+        self.parallel_for(0..len, |i| body(unsafe { &mut *items.get(i) }));
+    }
+}
+
+/// A pointer to the items of a slice, shared between the threads of a parallel loop.
+struct SharedSlice<T>(*mut T);
+
+impl<T> SharedSlice<T> {
+    fn get(&self, index: usize) -> *mut T {
+        self.0.wrapping_add(index)
+    }
+}
+
+// SAFETY: The items are `Send` and every item is only accessed by a single thread.
+unsafe impl<T: Send> Sync for SharedSlice<T> {}
+
+struct ParallelWork {
+    run_chunk: &'static (dyn Fn(usize) + Sync),
+    chunk_count: usize,
+    next: AtomicUsize,
+    done: Mutex<usize>,
+    all_done: Condvar,
+    /// The first panic of a chunk, resumed by the caller.
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+impl ParallelWork {
+    /// Runs chunks until none are left to claim.
+    fn run(&self) {
+        loop {
+            let chunk = self.next.fetch_add(1, Ordering::Relaxed);
+            if chunk >= self.chunk_count {
+                return;
+            }
+
+            // A panicking chunk still counts as done, so the caller does not wait forever
+            if let Err(panic) = catch_unwind(AssertUnwindSafe(|| (self.run_chunk)(chunk))) {
+                self.panic.lock().unwrap().get_or_insert(panic);
+            }
+
+            let mut done = self.done.lock().unwrap();
+            *done += 1;
+            if *done == self.chunk_count {
+                self.all_done.notify_all();
+            }
+        }
+    }
+}
+
+struct JobState<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+struct JobSlot<T> {
+    state: Mutex<JobState<T>>,
+    finished: Condvar,
+}
+
+/// The result of a job spawned by [`JobPool::spawn_with_result`].
+///
+/// The handle can also be awaited, e.g. from a task of the application.
+/// If the job panicked, the panic is resumed when its result is taken.
+pub struct JobHandle<T> {
+    /// `None` once the result has been taken.
+    slot: Option<Arc<JobSlot<T>>>,
+}
+
+impl<T> JobHandle<T> {
+    /// *Returns `true` if the job has finished and its result has not been taken yet.*
+    pub fn is_finished(&self) -> bool {
+        self.slot
+            .as_ref()
+            .is_some_and(|slot| slot.state.lock().unwrap().result.is_some())
+    }
+
+    /// *Returns the result once the job has finished, only once.*
+    pub fn poll_result(&mut self) -> Option<T> {
+        let result = self.slot.as_ref()?.state.lock().unwrap().result.take()?;
+        self.slot = None;
+
+        Some(result.unwrap_or_else(|panic| resume_unwind(panic)))
+    }
+
+    /// Blocks until the job has finished.
+    ///
+    /// ***Must not*** be called on the web, where the main thread cannot block.
+    pub fn wait(mut self) -> T {
+        let slot = self.slot.take().expect("the result was already taken");
+        let mut state = slot.state.lock().unwrap();
+
+        loop {
+            if let Some(result) = state.result.take() {
+                return result.unwrap_or_else(|panic| resume_unwind(panic));
+            }
+
+            state = slot.finished.wait(state).unwrap();
+        }
+    }
+}
+
+impl<T> Future for JobHandle<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(slot) = &self.slot {
+            slot.state.lock().unwrap().waker = Some(cx.waker().clone());
+        }
+
+        match self.poll_result() {
+            Some(result) => Poll::Ready(result),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::jobs::JobPool;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Jobs are spawned on a pool with workers and on one without, one of them panicking.
+    /// Every job should run, results should be received and the panic should reach its handle only.
+    #[test]
+    fn test_spawn_jobs() {
+        for threads in [0, 3] {
+            let pool = JobPool::new(threads);
+            let counter = Arc::new(AtomicUsize::new(0));
+
+            let counting = (0..10)
+                .map(|_| {
+                    let counter = counter.clone();
+                    pool.spawn_with_result(move || {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let handles = (0..10u32)
+                .map(|i| pool.spawn_with_result(move || i * i))
+                .collect::<Vec<_>>();
+            let results = handles.into_iter().map(|handle| handle.wait()).sum::<u32>();
+            assert_eq!(results, 285);
+
+            let panicking = pool.spawn_with_result(|| -> u32 { panic!("job failed") });
+            let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| panicking.wait()));
+            assert!(panic.is_err());
+
+            counting.into_iter().for_each(|handle| handle.wait());
+            assert_eq!(counter.load(Ordering::Relaxed), 10);
+        }
+    }
+
+    /// A parallel loop squares the items of a slice, once from the caller and once from a job.
+    /// Every item should be visited exactly once, also when all workers are busy.
+    #[test]
+    fn test_parallel_for() {
+        let pool = JobPool::new(2);
+        let mut items = (0..1000u64).collect::<Vec<_>>();
+
+        pool.parallel_for_each_mut(&mut items, |item| *item *= *item);
+        assert!(items
+            .iter()
+            .enumerate()
+            .all(|(i, item)| *item == (i * i) as u64));
+
+        let job_pool = pool.clone();
+        let sum = pool
+            .spawn_with_result(move || {
+                let sum = AtomicUsize::new(0);
+                job_pool.parallel_for(0..100, |i| {
+                    sum.fetch_add(i, Ordering::Relaxed);
+                });
+                sum.into_inner()
+            })
+            .wait();
+        assert_eq!(sum, 4950);
+    }
+}
@@ -1,3 +1,10 @@
+pub mod asset;
+pub mod cache;
+pub mod jobs;
+pub mod pack;
+pub mod package;
+pub mod save;
+
 use std::path::Path;
 
 struct PlutoPath {
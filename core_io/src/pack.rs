@@ -0,0 +1,536 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! A single-file archive of assets, so games can ship one `assets.ppk` instead of loose files.
+//!
+//! All numbers are little endian:
+//!
+//! | Field          | Type            | Description                                   |
+//! |----------------|-----------------|-----------------------------------------------|
+//! | magic          | `[u8; 4]`       | `PPK1`                                        |
+//! | index offset   | `u64`           | Offset of the index from the start of the pack |
+//! | data           | `[u8]`          | Contents of all entries                       |
+//! | entry count    | `u32`           | The index, at the end of the pack              |
+//! | entries        | `[PackEntry]`   | See below                                     |
+//!
+//! Each entry of the index consists of the length of its path as `u16`, the UTF-8 path,
+//! the offset and stored length of its data as `u64`, its uncompressed length as `u64`,
+//! its [`PackCompression`] as `u8` and the CRC-32 of its uncompressed data as `u32`.
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Crc;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+
+const PACK_MAGIC: &[u8; 4] = b"PPK1";
+
+#[derive(Debug)]
+pub enum PackError {
+    Io(io::Error),
+    Malformed(&'static str),
+    NotFound(String),
+    /// The data of the entry does not match its checksum.
+    Corrupted(String),
+}
+
+impl Display for PackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackError::Io(err) => write!(f, "{}", err),
+            PackError::Malformed(reason) => write!(f, "malformed pack: {}", reason),
+            PackError::NotFound(path) => write!(f, "{} not found in the pack", path),
+            PackError::Corrupted(path) => write!(f, "{} is corrupted", path),
+        }
+    }
+}
+
+impl Error for PackError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PackError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PackError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PackCompression {
+    None,
+    Deflate,
+}
+
+impl PackCompression {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::None),
+            1 => Some(Self::Deflate),
+            _ => None,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Deflate => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PackEntry {
+    path: String,
+    offset: u64,
+    stored_len: u64,
+    len: u64,
+    compression: PackCompression,
+    crc: u32,
+}
+
+impl PackEntry {
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    /// *Returns the uncompressed length of the entry.*
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get_compression(&self) -> PackCompression {
+        self.compression
+    }
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+/// Writes a pack, entry by entry.
+pub struct PackWriter<W: Write + Seek> {
+    writer: W,
+    entries: Vec<PackEntry>,
+}
+
+impl<W: Write + Seek> PackWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self, PackError> {
+        writer.write_all(PACK_MAGIC)?;
+        writer.write_all(&0u64.to_le_bytes())?;
+
+        Ok(Self {
+            writer,
+            entries: Vec::new(),
+        })
+    }
+
+    /// Adds an entry, paths use `/` as the separator.
+    pub fn add(
+        &mut self,
+        path: &str,
+        data: &[u8],
+        compression: PackCompression,
+    ) -> Result<(), PackError> {
+        if path.len() > u16::MAX as usize {
+            return Err(PackError::Malformed("path too long"));
+        }
+
+        let stored = match compression {
+            PackCompression::None => data.to_vec(),
+            PackCompression::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+        };
+
+        let offset = self.writer.stream_position()?;
+        self.writer.write_all(&stored)?;
+
+        self.entries.push(PackEntry {
+            path: path.to_owned(),
+            offset,
+            stored_len: stored.len() as u64,
+            len: data.len() as u64,
+            compression,
+            crc: checksum(data),
+        });
+
+        Ok(())
+    }
+
+    /// Adds all files of a directory and its subdirectories, relative to the directory.
+    pub fn add_dir(
+        &mut self,
+        dir: impl AsRef<Path>,
+        compression: PackCompression,
+    ) -> Result<(), PackError> {
+        self.add_dir_prefixed(dir.as_ref(), "", compression)
+    }
+
+    fn add_dir_prefixed(
+        &mut self,
+        dir: &Path,
+        prefix: &str,
+        compression: PackCompression,
+    ) -> Result<(), PackError> {
+        let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let name = entry.file_name();
+            let name = name
+                .to_str()
+                .ok_or(PackError::Malformed("path is not valid UTF-8"))?;
+            let path = format!("{}{}", prefix, name);
+
+            if entry.file_type()?.is_dir() {
+                self.add_dir_prefixed(&entry.path(), &format!("{}/", path), compression)?;
+            } else {
+                self.add(&path, &std::fs::read(entry.path())?, compression)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the index, returning the underlying writer.
+    pub fn finish(mut self) -> Result<W, PackError> {
+        let index_offset = self.writer.stream_position()?;
+
+        self.writer
+            .write_all(&(self.entries.len() as u32).to_le_bytes())?;
+
+        for entry in &self.entries {
+            self.writer
+                .write_all(&(entry.path.len() as u16).to_le_bytes())?;
+            self.writer.write_all(entry.path.as_bytes())?;
+            self.writer.write_all(&entry.offset.to_le_bytes())?;
+            self.writer.write_all(&entry.stored_len.to_le_bytes())?;
+            self.writer.write_all(&entry.len.to_le_bytes())?;
+            self.writer.write_all(&[entry.compression.to_u8()])?;
+            self.writer.write_all(&entry.crc.to_le_bytes())?;
+        }
+
+        self.writer.seek(SeekFrom::Start(PACK_MAGIC.len() as u64))?;
+        self.writer.write_all(&index_offset.to_le_bytes())?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], PackError> {
+    let mut bytes = [0; N];
+    reader
+        .read_exact(&mut bytes)
+        .map_err(|_| PackError::Malformed("unexpected end of the pack"))?;
+    Ok(bytes)
+}
+
+/// Reads entries of a pack, the index is loaded when opening the pack.
+pub struct PackReader<R: Read + Seek> {
+    reader: R,
+    entries: HashMap<String, PackEntry>,
+}
+
+impl PackReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PackError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl PackReader<Cursor<Vec<u8>>> {
+    /// Reads a pack kept in memory, for example fetched at once on the web.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, PackError> {
+        Self::new(Cursor::new(bytes))
+    }
+}
+
+impl<R: Read + Seek> PackReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, PackError> {
+        if &read_array::<4>(&mut reader)? != PACK_MAGIC {
+            return Err(PackError::Malformed("not a pack"));
+        }
+
+        let index_offset = u64::from_le_bytes(read_array(&mut reader)?);
+        let pack_len = reader.seek(SeekFrom::End(0))?;
+        if index_offset > pack_len {
+            return Err(PackError::Malformed("index out of range"));
+        }
+        reader.seek(SeekFrom::Start(index_offset))?;
+
+        let count = u32::from_le_bytes(read_array(&mut reader)?);
+        let mut entries = HashMap::new();
+
+        for _ in 0..count {
+            let path_len = u16::from_le_bytes(read_array(&mut reader)?) as usize;
+            let mut path = vec![0; path_len];
+            reader
+                .read_exact(&mut path)
+                .map_err(|_| PackError::Malformed("unexpected end of the pack"))?;
+            let path = String::from_utf8(path)
+                .map_err(|_| PackError::Malformed("path is not valid UTF-8"))?;
+
+            let entry = PackEntry {
+                path: path.clone(),
+                offset: u64::from_le_bytes(read_array(&mut reader)?),
+                stored_len: u64::from_le_bytes(read_array(&mut reader)?),
+                len: u64::from_le_bytes(read_array(&mut reader)?),
+                compression: PackCompression::from_u8(read_array::<1>(&mut reader)?[0])
+                    .ok_or(PackError::Malformed("unknown compression"))?,
+                crc: u32::from_le_bytes(read_array(&mut reader)?),
+            };
+
+            match entry.offset.checked_add(entry.stored_len) {
+                Some(end) if end <= pack_len => {}
+                _ => return Err(PackError::Malformed("entry out of range")),
+            }
+
+            entries.insert(path, entry);
+        }
+
+        Ok(Self { reader, entries })
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &PackEntry> {
+        self.entries.values()
+    }
+
+    pub fn get_entry(&self, path: &str) -> Option<&PackEntry> {
+        self.entries.get(path)
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// Reads and decompresses an entry, verifying its checksum.
+    pub fn read(&mut self, path: &str) -> Result<Vec<u8>, PackError> {
+        let entry = self
+            .entries
+            .get(path)
+            .ok_or_else(|| PackError::NotFound(path.to_owned()))?;
+
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        let stored = (&mut self.reader).take(entry.stored_len);
+
+        // The uncompressed length comes from the pack, so only the checked stored length is trusted
+        let mut data = Vec::with_capacity(entry.stored_len.min(entry.len) as usize);
+        match entry.compression {
+            PackCompression::None => stored.take(entry.len).read_to_end(&mut data),
+            PackCompression::Deflate => DeflateDecoder::new(stored)
+                .take(entry.len)
+                .read_to_end(&mut data),
+        }
+        .map_err(|_| PackError::Corrupted(path.to_owned()))?;
+
+        if data.len() as u64 != entry.len || checksum(&data) != entry.crc {
+            return Err(PackError::Corrupted(path.to_owned()));
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type PackRequest = (String, Sender<Result<Vec<u8>, PackError>>);
+
+/// Loads entries of a pack without blocking the caller.
+///
+/// On native platforms the entries are read by a worker thread,
+/// on the web, where the whole pack is kept in memory, they are read immediately.
+pub struct PackLoader {
+    #[cfg(not(target_arch = "wasm32"))]
+    requests: Sender<PackRequest>,
+    #[cfg(target_arch = "wasm32")]
+    reader: std::cell::RefCell<PackReader<Cursor<Vec<u8>>>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PackLoader {
+    pub fn new<R: Read + Seek + Send + 'static>(mut reader: PackReader<R>) -> Self {
+        let (requests, received) = channel::<PackRequest>();
+
+        std::thread::Builder::new()
+            .name("pack-loader".to_owned())
+            .spawn(move || {
+                for (path, result) in received {
+                    // The requester may have dropped the pending asset
+                    let _ = result.send(reader.read(&path));
+                }
+            })
+            .expect("failed to spawn the pack loader thread");
+
+        Self { requests }
+    }
+
+    pub fn load(&self, path: &str) -> PendingAsset {
+        let (sender, receiver) = channel();
+        let request = (path.to_owned(), sender);
+
+        if let Err(err) = self.requests.send(request) {
+            let (_, sender) = err.0;
+            let _ = sender.send(Err(PackError::Io(io::ErrorKind::BrokenPipe.into())));
+        }
+
+        PendingAsset(receiver)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl PackLoader {
+    pub fn new(reader: PackReader<Cursor<Vec<u8>>>) -> Self {
+        Self {
+            reader: std::cell::RefCell::new(reader),
+        }
+    }
+
+    pub fn load(&self, path: &str) -> PendingAsset {
+        let (sender, receiver) = channel();
+        let _ = sender.send(self.reader.borrow_mut().read(path));
+        PendingAsset(receiver)
+    }
+}
+
+/// An entry being loaded by a [`PackLoader`].
+pub struct PendingAsset(Receiver<Result<Vec<u8>, PackError>>);
+
+impl PendingAsset {
+    /// *Returns the result once the entry has been loaded.*
+    pub fn poll_result(&mut self) -> Option<Result<Vec<u8>, PackError>> {
+        match self.0.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Err(PackError::Io(io::ErrorKind::BrokenPipe.into())))
+            }
+        }
+    }
+
+    /// Blocks until the entry has been loaded.
+    pub fn wait(self) -> Result<Vec<u8>, PackError> {
+        self.0
+            .recv()
+            .unwrap_or_else(|_| Err(PackError::Io(io::ErrorKind::BrokenPipe.into())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pack::{PackCompression, PackError, PackLoader, PackReader, PackWriter};
+    use std::io::Cursor;
+
+    fn create_pack() -> Vec<u8> {
+        let mut writer = PackWriter::new(Cursor::new(Vec::new())).unwrap();
+        writer
+            .add("textures/grass.png", &[7; 1000], PackCompression::Deflate)
+            .unwrap();
+        writer
+            .add("shaders/main.wgsl", b"fn main() {}", PackCompression::None)
+            .unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// A pack with a compressed and an uncompressed entry is written and read back.
+    /// The contents should match, the compressed entry should be smaller in the pack.
+    #[test]
+    fn test_pack_roundtrip() {
+        let pack = create_pack();
+        assert!(pack.len() < 1000);
+
+        let mut reader = PackReader::from_bytes(pack).unwrap();
+        assert_eq!(reader.entries().count(), 2);
+        assert_eq!(reader.read("textures/grass.png").unwrap(), [7; 1000]);
+        assert_eq!(reader.read("shaders/main.wgsl").unwrap(), b"fn main() {}");
+        assert!(matches!(
+            reader.read("missing"),
+            Err(PackError::NotFound(_))
+        ));
+    }
+
+    /// A byte of an uncompressed entry is flipped.
+    /// Reading the entry should fail, while the loader should still load the other one.
+    #[test]
+    fn test_pack_corrupted() {
+        let mut pack = create_pack();
+        let reader = PackReader::from_bytes(pack.clone()).unwrap();
+        let offset = reader.get_entry("shaders/main.wgsl").unwrap().offset as usize;
+        pack[offset] ^= 0xFF;
+
+        let loader = PackLoader::new(PackReader::from_bytes(pack).unwrap());
+        let corrupted = loader.load("shaders/main.wgsl");
+        let intact = loader.load("textures/grass.png");
+
+        assert!(matches!(corrupted.wait(), Err(PackError::Corrupted(_))));
+        assert_eq!(intact.wait().unwrap(), [7; 1000]);
+    }
+
+    /// The index entries are patched to point past the end of the pack and to claim a huge length.
+    /// Opening the pack should fail instead of reading out of range or preallocating the length.
+    #[test]
+    fn test_pack_entry_out_of_range() {
+        let pack = create_pack();
+        let path = b"shaders/main.wgsl";
+        // The path in the index is followed by the offset, the stored length and the length
+        let offset_at = pack
+            .windows(path.len())
+            .rposition(|bytes| bytes == path)
+            .unwrap()
+            + path.len();
+
+        let mut out_of_range = pack.clone();
+        let stored_len_at = offset_at + 8;
+        out_of_range[stored_len_at..stored_len_at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            PackReader::from_bytes(out_of_range),
+            Err(PackError::Malformed(_))
+        ));
+
+        let mut huge_len = pack.clone();
+        let len_at = offset_at + 16;
+        huge_len[len_at..len_at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        let mut reader = PackReader::from_bytes(huge_len).unwrap();
+        assert!(matches!(
+            reader.read("shaders/main.wgsl"),
+            Err(PackError::Corrupted(_))
+        ));
+    }
+}
@@ -0,0 +1,587 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Signed packages for plugins and mods.
+//!
+//! A package is a directory containing a [`PackageManifest`] listing the SHA-256 digest of every
+//! file in the package, and optionally a [`PackageSignature`], an ed25519 signature over the
+//! manifest. A [`PackageVerifier`] checks the signature against a [`TrustStore`] and the files
+//! against the manifest, so a verified package contains exactly the files its signer shipped.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use log::warn;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The name of the manifest file in the package root.
+pub const MANIFEST_FILE_NAME: &str = "package.manifest";
+
+/// The name of the signature file in the package root.
+pub const SIGNATURE_FILE_NAME: &str = "package.sig";
+
+pub type FileDigest = [u8; 32];
+
+#[derive(Debug)]
+pub enum PackageError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    MalformedManifest(String),
+    MalformedSignature,
+    /// The package is not signed and the [`UnsignedPolicy`] rejects unsigned packages.
+    Unsigned,
+    /// The package is signed with a key not present in the [`TrustStore`].
+    UntrustedKey([u8; 32]),
+    /// The signature does not match the manifest.
+    InvalidSignature,
+    /// A file listed in the manifest does not match its digest.
+    DigestMismatch(String),
+    /// A file is present in the package but not listed in the manifest.
+    UnlistedFile(String),
+}
+
+impl Display for PackageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackageError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            PackageError::MalformedManifest(reason) => write!(f, "malformed manifest: {}", reason),
+            PackageError::MalformedSignature => write!(f, "malformed signature"),
+            PackageError::Unsigned => write!(f, "package is not signed"),
+            PackageError::UntrustedKey(key) => {
+                write!(f, "package is signed by an untrusted key {}", to_hex(key))
+            }
+            PackageError::InvalidSignature => write!(f, "signature does not match the manifest"),
+            PackageError::DigestMismatch(path) => write!(f, "{} does not match the manifest", path),
+            PackageError::UnlistedFile(path) => write!(f, "{} is not listed in the manifest", path),
+        }
+    }
+}
+
+impl Error for PackageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PackageError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> PackageError + '_ {
+    move |error| PackageError::Io {
+        path: path.to_path_buf(),
+        error,
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn from_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+
+    Some(bytes)
+}
+
+/// Checks that a manifest path is relative, uses `/` separators and stays inside the package.
+fn is_valid_package_path(path: &str) -> bool {
+    !path.is_empty()
+        && !path.starts_with('/')
+        && !path.contains('\\')
+        && path
+            .split('/')
+            .all(|component| !component.is_empty() && component != "." && component != "..")
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ManifestEntry {
+    /// The path of the file relative to the package root, separated by `/`.
+    pub path: String,
+    pub digest: FileDigest,
+}
+
+/// The list of files in a package, along with the package name and version.
+///
+/// The manifest is stored as text, one field per line:
+///
+/// ```text
+/// name example_mod
+/// version 1.0.0
+/// file <sha256 in hex> <path>
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PackageManifest {
+    pub name: String,
+    pub version: String,
+    pub files: Vec<ManifestEntry>,
+}
+
+impl PackageManifest {
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Adds a file with the given contents to the manifest.
+    ///
+    /// ***Panics*** if the path is absolute or leaves the package root.
+    pub fn add_file(&mut self, path: impl Into<String>, contents: &[u8]) {
+        let path = path.into();
+        assert!(
+            is_valid_package_path(&path),
+            "invalid package path {}",
+            path
+        );
+
+        self.files.push(ManifestEntry {
+            path,
+            digest: Sha256::digest(contents).into(),
+        });
+    }
+
+    /// Creates a manifest listing all files in the given directory,
+    /// except for the manifest and signature themselves.
+    pub fn from_dir(
+        name: impl Into<String>,
+        version: impl Into<String>,
+        dir: &Path,
+    ) -> Result<Self, PackageError> {
+        let mut manifest = Self::new(name, version);
+
+        for path in list_package_files(dir)? {
+            let contents = fs::read(dir.join(&path)).map_err(io_error(&dir.join(&path)))?;
+            manifest.add_file(path, &contents);
+        }
+
+        Ok(manifest)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, PackageError> {
+        let mut name = None;
+        let mut version = None;
+        let mut files = Vec::new();
+
+        for line in text.lines().filter(|line| !line.is_empty()) {
+            let malformed = || PackageError::MalformedManifest(line.to_string());
+
+            match line.split_once(' ').ok_or_else(malformed)? {
+                ("name", value) => name = Some(value.to_string()),
+                ("version", value) => version = Some(value.to_string()),
+                ("file", value) => {
+                    let (digest, path) = value.split_once(' ').ok_or_else(malformed)?;
+
+                    if !is_valid_package_path(path) {
+                        return Err(malformed());
+                    }
+
+                    files.push(ManifestEntry {
+                        path: path.to_string(),
+                        digest: from_hex(digest).ok_or_else(malformed)?,
+                    });
+                }
+                _ => return Err(malformed()),
+            }
+        }
+
+        Ok(Self {
+            name: name.ok_or_else(|| PackageError::MalformedManifest("missing name".into()))?,
+            version: version
+                .ok_or_else(|| PackageError::MalformedManifest("missing version".into()))?,
+            files,
+        })
+    }
+
+    pub fn get_file(&self, path: &str) -> Option<&ManifestEntry> {
+        self.files.iter().find(|entry| entry.path == path)
+    }
+
+    /// *Returns the manifest in the format accepted by [`PackageManifest::parse`].*
+    pub fn to_text(&self) -> String {
+        let mut text = format!("name {}\nversion {}\n", self.name, self.version);
+
+        for entry in &self.files {
+            text.push_str(&format!("file {} {}\n", to_hex(&entry.digest), entry.path));
+        }
+
+        text
+    }
+}
+
+/// Lists all files in a package directory relative to its root, except for the manifest and signature.
+fn list_package_files(dir: &Path) -> Result<Vec<String>, PackageError> {
+    let mut files = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), String::new())];
+
+    while let Some((current, prefix)) = pending.pop() {
+        for entry in fs::read_dir(&current).map_err(io_error(&current))? {
+            let entry = entry.map_err(io_error(&current))?;
+            let file_type = entry.file_type().map_err(io_error(&entry.path()))?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let path = format!("{}{}", prefix, name);
+
+            if file_type.is_dir() {
+                pending.push((entry.path(), format!("{}/", path)));
+            } else if path != MANIFEST_FILE_NAME && path != SIGNATURE_FILE_NAME {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// An ed25519 signature over the manifest bytes, along with the key of the signer.
+///
+/// Stored as the 32 byte public key followed by the 64 byte signature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PackageSignature {
+    pub key: VerifyingKey,
+    pub signature: Signature,
+}
+
+impl PackageSignature {
+    pub const LENGTH: usize = 32 + Signature::BYTE_SIZE;
+
+    pub fn sign(signing_key: &SigningKey, manifest: &[u8]) -> Self {
+        Self {
+            key: signing_key.verifying_key(),
+            signature: signing_key.sign(manifest),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PackageError> {
+        if bytes.len() != Self::LENGTH {
+            return Err(PackageError::MalformedSignature);
+        }
+
+        let (key, signature) = bytes.split_at(32);
+
+        Ok(Self {
+            key: VerifyingKey::try_from(key).map_err(|_| PackageError::MalformedSignature)?,
+            signature: Signature::from_slice(signature)
+                .map_err(|_| PackageError::MalformedSignature)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::LENGTH] {
+        let mut bytes = [0; Self::LENGTH];
+        bytes[..32].copy_from_slice(self.key.as_bytes());
+        bytes[32..].copy_from_slice(&self.signature.to_bytes());
+        bytes
+    }
+}
+
+/// Signs a package directory, writing its manifest and signature.
+pub fn sign_dir(
+    signing_key: &SigningKey,
+    name: impl Into<String>,
+    version: impl Into<String>,
+    dir: &Path,
+) -> Result<PackageManifest, PackageError> {
+    let manifest = PackageManifest::from_dir(name, version, dir)?;
+    let text = manifest.to_text();
+    let signature = PackageSignature::sign(signing_key, text.as_bytes());
+
+    let manifest_path = dir.join(MANIFEST_FILE_NAME);
+    fs::write(&manifest_path, &text).map_err(io_error(&manifest_path))?;
+
+    let signature_path = dir.join(SIGNATURE_FILE_NAME);
+    fs::write(&signature_path, signature.to_bytes()).map_err(io_error(&signature_path))?;
+
+    Ok(manifest)
+}
+
+/// A set of named public keys whose packages are trusted.
+#[derive(Clone, Debug, Default)]
+pub struct TrustStore {
+    keys: HashMap<[u8; 32], String>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_key(&mut self, name: impl Into<String>, key: VerifyingKey) {
+        self.keys.insert(key.to_bytes(), name.into());
+    }
+
+    /// Adds a key given as a hex encoded string, as printed by [`PackageError::UntrustedKey`].
+    pub fn add_key_hex(&mut self, name: impl Into<String>, key: &str) -> Result<(), PackageError> {
+        let key = from_hex(key)
+            .and_then(|key| VerifyingKey::from_bytes(&key).ok())
+            .ok_or(PackageError::MalformedSignature)?;
+        self.add_key(name, key);
+        Ok(())
+    }
+
+    pub fn remove_key(&mut self, key: &VerifyingKey) {
+        self.keys.remove(key.as_bytes());
+    }
+
+    /// *Returns the name of the key if it is trusted.*
+    pub fn get_key_name(&self, key: &VerifyingKey) -> Option<&str> {
+        self.keys.get(key.as_bytes()).map(String::as_str)
+    }
+}
+
+/// What to do with packages that are not signed at all.
+///
+/// *Packages signed by an untrusted key or with an invalid signature are always rejected.*
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum UnsignedPolicy {
+    #[default]
+    Reject,
+    /// Accept unsigned packages, logging a warning.
+    Warn,
+    Allow,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PackageSigner {
+    /// The name of the trusted key the package was signed with.
+    Trusted(String),
+    /// The package is not signed and was accepted by the [`UnsignedPolicy`].
+    Unsigned,
+}
+
+#[derive(Clone, Debug)]
+pub struct VerifiedPackage {
+    pub manifest: PackageManifest,
+    pub signer: PackageSigner,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PackageVerifier {
+    pub trust_store: TrustStore,
+    pub unsigned_policy: UnsignedPolicy,
+}
+
+impl PackageVerifier {
+    pub fn new(trust_store: TrustStore) -> Self {
+        Self {
+            trust_store,
+            unsigned_policy: UnsignedPolicy::default(),
+        }
+    }
+
+    pub fn unsigned_policy(mut self, unsigned_policy: UnsignedPolicy) -> Self {
+        self.unsigned_policy = unsigned_policy;
+        self
+    }
+
+    /// Verifies the signature of a manifest and parses it.
+    ///
+    /// *Does not check the files of the package, see [`PackageVerifier::verify_dir`].*
+    pub fn verify_manifest(
+        &self,
+        manifest: &[u8],
+        signature: Option<&[u8]>,
+    ) -> Result<VerifiedPackage, PackageError> {
+        let signer = match signature {
+            Some(signature) => {
+                let signature = PackageSignature::from_bytes(signature)?;
+                let name = self
+                    .trust_store
+                    .get_key_name(&signature.key)
+                    .ok_or(PackageError::UntrustedKey(signature.key.to_bytes()))?;
+
+                signature
+                    .key
+                    .verify_strict(manifest, &signature.signature)
+                    .map_err(|_| PackageError::InvalidSignature)?;
+
+                PackageSigner::Trusted(name.to_string())
+            }
+            None => PackageSigner::Unsigned,
+        };
+
+        let text = std::str::from_utf8(manifest)
+            .map_err(|_| PackageError::MalformedManifest("not valid UTF-8".into()))?;
+        let manifest = PackageManifest::parse(text)?;
+
+        if signer == PackageSigner::Unsigned {
+            match self.unsigned_policy {
+                UnsignedPolicy::Reject => return Err(PackageError::Unsigned),
+                UnsignedPolicy::Warn => warn!("Loading unsigned package {}.", manifest.name),
+                UnsignedPolicy::Allow => {}
+            }
+        }
+
+        Ok(VerifiedPackage { manifest, signer })
+    }
+
+    /// Verifies a package directory: the manifest signature,
+    /// the digest of every listed file and the absence of unlisted files.
+    pub fn verify_dir(&self, dir: &Path) -> Result<VerifiedPackage, PackageError> {
+        let manifest_path = dir.join(MANIFEST_FILE_NAME);
+        let manifest = fs::read(&manifest_path).map_err(io_error(&manifest_path))?;
+
+        let signature_path = dir.join(SIGNATURE_FILE_NAME);
+        let signature = match fs::read(&signature_path) {
+            Ok(signature) => Some(signature),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => return Err(io_error(&signature_path)(error)),
+        };
+
+        let package = self.verify_manifest(&manifest, signature.as_deref())?;
+
+        for entry in &package.manifest.files {
+            let path = dir.join(&entry.path);
+            let contents = fs::read(&path).map_err(io_error(&path))?;
+
+            if Sha256::digest(&contents).as_slice() != entry.digest {
+                return Err(PackageError::DigestMismatch(entry.path.clone()));
+            }
+        }
+
+        if let Some(unlisted) = list_package_files(dir)?
+            .into_iter()
+            .find(|path| package.manifest.get_file(path).is_none())
+        {
+            return Err(PackageError::UnlistedFile(unlisted));
+        }
+
+        Ok(package)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::package::{
+        sign_dir, PackageError, PackageManifest, PackageSignature, PackageSigner, PackageVerifier,
+        TrustStore, UnsignedPolicy,
+    };
+    use ed25519_dalek::SigningKey;
+    use std::fs;
+
+    fn signed_manifest(key: &SigningKey) -> (Vec<u8>, Vec<u8>) {
+        let mut manifest = PackageManifest::new("example_mod", "1.0.0");
+        manifest.add_file("plugin.so", b"plugin");
+        manifest.add_file("assets/icon.png", b"icon");
+
+        let text = manifest.to_text().into_bytes();
+        let signature = PackageSignature::sign(key, &text).to_bytes().to_vec();
+        (text, signature)
+    }
+
+    /// A manifest signed by a trusted key is verified and parsed.
+    /// Tampering with the manifest should invalidate the signature.
+    #[test]
+    fn test_verify_manifest() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let mut trust_store = TrustStore::new();
+        trust_store.add_key("publisher", key.verifying_key());
+        let verifier = PackageVerifier::new(trust_store);
+
+        let (mut manifest, signature) = signed_manifest(&key);
+        let package = verifier
+            .verify_manifest(&manifest, Some(&signature))
+            .unwrap();
+
+        assert_eq!(package.signer, PackageSigner::Trusted("publisher".into()));
+        assert_eq!(package.manifest.files.len(), 2);
+
+        manifest[5] ^= 1;
+        assert!(matches!(
+            verifier.verify_manifest(&manifest, Some(&signature)),
+            Err(PackageError::InvalidSignature)
+        ));
+    }
+
+    /// A manifest signed by an unknown key and an unsigned manifest are verified.
+    /// The first should always be rejected, the second only with the default policy.
+    #[test]
+    fn test_untrusted_and_unsigned() {
+        let (manifest, signature) = signed_manifest(&SigningKey::from_bytes(&[1; 32]));
+        let verifier = PackageVerifier::default();
+
+        assert!(matches!(
+            verifier.verify_manifest(&manifest, Some(&signature)),
+            Err(PackageError::UntrustedKey(_))
+        ));
+        assert!(matches!(
+            verifier.verify_manifest(&manifest, None),
+            Err(PackageError::Unsigned)
+        ));
+
+        let verifier = verifier.unsigned_policy(UnsignedPolicy::Allow);
+        let package = verifier.verify_manifest(&manifest, None).unwrap();
+        assert_eq!(package.signer, PackageSigner::Unsigned);
+        assert!(verifier
+            .verify_manifest(&manifest, Some(&signature))
+            .is_err());
+    }
+
+    /// A package directory is signed and verified.
+    /// Modifying a file or adding an unlisted one should fail the verification.
+    #[test]
+    fn test_verify_dir() {
+        let dir = std::env::temp_dir().join(format!("pluto_io_package_{}", std::process::id()));
+        fs::create_dir_all(dir.join("assets")).unwrap();
+        fs::write(dir.join("plugin.so"), b"plugin").unwrap();
+        fs::write(dir.join("assets/icon.png"), b"icon").unwrap();
+
+        let key = SigningKey::from_bytes(&[3; 32]);
+        sign_dir(&key, "example_mod", "1.0.0", &dir).unwrap();
+
+        let mut trust_store = TrustStore::new();
+        trust_store.add_key("publisher", key.verifying_key());
+        let verifier = PackageVerifier::new(trust_store);
+
+        assert!(verifier.verify_dir(&dir).is_ok());
+
+        fs::write(dir.join("extra.so"), b"extra").unwrap();
+        assert!(matches!(
+            verifier.verify_dir(&dir),
+            Err(PackageError::UnlistedFile(path)) if path == "extra.so"
+        ));
+        fs::remove_file(dir.join("extra.so")).unwrap();
+
+        fs::write(dir.join("assets/icon.png"), b"evil").unwrap();
+        assert!(matches!(
+            verifier.verify_dir(&dir),
+            Err(PackageError::DigestMismatch(path)) if path == "assets/icon.png"
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
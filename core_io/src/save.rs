@@ -0,0 +1,297 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Versioned save data.
+//!
+//! Every persisted type declares its schema version, which is stored in the header of the saved
+//! file. When older data is loaded, the registered [`Migrations`] transform the payload one
+//! version at a time until it matches the current schema, so shipped games can evolve their
+//! data formats without breaking existing saves and configs.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::Path;
+
+const SAVE_MAGIC: &[u8; 4] = b"PSV1";
+
+const HEADER_SIZE: usize = SAVE_MAGIC.len() + std::mem::size_of::<u32>();
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Malformed(&'static str),
+    /// The data was saved by a newer version of the game.
+    FutureVersion {
+        found: u32,
+        current: u32,
+    },
+    /// No migration from this version is registered.
+    MissingMigration {
+        from: u32,
+    },
+    /// A migration rejected the data.
+    Migration {
+        from: u32,
+        reason: String,
+    },
+    /// The migrated data could not be decoded.
+    Decode(String),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "{}", err),
+            SaveError::Malformed(reason) => write!(f, "malformed save data: {}", reason),
+            SaveError::FutureVersion { found, current } => write!(
+                f,
+                "save data version {} is newer than the supported version {}",
+                found, current
+            ),
+            SaveError::MissingMigration { from } => {
+                write!(f, "no migration from save data version {}", from)
+            }
+            SaveError::Migration { from, reason } => {
+                write!(f, "migration from version {} failed: {}", from, reason)
+            }
+            SaveError::Decode(reason) => write!(f, "could not decode save data: {}", reason),
+        }
+    }
+}
+
+impl Error for SaveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SaveError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// A type stored in save games or configs.
+pub trait Persisted: Sized {
+    /// The version of the encoded format, bumped whenever it changes.
+    const SCHEMA_VERSION: u32;
+
+    fn encode(&self) -> Vec<u8>;
+
+    fn decode(bytes: &[u8]) -> Result<Self, String>;
+}
+
+/// Transforms a payload of one version to the next one.
+pub type MigrationFn = fn(Vec<u8>) -> Result<Vec<u8>, String>;
+
+/// The migrations of a persisted type.
+pub struct Migrations<T: Persisted> {
+    steps: HashMap<u32, MigrationFn>,
+    persisted: PhantomData<T>,
+}
+
+impl<T: Persisted> Default for Migrations<T> {
+    fn default() -> Self {
+        Self {
+            steps: HashMap::new(),
+            persisted: PhantomData,
+        }
+    }
+}
+
+impl<T: Persisted> Migrations<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the migration of payloads of version `from` to version `from + 1`.
+    pub fn step(mut self, from: u32, migration: MigrationFn) -> Self {
+        self.steps.insert(from, migration);
+        self
+    }
+
+    /// Encodes the value with a header containing the current schema version.
+    pub fn save(&self, value: &T) -> Vec<u8> {
+        let payload = value.encode();
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+        bytes.extend_from_slice(SAVE_MAGIC);
+        bytes.extend_from_slice(&T::SCHEMA_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&payload);
+        bytes
+    }
+
+    /// Decodes saved data, migrating it to the current schema version first.
+    pub fn load(&self, bytes: &[u8]) -> Result<T, SaveError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(SaveError::Malformed("truncated header"));
+        }
+
+        let (header, payload) = bytes.split_at(HEADER_SIZE);
+        if &header[..SAVE_MAGIC.len()] != SAVE_MAGIC {
+            return Err(SaveError::Malformed("bad magic"));
+        }
+
+        let version = u32::from_le_bytes(header[SAVE_MAGIC.len()..].try_into().unwrap());
+        let payload = self.migrate(version, payload.to_vec())?;
+
+        T::decode(&payload).map_err(SaveError::Decode)
+    }
+
+    /// Migrates a payload of the given version to the current schema version.
+    pub fn migrate(&self, mut version: u32, mut payload: Vec<u8>) -> Result<Vec<u8>, SaveError> {
+        if version > T::SCHEMA_VERSION {
+            return Err(SaveError::FutureVersion {
+                found: version,
+                current: T::SCHEMA_VERSION,
+            });
+        }
+
+        while version < T::SCHEMA_VERSION {
+            let migration = self
+                .steps
+                .get(&version)
+                .ok_or(SaveError::MissingMigration { from: version })?;
+
+            payload = migration(payload).map_err(|reason| SaveError::Migration {
+                from: version,
+                reason,
+            })?;
+            version += 1;
+        }
+
+        Ok(payload)
+    }
+
+    /// Saves the value to a file.
+    ///
+    /// *The data is written to a temporary file first,
+    /// so a crash while saving does not destroy the previous save.*
+    pub fn save_file(&self, path: impl AsRef<Path>, value: &T) -> Result<(), SaveError> {
+        let path = path.as_ref();
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, self.save(value))?;
+        fs::rename(temp_path, path)?;
+        Ok(())
+    }
+
+    pub fn load_file(&self, path: impl AsRef<Path>) -> Result<T, SaveError> {
+        self.load(&fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::save::{Migrations, Persisted, SaveError};
+
+    /// Version 1 stored the score as a u16, version 2 as a u32 and version 3 added the level.
+    #[derive(Debug, PartialEq)]
+    struct Progress {
+        score: u32,
+        level: u8,
+    }
+
+    impl Persisted for Progress {
+        const SCHEMA_VERSION: u32 = 3;
+
+        fn encode(&self) -> Vec<u8> {
+            let mut bytes = self.score.to_le_bytes().to_vec();
+            bytes.push(self.level);
+            bytes
+        }
+
+        fn decode(bytes: &[u8]) -> Result<Self, String> {
+            match bytes {
+                [a, b, c, d, level] => Ok(Self {
+                    score: u32::from_le_bytes([*a, *b, *c, *d]),
+                    level: *level,
+                }),
+                _ => Err(format!("expected 5 bytes, got {}", bytes.len())),
+            }
+        }
+    }
+
+    fn migrations() -> Migrations<Progress> {
+        Migrations::new()
+            .step(1, |mut payload| {
+                payload.extend_from_slice(&[0, 0]);
+                Ok(payload)
+            })
+            .step(2, |mut payload| {
+                payload.push(1);
+                Ok(payload)
+            })
+    }
+
+    /// Data of version 1 is loaded.
+    /// It should be migrated through version 2 to the current version.
+    #[test]
+    fn test_migrate_old_save() {
+        let mut old = b"PSV1".to_vec();
+        old.extend_from_slice(&1u32.to_le_bytes());
+        old.extend_from_slice(&500u16.to_le_bytes());
+
+        let migrations = migrations();
+        let progress = migrations.load(&old).unwrap();
+        assert_eq!(
+            progress,
+            Progress {
+                score: 500,
+                level: 1
+            }
+        );
+        assert_eq!(
+            migrations.load(&migrations.save(&progress)).unwrap(),
+            progress
+        );
+    }
+
+    /// Data of a future version and data of a version without a migration are loaded.
+    /// Both should fail with a controlled error.
+    #[test]
+    fn test_unsupported_versions() {
+        let mut future = b"PSV1".to_vec();
+        future.extend_from_slice(&4u32.to_le_bytes());
+        assert!(matches!(
+            migrations().load(&future),
+            Err(SaveError::FutureVersion {
+                found: 4,
+                current: 3
+            })
+        ));
+
+        let mut ancient = b"PSV1".to_vec();
+        ancient.extend_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(
+            migrations().load(&ancient),
+            Err(SaveError::MissingMigration { from: 0 })
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Audio output through cpal, which picks the audio API of the platform,
+//! such as WASAPI, CoreAudio or ALSA.
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{
+    BuildStreamError, DefaultStreamConfigError, FromSample, PlayStreamError, SampleFormat,
+    SizedSample, StreamConfig,
+};
+use log::warn;
+use pluto_engine_audio::device::{AudioDevice, AudioDeviceInfo, AudioHost};
+use pluto_engine_audio::mixer::SharedMixer;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum CpalError {
+    /// There is no output device, or none with the requested name.
+    NoDevice,
+    DefaultConfig(DefaultStreamConfigError),
+    UnsupportedSampleFormat(SampleFormat),
+    BuildStream(BuildStreamError),
+    PlayStream(PlayStreamError),
+}
+
+impl Display for CpalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CpalError::NoDevice => write!(f, "no audio output device"),
+            CpalError::DefaultConfig(err) => write!(f, "{}", err),
+            CpalError::UnsupportedSampleFormat(format) => {
+                write!(f, "unsupported sample format {}", format)
+            }
+            CpalError::BuildStream(err) => write!(f, "{}", err),
+            CpalError::PlayStream(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for CpalError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CpalError::DefaultConfig(err) => Some(err),
+            CpalError::BuildStream(err) => Some(err),
+            CpalError::PlayStream(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The default cpal host of the platform.
+pub struct CpalHost {
+    host: cpal::Host,
+}
+
+impl CpalHost {
+    pub fn new() -> Self {
+        Self {
+            host: cpal::default_host(),
+        }
+    }
+
+    fn default_device_name(&self) -> Option<String> {
+        self.host
+            .default_output_device()
+            .and_then(|device| device.name().ok())
+    }
+
+    /// *Returns `None` for devices which cannot output audio.*
+    fn device_info(device: &cpal::Device, default_name: Option<&str>) -> Option<AudioDeviceInfo> {
+        let name = device.name().ok()?;
+        let config = device.default_output_config().ok()?;
+
+        Some(AudioDeviceInfo {
+            is_default: default_name == Some(name.as_str()),
+            name,
+            sample_rate: config.sample_rate().0,
+            channels: config.channels(),
+        })
+    }
+}
+
+impl Default for CpalHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioHost for CpalHost {
+    type DeviceType = CpalDevice;
+    type ErrorType = CpalError;
+
+    fn enumerate_devices(&self) -> Vec<AudioDeviceInfo> {
+        let default_name = self.default_device_name();
+        let Ok(devices) = self.host.output_devices() else {
+            return Vec::new();
+        };
+
+        devices
+            .filter_map(|device| Self::device_info(&device, default_name.as_deref()))
+            .collect()
+    }
+
+    fn open_device(
+        &self,
+        device: Option<&AudioDeviceInfo>,
+        mixer: SharedMixer,
+    ) -> Result<Self::DeviceType, Self::ErrorType> {
+        let device = match device {
+            Some(info) => self.host.output_devices().ok().and_then(|mut devices| {
+                devices.find(|device| device.name().is_ok_and(|name| name == info.name))
+            }),
+            None => self.host.default_output_device(),
+        }
+        .ok_or(CpalError::NoDevice)?;
+
+        let supported = device
+            .default_output_config()
+            .map_err(CpalError::DefaultConfig)?;
+        let name = device.name().unwrap_or_default();
+        let info = AudioDeviceInfo {
+            is_default: self.default_device_name().as_ref() == Some(&name),
+            name,
+            sample_rate: supported.sample_rate().0,
+            channels: supported.channels(),
+        };
+
+        mixer
+            .lock()
+            .unwrap()
+            .set_output_format(info.sample_rate, info.channels);
+
+        let config = supported.config();
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, mixer),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, mixer),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, mixer),
+            SampleFormat::I32 => build_stream::<i32>(&device, &config, mixer),
+            format => return Err(CpalError::UnsupportedSampleFormat(format)),
+        }
+        .map_err(CpalError::BuildStream)?;
+
+        stream.play().map_err(CpalError::PlayStream)?;
+
+        Ok(CpalDevice { info, stream })
+    }
+}
+
+/// Builds a stream converting the mixed samples to the sample format of the device.
+fn build_stream<T: SizedSample + FromSample<f32>>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    mixer: SharedMixer,
+) -> Result<cpal::Stream, BuildStreamError> {
+    let mut mixed = Vec::new();
+
+    device.build_output_stream(
+        config,
+        move |output: &mut [T], _| {
+            mixed.resize(output.len(), 0.0);
+
+            // Stay silent instead of panicking on the audio thread if mixing has panicked before
+            match mixer.lock() {
+                Ok(mut mixer) => mixer.mix(&mut mixed),
+                Err(_) => mixed.fill(0.0),
+            }
+
+            for (sample, mixed) in output.iter_mut().zip(&mixed) {
+                *sample = T::from_sample(*mixed);
+            }
+        },
+        |err| warn!("Audio stream error: {}", err),
+        None,
+    )
+}
+
+/// An output stream of a cpal device, closed when dropped.
+pub struct CpalDevice {
+    info: AudioDeviceInfo,
+    stream: cpal::Stream,
+}
+
+impl AudioDevice for CpalDevice {
+    fn get_info(&self) -> &AudioDeviceInfo {
+        &self.info
+    }
+
+    fn pause(&self) {
+        if let Err(err) = self.stream.pause() {
+            warn!("Failed to pause the audio stream: {}", err);
+        }
+    }
+
+    fn resume(&self) {
+        if let Err(err) = self.stream.play() {
+            warn!("Failed to resume the audio stream: {}", err);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Audio output through the Web Audio API.
+//!
+//! Browsers only let an `AudioContext` play after a user gesture such as a click,
+//! a device opened before stays silent until [`AudioDevice::resume`] is called from one.
+
+use log::warn;
+use pluto_engine_audio::device::{AudioDevice, AudioDeviceInfo, AudioHost};
+use pluto_engine_audio::mixer::SharedMixer;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{AudioContext, AudioProcessingEvent, ScriptProcessorNode};
+
+/// The Web Audio API only exposes the output device chosen by the browser.
+const DEVICE_NAME: &str = "Web Audio";
+
+/// The number of frames mixed at once, a power of two as required by the script processor.
+const BUFFER_FRAMES: u32 = 2048;
+
+#[derive(Debug)]
+pub enum WebAudioError {
+    /// The browser refused to create the audio context or its nodes.
+    Js(JsValue),
+}
+
+impl Display for WebAudioError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebAudioError::Js(value) => write!(f, "Web Audio error: {:?}", value),
+        }
+    }
+}
+
+impl Error for WebAudioError {}
+
+#[derive(Default)]
+pub struct WebAudioHost;
+
+impl WebAudioHost {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl AudioHost for WebAudioHost {
+    type DeviceType = WebAudioDevice;
+    type ErrorType = WebAudioError;
+
+    fn enumerate_devices(&self) -> Vec<AudioDeviceInfo> {
+        // The format is only known from a context, a suspended one is enough to query it
+        let Ok(context) = AudioContext::new() else {
+            return Vec::new();
+        };
+
+        let info = device_info(&context);
+        let _ = context.close();
+        vec![info]
+    }
+
+    fn open_device(
+        &self,
+        _device: Option<&AudioDeviceInfo>,
+        mixer: SharedMixer,
+    ) -> Result<Self::DeviceType, Self::ErrorType> {
+        let context = AudioContext::new().map_err(WebAudioError::Js)?;
+        let info = device_info(&context);
+
+        mixer
+            .lock()
+            .unwrap()
+            .set_output_format(info.sample_rate, info.channels);
+
+        let node = context
+            .create_script_processor_with_buffer_size_and_number_of_input_channels_and_number_of_output_channels(
+                BUFFER_FRAMES,
+                0,
+                info.channels as u32,
+            )
+            .map_err(WebAudioError::Js)?;
+
+        let mut mixed = Vec::new();
+        let mut channel_samples = Vec::new();
+        let on_process =
+            Closure::<dyn FnMut(AudioProcessingEvent)>::new(move |event: AudioProcessingEvent| {
+                let Ok(output) = event.output_buffer() else {
+                    return;
+                };
+
+                let channels = output.number_of_channels().max(1) as usize;
+                mixed.resize(output.length() as usize * channels, 0.0);
+
+                // Stay silent instead of panicking in the callback if mixing has panicked before
+                match mixer.lock() {
+                    Ok(mut mixer) => mixer.mix(&mut mixed),
+                    Err(_) => mixed.fill(0.0),
+                }
+
+                // The mixer interleaves the channels, audio buffers store them separately
+                for channel in 0..channels {
+                    channel_samples.clear();
+                    channel_samples.extend(mixed.iter().skip(channel).step_by(channels));
+
+                    if let Err(err) = output.copy_to_channel(&channel_samples, channel as i32) {
+                        warn!("Failed to write audio channel {}: {:?}", channel, err);
+                    }
+                }
+            });
+
+        node.set_onaudioprocess(Some(on_process.as_ref().unchecked_ref()));
+        node.connect_with_audio_node(&context.destination())
+            .map_err(WebAudioError::Js)?;
+
+        Ok(WebAudioDevice {
+            info,
+            context,
+            node,
+            _on_process: on_process,
+        })
+    }
+}
+
+fn device_info(context: &AudioContext) -> AudioDeviceInfo {
+    AudioDeviceInfo {
+        name: DEVICE_NAME.to_owned(),
+        sample_rate: context.sample_rate() as u32,
+        channels: context.destination().channel_count() as u16,
+        is_default: true,
+    }
+}
+
+/// An audio context playing the mixer, closed when dropped.
+pub struct WebAudioDevice {
+    info: AudioDeviceInfo,
+    context: AudioContext,
+    node: ScriptProcessorNode,
+    // Called by the browser for as long as the node is connected
+    _on_process: Closure<dyn FnMut(AudioProcessingEvent)>,
+}
+
+impl AudioDevice for WebAudioDevice {
+    fn get_info(&self) -> &AudioDeviceInfo {
+        &self.info
+    }
+
+    fn pause(&self) {
+        if let Err(err) = self.context.suspend() {
+            warn!("Failed to suspend the audio context: {:?}", err);
+        }
+    }
+
+    fn resume(&self) {
+        if let Err(err) = self.context.resume() {
+            warn!("Failed to resume the audio context: {:?}", err);
+        }
+    }
+}
+
+impl Drop for WebAudioDevice {
+    fn drop(&mut self) {
+        self.node.set_onaudioprocess(None);
+        let _ = self.node.disconnect();
+        let _ = self.context.close();
+    }
+}
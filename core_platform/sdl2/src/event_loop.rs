@@ -0,0 +1,448 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::window::{Sdl2Window, Sdl2WindowEvent, Sdl2WindowState};
+use log::warn;
+use pluto_engine_window::event_loop::{
+    display_event_channel_with, CommandProxy, DisplayCommand, DisplayEvent,
+    DisplayEventChannelConfig, DisplayEventSender, EventLoop, EventLoopWindowFactory,
+};
+use pluto_engine_window::executor::{LocalExecutor, LocalFuture};
+use pluto_engine_window::window;
+use pluto_engine_window::window::Window;
+use sdl2::event::{Event, EventSender, WindowEvent};
+use sdl2::rect::Rect;
+use sdl2::{EventSubsystem, VideoSubsystem};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+/// Requests of windows and other threads, native windows can only be changed on the event loop thread.
+pub(crate) enum Sdl2Command {
+    Display(DisplayCommand),
+    Repaint(u32),
+    SetImePosition { window: u32, x: f64, y: f64 },
+    Destroy(u32),
+}
+
+struct NativeWindow {
+    window: sdl2::video::Window,
+    state: Arc<Mutex<Sdl2WindowState>>,
+}
+
+impl NativeWindow {
+    /// Reads the size of the window for the application thread.
+    ///
+    /// *Returns the previous and the updated state.*
+    fn update_state(&self) -> (Sdl2WindowState, Sdl2WindowState) {
+        let mut state = self.state.lock().unwrap();
+        let previous = *state;
+        *state = Sdl2WindowState::read(&self.window, previous.ime_allowed);
+        (previous, *state)
+    }
+}
+
+/// The SDL subsystems and native windows, only usable on the event loop thread.
+pub struct Sdl2Context {
+    video: VideoSubsystem,
+    event: EventSubsystem,
+    windows: RefCell<HashMap<u32, NativeWindow>>,
+}
+
+impl Sdl2Context {
+    pub fn video(&self) -> &VideoSubsystem {
+        &self.video
+    }
+
+    /// Creates a sender other threads can use to push events to the event loop.
+    pub fn event_sender(&self) -> EventSender {
+        self.event.event_sender()
+    }
+
+    pub(crate) fn insert_window(
+        &self,
+        window: sdl2::video::Window,
+        state: Arc<Mutex<Sdl2WindowState>>,
+    ) {
+        self.windows
+            .borrow_mut()
+            .insert(window.id(), NativeWindow { window, state });
+    }
+
+    fn get_state(&self, id: u32) -> Option<Sdl2WindowState> {
+        self.windows
+            .borrow()
+            .get(&id)
+            .map(|native| *native.state.lock().unwrap())
+    }
+}
+
+/// Why a single window is not rendered, while the rest of the application keeps running.
+#[derive(Copy, Clone, Debug, Default)]
+struct WindowPause {
+    hidden: bool,
+    minimized: bool,
+}
+
+impl WindowPause {
+    fn is_paused(&self) -> bool {
+        self.hidden || self.minimized
+    }
+}
+
+pub struct Sdl2EventLoop {
+    context: Sdl2Context,
+    windows: HashMap<u32, DisplayEventSender>,
+    /// Windows which are hidden or minimized, and receive no frames until they are shown again.
+    paused: HashMap<u32, WindowPause>,
+    /// Applications running on the event loop thread.
+    executor: LocalExecutor,
+    /// Set while the whole application is suspended.
+    suspended: bool,
+    /// Set once the last window was destroyed.
+    exit: bool,
+}
+
+impl EventLoop for Sdl2EventLoop {
+    type WindowType = Sdl2Window;
+    type LoopType = Sdl2Context;
+
+    fn run<F: FnOnce(&mut dyn EventLoopWindowFactory<Self, LoopType = Self::LoopType>) + 'static>(
+        initializer: F,
+    ) -> Infallible
+    where
+        Self: Sized,
+    {
+        let sdl = sdl2::init().expect("failed to initialize SDL");
+        let context = Sdl2Context {
+            video: sdl
+                .video()
+                .expect("failed to initialize the SDL video subsystem"),
+            event: sdl
+                .event()
+                .expect("failed to initialize the SDL event subsystem"),
+            windows: RefCell::new(HashMap::new()),
+        };
+        context
+            .event
+            .register_custom_event::<Sdl2Command>()
+            .expect("failed to register the event loop commands");
+
+        let mut event_pump = sdl
+            .event_pump()
+            .expect("failed to create the SDL event pump");
+        let sender = context.event_sender();
+        let proxy: CommandProxy = Arc::new(move |cmd| {
+            sender.push_custom_event(Sdl2Command::Display(cmd)).ok();
+        });
+
+        let mut event_loop_data = Self {
+            context,
+            windows: HashMap::new(),
+            paused: HashMap::new(),
+            executor: LocalExecutor::with_command_proxy(proxy.clone()),
+            suspended: false,
+            exit: false,
+        };
+
+        initializer(&mut Sdl2EventLoopWindowFactory {
+            context: &event_loop_data.context,
+            proxy,
+            windows: &mut event_loop_data.windows,
+            executor: &mut event_loop_data.executor,
+        });
+        event_loop_data.executor.run_woken();
+
+        while !event_loop_data.exit {
+            // Stops polling for new frames while no window can be rendered, saving power
+            let first = if event_loop_data.is_idle() {
+                Some(event_pump.wait_event())
+            } else {
+                event_pump.poll_event()
+            };
+
+            for event in first.into_iter().chain(event_pump.poll_iter()) {
+                event_loop_data.dispatch(event);
+            }
+
+            event_loop_data.broadcast(DisplayEvent::NextFrame);
+            event_loop_data.executor.run_woken();
+        }
+
+        drop(event_loop_data);
+        std::process::exit(0)
+    }
+
+    fn send_event(
+        &mut self,
+        id: <<Self as EventLoop>::WindowType as Window>::IdType,
+        event: DisplayEvent,
+    ) {
+        match self.windows.get_mut(&id) {
+            Some(sender) => match sender.send(event) {
+                Ok(_) => {}
+                Err(err) => {
+                    warn!("Window ID {:?}: {}, removing the window.", id, err);
+                    self.windows.remove(&id);
+                    self.paused.remove(&id);
+                }
+            },
+            None => {
+                warn!(
+                    "Received an event for an unregistered window with ID {:?}.",
+                    id
+                );
+            }
+        }
+    }
+}
+
+impl Sdl2EventLoop {
+    fn broadcast(&mut self, event: DisplayEvent) {
+        let window: Vec<_> = self
+            .windows
+            .keys()
+            .copied()
+            .filter(|id| !self.is_paused(*id))
+            .collect();
+        window.into_iter().for_each(|id| {
+            self.send_event(id, event.clone());
+        });
+    }
+
+    fn is_paused(&self, id: u32) -> bool {
+        self.paused.get(&id).is_some_and(WindowPause::is_paused)
+    }
+
+    fn is_idle(&self) -> bool {
+        self.suspended
+            || (!self.windows.is_empty() && self.windows.keys().all(|id| self.is_paused(*id)))
+    }
+
+    /// Updates why a window is paused, suspending or resuming its display if that changed.
+    fn set_paused(&mut self, id: u32, update: impl FnOnce(&mut WindowPause)) {
+        let was_paused = self.is_paused(id);
+        update(self.paused.entry(id).or_default());
+        let paused = self.is_paused(id);
+
+        // While the whole application is suspended, the display is resumed with the application
+        if was_paused == paused || self.suspended {
+            return;
+        }
+
+        let event = if paused {
+            DisplayEvent::Suspended
+        } else {
+            DisplayEvent::Resumed
+        };
+
+        self.send_event(id, event);
+    }
+
+    fn suspend(&mut self) {
+        if !self.suspended {
+            self.suspended = true;
+            self.broadcast(DisplayEvent::Suspended);
+        }
+    }
+
+    fn resume(&mut self) {
+        if self.suspended {
+            self.suspended = false;
+            self.broadcast(DisplayEvent::Resumed);
+        }
+    }
+
+    /// Sends the new size of a window, SDL reports the size in logical units,
+    /// so it is read again in physical pixels.
+    fn resize(&mut self, id: u32) {
+        let Some((previous, state)) = self
+            .context
+            .windows
+            .borrow()
+            .get(&id)
+            .map(NativeWindow::update_state)
+        else {
+            return;
+        };
+
+        let event = if previous.scale_factor != state.scale_factor {
+            window::WindowEvent::ScaleFactorChanged {
+                scale_factor: state.scale_factor,
+                new_size: state.size,
+            }
+        } else {
+            window::WindowEvent::Resized(state.size)
+        };
+
+        self.send_event(id, DisplayEvent::WindowEvent(event));
+    }
+
+    fn execute(&mut self, command: Sdl2Command) {
+        match command {
+            Sdl2Command::Display(DisplayCommand::Suspend) => self.suspend(),
+            Sdl2Command::Display(DisplayCommand::Resume) => self.resume(),
+            // Receiving the command already woke the event loop
+            Sdl2Command::Display(DisplayCommand::Wake) => {}
+            Sdl2Command::Display(DisplayCommand::User { window, event }) => {
+                match u32::try_from(window) {
+                    Ok(id) => self.send_event(id, DisplayEvent::User(event)),
+                    Err(_) => warn!("Received an event for an invalid window ID {}.", window),
+                }
+            }
+            Sdl2Command::Display(DisplayCommand::WindowEvent { window, event }) => {
+                match u32::try_from(window) {
+                    Ok(id) => self.send_event(id, DisplayEvent::WindowEvent(event)),
+                    Err(_) => warn!("Received an event for an invalid window ID {}.", window),
+                }
+            }
+            Sdl2Command::Repaint(id) => self.send_event(id, DisplayEvent::Repaint),
+            Sdl2Command::SetImePosition { window, x, y } => {
+                if self.context.windows.borrow().contains_key(&window) {
+                    self.context
+                        .video
+                        .text_input()
+                        .set_rect(Rect::new(x as i32, y as i32, 1, 1));
+                }
+            }
+            Sdl2Command::Destroy(id) => {
+                self.context.windows.borrow_mut().remove(&id);
+                self.windows.remove(&id);
+                self.paused.remove(&id);
+
+                if self.context.windows.borrow().is_empty() {
+                    self.exit = true;
+                }
+            }
+        }
+    }
+
+    fn dispatch(&mut self, event: Event) {
+        match event {
+            Event::User { .. } => {
+                if let Some(command) = event.as_user_event_type::<Sdl2Command>() {
+                    self.execute(command);
+                }
+            }
+
+            Event::AppWillEnterBackground { .. } => self.suspend(),
+
+            Event::AppDidEnterForeground { .. } => self.resume(),
+
+            Event::Window {
+                win_event: WindowEvent::Exposed,
+                window_id,
+                ..
+            } => {
+                self.send_event(window_id, DisplayEvent::Repaint);
+            }
+
+            // Minimized windows are resized to zero on some platforms,
+            // the new size is forwarded before the display is suspended or resumed
+            Event::Window {
+                win_event: WindowEvent::SizeChanged(..),
+                window_id,
+                ..
+            } => {
+                self.resize(window_id);
+            }
+
+            Event::Window {
+                win_event: WindowEvent::Minimized,
+                window_id,
+                ..
+            } => {
+                self.set_paused(window_id, |pause| pause.minimized = true);
+            }
+
+            Event::Window {
+                win_event: WindowEvent::Restored | WindowEvent::Maximized,
+                window_id,
+                ..
+            } => {
+                self.set_paused(window_id, |pause| pause.minimized = false);
+            }
+
+            Event::Window {
+                win_event: WindowEvent::Hidden,
+                window_id,
+                ..
+            } => {
+                self.set_paused(window_id, |pause| pause.hidden = true);
+            }
+
+            Event::Window {
+                win_event: WindowEvent::Shown,
+                window_id,
+                ..
+            } => {
+                self.set_paused(window_id, |pause| pause.hidden = false);
+            }
+
+            ref event => {
+                let Some(window_id) = event.get_window_id() else {
+                    return;
+                };
+
+                // Events of windows the application has already dropped
+                let Some(state) = self.context.get_state(window_id) else {
+                    return;
+                };
+
+                self.send_event(
+                    window_id,
+                    DisplayEvent::WindowEvent(Sdl2WindowEvent(event, state).into()),
+                );
+            }
+        }
+    }
+}
+
+pub struct Sdl2EventLoopWindowFactory<'a> {
+    context: &'a Sdl2Context,
+    proxy: CommandProxy,
+    windows: &'a mut HashMap<u32, DisplayEventSender>,
+    executor: &'a mut LocalExecutor,
+}
+
+impl<'a> EventLoopWindowFactory<Sdl2EventLoop> for Sdl2EventLoopWindowFactory<'a> {
+    type LoopType = Sdl2Context;
+
+    fn create_window_with_channel(&mut self, channel: DisplayEventChannelConfig) -> Sdl2Window {
+        let (sender, receiver) = display_event_channel_with(channel);
+        let window = Sdl2Window::new(self, receiver, self.proxy.clone());
+        let id = window.get_id();
+        self.windows.insert(id, sender);
+        window
+    }
+
+    fn spawn_local(&mut self, future: LocalFuture) {
+        self.executor.spawn(future);
+    }
+
+    fn get_backing_loop(&self) -> &Self::LoopType {
+        self.context
+    }
+}
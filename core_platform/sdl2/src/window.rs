@@ -0,0 +1,378 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::event_loop::{Sdl2Command, Sdl2Context};
+use log::info;
+use pluto_engine_window::event_loop::{
+    CommandProxy, DisplayEvent, DisplayEventProxy, DisplayEventReceiver, EventLoop,
+    EventLoopWindowFactory, NextDisplayEvent,
+};
+use pluto_engine_window::keyboard::Key;
+use pluto_engine_window::mouse::{MouseButton, MouseScrollDelta};
+use pluto_engine_window::window;
+use pluto_engine_window::window::{Preedit, TextInputEvent, Window, WindowEventReceiver};
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+use sdl2::event::{Event, EventSender};
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::mouse::MouseWheelDirection;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_TITLE: &str = "Pluto Engine";
+const DEFAULT_SIZE: (u32, u32) = (1024, 768);
+
+/// The state of a native window, kept up to date by the event loop.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Sdl2WindowState {
+    pub(crate) size: window::PhysicalSize<u32>,
+    pub(crate) scale_factor: f64,
+    pub(crate) ime_allowed: bool,
+}
+
+impl Sdl2WindowState {
+    /// Reads the size of a native window, SDL reports the window size in logical units
+    /// and the drawable size in physical pixels.
+    pub(crate) fn read(window: &sdl2::video::Window, ime_allowed: bool) -> Self {
+        let (width, height) = window.drawable_size();
+        let (logical_width, _) = window.size();
+
+        Self {
+            size: window::PhysicalSize { width, height },
+            scale_factor: if logical_width == 0 {
+                1.0
+            } else {
+                width as f64 / logical_width as f64
+            },
+            ime_allowed,
+        }
+    }
+}
+
+/// The parts of a native SDL window usable from any thread.
+pub struct Sdl2WindowHandle {
+    id: u32,
+    raw: RawWindowHandle,
+    state: Arc<Mutex<Sdl2WindowState>>,
+}
+
+// The raw handle stays valid until the event loop destroys the native window,
+// which only happens once the owning window is dropped.
+unsafe impl Send for Sdl2WindowHandle {}
+unsafe impl Sync for Sdl2WindowHandle {}
+
+impl Sdl2WindowHandle {
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn get_state(&self) -> Sdl2WindowState {
+        *self.state.lock().unwrap()
+    }
+}
+
+unsafe impl HasRawWindowHandle for Sdl2WindowHandle {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        self.raw
+    }
+}
+
+/// A handle to a native SDL window owned by the event loop,
+/// the native window is destroyed once this handle is dropped.
+pub struct Sdl2Window {
+    handle: Sdl2WindowHandle,
+    commands: EventSender,
+    command_proxy: CommandProxy,
+    event_receiver: DisplayEventReceiver,
+}
+
+impl Sdl2Window {
+    fn send_command(&self, command: Sdl2Command) {
+        if let Err(err) = self.commands.push_custom_event(command) {
+            info!(
+                "The window ID {} could not reach the event loop: {err}",
+                self.get_id()
+            );
+        }
+    }
+}
+
+pub struct Sdl2WindowEvent<'a>(pub(crate) &'a Event, pub(crate) Sdl2WindowState);
+
+impl<'a> WindowEventReceiver<Sdl2WindowEvent<'a>> for Sdl2Window {
+    type EventType = Sdl2WindowEvent<'a>;
+}
+
+impl Window for Sdl2Window {
+    type IdType = u32;
+    type BackingType = Sdl2WindowHandle;
+    type SizeType = u32;
+    type LoopType = Sdl2Context;
+
+    fn new<
+        EL: EventLoop<WindowType = Self> + 'static,
+        ELW: EventLoopWindowFactory<EL, LoopType = Self::LoopType>,
+    >(
+        event_loop: &ELW,
+        event_receiver: DisplayEventReceiver,
+        command_proxy: CommandProxy,
+    ) -> Self {
+        let context = event_loop.get_backing_loop();
+        let (width, height) = DEFAULT_SIZE;
+        let window = context
+            .video()
+            .window(DEFAULT_TITLE, width, height)
+            .resizable()
+            .allow_highdpi()
+            .build()
+            .unwrap();
+
+        let state = Arc::new(Mutex::new(Sdl2WindowState::read(&window, false)));
+        let handle = Sdl2WindowHandle {
+            id: window.id(),
+            raw: window.raw_window_handle(),
+            state: Arc::clone(&state),
+        };
+        context.insert_window(window, state);
+
+        Self {
+            handle,
+            commands: context.event_sender(),
+            command_proxy,
+            event_receiver,
+        }
+    }
+
+    fn receive_event(&self) -> DisplayEvent {
+        self.event_receiver.recv().unwrap_or_else(|e| {
+            info!(
+                "The window ID {:?} channel was disconnected: {e}",
+                self.get_id()
+            );
+
+            DisplayEvent::Disconnected
+        })
+    }
+
+    fn next_event(&self) -> NextDisplayEvent<'_> {
+        self.event_receiver.next_event()
+    }
+
+    /// SDL has no redraw requests, the event loop sends the repaint event right away.
+    fn request_repaint(&self) {
+        self.send_command(Sdl2Command::Repaint(self.get_id()))
+    }
+
+    fn create_event_proxy(&self) -> DisplayEventProxy {
+        DisplayEventProxy::new(self.get_id().into(), self.command_proxy.clone())
+    }
+
+    fn get_id(&self) -> Self::IdType {
+        self.handle.id
+    }
+
+    fn get_size(&self) -> window::PhysicalSize<u32> {
+        self.handle.get_state().size
+    }
+
+    fn get_scale_factor(&self) -> f64 {
+        self.handle.get_state().scale_factor
+    }
+
+    /// SDL always receives text input, composition events are only forwarded while allowed.
+    fn set_ime_allowed(&self, allowed: bool) {
+        self.handle.state.lock().unwrap().ime_allowed = allowed;
+    }
+
+    fn set_ime_position(&self, x: f64, y: f64) {
+        self.send_command(Sdl2Command::SetImePosition {
+            window: self.get_id(),
+            x,
+            y,
+        })
+    }
+
+    fn get_backing_window(&self) -> &Self::BackingType {
+        &self.handle
+    }
+}
+
+impl Drop for Sdl2Window {
+    fn drop(&mut self) {
+        self.send_command(Sdl2Command::Destroy(self.get_id()));
+    }
+}
+
+unsafe impl HasRawWindowHandle for Sdl2Window {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        self.get_backing_window().raw_window_handle()
+    }
+}
+
+pub struct Sdl2Key(pub Keycode);
+
+macro_rules! map_keys {
+    ($code:expr, $scancode:expr; $($same:ident),*; $($sdl:ident => $pluto:ident),*) => {
+        match $code {
+            $(Keycode::$same => Key::$same,)*
+            $(Keycode::$sdl => Key::$pluto,)*
+            _ => Key::Other($scancode),
+        }
+    };
+}
+
+impl Sdl2Key {
+    /// Converts the key code, falling back to the scancode for keys without a [`Key`] variant.
+    pub fn into_key(self, scancode: u32) -> Key {
+        map_keys!(self.0, scancode;
+            A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+            F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+            Escape, Space, Tab, Insert, Delete, Home, End, PageUp, PageDown,
+            Left, Right, Up, Down, Backspace, PrintScreen;
+            Num0 => Digit0, Num1 => Digit1, Num2 => Digit2, Num3 => Digit3, Num4 => Digit4,
+            Num5 => Digit5, Num6 => Digit6, Num7 => Digit7, Num8 => Digit8, Num9 => Digit9,
+            Return => Enter,
+            LShift => LeftShift, RShift => RightShift,
+            LCtrl => LeftControl, RCtrl => RightControl,
+            LAlt => LeftAlt, RAlt => RightAlt
+        )
+    }
+}
+
+fn convert_key(keycode: Option<Keycode>, scancode: Option<Scancode>) -> Key {
+    let scancode = scancode.map_or(0, |code| code as u32);
+    keycode.map_or(Key::Other(scancode), |code| {
+        Sdl2Key(code).into_key(scancode)
+    })
+}
+
+/// Converts the composition cursor SDL reports in characters to a byte range of the text.
+fn preedit_cursor(text: &str, start: i32, length: i32) -> Option<(usize, usize)> {
+    let start = usize::try_from(start).ok()?;
+    let end = start + usize::try_from(length).unwrap_or(0);
+    let byte_offset = |chars: usize| {
+        text.char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(text.len()))
+            .nth(chars)
+    };
+
+    Some((byte_offset(start)?, byte_offset(end)?))
+}
+
+impl From<Sdl2WindowEvent<'_>> for window::WindowEvent {
+    fn from(e: Sdl2WindowEvent) -> Self {
+        let Sdl2WindowEvent(event, state) = e;
+
+        match event {
+            Event::Window {
+                win_event: sdl2::event::WindowEvent::Close,
+                ..
+            } => window::WindowEvent::CloseRequested,
+            Event::Window {
+                win_event: sdl2::event::WindowEvent::Leave,
+                ..
+            } => window::WindowEvent::CursorLeft,
+            Event::KeyDown {
+                keycode, scancode, ..
+            } => window::WindowEvent::KeyboardInput {
+                key: convert_key(*keycode, *scancode),
+                pressed: true,
+            },
+            Event::KeyUp {
+                keycode, scancode, ..
+            } => window::WindowEvent::KeyboardInput {
+                key: convert_key(*keycode, *scancode),
+                pressed: false,
+            },
+            Event::TextInput { text, .. } => {
+                window::WindowEvent::TextInput(TextInputEvent::Commit(text.clone()))
+            }
+            Event::TextEditing {
+                text,
+                start,
+                length,
+                ..
+            } if state.ime_allowed => {
+                window::WindowEvent::TextInput(TextInputEvent::Preedit(Preedit {
+                    text: text.clone(),
+                    cursor: preedit_cursor(text, *start, *length),
+                }))
+            }
+            // Positions are in logical units on high DPI displays
+            Event::MouseMotion { x, y, .. } => window::WindowEvent::CursorMoved {
+                x: *x as f64 * state.scale_factor,
+                y: *y as f64 * state.scale_factor,
+            },
+            Event::MouseButtonDown { mouse_btn, .. } => window::WindowEvent::MouseInput {
+                button: convert_mouse_button(*mouse_btn),
+                pressed: true,
+            },
+            Event::MouseButtonUp { mouse_btn, .. } => window::WindowEvent::MouseInput {
+                button: convert_mouse_button(*mouse_btn),
+                pressed: false,
+            },
+            Event::MouseWheel {
+                x, y, direction, ..
+            } => {
+                let sign = match direction {
+                    MouseWheelDirection::Flipped => -1.0,
+                    _ => 1.0,
+                };
+
+                window::WindowEvent::MouseWheel(MouseScrollDelta::Lines {
+                    x: *x as f32 * sign,
+                    y: *y as f32 * sign,
+                })
+            }
+            Event::DropFile { filename, .. } => {
+                window::WindowEvent::FileDropped(PathBuf::from(filename))
+            }
+            _ => window::WindowEvent::Unknown,
+        }
+    }
+}
+
+fn convert_mouse_button(button: sdl2::mouse::MouseButton) -> MouseButton {
+    match button {
+        sdl2::mouse::MouseButton::Left => MouseButton::Left,
+        sdl2::mouse::MouseButton::Right => MouseButton::Right,
+        sdl2::mouse::MouseButton::Middle => MouseButton::Middle,
+        button => MouseButton::Other(button as u16),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::window::preedit_cursor;
+
+    /// The composition cursor of a text with multi-byte characters is converted.
+    /// The character range should map to the byte offsets of the characters.
+    #[test]
+    fn test_preedit_cursor_bytes() {
+        assert_eq!(preedit_cursor("日本語", 1, 1), Some((3, 6)));
+        assert_eq!(preedit_cursor("日本語", 3, 0), Some((9, 9)));
+        assert_eq!(preedit_cursor("abc", 4, 0), None);
+        assert_eq!(preedit_cursor("abc", -1, 0), None);
+    }
+}
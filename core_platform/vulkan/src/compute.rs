@@ -0,0 +1,68 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::device::DeviceContext;
+use crate::pipeline::VulkanPipelineLayout;
+use ash::vk;
+use pluto_engine_render::compute::ComputePipeline;
+use std::sync::Arc;
+
+pub struct VulkanComputePipeline<'a> {
+    device: Arc<DeviceContext>,
+    pipeline: vk::Pipeline,
+    layout: VulkanPipelineLayout<'a>,
+}
+
+impl<'a> VulkanComputePipeline<'a> {
+    pub(crate) fn new(
+        device: Arc<DeviceContext>,
+        pipeline: vk::Pipeline,
+        layout: VulkanPipelineLayout<'a>,
+    ) -> Self {
+        Self {
+            device,
+            pipeline,
+            layout,
+        }
+    }
+
+    /// Returns the layout of the pipeline, with the storage buffers and uniforms at set 0.
+    pub fn get_layout(&self) -> &VulkanPipelineLayout<'a> {
+        &self.layout
+    }
+}
+
+impl Drop for VulkanComputePipeline<'_> {
+    fn drop(&mut self) {
+        unsafe { self.device.device.destroy_pipeline(self.pipeline, None) };
+    }
+}
+
+impl<'a> ComputePipeline<'_> for VulkanComputePipeline<'a> {
+    type BackingType = vk::Pipeline;
+
+    fn get_backing_compute_pipeline(&self) -> &Self::BackingType {
+        &self.pipeline
+    }
+}
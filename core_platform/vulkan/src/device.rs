@@ -0,0 +1,962 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::compute::VulkanComputePipeline;
+use crate::instance::VulkanContext;
+use crate::mesh::vertex_input;
+use crate::pipeline::{
+    create_descriptor_set_layout, create_render_pass, shader_stages, VulkanPipeline,
+    VulkanPipelineLayout,
+};
+use crate::shader::{wgsl_to_spirv, VulkanShader};
+use crate::texture::{VulkanTexture, VulkanTextureFormat};
+use ash::extensions::khr;
+use ash::vk;
+use pluto_engine_render::compute::ComputePipelineCreateInfo;
+use pluto_engine_render::device::{
+    CommandBuffer, CommandBufferBuilder, Device, DeviceError, DeviceTextureFactory, PhysicalDevice,
+    Queue,
+};
+use pluto_engine_render::image::TextureImage;
+use pluto_engine_render::pipeline::{
+    BlendMode, CullMode, FrontFace, PipelineCreateInfo, PipelineLayout, PolygonMode,
+    PrimitiveTopology,
+};
+use pluto_engine_render::shader::ShaderCode;
+use pluto_engine_render::target::DepthFormat;
+use pluto_engine_render::texture::TextureFormat;
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+/// A physical device and the queue family devices created from it render and present with.
+#[derive(Clone)]
+pub struct VulkanAdapter {
+    pub(crate) context: Arc<VulkanContext>,
+    pub(crate) physical_device: vk::PhysicalDevice,
+    pub(crate) queue_family_index: u32,
+}
+
+impl VulkanAdapter {
+    pub fn get_physical_device(&self) -> vk::PhysicalDevice {
+        self.physical_device
+    }
+
+    pub fn get_queue_family_index(&self) -> u32 {
+        self.queue_family_index
+    }
+
+    pub fn get_instance(&self) -> &ash::Instance {
+        &self.context.instance
+    }
+}
+
+/// The logical device, kept alive by every object created from it.
+pub(crate) struct DeviceContext {
+    pub(crate) device: ash::Device,
+    pub(crate) adapter: VulkanAdapter,
+    pub(crate) swapchain_loader: khr::Swapchain,
+    /// The queue surface textures are presented with.
+    pub(crate) queue: vk::Queue,
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    limits: vk::PhysicalDeviceLimits,
+    command_pool: Mutex<vk::CommandPool>,
+}
+
+impl DeviceContext {
+    pub(crate) fn allocate_command_buffer(&self) -> vk::CommandBuffer {
+        let command_pool = self.command_pool.lock().unwrap();
+
+        unsafe {
+            self.device.allocate_command_buffers(
+                &vk::CommandBufferAllocateInfo::builder()
+                    .command_pool(*command_pool)
+                    .level(vk::CommandBufferLevel::PRIMARY)
+                    .command_buffer_count(1),
+            )
+        }
+        .expect("Failed to allocate a command buffer")[0]
+    }
+
+    pub(crate) fn free_command_buffer(&self, command_buffer: vk::CommandBuffer) {
+        let command_pool = self.command_pool.lock().unwrap();
+
+        unsafe {
+            self.device
+                .free_command_buffers(*command_pool, &[command_buffer])
+        };
+    }
+
+    /// Allocates memory of the first memory type with the properties.
+    ///
+    /// ***Panics*** if no such memory type exists or the device is out of memory.
+    pub(crate) fn allocate_memory(
+        &self,
+        requirements: vk::MemoryRequirements,
+        properties: vk::MemoryPropertyFlags,
+    ) -> vk::DeviceMemory {
+        let memory_types = &self.memory_properties.memory_types
+            [..self.memory_properties.memory_type_count as usize];
+
+        let memory_type_index = (0..memory_types.len() as u32)
+            .find(|&index| {
+                requirements.memory_type_bits & (1 << index) != 0
+                    && memory_types[index as usize]
+                        .property_flags
+                        .contains(properties)
+            })
+            .unwrap_or_else(|| panic!("No memory type with the properties {:?}", properties));
+
+        unsafe {
+            self.device.allocate_memory(
+                &vk::MemoryAllocateInfo::builder()
+                    .allocation_size(requirements.size)
+                    .memory_type_index(memory_type_index),
+                None,
+            )
+        }
+        .expect("Failed to allocate device memory")
+    }
+
+    /// *Returns the format backing the depth format, falling back to a 32-bit float format
+    /// if the device cannot render to 24-bit depth.*
+    pub(crate) fn depth_format(&self, depth_format: DepthFormat) -> vk::Format {
+        match depth_format {
+            DepthFormat::Depth32Float => vk::Format::D32_SFLOAT,
+            DepthFormat::Depth24Plus => {
+                let properties = unsafe {
+                    self.adapter
+                        .context
+                        .instance
+                        .get_physical_device_format_properties(
+                            self.adapter.physical_device,
+                            vk::Format::X8_D24_UNORM_PACK32,
+                        )
+                };
+
+                if properties
+                    .optimal_tiling_features
+                    .contains(vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)
+                {
+                    vk::Format::X8_D24_UNORM_PACK32
+                } else {
+                    vk::Format::D32_SFLOAT
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DeviceContext {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = self.device.device_wait_idle();
+            self.device
+                .destroy_command_pool(*self.command_pool.get_mut().unwrap(), None);
+            self.device.destroy_device(None);
+        }
+    }
+}
+
+/// A command buffer submitted to a queue, freed once the fence is signaled.
+struct InFlight {
+    fence: vk::Fence,
+    command_buffer: vk::CommandBuffer,
+}
+
+pub struct VulkanQueue<'a> {
+    device: Arc<DeviceContext>,
+    queue: vk::Queue,
+    in_flight: Mutex<Vec<InFlight>>,
+    parent: PhantomData<&'a ()>,
+}
+
+impl<'a> VulkanQueue<'a> {
+    /// Submits the command buffer, which is freed once the device has executed it.
+    pub fn submit(&self, command_buffer: VulkanCommandBuffer<'_>) {
+        self.submit_fenced(command_buffer);
+    }
+
+    /// Submits the command buffer and blocks until the device has executed it.
+    pub fn submit_and_wait(&self, command_buffer: VulkanCommandBuffer<'_>) {
+        let fence = self.submit_fenced(command_buffer);
+
+        unsafe { self.device.device.wait_for_fences(&[fence], true, u64::MAX) }
+            .expect("Failed to wait for the submission");
+    }
+
+    fn submit_fenced(&self, command_buffer: VulkanCommandBuffer<'_>) -> vk::Fence {
+        let device = &self.device.device;
+        let mut in_flight = self.in_flight.lock().unwrap();
+        self.reclaim(&mut in_flight);
+
+        let command_buffer = command_buffer.get_backing_command_buffer();
+        let fence = unsafe { device.create_fence(&vk::FenceCreateInfo::default(), None) }
+            .expect("Failed to create a fence");
+
+        let command_buffers = [command_buffer];
+        unsafe {
+            device.queue_submit(
+                self.queue,
+                &[vk::SubmitInfo::builder()
+                    .command_buffers(&command_buffers)
+                    .build()],
+                fence,
+            )
+        }
+        .expect("Failed to submit the command buffer");
+
+        in_flight.push(InFlight {
+            fence,
+            command_buffer,
+        });
+
+        fence
+    }
+
+    /// Frees the command buffers the device has finished executing.
+    fn reclaim(&self, in_flight: &mut Vec<InFlight>) {
+        let device = &self.device.device;
+
+        in_flight.retain(|submission| {
+            let done = unsafe { device.get_fence_status(submission.fence) }.unwrap_or(true);
+
+            if done {
+                unsafe { device.destroy_fence(submission.fence, None) };
+                self.device.free_command_buffer(submission.command_buffer);
+            }
+
+            !done
+        });
+    }
+}
+
+impl Drop for VulkanQueue<'_> {
+    fn drop(&mut self) {
+        let in_flight = self.in_flight.get_mut().unwrap();
+        let fences = in_flight
+            .iter()
+            .map(|submission| submission.fence)
+            .collect::<Vec<_>>();
+
+        if !fences.is_empty() {
+            let _ = unsafe { self.device.device.wait_for_fences(&fences, true, u64::MAX) };
+        }
+
+        let mut in_flight = std::mem::take(in_flight);
+        self.reclaim(&mut in_flight);
+    }
+}
+
+impl<'a> Queue<'_> for VulkanQueue<'a> {
+    type BackingType = vk::Queue;
+
+    fn get_backing_queue(&self) -> &Self::BackingType {
+        &self.queue
+    }
+}
+
+pub struct VulkanPhysicalDevice<'a>(VulkanAdapter, PhantomData<&'a ()>);
+
+impl<'a> PhysicalDevice<'_> for VulkanPhysicalDevice<'a> {
+    type BackingType = VulkanAdapter;
+
+    type DeviceType = VulkanDevice<'a>;
+    type QueueType = VulkanQueue<'a>;
+
+    fn new(adapter: Self::BackingType) -> Self {
+        Self(adapter, PhantomData)
+    }
+
+    fn get_backing_physical_device(&self) -> &Self::BackingType {
+        &self.0
+    }
+
+    fn create_device_and_queue(&self) -> Result<(Self::DeviceType, Self::QueueType), DeviceError> {
+        let adapter = &self.0;
+        let instance = &adapter.context.instance;
+
+        let supported = unsafe { instance.get_physical_device_features(adapter.physical_device) };
+        // Line polygons are only used by the wireframe debug mode, so they are optional
+        let features = vk::PhysicalDeviceFeatures::builder()
+            .fill_mode_non_solid(supported.fill_mode_non_solid == vk::TRUE);
+
+        let queue_priorities = [1.0];
+        let queue_infos = [vk::DeviceQueueCreateInfo::builder()
+            .queue_family_index(adapter.queue_family_index)
+            .queue_priorities(&queue_priorities)
+            .build()];
+        let extension_names = [khr::Swapchain::name().as_ptr()];
+
+        let device = unsafe {
+            instance.create_device(
+                adapter.physical_device,
+                &vk::DeviceCreateInfo::builder()
+                    .queue_create_infos(&queue_infos)
+                    .enabled_extension_names(&extension_names)
+                    .enabled_features(&features),
+                None,
+            )
+        }
+        .map_err(|err| DeviceError::RequestDevice(err.to_string()))?;
+
+        let command_pool = unsafe {
+            device.create_command_pool(
+                &vk::CommandPoolCreateInfo::builder()
+                    .queue_family_index(adapter.queue_family_index)
+                    .flags(vk::CommandPoolCreateFlags::TRANSIENT),
+                None,
+            )
+        }
+        .map_err(|err| {
+            unsafe { device.destroy_device(None) };
+            DeviceError::RequestDevice(err.to_string())
+        })?;
+
+        let queue = unsafe { device.get_device_queue(adapter.queue_family_index, 0) };
+        let properties =
+            unsafe { instance.get_physical_device_properties(adapter.physical_device) };
+
+        let context = Arc::new(DeviceContext {
+            swapchain_loader: khr::Swapchain::new(instance, &device),
+            device,
+            adapter: adapter.clone(),
+            queue,
+            memory_properties: unsafe {
+                instance.get_physical_device_memory_properties(adapter.physical_device)
+            },
+            limits: properties.limits,
+            command_pool: Mutex::new(command_pool),
+        });
+
+        Ok((
+            VulkanDevice(Arc::clone(&context), PhantomData),
+            VulkanQueue {
+                device: context,
+                queue,
+                in_flight: Mutex::new(Vec::new()),
+                parent: PhantomData,
+            },
+        ))
+    }
+}
+
+/// A Vulkan device, creating its objects from the same descriptions as the wgpu backend.
+///
+/// *Materials are not supported yet, pipelines cannot be created with them.*
+pub struct VulkanDevice<'a>(pub(crate) Arc<DeviceContext>, PhantomData<&'a ()>);
+
+impl<'a> VulkanDevice<'a> {
+    pub fn get_adapter(&self) -> &VulkanAdapter {
+        &self.0.adapter
+    }
+
+    /// Creates a pipeline layout with the descriptor set layouts and push constant ranges.
+    fn build_pipeline_layout(
+        &self,
+        set_layouts: Vec<vk::DescriptorSetLayout>,
+        push_constant_ranges: &[vk::PushConstantRange],
+    ) -> VulkanPipelineLayout<'a> {
+        let layout = unsafe {
+            self.0.device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::builder()
+                    .set_layouts(&set_layouts)
+                    .push_constant_ranges(push_constant_ranges),
+                None,
+            )
+        }
+        .expect("Failed to create a pipeline layout");
+
+        VulkanPipelineLayout::new(Arc::clone(&self.0), layout, set_layouts)
+    }
+}
+
+impl<'a> Device<'_> for VulkanDevice<'a> {
+    type BackingType = ash::Device;
+    type ShaderType = VulkanShader<'a>;
+    type PipelineLayoutType = VulkanPipelineLayout<'a>;
+    type PipelineType = VulkanPipeline<'a>;
+    type CommandBufferBuilderType = VulkanCommandBufferBuilder<'a>;
+    type CommandBufferType = VulkanCommandBuffer<'a>;
+    type ImageFormatType = VulkanTextureFormat;
+    type TextureType = VulkanTexture<'a>;
+    type ComputePipelineType = VulkanComputePipeline<'a>;
+
+    fn get_backing_device(&self) -> &Self::BackingType {
+        &self.0.device
+    }
+
+    fn begin_command_buffer(&self) -> Self::CommandBufferBuilderType {
+        let command_buffer = self.0.allocate_command_buffer();
+
+        unsafe {
+            self.0.device.begin_command_buffer(
+                command_buffer,
+                &vk::CommandBufferBeginInfo::builder()
+                    .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+            )
+        }
+        .expect("Failed to begin a command buffer");
+
+        VulkanCommandBufferBuilder {
+            device: Arc::clone(&self.0),
+            command_buffer,
+            parent: PhantomData,
+        }
+    }
+
+    fn create_pipeline_layout(&self, _shader: &Self::ShaderType) -> Self::PipelineLayoutType {
+        self.build_pipeline_layout(Vec::new(), &[])
+    }
+
+    fn create_pipeline(
+        &self,
+        info: &PipelineCreateInfo<
+            '_,
+            Self::PipelineLayoutType,
+            Self::ShaderType,
+            Self::ImageFormatType,
+        >,
+    ) -> Self::PipelineType {
+        assert!(
+            info.material.is_none(),
+            "Materials are not supported by the Vulkan backend"
+        );
+
+        let device = &self.0.device;
+        let label = info
+            .label
+            .or_else(|| info.shader.get_label())
+            .unwrap_or("Render Pipeline");
+
+        let uniform_layout = (!info.uniforms.is_empty()).then(|| {
+            create_descriptor_set_layout(
+                device,
+                info.uniforms.iter().map(|uniform| {
+                    (
+                        uniform.binding,
+                        vk::DescriptorType::UNIFORM_BUFFER,
+                        shader_stages(uniform.visibility),
+                    )
+                }),
+            )
+        });
+
+        let push_constant_ranges = info
+            .push_constants
+            .map(|layout| {
+                let max_size = self.0.limits.max_push_constants_size;
+                assert!(
+                    layout.size <= max_size,
+                    "Push constants are limited to {} bytes on this device",
+                    max_size
+                );
+
+                vk::PushConstantRange {
+                    stage_flags: shader_stages(layout.visibility),
+                    offset: 0,
+                    size: layout.size,
+                }
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        // Uniforms and push constants have to be part of the layout the pipeline is created with
+        let layout = if uniform_layout.is_some() || !push_constant_ranges.is_empty() {
+            self.build_pipeline_layout(uniform_layout.into_iter().collect(), &push_constant_ranges)
+        } else {
+            info.pipeline_layout.clone()
+        };
+
+        assert!(
+            info.additional_color_targets.is_empty(),
+            "Multiple color targets are not supported by the Vulkan backend yet"
+        );
+
+        let depth_format = info
+            .depth_format
+            .map(|depth_format| self.0.depth_format(depth_format));
+        let render_pass = create_render_pass(
+            device,
+            info.texture_format.get_backing_format(),
+            depth_format,
+        );
+
+        let stages = [
+            vk::PipelineShaderStageCreateInfo::builder()
+                .stage(vk::ShaderStageFlags::VERTEX)
+                .module(info.shader.module.module)
+                .name(&info.shader.vertex_entry)
+                .build(),
+            vk::PipelineShaderStageCreateInfo::builder()
+                .stage(vk::ShaderStageFlags::FRAGMENT)
+                .module(info.shader.module.module)
+                .name(&info.shader.fragment_entry)
+                .build(),
+        ];
+
+        let (bindings, attributes) = vertex_input(info.buffer_layout);
+        let vertex_input = vk::PipelineVertexInputStateCreateInfo::builder()
+            .vertex_binding_descriptions(&bindings)
+            .vertex_attribute_descriptions(&attributes);
+
+        let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::builder().topology(
+            match info.primitive.topology {
+                PrimitiveTopology::PointList => vk::PrimitiveTopology::POINT_LIST,
+                PrimitiveTopology::LineList => vk::PrimitiveTopology::LINE_LIST,
+                PrimitiveTopology::LineStrip => vk::PrimitiveTopology::LINE_STRIP,
+                PrimitiveTopology::TriangleList => vk::PrimitiveTopology::TRIANGLE_LIST,
+                PrimitiveTopology::TriangleStrip => vk::PrimitiveTopology::TRIANGLE_STRIP,
+            },
+        );
+
+        // The viewport and scissor are set while recording, to match the target
+        let viewport = vk::PipelineViewportStateCreateInfo::builder()
+            .viewport_count(1)
+            .scissor_count(1);
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic = vk::PipelineDynamicStateCreateInfo::builder().dynamic_states(&dynamic_states);
+
+        let rasterization = vk::PipelineRasterizationStateCreateInfo::builder()
+            .polygon_mode(match info.primitive.polygon_mode {
+                PolygonMode::Fill => vk::PolygonMode::FILL,
+                PolygonMode::Line => vk::PolygonMode::LINE,
+                PolygonMode::Point => vk::PolygonMode::POINT,
+            })
+            .cull_mode(match info.primitive.cull_mode {
+                CullMode::None => vk::CullModeFlags::NONE,
+                CullMode::Front => vk::CullModeFlags::FRONT,
+                CullMode::Back => vk::CullModeFlags::BACK,
+            })
+            .front_face(match info.primitive.front_face {
+                FrontFace::Ccw => vk::FrontFace::COUNTER_CLOCKWISE,
+                FrontFace::Cw => vk::FrontFace::CLOCKWISE,
+            })
+            .line_width(1.0);
+
+        let multisample = vk::PipelineMultisampleStateCreateInfo::builder()
+            .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+
+        let depth_stencil = vk::PipelineDepthStencilStateCreateInfo::builder()
+            .depth_test_enable(depth_format.is_some())
+            .depth_write_enable(depth_format.is_some())
+            .depth_compare_op(vk::CompareOp::LESS);
+
+        let (src_color, dst_color, src_alpha, dst_alpha) = match info.blend {
+            BlendMode::Replace | BlendMode::Alpha => (
+                vk::BlendFactor::SRC_ALPHA,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            ),
+            BlendMode::PremultipliedAlpha => (
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            ),
+            BlendMode::Additive => (
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE,
+            ),
+        };
+
+        let blend_attachments = [vk::PipelineColorBlendAttachmentState::builder()
+            .blend_enable(info.blend != BlendMode::Replace)
+            .src_color_blend_factor(src_color)
+            .dst_color_blend_factor(dst_color)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(src_alpha)
+            .dst_alpha_blend_factor(dst_alpha)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .color_write_mask(
+                vk::ColorComponentFlags::R
+                    | vk::ColorComponentFlags::G
+                    | vk::ColorComponentFlags::B
+                    | vk::ColorComponentFlags::A,
+            )
+            .build()];
+        let color_blend =
+            vk::PipelineColorBlendStateCreateInfo::builder().attachments(&blend_attachments);
+
+        let create_info = vk::GraphicsPipelineCreateInfo::builder()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input)
+            .input_assembly_state(&input_assembly)
+            .viewport_state(&viewport)
+            .rasterization_state(&rasterization)
+            .multisample_state(&multisample)
+            .depth_stencil_state(&depth_stencil)
+            .color_blend_state(&color_blend)
+            .dynamic_state(&dynamic)
+            .layout(*layout.get_backing_pipeline_layout())
+            .render_pass(render_pass)
+            .subpass(0)
+            .build();
+
+        let pipeline = unsafe {
+            device.create_graphics_pipelines(vk::PipelineCache::null(), &[create_info], None)
+        }
+        .map_err(|(_, err)| err)
+        .unwrap_or_else(|err| {
+            panic!(
+                "Failed to create the render pipeline \"{}\": {}",
+                label, err
+            )
+        })[0];
+
+        VulkanPipeline::new(
+            Arc::clone(&self.0),
+            pipeline,
+            render_pass,
+            layout,
+            !info.uniforms.is_empty(),
+        )
+    }
+
+    fn create_shader(&self, shader_code: &ShaderCode<'_>) -> Self::ShaderType {
+        match *shader_code {
+            ShaderCode::Wgsl {
+                code,
+                vertex_entry,
+                fragment_entry,
+                label,
+            } => {
+                let words = wgsl_to_spirv(code).unwrap_or_else(|err| {
+                    panic!(
+                        "Failed to create the shader \"{}\": {}",
+                        label.unwrap_or("unlabeled"),
+                        err
+                    )
+                });
+
+                let module = unsafe {
+                    self.0.device.create_shader_module(
+                        &vk::ShaderModuleCreateInfo::builder().code(&words),
+                        None,
+                    )
+                }
+                .expect("Failed to create a shader module");
+
+                VulkanShader::new(
+                    Arc::clone(&self.0),
+                    module,
+                    vertex_entry,
+                    fragment_entry,
+                    label,
+                )
+            }
+        }
+    }
+
+    fn create_compute_pipeline(
+        &self,
+        info: &ComputePipelineCreateInfo<'_, Self::ShaderType>,
+    ) -> Self::ComputePipelineType {
+        let device = &self.0.device;
+        let label = info
+            .label
+            .or_else(|| info.shader.get_label())
+            .unwrap_or("Compute Pipeline");
+
+        let storage_bindings = info.storage_buffers.iter().map(|storage| {
+            (
+                storage.binding,
+                vk::DescriptorType::STORAGE_BUFFER,
+                vk::ShaderStageFlags::COMPUTE,
+            )
+        });
+        let uniform_bindings = info.uniforms.iter().map(|uniform| {
+            (
+                uniform.binding,
+                vk::DescriptorType::UNIFORM_BUFFER,
+                vk::ShaderStageFlags::COMPUTE,
+            )
+        });
+
+        let set_layout =
+            (!info.storage_buffers.is_empty() || !info.uniforms.is_empty()).then(|| {
+                create_descriptor_set_layout(device, storage_bindings.chain(uniform_bindings))
+            });
+        let layout = self.build_pipeline_layout(set_layout.into_iter().collect(), &[]);
+
+        let entry_point = CString::new(info.entry_point).unwrap();
+        let create_info = vk::ComputePipelineCreateInfo::builder()
+            .stage(
+                vk::PipelineShaderStageCreateInfo::builder()
+                    .stage(vk::ShaderStageFlags::COMPUTE)
+                    .module(info.shader.module.module)
+                    .name(&entry_point)
+                    .build(),
+            )
+            .layout(*layout.get_backing_pipeline_layout())
+            .build();
+
+        let pipeline = unsafe {
+            device.create_compute_pipelines(vk::PipelineCache::null(), &[create_info], None)
+        }
+        .map_err(|(_, err)| err)
+        .unwrap_or_else(|err| {
+            panic!(
+                "Failed to create the compute pipeline \"{}\": {}",
+                label, err
+            )
+        })[0];
+
+        VulkanComputePipeline::new(Arc::clone(&self.0), pipeline, layout)
+    }
+
+    fn wait_idle(&self) {
+        let _ = unsafe { self.0.device.device_wait_idle() };
+    }
+}
+
+impl<'a> DeviceTextureFactory<'_, VulkanQueue<'a>> for VulkanDevice<'a> {
+    fn create_texture_from_pixels(
+        &self,
+        queue: &VulkanQueue<'a>,
+        image: &TextureImage,
+    ) -> Self::TextureType {
+        let device = &self.0.device;
+        let size = image.get_size();
+        let format = if image.srgb {
+            vk::Format::R8G8B8A8_SRGB
+        } else {
+            vk::Format::R8G8B8A8_UNORM
+        };
+        let mip_levels = image.levels.len() as u32;
+
+        let texture = VulkanTexture::new(
+            Arc::clone(&self.0),
+            size,
+            format,
+            mip_levels,
+            vk::ImageUsageFlags::SAMPLED
+                | vk::ImageUsageFlags::TRANSFER_DST
+                | vk::ImageUsageFlags::TRANSFER_SRC,
+        );
+
+        // All levels are staged in one buffer, in order
+        let staged_size = image
+            .levels
+            .iter()
+            .map(|level| level.data.len() as u64)
+            .sum::<u64>();
+
+        let staging_buffer = unsafe {
+            device.create_buffer(
+                &vk::BufferCreateInfo::builder()
+                    .size(staged_size)
+                    .usage(vk::BufferUsageFlags::TRANSFER_SRC)
+                    .sharing_mode(vk::SharingMode::EXCLUSIVE),
+                None,
+            )
+        }
+        .expect("Failed to create a staging buffer");
+
+        let staging_memory = self.0.allocate_memory(
+            unsafe { device.get_buffer_memory_requirements(staging_buffer) },
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+
+        let mut copies = Vec::with_capacity(image.levels.len());
+
+        unsafe {
+            device
+                .bind_buffer_memory(staging_buffer, staging_memory, 0)
+                .expect("Failed to bind the staging memory");
+
+            let mapped = device
+                .map_memory(staging_memory, 0, staged_size, vk::MemoryMapFlags::empty())
+                .expect("Failed to map the staging memory") as *mut u8;
+
+            let mut offset = 0;
+            for (mip_level, level) in image.levels.iter().enumerate() {
+                std::ptr::copy_nonoverlapping(
+                    level.data.as_ptr(),
+                    mapped.add(offset),
+                    level.data.len(),
+                );
+
+                copies.push(vk::BufferImageCopy {
+                    buffer_offset: offset as u64,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: mip_level as u32,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    image_offset: vk::Offset3D::default(),
+                    image_extent: vk::Extent3D {
+                        width: level.size.width,
+                        height: level.size.height,
+                        depth: 1,
+                    },
+                });
+
+                offset += level.data.len();
+            }
+
+            device.unmap_memory(staging_memory);
+        }
+
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: mip_levels,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let barrier = |old_layout, new_layout, src_access_mask, dst_access_mask| {
+            vk::ImageMemoryBarrier::builder()
+                .old_layout(old_layout)
+                .new_layout(new_layout)
+                .src_access_mask(src_access_mask)
+                .dst_access_mask(dst_access_mask)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(texture.image)
+                .subresource_range(subresource_range)
+                .build()
+        };
+
+        let mut command_buffer = self.begin_command_buffer();
+        let recording = *command_buffer.get_backing_command_buffer_builder();
+
+        unsafe {
+            device.cmd_pipeline_barrier(
+                recording,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[barrier(
+                    vk::ImageLayout::UNDEFINED,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::AccessFlags::empty(),
+                    vk::AccessFlags::TRANSFER_WRITE,
+                )],
+            );
+
+            device.cmd_copy_buffer_to_image(
+                recording,
+                staging_buffer,
+                texture.image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &copies,
+            );
+
+            device.cmd_pipeline_barrier(
+                recording,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[barrier(
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    vk::AccessFlags::TRANSFER_WRITE,
+                    vk::AccessFlags::SHADER_READ,
+                )],
+            );
+        }
+
+        queue.submit_and_wait(command_buffer.build());
+
+        unsafe {
+            device.destroy_buffer(staging_buffer, None);
+            device.free_memory(staging_memory, None);
+        }
+
+        texture
+    }
+}
+
+/// A command buffer being recorded, freed if dropped before it is built.
+pub struct VulkanCommandBufferBuilder<'a> {
+    device: Arc<DeviceContext>,
+    command_buffer: vk::CommandBuffer,
+    parent: PhantomData<&'a ()>,
+}
+
+impl<'a> CommandBufferBuilder<'_, VulkanCommandBuffer<'a>> for VulkanCommandBufferBuilder<'a> {
+    type BackingType = vk::CommandBuffer;
+
+    fn build(mut self) -> VulkanCommandBuffer<'a> {
+        let command_buffer = std::mem::replace(&mut self.command_buffer, vk::CommandBuffer::null());
+
+        unsafe { self.device.device.end_command_buffer(command_buffer) }
+            .expect("Failed to end the command buffer");
+
+        VulkanCommandBuffer {
+            device: Arc::clone(&self.device),
+            command_buffer,
+            parent: PhantomData,
+        }
+    }
+
+    fn get_backing_command_buffer_builder(&mut self) -> &mut Self::BackingType {
+        &mut self.command_buffer
+    }
+}
+
+impl Drop for VulkanCommandBufferBuilder<'_> {
+    fn drop(&mut self) {
+        if self.command_buffer != vk::CommandBuffer::null() {
+            self.device.free_command_buffer(self.command_buffer);
+        }
+    }
+}
+
+/// A recorded command buffer, freed if dropped before it is submitted.
+///
+/// *Command buffers taken with [`CommandBuffer::get_backing_command_buffer`] have to be freed
+/// by the caller, [`VulkanQueue::submit`] submits and frees them.*
+pub struct VulkanCommandBuffer<'a> {
+    device: Arc<DeviceContext>,
+    command_buffer: vk::CommandBuffer,
+    parent: PhantomData<&'a ()>,
+}
+
+impl<'a> CommandBuffer<'_> for VulkanCommandBuffer<'a> {
+    type BackingType = vk::CommandBuffer;
+
+    fn get_backing_command_buffer(mut self) -> Self::BackingType {
+        std::mem::replace(&mut self.command_buffer, vk::CommandBuffer::null())
+    }
+}
+
+impl Drop for VulkanCommandBuffer<'_> {
+    fn drop(&mut self) {
+        if self.command_buffer != vk::CommandBuffer::null() {
+            self.device.free_command_buffer(self.command_buffer);
+        }
+    }
+}
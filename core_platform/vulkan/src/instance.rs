@@ -0,0 +1,355 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::device::{VulkanAdapter, VulkanPhysicalDevice};
+use crate::surface::VulkanSurface;
+use ash::extensions::khr;
+use ash::vk;
+use pluto_engine_render::device::{DeviceError, PhysicalDevice};
+use pluto_engine_render::instance::{
+    AdapterInfo, AdapterLimits, AdapterSelection, AdapterType, ContextInstance, GraphicsBackend,
+};
+use pluto_engine_render::pluto_engine_window::window::Window;
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+use std::ffi::{CStr, CString};
+use std::sync::Arc;
+
+/// The loaded Vulkan library and instance, kept alive by every object created from it.
+pub(crate) struct VulkanContext {
+    pub(crate) entry: ash::Entry,
+    pub(crate) instance: ash::Instance,
+    pub(crate) surface_loader: khr::Surface,
+}
+
+impl Drop for VulkanContext {
+    fn drop(&mut self) {
+        unsafe { self.instance.destroy_instance(None) };
+    }
+}
+
+/// *Returns the instance extensions required to present to the window, `None` for
+/// window systems the backend does not support.*
+fn surface_extensions(handle: &RawWindowHandle) -> Option<[&'static CStr; 2]> {
+    let platform = match handle {
+        RawWindowHandle::Xlib(_) => khr::XlibSurface::name(),
+        RawWindowHandle::Xcb(_) => khr::XcbSurface::name(),
+        RawWindowHandle::Wayland(_) => khr::WaylandSurface::name(),
+        RawWindowHandle::Win32(_) => khr::Win32Surface::name(),
+        _ => return None,
+    };
+
+    Some([khr::Surface::name(), platform])
+}
+
+/// Creates a surface for the native window.
+///
+/// # Safety
+///
+/// The window has to outlive the surface.
+unsafe fn create_surface(
+    context: &VulkanContext,
+    handle: &RawWindowHandle,
+) -> Result<vk::SurfaceKHR, vk::Result> {
+    let (entry, instance) = (&context.entry, &context.instance);
+
+    match handle {
+        RawWindowHandle::Xlib(handle) => khr::XlibSurface::new(entry, instance)
+            .create_xlib_surface(
+                &vk::XlibSurfaceCreateInfoKHR::builder()
+                    .dpy(handle.display as *mut vk::Display)
+                    .window(handle.window),
+                None,
+            ),
+        RawWindowHandle::Xcb(handle) => khr::XcbSurface::new(entry, instance).create_xcb_surface(
+            &vk::XcbSurfaceCreateInfoKHR::builder()
+                .connection(handle.connection)
+                .window(handle.window),
+            None,
+        ),
+        RawWindowHandle::Wayland(handle) => khr::WaylandSurface::new(entry, instance)
+            .create_wayland_surface(
+                &vk::WaylandSurfaceCreateInfoKHR::builder()
+                    .display(handle.display)
+                    .surface(handle.surface),
+                None,
+            ),
+        RawWindowHandle::Win32(handle) => khr::Win32Surface::new(entry, instance)
+            .create_win32_surface(
+                &vk::Win32SurfaceCreateInfoKHR::builder()
+                    .hinstance(handle.hinstance)
+                    .hwnd(handle.hwnd),
+                None,
+            ),
+        _ => Err(vk::Result::ERROR_EXTENSION_NOT_PRESENT),
+    }
+}
+
+fn adapter_info(instance: &ash::Instance, physical_device: vk::PhysicalDevice) -> AdapterInfo {
+    let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+    let limits = properties.limits;
+
+    AdapterInfo {
+        name: unsafe { CStr::from_ptr(properties.device_name.as_ptr()) }
+            .to_string_lossy()
+            .into_owned(),
+        adapter_type: match properties.device_type {
+            vk::PhysicalDeviceType::DISCRETE_GPU => AdapterType::DiscreteGpu,
+            vk::PhysicalDeviceType::INTEGRATED_GPU => AdapterType::IntegratedGpu,
+            vk::PhysicalDeviceType::VIRTUAL_GPU => AdapterType::VirtualGpu,
+            vk::PhysicalDeviceType::CPU => AdapterType::Cpu,
+            _ => AdapterType::Other,
+        },
+        backend: GraphicsBackend::Vulkan,
+        limits: AdapterLimits {
+            max_texture_dimension_2d: limits.max_image_dimension2_d,
+            max_bind_groups: limits.max_bound_descriptor_sets,
+            max_push_constant_size: limits.max_push_constants_size,
+        },
+    }
+}
+
+/// *Returns the index of the first queue family able to render and present to the surface.*
+fn present_queue_family(
+    context: &VulkanContext,
+    physical_device: vk::PhysicalDevice,
+    surface: vk::SurfaceKHR,
+) -> Option<u32> {
+    let families = unsafe {
+        context
+            .instance
+            .get_physical_device_queue_family_properties(physical_device)
+    };
+
+    (0..families.len() as u32).find(|&index| {
+        families[index as usize]
+            .queue_flags
+            .contains(vk::QueueFlags::GRAPHICS)
+            && unsafe {
+                context.surface_loader.get_physical_device_surface_support(
+                    physical_device,
+                    index,
+                    surface,
+                )
+            }
+            .unwrap_or(false)
+    })
+}
+
+/// *Returns the index of the adapter to create the device from, software adapters
+/// are only considered if no hardware adapter is available and the fallback is enabled.*
+pub(crate) fn choose_adapter(
+    adapters: &[AdapterInfo],
+    selection: &AdapterSelection,
+) -> Option<usize> {
+    let hardware = adapters
+        .iter()
+        .enumerate()
+        .filter(|(_, adapter)| adapter.adapter_type != AdapterType::Cpu)
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+
+    if hardware.is_empty() {
+        return selection
+            .software_fallback
+            .then(|| {
+                adapters
+                    .iter()
+                    .position(|adapter| adapter.adapter_type == AdapterType::Cpu)
+            })
+            .flatten();
+    }
+
+    let infos = hardware
+        .iter()
+        .map(|&index| adapters[index].clone())
+        .collect::<Vec<_>>();
+
+    Some(hardware[selection.choose(&infos).unwrap_or(0)])
+}
+
+/// A Vulkan instance creating devices and surfaces for a window.
+///
+/// *The Vulkan library is loaded at runtime, instances without a driver have no adapters,
+/// see [`VulkanInstance::is_available`].*
+pub struct VulkanInstance<'a, W: Window<SizeType = u32> + HasRawWindowHandle + 'a> {
+    context: Option<Arc<VulkanContext>>,
+    window: &'a W,
+}
+
+impl<'a, W: Window<SizeType = u32> + HasRawWindowHandle + 'a> VulkanInstance<'a, W> {
+    /// *Returns `false` if no Vulkan driver is installed or it cannot present to the window.*
+    pub fn is_available(&self) -> bool {
+        self.context.is_some()
+    }
+
+    fn load(window: &W) -> Option<VulkanContext> {
+        let extensions = surface_extensions(&window.raw_window_handle())?;
+        let entry = unsafe { ash::Entry::load() }.ok()?;
+
+        let application_name = CString::new("Pluto Engine").unwrap();
+        let application_info = vk::ApplicationInfo::builder()
+            .application_name(&application_name)
+            .engine_name(&application_name)
+            .api_version(vk::API_VERSION_1_1);
+
+        let extension_names = extensions.map(CStr::as_ptr);
+        let instance = unsafe {
+            entry.create_instance(
+                &vk::InstanceCreateInfo::builder()
+                    .application_info(&application_info)
+                    .enabled_extension_names(&extension_names),
+                None,
+            )
+        }
+        .ok()?;
+
+        let surface_loader = khr::Surface::new(&entry, &instance);
+
+        Some(VulkanContext {
+            entry,
+            instance,
+            surface_loader,
+        })
+    }
+}
+
+impl<'a, W: Window<SizeType = u32> + HasRawWindowHandle + 'a> ContextInstance<'a>
+    for VulkanInstance<'a, W>
+{
+    type BackingType = ash::Instance;
+
+    // Neither borrows the window, so that displays can own the window together with its surface,
+    // the window must then outlive the surface.
+    type PhysicalDeviceType = VulkanPhysicalDevice<'static>;
+    type SurfaceType = VulkanSurface<'static>;
+    type WindowType = W;
+
+    fn new(window: &'a Self::WindowType) -> Self {
+        Self {
+            context: Self::load(window).map(Arc::new),
+            window,
+        }
+    }
+
+    fn create_device_and_surface_with(
+        &self,
+        selection: &AdapterSelection,
+    ) -> Result<(Self::PhysicalDeviceType, Self::SurfaceType), DeviceError> {
+        let context = self.context.as_ref().ok_or(DeviceError::NoAdapter)?;
+
+        let surface = unsafe { create_surface(context, &self.window.raw_window_handle()) }
+            .map_err(|err| {
+                DeviceError::RequestDevice(format!("failed to create the surface: {}", err))
+            })?;
+        // Destroys the surface if no device can present to it
+        let surface = VulkanSurface::new(Arc::clone(context), surface, self.window.get_size());
+
+        let adapters = unsafe { context.instance.enumerate_physical_devices() }
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|physical_device| {
+                present_queue_family(context, physical_device, surface.surface)
+                    .map(|queue_family| (physical_device, queue_family))
+            })
+            .collect::<Vec<_>>();
+
+        let infos = adapters
+            .iter()
+            .map(|&(physical_device, _)| adapter_info(&context.instance, physical_device))
+            .collect::<Vec<_>>();
+
+        let (physical_device, queue_family_index) = choose_adapter(&infos, selection)
+            .map(|index| adapters[index])
+            .ok_or(DeviceError::NoAdapter)?;
+
+        let physical_device = VulkanPhysicalDevice::new(VulkanAdapter {
+            context: Arc::clone(context),
+            physical_device,
+            queue_family_index,
+        });
+        let surface = surface.with_physical_device(physical_device.get_backing_physical_device());
+
+        Ok((physical_device, surface))
+    }
+
+    fn enumerate_adapters(&self) -> Vec<AdapterInfo> {
+        let Some(context) = &self.context else {
+            return Vec::new();
+        };
+
+        unsafe { context.instance.enumerate_physical_devices() }
+            .unwrap_or_default()
+            .into_iter()
+            .map(|physical_device| adapter_info(&context.instance, physical_device))
+            .collect()
+    }
+
+    /// ***Panics*** if Vulkan is not available, see [`VulkanInstance::is_available`].
+    fn get_backing_instance(&self) -> &Self::BackingType {
+        &self
+            .context
+            .as_ref()
+            .expect("Vulkan is not available")
+            .instance
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::instance::choose_adapter;
+    use pluto_engine_render::instance::{
+        AdapterInfo, AdapterLimits, AdapterPreference, AdapterSelection, AdapterType,
+        GraphicsBackend,
+    };
+
+    /// An adapter is chosen from hardware and software adapters.
+    /// Software adapters should only be chosen as a fallback, if enabled.
+    #[test]
+    fn test_choose_adapter() {
+        let adapter = |name: &str, adapter_type| AdapterInfo {
+            name: name.to_string(),
+            adapter_type,
+            backend: GraphicsBackend::Vulkan,
+            limits: AdapterLimits::default(),
+        };
+
+        let adapters = [
+            adapter("llvmpipe", AdapterType::Cpu),
+            adapter("Intel(R) UHD Graphics", AdapterType::IntegratedGpu),
+            adapter("NVIDIA GeForce RTX 3060", AdapterType::DiscreteGpu),
+        ];
+
+        let selection = AdapterSelection::new(AdapterPreference::HighPerformance);
+        assert_eq!(choose_adapter(&adapters, &selection), Some(2));
+        assert_eq!(
+            choose_adapter(&adapters, &AdapterSelection::default()),
+            Some(1)
+        );
+        assert_eq!(choose_adapter(&adapters[..1], &selection), None);
+        assert_eq!(
+            choose_adapter(&adapters[..1], &selection.software_fallback(true)),
+            Some(0)
+        );
+    }
+}
@@ -0,0 +1,122 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use ash::vk;
+use pluto_engine_render::mesh::{AttributeFormat, MeshLayout, StepMode, VertexLayout};
+use smallvec::SmallVec;
+
+fn attribute_format(format: AttributeFormat) -> vk::Format {
+    match format {
+        AttributeFormat::Float32 => vk::Format::R32_SFLOAT,
+        AttributeFormat::Float32x2 => vk::Format::R32G32_SFLOAT,
+        AttributeFormat::Float32x3 => vk::Format::R32G32B32_SFLOAT,
+        AttributeFormat::Float32x4 => vk::Format::R32G32B32A32_SFLOAT,
+    }
+}
+
+/// Splits vertex layouts into vertex buffer bindings, planar layouts using one binding
+/// per attribute.
+///
+/// Shader locations are assigned in order across all bindings.
+pub(crate) fn vertex_input(
+    layouts: &[VertexLayout],
+) -> (
+    SmallVec<[vk::VertexInputBindingDescription; 8]>,
+    SmallVec<[vk::VertexInputAttributeDescription; 8]>,
+) {
+    let mut bindings = SmallVec::<[vk::VertexInputBindingDescription; 8]>::new();
+    let mut attributes = SmallVec::new();
+
+    for layout in layouts {
+        let input_rate = match layout.step_mode {
+            StepMode::Vertex => vk::VertexInputRate::VERTEX,
+            StepMode::Instance => vk::VertexInputRate::INSTANCE,
+        };
+
+        let mut bind = |stride: usize, attrs: &[AttributeFormat]| {
+            let binding = bindings.len() as u32;
+            let mut offset = 0;
+
+            for attr in attrs {
+                attributes.push(vk::VertexInputAttributeDescription {
+                    location: attributes.len() as u32,
+                    binding,
+                    format: attribute_format(*attr),
+                    offset,
+                });
+                offset += attr.size() as u32;
+            }
+
+            bindings.push(vk::VertexInputBindingDescription {
+                binding,
+                stride: stride as u32,
+                input_rate,
+            });
+        };
+
+        match layout.layout {
+            MeshLayout::Interleaved => bind(layout.stride, layout.attributes),
+            MeshLayout::Planar => {
+                for attr in layout.attributes {
+                    bind(attr.size(), std::slice::from_ref(attr));
+                }
+            }
+        }
+    }
+
+    (bindings, attributes)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mesh::vertex_input;
+    use ash::vk;
+    use pluto_engine_render::mesh::{AttributeFormat, MeshLayout, StepMode, VertexLayout};
+
+    /// A planar layout is followed by an interleaved per-instance layout.
+    /// Each planar attribute should get its own binding and locations continue across bindings.
+    #[test]
+    fn test_vertex_input() {
+        let planar =
+            VertexLayout::planar(&[AttributeFormat::Float32x3, AttributeFormat::Float32x2]);
+        let instance = VertexLayout {
+            stride: 32,
+            layout: MeshLayout::Interleaved,
+            attributes: &[AttributeFormat::Float32x4, AttributeFormat::Float32x4],
+            step_mode: StepMode::Instance,
+        };
+
+        let (bindings, attributes) = vertex_input(&[planar, instance]);
+
+        assert_eq!(bindings.len(), 3);
+        assert_eq!(bindings[1].stride, 8);
+        assert_eq!(bindings[2].input_rate, vk::VertexInputRate::INSTANCE);
+
+        let placement = attributes
+            .iter()
+            .map(|attr| (attr.location, attr.binding, attr.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(placement, [(0, 0, 0), (1, 1, 0), (2, 2, 0), (3, 2, 16)]);
+    }
+}
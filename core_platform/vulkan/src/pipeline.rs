@@ -0,0 +1,256 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::device::DeviceContext;
+use ash::vk;
+use pluto_engine_render::pipeline::{Pipeline, PipelineLayout};
+use pluto_engine_render::uniform::ShaderStages;
+use smallvec::SmallVec;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+pub(crate) fn shader_stages(stages: ShaderStages) -> vk::ShaderStageFlags {
+    match stages {
+        ShaderStages::Vertex => vk::ShaderStageFlags::VERTEX,
+        ShaderStages::Fragment => vk::ShaderStageFlags::FRAGMENT,
+        ShaderStages::VertexFragment => {
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT
+        }
+    }
+}
+
+/// Creates a descriptor set layout with one descriptor per binding.
+pub(crate) fn create_descriptor_set_layout(
+    device: &ash::Device,
+    bindings: impl Iterator<Item = (u32, vk::DescriptorType, vk::ShaderStageFlags)>,
+) -> vk::DescriptorSetLayout {
+    let bindings = bindings
+        .map(|(binding, descriptor_type, stage_flags)| {
+            vk::DescriptorSetLayoutBinding::builder()
+                .binding(binding)
+                .descriptor_type(descriptor_type)
+                .descriptor_count(1)
+                .stage_flags(stage_flags)
+                .build()
+        })
+        .collect::<SmallVec<[_; 8]>>();
+
+    unsafe {
+        device.create_descriptor_set_layout(
+            &vk::DescriptorSetLayoutCreateInfo::builder().bindings(&bindings),
+            None,
+        )
+    }
+    .expect("Failed to create a descriptor set layout")
+}
+
+/// Creates a render pass drawing into a single color attachment, cleared before it is drawn
+/// and presentable afterwards, with an optional depth attachment.
+pub(crate) fn create_render_pass(
+    device: &ash::Device,
+    format: vk::Format,
+    depth_format: Option<vk::Format>,
+) -> vk::RenderPass {
+    let color = vk::AttachmentDescription::builder()
+        .format(format)
+        .samples(vk::SampleCountFlags::TYPE_1)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::PRESENT_SRC_KHR)
+        .build();
+
+    let depth = depth_format.map(|depth_format| {
+        vk::AttachmentDescription::builder()
+            .format(depth_format)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .build()
+    });
+
+    let color_references = [vk::AttachmentReference {
+        attachment: 0,
+        layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+    }];
+    let depth_reference = vk::AttachmentReference {
+        attachment: 1,
+        layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+    };
+
+    let mut subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .color_attachments(&color_references);
+    if depth.is_some() {
+        subpass = subpass.depth_stencil_attachment(&depth_reference);
+    }
+
+    let attachments = [Some(color), depth]
+        .into_iter()
+        .flatten()
+        .collect::<SmallVec<[_; 2]>>();
+    let subpasses = [subpass.build()];
+
+    unsafe {
+        device.create_render_pass(
+            &vk::RenderPassCreateInfo::builder()
+                .attachments(&attachments)
+                .subpasses(&subpasses),
+            None,
+        )
+    }
+    .expect("Failed to create a render pass")
+}
+
+struct PipelineLayoutObjects {
+    device: Arc<DeviceContext>,
+    layout: vk::PipelineLayout,
+    set_layouts: Vec<vk::DescriptorSetLayout>,
+}
+
+impl Drop for PipelineLayoutObjects {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .device
+                .destroy_pipeline_layout(self.layout, None);
+
+            for set_layout in &self.set_layouts {
+                self.device
+                    .device
+                    .destroy_descriptor_set_layout(*set_layout, None);
+            }
+        }
+    }
+}
+
+/// A pipeline layout, clones share the same backing objects.
+#[derive(Clone)]
+pub struct VulkanPipelineLayout<'a> {
+    objects: Arc<PipelineLayoutObjects>,
+    parent: PhantomData<&'a ()>,
+}
+
+impl<'a> VulkanPipelineLayout<'a> {
+    pub(crate) fn new(
+        device: Arc<DeviceContext>,
+        layout: vk::PipelineLayout,
+        set_layouts: Vec<vk::DescriptorSetLayout>,
+    ) -> Self {
+        Self {
+            objects: Arc::new(PipelineLayoutObjects {
+                device,
+                layout,
+                set_layouts,
+            }),
+            parent: PhantomData,
+        }
+    }
+
+    /// Returns the layouts of the descriptor sets, starting with set 0.
+    pub fn get_set_layouts(&self) -> &[vk::DescriptorSetLayout] {
+        &self.objects.set_layouts
+    }
+}
+
+impl<'a> PipelineLayout<'_> for VulkanPipelineLayout<'a> {
+    type BackingType = vk::PipelineLayout;
+
+    fn get_backing_pipeline_layout(&self) -> &Self::BackingType {
+        &self.objects.layout
+    }
+}
+
+struct PipelineObjects {
+    device: Arc<DeviceContext>,
+    pipeline: vk::Pipeline,
+    render_pass: vk::RenderPass,
+}
+
+impl Drop for PipelineObjects {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.device.destroy_pipeline(self.pipeline, None);
+            self.device
+                .device
+                .destroy_render_pass(self.render_pass, None);
+        }
+    }
+}
+
+/// A render pipeline, clones share the same backing objects.
+#[derive(Clone)]
+pub struct VulkanPipeline<'a> {
+    objects: Arc<PipelineObjects>,
+    layout: VulkanPipelineLayout<'a>,
+    has_uniforms: bool,
+}
+
+impl<'a> VulkanPipeline<'a> {
+    pub(crate) fn new(
+        device: Arc<DeviceContext>,
+        pipeline: vk::Pipeline,
+        render_pass: vk::RenderPass,
+        layout: VulkanPipelineLayout<'a>,
+        has_uniforms: bool,
+    ) -> Self {
+        Self {
+            objects: Arc::new(PipelineObjects {
+                device,
+                pipeline,
+                render_pass,
+            }),
+            layout,
+            has_uniforms,
+        }
+    }
+
+    /// Returns a render pass compatible with the pipeline, clearing its color attachment
+    /// and leaving it ready to be presented.
+    pub fn get_render_pass(&self) -> vk::RenderPass {
+        self.objects.render_pass
+    }
+
+    /// Returns the layout the pipeline was created with, including its uniforms
+    /// and push constants.
+    pub fn get_layout(&self) -> &VulkanPipelineLayout<'a> {
+        &self.layout
+    }
+
+    /// *Returns the layout of descriptor set 0, present if the pipeline was created with uniforms.*
+    pub fn get_uniform_layout(&self) -> Option<vk::DescriptorSetLayout> {
+        self.has_uniforms.then(|| self.layout.get_set_layouts()[0])
+    }
+}
+
+impl<'a> Pipeline<'_> for VulkanPipeline<'a> {
+    type BackingType = vk::Pipeline;
+    type LayoutType = VulkanPipelineLayout<'a>;
+
+    fn get_backing_pipeline(&self) -> &Self::BackingType {
+        &self.objects.pipeline
+    }
+}
@@ -0,0 +1,133 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::device::DeviceContext;
+use ash::vk;
+use pluto_engine_render::shader::Shader;
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Translates WGSL code to SPIR-V, validating it on the way.
+///
+/// *Returns the error formatted for the code if it is invalid.*
+pub fn wgsl_to_spirv(code: &str) -> Result<Vec<u32>, String> {
+    let module = naga::front::wgsl::parse_str(code).map_err(|err| err.emit_to_string(code))?;
+
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::PUSH_CONSTANT,
+    )
+    .validate(&module)
+    .map_err(|err| err.to_string())?;
+
+    naga::back::spv::write_vec(&module, &info, &naga::back::spv::Options::default(), None)
+        .map_err(|err| err.to_string())
+}
+
+pub(crate) struct ShaderModule {
+    device: Arc<DeviceContext>,
+    pub(crate) module: vk::ShaderModule,
+}
+
+impl Drop for ShaderModule {
+    fn drop(&mut self) {
+        unsafe { self.device.device.destroy_shader_module(self.module, None) };
+    }
+}
+
+/// A compiled shader, clones share the same module.
+#[derive(Clone)]
+pub struct VulkanShader<'a> {
+    pub(crate) module: Arc<ShaderModule>,
+    pub(crate) vertex_entry: CString,
+    pub(crate) fragment_entry: CString,
+    label: Option<String>,
+    parent: PhantomData<&'a ()>,
+}
+
+impl<'a> VulkanShader<'a> {
+    pub(crate) fn new(
+        device: Arc<DeviceContext>,
+        module: vk::ShaderModule,
+        vertex_entry: &str,
+        fragment_entry: &str,
+        label: Option<&str>,
+    ) -> Self {
+        Self {
+            module: Arc::new(ShaderModule { device, module }),
+            vertex_entry: CString::new(vertex_entry).unwrap(),
+            fragment_entry: CString::new(fragment_entry).unwrap(),
+            label: label.map(str::to_owned),
+            parent: PhantomData,
+        }
+    }
+
+    pub fn fragment_entry_point(&self) -> &str {
+        self.fragment_entry.to_str().unwrap()
+    }
+
+    pub fn vertex_entry_point(&self) -> &str {
+        self.vertex_entry.to_str().unwrap()
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+}
+
+impl<'a> Shader<'_> for VulkanShader<'a> {
+    type BackingType = vk::ShaderModule;
+
+    fn get_backing_module(&self) -> &Self::BackingType {
+        &self.module.module
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shader::wgsl_to_spirv;
+
+    /// A vertex and fragment shader is translated, then a shader with a typo.
+    /// The valid shader should become a SPIR-V module and the typo be reported.
+    #[test]
+    fn test_wgsl_to_spirv() {
+        let code = "
+            [[stage(vertex)]]
+            fn vs_main([[builtin(vertex_index)]] index: u32) -> [[builtin(position)]] vec4<f32> {
+                return vec4<f32>(f32(index), 0.0, 0.0, 1.0);
+            }
+
+            [[stage(fragment)]]
+            fn fs_main() -> [[location(0)]] vec4<f32> {
+                return vec4<f32>(1.0, 0.0, 0.0, 1.0);
+            }
+        ";
+
+        let words = wgsl_to_spirv(code).unwrap();
+        assert_eq!(words[0], 0x0723_0203);
+
+        assert!(wgsl_to_spirv(&code.replace("vec4<f32>(1.0", "vec4<f32>(x")).is_err());
+    }
+}
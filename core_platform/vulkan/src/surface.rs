@@ -0,0 +1,365 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::device::{DeviceContext, VulkanAdapter, VulkanDevice};
+use crate::instance::VulkanContext;
+use crate::texture::{create_view, VulkanTextureFormat, VulkanTextureView};
+use ash::vk;
+use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+use pluto_engine_render::surface::{Surface, SurfaceError, SurfaceFormat, SurfaceTexture};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+#[derive(Copy, Clone, Debug)]
+pub struct VulkanSurfaceFormat(vk::SurfaceFormatKHR);
+
+impl SurfaceFormat for VulkanSurfaceFormat {
+    type BackingType = vk::SurfaceFormatKHR;
+
+    fn get_backing_format(&self) -> Self::BackingType {
+        self.0
+    }
+}
+
+/// *Returns the preferred format of a surface, an sRGB format if it supports one.*
+pub(crate) fn preferred_format(formats: &[vk::SurfaceFormatKHR]) -> Option<vk::SurfaceFormatKHR> {
+    formats
+        .iter()
+        .find(|format| {
+            matches!(
+                format.format,
+                vk::Format::B8G8R8A8_SRGB | vk::Format::R8G8B8A8_SRGB
+            )
+        })
+        .or_else(|| formats.first())
+        .copied()
+}
+
+struct Swapchain {
+    device: Arc<DeviceContext>,
+    swapchain: vk::SwapchainKHR,
+    images: Vec<vk::Image>,
+    /// Signaled once an acquired image can be rendered to.
+    acquire_fence: vk::Fence,
+}
+
+impl Drop for Swapchain {
+    fn drop(&mut self) {
+        unsafe {
+            // Presented images may still be in use
+            let _ = self.device.device.device_wait_idle();
+            self.device.device.destroy_fence(self.acquire_fence, None);
+            self.device
+                .swapchain_loader
+                .destroy_swapchain(self.swapchain, None);
+        }
+    }
+}
+
+/// A surface presenting through a swapchain, created once the surface is configured.
+pub struct VulkanSurface<'a> {
+    context: Arc<VulkanContext>,
+    pub(crate) surface: vk::SurfaceKHR,
+    format: vk::SurfaceFormatKHR,
+    size: PhysicalSize<u32>,
+    swapchain: Option<Swapchain>,
+    parent: PhantomData<&'a ()>,
+}
+
+impl<'a> VulkanSurface<'a> {
+    pub(crate) fn new(
+        context: Arc<VulkanContext>,
+        surface: vk::SurfaceKHR,
+        size: PhysicalSize<u32>,
+    ) -> Self {
+        Self {
+            context,
+            surface,
+            format: vk::SurfaceFormatKHR::default(),
+            size,
+            swapchain: None,
+            parent: PhantomData,
+        }
+    }
+
+    /// Picks the format of the surface from the ones supported by the adapter.
+    pub(crate) fn with_physical_device(mut self, adapter: &VulkanAdapter) -> Self {
+        let formats = unsafe {
+            self.context
+                .surface_loader
+                .get_physical_device_surface_formats(adapter.physical_device, self.surface)
+        }
+        .unwrap_or_default();
+
+        self.format = preferred_format(&formats).unwrap_or_default();
+        self
+    }
+}
+
+impl Drop for VulkanSurface<'_> {
+    fn drop(&mut self) {
+        self.swapchain = None;
+
+        unsafe {
+            self.context
+                .surface_loader
+                .destroy_surface(self.surface, None)
+        };
+    }
+}
+
+/// An image of the swapchain, rendered to before it is presented.
+///
+/// *Render passes drawing into it have to leave it in the present layout,
+/// as the render passes of pipelines do.*
+pub struct VulkanSurfaceTexture<'a> {
+    device: Arc<DeviceContext>,
+    swapchain: vk::SwapchainKHR,
+    image: vk::Image,
+    index: u32,
+    format: vk::Format,
+    parent: PhantomData<&'a ()>,
+}
+
+impl<'a> SurfaceTexture<'_> for VulkanSurfaceTexture<'a> {
+    type BackingType = vk::Image;
+    type TextureViewType = VulkanTextureView<'a>;
+
+    fn get_backing_texture(&self) -> &Self::BackingType {
+        &self.image
+    }
+
+    fn get_texture_view(&self) -> Self::TextureViewType {
+        create_view(&self.device, self.image, self.format, 1)
+    }
+
+    /// Presents the image once the work submitted to the queue is complete.
+    ///
+    /// *Waits for the queue to be idle, as submissions do not signal semaphores.*
+    fn present(self) {
+        let swapchains = [self.swapchain];
+        let indices = [self.index];
+
+        unsafe {
+            let _ = self.device.device.queue_wait_idle(self.device.queue);
+            // Outdated swapchains are reported by the next acquire
+            let _ = self.device.swapchain_loader.queue_present(
+                self.device.queue,
+                &vk::PresentInfoKHR::builder()
+                    .swapchains(&swapchains)
+                    .image_indices(&indices),
+            );
+        }
+    }
+}
+
+impl<'a> Surface<'_> for VulkanSurface<'a> {
+    type BackingType = vk::SurfaceKHR;
+
+    type SizeType = u32;
+    type DeviceType = VulkanDevice<'a>;
+    type FormatType = VulkanSurfaceFormat;
+    type TextureFormatType = VulkanTextureFormat;
+    type TextureType = VulkanSurfaceTexture<'a>;
+
+    type ErrorType = vk::Result;
+
+    /// Creates the swapchain, replacing the previous one.
+    ///
+    /// ***Panics*** if the swapchain cannot be created.
+    fn configure(&mut self, device: &VulkanDevice<'a>) {
+        let device = &device.0;
+        let capabilities = unsafe {
+            self.context
+                .surface_loader
+                .get_physical_device_surface_capabilities(
+                    device.adapter.physical_device,
+                    self.surface,
+                )
+        }
+        .expect("Failed to query the surface capabilities");
+
+        // The extent is decided by the swapchain on some platforms, such as Wayland
+        let extent = if capabilities.current_extent.width == u32::MAX {
+            vk::Extent2D {
+                width: self.size.width.clamp(
+                    capabilities.min_image_extent.width,
+                    capabilities.max_image_extent.width,
+                ),
+                height: self.size.height.clamp(
+                    capabilities.min_image_extent.height,
+                    capabilities.max_image_extent.height,
+                ),
+            }
+        } else {
+            capabilities.current_extent
+        };
+
+        let mut image_count = capabilities.min_image_count + 1;
+        if capabilities.max_image_count > 0 {
+            image_count = image_count.min(capabilities.max_image_count);
+        }
+
+        // Surface textures can be copied from to take screenshots
+        let usage = vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | (capabilities.supported_usage_flags & vk::ImageUsageFlags::TRANSFER_SRC);
+
+        let old_swapchain = self.swapchain.take();
+
+        let swapchain = unsafe {
+            device.swapchain_loader.create_swapchain(
+                &vk::SwapchainCreateInfoKHR::builder()
+                    .surface(self.surface)
+                    .min_image_count(image_count)
+                    .image_format(self.format.format)
+                    .image_color_space(self.format.color_space)
+                    .image_extent(extent)
+                    .image_array_layers(1)
+                    .image_usage(usage)
+                    .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+                    .pre_transform(capabilities.current_transform)
+                    .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+                    .present_mode(vk::PresentModeKHR::FIFO)
+                    .clipped(true)
+                    .old_swapchain(
+                        old_swapchain
+                            .as_ref()
+                            .map_or(vk::SwapchainKHR::null(), |old| old.swapchain),
+                    ),
+                None,
+            )
+        }
+        .expect("Failed to create the swapchain");
+        drop(old_swapchain);
+
+        let images = unsafe { device.swapchain_loader.get_swapchain_images(swapchain) }
+            .expect("Failed to get the swapchain images");
+        let acquire_fence = unsafe {
+            device
+                .device
+                .create_fence(&vk::FenceCreateInfo::default(), None)
+        }
+        .expect("Failed to create a fence");
+
+        self.size = PhysicalSize {
+            width: extent.width,
+            height: extent.height,
+        };
+        self.swapchain = Some(Swapchain {
+            device: Arc::clone(device),
+            swapchain,
+            images,
+            acquire_fence,
+        });
+    }
+
+    fn resize(&mut self, device: &VulkanDevice<'a>, size: PhysicalSize<u32>) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
+        self.size = size;
+        self.configure(device);
+    }
+
+    fn get_format(&self) -> VulkanSurfaceFormat {
+        VulkanSurfaceFormat(self.format)
+    }
+
+    fn get_texture_format(&self) -> Self::TextureFormatType {
+        VulkanTextureFormat(self.format.format)
+    }
+
+    fn get_backing_surface(&self) -> &Self::BackingType {
+        &self.surface
+    }
+
+    /// ***Panics*** if the surface was not configured.
+    fn acquire_next_texture(&self) -> Result<Self::TextureType, SurfaceError<Self::ErrorType>> {
+        let swapchain = self
+            .swapchain
+            .as_ref()
+            .expect("The surface has to be configured before acquiring textures");
+        let device = &swapchain.device;
+
+        let (index, _suboptimal) = unsafe {
+            device.swapchain_loader.acquire_next_image(
+                swapchain.swapchain,
+                u64::MAX,
+                vk::Semaphore::null(),
+                swapchain.acquire_fence,
+            )
+        }
+        .map_err(|err| match err {
+            vk::Result::ERROR_SURFACE_LOST_KHR => SurfaceError::DeviceLost,
+            vk::Result::ERROR_OUT_OF_DATE_KHR => SurfaceError::Outdated,
+            vk::Result::TIMEOUT | vk::Result::NOT_READY => SurfaceError::Timeout,
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY | vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => {
+                SurfaceError::OutOfMemory
+            }
+            err => SurfaceError::Other(err),
+        })?;
+
+        // Without semaphores, the image is waited for before rendering is submitted
+        unsafe {
+            device
+                .device
+                .wait_for_fences(&[swapchain.acquire_fence], true, u64::MAX)
+                .and_then(|_| device.device.reset_fences(&[swapchain.acquire_fence]))
+        }
+        .map_err(SurfaceError::Other)?;
+
+        Ok(VulkanSurfaceTexture {
+            device: Arc::clone(device),
+            swapchain: swapchain.swapchain,
+            image: swapchain.images[index as usize],
+            index,
+            format: self.format.format,
+            parent: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::surface::preferred_format;
+    use ash::vk;
+
+    /// Surfaces supporting and lacking an sRGB format are configured.
+    /// The sRGB format should be preferred, otherwise the first format is used.
+    #[test]
+    fn test_preferred_format() {
+        let format = |format| vk::SurfaceFormatKHR {
+            format,
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        };
+
+        let unorm = format(vk::Format::B8G8R8A8_UNORM);
+        let srgb = format(vk::Format::B8G8R8A8_SRGB);
+
+        assert_eq!(preferred_format(&[unorm, srgb]), Some(srgb));
+        assert_eq!(preferred_format(&[unorm]), Some(unorm));
+        assert_eq!(preferred_format(&[]), None);
+    }
+}
@@ -0,0 +1,191 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::device::DeviceContext;
+use ash::vk;
+use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+use pluto_engine_render::texture::{Texture, TextureFormat, TextureView};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct VulkanTextureFormat(pub(crate) vk::Format);
+
+impl TextureFormat for VulkanTextureFormat {
+    type BackingType = vk::Format;
+
+    fn get_backing_format(&self) -> Self::BackingType {
+        self.0
+    }
+}
+
+/// Allows rendering into formats not provided by a surface, such as for offscreen rendering.
+impl From<vk::Format> for VulkanTextureFormat {
+    fn from(format: vk::Format) -> Self {
+        Self(format)
+    }
+}
+
+/// Creates a view of all mip levels of a color image.
+pub(crate) fn create_view<'a>(
+    device: &Arc<DeviceContext>,
+    image: vk::Image,
+    format: vk::Format,
+    mip_levels: u32,
+) -> VulkanTextureView<'a> {
+    let view = unsafe {
+        device.device.create_image_view(
+            &vk::ImageViewCreateInfo::builder()
+                .image(image)
+                .view_type(vk::ImageViewType::TYPE_2D)
+                .format(format)
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: mip_levels,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                }),
+            None,
+        )
+    }
+    .expect("Failed to create an image view");
+
+    VulkanTextureView {
+        device: Arc::clone(device),
+        view,
+        parent: PhantomData,
+    }
+}
+
+/// A 2D color texture in device local memory.
+pub struct VulkanTexture<'a> {
+    device: Arc<DeviceContext>,
+    pub(crate) image: vk::Image,
+    memory: vk::DeviceMemory,
+    size: PhysicalSize<u32>,
+    format: vk::Format,
+    mip_levels: u32,
+    parent: PhantomData<&'a ()>,
+}
+
+impl<'a> VulkanTexture<'a> {
+    pub(crate) fn new(
+        device: Arc<DeviceContext>,
+        size: PhysicalSize<u32>,
+        format: vk::Format,
+        mip_levels: u32,
+        usage: vk::ImageUsageFlags,
+    ) -> Self {
+        let image = unsafe {
+            device.device.create_image(
+                &vk::ImageCreateInfo::builder()
+                    .image_type(vk::ImageType::TYPE_2D)
+                    .format(format)
+                    .extent(vk::Extent3D {
+                        width: size.width,
+                        height: size.height,
+                        depth: 1,
+                    })
+                    .mip_levels(mip_levels)
+                    .array_layers(1)
+                    .samples(vk::SampleCountFlags::TYPE_1)
+                    .tiling(vk::ImageTiling::OPTIMAL)
+                    .usage(usage)
+                    .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                    .initial_layout(vk::ImageLayout::UNDEFINED),
+                None,
+            )
+        }
+        .expect("Failed to create an image");
+
+        let memory = device.allocate_memory(
+            unsafe { device.device.get_image_memory_requirements(image) },
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        );
+
+        unsafe { device.device.bind_image_memory(image, memory, 0) }
+            .expect("Failed to bind the image memory");
+
+        Self {
+            device,
+            image,
+            memory,
+            size,
+            format,
+            mip_levels,
+            parent: PhantomData,
+        }
+    }
+
+    pub fn get_size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+
+    pub fn get_format(&self) -> VulkanTextureFormat {
+        VulkanTextureFormat(self.format)
+    }
+}
+
+impl Drop for VulkanTexture<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.device.destroy_image(self.image, None);
+            self.device.device.free_memory(self.memory, None);
+        }
+    }
+}
+
+impl<'a> Texture<'_> for VulkanTexture<'a> {
+    type BackingType = vk::Image;
+    type ViewType = VulkanTextureView<'a>;
+
+    fn get_backing_texture(&self) -> &Self::BackingType {
+        &self.image
+    }
+
+    fn create_view(&self) -> Self::ViewType {
+        create_view(&self.device, self.image, self.format, self.mip_levels)
+    }
+}
+
+pub struct VulkanTextureView<'a> {
+    device: Arc<DeviceContext>,
+    view: vk::ImageView,
+    parent: PhantomData<&'a ()>,
+}
+
+impl Drop for VulkanTextureView<'_> {
+    fn drop(&mut self) {
+        unsafe { self.device.device.destroy_image_view(self.view, None) };
+    }
+}
+
+impl<'a> TextureView<'_> for VulkanTextureView<'a> {
+    type BackingType = vk::ImageView;
+
+    fn get_backing_texture_view(&self) -> &Self::BackingType {
+        &self.view
+    }
+}
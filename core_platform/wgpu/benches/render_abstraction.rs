@@ -0,0 +1,158 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Measures the overhead of the render abstraction over the equivalent raw wgpu calls.
+//!
+//! Run with `cargo bench --bench render_abstraction`,
+//! skipped on machines without a graphics adapter.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pluto_engine_core_platform_wgpu::instance::create_headless_device;
+use pluto_engine_core_platform_wgpu::texture::WgpuTextureFormat;
+use pluto_engine_core_platform_wgpu::wgpu;
+use pluto_engine_render::device::{
+    CommandBuffer, CommandBufferBuilder, Device, DeviceUniforms, PhysicalDevice, Queue,
+};
+use pluto_engine_render::instance::AdapterSelection;
+use pluto_engine_render::pipeline::PipelineCreateInfo;
+use pluto_engine_render::shader::ShaderCode;
+use pluto_engine_render::texture::TextureFormat;
+use pluto_engine_render::uniform::UniformBuffer;
+
+const SHADER: &str = r#"
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> [[builtin(position)]] vec4<f32> {
+    return vec4<f32>(f32(index), 0.0, 0.0, 1.0);
+}
+
+[[stage(fragment)]]
+fn fs_main() -> [[location(0)]] vec4<f32> {
+    return vec4<f32>(1.0);
+}
+"#;
+
+fn render_abstraction(c: &mut Criterion) {
+    let Ok(physical_device) = create_headless_device(&AdapterSelection::default()) else {
+        println!("No graphics adapter available, skipping the render benchmarks.");
+        return;
+    };
+
+    let (device, queue) = physical_device
+        .create_device_and_queue()
+        .expect("Failed to create the device");
+    let raw_device = device.get_backing_device();
+    let raw_queue = queue.get_backing_queue();
+
+    let mut group = c.benchmark_group("command_buffer");
+    group.bench_function("engine", |b| {
+        b.iter(|| {
+            let command_buffer = device.begin_command_buffer().build();
+            black_box(command_buffer.get_backing_command_buffer());
+        })
+    });
+    group.bench_function("raw", |b| {
+        b.iter(|| {
+            let encoder = raw_device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+            black_box(encoder.finish());
+        })
+    });
+    group.finish();
+
+    let data = [0u8; 256];
+    let uniform_buffer = device.create_uniform_buffer(data.len() as u64);
+    let mut group = c.benchmark_group("uniform_write");
+    group.bench_function("engine", |b| {
+        b.iter(|| {
+            device.write_uniform_buffer(&queue, &uniform_buffer, black_box(&data));
+            raw_queue.submit(None);
+        })
+    });
+    group.bench_function("raw", |b| {
+        b.iter(|| {
+            raw_queue.write_buffer(uniform_buffer.get_backing_buffer(), 0, black_box(&data));
+            raw_queue.submit(None);
+        })
+    });
+    group.finish();
+
+    // The engine caches pipelines, so repeated creation is a lookup
+    let shader = device.create_shader(&ShaderCode::Wgsl {
+        code: SHADER,
+        vertex_entry: "vs_main",
+        fragment_entry: "fs_main",
+        label: Some("Bench Shader"),
+    });
+    let pipeline_layout = device.create_pipeline_layout(&shader);
+    let format = WgpuTextureFormat::from(wgpu::TextureFormat::Rgba8Unorm);
+    let mut group = c.benchmark_group("create_pipeline");
+    group.bench_function("engine", |b| {
+        b.iter(|| {
+            black_box(device.create_pipeline(&PipelineCreateInfo {
+                label: Some("Bench Pipeline"),
+                ..PipelineCreateInfo::new(&pipeline_layout, &shader, format)
+            }))
+        })
+    });
+
+    let module = raw_device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+        label: Some("Bench Shader"),
+        source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+    });
+    let raw_layout = raw_device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: None,
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+    group.bench_function("raw", |b| {
+        b.iter(|| {
+            black_box(
+                raw_device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Bench Pipeline"),
+                    layout: Some(&raw_layout),
+                    vertex: wgpu::VertexState {
+                        module: &module,
+                        entry_point: "vs_main",
+                        buffers: &[],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &module,
+                        entry_point: "fs_main",
+                        targets: &[wgpu::ColorTargetState::from(format.get_backing_format())],
+                    }),
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                }),
+            )
+        })
+    });
+    group.finish();
+
+    device.wait_idle();
+}
+
+criterion_group!(benches, render_abstraction);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use pluto_engine_render::compute::{ComputeBindGroup, ComputePipeline, StorageBuffer};
+use std::marker::PhantomData;
+
+pub struct WgpuComputePipeline<'a> {
+    pub(crate) pipeline: wgpu::ComputePipeline,
+    /// The layout of bind group 0, present if the pipeline was created with any buffers.
+    pub(crate) bind_group_layout: Option<wgpu::BindGroupLayout>,
+    pub(crate) storage_bindings: Vec<u32>,
+    pub(crate) uniform_bindings: Vec<u32>,
+    pub(crate) parent: PhantomData<&'a ()>,
+}
+
+impl<'a> ComputePipeline<'_> for WgpuComputePipeline<'a> {
+    type BackingType = wgpu::ComputePipeline;
+
+    fn get_backing_compute_pipeline(&self) -> &Self::BackingType {
+        &self.pipeline
+    }
+}
+
+pub struct WgpuStorageBuffer<'a> {
+    pub(crate) buffer: wgpu::Buffer,
+    pub(crate) size: u64,
+    pub(crate) parent: PhantomData<&'a ()>,
+}
+
+impl<'a> StorageBuffer<'_> for WgpuStorageBuffer<'a> {
+    type BackingType = wgpu::Buffer;
+
+    fn get_backing_buffer(&self) -> &Self::BackingType {
+        &self.buffer
+    }
+
+    fn get_size(&self) -> u64 {
+        self.size
+    }
+}
+
+pub struct WgpuComputeBindGroup<'a> {
+    pub(crate) bind_group: wgpu::BindGroup,
+    pub(crate) parent: PhantomData<&'a ()>,
+}
+
+impl<'a> ComputeBindGroup<'_> for WgpuComputeBindGroup<'a> {
+    type BackingType = wgpu::BindGroup;
+
+    fn get_backing_bind_group(&self) -> &Self::BackingType {
+        &self.bind_group
+    }
+}
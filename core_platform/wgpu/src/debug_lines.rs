@@ -0,0 +1,232 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::device::{WgpuCommandBufferBuilder, WgpuDevice};
+use crate::texture::{WgpuTextureFormat, WgpuTextureView};
+use pluto_engine_render::device::{CommandBufferBuilder, Device};
+use pluto_engine_render::texture::TextureFormat;
+use std::borrow::Cow;
+use wgpu::util::DeviceExt;
+
+/// The WGSL code drawing debug lines.
+pub const DEBUG_LINES_SHADER: &str = include_str!("shaders/debug_lines.wgsl");
+
+/// The end of a debug line, with an sRGB color and straight alpha.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct DebugLineVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl DebugLineVertex {
+    const SIZE: u64 = std::mem::size_of::<Self>() as u64;
+}
+
+/// Lines sharing a view-projection matrix, every two vertices form a line.
+pub struct DebugLineBatch<'b> {
+    /// A column-major matrix transforming the vertices into clip space.
+    pub view_projection: [[f32; 4]; 4],
+    pub vertices: &'b [DebugLineVertex],
+}
+
+/// Draws batches of lines on top of a texture, without depth testing.
+pub struct WgpuDebugLineRenderer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// Whether the target converts colors to sRGB, in which case the shader outputs linear colors.
+    linear_output: bool,
+}
+
+impl WgpuDebugLineRenderer {
+    /// Creates a renderer drawing into textures of the given format.
+    pub fn new(device: &WgpuDevice<'_>, format: WgpuTextureFormat) -> Self {
+        let device = device.get_backing_device();
+        let format = format.get_backing_format();
+
+        let module = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("Debug Lines Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::from(DEBUG_LINES_SHADER)),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Debug Lines"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Lines"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug Lines"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: DebugLineVertex::SIZE,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x4],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                cull_mode: None,
+                ..wgpu::PrimitiveState::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            linear_output: format.describe().srgb,
+        }
+    }
+
+    /// Records a pass drawing the batches in order on top of the output.
+    pub fn record(
+        &self,
+        device: &WgpuDevice<'_>,
+        command_buffer: &mut WgpuCommandBufferBuilder<'_>,
+        output: &WgpuTextureView<'_>,
+        batches: &[DebugLineBatch],
+    ) {
+        let device = device.get_backing_device();
+
+        let batches = batches
+            .iter()
+            .filter(|batch| batch.vertices.len() >= 2)
+            .map(|batch| {
+                let mut uniform = batch
+                    .view_projection
+                    .iter()
+                    .flatten()
+                    .flat_map(|value| value.to_ne_bytes())
+                    .collect::<Vec<_>>();
+                uniform.extend(
+                    [if self.linear_output { 1.0f32 } else { 0.0 }, 0.0, 0.0, 0.0]
+                        .iter()
+                        .flat_map(|value| value.to_ne_bytes()),
+                );
+
+                let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Debug Lines Uniform"),
+                    contents: &uniform,
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Debug Lines"),
+                    layout: &self.bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    }],
+                });
+
+                let vertices = batch
+                    .vertices
+                    .iter()
+                    .flat_map(|vertex| vertex.position.iter().chain(vertex.color.iter()))
+                    .flat_map(|value| value.to_ne_bytes())
+                    .collect::<Vec<_>>();
+
+                let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Debug Lines Vertices"),
+                    contents: &vertices,
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+                // An odd vertex would not form a line
+                let vertex_count = batch.vertices.len() as u32 & !1;
+
+                (bind_group, vertex_buffer, vertex_count)
+            })
+            .collect::<Vec<_>>();
+
+        let encoder = command_buffer.get_backing_command_buffer_builder();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Debug Lines"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: &output.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+
+        for (bind_group, vertex_buffer, vertex_count) in &batches {
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.draw(0..*vertex_count, 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::debug_lines::DEBUG_LINES_SHADER;
+
+    /// The debug lines shader is parsed and validated.
+    /// It should be valid WGSL.
+    #[test]
+    fn test_debug_lines_shader_validates() {
+        let module = naga::front::wgsl::parse_str(DEBUG_LINES_SHADER).unwrap();
+
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap();
+    }
+}
@@ -0,0 +1,439 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::device::{WgpuCommandBufferBuilder, WgpuDevice, WgpuQueue};
+use crate::texture::{WgpuTextureFormat, WgpuTextureView};
+use egui::epaint::Primitive;
+use egui::{ClippedPrimitive, ImageData, TextureFilter, TextureId, TexturesDelta};
+use pluto_engine_render::device::{CommandBufferBuilder, Device, Queue};
+use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+use pluto_engine_render::texture::TextureFormat;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use wgpu::util::DeviceExt;
+
+/// The WGSL code drawing the meshes of the debug UI.
+pub const DEBUG_UI_SHADER: &str = include_str!("shaders/debug_ui.wgsl");
+
+/// The size of a vertex, a position and texture coordinates followed by an RGBA color.
+const VERTEX_SIZE: u64 = 20;
+
+struct UiTexture {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Draws the output of an egui context on top of a texture, usually the surface texture.
+pub struct WgpuDebugUiRenderer {
+    pipeline: wgpu::RenderPipeline,
+    screen_buffer: wgpu::Buffer,
+    screen_bind_group: wgpu::BindGroup,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    linear_sampler: wgpu::Sampler,
+    nearest_sampler: wgpu::Sampler,
+    textures: HashMap<TextureId, UiTexture>,
+    /// Whether the target converts colors to sRGB, in which case the shader outputs linear colors.
+    linear_output: bool,
+}
+
+impl WgpuDebugUiRenderer {
+    /// Creates a renderer drawing into textures of the given format.
+    pub fn new(device: &WgpuDevice<'_>, format: WgpuTextureFormat) -> Self {
+        let device = device.get_backing_device();
+        let format = format.get_backing_format();
+
+        let module = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("Debug UI Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::from(DEBUG_UI_SHADER)),
+        });
+
+        let screen_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug UI Screen"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let screen_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Debug UI Screen"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let screen_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Debug UI Screen"),
+            layout: &screen_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: screen_buffer.as_entire_binding(),
+            }],
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Debug UI Texture"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug UI"),
+            bind_group_layouts: &[&screen_bind_group_layout, &texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug UI"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: VERTEX_SIZE,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![
+                        0 => Float32x2,
+                        1 => Float32x2,
+                        2 => Unorm8x4
+                    ],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: None,
+                ..wgpu::PrimitiveState::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let sampler = |filter| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Debug UI Sampler"),
+                mag_filter: filter,
+                min_filter: filter,
+                ..Default::default()
+            })
+        };
+
+        Self {
+            pipeline,
+            screen_buffer,
+            screen_bind_group,
+            texture_bind_group_layout,
+            linear_sampler: sampler(wgpu::FilterMode::Linear),
+            nearest_sampler: sampler(wgpu::FilterMode::Nearest),
+            textures: HashMap::new(),
+            linear_output: format.describe().srgb,
+        }
+    }
+
+    /// Uploads the textures allocated or modified by the context and frees the unused ones.
+    pub fn update_textures(
+        &mut self,
+        device: &WgpuDevice<'_>,
+        queue: &WgpuQueue<'_>,
+        delta: &TexturesDelta,
+    ) {
+        for (id, image_delta) in &delta.set {
+            let size = image_delta.image.size();
+            let pixels = match &image_delta.image {
+                ImageData::Color(image) => image
+                    .pixels
+                    .iter()
+                    .flat_map(|color| color.to_array())
+                    .collect::<Vec<_>>(),
+                ImageData::Font(image) => image
+                    .srgba_pixels(1.0)
+                    .flat_map(|color| color.to_array())
+                    .collect::<Vec<_>>(),
+            };
+
+            let origin = match image_delta.pos {
+                Some([x, y]) => wgpu::Origin3d {
+                    x: x as u32,
+                    y: y as u32,
+                    z: 0,
+                },
+                None => {
+                    let texture = self.create_texture(device, size, image_delta.filter);
+                    self.textures.insert(*id, texture);
+                    wgpu::Origin3d::ZERO
+                }
+            };
+
+            let Some(texture) = self.textures.get(id) else {
+                continue;
+            };
+
+            queue.get_backing_queue().write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture.texture,
+                    mip_level: 0,
+                    origin,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &pixels,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(size[0] as u32 * 4),
+                    rows_per_image: NonZeroU32::new(size[1] as u32),
+                },
+                wgpu::Extent3d {
+                    width: size[0] as u32,
+                    height: size[1] as u32,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        for id in &delta.free {
+            self.textures.remove(id);
+        }
+    }
+
+    fn create_texture(
+        &self,
+        device: &WgpuDevice<'_>,
+        size: [usize; 2],
+        filter: TextureFilter,
+    ) -> UiTexture {
+        let device = device.get_backing_device();
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Debug UI Texture"),
+            size: wgpu::Extent3d {
+                width: size[0] as u32,
+                height: size[1] as u32,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // The colors are blended in gamma space, the shader converts them if needed
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = match filter {
+            TextureFilter::Nearest => &self.nearest_sampler,
+            TextureFilter::Linear => &self.linear_sampler,
+        };
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Debug UI Texture"),
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+
+        UiTexture {
+            texture,
+            bind_group,
+        }
+    }
+
+    /// Records a pass drawing the tessellated meshes on top of the output.
+    ///
+    /// *Meshes using unknown textures and paint callbacks are skipped.*
+    #[allow(clippy::too_many_arguments)]
+    pub fn record(
+        &self,
+        device: &WgpuDevice<'_>,
+        queue: &WgpuQueue<'_>,
+        command_buffer: &mut WgpuCommandBufferBuilder<'_>,
+        output: &WgpuTextureView<'_>,
+        primitives: &[ClippedPrimitive],
+        size: PhysicalSize<u32>,
+        pixels_per_point: f32,
+    ) {
+        let screen = [
+            size.width as f32 / pixels_per_point,
+            size.height as f32 / pixels_per_point,
+            if self.linear_output { 1.0 } else { 0.0 },
+            0.0,
+        ];
+        queue.get_backing_queue().write_buffer(
+            &self.screen_buffer,
+            0,
+            &screen
+                .iter()
+                .flat_map(|value| value.to_ne_bytes())
+                .collect::<Vec<_>>(),
+        );
+
+        let meshes = primitives
+            .iter()
+            .filter_map(|primitive| match &primitive.primitive {
+                Primitive::Mesh(mesh) if !mesh.indices.is_empty() => Some((primitive, mesh)),
+                _ => None,
+            })
+            .filter_map(|(primitive, mesh)| {
+                let texture = self.textures.get(&mesh.texture_id)?;
+                let vertices = mesh
+                    .vertices
+                    .iter()
+                    .flat_map(|vertex| {
+                        let mut bytes = [0; VERTEX_SIZE as usize];
+                        bytes[0..4].copy_from_slice(&vertex.pos.x.to_ne_bytes());
+                        bytes[4..8].copy_from_slice(&vertex.pos.y.to_ne_bytes());
+                        bytes[8..12].copy_from_slice(&vertex.uv.x.to_ne_bytes());
+                        bytes[12..16].copy_from_slice(&vertex.uv.y.to_ne_bytes());
+                        bytes[16..20].copy_from_slice(&vertex.color.to_array());
+                        bytes
+                    })
+                    .collect::<Vec<_>>();
+                let indices = mesh
+                    .indices
+                    .iter()
+                    .flat_map(|index| index.to_ne_bytes())
+                    .collect::<Vec<_>>();
+
+                let device = device.get_backing_device();
+                let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Debug UI Vertices"),
+                    contents: &vertices,
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+                let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Debug UI Indices"),
+                    contents: &indices,
+                    usage: wgpu::BufferUsages::INDEX,
+                });
+
+                let clip_rect = Self::clip_rect(primitive.clip_rect, size, pixels_per_point)?;
+
+                Some((
+                    texture,
+                    vertex_buffer,
+                    index_buffer,
+                    mesh.indices.len() as u32,
+                    clip_rect,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let encoder = command_buffer.get_backing_command_buffer_builder();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Debug UI"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: &output.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.screen_bind_group, &[]);
+
+        for (texture, vertex_buffer, index_buffer, index_count, [x, y, width, height]) in &meshes {
+            render_pass.set_scissor_rect(*x, *y, *width, *height);
+            render_pass.set_bind_group(1, &texture.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..*index_count, 0, 0..1);
+        }
+    }
+
+    /// *Returns the clip rectangle in pixels clamped to the target,
+    /// `None` if nothing would be drawn.*
+    fn clip_rect(
+        clip_rect: egui::Rect,
+        size: PhysicalSize<u32>,
+        pixels_per_point: f32,
+    ) -> Option<[u32; 4]> {
+        let min_x = ((clip_rect.min.x * pixels_per_point).round() as u32).min(size.width);
+        let min_y = ((clip_rect.min.y * pixels_per_point).round() as u32).min(size.height);
+        let max_x = ((clip_rect.max.x * pixels_per_point).round() as u32).clamp(min_x, size.width);
+        let max_y = ((clip_rect.max.y * pixels_per_point).round() as u32).clamp(min_y, size.height);
+
+        (max_x > min_x && max_y > min_y).then(|| [min_x, min_y, max_x - min_x, max_y - min_y])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::debug_ui::DEBUG_UI_SHADER;
+
+    /// The debug UI shader is parsed and validated.
+    /// It should be valid WGSL.
+    #[test]
+    fn test_debug_ui_shader_validates() {
+        let module = naga::front::wgsl::parse_str(DEBUG_UI_SHADER).unwrap();
+
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::compute::WgpuStorageBuffer;
+use crate::material::WgpuMaterial;
+use crate::pipeline::WgpuPipeline;
+use crate::target::WgpuRenderTarget;
+use crate::texture::{WgpuTexture, WgpuTextureView};
+use crate::uniform::{WgpuUniformBindGroup, WgpuUniformBuffer};
+use pluto_engine_render::deletion::DeletionQueue;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll, Waker};
+
+/// An engine object waiting for the frames using it to complete.
+pub enum WgpuResource<'a> {
+    Texture(WgpuTexture<'a>),
+    TextureView(WgpuTextureView<'a>),
+    UniformBuffer(WgpuUniformBuffer<'a>),
+    UniformBindGroup(WgpuUniformBindGroup<'a>),
+    StorageBuffer(WgpuStorageBuffer<'a>),
+    RenderTarget(WgpuRenderTarget<'a>),
+    Pipeline(WgpuPipeline<'a>),
+    Material(Box<WgpuMaterial<'a>>),
+}
+
+impl WgpuResource<'_> {
+    /// Releases the memory of buffers and textures right away instead of
+    /// waiting for every handle to be dropped.
+    fn destroy(self) {
+        match self {
+            WgpuResource::Texture(texture) => texture.texture.destroy(),
+            WgpuResource::UniformBuffer(buffer) => buffer.buffer.destroy(),
+            WgpuResource::StorageBuffer(buffer) => buffer.buffer.destroy(),
+            WgpuResource::RenderTarget(target) => {
+                for color in target.colors {
+                    color.texture.destroy();
+                }
+                if let Some(depth) = target.depth {
+                    depth.texture.destroy();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+macro_rules! impl_wgpu_resource {
+    ($($variant:ident($type:ident)),* $(,)?) => {
+        $(
+            impl<'a> From<$type<'a>> for WgpuResource<'a> {
+                fn from(resource: $type<'a>) -> Self {
+                    WgpuResource::$variant(resource)
+                }
+            }
+        )*
+    };
+}
+
+impl_wgpu_resource!(
+    Texture(WgpuTexture),
+    TextureView(WgpuTextureView),
+    UniformBuffer(WgpuUniformBuffer),
+    UniformBindGroup(WgpuUniformBindGroup),
+    StorageBuffer(WgpuStorageBuffer),
+    RenderTarget(WgpuRenderTarget),
+    Pipeline(WgpuPipeline),
+);
+
+impl<'a> From<WgpuMaterial<'a>> for WgpuResource<'a> {
+    fn from(material: WgpuMaterial<'a>) -> Self {
+        WgpuResource::Material(Box::new(material))
+    }
+}
+
+type FrameFence = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+struct DeletionState<'a> {
+    queue: DeletionQueue<WgpuResource<'a>>,
+    /// The fences of submitted frames which have not completed yet, oldest first.
+    fences: VecDeque<(u64, FrameFence)>,
+    frame: u64,
+}
+
+/// Destroys dropped resources once the frames which may use them have completed on the GPU.
+pub(crate) struct WgpuDeletionQueue<'a> {
+    state: Mutex<DeletionState<'a>>,
+}
+
+impl Default for WgpuDeletionQueue<'_> {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(DeletionState {
+                queue: DeletionQueue::new(),
+                fences: VecDeque::new(),
+                frame: 0,
+            }),
+        }
+    }
+}
+
+impl<'a> WgpuDeletionQueue<'a> {
+    pub(crate) fn push(&self, resource: WgpuResource<'a>) {
+        let mut state = self.state.lock().unwrap();
+        let frame = state.frame;
+        state.queue.push(frame, resource);
+    }
+
+    /// Signals the fence of the current frame after all work submitted so far,
+    /// then destroys the resources of all frames whose fences were signaled.
+    pub(crate) fn end_frame(&self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let mut state = self.state.lock().unwrap();
+        let frame = state.frame;
+        state.frame += 1;
+
+        // Frames without resources to destroy do not need a fence
+        if state.queue.is_empty() {
+            return;
+        }
+
+        state
+            .fences
+            .push_back((frame, Box::pin(queue.on_submitted_work_done())));
+
+        device.poll(wgpu::Maintain::Poll);
+
+        let mut context = Context::from_waker(Waker::noop());
+        let mut completed = None;
+        while let Some((frame, fence)) = state.fences.front_mut() {
+            match fence.as_mut().poll(&mut context) {
+                Poll::Ready(()) => {
+                    completed = Some(*frame);
+                    state.fences.pop_front();
+                }
+                Poll::Pending => break,
+            }
+        }
+
+        if let Some(completed) = completed {
+            state
+                .queue
+                .collect(completed)
+                .into_iter()
+                .for_each(WgpuResource::destroy);
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.state.lock().unwrap().queue.len()
+    }
+}
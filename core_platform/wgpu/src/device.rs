@@ -22,21 +22,58 @@
  * SOFTWARE.
  */
 
-use crate::mesh::WgpuAttribute;
-use crate::pipeline::{WgpuPipeline, WgpuPipelineLayout};
-use crate::shader::WgpuShader;
-use crate::texture::{WgpuTexture, WgpuTextureFormat};
+use crate::compute::{WgpuComputeBindGroup, WgpuComputePipeline, WgpuStorageBuffer};
+use crate::deletion::{WgpuDeletionQueue, WgpuResource};
+use crate::material::{create_material_bind_group_layout, WgpuMaterial};
+use crate::mesh::buffer_layouts;
+use crate::picking::{WgpuPickReadback, PICK_FORMAT};
+use crate::pipeline::{WgpuPipeline, WgpuPipelineCache, WgpuPipelineLayout, OVERDRAW_SHADER};
+use crate::push_constant::{PushConstantEmulation, WgpuPushConstants};
+use crate::shader::{emulate_push_constants, WgpuShader};
+use crate::shadow::{create_shadow_bind_group_layout, WgpuShadowMap};
+use crate::target::{WgpuDepthFormat, WgpuRenderTarget};
+use crate::texture::{WgpuReadableTexture, WgpuTexture, WgpuTextureFormat, WgpuTextureReadback};
+use crate::timer::WgpuGpuTimer;
+use crate::uniform::WgpuShaderStages;
+use crate::uniform::{WgpuUniformBindGroup, WgpuUniformBuffer};
+use crate::upload::{StagedLevel, StagedUpload, WgpuUploader};
+use crate::validation;
+use pluto_engine_render::cache::{
+    pipeline_key, shader_key, shadow_pipeline_key, CacheStats, DevicePipelineCache,
+};
+use pluto_engine_render::compute::{ComputeDispatch, ComputePipelineCreateInfo};
+use pluto_engine_render::debug::{
+    DeviceFrameCapture, DeviceRenderDebug, RenderDebugMode, RenderDebugSwitch,
+};
+use pluto_engine_render::deletion::DeviceDeferredDeletion;
 use pluto_engine_render::device::{
-    CommandBuffer, CommandBufferBuilder, Device, PhysicalDevice, Queue,
+    CommandBuffer, CommandBufferBuilder, Device, DeviceCompute, DeviceError, DevicePushConstants,
+    DeviceQueues, DeviceTextureFactory, DeviceTextureReader, DeviceUniforms, PhysicalDevice, Queue,
 };
-use pluto_engine_render::mesh::MeshLayout;
-use pluto_engine_render::pipeline::{PipelineCreateInfo, PipelineLayout};
+use pluto_engine_render::image::{ImageOptions, TextureImage};
+use pluto_engine_render::material::{DeviceMaterials, ParameterBlock};
+use pluto_engine_render::picking::DevicePicking;
+use pluto_engine_render::pipeline::{
+    BlendMode, CullMode, FrontFace, PipelineCreateInfo, PipelineLayout, PolygonMode,
+    PrimitiveTopology,
+};
+use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
 use pluto_engine_render::shader::{Shader, ShaderCode};
-use pluto_engine_render::texture::TextureFormat;
+use pluto_engine_render::shadow::{
+    DeviceShadowMaps, ShadowMapConfig, ShadowPass, ShadowPipelineCreateInfo, ShadowUniform,
+};
+use pluto_engine_render::target::{DepthFormat, DeviceRenderTargets};
+use pluto_engine_render::texture::{ReadbackError, Texture, TextureFormat, TexturePixels};
+use pluto_engine_render::timer::{DeviceGpuTimer, PassTiming};
+use pluto_engine_render::uniform::UniformBuffer;
+use pluto_engine_render::upload::{DeviceUploads, PendingUpload, UploadScheduler};
 use smallvec::SmallVec;
 use std::borrow::Cow;
+use std::cell::RefCell;
 use std::marker::PhantomData;
-use wgpu::{BufferAddress, VertexBufferLayout, VertexStepMode};
+use std::num::{NonZeroU32, NonZeroU64};
+use std::sync::Arc;
+use wgpu::VertexBufferLayout;
 
 pub struct WgpuQueue<'a>(wgpu::Queue, PhantomData<&'a ()>);
 
@@ -64,29 +101,478 @@ impl<'a> PhysicalDevice<'_> for WgpuPhysicalDevice<'a> {
         &self.0
     }
 
-    fn create_device_and_queue(&self) -> (Self::DeviceType, Self::QueueType) {
+    fn create_device_and_queue(&self) -> Result<(Self::DeviceType, Self::QueueType), DeviceError> {
         let (device, queue) = pollster::block_on(self.0.request_device(
             &wgpu::DeviceDescriptor {
-                features: wgpu::Features::empty(),
-                limits: if cfg!(target_arch = "wasm32") {
-                    wgpu::Limits::downlevel_webgl2_defaults()
-                } else {
-                    wgpu::Limits::default()
+                // Timestamp queries are only used for profiling, push constants are emulated
+                // where unavailable and line polygons are only used by the wireframe debug mode,
+                // so they are optional.
+                features: self.0.features()
+                    & (wgpu::Features::TIMESTAMP_QUERY
+                        | wgpu::Features::PUSH_CONSTANTS
+                        | wgpu::Features::POLYGON_MODE_LINE),
+                limits: wgpu::Limits {
+                    max_push_constant_size: self.0.limits().max_push_constant_size,
+                    ..if cfg!(target_arch = "wasm32") {
+                        wgpu::Limits::downlevel_webgl2_defaults()
+                    } else {
+                        wgpu::Limits::default()
+                    }
                 },
                 label: None,
             },
             None,
         ))
-        .unwrap();
+        .map_err(|err| DeviceError::RequestDevice(err.to_string()))?;
 
-        (
-            WgpuDevice(device, PhantomData),
+        Ok((
+            WgpuDevice(
+                device,
+                PhantomData,
+                RenderDebugSwitch::default(),
+                WgpuPipelineCache::default(),
+                WgpuDeletionQueue::default(),
+            ),
             WgpuQueue(queue, PhantomData),
-        )
+        ))
     }
 }
 
-pub struct WgpuDevice<'a>(wgpu::Device, PhantomData<&'a ()>);
+pub struct WgpuDevice<'a>(
+    wgpu::Device,
+    PhantomData<&'a ()>,
+    RenderDebugSwitch,
+    WgpuPipelineCache<'a>,
+    WgpuDeletionQueue<'a>,
+);
+
+/// The largest push constants supported natively, larger ones have to be emulated on most devices.
+const MAX_PUSH_CONSTANT_SIZE: u32 = 128;
+
+impl<'a> WgpuDevice<'a> {
+    /// Creates an uninitialized texture for all mip levels of the image.
+    fn create_image_texture(&self, image: &TextureImage) -> WgpuTexture<'a> {
+        let size = image.get_size();
+        let format = if image.srgb {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        } else {
+            wgpu::TextureFormat::Rgba8Unorm
+        };
+
+        let texture = self.0.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Image Texture"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: image.levels.len() as u32,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+
+        WgpuTexture {
+            texture,
+            size,
+            format,
+            parent: PhantomData,
+        }
+    }
+
+    /// Creates an object within a validation error scope,
+    /// so errors name the engine object instead of the call that used it later.
+    ///
+    /// *Scopes are not popped on the web, where they can only be awaited asynchronously.*
+    fn validated<T>(&self, kind: &str, label: Option<&str>, create: impl FnOnce() -> T) -> T {
+        if cfg!(target_arch = "wasm32") {
+            return create();
+        }
+
+        self.0.push_error_scope(wgpu::ErrorFilter::Validation);
+        let object = create();
+
+        if let Some(err) = pollster::block_on(self.0.pop_error_scope()) {
+            panic!(
+                "Failed to create the {} \"{}\": {}",
+                kind,
+                label.unwrap_or("unlabeled"),
+                err
+            );
+        }
+
+        object
+    }
+
+    /// Like [`Self::validated`], but only scoped when [`validation`] is enabled,
+    /// for objects created too often to pay for a scope each in regular builds.
+    fn debug_validated<T>(&self, kind: &str, label: Option<&str>, create: impl FnOnce() -> T) -> T {
+        if validation::is_enabled() {
+            self.validated(kind, label, create)
+        } else {
+            create()
+        }
+    }
+
+    /// Creates a pipeline without looking it up in the cache,
+    /// taking the given part in shadow mapping if any.
+    fn build_pipeline(
+        &self,
+        info: &PipelineCreateInfo<'_, WgpuPipelineLayout<'a>, WgpuShader<'a>, WgpuTextureFormat>,
+        shadow: Option<ShadowPass>,
+    ) -> WgpuPipeline<'a> {
+        let label = info
+            .label
+            .or_else(|| info.shader.get_label())
+            .unwrap_or("Render Pipeline");
+
+        let buffer_layouts = buffer_layouts(info.buffer_layout);
+
+        let buffer_layout_slice: SmallVec<[_; 8]> = buffer_layouts
+            .iter()
+            .map(|layout| VertexBufferLayout {
+                array_stride: layout.stride,
+                step_mode: layout.step_mode,
+                attributes: layout.attributes.as_slice(),
+            })
+            .collect();
+
+        let uniform_layout = (!info.uniforms.is_empty()).then(|| {
+            let entries = info
+                .uniforms
+                .iter()
+                .map(|uniform| wgpu::BindGroupLayoutEntry {
+                    binding: uniform.binding,
+                    visibility: uniform.visibility.to_wgpu(),
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: NonZeroU64::new(uniform.size),
+                    },
+                    count: None,
+                })
+                .collect::<SmallVec<[_; 4]>>();
+
+            self.0
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Uniform Bind Group Layout"),
+                    entries: entries.as_slice(),
+                })
+        });
+
+        let emulated_push_constants = info
+            .push_constants
+            .filter(|_| self.emulates_push_constants());
+
+        let push_constant_layout = emulated_push_constants.map(|layout| {
+            self.0
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Push Constant Bind Group Layout"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: layout.visibility.to_wgpu(),
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: NonZeroU64::new(layout.size as u64),
+                        },
+                        count: None,
+                    }],
+                })
+        });
+
+        let material_layout = info
+            .material
+            .map(|material| create_material_bind_group_layout(&self.0, material));
+
+        let shadow_layout = matches!(shadow, Some(ShadowPass::Receiver))
+            .then(|| create_shadow_bind_group_layout(&self.0));
+
+        // Emulated push constants are bound at group 1, materials at group 2 and shadow maps
+        // at group 3, groups in front of them are filled with empty ones
+        let groups = [
+            uniform_layout.as_ref(),
+            push_constant_layout.as_ref(),
+            material_layout.as_ref(),
+            shadow_layout.as_ref(),
+        ];
+        let group_count = groups
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |last| last + 1);
+
+        let empty_layout = groups[..group_count].iter().any(Option::is_none).then(|| {
+            self.0
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Empty Bind Group Layout"),
+                    entries: &[],
+                })
+        });
+
+        let push_constant_ranges = info
+            .push_constants
+            .filter(|_| emulated_push_constants.is_none())
+            .map(|layout| {
+                assert!(
+                    layout.size <= MAX_PUSH_CONSTANT_SIZE,
+                    "Push constants are limited to {} bytes",
+                    MAX_PUSH_CONSTANT_SIZE
+                );
+
+                wgpu::PushConstantRange {
+                    stages: layout.visibility.to_wgpu(),
+                    range: 0..layout.size,
+                }
+            })
+            .into_iter()
+            .collect::<SmallVec<[_; 1]>>();
+
+        // Bind groups and push constants have to be part of the layout the pipeline is created with
+        let custom_pipeline_layout = {
+            let bind_group_layouts = groups[..group_count]
+                .iter()
+                .flat_map(|layout| layout.or(empty_layout.as_ref()))
+                .collect::<SmallVec<[_; 4]>>();
+
+            (!bind_group_layouts.is_empty() || !push_constant_ranges.is_empty()).then(|| {
+                self.0
+                    .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                        label: Some(label),
+                        bind_group_layouts: bind_group_layouts.as_slice(),
+                        push_constant_ranges: push_constant_ranges.as_slice(),
+                    })
+            })
+        };
+
+        let primitive = wgpu::PrimitiveState {
+            topology: match info.primitive.topology {
+                PrimitiveTopology::PointList => wgpu::PrimitiveTopology::PointList,
+                PrimitiveTopology::LineList => wgpu::PrimitiveTopology::LineList,
+                PrimitiveTopology::LineStrip => wgpu::PrimitiveTopology::LineStrip,
+                PrimitiveTopology::TriangleList => wgpu::PrimitiveTopology::TriangleList,
+                PrimitiveTopology::TriangleStrip => wgpu::PrimitiveTopology::TriangleStrip,
+            },
+            strip_index_format: None,
+            front_face: match info.primitive.front_face {
+                FrontFace::Ccw => wgpu::FrontFace::Ccw,
+                FrontFace::Cw => wgpu::FrontFace::Cw,
+            },
+            cull_mode: match info.primitive.cull_mode {
+                CullMode::None => None,
+                CullMode::Front => Some(wgpu::Face::Front),
+                CullMode::Back => Some(wgpu::Face::Back),
+            },
+            polygon_mode: match info.primitive.polygon_mode {
+                PolygonMode::Fill => wgpu::PolygonMode::Fill,
+                PolygonMode::Line => wgpu::PolygonMode::Line,
+                PolygonMode::Point => wgpu::PolygonMode::Point,
+            },
+            unclipped_depth: false,
+            conservative: false,
+        };
+
+        let caster_bias = match shadow {
+            Some(ShadowPass::Caster(bias)) => Some(bias),
+            _ => None,
+        };
+
+        assert!(
+            caster_bias.is_none() || info.depth_format.is_some(),
+            "Shadow casters need a depth format"
+        );
+
+        let depth_stencil = info
+            .depth_format
+            .map(|depth_format| wgpu::DepthStencilState {
+                format: depth_format.to_wgpu(),
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: caster_bias.map_or_else(wgpu::DepthBiasState::default, |bias| {
+                    wgpu::DepthBiasState {
+                        constant: bias.constant,
+                        slope_scale: bias.slope_scale,
+                        clamp: bias.clamp,
+                    }
+                }),
+            });
+
+        let color_target = |format: WgpuTextureFormat, blend| wgpu::ColorTargetState {
+            format: format.get_backing_format(),
+            blend: to_wgpu_blend(blend),
+            write_mask: wgpu::ColorWrites::ALL,
+        };
+        let color_targets = std::iter::once(color_target(info.texture_format, info.blend))
+            .chain(
+                info.additional_color_targets
+                    .iter()
+                    .map(|target| color_target(target.format, target.blend)),
+            )
+            .collect::<SmallVec<[_; 4]>>();
+
+        let create_pipeline =
+            |label: &str,
+             primitive,
+             depth_stencil,
+             fragment_module,
+             fragment_entry,
+             color_targets: &[wgpu::ColorTargetState]| {
+                self.validated("render pipeline", Some(label), || {
+                    self.0
+                        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                            label: Some(label),
+                            layout: Some(custom_pipeline_layout.as_ref().unwrap_or_else(|| {
+                                info.pipeline_layout.get_backing_pipeline_layout()
+                            })),
+                            vertex: wgpu::VertexState {
+                                module: info.shader.get_backing_module(),
+                                entry_point: info.shader.vertex_entry_point(),
+                                buffers: buffer_layout_slice.as_slice(),
+                            },
+                            // Shadow casters only write depth
+                            fragment: caster_bias.is_none().then_some(wgpu::FragmentState {
+                                module: fragment_module,
+                                entry_point: fragment_entry,
+                                targets: color_targets,
+                            }),
+                            primitive,
+                            depth_stencil,
+                            multisample: wgpu::MultisampleState::default(),
+                            multiview: None,
+                        })
+                })
+            };
+
+        let pipeline = create_pipeline(
+            label,
+            primitive,
+            depth_stencil.clone(),
+            info.shader.get_backing_module(),
+            info.shader.fragment_entry_point(),
+            &color_targets,
+        );
+
+        let mut debug_variants = Vec::new();
+
+        if cfg!(feature = "render_debug") && caster_bias.is_none() {
+            if self.supports_render_debug_mode(RenderDebugMode::Wireframe)
+                && matches!(
+                    info.primitive.topology,
+                    PrimitiveTopology::TriangleList | PrimitiveTopology::TriangleStrip
+                )
+            {
+                let wireframe_label = format!("{} (Wireframe)", label);
+                let wireframe = create_pipeline(
+                    &wireframe_label,
+                    wgpu::PrimitiveState {
+                        polygon_mode: wgpu::PolygonMode::Line,
+                        ..primitive
+                    },
+                    depth_stencil.clone(),
+                    info.shader.get_backing_module(),
+                    info.shader.fragment_entry_point(),
+                    &color_targets,
+                );
+
+                debug_variants.push((RenderDebugMode::Wireframe, wireframe));
+            }
+
+            // Every fragment is counted, so hidden ones are neither culled nor depth tested
+            let overdraw_module = self.0.create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: Some("Overdraw Shader"),
+                source: wgpu::ShaderSource::Wgsl(Cow::from(OVERDRAW_SHADER)),
+            });
+
+            // Only the first target shows the overdraw, the others are left untouched
+            let overdraw_targets = color_targets
+                .iter()
+                .enumerate()
+                .map(|(i, target)| wgpu::ColorTargetState {
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent::OVER,
+                    }),
+                    write_mask: if i == 0 {
+                        wgpu::ColorWrites::ALL
+                    } else {
+                        wgpu::ColorWrites::empty()
+                    },
+                    ..target.clone()
+                })
+                .collect::<SmallVec<[_; 4]>>();
+
+            let overdraw_label = format!("{} (Overdraw)", label);
+            let overdraw = create_pipeline(
+                &overdraw_label,
+                wgpu::PrimitiveState {
+                    cull_mode: None,
+                    ..primitive
+                },
+                depth_stencil.map(|depth_stencil| wgpu::DepthStencilState {
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    ..depth_stencil
+                }),
+                &overdraw_module,
+                "fs_overdraw",
+                &overdraw_targets,
+            );
+
+            debug_variants.push((RenderDebugMode::Overdraw, overdraw));
+        }
+
+        WgpuPipeline {
+            pipeline: Arc::new(pipeline),
+            debug_variants: debug_variants.into(),
+            debug_switch: self.2.clone(),
+            uniform_layout: uniform_layout.map(Arc::new),
+            uniform_bindings: info
+                .uniforms
+                .iter()
+                .map(|uniform| uniform.binding)
+                .collect(),
+            push_constants: info.push_constants,
+            push_constant_layout: push_constant_layout.map(Arc::new),
+            empty_layout: empty_layout.map(Arc::new),
+            material_layout: material_layout.map(Arc::new),
+            shadow_layout: shadow_layout.map(Arc::new),
+            parent: PhantomData,
+        }
+    }
+
+    /// *Returns `true` if push constants are supplied through uniform buffers on this device.*
+    pub fn emulates_push_constants(&self) -> bool {
+        !self.0.features().contains(wgpu::Features::PUSH_CONSTANTS)
+            || self.0.limits().max_push_constant_size < MAX_PUSH_CONSTANT_SIZE
+    }
+}
+
+fn to_wgpu_blend(blend: BlendMode) -> Option<wgpu::BlendState> {
+    match blend {
+        BlendMode::Replace => None,
+        BlendMode::Alpha => Some(wgpu::BlendState::ALPHA_BLENDING),
+        BlendMode::PremultipliedAlpha => Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        BlendMode::Additive => Some(wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+        }),
+    }
+}
 
 impl<'a> Device<'_> for WgpuDevice<'a> {
     type BackingType = wgpu::Device;
@@ -97,6 +583,7 @@ impl<'a> Device<'_> for WgpuDevice<'a> {
     type CommandBufferType = WgpuCommandBuffer<'a>;
     type ImageFormatType = WgpuTextureFormat;
     type TextureType = WgpuTexture<'a>;
+    type ComputePipelineType = WgpuComputePipeline<'a>;
 
     fn get_backing_device(&self) -> &Self::BackingType {
         &self.0
@@ -117,7 +604,7 @@ impl<'a> Device<'_> for WgpuDevice<'a> {
             layout: self
                 .0
                 .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: None,
+                    label: shader.get_label(),
                     bind_group_layouts: &[],
                     push_constant_ranges: &[],
                 }),
@@ -134,96 +621,607 @@ impl<'a> Device<'_> for WgpuDevice<'a> {
             Self::ImageFormatType,
         >,
     ) -> Self::PipelineType {
-        // TODO: Very ugly and I really don't like the SmallVec here.
-        // Either I use a Vec or somehow convert these compile-time.
+        let key = pipeline_key(info.shader.key, info);
 
-        let buffer_layouts: SmallVec<[_; 8]> = info
-            .buffer_layout
-            .iter()
-            .map(|vertex_layout| {
-                let mut offset: usize = 0;
-                let attribs = vertex_layout
-                    .attributes
-                    .iter()
-                    .enumerate()
-                    .map(|(i, attr)| attr.pluto_to_wgpu(&mut offset, i))
-                    .collect::<SmallVec<[_; 16]>>();
+        self.3
+            .pipelines
+            .get_or_create(key, info.shader.key, || self.build_pipeline(info, None))
+    }
 
-                (attribs, &vertex_layout.layout, &vertex_layout.stride)
-            })
-            .collect();
+    fn create_shader(&self, shader_code: &ShaderCode<'_>) -> Self::ShaderType {
+        let key = shader_key(shader_code);
 
-        let buffer_layout_slice: SmallVec<[_; 8]> = buffer_layouts
-            .iter()
-            .map(|layout| VertexBufferLayout {
-                array_stride: *layout.2 as BufferAddress,
-                step_mode: match layout.1 {
-                    MeshLayout::Planar => todo!(),
-                    MeshLayout::Interleaved => VertexStepMode::Vertex,
-                },
-                attributes: layout.0.as_slice(),
-            })
-            .collect();
+        match *shader_code {
+            ShaderCode::Wgsl {
+                code,
+                fragment_entry,
+                vertex_entry,
+                label,
+            } => {
+                let shader = self.3.shaders.get_or_create(key, key, || {
+                    let code = if self.emulates_push_constants() {
+                        emulate_push_constants(code)
+                    } else {
+                        Cow::from(code)
+                    };
 
-        let pipeline = self
-            .0
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Render Pipeline"),
-                layout: Some(info.pipeline_layout.get_backing_pipeline_layout()),
-                vertex: wgpu::VertexState {
-                    module: info.shader.get_backing_module(),
-                    entry_point: info.shader.vertex_entry_point(),
-                    buffers: buffer_layout_slice.as_slice(),
+                    let module = self.validated("shader", label, || {
+                        self.0.create_shader_module(&wgpu::ShaderModuleDescriptor {
+                            label,
+                            source: wgpu::ShaderSource::Wgsl(code),
+                        })
+                    });
+
+                    WgpuShader {
+                        module: Arc::new(module),
+                        key,
+                        vertex_entry: vertex_entry.to_string(),
+                        fragment_entry: fragment_entry.to_string(),
+                        label: label.map(str::to_owned),
+                        parent: PhantomData,
+                    }
+                });
+
+                // Labels are not part of the key, so a cached shader is renamed
+                WgpuShader {
+                    label: label.map(str::to_owned),
+                    ..shader
+                }
+            }
+        }
+    }
+
+    fn create_compute_pipeline(
+        &self,
+        info: &ComputePipelineCreateInfo<'_, Self::ShaderType>,
+    ) -> Self::ComputePipelineType {
+        let storage_entries = info.storage_buffers.iter().map(|storage| {
+            (
+                storage.binding,
+                wgpu::BufferBindingType::Storage {
+                    read_only: storage.read_only,
                 },
-                fragment: Some(wgpu::FragmentState {
-                    module: info.shader.get_backing_module(),
-                    entry_point: info.shader.fragment_entry_point(),
-                    targets: &[wgpu::ColorTargetState {
-                        format: info.texture_format.get_backing_format(),
-                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    }],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    unclipped_depth: false,
-                    conservative: false,
+                None,
+            )
+        });
+        let uniform_entries = info.uniforms.iter().map(|uniform| {
+            (
+                uniform.binding,
+                wgpu::BufferBindingType::Uniform,
+                NonZeroU64::new(uniform.size),
+            )
+        });
+
+        let entries = storage_entries
+            .chain(uniform_entries)
+            .map(
+                |(binding, ty, min_binding_size)| wgpu::BindGroupLayoutEntry {
+                    binding,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty,
+                        has_dynamic_offset: false,
+                        min_binding_size,
+                    },
+                    count: None,
                 },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
+            )
+            .collect::<SmallVec<[_; 8]>>();
+
+        let bind_group_layout = (!entries.is_empty()).then(|| {
+            self.0
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Compute Bind Group Layout"),
+                    entries: entries.as_slice(),
+                })
+        });
+
+        let label = info
+            .label
+            .or_else(|| info.shader.get_label())
+            .unwrap_or("Compute Pipeline");
+
+        let layout = self
+            .0
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: bind_group_layout.as_ref().as_slice(),
+                push_constant_ranges: &[],
             });
 
-        Self::PipelineType {
+        let pipeline = self.validated("compute pipeline", Some(label), || {
+            self.0
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(&layout),
+                    module: info.shader.get_backing_module(),
+                    entry_point: info.entry_point,
+                })
+        });
+
+        WgpuComputePipeline {
             pipeline,
+            bind_group_layout,
+            storage_bindings: info
+                .storage_buffers
+                .iter()
+                .map(|storage| storage.binding)
+                .collect(),
+            uniform_bindings: info
+                .uniforms
+                .iter()
+                .map(|uniform| uniform.binding)
+                .collect(),
             parent: PhantomData,
         }
     }
 
-    fn create_shader(&self, shader_code: &ShaderCode<'_>) -> Self::ShaderType {
-        match *shader_code {
-            ShaderCode::Wgsl {
-                code,
-                fragment_entry,
-                vertex_entry,
-            } => {
-                let module = self.0.create_shader_module(&wgpu::ShaderModuleDescriptor {
-                    label: None,
-                    source: wgpu::ShaderSource::Wgsl(Cow::from(code)),
+    fn wait_idle(&self) {
+        self.0.poll(wgpu::Maintain::Wait);
+    }
+}
+
+impl<'a> DeviceUniforms<'_, WgpuQueue<'a>> for WgpuDevice<'a> {
+    type UniformBufferType = WgpuUniformBuffer<'a>;
+    type UniformBindGroupType = WgpuUniformBindGroup<'a>;
+
+    fn create_uniform_buffer(&self, size: u64) -> Self::UniformBufferType {
+        let buffer = self.0.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Uniform Buffer"),
+            size,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        WgpuUniformBuffer {
+            buffer,
+            size,
+            parent: PhantomData,
+        }
+    }
+
+    fn write_uniform_buffer(
+        &self,
+        queue: &WgpuQueue<'a>,
+        buffer: &Self::UniformBufferType,
+        data: &[u8],
+    ) {
+        queue.0.write_buffer(buffer.get_backing_buffer(), 0, data);
+    }
+
+    fn create_uniform_bind_group(
+        &self,
+        pipeline: &Self::PipelineType,
+        buffers: &[&Self::UniformBufferType],
+    ) -> Self::UniformBindGroupType {
+        let layout = pipeline
+            .uniform_layout
+            .as_ref()
+            .expect("The pipeline was created without uniforms");
+
+        assert_eq!(
+            pipeline.uniform_bindings.len(),
+            buffers.len(),
+            "Expected one buffer per uniform of the pipeline"
+        );
+
+        let entries = pipeline
+            .uniform_bindings
+            .iter()
+            .zip(buffers)
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: *binding,
+                resource: buffer.buffer.as_entire_binding(),
+            })
+            .collect::<SmallVec<[_; 4]>>();
+
+        let bind_group =
+            self.debug_validated("uniform bind group", Some("Uniform Bind Group"), || {
+                self.0.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Uniform Bind Group"),
+                    layout,
+                    entries: entries.as_slice(),
+                })
+            });
+
+        WgpuUniformBindGroup {
+            bind_group,
+            parent: PhantomData,
+        }
+    }
+}
+
+impl<'a> DeviceCompute<'_, WgpuQueue<'a>> for WgpuDevice<'a> {
+    type StorageBufferType = WgpuStorageBuffer<'a>;
+    type ComputeBindGroupType = WgpuComputeBindGroup<'a>;
+
+    fn create_storage_buffer(&self, size: u64) -> Self::StorageBufferType {
+        let buffer = self.0.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Storage Buffer"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        WgpuStorageBuffer {
+            buffer,
+            size,
+            parent: PhantomData,
+        }
+    }
+
+    fn write_storage_buffer(
+        &self,
+        queue: &WgpuQueue<'a>,
+        buffer: &Self::StorageBufferType,
+        data: &[u8],
+    ) {
+        queue.0.write_buffer(&buffer.buffer, 0, data);
+    }
+
+    fn create_compute_bind_group(
+        &self,
+        pipeline: &Self::ComputePipelineType,
+        storage_buffers: &[&Self::StorageBufferType],
+        uniforms: &[&Self::UniformBufferType],
+    ) -> Self::ComputeBindGroupType {
+        let layout = pipeline
+            .bind_group_layout
+            .as_ref()
+            .expect("The compute pipeline was created without buffers");
+
+        assert_eq!(
+            (
+                pipeline.storage_bindings.len(),
+                pipeline.uniform_bindings.len()
+            ),
+            (storage_buffers.len(), uniforms.len()),
+            "Expected one buffer per binding of the pipeline"
+        );
+
+        let storage_entries = pipeline
+            .storage_bindings
+            .iter()
+            .zip(storage_buffers.iter().map(|storage| &storage.buffer));
+        let uniform_entries = pipeline
+            .uniform_bindings
+            .iter()
+            .zip(uniforms.iter().map(|uniform| &uniform.buffer));
+
+        let entries = storage_entries
+            .chain(uniform_entries)
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: *binding,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<SmallVec<[_; 8]>>();
+
+        let bind_group =
+            self.debug_validated("compute bind group", Some("Compute Bind Group"), || {
+                self.0.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Compute Bind Group"),
+                    layout,
+                    entries: entries.as_slice(),
+                })
+            });
+
+        WgpuComputeBindGroup {
+            bind_group,
+            parent: PhantomData,
+        }
+    }
+
+    fn record_compute_pass(
+        &self,
+        command_buffer: &mut Self::CommandBufferBuilderType,
+        pipeline: &Self::ComputePipelineType,
+        dispatches: &[ComputeDispatch<'_, Self::ComputeBindGroupType>],
+    ) {
+        let mut compute_pass = command_buffer
+            .0
+            .begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Compute Pass"),
+            });
+
+        compute_pass.set_pipeline(&pipeline.pipeline);
+
+        for dispatch in dispatches {
+            let [x, y, z] = dispatch.workgroups;
+            compute_pass.set_bind_group(0, &dispatch.bind_group.bind_group, &[]);
+            compute_pass.dispatch(x, y, z);
+        }
+    }
+}
+
+impl<'a> DevicePushConstants<'_, WgpuQueue<'a>> for WgpuDevice<'a> {
+    type PushConstantsType = WgpuPushConstants<'a>;
+
+    fn create_push_constants(
+        &self,
+        pipeline: &Self::PipelineType,
+        max_draws: u32,
+    ) -> Self::PushConstantsType {
+        let layout = pipeline
+            .push_constants
+            .expect("The pipeline was created without push constants");
+
+        let emulation = pipeline
+            .push_constant_layout
+            .as_ref()
+            .map(|bind_group_layout| {
+                let alignment = self.0.limits().min_uniform_buffer_offset_alignment;
+                let stride = layout.size.div_ceil(alignment) * alignment;
+
+                let buffer = self.0.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Push Constant Buffer"),
+                    size: stride as u64 * max_draws.max(1) as u64,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
                 });
 
-                WgpuShader {
-                    module,
-                    vertex_entry: vertex_entry.to_string(),
-                    fragment_entry: fragment_entry.to_string(),
-                    parent: PhantomData,
+                let bind_group = self.0.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Push Constant Bind Group"),
+                    layout: bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: &buffer,
+                            offset: 0,
+                            size: NonZeroU64::new(layout.size as u64),
+                        }),
+                    }],
+                });
+
+                let empty_bind_group = pipeline
+                    .empty_layout
+                    .as_ref()
+                    .filter(|_| pipeline.uniform_layout.is_none())
+                    .map(|empty_layout| {
+                        self.0.create_bind_group(&wgpu::BindGroupDescriptor {
+                            label: Some("Empty Bind Group"),
+                            layout: empty_layout,
+                            entries: &[],
+                        })
+                    });
+
+                PushConstantEmulation {
+                    buffer,
+                    bind_group,
+                    empty_bind_group,
+                    stride,
+                    max_draws,
+                    staging: RefCell::new(Vec::new()),
                 }
+            });
+
+        WgpuPushConstants {
+            size: layout.size,
+            stages: layout.visibility.to_wgpu(),
+            emulation,
+            parent: PhantomData,
+        }
+    }
+
+    fn flush_push_constants(
+        &self,
+        queue: &WgpuQueue<'a>,
+        push_constants: &Self::PushConstantsType,
+    ) {
+        if let Some(emulation) = &push_constants.emulation {
+            let mut staging = emulation.staging.borrow_mut();
+            if !staging.is_empty() {
+                queue.0.write_buffer(&emulation.buffer, 0, &staging);
+                staging.clear();
+            }
+        }
+    }
+}
+
+impl<'a> DeviceRenderTargets<'_> for WgpuDevice<'a> {
+    type RenderTargetType = WgpuRenderTarget<'a>;
+
+    fn create_multi_render_target(
+        &self,
+        formats: &[Self::ImageFormatType],
+        depth_format: Option<DepthFormat>,
+        size: PhysicalSize<u32>,
+    ) -> Self::RenderTargetType {
+        assert!(!formats.is_empty(), "A render target needs a color texture");
+
+        let create_texture = |label, format, usage| {
+            let texture = self.debug_validated("render target texture", Some(label), || {
+                self.0.create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: size.width,
+                        height: size.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING
+                        | usage,
+                })
+            });
+
+            WgpuTexture {
+                texture,
+                size,
+                format,
+                parent: PhantomData,
             }
+        };
+
+        let colors = formats
+            .iter()
+            .map(|format| {
+                create_texture(
+                    "Render Target Color",
+                    format.get_backing_format(),
+                    wgpu::TextureUsages::COPY_SRC,
+                )
+            })
+            .collect::<Vec<_>>();
+        let depth = depth_format.map(|depth_format| {
+            create_texture(
+                "Render Target Depth",
+                depth_format.to_wgpu(),
+                wgpu::TextureUsages::empty(),
+            )
+        });
+
+        let create_view = |texture: &WgpuTexture| {
+            texture
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+
+        WgpuRenderTarget {
+            color_views: colors.iter().map(create_view).collect(),
+            depth_view: depth.as_ref().map(create_view),
+            colors,
+            depth,
+            depth_format,
+        }
+    }
+}
+
+impl<'a> DeviceTextureFactory<'_, WgpuQueue<'a>> for WgpuDevice<'a> {
+    fn create_texture_from_pixels(
+        &self,
+        queue: &WgpuQueue<'a>,
+        image: &TextureImage,
+    ) -> Self::TextureType {
+        let texture = self.create_image_texture(image);
+
+        for (mip_level, level) in image.levels.iter().enumerate() {
+            queue.0.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture.texture,
+                    mip_level: mip_level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &level.data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(level.size.width * 4),
+                    rows_per_image: NonZeroU32::new(level.size.height),
+                },
+                wgpu::Extent3d {
+                    width: level.size.width,
+                    height: level.size.height,
+                    depth_or_array_layers: 1,
+                },
+            );
         }
+
+        texture
+    }
+}
+
+impl<'a, T: WgpuReadableTexture> DeviceTextureReader<'_, WgpuQueue<'a>, T> for WgpuDevice<'a> {
+    type ReadbackType = WgpuTextureReadback;
+
+    fn read_pixels(&self, queue: &WgpuQueue<'a>, texture: &T) -> Self::ReadbackType {
+        let size = texture.get_readback_size();
+        let swap_red_blue = match texture.get_readback_format() {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            _ => {
+                return WgpuTextureReadback::failed(ReadbackError::UnsupportedFormat);
+            }
+        };
+
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = (size.width * 4).div_ceil(align) * align;
+
+        let buffer = self.0.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Texture Readback Buffer"),
+            size: padded_bytes_per_row as u64 * size.height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .0
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Texture Readback Encoder"),
+            });
+
+        encoder.copy_texture_to_buffer(
+            texture.get_readback_texture().as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        queue.0.submit(std::iter::once(encoder.finish()));
+
+        WgpuTextureReadback::mapping(buffer, size, padded_bytes_per_row, swap_red_blue)
+    }
+
+    fn poll_readback(
+        &self,
+        readback: &mut Self::ReadbackType,
+    ) -> Option<Result<TexturePixels, ReadbackError>> {
+        self.0.poll(wgpu::Maintain::Poll);
+        readback.poll()
+    }
+
+    fn wait_readback(
+        &self,
+        mut readback: Self::ReadbackType,
+    ) -> Result<TexturePixels, ReadbackError> {
+        #[cfg(target_arch = "wasm32")]
+        panic!("cannot block on a texture readback on the web");
+
+        self.0.poll(wgpu::Maintain::Wait);
+        readback
+            .poll()
+            .expect("the texture readback was already taken")
+    }
+}
+
+impl<'a> DeviceGpuTimer<'_> for WgpuDevice<'a> {
+    type QueueType = WgpuQueue<'a>;
+    type GpuTimerType = WgpuGpuTimer<'a>;
+
+    fn create_gpu_timer(
+        &self,
+        queue: &Self::QueueType,
+        max_passes: u32,
+    ) -> Option<Self::GpuTimerType> {
+        if !self.0.features().contains(wgpu::Features::TIMESTAMP_QUERY) || max_passes == 0 {
+            return None;
+        }
+
+        Some(WgpuGpuTimer::new(
+            &self.0,
+            queue.0.get_timestamp_period(),
+            max_passes,
+        ))
+    }
+
+    fn poll_gpu_timer(&self, timer: &mut Self::GpuTimerType) -> Option<Vec<PassTiming>> {
+        self.0.poll(wgpu::Maintain::Poll);
+        timer.poll()
     }
 }
 
@@ -250,3 +1248,546 @@ impl<'a> CommandBuffer<'_> for WgpuCommandBuffer<'a> {
         self.0
     }
 }
+
+impl<'a> DeviceRenderDebug<'_> for WgpuDevice<'a> {
+    fn get_render_debug_switch(&self) -> RenderDebugSwitch {
+        self.2.clone()
+    }
+
+    fn supports_render_debug_mode(&self, mode: RenderDebugMode) -> bool {
+        match mode {
+            RenderDebugMode::Normal => true,
+            RenderDebugMode::Wireframe => {
+                cfg!(feature = "render_debug")
+                    && self
+                        .0
+                        .features()
+                        .contains(wgpu::Features::POLYGON_MODE_LINE)
+            }
+            RenderDebugMode::Overdraw => cfg!(feature = "render_debug"),
+        }
+    }
+}
+
+// wgpu forwards captures to the in-application API of RenderDoc when it is loaded
+impl<'a> DeviceFrameCapture<'_> for WgpuDevice<'a> {
+    fn start_frame_capture(&self) {
+        if cfg!(not(target_arch = "wasm32")) {
+            self.0.start_capture();
+        }
+    }
+
+    fn stop_frame_capture(&self) {
+        if cfg!(not(target_arch = "wasm32")) {
+            self.0.stop_capture();
+        }
+    }
+}
+
+impl<'a> DeviceUploads<'_, WgpuQueue<'a>> for WgpuDevice<'a> {
+    type UploaderType = WgpuUploader<'a>;
+
+    fn create_uploader(&self, budget: u64) -> Self::UploaderType {
+        WgpuUploader {
+            scheduler: UploadScheduler::new(budget),
+        }
+    }
+
+    fn queue_texture_upload(
+        &self,
+        uploader: &mut Self::UploaderType,
+        image: &TextureImage,
+    ) -> PendingUpload<Self::TextureType> {
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let mut upload_size = 0;
+
+        // Rows of buffer to texture copies have to be aligned
+        let levels = image
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(mip_level, level)| {
+                let bytes_per_row = level.size.width * 4;
+                let padded_bytes_per_row = bytes_per_row.div_ceil(align) * align;
+                let size = padded_bytes_per_row as u64 * level.size.height as u64;
+                upload_size += size;
+
+                let buffer = self.0.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Texture Upload Buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: true,
+                });
+
+                {
+                    let mut mapped = buffer.slice(..).get_mapped_range_mut();
+                    for (row, data) in level.data.chunks_exact(bytes_per_row as usize).enumerate() {
+                        let start = row * padded_bytes_per_row as usize;
+                        mapped[start..start + data.len()].copy_from_slice(data);
+                    }
+                }
+                buffer.unmap();
+
+                StagedLevel {
+                    buffer,
+                    padded_bytes_per_row,
+                    mip_level: mip_level as u32,
+                    size: wgpu::Extent3d {
+                        width: level.size.width,
+                        height: level.size.height,
+                        depth_or_array_layers: 1,
+                    },
+                }
+            })
+            .collect();
+
+        let (pending, slot) = PendingUpload::new();
+        uploader.scheduler.push(
+            upload_size,
+            StagedUpload::Texture {
+                levels,
+                texture: self.create_image_texture(image),
+                slot,
+            },
+        );
+
+        pending
+    }
+
+    fn queue_storage_upload(
+        &self,
+        uploader: &mut Self::UploaderType,
+        data: &[u8],
+    ) -> PendingUpload<Self::StorageBufferType> {
+        // Buffer copies are made in whole words
+        let align = wgpu::COPY_BUFFER_ALIGNMENT;
+        let size = (data.len() as u64).div_ceil(align) * align;
+
+        let staging = self.0.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Storage Upload Buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        });
+        staging.slice(..).get_mapped_range_mut()[..data.len()].copy_from_slice(data);
+        staging.unmap();
+
+        let (pending, slot) = PendingUpload::new();
+        uploader.scheduler.push(
+            size,
+            StagedUpload::Storage {
+                staging,
+                buffer: self.create_storage_buffer(size),
+                slot,
+            },
+        );
+
+        pending
+    }
+
+    fn submit_uploads(
+        &self,
+        queues: &DeviceQueues<WgpuQueue<'a>>,
+        uploader: &mut Self::UploaderType,
+    ) {
+        let batch = uploader.scheduler.take_batch();
+        if batch.is_empty() {
+            return;
+        }
+
+        let mut encoder = self
+            .0
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Upload Encoder"),
+            });
+
+        for upload in &batch {
+            upload.record(&mut encoder);
+        }
+
+        queues
+            .transfer()
+            .0
+            .submit(std::iter::once(encoder.finish()));
+
+        batch.into_iter().for_each(StagedUpload::complete);
+    }
+}
+
+impl<'a> DevicePipelineCache<'_> for WgpuDevice<'a> {
+    fn invalidate_shader(&self, code: &ShaderCode<'_>) {
+        let key = shader_key(code);
+        self.3.shaders.invalidate_shader(key);
+        self.3.pipelines.invalidate_shader(key);
+    }
+
+    fn clear_pipeline_cache(&self) {
+        self.3.shaders.clear();
+        self.3.pipelines.clear();
+    }
+
+    fn get_pipeline_cache_stats(&self) -> (CacheStats, CacheStats) {
+        (self.3.shaders.get_stats(), self.3.pipelines.get_stats())
+    }
+}
+
+impl<'a> DeviceMaterials<'_, WgpuQueue<'a>> for WgpuDevice<'a> {
+    type MaterialType = WgpuMaterial<'a>;
+
+    fn create_material(
+        &self,
+        queue: &WgpuQueue<'a>,
+        info: &PipelineCreateInfo<
+            '_,
+            Self::PipelineLayoutType,
+            Self::ShaderType,
+            Self::ImageFormatType,
+        >,
+    ) -> Self::MaterialType {
+        let layout = info
+            .material
+            .expect("The pipeline was created without a material layout")
+            .clone();
+
+        let pipeline = self.create_pipeline(info);
+
+        let buffer = (layout.get_size() > 0).then(|| {
+            self.0.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Material Buffer"),
+                size: layout.get_size(),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        });
+
+        let placeholder = (!layout.get_textures().is_empty()).then(|| {
+            let white = TexturePixels {
+                size: PhysicalSize {
+                    width: 1,
+                    height: 1,
+                },
+                data: vec![u8::MAX; 4],
+            };
+
+            self.create_texture_from_pixels(
+                queue,
+                &TextureImage::from_pixels(
+                    white,
+                    ImageOptions {
+                        srgb: true,
+                        generate_mipmaps: false,
+                    },
+                ),
+            )
+        });
+
+        // Views keep the placeholder texture alive
+        let views = placeholder
+            .iter()
+            .flat_map(|texture| layout.get_textures().iter().map(|_| texture.create_view()))
+            .collect();
+
+        let sampler = self.0.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Material Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let empty_bind_group = pipeline.empty_layout.as_ref().map(|empty_layout| {
+            self.0.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Empty Bind Group"),
+                layout: empty_layout,
+                entries: &[],
+            })
+        });
+
+        WgpuMaterial {
+            pipeline,
+            params: ParameterBlock::new(layout),
+            buffer,
+            views,
+            sampler,
+            bind_group: None,
+            empty_bind_group,
+        }
+    }
+
+    fn flush_material(&self, queue: &WgpuQueue<'a>, material: &mut Self::MaterialType) {
+        if material.params.take_dirty() {
+            if let Some(buffer) = &material.buffer {
+                queue.0.write_buffer(buffer, 0, material.params.get_bytes());
+            }
+        }
+
+        if material.bind_group.is_some() {
+            return;
+        }
+
+        let layout = material
+            .pipeline
+            .material_layout
+            .as_ref()
+            .expect("The pipeline was created without a material layout");
+
+        let params = material.buffer.as_ref().map(|buffer| wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        });
+
+        let textures = material.views.iter().zip(0u32..).flat_map(|(view, index)| {
+            [
+                wgpu::BindGroupEntry {
+                    binding: 1 + 2 * index,
+                    resource: wgpu::BindingResource::TextureView(&view.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2 + 2 * index,
+                    resource: wgpu::BindingResource::Sampler(&material.sampler),
+                },
+            ]
+        });
+
+        let entries = params
+            .into_iter()
+            .chain(textures)
+            .collect::<SmallVec<[_; 8]>>();
+
+        let bind_group =
+            self.debug_validated("material bind group", Some("Material Bind Group"), || {
+                self.0.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Material Bind Group"),
+                    layout,
+                    entries: entries.as_slice(),
+                })
+            });
+        material.bind_group = Some(bind_group);
+    }
+}
+
+impl<'a> DevicePicking<'_, WgpuQueue<'a>> for WgpuDevice<'a> {
+    type PickReadbackType = WgpuPickReadback;
+
+    fn get_pick_format(&self) -> Self::ImageFormatType {
+        WgpuTextureFormat::from(PICK_FORMAT)
+    }
+
+    fn read_pick_id(
+        &self,
+        queue: &WgpuQueue<'a>,
+        target: &Self::RenderTargetType,
+        x: u32,
+        y: u32,
+    ) -> Self::PickReadbackType {
+        let texture = &target.colors[0];
+        assert!(
+            x < texture.size.width && y < texture.size.height,
+            "The pick position is outside of the target"
+        );
+
+        if texture.format != PICK_FORMAT {
+            return WgpuPickReadback(WgpuTextureReadback::failed(
+                ReadbackError::UnsupportedFormat,
+            ));
+        }
+
+        // A single row still has to be aligned
+        let padded_bytes_per_row = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = self.0.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pick Readback Buffer"),
+            size: padded_bytes_per_row as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .0
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Pick Readback Encoder"),
+            });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        queue.0.submit(std::iter::once(encoder.finish()));
+
+        let size = PhysicalSize {
+            width: 1,
+            height: 1,
+        };
+        WgpuPickReadback(WgpuTextureReadback::mapping(
+            buffer,
+            size,
+            padded_bytes_per_row,
+            false,
+        ))
+    }
+
+    fn poll_pick_id(
+        &self,
+        readback: &mut Self::PickReadbackType,
+    ) -> Option<Result<Option<u32>, ReadbackError>> {
+        self.0.poll(wgpu::Maintain::Poll);
+        readback.poll()
+    }
+}
+
+impl<'a> DeviceShadowMaps<'_, WgpuQueue<'a>> for WgpuDevice<'a> {
+    type ShadowMapType = WgpuShadowMap<'a>;
+
+    fn create_shadow_pipeline(
+        &self,
+        info: &ShadowPipelineCreateInfo<
+            '_,
+            Self::PipelineLayoutType,
+            Self::ShaderType,
+            Self::ImageFormatType,
+        >,
+    ) -> Self::PipelineType {
+        let shader_key = info.pipeline.shader.key;
+        let key = shadow_pipeline_key(shader_key, info);
+
+        self.3.pipelines.get_or_create(key, shader_key, || {
+            self.build_pipeline(&info.pipeline, Some(info.pass))
+        })
+    }
+
+    fn create_shadow_map(&self, config: ShadowMapConfig) -> Self::ShadowMapType {
+        let size = PhysicalSize {
+            width: config.size,
+            height: config.size,
+        };
+        let format = config.depth_format.to_wgpu();
+
+        let texture = self.0.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        });
+        let depth_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Texels outside of the shadow map are lit
+        let sampler = self.0.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let buffer = self.0.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow Buffer"),
+            size: ShadowUniform::SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group =
+            self.debug_validated("shadow bind group", Some("Shadow Bind Group"), || {
+                self.0.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Shadow Bind Group"),
+                    layout: &create_shadow_bind_group_layout(&self.0),
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(&depth_view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: wgpu::BindingResource::Sampler(&sampler),
+                        },
+                    ],
+                })
+            });
+
+        let empty_layout = self
+            .0
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Empty Bind Group Layout"),
+                entries: &[],
+            });
+        let empty_bind_group = self.0.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Empty Bind Group"),
+            layout: &empty_layout,
+            entries: &[],
+        });
+
+        WgpuShadowMap {
+            depth: WgpuTexture {
+                texture,
+                size,
+                format,
+                parent: PhantomData,
+            },
+            depth_view,
+            config,
+            buffer,
+            bind_group,
+            empty_bind_group,
+        }
+    }
+
+    fn write_shadow_uniform(
+        &self,
+        queue: &WgpuQueue<'a>,
+        shadow_map: &Self::ShadowMapType,
+        uniform: &ShadowUniform,
+    ) {
+        queue
+            .0
+            .write_buffer(&shadow_map.buffer, 0, &uniform.as_bytes());
+    }
+}
+
+impl<'a> DeviceDeferredDeletion<'_, WgpuQueue<'a>> for WgpuDevice<'a> {
+    type ResourceType = WgpuResource<'a>;
+
+    fn destroy_deferred(&self, resource: impl Into<Self::ResourceType>) {
+        self.4.push(resource.into());
+    }
+
+    fn end_frame(&self, queue: &WgpuQueue<'a>) {
+        self.4.end_frame(&self.0, &queue.0);
+    }
+
+    fn get_pending_deletions(&self) -> usize {
+        self.4.len()
+    }
+}
@@ -0,0 +1,218 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::device::{WgpuCommandBufferBuilder, WgpuDevice};
+use crate::texture::{WgpuTextureFormat, WgpuTextureView};
+use pluto_engine_render::device::{CommandBufferBuilder, Device};
+use pluto_engine_render::texture::TextureFormat;
+use std::borrow::Cow;
+use wgpu::util::DeviceExt;
+
+/// The WGSL code drawing immediate 2D triangles.
+pub const DRAW_2D_SHADER: &str = include_str!("shaders/draw_2d.wgsl");
+
+/// A corner of a 2D triangle, with an sRGB color and straight alpha.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Draw2DVertex {
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl Draw2DVertex {
+    const SIZE: u64 = std::mem::size_of::<Self>() as u64;
+}
+
+/// Draws 2D triangles in submission order on top of a texture, without depth testing.
+pub struct WgpuDraw2DRenderer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// Whether the target converts colors to sRGB, in which case the shader outputs linear colors.
+    linear_output: bool,
+}
+
+impl WgpuDraw2DRenderer {
+    /// Creates a renderer drawing into textures of the given format.
+    pub fn new(device: &WgpuDevice<'_>, format: WgpuTextureFormat) -> Self {
+        let device = device.get_backing_device();
+        let format = format.get_backing_format();
+
+        let module = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("Draw 2D Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::from(DRAW_2D_SHADER)),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Draw 2D"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Draw 2D"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Draw 2D"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: Draw2DVertex::SIZE,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: None,
+                ..wgpu::PrimitiveState::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            linear_output: format.describe().srgb,
+        }
+    }
+
+    /// Records a pass drawing the triangles on top of the output,
+    /// every three vertices form a triangle.
+    ///
+    /// `projection` is a column-major matrix transforming the vertices into clip space.
+    pub fn record(
+        &self,
+        device: &WgpuDevice<'_>,
+        command_buffer: &mut WgpuCommandBufferBuilder<'_>,
+        output: &WgpuTextureView<'_>,
+        projection: [[f32; 4]; 4],
+        vertices: &[Draw2DVertex],
+    ) {
+        // An incomplete triangle would not be drawn
+        let vertex_count = vertices.len() as u32 / 3 * 3;
+        if vertex_count == 0 {
+            return;
+        }
+
+        let device = device.get_backing_device();
+
+        let mut uniform = projection
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect::<Vec<_>>();
+        uniform.extend(
+            [if self.linear_output { 1.0f32 } else { 0.0 }, 0.0, 0.0, 0.0]
+                .iter()
+                .flat_map(|value| value.to_ne_bytes()),
+        );
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Draw 2D Uniform"),
+            contents: &uniform,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Draw 2D"),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let vertex_data = vertices
+            .iter()
+            .flat_map(|vertex| vertex.position.iter().chain(vertex.color.iter()))
+            .flat_map(|value| value.to_ne_bytes())
+            .collect::<Vec<_>>();
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Draw 2D Vertices"),
+            contents: &vertex_data,
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let encoder = command_buffer.get_backing_command_buffer_builder();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Draw 2D"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: &output.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.draw(0..vertex_count, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::draw_2d::DRAW_2D_SHADER;
+
+    /// The 2D shader is parsed and validated.
+    /// It should be valid WGSL.
+    #[test]
+    fn test_draw_2d_shader_validates() {
+        let module = naga::front::wgsl::parse_str(DRAW_2D_SHADER).unwrap();
+
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap();
+    }
+}
@@ -0,0 +1,159 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::device::WgpuCommandBufferBuilder;
+use crate::render_pass::WgpuRenderPass;
+use crate::texture::WgpuTextureView;
+use pluto_engine_render::device::CommandBufferBuilder;
+use pluto_engine_render::frame::{ClearColor, FrameComposer, LoadOp};
+use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+use pluto_engine_render::render_pass::{RenderPass, ScissorRect, Viewport};
+
+/// The target and command encoder a frame pass records into.
+pub struct WgpuFramePassContext<'r> {
+    name: &'static str,
+    encoder: &'r mut wgpu::CommandEncoder,
+    view: &'r wgpu::TextureView,
+    size: PhysicalSize<u32>,
+    load: LoadOp,
+    viewport: Option<Viewport>,
+    scissor_rect: Option<ScissorRect>,
+}
+
+impl<'r> WgpuFramePassContext<'r> {
+    /// Begins a render pass drawing into the target of the frame,
+    /// using the load operation, viewport and scissor rectangle of the frame pass.
+    ///
+    /// *The render pass is labeled with the name of the frame pass unless a label is given.*
+    pub fn begin_render_pass<'p>(&'p mut self, label: Option<&'p str>) -> WgpuRenderPass<'p> {
+        let viewport = self.get_viewport();
+        let scissor_rect = self.get_scissor_rect();
+
+        let mut render_pass =
+            WgpuRenderPass(self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: label.or(Some(self.name)),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: self.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: to_wgpu_load(self.load),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            }));
+
+        // A viewport entirely outside of the target is kept from drawing by an empty scissor
+        match viewport {
+            Some(viewport) => {
+                render_pass.set_viewport(&viewport);
+                render_pass.set_scissor_rect(&scissor_rect);
+            }
+            None => render_pass.set_scissor_rect(&ScissorRect::new(0, 0, 0, 0)),
+        }
+
+        render_pass
+    }
+
+    /// *Returns the viewport of the frame pass clamped to the target,
+    /// `None` if it lies outside of the target.*
+    pub fn get_viewport(&self) -> Option<Viewport> {
+        self.viewport
+            .unwrap_or_else(|| Viewport::full(self.size))
+            .clamp(self.size)
+    }
+
+    /// *Returns the scissor rectangle of the frame pass clamped to the target.*
+    pub fn get_scissor_rect(&self) -> ScissorRect {
+        self.scissor_rect
+            .unwrap_or_else(|| ScissorRect::full(self.size))
+            .clamp(self.size)
+    }
+
+    /// Returns the size of the target, e.g. to split it into viewports.
+    pub fn get_target_size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+
+    /// Returns the encoder, for work other than drawing into the target such as copies.
+    pub fn get_encoder(&mut self) -> &mut wgpu::CommandEncoder {
+        self.encoder
+    }
+
+    pub fn get_view(&self) -> &wgpu::TextureView {
+        self.view
+    }
+}
+
+pub type WgpuFramePass<'a> = Box<dyn FnMut(&mut WgpuFramePassContext<'_>) + 'a>;
+
+pub type WgpuFrameComposer<'a> = FrameComposer<WgpuFramePass<'a>>;
+
+pub(crate) fn to_wgpu_load(load: LoadOp) -> wgpu::LoadOp<wgpu::Color> {
+    match load {
+        LoadOp::Load => wgpu::LoadOp::Load,
+        LoadOp::Clear(ClearColor { r, g, b, a }) => wgpu::LoadOp::Clear(wgpu::Color { r, g, b, a }),
+    }
+}
+
+/// Clears the target to the clear color of the composer and records all of its passes.
+pub fn record_frame(
+    composer: &mut WgpuFrameComposer<'_>,
+    command_buffer: &mut WgpuCommandBufferBuilder<'_>,
+    target: &WgpuTextureView<'_>,
+) {
+    let encoder = command_buffer.get_backing_command_buffer_builder();
+
+    if let Some(clear_color) = composer.get_clear_color() {
+        WgpuFramePassContext {
+            name: "Frame clear",
+            encoder,
+            view: &target.view,
+            size: target.size,
+            load: LoadOp::Clear(clear_color),
+            viewport: None,
+            scissor_rect: None,
+        }
+        .begin_render_pass(None);
+    }
+
+    // Groups the commands of each pass under its name in frame debuggers
+    composer.for_each_pass(|pass| {
+        encoder.push_debug_group(pass.get_name());
+
+        let mut context = WgpuFramePassContext {
+            name: pass.get_name(),
+            encoder,
+            view: &target.view,
+            size: target.size,
+            load: pass.get_load(),
+            viewport: pass.get_viewport(),
+            scissor_rect: pass.get_scissor_rect(),
+        };
+
+        (pass.recorder)(&mut context);
+
+        encoder.pop_debug_group();
+    });
+}
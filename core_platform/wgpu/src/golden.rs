@@ -0,0 +1,255 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+//! Renders scenes on a headless device and compares them with golden images,
+//! so renderer changes can be validated automatically.
+//!
+//! Golden images are stored in the `golden` directory of this crate. Setting the
+//! `PLUTO_UPDATE_GOLDEN` environment variable writes the rendered images instead of comparing,
+//! the changed images should then be reviewed before committing them.
+//!
+//! *Scenes are skipped on machines without a graphics adapter.*
+
+use crate::device::{WgpuCommandBufferBuilder, WgpuDevice, WgpuQueue};
+use crate::instance::create_headless_device;
+use crate::texture::{WgpuTexture, WgpuTextureFormat, WgpuTextureView};
+use pluto_engine_render::device::{
+    CommandBuffer, CommandBufferBuilder, Device, DeviceTextureReader, PhysicalDevice, Queue,
+};
+use pluto_engine_render::golden::{compare_pixels, diff_pixels, GoldenTolerance};
+use pluto_engine_render::instance::AdapterSelection;
+use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+use pluto_engine_render::target::{DeviceRenderTargets, RenderTarget};
+use pluto_engine_render::texture::{Texture, TexturePixels};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/golden");
+const UPDATE_VARIABLE: &str = "PLUTO_UPDATE_GOLDEN";
+
+/// The format scenes are rendered in, without sRGB conversion so pixels match the colors.
+pub(crate) const GOLDEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// *Returns `None` if no adapter is available, including the software fallback.*
+pub(crate) fn headless_device() -> Option<(WgpuDevice<'static>, WgpuQueue<'static>)> {
+    let selection = AdapterSelection {
+        software_fallback: true,
+        ..AdapterSelection::default()
+    };
+
+    let physical_device = match create_headless_device(&selection) {
+        Ok(physical_device) => physical_device,
+        Err(err) => {
+            eprintln!("Skipping the golden image test: {}", err);
+            return None;
+        }
+    };
+
+    Some(physical_device.create_device_and_queue().unwrap())
+}
+
+/// Renders a scene into a new target of [`GOLDEN_FORMAT`] and reads it back.
+pub(crate) fn render_scene(
+    device: &WgpuDevice<'static>,
+    queue: &WgpuQueue<'static>,
+    size: PhysicalSize<u32>,
+    record: impl FnOnce(&mut WgpuCommandBufferBuilder<'static>, &WgpuTextureView<'static>),
+) -> TexturePixels {
+    let target = device.create_render_target(WgpuTextureFormat::from(GOLDEN_FORMAT), None, size);
+
+    let mut command_buffer = device.begin_command_buffer();
+    record(
+        &mut command_buffer,
+        &target.get_color_texture().create_view(),
+    );
+    queue.get_backing_queue().submit(std::iter::once(
+        command_buffer.build().get_backing_command_buffer(),
+    ));
+
+    let readback = device.read_pixels(queue, target.get_color_texture());
+    DeviceTextureReader::<_, WgpuTexture<'static>>::wait_readback(device, readback).unwrap()
+}
+
+/// Compares the pixels with the golden image of the given name.
+///
+/// ***Panics*** if the images differ by more than the tolerance, writing the rendered image
+/// and a difference image to the temporary directory.
+pub(crate) fn assert_golden(name: &str, pixels: &TexturePixels, tolerance: &GoldenTolerance) {
+    let path = Path::new(GOLDEN_DIR).join(format!("{}.png", name));
+
+    if std::env::var_os(UPDATE_VARIABLE).is_some() {
+        write_png(&path, pixels);
+        return;
+    }
+
+    let expected = read_png(&path);
+    if let Err(err) = compare_pixels(pixels, &expected, tolerance) {
+        let (actual_path, diff_path) = failure_paths(name);
+        write_png(&actual_path, pixels);
+        if pixels.size == expected.size {
+            write_png(&diff_path, &diff_pixels(pixels, &expected, tolerance));
+        }
+
+        panic!(
+            "The scene \"{}\" does not match its golden image, {}. The rendered image was written to {}.",
+            name,
+            err,
+            actual_path.display()
+        );
+    }
+}
+
+fn failure_paths(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join("pluto_golden");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    (
+        dir.join(format!("{}.actual.png", name)),
+        dir.join(format!("{}.diff.png", name)),
+    )
+}
+
+fn read_png(path: &Path) -> TexturePixels {
+    let file = File::open(path).unwrap_or_else(|err| {
+        panic!(
+            "Failed to open the golden image {}, set {} to create it: {}",
+            path.display(),
+            UPDATE_VARIABLE,
+            err
+        )
+    });
+
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).unwrap();
+    data.truncate(info.buffer_size());
+
+    assert_eq!(
+        (info.color_type, info.bit_depth),
+        (png::ColorType::Rgba, png::BitDepth::Eight),
+        "Golden images must be 8-bit RGBA"
+    );
+
+    TexturePixels {
+        size: PhysicalSize {
+            width: info.width,
+            height: info.height,
+        },
+        data,
+    }
+}
+
+fn write_png(path: &Path, pixels: &TexturePixels) {
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, pixels.size.width, pixels.size.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(&pixels.data)
+        .unwrap();
+}
+
+#[cfg(test)]
+mod test {
+    use crate::draw_2d::{Draw2DVertex, WgpuDraw2DRenderer};
+    use crate::frame::{record_frame, WgpuFrameComposer};
+    use crate::golden::{assert_golden, headless_device, render_scene, GOLDEN_FORMAT};
+    use crate::texture::WgpuTextureFormat;
+    use pluto_engine_render::frame::ClearColor;
+    use pluto_engine_render::golden::GoldenTolerance;
+    use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+
+    const SIZE: PhysicalSize<u32> = PhysicalSize {
+        width: 64,
+        height: 64,
+    };
+
+    /// A target is cleared by a frame composer.
+    /// Every pixel should have the clear color.
+    #[test]
+    fn test_golden_clear() {
+        let Some((device, queue)) = headless_device() else {
+            return;
+        };
+
+        let pixels = render_scene(&device, &queue, SIZE, |command_buffer, view| {
+            let mut composer = WgpuFrameComposer::new();
+            composer.set_clear_color(Some(ClearColor::new(0.2, 0.4, 0.6, 1.0)));
+            record_frame(&mut composer, command_buffer, view);
+        });
+
+        assert_golden("clear", &pixels, &GoldenTolerance::default());
+    }
+
+    /// An opaque red and a translucent green rectangle are drawn by the 2D renderer,
+    /// overlapping on a black background.
+    /// The rectangles should cover whole pixels and the green one should be blended.
+    #[test]
+    fn test_golden_draw_2d() {
+        let Some((device, queue)) = headless_device() else {
+            return;
+        };
+
+        let rectangle = |x: f32, y: f32, width: f32, height: f32, color: [f32; 4]| {
+            [
+                [x, y],
+                [x, y + height],
+                [x + width, y],
+                [x + width, y],
+                [x, y + height],
+                [x + width, y + height],
+            ]
+            .map(|position| Draw2DVertex { position, color })
+        };
+
+        let vertices = [
+            rectangle(16.0, 8.0, 32.0, 16.0, [1.0, 0.0, 0.0, 1.0]),
+            rectangle(32.0, 16.0, 24.0, 24.0, [0.0, 1.0, 0.0, 0.5]),
+        ]
+        .concat();
+
+        // Maps pixel coordinates with the origin in the top left corner to clip space
+        let projection = [
+            [2.0 / SIZE.width as f32, 0.0, 0.0, 0.0],
+            [0.0, -2.0 / SIZE.height as f32, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [-1.0, 1.0, 0.0, 1.0],
+        ];
+
+        let renderer = WgpuDraw2DRenderer::new(&device, WgpuTextureFormat::from(GOLDEN_FORMAT));
+        let pixels = render_scene(&device, &queue, SIZE, |command_buffer, view| {
+            let mut composer = WgpuFrameComposer::new();
+            composer.set_clear_color(Some(ClearColor::new(0.0, 0.0, 0.0, 1.0)));
+            record_frame(&mut composer, command_buffer, view);
+
+            renderer.record(&device, command_buffer, view, projection, &vertices);
+        });
+
+        assert_golden("draw_2d", &pixels, &GoldenTolerance::default());
+    }
+}
@@ -24,16 +24,50 @@
 
 use crate::device::WgpuPhysicalDevice;
 use crate::surface::WgpuSurface;
-use pluto_engine_render::device::PhysicalDevice;
-use pluto_engine_render::instance::ContextInstance;
+use pluto_engine_render::device::{DeviceError, PhysicalDevice};
+use pluto_engine_render::instance::{
+    AdapterInfo, AdapterLimits, AdapterPreference, AdapterSelection, AdapterType, ContextInstance,
+    GraphicsBackend,
+};
 use pluto_engine_render::pluto_engine_window::window::Window;
 use pluto_engine_render::surface::Surface;
 use raw_window_handle::HasRawWindowHandle;
+use std::sync::Arc;
 
 pub struct WgpuInstance<
     'a,
     W: Window<SizeType = <WgpuSurface<'a> as Surface<'a>>::SizeType> + HasRawWindowHandle + 'a,
->(wgpu::Instance, &'a W);
+>(Arc<wgpu::Instance>, &'a W);
+
+fn adapter_info(adapter: &wgpu::Adapter) -> AdapterInfo {
+    let info = adapter.get_info();
+    let limits = adapter.limits();
+
+    AdapterInfo {
+        name: info.name,
+        adapter_type: match info.device_type {
+            wgpu::DeviceType::DiscreteGpu => AdapterType::DiscreteGpu,
+            wgpu::DeviceType::IntegratedGpu => AdapterType::IntegratedGpu,
+            wgpu::DeviceType::VirtualGpu => AdapterType::VirtualGpu,
+            wgpu::DeviceType::Cpu => AdapterType::Cpu,
+            wgpu::DeviceType::Other => AdapterType::Other,
+        },
+        backend: match info.backend {
+            wgpu::Backend::Vulkan => GraphicsBackend::Vulkan,
+            wgpu::Backend::Metal => GraphicsBackend::Metal,
+            wgpu::Backend::Dx12 => GraphicsBackend::Dx12,
+            wgpu::Backend::Dx11 => GraphicsBackend::Dx11,
+            wgpu::Backend::Gl => GraphicsBackend::Gl,
+            wgpu::Backend::BrowserWebGpu => GraphicsBackend::BrowserWebGpu,
+            wgpu::Backend::Empty => GraphicsBackend::Other,
+        },
+        limits: AdapterLimits {
+            max_texture_dimension_2d: limits.max_texture_dimension_2d,
+            max_bind_groups: limits.max_bind_groups,
+            max_push_constant_size: limits.max_push_constant_size,
+        },
+    }
+}
 
 impl<
         'a,
@@ -42,31 +76,110 @@ impl<
 {
     type BackingType = wgpu::Instance;
 
-    type PhysicalDeviceType = WgpuPhysicalDevice<'a>;
-    type SurfaceType = WgpuSurface<'a>;
+    // Neither borrows the window, so that displays can own the window together with its surface,
+    // the window must then outlive the surface.
+    type PhysicalDeviceType = WgpuPhysicalDevice<'static>;
+    type SurfaceType = WgpuSurface<'static>;
     type WindowType = W;
 
     fn new(window: &'a Self::WindowType) -> Self {
         let instance = wgpu::Instance::new(wgpu::Backends::all());
-        Self(instance, window)
+        Self(Arc::new(instance), window)
     }
 
-    fn create_device_and_surface(&self) -> (Self::PhysicalDeviceType, Self::SurfaceType) {
+    fn create_device_and_surface_with(
+        &self,
+        selection: &AdapterSelection,
+    ) -> Result<(Self::PhysicalDeviceType, Self::SurfaceType), DeviceError> {
         let surface = unsafe { self.0.create_surface(self.1) };
-        let adapter = pollster::block_on(self.0.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::default(),
-            compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
-        }))
-        .unwrap();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut adapters = self
+            .0
+            .enumerate_adapters(wgpu::Backends::all())
+            .filter(|adapter| adapter.is_surface_supported(&surface))
+            .collect::<Vec<_>>();
+        #[cfg(target_arch = "wasm32")]
+        let mut adapters = Vec::<wgpu::Adapter>::new();
+
+        let infos = adapters.iter().map(adapter_info).collect::<Vec<_>>();
+        let chosen = selection
+            .choose(&infos)
+            .map(|index| adapters.swap_remove(index));
+
+        let request = |force_fallback_adapter| {
+            pollster::block_on(self.0.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: match selection.preference {
+                    AdapterPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+                    _ => wgpu::PowerPreference::LowPower,
+                },
+                compatible_surface: Some(&surface),
+                force_fallback_adapter,
+            }))
+        };
+
+        let adapter = chosen
+            .or_else(|| request(false))
+            .or_else(|| selection.software_fallback.then(|| request(true)).flatten())
+            .ok_or(DeviceError::NoAdapter)?;
 
         let physical_device = WgpuPhysicalDevice::new(adapter);
-        let sfc = WgpuSurface::from_window(self.1, &physical_device, surface);
+        let sfc = WgpuSurface::from_window(self.1, &physical_device, Arc::clone(&self.0), surface);
 
-        (physical_device, sfc)
+        Ok((physical_device, sfc))
+    }
+
+    fn enumerate_adapters(&self) -> Vec<AdapterInfo> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self
+            .0
+            .enumerate_adapters(wgpu::Backends::all())
+            .map(|adapter| adapter_info(&adapter))
+            .collect();
+
+        #[cfg(target_arch = "wasm32")]
+        Vec::new()
     }
 
     fn get_backing_instance(&self) -> &wgpu::Instance {
         &self.0
     }
 }
+
+/// Creates a physical device without a window or surface, to render into offscreen targets
+/// such as in tests and tools.
+///
+/// *Prefers adapters chosen by the selection, falling back to any adapter
+/// and then to the software adapter if allowed.*
+#[cfg(not(target_arch = "wasm32"))]
+pub fn create_headless_device(
+    selection: &AdapterSelection,
+) -> Result<WgpuPhysicalDevice<'static>, DeviceError> {
+    let instance = wgpu::Instance::new(wgpu::Backends::all());
+
+    let mut adapters = instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .collect::<Vec<_>>();
+    let infos = adapters.iter().map(adapter_info).collect::<Vec<_>>();
+    let chosen = selection
+        .choose(&infos)
+        .map(|index| adapters.swap_remove(index));
+
+    let request = |force_fallback_adapter| {
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: match selection.preference {
+                AdapterPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+                _ => wgpu::PowerPreference::LowPower,
+            },
+            compatible_surface: None,
+            force_fallback_adapter,
+        }))
+    };
+
+    let adapter = chosen
+        .or_else(|| request(false))
+        .or_else(|| selection.software_fallback.then(|| request(true)).flatten())
+        .ok_or(DeviceError::NoAdapter)?;
+
+    Ok(WgpuPhysicalDevice::new(adapter))
+}
@@ -27,11 +27,35 @@ extern crate core;
 pub use raw_window_handle;
 pub use wgpu;
 
+#[cfg(feature = "debug_ui")]
+pub use egui;
+
+pub mod compute;
+pub mod debug_lines;
+#[cfg(feature = "debug_ui")]
+pub mod debug_ui;
+pub mod deletion;
 pub mod device;
+pub mod draw_2d;
+pub mod frame;
+#[cfg(test)]
+mod golden;
 pub mod instance;
+pub mod material;
 pub mod mesh;
+pub mod particles;
+pub mod picking;
 pub mod pipeline;
+pub mod post_process;
+pub mod push_constant;
 pub mod render_pass;
 pub mod shader;
+pub mod shadow;
+pub mod sprite;
 pub mod surface;
+pub mod target;
 pub mod texture;
+pub mod timer;
+pub mod uniform;
+pub mod upload;
+pub mod validation;
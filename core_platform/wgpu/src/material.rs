@@ -0,0 +1,155 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::pipeline::WgpuPipeline;
+use crate::texture::WgpuTextureView;
+use pluto_engine_render::material::{
+    Material, MaterialError, MaterialLayout, MaterialParam, ParameterBlock, MATERIAL_GROUP,
+};
+use pluto_engine_render::pipeline::Pipeline;
+use smallvec::SmallVec;
+use std::num::NonZeroU64;
+
+/// *Returns the layout of the material bind group, see [`pluto_engine_render::material`].*
+pub(crate) fn create_material_bind_group_layout(
+    device: &wgpu::Device,
+    layout: &MaterialLayout,
+) -> wgpu::BindGroupLayout {
+    let params = (layout.get_size() > 0).then(|| wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: NonZeroU64::new(layout.get_size()),
+        },
+        count: None,
+    });
+
+    let textures = (0..layout.get_textures().len() as u32).flat_map(|index| {
+        [
+            wgpu::BindGroupLayoutEntry {
+                binding: 1 + 2 * index,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2 + 2 * index,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ]
+    });
+
+    let entries = params
+        .into_iter()
+        .chain(textures)
+        .collect::<SmallVec<[_; 8]>>();
+
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Material Bind Group Layout"),
+        entries: entries.as_slice(),
+    })
+}
+
+/// A pipeline with its parameters and textures, see [`pluto_engine_render::material`].
+pub struct WgpuMaterial<'a> {
+    pub(crate) pipeline: WgpuPipeline<'a>,
+    pub(crate) params: ParameterBlock,
+    pub(crate) buffer: Option<wgpu::Buffer>,
+    /// Views of a white texture for textures which were not set yet.
+    pub(crate) views: Vec<WgpuTextureView<'a>>,
+    pub(crate) sampler: wgpu::Sampler,
+    /// Recreated by a flush after textures were set.
+    pub(crate) bind_group: Option<wgpu::BindGroup>,
+    pub(crate) empty_bind_group: Option<wgpu::BindGroup>,
+}
+
+impl<'a> WgpuMaterial<'a> {
+    /// *Panics if the material was not flushed yet.*
+    pub fn get_backing_bind_group(&self) -> &wgpu::BindGroup {
+        self.bind_group
+            .as_ref()
+            .expect("The material has to be flushed before drawing")
+    }
+
+    /// Sets the pipeline of the material and binds the material,
+    /// along with empty groups the pipeline does not use otherwise.
+    ///
+    /// *Uniforms and push constants of the pipeline are bound separately.*
+    pub fn bind<'p>(&'p self, render_pass: &mut wgpu::RenderPass<'p>) {
+        render_pass.set_pipeline(self.pipeline.get_backing_pipeline());
+
+        if let Some(empty_bind_group) = &self.empty_bind_group {
+            if self.pipeline.uniform_layout.is_none() {
+                render_pass.set_bind_group(0, empty_bind_group, &[]);
+            }
+
+            if self.pipeline.push_constant_layout.is_none() {
+                render_pass.set_bind_group(1, empty_bind_group, &[]);
+            }
+        }
+
+        render_pass.set_bind_group(MATERIAL_GROUP, self.get_backing_bind_group(), &[]);
+    }
+}
+
+impl<'a> Material<'_> for WgpuMaterial<'a> {
+    type PipelineType = WgpuPipeline<'a>;
+    type TextureViewType = WgpuTextureView<'a>;
+
+    fn get_pipeline(&self) -> &Self::PipelineType {
+        &self.pipeline
+    }
+
+    fn get_layout(&self) -> &MaterialLayout {
+        self.params.get_layout()
+    }
+
+    fn set(&mut self, name: &str, value: impl Into<MaterialParam>) -> Result<(), MaterialError> {
+        self.params.set(name, value)
+    }
+
+    fn set_texture(
+        &mut self,
+        name: &str,
+        view: Self::TextureViewType,
+    ) -> Result<(), MaterialError> {
+        let index = self
+            .params
+            .get_layout()
+            .find_texture(name)
+            .ok_or_else(|| MaterialError::UnknownTexture(name.to_owned()))?;
+
+        self.views[index] = view;
+        self.bind_group = None;
+
+        Ok(())
+    }
+}
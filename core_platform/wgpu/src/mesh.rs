@@ -22,8 +22,9 @@
  * SOFTWARE.
  */
 
-use pluto_engine_render::mesh::AttributeFormat;
-use wgpu::{BufferAddress, VertexAttribute, VertexFormat};
+use pluto_engine_render::mesh::{AttributeFormat, MeshLayout, StepMode, VertexLayout};
+use smallvec::{smallvec, SmallVec};
+use wgpu::{BufferAddress, VertexAttribute, VertexFormat, VertexStepMode};
 
 pub(crate) trait WgpuAttribute: Sized {
     fn pluto_to_wgpu(&self, offset: &mut usize, position: usize) -> VertexAttribute;
@@ -47,3 +48,95 @@ impl WgpuAttribute for AttributeFormat {
         attrib
     }
 }
+
+/// The attributes and stride of a single vertex buffer.
+pub(crate) struct WgpuBufferLayout {
+    pub(crate) stride: BufferAddress,
+    pub(crate) step_mode: VertexStepMode,
+    pub(crate) attributes: SmallVec<[VertexAttribute; 16]>,
+}
+
+/// Splits vertex layouts into vertex buffers, planar layouts using one buffer per attribute.
+///
+/// Shader locations are assigned in order across all buffers.
+pub(crate) fn buffer_layouts(layouts: &[VertexLayout]) -> SmallVec<[WgpuBufferLayout; 8]> {
+    let mut location = 0;
+    let mut buffers = SmallVec::new();
+
+    for layout in layouts {
+        let step_mode = match layout.step_mode {
+            StepMode::Vertex => VertexStepMode::Vertex,
+            StepMode::Instance => VertexStepMode::Instance,
+        };
+
+        match layout.layout {
+            MeshLayout::Interleaved => {
+                let mut offset = 0;
+                let attributes = layout
+                    .attributes
+                    .iter()
+                    .map(|attr| {
+                        location += 1;
+                        attr.pluto_to_wgpu(&mut offset, location - 1)
+                    })
+                    .collect();
+
+                buffers.push(WgpuBufferLayout {
+                    stride: layout.stride as BufferAddress,
+                    step_mode,
+                    attributes,
+                });
+            }
+            MeshLayout::Planar => {
+                for attr in layout.attributes {
+                    let mut offset = 0;
+                    let attributes = smallvec![attr.pluto_to_wgpu(&mut offset, location)];
+                    location += 1;
+
+                    buffers.push(WgpuBufferLayout {
+                        stride: offset as BufferAddress,
+                        step_mode,
+                        attributes,
+                    });
+                }
+            }
+        }
+    }
+
+    buffers
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mesh::buffer_layouts;
+    use pluto_engine_render::mesh::{AttributeFormat, MeshLayout, StepMode, VertexLayout};
+
+    /// A planar layout of a position and a color followed by an interleaved instance layout
+    /// of two attributes.
+    /// The planar attributes should each get a tightly packed buffer,
+    /// shader locations should continue across buffers.
+    #[test]
+    fn test_planar_buffer_layouts() {
+        let planar =
+            VertexLayout::planar(&[AttributeFormat::Float32x3, AttributeFormat::Float32x4]);
+        let interleaved = VertexLayout {
+            stride: 12,
+            layout: MeshLayout::Interleaved,
+            attributes: &[AttributeFormat::Float32x2, AttributeFormat::Float32],
+            step_mode: StepMode::Instance,
+        };
+
+        let buffers = buffer_layouts(&[planar, interleaved]);
+        assert_eq!(buffers.len(), 3);
+
+        assert_eq!(buffers[0].stride, 12);
+        assert_eq!(buffers[1].stride, 16);
+        assert_eq!(buffers[1].attributes[0].offset, 0);
+        assert_eq!(buffers[1].attributes[0].shader_location, 1);
+
+        assert_eq!(buffers[2].stride, 12);
+        assert_eq!(buffers[2].attributes[1].offset, 8);
+        assert_eq!(buffers[2].attributes[1].shader_location, 3);
+        assert_eq!(buffers[2].step_mode, wgpu::VertexStepMode::Instance);
+    }
+}
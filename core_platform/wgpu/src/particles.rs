@@ -0,0 +1,272 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::device::{WgpuCommandBufferBuilder, WgpuDevice};
+use crate::mesh::buffer_layouts;
+use crate::target::WgpuDepthFormat;
+use crate::texture::{WgpuTextureFormat, WgpuTextureView};
+use pluto_engine_render::device::{CommandBufferBuilder, Device};
+use pluto_engine_render::mesh::{AttributeFormat, Vertex};
+use pluto_engine_render::target::DepthFormat;
+use pluto_engine_render::texture::TextureFormat;
+use std::borrow::Cow;
+use wgpu::util::DeviceExt;
+
+/// The WGSL code drawing instanced particle quads.
+pub const PARTICLE_SHADER: &str = include_str!("shaders/particles.wgsl");
+
+/// A single particle, drawn as a round quad facing the camera,
+/// with an sRGB color and straight alpha.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ParticleInstance {
+    pub position: [f32; 3],
+    /// The width and height of the quad in world units.
+    pub size: f32,
+    pub color: [f32; 4],
+}
+
+impl Vertex for ParticleInstance {
+    const ATTRIBS: &'static [AttributeFormat] = &[
+        AttributeFormat::Float32x3,
+        AttributeFormat::Float32,
+        AttributeFormat::Float32x4,
+    ];
+}
+
+/// The camera the particles are drawn with.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParticleView {
+    /// A column-major matrix transforming world space positions into clip space.
+    pub view_projection: [[f32; 4]; 4],
+    /// The world space direction pointing right on the screen.
+    pub camera_right: [f32; 3],
+    /// The world space direction pointing up on the screen.
+    pub camera_up: [f32; 3],
+}
+
+/// Draws particles as instanced quads in a single draw call, with alpha blending.
+///
+/// *Particles are tested against the depth texture if present, but do not write to it.*
+pub struct WgpuParticleRenderer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// Whether the target converts colors to sRGB, in which case the shader outputs linear colors.
+    linear_output: bool,
+}
+
+impl WgpuParticleRenderer {
+    /// Creates a renderer drawing into textures of the given formats.
+    pub fn new(
+        device: &WgpuDevice<'_>,
+        format: WgpuTextureFormat,
+        depth_format: Option<DepthFormat>,
+    ) -> Self {
+        let device = device.get_backing_device();
+        let format = format.get_backing_format();
+
+        let module = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::from(PARTICLE_SHADER)),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particles"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particles"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let instance_layouts = buffer_layouts(&[ParticleInstance::instance_layout()]);
+        let instance_layout = &instance_layouts[0];
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Particles"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: instance_layout.stride,
+                    step_mode: instance_layout.step_mode,
+                    attributes: &instance_layout.attributes,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                cull_mode: None,
+                ..wgpu::PrimitiveState::default()
+            },
+            depth_stencil: depth_format.map(|depth_format| wgpu::DepthStencilState {
+                format: depth_format.to_wgpu(),
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            linear_output: format.describe().srgb,
+        }
+    }
+
+    /// Records a pass drawing the particles on top of the output, in the order given.
+    ///
+    /// *The depth texture has to be given if the renderer was created with a depth format.*
+    pub fn record(
+        &self,
+        device: &WgpuDevice<'_>,
+        command_buffer: &mut WgpuCommandBufferBuilder<'_>,
+        output: &WgpuTextureView<'_>,
+        depth: Option<&WgpuTextureView<'_>>,
+        view: &ParticleView,
+        particles: &[ParticleInstance],
+    ) {
+        if particles.is_empty() {
+            return;
+        }
+
+        let device = device.get_backing_device();
+
+        let mut uniform = view
+            .view_projection
+            .iter()
+            .flatten()
+            .chain(view.camera_right.iter())
+            .chain(&[0.0])
+            .chain(view.camera_up.iter())
+            .chain(&[0.0])
+            .flat_map(|value| value.to_ne_bytes())
+            .collect::<Vec<_>>();
+        uniform.extend(
+            [if self.linear_output { 1.0f32 } else { 0.0 }, 0.0, 0.0, 0.0]
+                .iter()
+                .flat_map(|value| value.to_ne_bytes()),
+        );
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Uniform"),
+            contents: &uniform,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particles"),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let instance_data = particles
+            .iter()
+            .flat_map(|particle| {
+                particle
+                    .position
+                    .into_iter()
+                    .chain([particle.size])
+                    .chain(particle.color)
+            })
+            .flat_map(|value| value.to_ne_bytes())
+            .collect::<Vec<_>>();
+
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Instances"),
+            contents: &instance_data,
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let encoder = command_buffer.get_backing_command_buffer_builder();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Particles"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: &output.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: depth.map(|depth| wgpu::RenderPassDepthStencilAttachment {
+                view: &depth.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.set_vertex_buffer(0, instance_buffer.slice(..));
+        render_pass.draw(0..4, 0..particles.len() as u32);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::particles::PARTICLE_SHADER;
+
+    /// The particle shader is parsed and validated.
+    /// It should be valid WGSL.
+    #[test]
+    fn test_particle_shader_validates() {
+        let module = naga::front::wgsl::parse_str(PARTICLE_SHADER).unwrap();
+
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap();
+    }
+}
@@ -0,0 +1,138 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::texture::WgpuTextureReadback;
+use pluto_engine_render::picking::NO_PICK_ID;
+use pluto_engine_render::texture::ReadbackError;
+
+/// The format of pick targets, see [`pluto_engine_render::picking`].
+pub const PICK_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+
+/// A pick id copy in flight, see [`pluto_engine_render::picking::DevicePicking`].
+pub struct WgpuPickReadback(pub(crate) WgpuTextureReadback);
+
+impl WgpuPickReadback {
+    pub(crate) fn poll(&mut self) -> Option<Result<Option<u32>, ReadbackError>> {
+        let pixels = self.0.poll()?;
+
+        Some(pixels.map(|pixels| {
+            let id = u32::from_ne_bytes(pixels.data[..4].try_into().unwrap());
+            (id != NO_PICK_ID).then_some(id)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::golden::headless_device;
+    use crate::texture::WgpuTextureFormat;
+    use pluto_engine_render::device::{CommandBuffer, CommandBufferBuilder, Device, Queue};
+    use pluto_engine_render::frame::{ClearColor, LoadOp};
+    use pluto_engine_render::picking::DevicePicking;
+    use pluto_engine_render::pipeline::{
+        BlendMode, CullMode, Pipeline, PipelineCreateInfo, PrimitiveState,
+    };
+    use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+    use pluto_engine_render::shader::ShaderCode;
+    use pluto_engine_render::target::RenderTarget;
+
+    const SHADER: &str = r#"
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> [[builtin(position)]] vec4<f32> {
+    // A triangle covering the left half of the target
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv.x - 1.0, uv.y * 2.0 - 1.0, 0.0, 1.0);
+}
+
+[[stage(fragment)]]
+fn fs_main() -> [[location(0)]] u32 {
+    return 42u;
+}
+"#;
+
+    /// An object with the id 42 is drawn over the left half of a pick target.
+    /// Its id should be read back on the left, and no id on the right.
+    #[test]
+    fn test_pick_id() {
+        let Some((device, queue)) = headless_device() else {
+            return;
+        };
+
+        let target = device.create_pick_target(
+            None,
+            PhysicalSize {
+                width: 8,
+                height: 8,
+            },
+        );
+        assert_eq!(
+            target.get_format(),
+            WgpuTextureFormat::from(super::PICK_FORMAT)
+        );
+
+        let shader = device.create_shader(&ShaderCode::Wgsl {
+            code: SHADER,
+            vertex_entry: "vs_main",
+            fragment_entry: "fs_main",
+            label: Some("Pick Shader"),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&shader);
+        let pipeline = device.create_pipeline(&PipelineCreateInfo {
+            blend: BlendMode::Replace,
+            primitive: PrimitiveState {
+                cull_mode: CullMode::None,
+                ..PrimitiveState::default()
+            },
+            ..PipelineCreateInfo::new(&pipeline_layout, &shader, target.get_format())
+        });
+
+        let mut command_buffer = device.begin_command_buffer();
+        {
+            let mut render_pass = target.begin_render_pass(
+                command_buffer.get_backing_command_buffer_builder(),
+                None,
+                &[LoadOp::Clear(ClearColor::BLACK)],
+                false,
+            );
+            let render_pass = render_pass.get_backing_render_pass();
+            render_pass.set_pipeline(pipeline.get_backing_pipeline());
+            render_pass.draw(0..3, 0..1);
+        }
+        queue.get_backing_queue().submit(std::iter::once(
+            command_buffer.build().get_backing_command_buffer(),
+        ));
+
+        let pick = |x, y| {
+            let mut readback = device.read_pick_id(&queue, &target, x, y);
+            loop {
+                device.get_backing_device().poll(wgpu::Maintain::Wait);
+                if let Some(result) = device.poll_pick_id(&mut readback) {
+                    break result.unwrap();
+                }
+            }
+        };
+
+        assert_eq!(pick(1, 4), Some(42));
+        assert_eq!(pick(6, 4), None);
+    }
+}
@@ -22,8 +22,16 @@
  * SOFTWARE.
  */
 
+use crate::shader::WgpuShader;
+use pluto_engine_render::cache::ResourceCache;
+use pluto_engine_render::debug::{RenderDebugMode, RenderDebugSwitch};
 use pluto_engine_render::pipeline::{Pipeline, PipelineLayout};
+use pluto_engine_render::push_constant::PushConstantLayout;
 use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// The WGSL code replacing the fragment stage of pipelines in the overdraw debug mode.
+pub const OVERDRAW_SHADER: &str = include_str!("shaders/overdraw.wgsl");
 
 pub struct WgpuPipelineLayout<'a> {
     pub(crate) layout: wgpu::PipelineLayout,
@@ -38,16 +46,69 @@ impl<'a> PipelineLayout<'_> for WgpuPipelineLayout<'a> {
     }
 }
 
+/// A render pipeline, clones share the same backing objects.
+#[derive(Clone)]
 pub struct WgpuPipeline<'a> {
-    pub(crate) pipeline: wgpu::RenderPipeline,
+    pub(crate) pipeline: Arc<wgpu::RenderPipeline>,
+    /// Pipelines drawn instead of the regular one in debug modes,
+    /// only created with the `render_debug` feature.
+    pub(crate) debug_variants: Arc<[(RenderDebugMode, wgpu::RenderPipeline)]>,
+    pub(crate) debug_switch: RenderDebugSwitch,
+    /// The layout of bind group 0, present if the pipeline was created with uniforms.
+    pub(crate) uniform_layout: Option<Arc<wgpu::BindGroupLayout>>,
+    pub(crate) uniform_bindings: Vec<u32>,
+    pub(crate) push_constants: Option<PushConstantLayout>,
+    /// The layout of bind group 1, present if the pipeline emulates push constants.
+    pub(crate) push_constant_layout: Option<Arc<wgpu::BindGroupLayout>>,
+    /// An empty layout for groups in front of emulated push constants, materials or shadow maps.
+    pub(crate) empty_layout: Option<Arc<wgpu::BindGroupLayout>>,
+    /// The layout of bind group 2, present if the pipeline was created for materials.
+    pub(crate) material_layout: Option<Arc<wgpu::BindGroupLayout>>,
+    /// The layout of bind group 3, present if the pipeline receives shadows.
+    pub(crate) shadow_layout: Option<Arc<wgpu::BindGroupLayout>>,
     pub(crate) parent: PhantomData<&'a ()>,
 }
 
+/// The shaders and pipelines reused by a device.
+#[derive(Default)]
+pub(crate) struct WgpuPipelineCache<'a> {
+    pub(crate) shaders: ResourceCache<WgpuShader<'a>>,
+    pub(crate) pipelines: ResourceCache<WgpuPipeline<'a>>,
+}
+
 impl<'a> Pipeline<'_> for WgpuPipeline<'a> {
     type BackingType = wgpu::RenderPipeline;
     type LayoutType = WgpuPipelineLayout<'a>;
 
     fn get_backing_pipeline(&self) -> &Self::BackingType {
-        &self.pipeline
+        if self.debug_variants.is_empty() {
+            return &self.pipeline;
+        }
+
+        let mode = self.debug_switch.get();
+
+        self.debug_variants
+            .iter()
+            .find(|(variant_mode, _)| *variant_mode == mode)
+            .map_or(&self.pipeline, |(_, pipeline)| pipeline)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pipeline::OVERDRAW_SHADER;
+
+    /// The overdraw shader is parsed and validated.
+    /// It should be valid WGSL.
+    #[test]
+    fn test_overdraw_shader_validates() {
+        let module = naga::front::wgsl::parse_str(OVERDRAW_SHADER).unwrap();
+
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap();
     }
 }
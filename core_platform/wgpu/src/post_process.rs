@@ -0,0 +1,356 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::device::{WgpuCommandBufferBuilder, WgpuDevice, WgpuQueue};
+use crate::target::WgpuRenderTarget;
+use crate::texture::{WgpuTextureFormat, WgpuTextureView};
+use pluto_engine_render::device::{CommandBufferBuilder, Device, Queue};
+use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+use pluto_engine_render::post_process::PostProcessPass;
+use pluto_engine_render::target::{
+    DepthFormat, DeviceRenderTargets, RenderTarget, SurfaceDependentResource,
+};
+use pluto_engine_render::texture::{Texture, TextureFormat};
+use std::borrow::Cow;
+
+struct PostProcessStage {
+    name: String,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    params: Option<wgpu::Buffer>,
+    /// Binds the output of the previous stage, recreated when the targets are resized.
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+/// A chain of fullscreen passes applied to the rendered scene before it is presented.
+///
+/// The scene is rendered into the scene target, then each pass reads the output
+/// of the previous one, the last pass writing into the output texture.
+pub struct WgpuPostProcessChain<'a> {
+    stages: Vec<PostProcessStage>,
+    sampler: wgpu::Sampler,
+    scene_target: WgpuRenderTarget<'a>,
+    /// Ping-pong targets between passes, as many as needed up to two.
+    intermediate_targets: Vec<WgpuRenderTarget<'a>>,
+}
+
+impl<'a> WgpuPostProcessChain<'a> {
+    /// Creates a chain writing into textures of the given format,
+    /// a chain without passes copies the scene target unchanged.
+    pub fn new(
+        device: &WgpuDevice<'a>,
+        format: WgpuTextureFormat,
+        depth_format: Option<DepthFormat>,
+        size: PhysicalSize<u32>,
+        passes: &[PostProcessPass],
+    ) -> Self {
+        let backing_device = device.get_backing_device();
+
+        let passthrough = [PostProcessPass::passthrough()];
+        let passes = if passes.is_empty() {
+            &passthrough
+        } else {
+            passes
+        };
+
+        let stages = passes
+            .iter()
+            .map(|pass| Self::create_stage(backing_device, format, pass))
+            .collect();
+
+        let sampler = backing_device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Process Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let mut chain = Self {
+            stages,
+            sampler,
+            scene_target: device.create_render_target(format, depth_format, size),
+            intermediate_targets: Vec::new(),
+        };
+
+        chain.create_targets(device, size);
+        chain
+    }
+
+    fn create_stage(
+        device: &wgpu::Device,
+        format: WgpuTextureFormat,
+        pass: &PostProcessPass,
+    ) -> PostProcessStage {
+        let module = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some(&pass.name),
+            source: wgpu::ShaderSource::Wgsl(Cow::from(pass.get_source())),
+        });
+
+        let mut entries = vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ];
+
+        let params = pass.params.as_ref().map(|params| {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            });
+
+            wgpu::util::DeviceExt::create_buffer_init(
+                device,
+                &wgpu::util::BufferInitDescriptor {
+                    label: Some(&pass.name),
+                    contents: params,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                },
+            )
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&pass.name),
+            entries: &entries,
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&pass.name),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(&pass.name),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format: format.get_backing_format(),
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        PostProcessStage {
+            name: pass.name.to_string(),
+            pipeline,
+            bind_group_layout,
+            params,
+            bind_group: None,
+        }
+    }
+
+    /// Creates the intermediate targets and binds each stage to the output of the previous one.
+    fn create_targets(&mut self, device: &WgpuDevice<'a>, size: PhysicalSize<u32>) {
+        let format = self.scene_target.get_format();
+        let intermediate_count = (self.stages.len() - 1).min(2);
+
+        self.intermediate_targets = (0..intermediate_count)
+            .map(|_| device.create_render_target(format, None, size))
+            .collect();
+
+        for i in 0..self.stages.len() {
+            let source = match i {
+                0 => &self.scene_target,
+                _ => &self.intermediate_targets[(i - 1) % 2],
+            };
+            let view = source.get_color_texture().create_view();
+
+            let stage = &self.stages[i];
+            let mut entries = vec![
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ];
+
+            if let Some(params) = &stage.params {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params.as_entire_binding(),
+                });
+            }
+
+            let bind_group =
+                device
+                    .get_backing_device()
+                    .create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some(&stage.name),
+                        layout: &stage.bind_group_layout,
+                        entries: &entries,
+                    });
+
+            self.stages[i].bind_group = Some(bind_group);
+        }
+    }
+
+    /// *Returns the target the scene should be rendered into.*
+    pub fn get_scene_target(&self) -> &WgpuRenderTarget<'a> {
+        &self.scene_target
+    }
+
+    pub fn get_size(&self) -> PhysicalSize<u32> {
+        self.scene_target.get_size()
+    }
+
+    /// Updates the uniform of a pass.
+    ///
+    /// ***Panics*** if the pass was created without parameters.
+    pub fn set_params(&self, queue: &WgpuQueue<'a>, pass: usize, params: &[u8]) {
+        let buffer = self.stages[pass]
+            .params
+            .as_ref()
+            .expect("The pass has no parameters");
+
+        queue.get_backing_queue().write_buffer(buffer, 0, params);
+    }
+
+    /// Records all passes, the last one writing into the output.
+    pub fn record(
+        &self,
+        command_buffer: &mut WgpuCommandBufferBuilder<'a>,
+        output: &WgpuTextureView<'_>,
+    ) {
+        let encoder = command_buffer.get_backing_command_buffer_builder();
+        let intermediate_views = self
+            .intermediate_targets
+            .iter()
+            .map(|target| target.get_color_texture().create_view())
+            .collect::<Vec<_>>();
+
+        for (i, stage) in self.stages.iter().enumerate() {
+            let target = if i + 1 == self.stages.len() {
+                &output.view
+            } else {
+                &intermediate_views[i % 2].view
+            };
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(&stage.name),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&stage.pipeline);
+            render_pass.set_bind_group(0, stage.bind_group.as_ref().unwrap(), &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
+
+impl<'a> SurfaceDependentResource<'a, WgpuDevice<'a>> for WgpuPostProcessChain<'a> {
+    fn on_surface_resized(
+        &mut self,
+        device: &WgpuDevice<'a>,
+        surface_size: PhysicalSize<u32>,
+    ) -> bool {
+        let size = PhysicalSize {
+            width: surface_size.width.max(1),
+            height: surface_size.height.max(1),
+        };
+
+        if size == self.get_size() {
+            return false;
+        }
+
+        self.scene_target = device.create_render_target(
+            self.scene_target.get_format(),
+            self.scene_target.get_depth_format(),
+            size,
+        );
+        self.create_targets(device, size);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pluto_engine_render::post_process::PostProcessPass;
+
+    /// The built-in passes are parsed and validated.
+    /// All of them should be valid WGSL.
+    #[test]
+    fn test_builtin_passes_validate() {
+        let passes = [
+            PostProcessPass::passthrough(),
+            PostProcessPass::gamma_correction(2.2),
+            PostProcessPass::fxaa(),
+            PostProcessPass::color_transform([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]),
+        ];
+
+        for pass in passes {
+            let module = naga::front::wgsl::parse_str(&pass.get_source())
+                .unwrap_or_else(|err| panic!("{}: {:?}", pass.name, err));
+
+            naga::valid::Validator::new(
+                naga::valid::ValidationFlags::all(),
+                naga::valid::Capabilities::empty(),
+            )
+            .validate(&module)
+            .unwrap_or_else(|err| panic!("{}: {:?}", pass.name, err));
+        }
+    }
+}
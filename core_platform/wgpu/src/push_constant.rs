@@ -0,0 +1,89 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use pluto_engine_render::push_constant::PushConstants;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+/// A uniform buffer with dynamic offsets standing in for push constants.
+pub(crate) struct PushConstantEmulation {
+    pub(crate) buffer: wgpu::Buffer,
+    pub(crate) bind_group: wgpu::BindGroup,
+    pub(crate) empty_bind_group: Option<wgpu::BindGroup>,
+    /// The size of the data of one draw, aligned to the uniform offset alignment.
+    pub(crate) stride: u32,
+    pub(crate) max_draws: u32,
+    /// The data of all draws since the last flush.
+    pub(crate) staging: RefCell<Vec<u8>>,
+}
+
+pub struct WgpuPushConstants<'a> {
+    pub(crate) size: u32,
+    pub(crate) stages: wgpu::ShaderStages,
+    pub(crate) emulation: Option<PushConstantEmulation>,
+    pub(crate) parent: PhantomData<&'a ()>,
+}
+
+impl<'a> WgpuPushConstants<'a> {
+    /// Sets the data for the following draws of the render pass.
+    ///
+    /// When emulated, the data is uploaded by `DevicePushConstants::flush_push_constants`.
+    pub fn set<'p>(&'p self, render_pass: &mut wgpu::RenderPass<'p>, data: &[u8]) {
+        assert!(
+            data.len() <= self.size as usize,
+            "The data is larger than the push constants of the pipeline"
+        );
+
+        let emulation = match &self.emulation {
+            Some(emulation) => emulation,
+            None => {
+                render_pass.set_push_constants(self.stages, 0, data);
+                return;
+            }
+        };
+
+        let mut staging = emulation.staging.borrow_mut();
+        let offset = staging.len();
+        assert!(
+            offset < (emulation.stride * emulation.max_draws) as usize,
+            "More than {} draws with push constants between flushes",
+            emulation.max_draws
+        );
+
+        staging.extend_from_slice(data);
+        staging.resize(offset + emulation.stride as usize, 0);
+
+        if let Some(empty_bind_group) = &emulation.empty_bind_group {
+            render_pass.set_bind_group(0, empty_bind_group, &[]);
+        }
+
+        render_pass.set_bind_group(1, &emulation.bind_group, &[offset as u32]);
+    }
+}
+
+impl<'a> PushConstants<'_> for WgpuPushConstants<'a> {
+    fn is_emulated(&self) -> bool {
+        self.emulation.is_some()
+    }
+}
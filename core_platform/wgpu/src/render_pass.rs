@@ -21,3 +21,36 @@
  * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
  * SOFTWARE.
  */
+use pluto_engine_render::render_pass::{RenderPass, ScissorRect, Viewport};
+
+/// A render pass being recorded, see [`crate::frame::WgpuFramePassContext::begin_render_pass`].
+pub struct WgpuRenderPass<'p>(pub(crate) wgpu::RenderPass<'p>);
+
+impl<'p> WgpuRenderPass<'p> {
+    /// Returns the wgpu render pass, for binding resources and drawing.
+    pub fn get_backing_render_pass(&mut self) -> &mut wgpu::RenderPass<'p> {
+        &mut self.0
+    }
+}
+
+impl RenderPass for WgpuRenderPass<'_> {
+    fn set_viewport(&mut self, viewport: &Viewport) {
+        self.0.set_viewport(
+            viewport.x,
+            viewport.y,
+            viewport.width,
+            viewport.height,
+            viewport.min_depth,
+            viewport.max_depth,
+        );
+    }
+
+    fn set_scissor_rect(&mut self, scissor_rect: &ScissorRect) {
+        self.0.set_scissor_rect(
+            scissor_rect.x,
+            scissor_rect.y,
+            scissor_rect.width,
+            scissor_rect.height,
+        );
+    }
+}
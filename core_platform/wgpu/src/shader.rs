@@ -23,12 +23,19 @@
  */
 
 use pluto_engine_render::shader::Shader;
+use std::borrow::Cow;
 use std::marker::PhantomData;
+use std::sync::Arc;
 
+/// A compiled shader, clones share the same module.
+#[derive(Clone)]
 pub struct WgpuShader<'a> {
-    pub(super) module: wgpu::ShaderModule,
+    pub(super) module: Arc<wgpu::ShaderModule>,
+    /// The cache key of the shader source.
+    pub(super) key: u64,
     pub(super) vertex_entry: String,
     pub(super) fragment_entry: String,
+    pub(super) label: Option<String>,
     pub(super) parent: PhantomData<&'a ()>,
 }
 
@@ -40,6 +47,10 @@ impl<'a> WgpuShader<'a> {
     pub fn vertex_entry_point(&self) -> &str {
         &self.vertex_entry
     }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
 }
 
 impl<'a> Shader<'_> for WgpuShader<'a> {
@@ -49,3 +60,34 @@ impl<'a> Shader<'_> for WgpuShader<'a> {
         &self.module
     }
 }
+
+/// Rewrites the push constant declaration of a WGSL shader to the uniform emulating it.
+pub(crate) fn emulate_push_constants(code: &str) -> Cow<'_, str> {
+    const PUSH_CONSTANT: &str = "var<push_constant>";
+
+    if code.contains(PUSH_CONSTANT) {
+        Cow::from(code.replace(PUSH_CONSTANT, "[[group(1), binding(0)]] var<uniform>"))
+    } else {
+        Cow::from(code)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shader::emulate_push_constants;
+
+    /// A shader with push constants and one without are rewritten.
+    /// Only the push constant declaration should change.
+    #[test]
+    fn test_emulate_push_constants() {
+        let code = "var<push_constant> model: Model;\nvar<private> x: f32;";
+        assert_eq!(
+            emulate_push_constants(code),
+            "[[group(1), binding(0)]] var<uniform> model: Model;\nvar<private> x: f32;"
+        );
+        assert_eq!(
+            emulate_push_constants("var<private> x: f32;"),
+            "var<private> x: f32;"
+        );
+    }
+}
@@ -0,0 +1,43 @@
+// Draws debug lines with straight alpha sRGB colors.
+
+struct LineUniform {
+    view_projection: mat4x4<f32>;
+    // 1.0 if the target expects linear colors, converting them to sRGB on write.
+    linear_output: f32;
+};
+
+[[group(0), binding(0)]]
+var<uniform> line_uniform: LineUniform;
+
+struct VertexInput {
+    [[location(0)]] position: vec3<f32>;
+    [[location(1)]] color: vec4<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] color: vec4<f32>;
+};
+
+fn linear_from_srgb(srgb: vec3<f32>) -> vec3<f32> {
+    let lower = srgb / vec3<f32>(12.92);
+    let higher = pow((srgb + vec3<f32>(0.055)) / vec3<f32>(1.055), vec3<f32>(2.4));
+    return select(higher, lower, srgb < vec3<f32>(0.04045));
+}
+
+[[stage(vertex)]]
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    var vertex_out: VertexOutput;
+    vertex_out.position = line_uniform.view_projection * vec4<f32>(vertex.position, 1.0);
+    vertex_out.color = vertex.color;
+    return vertex_out;
+}
+
+[[stage(fragment)]]
+fn fs_main(vertex: VertexOutput) -> [[location(0)]] vec4<f32> {
+    if (line_uniform.linear_output > 0.5) {
+        return vec4<f32>(linear_from_srgb(vertex.color.rgb), vertex.color.a);
+    }
+
+    return vertex.color;
+}
@@ -0,0 +1,61 @@
+// Draws the meshes of the debug UI, positioned in points with premultiplied sRGB colors.
+
+struct ScreenUniform {
+    size: vec2<f32>;
+    // 1.0 if the target expects linear colors, converting them to sRGB on write.
+    linear_output: f32;
+    padding: f32;
+};
+
+[[group(0), binding(0)]]
+var<uniform> screen: ScreenUniform;
+
+[[group(1), binding(0)]]
+var ui_texture: texture_2d<f32>;
+
+[[group(1), binding(1)]]
+var ui_sampler: sampler;
+
+struct VertexInput {
+    [[location(0)]] position: vec2<f32>;
+    [[location(1)]] uv: vec2<f32>;
+    [[location(2)]] color: vec4<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+    [[location(1)]] color: vec4<f32>;
+};
+
+fn linear_from_srgb(srgb: vec3<f32>) -> vec3<f32> {
+    let lower = srgb / vec3<f32>(12.92);
+    let higher = pow((srgb + vec3<f32>(0.055)) / vec3<f32>(1.055), vec3<f32>(2.4));
+    return select(higher, lower, srgb < vec3<f32>(0.04045));
+}
+
+[[stage(vertex)]]
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    var vertex_out: VertexOutput;
+    vertex_out.position = vec4<f32>(
+        2.0 * vertex.position.x / screen.size.x - 1.0,
+        1.0 - 2.0 * vertex.position.y / screen.size.y,
+        0.0,
+        1.0
+    );
+    vertex_out.uv = vertex.uv;
+    vertex_out.color = vertex.color;
+    return vertex_out;
+}
+
+[[stage(fragment)]]
+fn fs_main(vertex: VertexOutput) -> [[location(0)]] vec4<f32> {
+    // Both the vertex color and the texture are sampled as sRGB, blending happens in gamma space
+    let color = vertex.color * textureSample(ui_texture, ui_sampler, vertex.uv);
+
+    if (screen.linear_output > 0.5) {
+        return vec4<f32>(linear_from_srgb(color.rgb), color.a);
+    }
+
+    return color;
+}
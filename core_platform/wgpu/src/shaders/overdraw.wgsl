@@ -0,0 +1,7 @@
+// Replaces the fragment stage of pipelines in the overdraw debug mode,
+// the constant color is blended additively so the heat goes from red over yellow to white.
+
+[[stage(fragment)]]
+fn fs_overdraw() -> [[location(0)]] vec4<f32> {
+    return vec4<f32>(0.1, 0.04, 0.02, 1.0);
+}
@@ -0,0 +1,57 @@
+// Draws instanced camera-facing particle quads with straight alpha sRGB colors.
+
+struct ParticleUniform {
+    view_projection: mat4x4<f32>;
+    // The world space axes of the camera, the quads are spanned by them.
+    camera_right: vec4<f32>;
+    camera_up: vec4<f32>;
+    // 1.0 if the target expects linear colors, converting them to sRGB on write.
+    linear_output: f32;
+};
+
+[[group(0), binding(0)]]
+var<uniform> particle_uniform: ParticleUniform;
+
+struct InstanceInput {
+    [[location(0)]] position: vec3<f32>;
+    [[location(1)]] size: f32;
+    [[location(2)]] color: vec4<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] corner: vec2<f32>;
+    [[location(1)]] color: vec4<f32>;
+};
+
+fn linear_from_srgb(srgb: vec3<f32>) -> vec3<f32> {
+    let lower = srgb / vec3<f32>(12.92);
+    let higher = pow((srgb + vec3<f32>(0.055)) / vec3<f32>(1.055), vec3<f32>(2.4));
+    return select(higher, lower, srgb < vec3<f32>(0.04045));
+}
+
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] vertex_index: u32, instance: InstanceInput) -> VertexOutput {
+    // The four corners of a triangle strip quad, from -1 to 1 on both axes
+    let corner = vec2<f32>(f32(vertex_index & 1u), f32(vertex_index >> 1u)) * 2.0 - vec2<f32>(1.0);
+    let offset = particle_uniform.camera_right.xyz * corner.x + particle_uniform.camera_up.xyz * corner.y;
+    let position = instance.position + offset * (instance.size * 0.5);
+
+    var vertex_out: VertexOutput;
+    vertex_out.position = particle_uniform.view_projection * vec4<f32>(position, 1.0);
+    vertex_out.corner = corner;
+    vertex_out.color = instance.color;
+    return vertex_out;
+}
+
+[[stage(fragment)]]
+fn fs_main(vertex: VertexOutput) -> [[location(0)]] vec4<f32> {
+    // Fades out towards the edge of the inscribed circle
+    let alpha = vertex.color.a * clamp((1.0 - length(vertex.corner)) * 2.0, 0.0, 1.0);
+
+    if (particle_uniform.linear_output > 0.5) {
+        return vec4<f32>(linear_from_srgb(vertex.color.rgb), alpha);
+    }
+
+    return vec4<f32>(vertex.color.rgb, alpha);
+}
@@ -0,0 +1,62 @@
+// Draws textured 2D quads with straight alpha sRGB colors.
+
+struct SpriteUniform {
+    projection: mat4x4<f32>;
+    // 1.0 if the target expects linear colors, converting them to sRGB on write.
+    linear_output: f32;
+};
+
+[[group(0), binding(0)]]
+var<uniform> sprite_uniform: SpriteUniform;
+
+[[group(1), binding(0)]]
+var sprite_texture: texture_2d<f32>;
+
+[[group(1), binding(1)]]
+var sprite_sampler: sampler;
+
+struct VertexInput {
+    [[location(0)]] position: vec2<f32>;
+    [[location(1)]] uv: vec2<f32>;
+    [[location(2)]] color: vec4<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+    [[location(1)]] color: vec4<f32>;
+};
+
+fn linear_from_srgb(srgb: vec3<f32>) -> vec3<f32> {
+    let lower = srgb / vec3<f32>(12.92);
+    let higher = pow((srgb + vec3<f32>(0.055)) / vec3<f32>(1.055), vec3<f32>(2.4));
+    return select(higher, lower, srgb < vec3<f32>(0.04045));
+}
+
+fn srgb_from_linear(linear: vec3<f32>) -> vec3<f32> {
+    let lower = linear * vec3<f32>(12.92);
+    let higher = vec3<f32>(1.055) * pow(linear, vec3<f32>(1.0 / 2.4)) - vec3<f32>(0.055);
+    return select(higher, lower, linear < vec3<f32>(0.0031308));
+}
+
+[[stage(vertex)]]
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    var vertex_out: VertexOutput;
+    vertex_out.position = sprite_uniform.projection * vec4<f32>(vertex.position, 0.0, 1.0);
+    vertex_out.uv = vertex.uv;
+    vertex_out.color = vertex.color;
+    return vertex_out;
+}
+
+[[stage(fragment)]]
+fn fs_main(vertex: VertexOutput) -> [[location(0)]] vec4<f32> {
+    // The texture is sampled as sRGB, so filtering happens in linear space
+    let texel = textureSample(sprite_texture, sprite_sampler, vertex.uv);
+    let color = vec4<f32>(linear_from_srgb(vertex.color.rgb) * texel.rgb, vertex.color.a * texel.a);
+
+    if (sprite_uniform.linear_output > 0.5) {
+        return color;
+    }
+
+    return vec4<f32>(srgb_from_linear(color.rgb), color.a);
+}
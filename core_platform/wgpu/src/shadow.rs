@@ -0,0 +1,290 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::pipeline::WgpuPipeline;
+use crate::render_pass::WgpuRenderPass;
+use crate::texture::WgpuTexture;
+use pluto_engine_render::shadow::{ShadowMap, ShadowMapConfig, ShadowUniform, SHADOW_GROUP};
+use std::num::NonZeroU64;
+
+/// *Returns the layout of the shadow bind group, see [`pluto_engine_render::shadow`].*
+pub(crate) fn create_shadow_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Shadow Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: NonZeroU64::new(ShadowUniform::SIZE),
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                count: None,
+            },
+        ],
+    })
+}
+
+/// A depth texture drawn from the view of a light, see [`pluto_engine_render::shadow`].
+pub struct WgpuShadowMap<'a> {
+    pub(crate) depth: WgpuTexture<'a>,
+    pub(crate) depth_view: wgpu::TextureView,
+    pub(crate) config: ShadowMapConfig,
+    pub(crate) buffer: wgpu::Buffer,
+    pub(crate) bind_group: wgpu::BindGroup,
+    pub(crate) empty_bind_group: wgpu::BindGroup,
+}
+
+impl<'a> WgpuShadowMap<'a> {
+    pub fn get_backing_bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    /// Begins a render pass clearing the shadow map and drawing shadow casters into it.
+    pub fn begin_render_pass<'p>(
+        &'p self,
+        encoder: &'p mut wgpu::CommandEncoder,
+    ) -> WgpuRenderPass<'p> {
+        WgpuRenderPass(encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        }))
+    }
+
+    /// Binds the shadow map for a receiving pipeline, along with empty groups
+    /// the pipeline does not use otherwise.
+    ///
+    /// *Panics if the pipeline was not created as a shadow receiver.*
+    pub fn bind<'p>(&'p self, render_pass: &mut wgpu::RenderPass<'p>, pipeline: &WgpuPipeline) {
+        assert!(
+            pipeline.shadow_layout.is_some(),
+            "The pipeline was not created as a shadow receiver"
+        );
+
+        let groups = [
+            pipeline.uniform_layout.is_some(),
+            pipeline.push_constant_layout.is_some(),
+            pipeline.material_layout.is_some(),
+        ];
+
+        for (index, _) in (0u32..).zip(groups).filter(|(_, used)| !used) {
+            render_pass.set_bind_group(index, &self.empty_bind_group, &[]);
+        }
+
+        render_pass.set_bind_group(SHADOW_GROUP, &self.bind_group, &[]);
+    }
+}
+
+impl<'a> ShadowMap<'_> for WgpuShadowMap<'a> {
+    type TextureType = WgpuTexture<'a>;
+
+    fn get_depth_texture(&self) -> &Self::TextureType {
+        &self.depth
+    }
+
+    fn get_config(&self) -> ShadowMapConfig {
+        self.config
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::golden::headless_device;
+    use crate::texture::{WgpuTexture, WgpuTextureFormat};
+    use pluto_engine_render::device::{
+        CommandBuffer, CommandBufferBuilder, Device, DeviceTextureReader, Queue,
+    };
+    use pluto_engine_render::frame::{ClearColor, LoadOp};
+    use pluto_engine_render::pipeline::{
+        BlendMode, CullMode, Pipeline, PipelineCreateInfo, PrimitiveState,
+    };
+    use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+    use pluto_engine_render::shader::ShaderCode;
+    use pluto_engine_render::shadow::{
+        DepthBias, DeviceShadowMaps, ShadowMap, ShadowMapConfig, ShadowPass,
+        ShadowPipelineCreateInfo, ShadowUniform,
+    };
+    use pluto_engine_render::target::{DepthFormat, DeviceRenderTargets, RenderTarget};
+
+    const CASTER_SHADER: &str = r#"
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> [[builtin(position)]] vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.5, 1.0);
+}
+
+[[stage(fragment)]]
+fn fs_main() {
+}
+"#;
+
+    const RECEIVER_SHADER: &str = r#"
+struct Shadow {
+    light_view_projection: mat4x4<f32>;
+    texel_size: f32;
+};
+
+[[group(3), binding(0)]] var<uniform> shadow: Shadow;
+[[group(3), binding(1)]] var shadow_map: texture_depth_2d;
+[[group(3), binding(2)]] var shadow_sampler: sampler_comparison;
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    // The left half is in front of the caster, the right half behind it
+    let depth = select(0.75, 0.25, in.uv.x < 0.5);
+    let light = shadow.light_view_projection * vec4<f32>(0.0, 0.0, depth, 1.0);
+    let lit = textureSampleCompare(shadow_map, shadow_sampler, vec2<f32>(0.5, 0.5), light.z);
+    return vec4<f32>(lit, lit, lit, 1.0);
+}
+"#;
+
+    /// A caster covering the shadow map at a depth of 0.5 is drawn,
+    /// then a receiver compares depths in front of and behind it.
+    /// Only the half in front of the caster should be lit.
+    #[test]
+    fn test_shadow_map() {
+        let Some((device, queue)) = headless_device() else {
+            return;
+        };
+
+        let format = WgpuTextureFormat::from(wgpu::TextureFormat::Rgba8Unorm);
+        let size = PhysicalSize {
+            width: 4,
+            height: 4,
+        };
+        let config = ShadowMapConfig {
+            size: 8,
+            depth_format: DepthFormat::Depth32Float,
+        };
+
+        let create_pipeline = |code, depth_format, pass| {
+            let shader = device.create_shader(&ShaderCode::Wgsl {
+                code,
+                vertex_entry: "vs_main",
+                fragment_entry: "fs_main",
+                label: None,
+            });
+            let pipeline_layout = device.create_pipeline_layout(&shader);
+
+            device.create_shadow_pipeline(&ShadowPipelineCreateInfo {
+                pipeline: PipelineCreateInfo {
+                    blend: BlendMode::Replace,
+                    primitive: PrimitiveState {
+                        cull_mode: CullMode::None,
+                        ..PrimitiveState::default()
+                    },
+                    depth_format,
+                    ..PipelineCreateInfo::new(&pipeline_layout, &shader, format)
+                },
+                pass,
+            })
+        };
+
+        let caster = create_pipeline(
+            CASTER_SHADER,
+            Some(config.depth_format),
+            ShadowPass::Caster(DepthBias::default()),
+        );
+        let receiver = create_pipeline(RECEIVER_SHADER, None, ShadowPass::Receiver);
+
+        let shadow_map = device.create_shadow_map(config);
+        assert_eq!(shadow_map.get_config(), config);
+
+        let mut identity = [[0.0; 4]; 4];
+        (0..4).for_each(|i| identity[i][i] = 1.0);
+        device.write_shadow_uniform(&queue, &shadow_map, &ShadowUniform::new(identity, &config));
+
+        let target = device.create_render_target(format, None, size);
+
+        let mut command_buffer = device.begin_command_buffer();
+        {
+            let mut render_pass =
+                shadow_map.begin_render_pass(command_buffer.get_backing_command_buffer_builder());
+            let render_pass = render_pass.get_backing_render_pass();
+            render_pass.set_pipeline(caster.get_backing_pipeline());
+            render_pass.draw(0..3, 0..1);
+        }
+        {
+            let mut render_pass = target.begin_render_pass(
+                command_buffer.get_backing_command_buffer_builder(),
+                None,
+                &[LoadOp::Clear(ClearColor::BLACK)],
+                false,
+            );
+            let render_pass = render_pass.get_backing_render_pass();
+            render_pass.set_pipeline(receiver.get_backing_pipeline());
+            shadow_map.bind(render_pass, &receiver);
+            render_pass.draw(0..3, 0..1);
+        }
+        queue.get_backing_queue().submit(std::iter::once(
+            command_buffer.build().get_backing_command_buffer(),
+        ));
+
+        let readback = device.read_pixels(&queue, target.get_color_texture());
+        let pixels =
+            DeviceTextureReader::<_, WgpuTexture<'static>>::wait_readback(&device, readback)
+                .unwrap();
+
+        assert_eq!(pixels.get_pixel(0, 1), [255, 255, 255, 255]);
+        assert_eq!(pixels.get_pixel(3, 1), [0, 0, 0, 255]);
+    }
+}
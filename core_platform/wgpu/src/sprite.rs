@@ -0,0 +1,362 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::device::{WgpuCommandBufferBuilder, WgpuDevice, WgpuQueue};
+use crate::texture::{WgpuTextureFormat, WgpuTextureView};
+use pluto_engine_render::device::{CommandBufferBuilder, Device, Queue};
+use pluto_engine_render::texture::{TextureFormat, TexturePixels};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::ops::Range;
+use wgpu::util::DeviceExt;
+
+/// The WGSL code drawing textured 2D quads.
+pub const SPRITE_SHADER: &str = include_str!("shaders/sprite.wgsl");
+
+/// A corner of a sprite, with an sRGB color and straight alpha.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SpriteVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl SpriteVertex {
+    const SIZE: u64 = std::mem::size_of::<Self>() as u64;
+}
+
+/// Vertices drawn with one texture, every three of them form a triangle.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpriteDraw {
+    pub texture: u64,
+    pub vertices: Range<u32>,
+}
+
+struct SpriteTexture {
+    // Kept alive for the bind group
+    _texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Draws textured 2D triangles in submission order on top of a texture, without depth testing.
+pub struct WgpuSpriteRenderer {
+    pipeline: wgpu::RenderPipeline,
+    uniform_bind_group_layout: wgpu::BindGroupLayout,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    textures: HashMap<u64, SpriteTexture>,
+    /// Whether the target converts colors to sRGB, in which case the shader outputs linear colors.
+    linear_output: bool,
+}
+
+impl WgpuSpriteRenderer {
+    /// Creates a renderer drawing into textures of the given format.
+    pub fn new(device: &WgpuDevice<'_>, format: WgpuTextureFormat) -> Self {
+        let device = device.get_backing_device();
+        let format = format.get_backing_format();
+
+        let module = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("Sprite Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::from(SPRITE_SHADER)),
+        });
+
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Sprite Uniform"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Sprite Texture"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sprite"),
+            bind_group_layouts: &[&uniform_bind_group_layout, &texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sprite"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: SpriteVertex::SIZE,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![
+                        0 => Float32x2,
+                        1 => Float32x2,
+                        2 => Float32x4
+                    ],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: None,
+                ..wgpu::PrimitiveState::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        // Atlases pad their images, so clamping keeps the edges from wrapping around
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Sprite Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            pipeline,
+            uniform_bind_group_layout,
+            texture_bind_group_layout,
+            sampler,
+            textures: HashMap::new(),
+            linear_output: format.describe().srgb,
+        }
+    }
+
+    /// Uploads 8-bit sRGB pixels with straight alpha as the texture with the given id,
+    /// replacing any previous texture with it.
+    pub fn create_texture(
+        &mut self,
+        device: &WgpuDevice<'_>,
+        queue: &WgpuQueue<'_>,
+        id: u64,
+        pixels: &TexturePixels,
+    ) {
+        let device = device.get_backing_device();
+        let size = wgpu::Extent3d {
+            width: pixels.size.width,
+            height: pixels.size.height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Sprite Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+
+        queue.get_backing_queue().write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &pixels.data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(pixels.size.width * 4),
+                rows_per_image: NonZeroU32::new(pixels.size.height),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sprite Texture"),
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+
+        self.textures.insert(
+            id,
+            SpriteTexture {
+                _texture: texture,
+                bind_group,
+            },
+        );
+    }
+
+    pub fn free_texture(&mut self, id: u64) {
+        self.textures.remove(&id);
+    }
+
+    /// Records a pass drawing the sprites on top of the output.
+    ///
+    /// `projection` is a column-major matrix transforming the vertices into clip space.
+    ///
+    /// *Draws using unknown textures are skipped.*
+    pub fn record(
+        &self,
+        device: &WgpuDevice<'_>,
+        command_buffer: &mut WgpuCommandBufferBuilder<'_>,
+        output: &WgpuTextureView<'_>,
+        projection: [[f32; 4]; 4],
+        vertices: &[SpriteVertex],
+        draws: &[SpriteDraw],
+    ) {
+        if vertices.is_empty() || draws.is_empty() {
+            return;
+        }
+
+        let device = device.get_backing_device();
+
+        let mut uniform = projection
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect::<Vec<_>>();
+        uniform.extend(
+            [if self.linear_output { 1.0f32 } else { 0.0 }, 0.0, 0.0, 0.0]
+                .iter()
+                .flat_map(|value| value.to_ne_bytes()),
+        );
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Sprite Uniform"),
+            contents: &uniform,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sprite Uniform"),
+            layout: &self.uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let vertex_data = vertices
+            .iter()
+            .flat_map(|vertex| {
+                vertex
+                    .position
+                    .iter()
+                    .chain(vertex.uv.iter())
+                    .chain(vertex.color.iter())
+            })
+            .flat_map(|value| value.to_ne_bytes())
+            .collect::<Vec<_>>();
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Sprite Vertices"),
+            contents: &vertex_data,
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let encoder = command_buffer.get_backing_command_buffer_builder();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Sprite"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: &output.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &uniform_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+
+        for draw in draws {
+            let Some(texture) = self.textures.get(&draw.texture) else {
+                continue;
+            };
+
+            render_pass.set_bind_group(1, &texture.bind_group, &[]);
+            render_pass.draw(draw.vertices.clone(), 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sprite::SPRITE_SHADER;
+
+    /// The sprite shader is parsed and validated.
+    /// It should be valid WGSL.
+    #[test]
+    fn test_sprite_shader_validates() {
+        let module = naga::front::wgsl::parse_str(SPRITE_SHADER).unwrap();
+
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap();
+    }
+}
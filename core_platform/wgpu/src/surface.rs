@@ -23,12 +23,13 @@
  */
 
 use crate::device::WgpuDevice;
-use crate::texture::{WgpuTextureFormat, WgpuTextureView};
+use crate::texture::{WgpuReadableTexture, WgpuTextureFormat, WgpuTextureView};
 use pluto_engine_render::device::{Device, PhysicalDevice};
 use pluto_engine_render::pluto_engine_window::window::{PhysicalSize, Window};
 use pluto_engine_render::surface::{Surface, SurfaceError, SurfaceFormat, SurfaceTexture};
 use raw_window_handle::HasRawWindowHandle;
 use std::marker::PhantomData;
+use std::sync::Arc;
 use wgpu::TextureViewDescriptor;
 
 pub struct WgpuSurfaceFormat(wgpu::TextureFormat);
@@ -42,7 +43,10 @@ impl SurfaceFormat for WgpuSurfaceFormat {
 }
 
 pub struct WgpuSurface<'a> {
-    surface: wgpu::Surface,
+    /// Kept to recreate the surface, see [`WgpuSurface::recreate`].
+    instance: Arc<wgpu::Instance>,
+    /// `None` while the surface is released.
+    surface: Option<wgpu::Surface>,
     config: wgpu::SurfaceConfiguration,
     parent: PhantomData<&'a ()>,
 }
@@ -54,12 +58,21 @@ impl<'a> WgpuSurface<'_> {
     >(
         window: &W,
         physical_device: &D,
+        instance: Arc<wgpu::Instance>,
         surface: wgpu::Surface,
     ) -> Self {
         let size = window.get_size();
 
+        // Surface textures can be copied from to take screenshots,
+        // except on the web where this is not supported.
+        let usage = if cfg!(target_arch = "wasm32") {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC
+        };
+
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface
                 .get_preferred_format(physical_device.get_backing_physical_device())
                 .unwrap(),
@@ -69,18 +82,67 @@ impl<'a> WgpuSurface<'_> {
         };
 
         Self {
-            surface,
+            instance,
+            surface: Some(surface),
             config,
             parent: PhantomData,
         }
     }
 }
 
+impl<'a> WgpuSurface<'a> {
+    /// Destroys the native surface, for platforms destroying the native window
+    /// while the application is suspended, such as Android.
+    ///
+    /// *Textures cannot be acquired until the surface is recreated.*
+    pub fn release(&mut self) {
+        self.surface = None;
+    }
+
+    /// Creates and configures a new native surface for the window,
+    /// after it was released or the platform replaced the native window.
+    pub fn recreate<W: HasRawWindowHandle>(&mut self, window: &W, device: &WgpuDevice<'a>) {
+        self.surface = Some(unsafe { self.instance.create_surface(window) });
+        self.configure(device);
+    }
+
+    /// Sets whether presenting waits for the vertical blank, applied once the surface is configured.
+    ///
+    /// *Platforms not supporting immediate presentation, such as the web, always wait.*
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.config.present_mode = if vsync {
+            wgpu::PresentMode::Fifo
+        } else {
+            wgpu::PresentMode::Immediate
+        };
+    }
+
+    pub fn is_released(&self) -> bool {
+        self.surface.is_none()
+    }
+}
+
 pub struct WgpuSurfaceTexture<'a> {
     texture: wgpu::SurfaceTexture,
+    size: PhysicalSize<u32>,
+    format: wgpu::TextureFormat,
     parent: PhantomData<&'a ()>,
 }
 
+impl WgpuReadableTexture for WgpuSurfaceTexture<'_> {
+    fn get_readback_texture(&self) -> &wgpu::Texture {
+        &self.texture.texture
+    }
+
+    fn get_readback_size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+
+    fn get_readback_format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+}
+
 impl<'a> SurfaceTexture<'_> for WgpuSurfaceTexture<'a> {
     type BackingType = wgpu::SurfaceTexture;
     type TextureViewType = WgpuTextureView<'a>;
@@ -95,6 +157,7 @@ impl<'a> SurfaceTexture<'_> for WgpuSurfaceTexture<'a> {
                 .texture
                 .texture
                 .create_view(&TextureViewDescriptor::default()),
+            size: self.size,
             parent: PhantomData,
         }
     }
@@ -116,8 +179,9 @@ impl<'a> Surface<'_> for WgpuSurface<'a> {
     type ErrorType = wgpu::SurfaceError;
 
     fn configure(&mut self, device: &WgpuDevice<'a>) {
-        self.surface
-            .configure(device.get_backing_device(), &self.config);
+        if let Some(surface) = &self.surface {
+            surface.configure(device.get_backing_device(), &self.config);
+        }
     }
 
     fn resize(&mut self, device: &WgpuDevice<'a>, size: PhysicalSize<u32>) {
@@ -139,12 +203,25 @@ impl<'a> Surface<'_> for WgpuSurface<'a> {
     }
 
     fn get_backing_surface(&self) -> &Self::BackingType {
-        &self.surface
+        self.surface.as_ref().expect("the surface was released")
     }
 
     fn acquire_next_texture(&self) -> Result<Self::TextureType, SurfaceError<Self::ErrorType>> {
+        // A released surface is reported as outdated until it is recreated
+        let surface = self.surface.as_ref().ok_or(SurfaceError::Outdated)?;
+
         Ok(WgpuSurfaceTexture {
-            texture: self.surface.get_current_texture()?,
+            texture: surface.get_current_texture().map_err(|err| match err {
+                wgpu::SurfaceError::Lost => SurfaceError::DeviceLost,
+                wgpu::SurfaceError::Outdated => SurfaceError::Outdated,
+                wgpu::SurfaceError::Timeout => SurfaceError::Timeout,
+                wgpu::SurfaceError::OutOfMemory => SurfaceError::OutOfMemory,
+            })?,
+            size: PhysicalSize {
+                width: self.config.width,
+                height: self.config.height,
+            },
+            format: self.config.format,
             parent: PhantomData,
         })
     }
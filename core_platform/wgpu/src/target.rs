@@ -0,0 +1,229 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::frame::to_wgpu_load;
+use crate::render_pass::WgpuRenderPass;
+use crate::texture::{WgpuTexture, WgpuTextureFormat};
+use pluto_engine_render::frame::LoadOp;
+use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+use pluto_engine_render::target::{DepthFormat, RenderTarget};
+
+pub(crate) trait WgpuDepthFormat {
+    fn to_wgpu(self) -> wgpu::TextureFormat;
+}
+
+impl WgpuDepthFormat for DepthFormat {
+    fn to_wgpu(self) -> wgpu::TextureFormat {
+        match self {
+            DepthFormat::Depth32Float => wgpu::TextureFormat::Depth32Float,
+            DepthFormat::Depth24Plus => wgpu::TextureFormat::Depth24Plus,
+        }
+    }
+}
+
+pub struct WgpuRenderTarget<'a> {
+    pub(crate) colors: Vec<WgpuTexture<'a>>,
+    pub(crate) depth: Option<WgpuTexture<'a>>,
+    pub(crate) depth_format: Option<DepthFormat>,
+    /// Views of the color textures and the depth texture, attached by render passes.
+    pub(crate) color_views: Vec<wgpu::TextureView>,
+    pub(crate) depth_view: Option<wgpu::TextureView>,
+}
+
+impl<'a> WgpuRenderTarget<'a> {
+    /// Begins a render pass drawing into all color textures and the depth texture of the target.
+    ///
+    /// Each color texture is loaded or cleared by the operation at its index, color textures
+    /// without one are loaded. The depth texture is cleared to the far plane if `clear_depth`
+    /// is set.
+    pub fn begin_render_pass<'p>(
+        &'p self,
+        encoder: &'p mut wgpu::CommandEncoder,
+        label: Option<&'p str>,
+        loads: &[LoadOp],
+        clear_depth: bool,
+    ) -> WgpuRenderPass<'p> {
+        let color_attachments = self
+            .color_views
+            .iter()
+            .enumerate()
+            .map(|(i, view)| wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: to_wgpu_load(loads.get(i).copied().unwrap_or_default()),
+                    store: true,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        WgpuRenderPass(encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label,
+            color_attachments: &color_attachments,
+            depth_stencil_attachment: self.depth_view.as_ref().map(|view| {
+                wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: if clear_depth {
+                            wgpu::LoadOp::Clear(1.0)
+                        } else {
+                            wgpu::LoadOp::Load
+                        },
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }
+            }),
+        }))
+    }
+}
+
+impl<'a> RenderTarget<'_> for WgpuRenderTarget<'a> {
+    type TextureType = WgpuTexture<'a>;
+    type FormatType = WgpuTextureFormat;
+
+    fn get_color_textures(&self) -> &[Self::TextureType] {
+        &self.colors
+    }
+
+    fn get_depth_texture(&self) -> Option<&Self::TextureType> {
+        self.depth.as_ref()
+    }
+
+    fn get_formats(&self) -> Vec<Self::FormatType> {
+        self.colors
+            .iter()
+            .map(|color| WgpuTextureFormat(color.format))
+            .collect()
+    }
+
+    fn get_depth_format(&self) -> Option<DepthFormat> {
+        self.depth_format
+    }
+
+    fn get_size(&self) -> PhysicalSize<u32> {
+        self.colors[0].size
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::golden::headless_device;
+    use crate::texture::{WgpuTexture, WgpuTextureFormat};
+    use pluto_engine_render::device::{
+        CommandBuffer, CommandBufferBuilder, Device, DeviceTextureReader, Queue,
+    };
+    use pluto_engine_render::frame::{ClearColor, LoadOp};
+    use pluto_engine_render::pipeline::{
+        BlendMode, ColorTarget, CullMode, Pipeline, PipelineCreateInfo, PrimitiveState,
+    };
+    use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+    use pluto_engine_render::shader::ShaderCode;
+    use pluto_engine_render::target::{DeviceRenderTargets, RenderTarget};
+
+    const SHADER: &str = r#"
+struct GBuffer {
+    [[location(0)]] albedo: vec4<f32>;
+    [[location(1)]] normal: vec4<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> [[builtin(position)]] vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+[[stage(fragment)]]
+fn fs_main() -> GBuffer {
+    var out: GBuffer;
+    out.albedo = vec4<f32>(1.0, 0.0, 0.0, 1.0);
+    out.normal = vec4<f32>(0.0, 0.0, 1.0, 0.0);
+    return out;
+}
+"#;
+
+    /// A full screen triangle is drawn into a target with two color textures,
+    /// the second one without blending.
+    /// Each texture should be read back with the color written to its location.
+    #[test]
+    fn test_multiple_render_targets() {
+        let Some((device, queue)) = headless_device() else {
+            return;
+        };
+
+        let format = WgpuTextureFormat::from(wgpu::TextureFormat::Rgba8Unorm);
+        let size = PhysicalSize {
+            width: 4,
+            height: 4,
+        };
+
+        let shader = device.create_shader(&ShaderCode::Wgsl {
+            code: SHADER,
+            vertex_entry: "vs_main",
+            fragment_entry: "fs_main",
+            label: Some("G-Buffer Shader"),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&shader);
+        let pipeline = device.create_pipeline(&PipelineCreateInfo {
+            additional_color_targets: &[ColorTarget {
+                format,
+                blend: BlendMode::Replace,
+            }],
+            primitive: PrimitiveState {
+                cull_mode: CullMode::None,
+                ..PrimitiveState::default()
+            },
+            ..PipelineCreateInfo::new(&pipeline_layout, &shader, format)
+        });
+
+        let target = device.create_multi_render_target(&[format, format], None, size);
+        assert_eq!(target.get_formats(), [format, format]);
+
+        let mut command_buffer = device.begin_command_buffer();
+        {
+            let mut render_pass = target.begin_render_pass(
+                command_buffer.get_backing_command_buffer_builder(),
+                None,
+                &[LoadOp::Clear(ClearColor::BLACK)],
+                false,
+            );
+            let render_pass = render_pass.get_backing_render_pass();
+            render_pass.set_pipeline(pipeline.get_backing_pipeline());
+            render_pass.draw(0..3, 0..1);
+        }
+        queue.get_backing_queue().submit(std::iter::once(
+            command_buffer.build().get_backing_command_buffer(),
+        ));
+
+        let read = |texture: &WgpuTexture<'static>| {
+            let readback = device.read_pixels(&queue, texture);
+            DeviceTextureReader::<_, WgpuTexture<'static>>::wait_readback(&device, readback)
+                .unwrap()
+        };
+
+        let textures = target.get_color_textures();
+        assert_eq!(read(&textures[0]).get_pixel(1, 2), [255, 0, 0, 255]);
+        assert_eq!(read(&textures[1]).get_pixel(1, 2), [0, 0, 255, 0]);
+    }
+}
@@ -22,10 +22,17 @@
  * SOFTWARE.
  */
 
-use pluto_engine_render::texture::{Texture, TextureFormat, TextureView};
+use pluto_engine_render::pluto_engine_window::window::PhysicalSize;
+use pluto_engine_render::texture::{
+    ReadbackError, Texture, TextureFormat, TexturePixels, TextureView,
+};
+use std::future::Future;
 use std::marker::PhantomData;
-use wgpu::TextureViewDescriptor;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+use wgpu::{BufferAsyncError, TextureViewDescriptor};
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct WgpuTextureFormat(pub(crate) wgpu::TextureFormat);
 
 impl TextureFormat for WgpuTextureFormat {
@@ -36,8 +43,17 @@ impl TextureFormat for WgpuTextureFormat {
     }
 }
 
+/// Allows rendering into formats not provided by a surface, such as for offscreen rendering.
+impl From<wgpu::TextureFormat> for WgpuTextureFormat {
+    fn from(format: wgpu::TextureFormat) -> Self {
+        Self(format)
+    }
+}
+
 pub struct WgpuTexture<'a> {
     pub(crate) texture: wgpu::Texture,
+    pub(crate) size: PhysicalSize<u32>,
+    pub(crate) format: wgpu::TextureFormat,
     pub(crate) parent: PhantomData<&'a ()>,
 }
 
@@ -52,6 +68,7 @@ impl<'a> Texture<'_> for WgpuTexture<'a> {
     fn create_view(&self) -> Self::ViewType {
         WgpuTextureView {
             view: self.texture.create_view(&TextureViewDescriptor::default()),
+            size: self.size,
             parent: PhantomData,
         }
     }
@@ -59,9 +76,17 @@ impl<'a> Texture<'_> for WgpuTexture<'a> {
 
 pub struct WgpuTextureView<'a> {
     pub(crate) view: wgpu::TextureView,
+    pub(crate) size: PhysicalSize<u32>,
     pub(crate) parent: PhantomData<&'a ()>,
 }
 
+impl<'a> WgpuTextureView<'a> {
+    /// *Returns the size of the viewed texture.*
+    pub fn get_size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+}
+
 impl<'a> TextureView<'_> for WgpuTextureView<'a> {
     type BackingType = wgpu::TextureView;
 
@@ -69,3 +94,125 @@ impl<'a> TextureView<'_> for WgpuTextureView<'a> {
         &self.view
     }
 }
+
+/// A texture which can be copied into CPU memory.
+///
+/// *The texture must have been created with [`wgpu::TextureUsages::COPY_SRC`].*
+pub trait WgpuReadableTexture {
+    fn get_readback_texture(&self) -> &wgpu::Texture;
+
+    fn get_readback_size(&self) -> PhysicalSize<u32>;
+
+    fn get_readback_format(&self) -> wgpu::TextureFormat;
+}
+
+impl WgpuReadableTexture for WgpuTexture<'_> {
+    fn get_readback_texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    fn get_readback_size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+
+    fn get_readback_format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+}
+
+type MapFuture = Pin<Box<dyn Future<Output = Result<(), BufferAsyncError>> + Send>>;
+
+/// A texture copy in flight, see [`pluto_engine_render::device::DeviceTextureReader`].
+pub struct WgpuTextureReadback(ReadbackState);
+
+enum ReadbackState {
+    Mapping {
+        buffer: wgpu::Buffer,
+        map_future: MapFuture,
+        size: PhysicalSize<u32>,
+        padded_bytes_per_row: u32,
+        swap_red_blue: bool,
+    },
+    Failed(ReadbackError),
+    Finished,
+}
+
+impl WgpuTextureReadback {
+    pub(crate) fn mapping(
+        buffer: wgpu::Buffer,
+        size: PhysicalSize<u32>,
+        padded_bytes_per_row: u32,
+        swap_red_blue: bool,
+    ) -> Self {
+        let map_future = Box::pin(buffer.slice(..).map_async(wgpu::MapMode::Read));
+
+        Self(ReadbackState::Mapping {
+            buffer,
+            map_future,
+            size,
+            padded_bytes_per_row,
+            swap_red_blue,
+        })
+    }
+
+    pub(crate) fn failed(error: ReadbackError) -> Self {
+        Self(ReadbackState::Failed(error))
+    }
+
+    pub(crate) fn poll(&mut self) -> Option<Result<TexturePixels, ReadbackError>> {
+        let map_result = match &mut self.0 {
+            ReadbackState::Mapping { map_future, .. } => {
+                let mut context = Context::from_waker(Waker::noop());
+                match map_future.as_mut().poll(&mut context) {
+                    Poll::Ready(result) => result,
+                    Poll::Pending => return None,
+                }
+            }
+            ReadbackState::Failed(_) => Ok(()),
+            ReadbackState::Finished => return None,
+        };
+
+        match std::mem::replace(&mut self.0, ReadbackState::Finished) {
+            ReadbackState::Mapping {
+                buffer,
+                size,
+                padded_bytes_per_row,
+                swap_red_blue,
+                ..
+            } => Some(
+                map_result
+                    .map_err(|_| ReadbackError::MapFailed)
+                    .map(|_| Self::read_mapped(&buffer, size, padded_bytes_per_row, swap_red_blue)),
+            ),
+            ReadbackState::Failed(error) => Some(Err(error)),
+            ReadbackState::Finished => None,
+        }
+    }
+
+    fn read_mapped(
+        buffer: &wgpu::Buffer,
+        size: PhysicalSize<u32>,
+        padded_bytes_per_row: u32,
+        swap_red_blue: bool,
+    ) -> TexturePixels {
+        let unpadded_bytes_per_row = size.width as usize * 4;
+        let mut data = Vec::with_capacity(unpadded_bytes_per_row * size.height as usize);
+
+        {
+            let mapped = buffer.slice(..).get_mapped_range();
+            for row in mapped.chunks(padded_bytes_per_row as usize) {
+                data.extend_from_slice(&row[..unpadded_bytes_per_row]);
+            }
+        }
+
+        buffer.unmap();
+
+        if swap_red_blue {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        TexturePixels { size, data }
+    }
+}
@@ -0,0 +1,177 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::device::WgpuCommandBufferBuilder;
+use pluto_engine_render::device::CommandBufferBuilder;
+use pluto_engine_render::timer::{GpuTimer, PassTiming};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use wgpu::BufferAsyncError;
+
+type MapFuture = Pin<Box<dyn Future<Output = Result<(), BufferAsyncError>> + Send>>;
+
+enum TimerState {
+    /// The buffer is free to receive the timestamps of a frame.
+    Idle,
+    /// The timestamps of a frame were resolved into the buffer, waiting for submission.
+    Resolved(Vec<String>),
+    Mapping(MapFuture, Vec<String>),
+}
+
+pub struct WgpuGpuTimer<'a> {
+    query_set: wgpu::QuerySet,
+    buffer: wgpu::Buffer,
+    max_passes: u32,
+    /// The duration of a timestamp tick in nanoseconds.
+    timestamp_period: f32,
+    passes: Vec<String>,
+    /// Whether the last pass was begun and not ended yet.
+    pass_open: bool,
+    state: TimerState,
+    parent: PhantomData<&'a ()>,
+}
+
+impl<'a> WgpuGpuTimer<'a> {
+    pub(crate) fn new(device: &wgpu::Device, timestamp_period: f32, max_passes: u32) -> Self {
+        let query_count = max_passes * 2;
+
+        Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("GPU Timer Query Set"),
+                ty: wgpu::QueryType::Timestamp,
+                count: query_count,
+            }),
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("GPU Timer Buffer"),
+                size: query_count as u64 * wgpu::QUERY_SIZE as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            max_passes,
+            timestamp_period,
+            passes: Vec::new(),
+            pass_open: false,
+            state: TimerState::Idle,
+            parent: PhantomData,
+        }
+    }
+
+    /// Advances the readback of the last resolved frame, see [`pluto_engine_render::timer::DeviceGpuTimer`].
+    pub(crate) fn poll(&mut self) -> Option<Vec<PassTiming>> {
+        match std::mem::replace(&mut self.state, TimerState::Idle) {
+            TimerState::Idle => None,
+            TimerState::Resolved(names) => {
+                let future = self.buffer.slice(..).map_async(wgpu::MapMode::Read);
+                self.state = TimerState::Mapping(Box::pin(future), names);
+                None
+            }
+            TimerState::Mapping(mut future, names) => {
+                let mut context = Context::from_waker(Waker::noop());
+                match future.as_mut().poll(&mut context) {
+                    Poll::Pending => {
+                        self.state = TimerState::Mapping(future, names);
+                        None
+                    }
+                    Poll::Ready(Err(_)) => None,
+                    Poll::Ready(Ok(())) => Some(self.read_timings(names)),
+                }
+            }
+        }
+    }
+
+    fn read_timings(&self, names: Vec<String>) -> Vec<PassTiming> {
+        let timings = {
+            let mapped = self.buffer.slice(..).get_mapped_range();
+            let timestamps: Vec<u64> = mapped
+                .chunks_exact(8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                .collect();
+
+            names
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    let ticks = timestamps[i * 2 + 1].saturating_sub(timestamps[i * 2]);
+                    PassTiming {
+                        name,
+                        duration: Duration::from_nanos(
+                            (ticks as f64 * self.timestamp_period as f64) as u64,
+                        ),
+                    }
+                })
+                .collect()
+        };
+
+        self.buffer.unmap();
+        timings
+    }
+}
+
+impl<'a> GpuTimer<'_> for WgpuGpuTimer<'a> {
+    type CommandBufferBuilderType = WgpuCommandBufferBuilder<'a>;
+
+    fn begin_pass(&mut self, command_buffer: &mut Self::CommandBufferBuilderType, name: &str) {
+        if self.passes.len() as u32 >= self.max_passes {
+            return;
+        }
+
+        let index = self.passes.len() as u32 * 2;
+        command_buffer
+            .get_backing_command_buffer_builder()
+            .write_timestamp(&self.query_set, index);
+        self.passes.push(name.to_string());
+        self.pass_open = true;
+    }
+
+    fn end_pass(&mut self, command_buffer: &mut Self::CommandBufferBuilderType) {
+        if !self.pass_open {
+            return;
+        }
+
+        self.pass_open = false;
+
+        let index = self.passes.len() as u32 * 2 - 1;
+        command_buffer
+            .get_backing_command_buffer_builder()
+            .write_timestamp(&self.query_set, index);
+    }
+
+    fn end_frame(&mut self, command_buffer: &mut Self::CommandBufferBuilderType) {
+        let names = std::mem::take(&mut self.passes);
+        self.pass_open = false;
+
+        // Frames finishing while the previous one is still being read back are not measured.
+        if names.is_empty() || !matches!(self.state, TimerState::Idle) {
+            return;
+        }
+
+        command_buffer
+            .get_backing_command_buffer_builder()
+            .resolve_query_set(&self.query_set, 0..names.len() as u32 * 2, &self.buffer, 0);
+        self.state = TimerState::Resolved(names);
+    }
+}
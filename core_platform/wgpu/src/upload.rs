@@ -0,0 +1,220 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use crate::compute::WgpuStorageBuffer;
+use crate::device::{WgpuDevice, WgpuQueue};
+use crate::texture::WgpuTexture;
+use pluto_engine_render::device::{Device, Queue};
+use pluto_engine_render::upload::{FrameAllocator, FrameArena, UploadScheduler, UploadSlot};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// A copy of a staging buffer into a texture mip level.
+pub(crate) struct StagedLevel {
+    pub(crate) buffer: wgpu::Buffer,
+    pub(crate) padded_bytes_per_row: u32,
+    pub(crate) mip_level: u32,
+    pub(crate) size: wgpu::Extent3d,
+}
+
+pub(crate) enum StagedUpload<'a> {
+    Texture {
+        levels: Vec<StagedLevel>,
+        texture: WgpuTexture<'a>,
+        slot: UploadSlot<WgpuTexture<'a>>,
+    },
+    Storage {
+        staging: wgpu::Buffer,
+        buffer: WgpuStorageBuffer<'a>,
+        slot: UploadSlot<WgpuStorageBuffer<'a>>,
+    },
+}
+
+impl<'a> StagedUpload<'a> {
+    /// Records the copies out of the staging buffers.
+    pub(crate) fn record(&self, encoder: &mut wgpu::CommandEncoder) {
+        match self {
+            StagedUpload::Texture {
+                levels, texture, ..
+            } => {
+                for level in levels {
+                    encoder.copy_buffer_to_texture(
+                        wgpu::ImageCopyBuffer {
+                            buffer: &level.buffer,
+                            layout: wgpu::ImageDataLayout {
+                                offset: 0,
+                                bytes_per_row: std::num::NonZeroU32::new(
+                                    level.padded_bytes_per_row,
+                                ),
+                                rows_per_image: None,
+                            },
+                        },
+                        wgpu::ImageCopyTexture {
+                            texture: &texture.texture,
+                            mip_level: level.mip_level,
+                            origin: wgpu::Origin3d::ZERO,
+                            aspect: wgpu::TextureAspect::All,
+                        },
+                        level.size,
+                    );
+                }
+            }
+            StagedUpload::Storage {
+                staging, buffer, ..
+            } => {
+                encoder.copy_buffer_to_buffer(staging, 0, &buffer.buffer, 0, buffer.size);
+            }
+        }
+    }
+
+    /// Hands the resource to its pending upload, the staging buffers are freed
+    /// once the submitted copies complete.
+    pub(crate) fn complete(self) {
+        match self {
+            StagedUpload::Texture { texture, slot, .. } => slot.complete(texture),
+            StagedUpload::Storage { buffer, slot, .. } => slot.complete(buffer),
+        }
+    }
+}
+
+/// Uploads textures and storage buffers over the next frames, see
+/// [`pluto_engine_render::upload::DeviceUploads`].
+///
+/// *wgpu exposes a single queue, so the copies are ordered before the rendering
+/// submitted after them, the resources can be used as soon as they are handed out.*
+pub struct WgpuUploader<'a> {
+    pub(crate) scheduler: UploadScheduler<StagedUpload<'a>>,
+}
+
+impl<'a> WgpuUploader<'a> {
+    /// *Returns the size of the uploads waiting to be submitted in bytes.*
+    pub fn get_queued_size(&self) -> u64 {
+        self.scheduler.get_queued_size()
+    }
+}
+
+/// A range of a buffer allocated by a [`WgpuFrameAllocator`], valid until the next frame.
+#[derive(Clone, Debug)]
+pub struct WgpuFrameSlice {
+    buffer: Arc<wgpu::Buffer>,
+    range: Range<u64>,
+}
+
+impl WgpuFrameSlice {
+    pub fn get_backing_slice(&self) -> wgpu::BufferSlice<'_> {
+        self.buffer.slice(self.range.clone())
+    }
+
+    /// Returns the buffer, for binding the range as a uniform with its offset.
+    pub fn get_backing_buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    pub fn get_offset(&self) -> u64 {
+        self.range.start
+    }
+
+    pub fn get_size(&self) -> u64 {
+        self.range.end - self.range.start
+    }
+}
+
+/// Uploads per-frame data into recycled buffers usable as vertex, index or uniform buffers.
+pub struct WgpuFrameAllocator {
+    arena: FrameArena,
+    chunks: Vec<Arc<wgpu::Buffer>>,
+}
+
+impl WgpuFrameAllocator {
+    /// The size of new chunks, larger uploads get a chunk of their own.
+    pub const DEFAULT_CHUNK_SIZE: u64 = 1 << 20;
+
+    pub fn new(device: &WgpuDevice<'_>, chunk_size: u64) -> Self {
+        // Uniform ranges have the strictest alignment of all uses
+        let alignment = device
+            .get_backing_device()
+            .limits()
+            .min_uniform_buffer_offset_alignment as u64;
+
+        Self {
+            arena: FrameArena::new(chunk_size, alignment),
+            chunks: Vec::new(),
+        }
+    }
+}
+
+impl<'a> FrameAllocator<'a, WgpuDevice<'a>, WgpuQueue<'a>> for WgpuFrameAllocator {
+    type SliceType = WgpuFrameSlice;
+
+    fn upload(
+        &mut self,
+        device: &WgpuDevice<'a>,
+        queue: &WgpuQueue<'a>,
+        data: &[u8],
+    ) -> Self::SliceType {
+        // Writes are made in whole words
+        let align = wgpu::COPY_BUFFER_ALIGNMENT;
+        let size = (data.len() as u64).div_ceil(align) * align;
+
+        let (range, new_chunk) = self.arena.allocate(size);
+
+        if let Some(capacity) = new_chunk {
+            let buffer = device
+                .get_backing_device()
+                .create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Frame Upload Buffer"),
+                    size: capacity,
+                    usage: wgpu::BufferUsages::VERTEX
+                        | wgpu::BufferUsages::INDEX
+                        | wgpu::BufferUsages::UNIFORM
+                        | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+
+            self.chunks.push(Arc::new(buffer));
+        }
+
+        let buffer = self.chunks[range.chunk].clone();
+
+        if size == data.len() as u64 {
+            queue
+                .get_backing_queue()
+                .write_buffer(&buffer, range.offset, data);
+        } else {
+            let mut padded = data.to_vec();
+            padded.resize(size as usize, 0);
+            queue
+                .get_backing_queue()
+                .write_buffer(&buffer, range.offset, &padded);
+        }
+
+        WgpuFrameSlice {
+            buffer,
+            range: range.offset..range.offset + data.len() as u64,
+        }
+    }
+
+    fn begin_frame(&mut self) {
+        self.arena.reset();
+    }
+}
@@ -0,0 +1,46 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Runtime toggle for the additional validation of the `validation` feature.
+//!
+//! Pipelines and shaders are always created within validation error scopes. With validation
+//! enabled, bind groups and render targets are too, at the cost of a blocking scope pop each.
+//! The checks are compiled out of release builds.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether the additional validation is part of this build.
+pub const COMPILED_IN: bool = cfg!(all(feature = "validation", debug_assertions));
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Toggles the additional validation at runtime, it is enabled by default when compiled in.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether the additional validation is compiled in and enabled.
+pub fn is_enabled() -> bool {
+    COMPILED_IN && ENABLED.load(Ordering::Relaxed)
+}
@@ -23,20 +23,43 @@
  */
 
 use crate::window::{WinitWindow, WinitWindowEvent};
-use log::{info, warn};
+use log::warn;
 use pluto_engine_window::event_loop::{
-    DisplayCommand, DisplayEvent, EventLoop, EventLoopWindowFactory,
+    display_event_channel_with, CommandProxy, DisplayCommand, DisplayEvent,
+    DisplayEventChannelConfig, DisplayEventSender, EventLoop, EventLoopWindowFactory,
 };
+use pluto_engine_window::executor::{LocalExecutor, LocalFuture};
 use pluto_engine_window::window::Window;
 use std::collections::HashMap;
 use std::convert::Infallible;
-use std::sync::mpsc;
-use winit::event::{Event, WindowEvent};
+use std::sync::{Arc, Mutex};
+use winit::event::{Event, StartCause, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoopProxy};
 
+/// Why a single window is not rendered, while the rest of the application keeps running.
+#[derive(Copy, Clone, Debug, Default)]
+struct WindowPause {
+    occluded: bool,
+    minimized: bool,
+}
+
+impl WindowPause {
+    fn is_paused(&self) -> bool {
+        self.occluded || self.minimized
+    }
+}
+
 pub struct WinitEventLoop {
-    windows: HashMap<<WinitWindow as Window>::IdType, mpsc::SyncSender<DisplayEvent>>,
+    windows: HashMap<<WinitWindow as Window>::IdType, DisplayEventSender>,
+    /// Windows which are occluded or minimized, and receive no frames until they are shown again.
+    paused: HashMap<<WinitWindow as Window>::IdType, WindowPause>,
     proxy: EventLoopProxy<DisplayCommand>,
+    /// Applications running on the event loop thread.
+    executor: LocalExecutor,
+    /// Set while the whole application is suspended.
+    suspended: bool,
+    /// Set when the current iteration of the event loop produces a frame, see [`starts_frame`].
+    frame_due: bool,
 }
 
 impl EventLoop for WinitEventLoop {
@@ -50,35 +73,202 @@ impl EventLoop for WinitEventLoop {
         Self: Sized,
     {
         let event_loop = winit::event_loop::EventLoopBuilder::with_user_event().build();
+        let proxy = event_loop.create_proxy();
         let mut event_loop_data = Self {
             windows: HashMap::new(),
-            proxy: event_loop.create_proxy(),
+            paused: HashMap::new(),
+            executor: LocalExecutor::with_command_proxy(command_proxy(&proxy)),
+            proxy,
+            suspended: false,
+            frame_due: false,
+        };
+
+        #[cfg(target_arch = "wasm32")]
+        forward_visibility_changes(event_loop_data.proxy.clone());
+
+        // On Android the native window only exists while the application is resumed,
+        // so windows are created on the first resume instead
+        let mut initializer = Some(initializer);
+        if cfg!(not(target_os = "android")) {
+            event_loop_data.initialize(&event_loop, &mut initializer);
+        }
+
+        event_loop.run(move |event, target, control_flow| {
+            if let Event::Resumed = event {
+                event_loop_data.initialize(target, &mut initializer);
+            }
+
+            event_loop_data.dispatch(event, control_flow);
+            event_loop_data.executor.run_woken();
+            event_loop_data.update_control_flow(control_flow);
+        })
+    }
+
+    fn send_event(
+        &mut self,
+        id: <<Self as EventLoop>::WindowType as Window>::IdType,
+        event: DisplayEvent,
+    ) {
+        match self.windows.get_mut(&id) {
+            Some(sender) => match sender.send(event) {
+                Ok(_) => {}
+                Err(err) => {
+                    warn!("Window ID {:?}: {}, removing the window.", id, err);
+                    self.windows.remove(&id);
+                    self.paused.remove(&id);
+                }
+            },
+            None => {
+                warn!(
+                    "Received an event for an unregistered window with ID {:?}.",
+                    id
+                );
+            }
+        }
+    }
+}
+
+impl WinitEventLoop {
+    /// Runs the initializer unless it already ran, creating the first windows.
+    fn initialize<
+        F: FnOnce(&mut dyn EventLoopWindowFactory<Self, LoopType = <Self as EventLoop>::LoopType>),
+    >(
+        &mut self,
+        event_loop: &winit::event_loop::EventLoopWindowTarget<DisplayCommand>,
+        initializer: &mut Option<F>,
+    ) {
+        let Some(initializer) = initializer.take() else {
+            return;
         };
+
         initializer(&mut WinitEventLoopWindowFactory {
-            windows: &mut event_loop_data.windows,
-            event_loop: &*event_loop,
-            proxy: event_loop_data.proxy.clone(),
+            windows: &mut self.windows,
+            event_loop,
+            proxy: self.proxy.clone(),
+            executor: &mut self.executor,
         });
+        self.executor.run_woken();
+    }
+
+    fn broadcast(&mut self, event: DisplayEvent) {
+        let window: Vec<_> = self
+            .windows
+            .keys()
+            .copied()
+            .filter(|id| !self.is_paused(*id))
+            .collect();
+        window.into_iter().for_each(|id| {
+            self.send_event(id, event.clone());
+        });
+    }
 
-        event_loop.run(move |event, _, control_flow| match event {
+    fn is_paused(&self, id: <WinitWindow as Window>::IdType) -> bool {
+        self.paused.get(&id).is_some_and(WindowPause::is_paused)
+    }
+
+    /// Updates why a window is paused, suspending or resuming its display if that changed.
+    fn set_paused(
+        &mut self,
+        id: <WinitWindow as Window>::IdType,
+        update: impl FnOnce(&mut WindowPause),
+    ) {
+        let was_paused = self.is_paused(id);
+        update(self.paused.entry(id).or_default());
+        let paused = self.is_paused(id);
+
+        // While the whole application is suspended, the display is resumed with the application
+        if was_paused == paused || self.suspended {
+            return;
+        }
+
+        let event = if paused {
+            DisplayEvent::Suspended
+        } else {
+            DisplayEvent::Resumed
+        };
+
+        self.send_event(id, event);
+    }
+
+    /// Stops polling for new frames while no window can be rendered, saving power.
+    fn update_control_flow(&self, control_flow: &mut ControlFlow) {
+        if let ControlFlow::ExitWithCode(_) = control_flow {
+            return;
+        }
+
+        let idle = self.suspended
+            || (!self.windows.is_empty() && self.windows.keys().all(|id| self.is_paused(*id)));
+
+        *control_flow = if idle {
+            ControlFlow::Wait
+        } else {
+            ControlFlow::Poll
+        };
+    }
+
+    fn dispatch(&mut self, event: Event<DisplayCommand>, control_flow: &mut ControlFlow) {
+        match event {
             Event::RedrawRequested(window_id) => {
-                event_loop_data.send_event(window_id, DisplayEvent::Repaint);
+                self.send_event(window_id, DisplayEvent::Repaint);
+            }
+
+            Event::NewEvents(cause) => {
+                self.frame_due = starts_frame(cause, cfg!(target_arch = "wasm32"));
             }
 
-            Event::MainEventsCleared => {
-                let window: Vec<_> = event_loop_data.windows.keys().copied().collect();
-                window.into_iter().for_each(|id| {
-                    event_loop_data.send_event(id, DisplayEvent::NextFrame);
-                });
+            Event::MainEventsCleared if self.frame_due => {
+                self.broadcast(DisplayEvent::NextFrame);
+            }
+
+            // Resumed is also sent on startup, only forward it after a suspension
+            Event::Suspended | Event::UserEvent(DisplayCommand::Suspend) if !self.suspended => {
+                self.suspended = true;
+                self.broadcast(DisplayEvent::Suspended);
+            }
+
+            Event::Resumed | Event::UserEvent(DisplayCommand::Resume) if self.suspended => {
+                self.suspended = false;
+                self.broadcast(DisplayEvent::Resumed);
+            }
+
+            Event::UserEvent(DisplayCommand::User { window, event }) => {
+                self.send_event(window.into(), DisplayEvent::User(event));
+            }
+
+            Event::UserEvent(DisplayCommand::WindowEvent { window, event }) => {
+                self.send_event(window.into(), DisplayEvent::WindowEvent(event));
+            }
+
+            Event::WindowEvent {
+                event: WindowEvent::Occluded(occluded),
+                window_id,
+            } => {
+                self.set_paused(window_id, |pause| pause.occluded = occluded);
+            }
+
+            // Minimized windows are resized to zero on some platforms,
+            // the new size is forwarded before the display is suspended or resumed
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                window_id,
+            } => {
+                self.send_event(
+                    window_id,
+                    DisplayEvent::WindowEvent(WinitWindowEvent(&WindowEvent::Resized(size)).into()),
+                );
+
+                let minimized = size.width == 0 || size.height == 0;
+                self.set_paused(window_id, |pause| pause.minimized = minimized);
             }
 
             Event::WindowEvent {
                 event: WindowEvent::Destroyed,
                 window_id,
             } => {
-                event_loop_data.windows.remove(&window_id);
+                self.windows.remove(&window_id);
+                self.paused.remove(&window_id);
 
-                if event_loop_data.windows.is_empty() {
+                if self.windows.is_empty() {
                     *control_flow = ControlFlow::Exit;
                 }
             }
@@ -87,60 +277,109 @@ impl EventLoop for WinitEventLoop {
                 ref event,
                 window_id,
             } => {
-                event_loop_data.send_event(
+                self.send_event(
                     window_id,
                     DisplayEvent::WindowEvent(WinitWindowEvent(event).into()),
                 );
             }
             _ => {}
-        })
-    }
-
-    fn send_event(
-        &mut self,
-        id: <<Self as EventLoop>::WindowType as Window>::IdType,
-        event: DisplayEvent,
-    ) {
-        match self.windows.get_mut(&id) {
-            Some(sender) => match sender.send(event) {
-                Ok(_) => {}
-                Err(_) => {
-                    info!("The window ID {:?} receiver was disconnected.", id);
-                    self.windows.remove(&id);
-                }
-            },
-            None => {
-                warn!(
-                    "Received an event for an unregistered window with ID {:?}.",
-                    id
-                );
-            }
         }
     }
 }
 
+/// Decides whether an iteration of the event loop started by the cause produces a frame.
+///
+/// On the web, polling runs once per `requestAnimationFrame` callback, but the event loop also
+/// wakes up for every input event. Frames are then only produced by the animation frame callbacks,
+/// the events received in between are dispatched before the next frame.
+fn starts_frame(cause: StartCause, web: bool) -> bool {
+    !web || matches!(cause, StartCause::Init | StartCause::Poll)
+}
+
+/// Suspends all windows while the browser tab is hidden,
+/// winit does not report the visibility of the page.
+#[cfg(target_arch = "wasm32")]
+fn forward_visibility_changes(proxy: EventLoopProxy<DisplayCommand>) {
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::JsCast;
+
+    let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+        warn!("No document to listen for visibility changes.");
+        return;
+    };
+
+    let listener_document = document.clone();
+    let listener = Closure::<dyn FnMut()>::new(move || {
+        let command = match listener_document.visibility_state() {
+            web_sys::VisibilityState::Hidden => DisplayCommand::Suspend,
+            _ => DisplayCommand::Resume,
+        };
+
+        proxy.send_event(command).ok();
+    });
+
+    document
+        .add_event_listener_with_callback("visibilitychange", listener.as_ref().unchecked_ref())
+        .ok();
+
+    // The listener lives as long as the page
+    listener.forget();
+}
+
+/// Wraps the event loop proxy, so commands can be sent from any thread.
+fn command_proxy(proxy: &EventLoopProxy<DisplayCommand>) -> CommandProxy {
+    // Event loop proxies are not shareable between threads on every platform
+    let proxy = Mutex::new(proxy.clone());
+    Arc::new(move |cmd| {
+        proxy.lock().unwrap().send_event(cmd).ok();
+    })
+}
+
 pub struct WinitEventLoopWindowFactory<'a> {
     event_loop: &'a winit::event_loop::EventLoopWindowTarget<DisplayCommand>,
     proxy: EventLoopProxy<DisplayCommand>,
-    windows: &'a mut HashMap<<WinitWindow as Window>::IdType, mpsc::SyncSender<DisplayEvent>>,
+    windows: &'a mut HashMap<<WinitWindow as Window>::IdType, DisplayEventSender>,
+    executor: &'a mut LocalExecutor,
 }
 
 impl<'a> EventLoopWindowFactory<WinitEventLoop> for WinitEventLoopWindowFactory<'a> {
     type LoopType = winit::event_loop::EventLoopWindowTarget<DisplayCommand>;
 
-    fn create_window(&mut self) -> WinitWindow {
-        let (sender, receiver) = mpsc::sync_channel(16);
-        let proxy = self.proxy.clone();
-        let proxy_arc = Box::new(move |cmd| {
-            proxy.send_event(cmd).ok();
-        });
-        let window = WinitWindow::new(self, receiver, proxy_arc);
+    fn create_window_with_channel(&mut self, channel: DisplayEventChannelConfig) -> WinitWindow {
+        let (sender, receiver) = display_event_channel_with(channel);
+        let proxy = command_proxy(&self.proxy);
+        let window = WinitWindow::new(self, receiver, proxy);
         let id = window.get_id();
         self.windows.insert(id, sender);
         window
     }
 
+    fn spawn_local(&mut self, future: LocalFuture) {
+        self.executor.spawn(future);
+    }
+
     fn get_backing_loop(&self) -> &Self::LoopType {
         self.event_loop
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::event_loop::starts_frame;
+    use winit::event::StartCause;
+
+    /// The event loop wakes up for input events and for polling, on the web and natively.
+    /// Only animation frame callbacks should produce frames on the web, every iteration natively.
+    #[test]
+    fn test_starts_frame() {
+        let input = StartCause::WaitCancelled {
+            start: std::time::Instant::now(),
+            requested_resume: None,
+        };
+
+        assert!(starts_frame(StartCause::Poll, true));
+        assert!(starts_frame(StartCause::Init, true));
+        assert!(!starts_frame(input, true));
+        assert!(starts_frame(input, false));
+    }
+}
@@ -27,4 +27,5 @@ pub use raw_window_handle;
 pub use winit;
 
 pub mod event_loop;
+pub mod web;
 pub mod window;
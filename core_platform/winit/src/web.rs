@@ -0,0 +1,242 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Placement of windows on the web page.
+//!
+//! Every window renders to a canvas, either an existing canvas of the page or a new one appended
+//! to a container element. The canvas is sized to its size on the page multiplied by the
+//! `devicePixelRatio`, and resized whenever the page layout changes it.
+
+use pluto_engine_window::window::{LogicalSize, PhysicalSize};
+use std::cell::RefCell;
+
+/// Where windows place their canvas on the web page, see [`configure_canvas`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct WebCanvasConfig {
+    /// The ID of the element new canvases are appended to.
+    pub container_id: String,
+    /// The ID of an existing canvas to render to instead of creating a new one.
+    pub canvas_id: Option<String>,
+    /// Resizes the canvas to its size on the page whenever the layout changes,
+    /// new canvases fill their container.
+    pub track_resize: bool,
+    /// The size of the canvas when its size is not tracked.
+    pub size: LogicalSize<f64>,
+}
+
+impl Default for WebCanvasConfig {
+    fn default() -> Self {
+        Self {
+            container_id: "pluto-viewport".to_owned(),
+            canvas_id: None,
+            track_resize: true,
+            size: LogicalSize {
+                width: 640.0,
+                height: 480.0,
+            },
+        }
+    }
+}
+
+thread_local! {
+    static CANVAS_CONFIG: RefCell<WebCanvasConfig> = RefCell::new(WebCanvasConfig::default());
+}
+
+/// Sets where windows created afterwards place their canvas, ignored on other platforms.
+pub fn configure_canvas(config: WebCanvasConfig) {
+    CANVAS_CONFIG.with(|current| *current.borrow_mut() = config);
+}
+
+pub fn get_canvas_config() -> WebCanvasConfig {
+    CANVAS_CONFIG.with(|current| current.borrow().clone())
+}
+
+/// Converts the size of an element on the page in CSS pixels to physical pixels.
+pub fn canvas_physical_size(
+    css_width: f64,
+    css_height: f64,
+    device_pixel_ratio: f64,
+) -> PhysicalSize<u32> {
+    PhysicalSize {
+        width: (css_width * device_pixel_ratio).round() as u32,
+        height: (css_height * device_pixel_ratio).round() as u32,
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) use dom::*;
+
+#[cfg(target_arch = "wasm32")]
+mod dom {
+    use crate::web::{canvas_physical_size, WebCanvasConfig};
+    use pluto_engine_window::event_loop::{CommandProxy, DisplayCommand};
+    use pluto_engine_window::window::WindowEvent;
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen::JsCast;
+    use web_sys::HtmlCanvasElement;
+
+    // Not part of the stable web-sys APIs supported by wgpu
+    #[wasm_bindgen]
+    extern "C" {
+        type ResizeObserver;
+
+        #[wasm_bindgen(constructor)]
+        fn new(callback: &JsValue) -> ResizeObserver;
+
+        #[wasm_bindgen(method)]
+        fn observe(this: &ResizeObserver, target: &web_sys::Element);
+
+        #[wasm_bindgen(method)]
+        fn disconnect(this: &ResizeObserver);
+    }
+
+    /// Keeps the size of a canvas in sync with its size on the page, until dropped.
+    pub(crate) struct CanvasResizeObserver {
+        observer: ResizeObserver,
+        _callback: Closure<dyn FnMut()>,
+    }
+
+    // The web is single threaded, the observer never leaves the thread of the event loop
+    unsafe impl Send for CanvasResizeObserver {}
+
+    impl Drop for CanvasResizeObserver {
+        fn drop(&mut self) {
+            self.observer.disconnect();
+        }
+    }
+
+    fn document() -> web_sys::Document {
+        web_sys::window()
+            .and_then(|window| window.document())
+            .expect("No document to place the canvas in!")
+    }
+
+    /// Finds the existing canvas configured for windows.
+    ///
+    /// *Panics if the configured canvas is missing from the page.*
+    pub(crate) fn find_canvas(config: &WebCanvasConfig) -> Option<HtmlCanvasElement> {
+        let id = config.canvas_id.as_ref()?;
+        let canvas = document()
+            .get_element_by_id(id)
+            .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+            .unwrap_or_else(|| panic!("Pluto canvas HTML element `{}` not found!", id));
+
+        Some(canvas)
+    }
+
+    /// Appends a new canvas to the configured container.
+    ///
+    /// *Panics if the container is missing from the page.*
+    pub(crate) fn append_canvas(canvas: &HtmlCanvasElement, config: &WebCanvasConfig) {
+        document()
+            .get_element_by_id(&config.container_id)
+            .and_then(|container| container.append_child(canvas).ok())
+            .unwrap_or_else(|| {
+                panic!(
+                    "Pluto window container HTML element `{}` not found!",
+                    config.container_id
+                )
+            });
+    }
+
+    /// Lets the page lay out the canvas, new canvases fill their container.
+    pub(crate) fn release_canvas_style(canvas: &HtmlCanvasElement, fill: bool) {
+        let style = canvas.style();
+        if fill {
+            style.set_property("width", "100%").ok();
+            style.set_property("height", "100%").ok();
+            style.set_property("display", "block").ok();
+        } else {
+            style.remove_property("width").ok();
+            style.remove_property("height").ok();
+        }
+    }
+
+    /// Resizes the canvas to its size on the page, reporting the new size to the window.
+    fn fit_canvas(canvas: &HtmlCanvasElement, window: u64, command_proxy: &CommandProxy) {
+        let device_pixel_ratio = web_sys::window().map_or(1.0, |w| w.device_pixel_ratio());
+        let size = canvas_physical_size(
+            canvas.client_width() as f64,
+            canvas.client_height() as f64,
+            device_pixel_ratio,
+        );
+
+        if size.width == canvas.width() && size.height == canvas.height() {
+            return;
+        }
+
+        canvas.set_width(size.width);
+        canvas.set_height(size.height);
+        command_proxy(DisplayCommand::WindowEvent {
+            window,
+            event: WindowEvent::Resized(size),
+        });
+    }
+
+    /// Resizes the canvas whenever its size on the page changes, starting right away.
+    pub(crate) fn observe_canvas(
+        canvas: HtmlCanvasElement,
+        window: u64,
+        command_proxy: CommandProxy,
+    ) -> CanvasResizeObserver {
+        let observed = canvas.clone();
+        let callback = Closure::<dyn FnMut()>::new(move || {
+            fit_canvas(&observed, window, &command_proxy);
+        });
+
+        let observer = ResizeObserver::new(callback.as_ref());
+        observer.observe(&canvas);
+
+        CanvasResizeObserver {
+            observer,
+            _callback: callback,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::canvas_physical_size;
+    use pluto_engine_window::window::PhysicalSize;
+
+    /// A canvas laid out at fractional CSS sizes is converted on a high density display.
+    /// The size should be rounded to whole physical pixels.
+    #[test]
+    fn test_canvas_physical_size() {
+        assert_eq!(
+            canvas_physical_size(640.0, 480.0, 1.5),
+            PhysicalSize {
+                width: 960,
+                height: 720
+            }
+        );
+        assert_eq!(
+            canvas_physical_size(100.4, 0.0, 2.0),
+            PhysicalSize {
+                width: 201,
+                height: 0
+            }
+        );
+    }
+}
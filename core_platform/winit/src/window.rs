@@ -24,13 +24,17 @@
 
 use log::info;
 use pluto_engine_window::event_loop::{
-    DisplayCommand, DisplayEvent, EventLoop, EventLoopWindowFactory,
+    CommandProxy, DisplayCommand, DisplayEvent, DisplayEventProxy, DisplayEventReceiver, EventLoop,
+    EventLoopWindowFactory, NextDisplayEvent,
 };
+use pluto_engine_window::keyboard::Key;
+use pluto_engine_window::mouse::{MouseButton, MouseScrollDelta};
+use pluto_engine_window::touch::{Touch, TouchPhase};
 use pluto_engine_window::window;
-use pluto_engine_window::window::{Window, WindowEventReceiver};
+use pluto_engine_window::window::{Preedit, TextInputEvent, Window, WindowEventReceiver};
 use raw_window_handle::RawWindowHandle;
-use std::sync::mpsc::Receiver;
-use winit::event::WindowEvent;
+use winit::event;
+use winit::event::{ElementState, Ime, KeyboardInput, VirtualKeyCode, WindowEvent};
 use winit::window::WindowBuilder;
 
 #[cfg(target_arch = "wasm32")]
@@ -40,8 +44,9 @@ use winit::dpi::PhysicalSize;
 
 pub struct WinitWindow(
     winit::window::Window,
-    Box<dyn Fn(DisplayCommand) + Send>,
-    Receiver<DisplayEvent>,
+    CommandProxy,
+    DisplayEventReceiver,
+    #[cfg(target_arch = "wasm32")] Option<crate::web::CanvasResizeObserver>,
 );
 
 pub struct WinitWindowEvent<'a, 'b>(pub(crate) &'a WindowEvent<'b>);
@@ -63,29 +68,51 @@ impl Window for WinitWindow {
         ELW: EventLoopWindowFactory<EL, LoopType = Self::LoopType>,
     >(
         event_loop: &ELW,
-        event_receiver: Receiver<DisplayEvent>,
-        command_proxy: Box<dyn Fn(DisplayCommand) + Send>,
+        event_receiver: DisplayEventReceiver,
+        command_proxy: CommandProxy,
     ) -> Self {
         let backing_loop = event_loop.get_backing_loop();
-        let window = WindowBuilder::new().build(backing_loop).unwrap();
 
         #[cfg(target_arch = "wasm32")]
         {
-            window.set_inner_size(winit::dpi::PhysicalSize::new(640, 480));
-
-            use winit::platform::web::WindowExtWebSys;
-            web_sys::window()
-                .and_then(web_sys::Window::document)
-                .and_then(|doc| {
-                    let dst = doc.get_element_by_id("pluto-viewport")?;
-                    let canvas = web_sys::Element::from(window.canvas());
-                    dst.append_child(&canvas).ok()?;
-                    Some(())
-                })
-                .expect("Pluto window container HTML element not found!");
+            use winit::platform::web::{WindowBuilderExtWebSys, WindowExtWebSys};
+
+            let config = crate::web::get_canvas_config();
+            let existing_canvas = crate::web::find_canvas(&config);
+            let window = WindowBuilder::new()
+                .with_canvas(existing_canvas.clone())
+                .build(backing_loop)
+                .unwrap();
+
+            let canvas = window.canvas();
+            if existing_canvas.is_none() {
+                crate::web::append_canvas(&canvas, &config);
+            }
+
+            let observer = if config.track_resize {
+                crate::web::release_canvas_style(&canvas, existing_canvas.is_none());
+                Some(crate::web::observe_canvas(
+                    canvas,
+                    window.id().into(),
+                    command_proxy.clone(),
+                ))
+            } else {
+                window.set_inner_size(winit::dpi::LogicalSize::new(
+                    config.size.width,
+                    config.size.height,
+                ));
+                None
+            };
+
+            Self(window, command_proxy, event_receiver, observer)
         }
 
-        Self(window, command_proxy, event_receiver)
+        #[cfg(not(target_arch = "wasm32"))]
+        Self(
+            WindowBuilder::new().build(backing_loop).unwrap(),
+            command_proxy,
+            event_receiver,
+        )
     }
 
     fn receive_event(&self) -> DisplayEvent {
@@ -99,10 +126,18 @@ impl Window for WinitWindow {
         })
     }
 
+    fn next_event(&self) -> NextDisplayEvent<'_> {
+        self.2.next_event()
+    }
+
     fn request_repaint(&self) {
         self.0.request_redraw()
     }
 
+    fn create_event_proxy(&self) -> DisplayEventProxy {
+        DisplayEventProxy::new(self.0.id().into(), self.1.clone())
+    }
+
     fn get_id(&self) -> Self::IdType {
         self.0.id()
     }
@@ -111,11 +146,54 @@ impl Window for WinitWindow {
         window::PhysicalSize::from(WinitPhysicalSize(self.0.inner_size()))
     }
 
+    fn get_scale_factor(&self) -> f64 {
+        self.0.scale_factor()
+    }
+
+    fn set_ime_allowed(&self, allowed: bool) {
+        self.0.set_ime_allowed(allowed)
+    }
+
+    fn set_ime_position(&self, x: f64, y: f64) {
+        self.0
+            .set_ime_position(winit::dpi::LogicalPosition::new(x, y))
+    }
+
     fn get_backing_window(&self) -> &Self::BackingType {
         &self.0
     }
 }
 
+pub struct WinitKey(pub VirtualKeyCode);
+
+macro_rules! map_keys {
+    ($code:expr, $scancode:expr; $($same:ident),*; $($winit:ident => $pluto:ident),*) => {
+        match $code {
+            $(VirtualKeyCode::$same => Key::$same,)*
+            $(VirtualKeyCode::$winit => Key::$pluto,)*
+            _ => Key::Other($scancode),
+        }
+    };
+}
+
+impl WinitKey {
+    /// Converts the key code, falling back to the scancode for keys without a [`Key`] variant.
+    pub fn into_key(self, scancode: u32) -> Key {
+        map_keys!(self.0, scancode;
+            A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+            F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+            Escape, Space, Tab, Insert, Delete, Home, End, PageUp, PageDown,
+            Left, Right, Up, Down;
+            Key0 => Digit0, Key1 => Digit1, Key2 => Digit2, Key3 => Digit3, Key4 => Digit4,
+            Key5 => Digit5, Key6 => Digit6, Key7 => Digit7, Key8 => Digit8, Key9 => Digit9,
+            Return => Enter, Back => Backspace, Snapshot => PrintScreen,
+            LShift => LeftShift, RShift => RightShift,
+            LControl => LeftControl, RControl => RightControl,
+            LAlt => LeftAlt, RAlt => RightAlt
+        )
+    }
+}
+
 impl From<WinitWindowEvent<'_, '_>> for window::WindowEvent {
     fn from(e: WinitWindowEvent) -> Self {
         match e.0 {
@@ -125,24 +203,84 @@ impl From<WinitWindowEvent<'_, '_>> for window::WindowEvent {
             WindowEvent::Moved(_) => window::WindowEvent::Unknown,
             WindowEvent::CloseRequested => window::WindowEvent::CloseRequested,
             WindowEvent::Destroyed => window::WindowEvent::Unknown,
-            WindowEvent::DroppedFile(_) => window::WindowEvent::Unknown,
-            WindowEvent::HoveredFile(_) => window::WindowEvent::Unknown,
-            WindowEvent::HoveredFileCancelled => window::WindowEvent::Unknown,
+            WindowEvent::DroppedFile(path) => window::WindowEvent::FileDropped(path.clone()),
+            WindowEvent::HoveredFile(path) => window::WindowEvent::FileHovered(path.clone()),
+            WindowEvent::HoveredFileCancelled => window::WindowEvent::FileHoverCancelled,
+            WindowEvent::ReceivedCharacter(c) if !c.is_control() => {
+                window::WindowEvent::TextInput(TextInputEvent::Commit(c.to_string()))
+            }
             WindowEvent::ReceivedCharacter(_) => window::WindowEvent::Unknown,
             WindowEvent::Focused(_) => window::WindowEvent::Unknown,
-            WindowEvent::KeyboardInput { .. } => window::WindowEvent::Unknown,
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        scancode,
+                        state,
+                        virtual_keycode,
+                        ..
+                    },
+                ..
+            } => window::WindowEvent::KeyboardInput {
+                key: virtual_keycode.map_or(Key::Other(*scancode), |code| {
+                    WinitKey(code).into_key(*scancode)
+                }),
+                pressed: *state == ElementState::Pressed,
+            },
             WindowEvent::ModifiersChanged(_) => window::WindowEvent::Unknown,
-            WindowEvent::CursorMoved { .. } => window::WindowEvent::Unknown,
+            WindowEvent::CursorMoved { position, .. } => window::WindowEvent::CursorMoved {
+                x: position.x,
+                y: position.y,
+            },
             WindowEvent::CursorEntered { .. } => window::WindowEvent::Unknown,
-            WindowEvent::CursorLeft { .. } => window::WindowEvent::Unknown,
-            WindowEvent::MouseWheel { .. } => window::WindowEvent::Unknown,
-            WindowEvent::MouseInput { .. } => window::WindowEvent::Unknown,
+            WindowEvent::CursorLeft { .. } => window::WindowEvent::CursorLeft,
+            WindowEvent::MouseWheel { delta, .. } => {
+                window::WindowEvent::MouseWheel(match *delta {
+                    event::MouseScrollDelta::LineDelta(x, y) => MouseScrollDelta::Lines { x, y },
+                    event::MouseScrollDelta::PixelDelta(position) => MouseScrollDelta::Pixels {
+                        x: position.x,
+                        y: position.y,
+                    },
+                })
+            }
+            WindowEvent::MouseInput { state, button, .. } => window::WindowEvent::MouseInput {
+                button: match *button {
+                    event::MouseButton::Left => MouseButton::Left,
+                    event::MouseButton::Right => MouseButton::Right,
+                    event::MouseButton::Middle => MouseButton::Middle,
+                    event::MouseButton::Other(button) => MouseButton::Other(button),
+                },
+                pressed: *state == ElementState::Pressed,
+            },
             WindowEvent::TouchpadPressure { .. } => window::WindowEvent::Unknown,
             WindowEvent::AxisMotion { .. } => window::WindowEvent::Unknown,
-            WindowEvent::Touch(_) => window::WindowEvent::Unknown,
-            WindowEvent::ScaleFactorChanged { .. } => window::WindowEvent::Unknown,
+            WindowEvent::Touch(touch) => window::WindowEvent::Touch(Touch {
+                id: touch.id,
+                phase: match touch.phase {
+                    event::TouchPhase::Started => TouchPhase::Started,
+                    event::TouchPhase::Moved => TouchPhase::Moved,
+                    event::TouchPhase::Ended => TouchPhase::Ended,
+                    event::TouchPhase::Cancelled => TouchPhase::Cancelled,
+                },
+                x: touch.location.x,
+                y: touch.location.y,
+            }),
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                new_inner_size,
+            } => window::WindowEvent::ScaleFactorChanged {
+                scale_factor: *scale_factor,
+                new_size: window::PhysicalSize::from(WinitPhysicalSize(**new_inner_size)),
+            },
             WindowEvent::ThemeChanged(_) => window::WindowEvent::Unknown,
-            WindowEvent::Ime(_) => window::WindowEvent::Unknown,
+            WindowEvent::Ime(ime) => window::WindowEvent::TextInput(match ime {
+                Ime::Enabled => TextInputEvent::ImeEnabled,
+                Ime::Preedit(text, cursor) => TextInputEvent::Preedit(Preedit {
+                    text: text.clone(),
+                    cursor: *cursor,
+                }),
+                Ime::Commit(text) => TextInputEvent::Commit(text.clone()),
+                Ime::Disabled => TextInputEvent::ImeDisabled,
+            }),
             WindowEvent::Occluded(_) => window::WindowEvent::Unknown,
         }
     }
@@ -1,4 +1,11 @@
 
+struct CameraUniform {
+    mvp: mat4x4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> camera: CameraUniform;
+
 struct VertexInput {
     [[location(0)]] position: vec3<f32>;
     [[location(1)]] color: vec3<f32>;
@@ -15,7 +22,7 @@ fn vs_main(
 ) -> VertexOutput {
     var vertex_out: VertexOutput;
     vertex_out.color = model.color;
-    vertex_out.clip_position = vec4<f32>(model.position, 1.0);
+    vertex_out.clip_position = camera.mvp * vec4<f32>(model.position, 1.0);
     return vertex_out;
 }
 
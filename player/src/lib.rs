@@ -24,65 +24,77 @@
 
 pub mod logger;
 
+use pluto_engine::asset::read_asset_to_string;
+use pluto_engine::pluto_engine_render::upload::FrameAllocator;
+use pluto_engine::prelude::*;
+use pluto_engine::render::camera::MvpUniform;
+use pluto_engine::runtime::platform::winit::factory::{ApplicationFactory, WindowContext};
 use pluto_engine::runtime::platform::winit::wgpu::WinitWgpuDisplay;
-use pluto_engine::runtime::pluto_runtime::PlutoRuntime;
-use pluto_engine::runtime::{ApplicationBootstrapper, Runtime};
-use std::fs;
-
-use pluto_engine::pluto_engine_display::pluto_engine_render::device::{
-    CommandBuffer, CommandBufferBuilder, Device, PhysicalDevice, Queue,
-};
-use pluto_engine::pluto_engine_display::pluto_engine_render::instance::ContextInstance;
-use pluto_engine::pluto_engine_display::pluto_engine_render::mesh::{AttributeFormat, Vertex};
-use pluto_engine::pluto_engine_display::pluto_engine_render::pipeline::{
-    Pipeline, PipelineCreateInfo,
-};
-use pluto_engine::pluto_engine_display::pluto_engine_render::shader::ShaderCode;
-use pluto_engine::pluto_engine_display::pluto_engine_render::surface::{Surface, SurfaceTexture};
-use pluto_engine::pluto_engine_display::pluto_engine_render::texture::TextureView;
-use pluto_engine::pluto_engine_display::{
-    ApplicationDisplay, ApplicationState, PlutoDevice, PlutoPipeline, PlutoQueue,
-    PlutoSurfaceTexture,
-};
+use pluto_engine_core_platform_wgpu::frame::WgpuFrameComposer;
 use pluto_engine_core_platform_wgpu::instance::WgpuInstance;
 use pluto_engine_core_platform_wgpu::raw_window_handle::HasRawWindowHandle;
 use pluto_engine_core_platform_wgpu::surface::WgpuSurface;
+use pluto_engine_core_platform_wgpu::uniform::{WgpuUniformBindGroup, WgpuUniformBuffer};
+use pluto_engine_core_platform_wgpu::upload::WgpuFrameAllocator;
 use pluto_engine_core_platform_wgpu::wgpu;
 use pluto_engine_core_platform_winit::event_loop::WinitEventLoop;
-use pluto_engine_core_platform_winit::pluto_engine_window::window::Window;
-use wgpu::util::DeviceExt;
+use std::sync::Arc;
+use std::time::Duration;
 
 use crate::AttributeFormat::Float32x3;
 
-use pluto_engine::application::layer::pluto::PlutoLayerManager;
-use pluto_engine::application::Application;
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
 pub async fn main() {
-    logger::init_logger();
-
-    PlutoRuntime::run(ApplicationBootstrapper::<WinitEventLoop>::new(Box::new(
-        |window| {
-            let instance = WgpuInstance::new(&window);
-            let (physical_device, mut surface) = instance.create_device_and_surface();
-            let (device, queue) = physical_device.create_device_and_queue();
-            surface.configure(&device);
-            let display = WinitWgpuDisplay::new(&mut surface, &window, &device);
-            let mut state = State::new(display, &device, &queue);
-            let mut layer_manager = PlutoLayerManager::new();
-            pluto_engine_test::ApplicationTest::run(&mut layer_manager);
-            ApplicationBootstrapper::<WinitEventLoop>::default_loop(&mut state);
-        },
-    )));
+    let config = EngineConfig::from_environment();
+    logger::init_logger(config.log_level);
+
+    PlutoRuntime::run(
+        ApplicationBootstrapper::<WinitEventLoop>::from_factory(Player).config(config),
+    );
+}
+
+#[cfg(target_os = "android")]
+fn android_main() {
+    pollster::block_on(main())
+}
+
+pluto_engine::android_main!(android_main);
+
+struct Player;
+
+impl ApplicationFactory for Player {
+    type State = State<'static, WinitWgpuDisplay>;
+
+    fn create(self, context: WindowContext) -> Result<Self::State, EngineError> {
+        let WindowContext {
+            mut display,
+            device,
+            queue,
+            ..
+        } = context;
+
+        let mut frame_composer = WgpuFrameComposer::new();
+        frame_composer.set_clear_color(Some(ClearColor::new(0.0, 0.6, 0.9, 1.0)));
+        display.set_frame_composer(frame_composer);
+
+        let mut state = State::new(display, device, queue)?;
+        pluto_engine_test::ApplicationTest::run(&mut state.layer_manager);
+        Ok(state)
+    }
 }
 
 struct State<'a, AD: ApplicationDisplay<'a>> {
     display: AD,
-    device: &'a PlutoDevice<'a, AD>,
-    queue: &'a PlutoQueue<'a, AD>,
+    device: Arc<PlutoDevice<'a, AD>>,
+    queue: Arc<PlutoQueue<'a, AD>>,
     render_pipeline: PlutoPipeline<'a, AD>,
+    camera_buffer: WgpuUniformBuffer<'static>,
+    camera_bind_group: WgpuUniformBindGroup<'static>,
+    frame_allocator: WgpuFrameAllocator,
+    layer_manager: PlutoLayerManager,
 }
 
 #[repr(C)]
@@ -113,50 +125,77 @@ const VERTICES: &[TestVertex] = &[
 
 impl<
         'a,
-        W: Window<SizeType = <WgpuSurface<'a> as Surface<'a>>::SizeType> + HasRawWindowHandle,
+        W: Window<SizeType = <WgpuSurface<'a> as Surface<'a>>::SizeType> + HasRawWindowHandle + 'a,
         AD: ApplicationDisplay<'a, WindowType = W, ContextType = WgpuInstance<'a, W>>,
     > ApplicationState<'a, AD> for State<'a, AD>
 {
-    fn new(display: AD, device: &'a PlutoDevice<'a, AD>, queue: &'a PlutoQueue<'a, AD>) -> Self {
-        let shader_code = fs::read_to_string("assets/plutoengine.base/shader.wgsl").unwrap();
+    fn new(
+        display: AD,
+        device: Arc<PlutoDevice<'a, AD>>,
+        queue: Arc<PlutoQueue<'a, AD>>,
+    ) -> Result<Self, EngineError> {
+        let shader_code = read_asset_to_string("plutoengine.base/shader.wgsl")?;
 
         let shader = device.create_shader(&ShaderCode::Wgsl {
             code: &shader_code,
             vertex_entry: "vs_main",
             fragment_entry: "fs_main",
+            label: Some("Test Shader"),
         });
 
         let pipeline_layout = device.create_pipeline_layout(&shader);
 
         let render_pipeline = device.create_pipeline(&PipelineCreateInfo {
-            shader: &shader,
-            pipeline_layout: &pipeline_layout,
             buffer_layout: &[TestVertex::layout()],
-            texture_format: display.get_surface().get_texture_format(),
+            uniforms: &[MvpUniform::layout(0)],
+            label: Some("Test Pipeline"),
+            ..PipelineCreateInfo::new(
+                &pipeline_layout,
+                &shader,
+                display.get_surface().get_texture_format(),
+            )
         });
 
-        Self {
+        let camera_buffer = device.create_uniform_buffer(MvpUniform::SIZE);
+        let camera_bind_group =
+            device.create_uniform_bind_group(&render_pipeline, &[&camera_buffer]);
+
+        let frame_allocator =
+            WgpuFrameAllocator::new(&device, WgpuFrameAllocator::DEFAULT_CHUNK_SIZE);
+
+        Ok(Self {
             display,
             device,
             queue,
             render_pipeline,
-        }
+            camera_buffer,
+            camera_bind_group,
+            frame_allocator,
+            layer_manager: PlutoLayerManager::new(),
+        })
+    }
+
+    fn update(&mut self, _delta: Duration) {
+        self.layer_manager.run();
     }
 
     fn render(&mut self, surface_texture: &PlutoSurfaceTexture<'a, AD>) {
         let view = surface_texture.get_texture_view();
 
+        let size = self.display.logical_size();
+        let camera = Camera::new((size.width / size.height.max(1.0)) as f32);
+        let mvp = MvpUniform::new(&camera, Mat4::identity());
+        self.device
+            .write_uniform_buffer(&self.queue, &self.camera_buffer, &mvp.as_bytes());
+
         let mut command_buf = self.device.begin_command_buffer();
 
         let encoder = command_buf.get_backing_command_buffer_builder();
 
-        let b_device = self.device.get_backing_device();
-
-        let vertex_buffer = b_device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::cast_slice(VERTICES),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+        self.frame_allocator.begin_frame();
+        let vertex_slice =
+            self.frame_allocator
+                .upload(&self.device, &self.queue, bytemuck::cast_slice(VERTICES));
 
         let num_vertices = VERTICES.len() as u32;
 
@@ -167,12 +206,7 @@ impl<
                     view: view.get_backing_texture_view(),
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.0,
-                            g: 0.6,
-                            b: 0.9,
-                            a: 1.0,
-                        }),
+                        load: wgpu::LoadOp::Load,
                         store: true,
                     },
                 }],
@@ -180,7 +214,8 @@ impl<
             });
 
             render_pass.set_pipeline(self.render_pipeline.get_backing_pipeline());
-            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.set_bind_group(0, self.camera_bind_group.get_backing_bind_group(), &[]);
+            render_pass.set_vertex_buffer(0, vertex_slice.get_backing_slice());
             render_pass.draw(0..num_vertices, 0..1);
         }
 
@@ -192,4 +227,8 @@ impl<
     fn display(&mut self) -> &mut AD {
         &mut self.display
     }
+
+    fn on_exit(&mut self) {
+        self.layer_manager.shutdown();
+    }
 }
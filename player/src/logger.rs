@@ -22,15 +22,24 @@
  * SOFTWARE.
  */
 
-use pluto_engine::log::LevelFilter::Warn;
+use pluto_engine::log::LevelFilter;
 
-pub fn init_logger() {
+/// Initializes the logger of the platform with the level of the engine configuration.
+pub fn init_logger(level: LevelFilter) {
     cfg_if::cfg_if! {
         if #[cfg(target_arch = "wasm32")] {
             std::panic::set_hook(Box::new(console_error_panic_hook::hook));
-            console_log::init_with_level(log::Level::Warn).expect("Could't initialize logger");
+            console_log::init_with_level(min_level(level)).expect("Could't initialize logger");
+        } else if #[cfg(target_os = "android")] {
+            pluto_engine::runtime::android::init_logger(min_level(level));
         } else {
-            env_logger::builder().filter_level(Warn).init();
+            env_logger::builder().filter_level(level).init();
         }
     }
 }
+
+/// Loggers taking a level cannot be turned off, the max level then filters all messages.
+#[cfg(any(target_arch = "wasm32", target_os = "android"))]
+fn min_level(level: LevelFilter) -> pluto_engine::log::Level {
+    level.to_level().unwrap_or(pluto_engine::log::Level::Error)
+}
@@ -0,0 +1,177 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Host functions imported by scripts from the `pluto` module.
+//!
+//! | Function                                          | Description                                    |
+//! |---------------------------------------------------|------------------------------------------------|
+//! | `log(level: i32, message: i32, length: i32)`      | Logs a UTF-8 message, levels 1 (error) to 5 (trace) |
+//! | `request_detach()`                                | Detaches the layer running the script          |
+//! | `is_key_down(key: i32) -> i32`                    | Queries the [`ScriptInput`] system             |
+//!
+//! Scripts may export the lifecycle hooks `on_attach()`, `on_update(delta_seconds: f32)`
+//! and `on_detach()`, all of which are optional.
+//!
+//! Each function requires a [`ScriptCapability`](crate::sandbox::ScriptCapability),
+//! see the [`crate::sandbox`] module.
+//!
+//! Scripts wishing to keep their state across hot reloads should also export
+//! `save_state() -> (i32, i32)`, returning the pointer and length of the serialized state
+//! in the exported `memory`, along with `alloc(length: i32) -> i32` and
+//! `load_state(pointer: i32, length: i32)`, called on the reloaded script.
+
+use crate::sandbox::{ScriptCapability, ScriptLimits};
+use log::{log, Level};
+use pluto_engine::application::system::System;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use wasmer::{Array, Exports, Function, ImportObject, LazyInit, Memory, Store, WasmPtr, WasmerEnv};
+
+/// The name of the module scripts import host functions from.
+pub const HOST_MODULE: &str = "pluto";
+
+/// A system providing input state to scripts.
+///
+/// Key codes are defined by the application, scripts query them using `is_key_down`.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptInput {
+    keys_down: HashSet<u32>,
+}
+
+impl ScriptInput {
+    pub fn set_key_down(&mut self, key: u32, down: bool) {
+        if down {
+            self.keys_down.insert(key);
+        } else {
+            self.keys_down.remove(&key);
+        }
+    }
+
+    pub fn is_key_down(&self, key: u32) -> bool {
+        self.keys_down.contains(&key)
+    }
+}
+
+impl System for ScriptInput {}
+
+/// The state shared between a script layer and the host functions of its script.
+#[derive(Debug, Default)]
+pub(crate) struct ScriptHostState {
+    pub(crate) detach_requested: bool,
+    pub(crate) input: ScriptInput,
+}
+
+#[derive(WasmerEnv, Clone)]
+pub(crate) struct ScriptEnv {
+    pub(crate) name: Arc<str>,
+    pub(crate) state: Arc<Mutex<ScriptHostState>>,
+    #[wasmer(export)]
+    memory: LazyInit<Memory>,
+}
+
+impl ScriptEnv {
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            state: Arc::default(),
+            memory: LazyInit::new(),
+        }
+    }
+
+    /// Creates an environment for a new instance of the same script, sharing its host state.
+    pub(crate) fn for_new_instance(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            state: self.state.clone(),
+            memory: LazyInit::new(),
+        }
+    }
+}
+
+fn log_level(level: i32) -> Level {
+    match level {
+        i32::MIN..=1 => Level::Error,
+        2 => Level::Warn,
+        3 => Level::Info,
+        4 => Level::Debug,
+        _ => Level::Trace,
+    }
+}
+
+fn host_log(env: &ScriptEnv, level: i32, message: WasmPtr<u8, Array>, length: u32) {
+    let message = env
+        .memory_ref()
+        .and_then(|memory| message.get_utf8_string(memory, length));
+
+    match message {
+        Some(message) => log!(log_level(level), "[{}] {}", env.name, message),
+        None => log!(
+            Level::Warn,
+            "[{}] Script logged an invalid string.",
+            env.name
+        ),
+    }
+}
+
+fn host_request_detach(env: &ScriptEnv) {
+    env.state.lock().unwrap().detach_requested = true;
+}
+
+fn host_is_key_down(env: &ScriptEnv, key: u32) -> i32 {
+    env.state.lock().unwrap().input.is_key_down(key) as i32
+}
+
+/// Creates the imports for a script, see the [module documentation](self).
+///
+/// Only the functions the script has the capability for are provided.
+pub(crate) fn host_imports(store: &Store, env: &ScriptEnv, limits: &ScriptLimits) -> ImportObject {
+    let functions = [
+        (
+            ScriptCapability::Log,
+            "log",
+            Function::new_native_with_env(store, env.clone(), host_log),
+        ),
+        (
+            ScriptCapability::Detach,
+            "request_detach",
+            Function::new_native_with_env(store, env.clone(), host_request_detach),
+        ),
+        (
+            ScriptCapability::Input,
+            "is_key_down",
+            Function::new_native_with_env(store, env.clone(), host_is_key_down),
+        ),
+    ];
+
+    let mut namespace = Exports::new();
+    for (capability, name, function) in functions {
+        if limits.allows(capability) {
+            namespace.insert(name, function);
+        }
+    }
+
+    let mut import_object = ImportObject::new();
+    import_object.register(HOST_MODULE, namespace);
+    import_object
+}
@@ -0,0 +1,338 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use crate::host::{host_imports, ScriptEnv, ScriptInput};
+use crate::sandbox::ScriptLimits;
+use crate::ScriptError;
+use log::{error, info, warn};
+use pluto_engine::application::layer::{
+    Layer, LayerDependencyDeclaration, LayerSwapType, LayerSystemManager, LayerWalker,
+};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use wasmer::{Instance, Module, Value};
+
+/// Watches the file of a script for modifications.
+struct HotReload {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    interval: Duration,
+    last_check: Instant,
+}
+
+impl HotReload {
+    fn get_modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    /// *Returns `true` if the file was modified since the last call.*
+    fn poll(&mut self) -> bool {
+        if self.last_check.elapsed() < self.interval {
+            return false;
+        }
+
+        self.last_check = Instant::now();
+        let modified = Self::get_modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+
+        self.modified = modified;
+        true
+    }
+}
+
+/// A layer running a WebAssembly script.
+///
+/// The script is updated every time the layer is entered, see the [`crate::host`] module
+/// for the functions available to scripts and the lifecycle hooks they may export.
+pub struct ScriptLayer {
+    name: String,
+    env: ScriptEnv,
+    instance: Instance,
+    last_update: Option<Instant>,
+    hot_reload: Option<HotReload>,
+    limits: ScriptLimits,
+}
+
+fn instantiate(
+    env: &ScriptEnv,
+    code: &[u8],
+    limits: &ScriptLimits,
+) -> Result<Instance, ScriptError> {
+    let store = limits.create_store();
+    let module = Module::new(&store, code)?;
+    limits.check(&module)?;
+    Ok(Instance::new(&module, &host_imports(&store, env, limits))?)
+}
+
+impl ScriptLayer {
+    /// Compiles and instantiates a script from WebAssembly binary or text format,
+    /// with the default [`ScriptLimits`].
+    pub fn new(name: impl Into<String>, code: impl AsRef<[u8]>) -> Result<Self, ScriptError> {
+        Self::with_limits(name, code, ScriptLimits::default())
+    }
+
+    /// Compiles and instantiates a script, rejecting it if it does not fit the limits.
+    pub fn with_limits(
+        name: impl Into<String>,
+        code: impl AsRef<[u8]>,
+        limits: ScriptLimits,
+    ) -> Result<Self, ScriptError> {
+        let name = name.into();
+        let env = ScriptEnv::new(&name);
+        let instance = instantiate(&env, code.as_ref(), &limits)?;
+
+        Ok(Self {
+            name,
+            env,
+            instance,
+            last_update: None,
+            hot_reload: None,
+            limits,
+        })
+    }
+
+    /// Loads a script from a file, named after the file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ScriptError> {
+        let path = path.as_ref();
+        let code = std::fs::read(path)?;
+        Self::new(path.display().to_string(), code)
+    }
+
+    /// Loads a script from a file and reloads it whenever the file changes,
+    /// checking for changes at most once per `interval`.
+    pub fn from_file_hot_reload(
+        path: impl AsRef<Path>,
+        interval: Duration,
+    ) -> Result<Self, ScriptError> {
+        let path = path.as_ref();
+        let modified = HotReload::get_modified(path);
+        let mut layer = Self::from_file(path)?;
+
+        layer.hot_reload = Some(HotReload {
+            path: path.to_path_buf(),
+            modified,
+            interval,
+            last_check: Instant::now(),
+        });
+
+        Ok(layer)
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Replaces the running script with new code, migrating its state,
+    /// see the [`crate::host`] module.
+    ///
+    /// *If the new code fails to compile or does not fit the limits of this layer,
+    /// the old script keeps running.*
+    pub fn reload(&mut self, code: impl AsRef<[u8]>) -> Result<(), ScriptError> {
+        let env = self.env.for_new_instance();
+        let instance = instantiate(&env, code.as_ref(), &self.limits)?;
+        let state = self.save_state();
+
+        self.env = env;
+        self.instance = instance;
+
+        if let Some(state) = state {
+            self.load_state(&state);
+        }
+
+        Ok(())
+    }
+
+    /// Calls the `save_state` hook of the script and copies the state out of its memory.
+    fn save_state(&self) -> Option<Vec<u8>> {
+        let result = self.call_hook("save_state", &[])?;
+        let memory = self.instance.exports.get_memory("memory").ok()?;
+
+        let (ptr, len) = match *result {
+            [Value::I32(ptr), Value::I32(len)] => (ptr as usize, len as usize),
+            _ => {
+                warn!("Script {} returned an invalid state.", self.name);
+                return None;
+            }
+        };
+
+        let view = memory.view::<u8>();
+        let Some(state) = view.get(ptr..ptr.saturating_add(len)) else {
+            warn!("Script {} returned a state out of bounds.", self.name);
+            return None;
+        };
+
+        Some(state.iter().map(|byte| byte.get()).collect())
+    }
+
+    /// Copies the state into the memory of the script and calls its `load_state` hook.
+    fn load_state(&self, state: &[u8]) {
+        let len = Value::I32(state.len() as i32);
+        let ptr = match self
+            .call_hook("alloc", std::slice::from_ref(&len))
+            .as_deref()
+        {
+            Some([Value::I32(ptr)]) => *ptr as usize,
+            _ => {
+                warn!("Script {} cannot allocate its saved state.", self.name);
+                return;
+            }
+        };
+
+        let Ok(memory) = self.instance.exports.get_memory("memory") else {
+            return;
+        };
+
+        let view = memory.view::<u8>();
+        let Some(target) = view.get(ptr..ptr.saturating_add(state.len())) else {
+            warn!("Script {} allocated its state out of bounds.", self.name);
+            return;
+        };
+
+        for (cell, byte) in target.iter().zip(state) {
+            cell.set(*byte);
+        }
+
+        self.call_hook("load_state", &[Value::I32(ptr as i32), len]);
+    }
+
+    fn poll_hot_reload(&mut self) {
+        let Some(hot_reload) = self.hot_reload.as_mut() else {
+            return;
+        };
+
+        if !hot_reload.poll() {
+            return;
+        }
+
+        let result = std::fs::read(&hot_reload.path)
+            .map_err(ScriptError::from)
+            .and_then(|code| self.reload(code));
+
+        match result {
+            Ok(()) => info!("Script {} reloaded.", self.name),
+            Err(err) => error!("Script {} failed to reload: {}", self.name, err),
+        }
+    }
+
+    /// Calls an exported hook of the script, if present.
+    ///
+    /// *Runtime errors are logged, a failing script is not stopped.*
+    fn call_hook(&self, hook: &str, args: &[Value]) -> Option<Box<[Value]>> {
+        let function = self.instance.exports.get_function(hook).ok()?;
+
+        match function.call(args) {
+            Ok(result) => Some(result),
+            Err(err) => {
+                error!("Script {} failed in {}: {}", self.name, hook, err);
+                None
+            }
+        }
+    }
+}
+
+impl Layer for ScriptLayer {
+    fn should_detach(&self) -> Option<LayerSwapType> {
+        if self.env.state.lock().unwrap().detach_requested {
+            Some(LayerSwapType::Synchronous)
+        } else {
+            None
+        }
+    }
+
+    fn on_attach(&mut self, _dependencies: &mut LayerDependencyDeclaration) {
+        self.call_hook("on_attach", &[]);
+    }
+
+    fn on_detach(&mut self) {
+        self.call_hook("on_detach", &[]);
+    }
+
+    fn on_enter(&mut self, systems: &mut dyn LayerSystemManager<'_>, next: &mut dyn LayerWalker) {
+        let now = Instant::now();
+        let delta = self
+            .last_update
+            .replace(now)
+            .map_or(0.0, |last_update| (now - last_update).as_secs_f32());
+
+        self.poll_hot_reload();
+
+        if let Some(input) = systems.query::<ScriptInput>() {
+            self.env.state.lock().unwrap().input = input.clone();
+        }
+
+        self.call_hook("on_update", &[delta.into()]);
+
+        next.next(systems);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::layer::ScriptLayer;
+    use wasmer::Value;
+
+    fn counter_script(step: i32) -> String {
+        format!(
+            r#"
+            (module
+              (memory (export "memory") 1)
+              (global $counter (mut i32) (i32.const 0))
+              (func (export "on_update") (param f32)
+                (global.set $counter (i32.add (global.get $counter) (i32.const {}))))
+              (func (export "get_counter") (result i32) (global.get $counter))
+              (func (export "save_state") (result i32 i32)
+                (i32.store (i32.const 0) (global.get $counter))
+                (i32.const 0) (i32.const 4))
+              (func (export "alloc") (param i32) (result i32) (i32.const 16))
+              (func (export "load_state") (param i32 i32)
+                (global.set $counter (i32.load (local.get 0)))))
+            "#,
+            step
+        )
+    }
+
+    fn get_counter(layer: &ScriptLayer) -> Option<Box<[Value]>> {
+        layer.call_hook("get_counter", &[])
+    }
+
+    /// A counting script is updated, then reloaded with a different step.
+    /// The counter should be preserved by the reload, and a broken reload should be ignored.
+    #[test]
+    fn test_reload_migrates_state() {
+        let mut layer = ScriptLayer::new("counter", counter_script(1)).unwrap();
+        layer.call_hook("on_update", &[Value::F32(0.0)]);
+        layer.call_hook("on_update", &[Value::F32(0.0)]);
+
+        layer.reload(counter_script(10)).unwrap();
+        assert_eq!(get_counter(&layer).as_deref(), Some(&[Value::I32(2)][..]));
+
+        layer.call_hook("on_update", &[Value::F32(0.0)]);
+        assert!(layer.reload("(module (func").is_err());
+        assert_eq!(get_counter(&layer).as_deref(), Some(&[Value::I32(12)][..]));
+    }
+}
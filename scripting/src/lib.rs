@@ -1,14 +1,89 @@
+pub mod host;
+pub mod layer;
+pub mod sandbox;
+
+use crate::sandbox::SandboxError;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use wasmer::{CompileError, InstantiationError};
+
+#[derive(Debug)]
+pub enum ScriptError {
+    Io(std::io::Error),
+    Compile(Box<CompileError>),
+    Instantiation(Box<InstantiationError>),
+    Sandbox(SandboxError),
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScriptError::Io(err) => write!(f, "{}", err),
+            ScriptError::Compile(err) => write!(f, "{}", err),
+            ScriptError::Instantiation(err) => write!(f, "{}", err),
+            ScriptError::Sandbox(err) => write!(f, "script rejected by the sandbox: {}", err),
+        }
+    }
+}
+
+impl Error for ScriptError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ScriptError::Io(err) => Some(err),
+            ScriptError::Compile(err) => Some(err.as_ref()),
+            ScriptError::Instantiation(err) => Some(err.as_ref()),
+            ScriptError::Sandbox(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for ScriptError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<CompileError> for ScriptError {
+    fn from(err: CompileError) -> Self {
+        Self::Compile(Box::new(err))
+    }
+}
+
+impl From<InstantiationError> for ScriptError {
+    fn from(err: InstantiationError) -> Self {
+        Self::Instantiation(Box::new(err))
+    }
+}
+
+impl From<SandboxError> for ScriptError {
+    fn from(err: SandboxError) -> Self {
+        Self::Sandbox(err)
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use crate::host::ScriptInput;
+    use crate::layer::ScriptLayer;
+    use pluto_engine::application::layer::pluto::PlutoLayerManager;
+    use pluto_engine::application::layer::{
+        Layer, LayerDependencyDeclaration, LayerManager, LayerSwapType,
+    };
     use std::error::Error;
     use wasmer::{imports, Instance, Module, Store, Value};
 
     #[test]
     fn main() -> Result<(), Box<dyn Error>> {
-        let wasm_bytes = include_bytes!("../add.wasm");
+        let wasm_bytes = r#"
+            (module
+              (func (export "add") (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add))
+        "#;
 
-        let mut store = Store::default();
-        let module = Module::new(&store, &wasm_bytes)?;
+        let store = Store::default();
+        let module = Module::new(&store, wasm_bytes)?;
         let import_obj = imports! {};
         let instance = Instance::new(&module, &import_obj)?;
         let add_fn = instance.exports.get_function("add")?;
@@ -17,4 +92,45 @@ mod test {
 
         Ok(())
     }
+
+    struct InputLayer;
+
+    impl Layer for InputLayer {
+        fn should_detach(&self) -> Option<LayerSwapType> {
+            Some(LayerSwapType::Synchronous)
+        }
+
+        fn on_attach(&mut self, dependencies: &mut LayerDependencyDeclaration) {
+            let mut input = ScriptInput::default();
+            input.set_key_down(32, true);
+            dependencies.provide(input);
+        }
+    }
+
+    /// A script requesting its own detachment while a key is held is run
+    /// above a layer holding the key and detaching after the first run.
+    /// The layer manager should finish, as the script layer is detached too.
+    #[test]
+    fn test_script_layer() -> Result<(), Box<dyn Error>> {
+        let script = r#"
+            (module
+              (import "pluto" "log" (func $log (param i32 i32 i32)))
+              (import "pluto" "request_detach" (func $request_detach))
+              (import "pluto" "is_key_down" (func $is_key_down (param i32) (result i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "updated")
+              (func (export "on_update") (param f32)
+                (call $log (i32.const 3) (i32.const 0) (i32.const 7))
+                (if (call $is_key_down (i32.const 32))
+                  (then (call $request_detach)))))
+        "#;
+
+        let mut layer_manager = PlutoLayerManager::new();
+        layer_manager.add_layer(Box::new(InputLayer));
+        layer_manager.add_layer(Box::new(ScriptLayer::new("test", script)?));
+
+        assert!((0..3).any(|_| layer_manager.run()));
+
+        Ok(())
+    }
 }
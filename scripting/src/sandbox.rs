@@ -0,0 +1,320 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 AMNatty
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Resource limits for scripts.
+//!
+//! Scripts are checked against their [`ScriptLimits`] before instantiation, a script importing
+//! a host function it has no capability for, or requiring more memory than the limit, is rejected.
+//! The maximum size of memories is clamped to the limit, so growing them past it fails.
+
+use crate::host::HOST_MODULE;
+use loupe::MemoryUsage;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::ptr::NonNull;
+use std::sync::Arc;
+use wasmer::vm::{
+    self, MemoryError, MemoryStyle, TableStyle, VMMemoryDefinition, VMTableDefinition,
+};
+use wasmer::{
+    BaseTunables, Cranelift, Engine, MemoryType, Module, Pages, Store, TableType, Tunables,
+    Universal, WASM_PAGE_SIZE,
+};
+
+/// A group of host functions a script may import.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ScriptCapability {
+    /// The `log` function.
+    Log,
+    /// The `request_detach` function.
+    Detach,
+    /// The `is_key_down` function.
+    Input,
+}
+
+impl ScriptCapability {
+    pub const ALL: [ScriptCapability; 3] = [Self::Log, Self::Detach, Self::Input];
+
+    /// Returns the capability required to import a host function, if such a function exists.
+    pub fn of_import(name: &str) -> Option<Self> {
+        match name {
+            "log" => Some(Self::Log),
+            "request_detach" => Some(Self::Detach),
+            "is_key_down" => Some(Self::Input),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SandboxError {
+    /// The script imports something the host does not provide.
+    UnknownImport { module: String, name: String },
+    /// The script imports a host function without having the capability to.
+    DeniedCapability(ScriptCapability),
+    /// The initial size of a memory declared by the script exceeds the limit, in pages.
+    MemoryLimit { requested: u32, limit: u32 },
+}
+
+impl Display for SandboxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SandboxError::UnknownImport { module, name } => {
+                write!(f, "unknown import {}.{}", module, name)
+            }
+            SandboxError::DeniedCapability(capability) => {
+                write!(f, "capability {:?} denied", capability)
+            }
+            SandboxError::MemoryLimit { requested, limit } => write!(
+                f,
+                "memory of {} pages exceeds the limit of {} pages",
+                requested, limit
+            ),
+        }
+    }
+}
+
+impl Error for SandboxError {}
+
+/// The limits a script runs under.
+///
+/// By default, scripts have all capabilities and up to 16 MiB of memory.
+#[derive(Clone, Debug)]
+pub struct ScriptLimits {
+    max_memory_pages: u32,
+    capabilities: HashSet<ScriptCapability>,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            max_memory_pages: 256,
+            capabilities: ScriptCapability::ALL.into_iter().collect(),
+        }
+    }
+}
+
+impl ScriptLimits {
+    /// Limits the memory of scripts, rounded down to whole WebAssembly pages.
+    pub fn with_max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory_pages = (bytes / WASM_PAGE_SIZE) as u32;
+        self
+    }
+
+    /// Replaces the capabilities granted to scripts.
+    pub fn with_capabilities(
+        mut self,
+        capabilities: impl IntoIterator<Item = ScriptCapability>,
+    ) -> Self {
+        self.capabilities = capabilities.into_iter().collect();
+        self
+    }
+
+    pub fn get_max_memory_pages(&self) -> u32 {
+        self.max_memory_pages
+    }
+
+    pub fn allows(&self, capability: ScriptCapability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    /// Checks the imports and memories of a compiled script against the limits.
+    pub fn check(&self, module: &Module) -> Result<(), SandboxError> {
+        for import in module.imports() {
+            let capability = Some(import.module())
+                .filter(|module| *module == HOST_MODULE)
+                .and_then(|_| ScriptCapability::of_import(import.name()))
+                .ok_or_else(|| SandboxError::UnknownImport {
+                    module: import.module().to_owned(),
+                    name: import.name().to_owned(),
+                })?;
+
+            if !self.allows(capability) {
+                return Err(SandboxError::DeniedCapability(capability));
+            }
+        }
+
+        for memory in module.info().memories.values() {
+            let requested = memory.minimum.0;
+
+            if requested > self.max_memory_pages {
+                return Err(SandboxError::MemoryLimit {
+                    requested,
+                    limit: self.max_memory_pages,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a store whose memories cannot grow past the limit.
+    pub(crate) fn create_store(&self) -> Store {
+        let engine = Universal::new(Cranelift::default()).engine();
+        let tunables = LimitingTunables {
+            base: BaseTunables::for_target(engine.target()),
+            limit: Pages(self.max_memory_pages),
+        };
+
+        Store::new_with_tunables(&engine, tunables)
+    }
+}
+
+/// Tunables clamping the maximum size of memories to a limit.
+#[derive(MemoryUsage)]
+struct LimitingTunables {
+    base: BaseTunables,
+    limit: Pages,
+}
+
+impl LimitingTunables {
+    fn adjust_memory(&self, memory: &MemoryType) -> Result<MemoryType, MemoryError> {
+        if memory.minimum > self.limit {
+            return Err(MemoryError::Generic(format!(
+                "memory of {} pages exceeds the limit of {} pages",
+                memory.minimum.0, self.limit.0
+            )));
+        }
+
+        let mut adjusted = *memory;
+        adjusted.maximum = Some(memory.maximum.map_or(self.limit, |max| max.min(self.limit)));
+        Ok(adjusted)
+    }
+}
+
+impl Tunables for LimitingTunables {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        let adjusted = self.adjust_memory(memory).unwrap_or(*memory);
+        self.base.memory_style(&adjusted)
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        self.base
+            .create_host_memory(&self.adjust_memory(ty)?, style)
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        self.base
+            .create_vm_memory(&self.adjust_memory(ty)?, style, vm_definition_location)
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sandbox::{SandboxError, ScriptCapability, ScriptLimits};
+    use wasmer::{imports, Instance, Module, Value};
+
+    /// Scripts exceeding the default limits and a restricted set of capabilities
+    /// should be rejected, while a script within the limits should pass.
+    #[test]
+    fn test_limits() {
+        let limits = ScriptLimits::default();
+        let restricted = ScriptLimits::default().with_capabilities([ScriptCapability::Log]);
+        let store = limits.create_store();
+
+        let small = Module::new(&store, r#"(module (memory 1))"#).unwrap();
+        assert!(limits.check(&small).is_ok());
+
+        let large = Module::new(&store, r#"(module (memory 1024))"#).unwrap();
+        assert!(matches!(
+            limits.check(&large),
+            Err(SandboxError::MemoryLimit {
+                requested: 1024,
+                ..
+            })
+        ));
+
+        let detaching = r#"(module (import "pluto" "request_detach" (func)))"#;
+        let detaching = Module::new(&store, detaching).unwrap();
+        assert!(limits.check(&detaching).is_ok());
+        assert!(matches!(
+            restricted.check(&detaching),
+            Err(SandboxError::DeniedCapability(ScriptCapability::Detach))
+        ));
+
+        let foreign = Module::new(&store, r#"(module (import "env" "abort" (func)))"#).unwrap();
+        assert!(matches!(
+            limits.check(&foreign),
+            Err(SandboxError::UnknownImport { .. })
+        ));
+    }
+
+    /// A script growing its memory by a page at a time should stop at the limit.
+    #[test]
+    fn test_memory_growth() {
+        let limits = ScriptLimits::default().with_max_memory(4 * wasmer::WASM_PAGE_SIZE);
+        let store = limits.create_store();
+
+        let script = r#"
+            (module
+              (memory 1)
+              (func (export "grow") (result i32)
+                (memory.grow (i32.const 1))))
+        "#;
+
+        let module = Module::new(&store, script).unwrap();
+        let instance = Instance::new(&module, &imports! {}).unwrap();
+        let grow = instance.exports.get_function("grow").unwrap();
+
+        let results = (0..4)
+            .map(|_| grow.call(&[]).unwrap()[0].clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            results,
+            [Value::I32(1), Value::I32(2), Value::I32(3), Value::I32(-1)]
+        );
+    }
+}